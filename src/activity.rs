@@ -0,0 +1,104 @@
+//! Activity monitor for the editor's own SQLite connections. Statement timings come
+//! from SQLite's profile hook, which takes a plain function, so they're collected
+//! process-wide rather than per database.
+
+use parking_lot::Mutex;
+use rusqlite::Connection;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window statements-per-second is averaged over
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Slowest statements kept for display
+const MAX_SLOWEST: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct SlowStatement {
+    pub sql: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivitySnapshot {
+    pub statements_per_second: f64,
+    pub total_statements: u64,
+    pub total_time: Duration,
+    pub rows_read: u64,
+    /// Slowest first
+    pub slowest: Vec<SlowStatement>,
+}
+
+struct ActivityLog {
+    recent: VecDeque<Instant>,
+    total_statements: u64,
+    total_time: Duration,
+    rows_read: u64,
+    slowest: Vec<SlowStatement>,
+}
+
+static LOG: Mutex<ActivityLog> = parking_lot::const_mutex(ActivityLog {
+    recent: VecDeque::new(),
+    total_statements: 0,
+    total_time: Duration::ZERO,
+    rows_read: 0,
+    slowest: Vec::new(),
+});
+
+/// Time every statement run on `connection`
+pub fn instrument(mut connection: Connection) -> Connection {
+    connection.profile(Some(record_statement));
+    // Session recording needs statements with their values bound, which only tracing gives
+    connection.trace(Some(crate::session_script::trace_statement));
+    connection.commit_hook(Some(crate::session_script::transaction_committed));
+    connection.rollback_hook(Some(crate::session_script::transaction_rolled_back));
+    connection
+}
+
+fn record_statement(sql: &str, duration: Duration) {
+    let now = Instant::now();
+    let mut log = LOG.lock();
+    log.recent.push_back(now);
+    while log.recent.front().is_some_and(|t| now.duration_since(*t) > RATE_WINDOW) {
+        log.recent.pop_front();
+    }
+    log.total_statements += 1;
+    log.total_time += duration;
+
+    let fastest_kept = log.slowest.last().map(|s| s.duration).unwrap_or_default();
+    if log.slowest.len() < MAX_SLOWEST || duration > fastest_kept {
+        log.slowest.push(SlowStatement {
+            sql: sql.split_whitespace().collect::<Vec<_>>().join(" "),
+            duration,
+        });
+        log.slowest.sort_by(|a, b| b.duration.cmp(&a.duration));
+        log.slowest.truncate(MAX_SLOWEST);
+    }
+}
+
+/// Count rows the editor read back from SQLite
+pub fn record_rows(rows: usize) {
+    LOG.lock().rows_read += rows as u64;
+}
+
+pub fn snapshot() -> ActivitySnapshot {
+    let log = LOG.lock();
+    let now = Instant::now();
+    let in_window = log.recent.iter().filter(|t| now.duration_since(**t) <= RATE_WINDOW).count();
+    ActivitySnapshot {
+        statements_per_second: in_window as f64 / RATE_WINDOW.as_secs_f64(),
+        total_statements: log.total_statements,
+        total_time: log.total_time,
+        rows_read: log.rows_read,
+        slowest: log.slowest.clone(),
+    }
+}
+
+pub fn reset() {
+    let mut log = LOG.lock();
+    log.recent.clear();
+    log.total_statements = 0;
+    log.total_time = Duration::ZERO;
+    log.rows_read = 0;
+    log.slowest.clear();
+}
@@ -0,0 +1,187 @@
+//! Moving rows out of the working database into a companion `<name>.archive.db`
+//! beside it, so tables that only grow stay small without the history being lost.
+//! Archive tables have the working table's own definition, so rows can move back.
+
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, ToSql};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use crate::database::{value_to_sql, DatabaseManager};
+use crate::sql_text::{quote_identifier, TableDefinition};
+
+/// Alias the archive is attached under
+const ARCHIVE_ALIAS: &str = "archive";
+
+/// `save01.archive.db` beside `save01.db`
+pub fn archive_path(db_path: &Path) -> PathBuf {
+    let stem = db_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = db_path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "db".to_string());
+    db_path.with_file_name(format!("{}.archive.{}", stem, extension))
+}
+
+/// What the archive holds for one table, and whether it still fits the working table
+#[derive(Debug, Clone)]
+pub struct ArchiveCheck {
+    pub rows: usize,
+    /// The archived table's definition differs from the working table's
+    pub definition_changed: bool,
+    /// Rows whose id is both archived and in the working table
+    pub duplicated: usize,
+}
+
+impl ArchiveCheck {
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} rows archived", self.rows);
+        if self.definition_changed {
+            text.push_str(", definition changed since archiving");
+        }
+        if self.duplicated > 0 {
+            text.push_str(&format!(", {} also in the working table", self.duplicated));
+        }
+        text
+    }
+}
+
+/// Move the rows of `table` matching `condition` into the archive. Both files change
+/// in one transaction, and the delete only happens once every matched row is found
+/// in the archive unchanged. Returns how many rows moved. SQLite can't attach the
+/// archive inside a transaction, so pending transactional edits must be settled first.
+pub fn move_rows(db: &DatabaseManager, archive: &Path, table: &str, condition: &str, params: &[Value]) -> Result<usize> {
+    if db.has_open_transaction() {
+        return Err(anyhow!("Commit or roll back the pending edits before archiving rows of {}", table));
+    }
+    let definition = definition(db, table)?;
+    db.with_attached_writable(archive, ARCHIVE_ALIAS, |conn| {
+        ensure_table(conn, table, &definition)?;
+        transfer(conn, "main", ARCHIVE_ALIAS, table, condition, params)
+    })
+}
+
+/// Move archived rows of `table` matching `condition` back into the working table
+pub fn restore_rows(db: &DatabaseManager, archive: &Path, table: &str, condition: &str, params: &[Value]) -> Result<usize> {
+    if !archive.exists() {
+        return Err(anyhow!("Nothing has been archived yet"));
+    }
+    if db.has_open_transaction() {
+        return Err(anyhow!("Commit or roll back the pending edits before restoring rows of {}", table));
+    }
+    let definition = definition(db, table)?;
+    db.with_attached_writable(archive, ARCHIVE_ALIAS, |conn| {
+        if archived_definition(conn, table)?.is_none() {
+            return Err(anyhow!("{} has no archived rows", table));
+        }
+        ensure_table(conn, table, &definition)?;
+        transfer(conn, ARCHIVE_ALIAS, "main", table, condition, params)
+    })
+}
+
+/// Compare the archived copy of `table` with the working one; `None` when nothing of
+/// it has been archived
+pub fn check(db: &DatabaseManager, archive: &Path, table: &str) -> Result<Option<ArchiveCheck>> {
+    if !archive.exists() {
+        return Ok(None);
+    }
+    let definition = definition(db, table)?;
+    let id = db
+        .get_schema(table)
+        .map(|schema| schema.row_id_column())
+        .unwrap_or("rowid");
+    db.with_attached(archive, ARCHIVE_ALIAS, |conn| {
+        let Some(archived) = archived_definition(conn, table)? else {
+            return Ok(None);
+        };
+        let table = quote_identifier(table);
+        let alias = quote_identifier(ARCHIVE_ALIAS);
+        let rows: usize = conn.query_row(&format!("SELECT COUNT(*) FROM {}.{}", alias, table), [], |row| row.get(0))?;
+        let duplicated: usize = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM main.{table} WHERE {id} IN (SELECT {id} FROM {alias}.{table})",
+                table = table,
+                alias = alias,
+                id = id
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(Some(ArchiveCheck {
+            rows,
+            definition_changed: archived.to_sql() != definition.to_sql(),
+            duplicated,
+        }))
+    })
+}
+
+fn definition(db: &DatabaseManager, table: &str) -> Result<TableDefinition> {
+    TableDefinition::parse(&db.table_sql(table)?).ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table))
+}
+
+fn archived_definition(conn: &Connection, table: &str) -> Result<Option<TableDefinition>> {
+    let sql: Option<String> = conn
+        .query_row(
+            &format!(
+                "SELECT sql FROM {}.sqlite_master WHERE type = 'table' AND name = ?1",
+                quote_identifier(ARCHIVE_ALIAS)
+            ),
+            [table],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(sql.as_deref().and_then(TableDefinition::parse))
+}
+
+/// Create the archive's copy of `table`, or check an existing one still matches
+fn ensure_table(conn: &Connection, table: &str, definition: &TableDefinition) -> Result<()> {
+    match archived_definition(conn, table)? {
+        Some(archived) if archived.to_sql() != definition.to_sql() => Err(anyhow!(
+            "The archived {} no longer has the same definition as the working table",
+            table
+        )),
+        Some(_) => Ok(()),
+        None => {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE {}.{} {}",
+                    quote_identifier(ARCHIVE_ALIAS),
+                    quote_identifier(table),
+                    definition.to_sql()
+                ),
+                [],
+            )?;
+            Ok(())
+        }
+    }
+}
+
+/// Copy matching rows from `from.table` to `to.table`, verify the copy, then delete them
+fn transfer(conn: &Connection, from: &str, to: &str, table: &str, condition: &str, params: &[Value]) -> Result<usize> {
+    let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+    let from_table = format!("{}.{}", quote_identifier(from), quote_identifier(table));
+    let to_table = format!("{}.{}", quote_identifier(to), quote_identifier(table));
+
+    let tx = conn.unchecked_transaction()?;
+    let copied = tx.execute(
+        &format!("INSERT INTO {} SELECT * FROM {} WHERE ({})", to_table, from_table, condition),
+        rusqlite::params_from_iter(boxed.iter()),
+    )?;
+    let missing: usize = tx.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM (SELECT * FROM {} WHERE ({}) EXCEPT SELECT * FROM {})",
+            from_table, condition, to_table
+        ),
+        rusqlite::params_from_iter(boxed.iter()),
+        |row| row.get(0),
+    )?;
+    if missing > 0 {
+        return Err(anyhow!("{} rows didn't arrive intact in {}; nothing was moved", missing, to));
+    }
+    let deleted = tx.execute(
+        &format!("DELETE FROM {} WHERE ({})", from_table, condition),
+        rusqlite::params_from_iter(boxed.iter()),
+    )?;
+    if copied != deleted {
+        return Err(anyhow!("Copied {} rows of {} but matched {} to delete", copied, table, deleted));
+    }
+    tx.commit()?;
+    tracing::info!("✓ Moved {} rows of {} from {} to {}", copied, table, from, to);
+    Ok(copied)
+}
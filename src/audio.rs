@@ -0,0 +1,117 @@
+//! Playing WAV and Ogg Vorbis BLOBs from the large-value editor, so sound banks stored
+//! in SQLite can be auditioned without exporting files. Playback is built only with the
+//! `audio` feature, which pulls in rodio and the platform audio libraries; without it
+//! detection still works and playing returns an error saying so.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Ogg,
+}
+
+impl AudioFormat {
+    /// The format of a BLOB, from its magic bytes
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+            Some(AudioFormat::Wav)
+        } else if bytes.starts_with(b"OggS") {
+            Some(AudioFormat::Ogg)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Ogg => "Ogg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Playback {
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[cfg(feature = "audio")]
+pub use enabled::AudioPlayer;
+
+#[cfg(feature = "audio")]
+mod enabled {
+    use anyhow::Result;
+    use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+    use std::io::Cursor;
+    use super::Playback;
+
+    /// One sound at a time; the output device is opened on first play
+    #[derive(Default)]
+    pub struct AudioPlayer {
+        output: Option<(OutputStream, OutputStreamHandle)>,
+        sink: Option<Sink>,
+    }
+
+    impl AudioPlayer {
+        /// Start playing `bytes` from the beginning, stopping whatever was playing
+        pub fn play(&mut self, bytes: Vec<u8>) -> Result<()> {
+            self.stop();
+            let output = match self.output.take() {
+                Some(output) => output,
+                None => OutputStream::try_default()?,
+            };
+            let sink = Sink::try_new(&output.1)?;
+            self.output = Some(output);
+            sink.append(Decoder::new(Cursor::new(bytes))?);
+            self.sink = Some(sink);
+            Ok(())
+        }
+
+        pub fn toggle_pause(&mut self) {
+            if let Some(sink) = &self.sink {
+                if sink.is_paused() {
+                    sink.play();
+                } else {
+                    sink.pause();
+                }
+            }
+        }
+
+        pub fn stop(&mut self) {
+            if let Some(sink) = self.sink.take() {
+                sink.stop();
+            }
+        }
+
+        /// Read at render time, so a sound that ran to its end shows as stopped
+        pub fn playback(&self) -> Playback {
+            match &self.sink {
+                Some(sink) if sink.empty() => Playback::Stopped,
+                Some(sink) if sink.is_paused() => Playback::Paused,
+                Some(_) => Playback::Playing,
+                None => Playback::Stopped,
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+#[derive(Default)]
+pub struct AudioPlayer;
+
+#[cfg(not(feature = "audio"))]
+impl AudioPlayer {
+    pub fn play(&mut self, _bytes: Vec<u8>) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("This build has no audio playback; rebuild with --features audio"))
+    }
+
+    pub fn toggle_pause(&mut self) {}
+
+    pub fn stop(&mut self) {}
+
+    pub fn playback(&self) -> Playback {
+        Playback::Stopped
+    }
+}
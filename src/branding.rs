@@ -0,0 +1,61 @@
+//! Labels databases by their `application_id`/`user_version` pragmas using
+//! application IDs registered by the project.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Registry file looked up in the database's directory and its ancestors
+pub const REGISTRY_FILE_NAME: &str = "database_applications.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownApplication {
+    pub application_id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ApplicationRegistry {
+    pub applications: Vec<KnownApplication>,
+}
+
+impl ApplicationRegistry {
+    /// Load the nearest registry above `db_path`, or an empty one if the project has none
+    pub fn discover(db_path: &Path) -> Result<Self> {
+        for dir in db_path.ancestors().skip(1) {
+            let candidate = dir.join(REGISTRY_FILE_NAME);
+            if candidate.is_file() {
+                let text = std::fs::read_to_string(&candidate)?;
+                let applications = serde_json::from_str(&text)?;
+                return Ok(Self { applications });
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn name_for(&self, application_id: i32) -> Option<&str> {
+        self.applications
+            .iter()
+            .find(|app| app.application_id == application_id)
+            .map(|app| app.name.as_str())
+    }
+
+    /// e.g. "Pulsar Quest DB v12", falling back to the raw id for unknown applications
+    pub fn label(&self, application_id: i32, user_version: i32) -> String {
+        match self.name_for(application_id) {
+            Some(name) => format!("{} v{}", name, user_version),
+            None if application_id == 0 => format!("SQLite database v{}", user_version),
+            None => format!("Application 0x{:08X} v{}", application_id, user_version),
+        }
+    }
+}
+
+/// Parse a pragma value typed by the user, accepting decimal or `0x` hex
+pub fn parse_pragma_int(input: &str) -> Result<i32> {
+    let input = input.trim();
+    let parsed = match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).map(|v| v as i32),
+        None => input.parse::<i32>(),
+    };
+    parsed.map_err(|_| anyhow!("'{}' is not a valid 32-bit integer", input))
+}
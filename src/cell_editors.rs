@@ -2,8 +2,10 @@ use gpui::{prelude::*, *};
 use ui::{
     h_flex, v_flex, ActiveTheme, Sizable, StyleSized, StyledExt,
 };
+use regex::Regex;
 use serde_json::Value;
-use crate::reflection::SqlType;
+use crate::reflection::{InputPattern, NumericRange, SqlType};
+use crate::tags;
 
 #[derive(Clone, Debug)]
 pub enum CellEditor {
@@ -97,6 +99,150 @@ impl CellEditor {
     }
 }
 
+/// A column's input pattern, compiled once when its cell is opened for editing
+#[derive(Clone, Debug)]
+pub struct CellPattern {
+    pattern: InputPattern,
+    regex: Regex,
+}
+
+impl CellPattern {
+    pub fn compile(pattern: InputPattern) -> Result<Self, String> {
+        let regex = Regex::new(&pattern.to_regex()).map_err(|e| format!("Invalid pattern: {}", e))?;
+        Ok(Self { pattern, regex })
+    }
+
+    /// Why `value` doesn't fit, or `None` when it does
+    pub fn mismatch(&self, value: &str) -> Option<String> {
+        (!self.regex.is_match(value)).then(|| format!("'{}' {}", value, self.pattern.describe()))
+    }
+}
+
+/// Segments of the slider drawn under a ranged numeric cell
+pub const SLIDER_STEPS: usize = 20;
+
+/// Spinner and slider for a numeric column with a known range: values are stepped and
+/// set from the slider within the range, and typed values outside it are flagged
+#[derive(Clone, Debug)]
+pub struct NumericEditor {
+    pub range: NumericRange,
+    pub integer: bool,
+}
+
+impl NumericEditor {
+    /// Step multiplier for the arrow keys: Shift ×10, Alt ×0.1 (whole steps on integers)
+    pub fn step_multiplier(modifiers: &Modifiers) -> f64 {
+        if modifiers.shift {
+            10.0
+        } else if modifiers.alt {
+            0.1
+        } else {
+            1.0
+        }
+    }
+
+    /// `text` moved by `steps` steps and clamped; blank or unparseable text starts from
+    /// the lower bound, or zero
+    pub fn step(&self, text: &str, steps: f64) -> String {
+        let current = text.trim().parse::<f64>().unwrap_or(self.range.min.unwrap_or(0.0));
+        let mut delta = self.range.step() * steps;
+        if self.integer {
+            delta = if delta < 0.0 { delta.min(-1.0) } else { delta.max(1.0) }.round();
+        }
+        self.format(self.range.clamp(current + delta))
+    }
+
+    /// Position of `text` along the range, from 0 to 1
+    pub fn fraction(&self, text: &str) -> Option<f32> {
+        let (min, max) = (self.range.min?, self.range.max?);
+        let value = text.trim().parse::<f64>().ok()?;
+        if max <= min {
+            return Some(1.0);
+        }
+        Some(((value - min) / (max - min)).clamp(0.0, 1.0) as f32)
+    }
+
+    /// The value at a position along the range, snapped to the step
+    pub fn at_fraction(&self, fraction: f64) -> String {
+        let (Some(min), Some(max)) = (self.range.min, self.range.max) else {
+            return String::new();
+        };
+        let step = self.range.step();
+        let value = min + ((max - min) * fraction / step).round() * step;
+        self.format(self.range.clamp(value))
+    }
+
+    /// Why `text` is outside the range, or `None` when it is inside or not a number
+    pub fn out_of_range(&self, text: &str) -> Option<String> {
+        let value = text.trim().parse::<f64>().ok()?;
+        (!self.range.contains(value)).then(|| format!("{} is outside {}", text.trim(), self.range.describe()))
+    }
+
+    fn format(&self, value: f64) -> String {
+        if self.integer {
+            (value.round() as i64).to_string()
+        } else {
+            // Steps like 0.1 accumulate binary noise; a dozen digits shows what was meant
+            let text = format!("{:.12}", value);
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    }
+}
+
+/// Suggestions offered under a tag cell at a time
+pub const MAX_TAG_SUGGESTIONS: usize = 8;
+
+/// Chips for a comma-separated tag cell, with suggestions from the column's other values
+#[derive(Clone, Debug)]
+pub struct TagEditor {
+    /// The column's tags, most used first
+    pub known: Vec<String>,
+}
+
+impl TagEditor {
+    /// The tag being typed: the text after the last separator, if any
+    fn partial(text: &str) -> &str {
+        text.rsplit(tags::TAG_SEPARATOR).next().unwrap_or("").trim()
+    }
+
+    /// Known tags not in `text` yet that start with the one being typed
+    pub fn suggestions(&self, text: &str) -> Vec<String> {
+        let partial = Self::partial(text);
+        let mut complete = tags::split_tags(text);
+        if !partial.is_empty() {
+            complete.pop();
+        }
+        let partial = partial.to_lowercase();
+        self.known
+            .iter()
+            .filter(|tag| !complete.contains(tag) && tag.to_lowercase().starts_with(&partial))
+            .take(MAX_TAG_SUGGESTIONS)
+            .cloned()
+            .collect()
+    }
+
+    /// `text` with `tag` completing the one being typed, or appended
+    pub fn with_tag(&self, text: &str, tag: &str) -> String {
+        let mut current = tags::split_tags(text);
+        let partial = Self::partial(text);
+        if !partial.is_empty() && tag.to_lowercase().starts_with(&partial.to_lowercase()) {
+            current.pop();
+        }
+        if !current.iter().any(|t| t == tag) {
+            current.push(tag.to_string());
+        }
+        tags::join_tags(&current)
+    }
+
+    pub fn without_tag(&self, text: &str, ix: usize) -> String {
+        let mut current = tags::split_tags(text);
+        if ix < current.len() {
+            current.remove(ix);
+        }
+        tags::join_tags(&current)
+    }
+}
+
 pub struct CellEditorView {
     editor: CellEditor,
 }
@@ -0,0 +1,188 @@
+//! "Record changes" mode: triggers log every insert, update and delete into a log
+//! table while a game runs against the database, and the log is read back as a
+//! timeline. The triggers live in the database itself rather than as TEMP triggers,
+//! since TEMP triggers only see writes made on the editor's own connection; they're
+//! removed again when recording stops. Virtual and `WITHOUT ROWID` tables can't carry
+//! the triggers (the log keys rows by rowid) and are left unrecorded.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use crate::database::{DatabaseManager, TableKind};
+use crate::sql_text::quote_identifier;
+
+pub const CAPTURE_TABLE: &str = "__pulsar_change_capture";
+const TRIGGER_PREFIX: &str = "__pulsar_capture_";
+
+#[derive(Debug, Clone)]
+pub struct CapturedChange {
+    pub seq: i64,
+    /// `YYYY-MM-DD HH:MM:SS.SSS` in UTC
+    pub at: String,
+    pub table: String,
+    /// `INSERT`, `UPDATE` or `DELETE`
+    pub operation: String,
+    pub row_id: i64,
+    pub before: Option<Map<String, Value>>,
+    pub after: Option<Map<String, Value>>,
+}
+
+impl CapturedChange {
+    /// `(column, before, after)` for columns an update actually changed
+    pub fn changed_columns(&self) -> Vec<(String, Value, Value)> {
+        let (Some(before), Some(after)) = (&self.before, &self.after) else {
+            return Vec::new();
+        };
+        after
+            .iter()
+            .filter_map(|(column, new)| {
+                let old = before.get(column).cloned().unwrap_or(Value::Null);
+                (old != *new).then(|| (column.clone(), old, new.clone()))
+            })
+            .collect()
+    }
+
+    /// One timeline line, e.g. `12:01:03.120 UPDATE items #5 hp: 10 → 9`
+    pub fn describe(&self) -> String {
+        let time = self.at.split(' ').nth(1).unwrap_or(&self.at);
+        let mut line = format!("{} {} {} #{}", time, self.operation, self.table, self.row_id);
+        let changes = self.changed_columns();
+        if !changes.is_empty() {
+            let details = changes
+                .iter()
+                .map(|(column, old, new)| format!("{}: {} → {}", column, old, new))
+                .collect::<Vec<_>>()
+                .join(", ");
+            line.push(' ');
+            line.push_str(&details);
+        }
+        line
+    }
+}
+
+pub fn is_recording(db: &DatabaseManager) -> bool {
+    db.schema_objects()
+        .map(|objects| objects.iter().any(|o| o.kind == "trigger" && o.name.starts_with(TRIGGER_PREFIX)))
+        .unwrap_or(false)
+}
+
+/// Install capture triggers on `tables`, clearing any previous recording. Returns the
+/// tables that can't be recorded, which are skipped.
+pub fn start(db: &DatabaseManager, tables: &[String]) -> Result<Vec<String>> {
+    let mut columns = Vec::new();
+    let mut skipped = Vec::new();
+    for table in tables {
+        if db.table_kind(table)? == TableKind::Rowid {
+            columns.push((table, db.column_names(table)?));
+        } else {
+            skipped.push(table.clone());
+        }
+    }
+
+    db.with_transaction(|tx| {
+        remove_triggers(tx)?;
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {log};
+             CREATE TABLE {log} (
+                 seq INTEGER PRIMARY KEY,
+                 at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+                 table_name TEXT NOT NULL,
+                 operation TEXT NOT NULL,
+                 row_id INTEGER,
+                 before TEXT,
+                 after TEXT
+             );",
+            log = CAPTURE_TABLE
+        ))?;
+
+        for (table, columns) in &columns {
+            let row = |alias: &str| {
+                let pairs = columns
+                    .iter()
+                    .map(|c| {
+                        let value = format!("{}.{}", alias, quote_identifier(c));
+                        // json_object() rejects BLOBs, which would fail the game's own write
+                        format!(
+                            "'{}', CASE WHEN typeof({v}) = 'blob' THEN '<' || length({v}) || ' bytes>' ELSE {v} END",
+                            c.replace('\'', "''"),
+                            v = value
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("json_object({})", pairs)
+            };
+            for (operation, row_id, before, after) in [
+                ("INSERT", "NEW.rowid", "NULL".to_string(), row("NEW")),
+                ("UPDATE", "NEW.rowid", row("OLD"), row("NEW")),
+                ("DELETE", "OLD.rowid", row("OLD"), "NULL".to_string()),
+            ] {
+                tx.execute_batch(&format!(
+                    "CREATE TRIGGER {trigger} AFTER {operation} ON {table} BEGIN
+                         INSERT INTO {log} (table_name, operation, row_id, before, after)
+                         VALUES ('{name}', '{operation}', {row_id}, {before}, {after});
+                     END;",
+                    trigger = quote_identifier(&format!("{}{}_{}", TRIGGER_PREFIX, table, operation.to_lowercase())),
+                    table = quote_identifier(table),
+                    log = CAPTURE_TABLE,
+                    name = table.replace('\'', "''"),
+                ))?;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(skipped)
+}
+
+/// Changes logged after `after_seq`, oldest first
+pub fn changes_since(db: &DatabaseManager, after_seq: i64) -> Result<Vec<CapturedChange>> {
+    let sql = format!(
+        "SELECT seq, at, table_name, operation, row_id, before, after FROM {} WHERE seq > {} ORDER BY seq",
+        CAPTURE_TABLE, after_seq
+    );
+    let rows = db.execute_query(&sql)?;
+    let text = |cell: &crate::database::CellValue| match &cell.value {
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    };
+    let object = |cell: &crate::database::CellValue| {
+        text(cell).and_then(|json| match serde_json::from_str(&json) {
+            Ok(Value::Object(map)) => Some(map),
+            _ => None,
+        })
+    };
+
+    Ok(rows
+        .iter()
+        .map(|row| CapturedChange {
+            seq: row[0].value.as_i64().unwrap_or_default(),
+            at: text(&row[1]).unwrap_or_default(),
+            table: text(&row[2]).unwrap_or_default(),
+            operation: text(&row[3]).unwrap_or_default(),
+            row_id: row[4].value.as_i64().unwrap_or_default(),
+            before: object(&row[5]),
+            after: object(&row[6]),
+        })
+        .collect())
+}
+
+/// Remove the triggers and log, returning everything that was recorded
+pub fn stop(db: &DatabaseManager) -> Result<Vec<CapturedChange>> {
+    let changes = changes_since(db, 0).unwrap_or_default();
+    db.with_transaction(|tx| {
+        remove_triggers(tx)?;
+        tx.execute_batch(&format!("DROP TABLE IF EXISTS {};", CAPTURE_TABLE))?;
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+fn remove_triggers(tx: &rusqlite::Connection) -> Result<()> {
+    let mut stmt = tx.prepare("SELECT name FROM sqlite_master WHERE type = 'trigger' AND substr(name, 1, ?1) = ?2")?;
+    let triggers = stmt
+        .query_map(rusqlite::params![TRIGGER_PREFIX.len(), TRIGGER_PREFIX], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for trigger in triggers {
+        tx.execute_batch(&format!("DROP TRIGGER IF EXISTS {};", quote_identifier(&trigger)))?;
+    }
+    Ok(())
+}
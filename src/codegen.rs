@@ -0,0 +1,291 @@
+//! Constant enums generated from lookup tables (an id column and a name column), so
+//! game code can say `ItemKind::Sword` instead of a magic number. Designated tables are
+//! listed in the `codegen` sidecar with a fingerprint of the rows their files were last
+//! generated from, so later edits to the table, or to the files, show up as drift.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::database::{CellValue, DatabaseManager};
+use crate::reflection::SqlType;
+use crate::row_hash::hash_row;
+use crate::sql_text::quote_identifier;
+
+pub const CODEGEN_SIDECAR: &str = "codegen";
+
+/// Directory under the project root the files are written to
+pub const GENERATED_DIR: &str = "generated";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnumLanguage {
+    Rust,
+    CSharp,
+    Json,
+}
+
+impl EnumLanguage {
+    pub const ALL: [EnumLanguage; 3] = [EnumLanguage::Rust, EnumLanguage::CSharp, EnumLanguage::Json];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EnumLanguage::Rust => "rs",
+            EnumLanguage::CSharp => "cs",
+            EnumLanguage::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodegenConfig {
+    pub enums: Vec<LookupEnum>,
+}
+
+impl CodegenConfig {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(crate::sidecar::load(db_path, CODEGEN_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        crate::sidecar::save(db_path, CODEGEN_SIDECAR, self)
+    }
+
+    pub fn get(&self, table: &str) -> Option<&LookupEnum> {
+        self.enums.iter().find(|e| e.table == table)
+    }
+
+    pub fn get_mut(&mut self, table: &str) -> Option<&mut LookupEnum> {
+        self.enums.iter_mut().find(|e| e.table == table)
+    }
+}
+
+/// A lookup table designated for code generation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupEnum {
+    pub table: String,
+    pub id_column: String,
+    pub name_column: String,
+    /// Type name in the generated code
+    pub enum_name: String,
+    pub languages: Vec<EnumLanguage>,
+    /// Regenerate on save instead of only flagging drift
+    #[serde(default)]
+    pub sync_on_save: bool,
+    /// Rows as of the last generation, `None` if never generated
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+/// One enum member: the row's id and the identifier made from its name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumEntry {
+    pub id: i64,
+    pub name: String,
+    pub identifier: String,
+}
+
+impl LookupEnum {
+    /// Designate `table` using its integer key and first text column
+    pub fn detect(db: &DatabaseManager, table: &str) -> Result<Self> {
+        let schema = db
+            .get_schema(table)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table))?;
+        let id_column = match schema.key_columns().as_slice() {
+            [column] => column.to_string(),
+            _ => return Err(anyhow!("{} needs a single-column key to generate an enum", table)),
+        };
+        let name_column = schema
+            .fields
+            .iter()
+            .find(|f| f.name != id_column && matches!(f.sql_type, SqlType::Text))
+            .map(|f| f.name.clone())
+            .ok_or_else(|| anyhow!("{} has no text column to name the enum members", table))?;
+
+        Ok(Self {
+            table: table.to_string(),
+            id_column,
+            name_column,
+            enum_name: to_pascal_case(table),
+            languages: EnumLanguage::ALL.to_vec(),
+            sync_on_save: false,
+            fingerprint: None,
+        })
+    }
+
+    pub fn entries(&self, db: &DatabaseManager) -> Result<Vec<EnumEntry>> {
+        let sql = format!(
+            "SELECT {id}, {name} FROM {table} ORDER BY {id}",
+            id = quote_identifier(&self.id_column),
+            name = quote_identifier(&self.name_column),
+            table = quote_identifier(&self.table),
+        );
+
+        let mut entries: Vec<EnumEntry> = Vec::new();
+        for row in db.fetch_query(&sql, usize::MAX)?.rows {
+            let id = row
+                .first()
+                .and_then(|cell| cell.value.as_i64())
+                .ok_or_else(|| anyhow!("{}.{} has a non-integer id", self.table, self.id_column))?;
+            let name = row.get(1).map(|cell| cell.display.clone()).unwrap_or_default();
+            let identifier = to_pascal_case(&name);
+            if let Some(clash) = entries.iter().find(|e| e.identifier == identifier) {
+                return Err(anyhow!(
+                    "'{}' (id {}) and '{}' (id {}) both become {}",
+                    clash.name,
+                    clash.id,
+                    name,
+                    id,
+                    identifier
+                ));
+            }
+            entries.push(EnumEntry { id, name, identifier });
+        }
+        Ok(entries)
+    }
+
+    pub fn output_path(&self, project_root: &Path, language: EnumLanguage) -> PathBuf {
+        project_root
+            .join(GENERATED_DIR)
+            .join(format!("{}.{}", self.enum_name, language.extension()))
+    }
+
+    pub fn render(&self, language: EnumLanguage, entries: &[EnumEntry]) -> Result<String> {
+        let header = format!("Generated from table {} by the table editor; do not edit.", self.table);
+        Ok(match language {
+            EnumLanguage::Rust => {
+                let mut code = format!(
+                    "// {}\n\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n#[repr(i64)]\npub enum {} {{\n",
+                    header, self.enum_name
+                );
+                for entry in entries {
+                    code.push_str(&format!("    {} = {},\n", entry.identifier, entry.id));
+                }
+                code.push_str(&format!(
+                    "}}\n\nimpl {} {{\n    pub fn from_id(id: i64) -> Option<Self> {{\n        match id {{\n",
+                    self.enum_name
+                ));
+                for entry in entries {
+                    code.push_str(&format!("            {} => Some(Self::{}),\n", entry.id, entry.identifier));
+                }
+                code.push_str("            _ => None,\n        }\n    }\n}\n");
+                code
+            }
+            EnumLanguage::CSharp => {
+                let mut code = format!("// {}\n\npublic enum {} : long\n{{\n", header, self.enum_name);
+                for entry in entries {
+                    code.push_str(&format!("    {} = {},\n", entry.identifier, entry.id));
+                }
+                code.push_str("}\n");
+                code
+            }
+            EnumLanguage::Json => {
+                let values: serde_json::Map<String, Value> = entries
+                    .iter()
+                    .map(|e| (e.identifier.clone(), Value::from(e.id)))
+                    .collect();
+                let document = serde_json::json!({
+                    "enum": self.enum_name,
+                    "table": self.table,
+                    "values": values,
+                });
+                format!("{}\n", serde_json::to_string_pretty(&document)?)
+            }
+        })
+    }
+
+    /// Write every language's file and record the rows they were generated from
+    pub fn generate(&mut self, db: &DatabaseManager, project_root: &Path) -> Result<Vec<PathBuf>> {
+        let entries = self.entries(db)?;
+        let mut written = Vec::new();
+        for language in &self.languages {
+            let path = self.output_path(project_root, *language);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, self.render(*language, &entries)?)?;
+            written.push(path);
+        }
+        self.fingerprint = Some(fingerprint(&entries));
+        tracing::info!("✓ Generated {} from {} ({} members)", self.enum_name, self.table, entries.len());
+        Ok(written)
+    }
+
+    /// Why the generated files no longer match the table, or `None` when they do
+    pub fn drift(&self, db: &DatabaseManager, project_root: &Path) -> Result<Option<String>> {
+        let entries = self.entries(db)?;
+        if self.fingerprint.as_deref() != Some(fingerprint(&entries).as_str()) {
+            return Ok(Some(match self.fingerprint {
+                Some(_) => format!("{} changed since {} was generated", self.table, self.enum_name),
+                None => format!("{} has never been generated", self.enum_name),
+            }));
+        }
+        for language in &self.languages {
+            let path = self.output_path(project_root, *language);
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            match std::fs::read_to_string(&path) {
+                Ok(text) if text == self.render(*language, &entries)? => {}
+                Ok(_) => return Ok(Some(format!("{} was edited by hand", name))),
+                Err(_) => return Ok(Some(format!("{} is missing", name))),
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn fingerprint(entries: &[EnumEntry]) -> String {
+    let cells: Vec<CellValue> = entries
+        .iter()
+        .flat_map(|e| [CellValue::new(Value::from(e.id)), CellValue::new(Value::String(e.name.clone()))])
+        .collect();
+    hash_row(&cells)
+}
+
+/// `quest state` -> `QuestState`; identifiers can't start with a digit, so those get `_`
+pub fn to_pascal_case(s: &str) -> String {
+    let mut identifier: String = s
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+/// Drift reasons by table for every designated enum, regenerating those set to sync
+/// when `regenerate` is given. The config is updated with new fingerprints.
+pub fn check_all(
+    db: &DatabaseManager,
+    config: &mut CodegenConfig,
+    project_root: &Path,
+    regenerate: bool,
+) -> BTreeMap<String, String> {
+    let mut drift = BTreeMap::new();
+    for lookup in &mut config.enums {
+        let result = lookup.drift(db, project_root).and_then(|reason| match reason {
+            Some(_) if regenerate && lookup.sync_on_save => lookup.generate(db, project_root).map(|_| None),
+            reason => Ok(reason),
+        });
+        match result {
+            Ok(Some(reason)) => {
+                drift.insert(lookup.table.clone(), reason);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Failed to check generated enum {}: {}", lookup.enum_name, e);
+                drift.insert(lookup.table.clone(), e.to_string());
+            }
+        }
+    }
+    drift
+}
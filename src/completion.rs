@@ -0,0 +1,124 @@
+//! Query editor completions: SQL keywords, tables and columns, plus saved snippets
+//! and recently run queries so earlier work can be recalled by typing a few characters.
+
+/// Suggestions offered at once
+const MAX_COMPLETIONS: usize = 12;
+
+/// Characters typed before suggestions appear
+const MIN_PREFIX_LEN: usize = 2;
+
+const KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP BY", "ORDER BY", "HAVING", "LIMIT", "OFFSET",
+    "INSERT INTO", "VALUES", "UPDATE", "SET", "DELETE FROM", "JOIN", "LEFT JOIN",
+    "INNER JOIN", "ON", "AND", "OR", "NOT", "NULL", "IS NULL", "IS NOT NULL", "IN",
+    "LIKE", "BETWEEN", "DISTINCT", "COUNT", "SUM", "AVG", "MIN", "MAX", "AS", "CASE",
+    "WHEN", "THEN", "ELSE", "END", "UNION", "EXISTS", "WITH",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Table,
+    Column,
+    Snippet,
+    History,
+}
+
+impl CompletionKind {
+    /// Short marker shown before the label so recalled queries stand out from schema names
+    pub fn badge(&self) -> &'static str {
+        match self {
+            CompletionKind::Keyword => "kw",
+            CompletionKind::Table => "📋",
+            CompletionKind::Column => "•",
+            CompletionKind::Snippet => "★",
+            CompletionKind::History => "🕘",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompletionItem {
+    pub kind: CompletionKind,
+    pub label: String,
+    pub insert_text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionSources<'a> {
+    pub tables: &'a [String],
+    pub columns: &'a [String],
+    /// `(name, sql)` pairs
+    pub snippets: &'a [(String, String)],
+    /// Most recent first
+    pub history: &'a [String],
+}
+
+/// The identifier being typed at the end of `text`
+pub fn current_word(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    &text[start..]
+}
+
+pub fn complete(prefix: &str, sources: &CompletionSources) -> Vec<CompletionItem> {
+    if prefix.chars().count() < MIN_PREFIX_LEN {
+        return Vec::new();
+    }
+    let needle = prefix.to_lowercase();
+    let starts = |s: &str| s.to_lowercase().starts_with(&needle);
+    let mut items = Vec::new();
+
+    for (name, sql) in sources.snippets {
+        if name.to_lowercase().contains(&needle) || sql.to_lowercase().contains(&needle) {
+            items.push(CompletionItem {
+                kind: CompletionKind::Snippet,
+                label: name.clone(),
+                insert_text: sql.clone(),
+            });
+        }
+    }
+
+    // History matches anywhere in the query, since that's how people remember them
+    for sql in sources.history {
+        if sql.to_lowercase().contains(&needle) {
+            items.push(CompletionItem {
+                kind: CompletionKind::History,
+                label: one_line(sql),
+                insert_text: sql.clone(),
+            });
+        }
+    }
+
+    let names = sources
+        .tables
+        .iter()
+        .map(|t| (CompletionKind::Table, t.as_str()))
+        .chain(sources.columns.iter().map(|c| (CompletionKind::Column, c.as_str())))
+        .chain(KEYWORDS.iter().map(|k| (CompletionKind::Keyword, *k)));
+    for (kind, name) in names {
+        if starts(name) && !name.eq_ignore_ascii_case(prefix) {
+            items.push(CompletionItem {
+                kind,
+                label: name.to_string(),
+                insert_text: name.to_string(),
+            });
+        }
+    }
+
+    items.truncate(MAX_COMPLETIONS);
+    items
+}
+
+fn one_line(sql: &str) -> String {
+    let line = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line
+    }
+}
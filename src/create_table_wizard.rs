@@ -0,0 +1,441 @@
+//! New tables without hand-written DDL: list the columns with their types, keys,
+//! NOT NULL, defaults and UNIQUE constraints, check the generated CREATE TABLE, and run
+//! it. A table with no key column gets the usual `id INTEGER PRIMARY KEY`.
+
+use anyhow::{Result, anyhow};
+use gpui::{prelude::*, *};
+use ui::{
+    h_flex, v_flex, button::{Button, ButtonVariants},
+    input::{TextInput, InputEvent, InputState},
+    ActiveTheme, Sizable, Disableable,
+};
+use crate::database::DatabaseManager;
+use crate::sql_text::quote_identifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+    Blob,
+    Numeric,
+    /// Declared `BOOLEAN` and stored as 0 or 1
+    Boolean,
+    /// Declared `DATETIME` and stored as ISO 8601 text
+    DateTime,
+}
+
+impl ColumnType {
+    pub const ALL: [ColumnType; 7] = [
+        ColumnType::Integer,
+        ColumnType::Real,
+        ColumnType::Text,
+        ColumnType::Blob,
+        ColumnType::Numeric,
+        ColumnType::Boolean,
+        ColumnType::DateTime,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Text => "TEXT",
+            ColumnType::Blob => "BLOB",
+            ColumnType::Numeric => "NUMERIC",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::DateTime => "DATETIME",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// How a column takes part in UNIQUE constraints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UniqueMode {
+    #[default]
+    None,
+    /// `UNIQUE` on the column alone
+    Column,
+    /// Part of the one multi-column `UNIQUE (..)` of the grouped columns
+    Group,
+}
+
+impl UniqueMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UniqueMode::None => "not unique",
+            UniqueMode::Column => "unique",
+            UniqueMode::Group => "unique group",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            UniqueMode::None => UniqueMode::Column,
+            UniqueMode::Column => UniqueMode::Group,
+            UniqueMode::Group => UniqueMode::None,
+        }
+    }
+}
+
+pub struct ColumnDraft {
+    name_input: Entity<InputState>,
+    pub column_type: ColumnType,
+    pub primary_key: bool,
+    pub not_null: bool,
+    pub unique: UniqueMode,
+    /// A literal or expression; bare words other than keywords are quoted as text
+    default_input: Entity<InputState>,
+}
+
+/// A column as the generated SQL has it
+struct ColumnSpec {
+    name: String,
+    column_type: ColumnType,
+    primary_key: bool,
+    not_null: bool,
+    unique: UniqueMode,
+    default: Option<String>,
+}
+
+pub struct CreateTableWizard {
+    db: DatabaseManager,
+    name_input: Entity<InputState>,
+    columns: Vec<ColumnDraft>,
+    tables: Vec<String>,
+    /// The table created last, for the editor to open
+    pub created: Option<String>,
+    message: Option<String>,
+    /// Re-render the SQL preview as names and defaults are typed
+    _subscriptions: Vec<Subscription>,
+}
+
+impl CreateTableWizard {
+    pub fn new(db: DatabaseManager, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let tables = db.list_tables().unwrap_or_default();
+        let mut wizard = Self {
+            db,
+            name_input: cx.new(|cx| InputState::new(window, cx)),
+            columns: Vec::new(),
+            tables,
+            created: None,
+            message: None,
+            _subscriptions: Vec::new(),
+        };
+        let name_input = wizard.name_input.clone();
+        wizard.watch(&name_input, cx);
+        wizard.add_column(window, cx);
+        wizard
+    }
+
+    fn watch(&mut self, input: &Entity<InputState>, cx: &mut Context<Self>) {
+        self._subscriptions.push(cx.subscribe(input, |_, _, event: &InputEvent, cx| {
+            if matches!(event, InputEvent::Change { .. }) {
+                cx.notify();
+            }
+        }));
+    }
+
+    pub fn add_column(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let draft = ColumnDraft {
+            name_input: cx.new(|cx| InputState::new(window, cx)),
+            column_type: ColumnType::Text,
+            primary_key: false,
+            not_null: false,
+            unique: UniqueMode::None,
+            default_input: cx.new(|cx| InputState::new(window, cx)),
+        };
+        self.watch(&draft.name_input, cx);
+        self.watch(&draft.default_input, cx);
+        self.columns.push(draft);
+    }
+
+    pub fn remove_column(&mut self, ix: usize) {
+        if ix < self.columns.len() {
+            self.columns.remove(ix);
+        }
+    }
+
+    fn specs(&self, cx: &App) -> Vec<ColumnSpec> {
+        self.columns
+            .iter()
+            .map(|draft| {
+                let default = draft.default_input.read(cx).value().trim().to_string();
+                ColumnSpec {
+                    name: draft.name_input.read(cx).value().trim().to_string(),
+                    column_type: draft.column_type,
+                    primary_key: draft.primary_key,
+                    not_null: draft.not_null,
+                    unique: draft.unique,
+                    default: (!default.is_empty()).then_some(default),
+                }
+            })
+            .collect()
+    }
+
+    /// The CREATE TABLE for the current columns, or what keeps it from being valid
+    pub fn create_sql(&self, cx: &App) -> Result<String> {
+        let name = self.name_input.read(cx).value().trim().to_string();
+        if name.is_empty() {
+            return Err(anyhow!("Name the table"));
+        }
+        if self.tables.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+            return Err(anyhow!("{} already exists", name));
+        }
+        let columns = self.specs(cx);
+        if columns.is_empty() {
+            return Err(anyhow!("Add a column"));
+        }
+        for (ix, column) in columns.iter().enumerate() {
+            if column.name.is_empty() {
+                return Err(anyhow!("Column {} has no name", ix + 1));
+            }
+            if columns[..ix].iter().any(|c| c.name.eq_ignore_ascii_case(&column.name)) {
+                return Err(anyhow!("Column {} appears twice", column.name));
+            }
+        }
+        Ok(create_table_sql(&name, &columns))
+    }
+
+    pub fn create(&mut self, cx: &mut Context<Self>) -> Result<()> {
+        let sql = self.create_sql(cx)?;
+        let name = self.name_input.read(cx).value().trim().to_string();
+        self.db.execute_query(&sql)?;
+        self.db.refresh_schemas()?;
+        self.tables = self.db.list_tables()?;
+
+        tracing::info!("✓ Created table {}", name);
+        self.message = Some(format!("✓ Created {}", name));
+        self.created = Some(name);
+        Ok(())
+    }
+
+    fn render_column(&self, ix: usize, draft: &ColumnDraft, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .w_full()
+            .gap_1()
+            .items_center()
+            .child(TextInput::new(&draft.name_input).w_40().text_xs())
+            .child(
+                Button::new(("column-type", ix))
+                    .label(draft.column_type.label())
+                    .tooltip("Declared type")
+                    .xsmall()
+                    .outline()
+                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                        let column = &mut wizard.columns[ix];
+                        column.column_type = column.column_type.next();
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new(("column-pk", ix))
+                    .label("PK")
+                    .tooltip("Part of the primary key; several make a composite key")
+                    .xsmall()
+                    .when(draft.primary_key, |b| b.primary())
+                    .when(!draft.primary_key, |b| b.ghost())
+                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                        let column = &mut wizard.columns[ix];
+                        column.primary_key = !column.primary_key;
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new(("column-not-null", ix))
+                    .label("NOT NULL")
+                    .xsmall()
+                    .when(draft.not_null, |b| b.primary())
+                    .when(!draft.not_null, |b| b.ghost())
+                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                        let column = &mut wizard.columns[ix];
+                        column.not_null = !column.not_null;
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new(("column-unique", ix))
+                    .label(draft.unique.label())
+                    .tooltip("Unique on its own, or together with the other grouped columns")
+                    .xsmall()
+                    .when(draft.unique != UniqueMode::None, |b| b.primary())
+                    .when(draft.unique == UniqueMode::None, |b| b.ghost())
+                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                        let column = &mut wizard.columns[ix];
+                        column.unique = column.unique.next();
+                        cx.notify();
+                    }))
+            )
+            .child(div().text_xs().text_color(cx.theme().muted_foreground).child("default"))
+            .child(TextInput::new(&draft.default_input).w_32().text_xs())
+            .child(
+                Button::new(("column-remove", ix))
+                    .label("Remove")
+                    .xsmall()
+                    .ghost()
+                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                        wizard.remove_column(ix);
+                        cx.notify();
+                    }))
+            )
+    }
+}
+
+/// `CREATE TABLE` for `columns`. One INTEGER key column becomes the rowid alias; no key
+/// column at all adds `id`, which the grid keys rows by.
+fn create_table_sql(table: &str, columns: &[ColumnSpec]) -> String {
+    let keys: Vec<&ColumnSpec> = columns.iter().filter(|c| c.primary_key).collect();
+    let inline_key = match keys.as_slice() {
+        [key] if key.column_type == ColumnType::Integer => Some(key.name.as_str()),
+        _ => None,
+    };
+
+    let mut definitions = Vec::new();
+    if keys.is_empty() {
+        definitions.push("id INTEGER PRIMARY KEY AUTOINCREMENT".to_string());
+    }
+    for column in columns {
+        let mut definition = format!("{} {}", quote_identifier(&column.name), column.column_type.label());
+        if inline_key == Some(column.name.as_str()) {
+            definition.push_str(" PRIMARY KEY");
+        }
+        if column.not_null {
+            definition.push_str(" NOT NULL");
+        }
+        if column.unique == UniqueMode::Column {
+            definition.push_str(" UNIQUE");
+        }
+        if let Some(default) = &column.default {
+            definition.push_str(&format!(" DEFAULT {}", default_expression(default)));
+        }
+        definitions.push(definition);
+    }
+
+    let quoted = |specs: Vec<&ColumnSpec>| specs.iter().map(|c| quote_identifier(&c.name)).collect::<Vec<_>>().join(", ");
+    if inline_key.is_none() && !keys.is_empty() {
+        definitions.push(format!("PRIMARY KEY ({})", quoted(keys)));
+    }
+    let group: Vec<&ColumnSpec> = columns.iter().filter(|c| c.unique == UniqueMode::Group).collect();
+    if !group.is_empty() {
+        definitions.push(format!("UNIQUE ({})", quoted(group)));
+    }
+
+    format!("CREATE TABLE {} (\n    {}\n);", quote_identifier(table), definitions.join(",\n    "))
+}
+
+/// A default as SQL: numbers, quoted strings, parenthesized expressions and the
+/// `NULL`/`TRUE`/`FALSE`/`CURRENT_*` keywords as typed, anything else as a text literal
+fn default_expression(text: &str) -> String {
+    let keyword = ["NULL", "TRUE", "FALSE", "CURRENT_TIME", "CURRENT_DATE", "CURRENT_TIMESTAMP"]
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(text));
+    let literal = text.parse::<f64>().is_ok()
+        || (text.starts_with('\'') && text.ends_with('\'') && text.len() > 1)
+        || (text.starts_with('(') && text.ends_with(')'));
+    if keyword || literal {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "''"))
+    }
+}
+
+impl Render for CreateTableWizard {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let sql = self.create_sql(cx);
+        let can_create = sql.is_ok() && !self.db.is_read_only();
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(div().text_sm().child("New table"))
+                    .child(TextInput::new(&self.name_input).w_64().text_xs())
+            )
+            .child(
+                v_flex()
+                    .id("create-table-columns")
+                    .flex_1()
+                    .min_h_0()
+                    .overflow_y_scroll()
+                    .gap_1()
+                    .p_2()
+                    .children(
+                        self.columns
+                            .iter()
+                            .enumerate()
+                            .map(|(ix, draft)| self.render_column(ix, draft, cx).into_any_element())
+                            .collect::<Vec<_>>()
+                    )
+                    .child(
+                        div().child(
+                            Button::new("add-column")
+                                .label("Add column")
+                                .xsmall()
+                                .outline()
+                                .on_click(cx.listener(|wizard, _, window, cx| {
+                                    wizard.add_column(window, cx);
+                                    cx.notify();
+                                }))
+                        )
+                    )
+            )
+            .child(
+                v_flex()
+                    .w_full()
+                    .gap_1()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_family("monospace")
+                            .text_color(if sql.is_ok() { cx.theme().foreground } else { cx.theme().muted_foreground })
+                            .child(match &sql {
+                                Ok(sql) => sql.clone(),
+                                Err(e) => e.to_string(),
+                            })
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                Button::new("create-table")
+                                    .label("Create Table")
+                                    .tooltip("Run the CREATE TABLE above")
+                                    .xsmall()
+                                    .primary()
+                                    .disabled(!can_create)
+                                    .on_click(cx.listener(|wizard, _, _, cx| {
+                                        if let Err(e) = wizard.create(cx) {
+                                            tracing::error!("Failed to create table: {}", e);
+                                            wizard.message = Some(e.to_string());
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .when_some(self.message.clone(), |row, message| {
+                                row.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(message)
+                                )
+                            })
+                    )
+            )
+    }
+}
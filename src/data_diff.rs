@@ -0,0 +1,507 @@
+//! Row-level comparison of two databases, matching rows by id.
+//!
+//! Both sides are walked in id order a page at a time (a merge join), so even
+//! large tables are compared without loading them into memory.
+
+use anyhow::{Result, anyhow};
+use rusqlite::Connection;
+use std::path::Path;
+use crate::database::{CellValue, DatabaseManager, RowData};
+use crate::sql_text::{quote_identifier, quote_if_needed};
+
+/// Rows fetched per keyset page while walking a table
+const DIFF_PAGE_ROWS: usize = 1000;
+
+/// Alias a sync target is attached under
+const TARGET_ALIAS: &str = "sync_target";
+
+/// Rows listed per kind of difference in a keyed diff; counts and syncs cover them all
+const KEYED_DIFF_ROWS: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct CellChange {
+    pub column: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RowChange {
+    pub id: i64,
+    pub cells: Vec<CellChange>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableDiff {
+    pub table_name: String,
+    /// Column lists differ, so rows weren't compared
+    pub schema_changed: bool,
+    pub added: Vec<i64>,
+    pub removed: Vec<i64>,
+    pub changed: Vec<RowChange>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.schema_changed && self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseDiff {
+    pub tables_added: Vec<String>,
+    pub tables_removed: Vec<String>,
+    pub tables: Vec<TableDiff>,
+}
+
+impl DatabaseDiff {
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("# Database diff\n\n");
+
+        for table in &self.tables_added {
+            md.push_str(&format!("- Table `{}` added\n", table));
+        }
+        for table in &self.tables_removed {
+            md.push_str(&format!("- Table `{}` removed\n", table));
+        }
+
+        for table in self.tables.iter().filter(|t| !t.is_empty()) {
+            md.push_str(&format!("\n## {}\n\n", table.table_name));
+            if table.schema_changed {
+                md.push_str("Columns differ; rows not compared.\n");
+                continue;
+            }
+
+            md.push_str(&format!(
+                "{} added, {} removed, {} changed\n\n",
+                table.added.len(),
+                table.removed.len(),
+                table.changed.len()
+            ));
+            for change in &table.changed {
+                for cell in &change.cells {
+                    md.push_str(&format!(
+                        "- id {}: `{}` {} → {}\n",
+                        change.id, cell.column, cell.before, cell.after
+                    ));
+                }
+            }
+        }
+
+        md
+    }
+}
+
+/// Compare two database files, e.g. consecutive save games. Both are opened read-only.
+pub fn diff_database_files(before: &Path, after: &Path) -> Result<DatabaseDiff> {
+    let before = DatabaseManager::open_read_only(before)?;
+    let after = DatabaseManager::open_read_only(after)?;
+    before.introspect_and_register_schemas()?;
+    after.introspect_and_register_schemas()?;
+    diff_databases(&before, &after)
+}
+
+pub fn diff_databases(before: &DatabaseManager, after: &DatabaseManager) -> Result<DatabaseDiff> {
+    let before_tables = before.list_tables()?;
+    let after_tables = after.list_tables()?;
+
+    let mut diff = DatabaseDiff {
+        tables_added: after_tables
+            .iter()
+            .filter(|t| !before_tables.contains(t))
+            .cloned()
+            .collect(),
+        tables_removed: before_tables
+            .iter()
+            .filter(|t| !after_tables.contains(t))
+            .cloned()
+            .collect(),
+        tables: Vec::new(),
+    };
+
+    for table in before_tables.iter().filter(|t| after_tables.contains(t)) {
+        diff.tables.push(diff_table(before, after, table)?);
+    }
+
+    Ok(diff)
+}
+
+pub fn diff_table(before: &DatabaseManager, after: &DatabaseManager, table_name: &str) -> Result<TableDiff> {
+    let mut diff = TableDiff {
+        table_name: table_name.to_string(),
+        ..Default::default()
+    };
+
+    let column_names = |db: &DatabaseManager| -> Vec<String> {
+        db.get_schema(table_name)
+            .map(|s| s.fields.into_iter().map(|f| f.name).collect())
+            .unwrap_or_default()
+    };
+    let columns = column_names(before);
+    if columns != column_names(after) {
+        diff.schema_changed = true;
+        return Ok(diff);
+    }
+
+    let mut left = RowCursor::new(before, table_name);
+    let mut right = RowCursor::new(after, table_name);
+    let mut a = left.next_row()?;
+    let mut b = right.next_row()?;
+
+    loop {
+        match (&a, &b) {
+            (None, None) => break,
+            (Some(row), None) => {
+                diff.removed.push(row.id);
+                a = left.next_row()?;
+            }
+            (None, Some(row)) => {
+                diff.added.push(row.id);
+                b = right.next_row()?;
+            }
+            (Some(x), Some(y)) if x.id < y.id => {
+                diff.removed.push(x.id);
+                a = left.next_row()?;
+            }
+            (Some(x), Some(y)) if x.id > y.id => {
+                diff.added.push(y.id);
+                b = right.next_row()?;
+            }
+            (Some(x), Some(y)) => {
+                let cells: Vec<CellChange> = columns
+                    .iter()
+                    .zip(x.cells.iter().zip(&y.cells))
+                    .filter(|(_, (old, new))| old.value != new.value || old.blob != new.blob)
+                    .map(|(column, (old, new))| CellChange {
+                        column: column.clone(),
+                        before: old.display.clone(),
+                        after: new.display.clone(),
+                    })
+                    .collect();
+
+                if !cells.is_empty() {
+                    diff.changed.push(RowChange { id: x.id, cells });
+                }
+                a = left.next_row()?;
+                b = right.next_row()?;
+            }
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Walks a table in id order using keyset pages
+struct RowCursor<'a> {
+    db: &'a DatabaseManager,
+    table_name: &'a str,
+    page: std::vec::IntoIter<RowData>,
+    last_id: i64,
+    exhausted: bool,
+}
+
+impl<'a> RowCursor<'a> {
+    fn new(db: &'a DatabaseManager, table_name: &'a str) -> Self {
+        Self {
+            db,
+            table_name,
+            page: Vec::new().into_iter(),
+            last_id: i64::MIN,
+            exhausted: false,
+        }
+    }
+
+    fn next_row(&mut self) -> Result<Option<RowData>> {
+        if let Some(row) = self.page.next() {
+            self.last_id = row.id;
+            return Ok(Some(row));
+        }
+        if self.exhausted {
+            return Ok(None);
+        }
+
+        let rows = self.db.fetch_rows_after(self.table_name, self.last_id, DIFF_PAGE_ROWS)?;
+        self.exhausted = rows.len() < DIFF_PAGE_ROWS;
+        self.page = rows.into_iter();
+
+        match self.page.next() {
+            Some(row) => {
+                self.last_id = row.id;
+                Ok(Some(row))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyedRowChange {
+    /// Key values, e.g. `7` or `EU, 12`
+    pub key: String,
+    pub cells: Vec<CellChange>,
+}
+
+/// One table compared by primary key between the open database (the source) and a
+/// target file. Tables without a primary key are matched by rowid.
+#[derive(Debug, Clone, Default)]
+pub struct KeyedTableDiff {
+    pub table_name: String,
+    pub key_columns: Vec<String>,
+    /// Keys of rows only in the source, which a sync inserts into the target
+    pub added: Vec<String>,
+    /// Keys of rows only in the target, which a sync deletes from it
+    pub removed: Vec<String>,
+    pub changed: Vec<KeyedRowChange>,
+    /// Totals, which may be more than the rows listed above
+    pub added_count: usize,
+    pub removed_count: usize,
+    pub changed_count: usize,
+}
+
+impl KeyedTableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_count == 0 && self.removed_count == 0 && self.changed_count == 0
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return format!("{} matches the target", self.table_name);
+        }
+        format!(
+            "{}: {} added, {} removed, {} changed",
+            self.table_name, self.added_count, self.removed_count, self.changed_count
+        )
+    }
+}
+
+/// Compare `table` in the open database with the same table in `target`, by primary key
+pub fn diff_table_with_file(db: &DatabaseManager, target: &Path, table: &str) -> Result<KeyedTableDiff> {
+    let plan = SyncPlan::new(db, table)?;
+    db.with_attached(target, TARGET_ALIAS, |conn| {
+        plan.check_target(conn)?;
+        plan.diff(conn)
+    })
+}
+
+/// Make `table` in `target` match the open database: insert the added rows, update the
+/// changed ones and delete the removed ones, in one transaction that only commits once a
+/// fresh diff comes back empty. Returns how many rows were inserted, updated and deleted.
+pub fn sync_table_to_file(db: &DatabaseManager, target: &Path, table: &str) -> Result<(usize, usize, usize)> {
+    if !target.is_file() {
+        return Err(anyhow!("No database file at {}", target.display()));
+    }
+    let plan = SyncPlan::new(db, table)?;
+    db.with_attached_writable(target, TARGET_ALIAS, |conn| {
+        plan.check_target(conn)?;
+        let tx = conn.unchecked_transaction()?;
+        let deleted = tx.execute(&plan.delete_sql(), [])?;
+        let updated = match plan.update_sql() {
+            Some(sql) => tx.execute(&sql, [])?,
+            None => 0,
+        };
+        let inserted = tx.execute(&plan.insert_sql(), [])?;
+
+        let remaining = plan.diff(&tx)?;
+        if !remaining.is_empty() {
+            return Err(anyhow!("The target still differs after syncing ({}); nothing was changed", remaining.summary()));
+        }
+        tx.commit()?;
+        tracing::info!(
+            "✓ Synced {} to {:?}: {} inserted, {} updated, {} deleted",
+            table, target, inserted, updated, deleted
+        );
+        Ok((inserted, updated, deleted))
+    })
+}
+
+/// The SQL for comparing and syncing one table, with the source aliased `s` and the target `d`
+struct SyncPlan {
+    table: String,
+    columns: Vec<String>,
+    keys: Vec<String>,
+}
+
+impl SyncPlan {
+    fn new(db: &DatabaseManager, table: &str) -> Result<Self> {
+        let columns: Vec<String> = db.column_info(table)?.into_iter().map(|c| c.name).collect();
+        let mut keys: Vec<String> = db.primary_key_columns(table)?.into_iter().map(|(name, _)| name).collect();
+        if keys.is_empty() {
+            keys.push("rowid".to_string());
+        }
+        Ok(Self { table: table.to_string(), columns, keys })
+    }
+
+    fn source(&self) -> String {
+        format!("main.{}", quote_identifier(&self.table))
+    }
+
+    fn target(&self) -> String {
+        format!("{}.{}", TARGET_ALIAS, quote_identifier(&self.table))
+    }
+
+    /// `s.a IS d.a AND …` over the key columns, qualified by `source` and `target`
+    fn key_match(&self, source: &str, target: &str) -> String {
+        self.keys
+            .iter()
+            .map(|k| format!("{source}.{k} IS {target}.{k}", k = quote_if_needed(k)))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// Non-key columns, the ones a changed row differs in
+    fn value_columns(&self) -> Vec<&String> {
+        self.columns.iter().filter(|c| !self.keys.iter().any(|k| k.eq_ignore_ascii_case(c))).collect()
+    }
+
+    /// `s.b IS NOT d.b OR …`, or `None` when every column is part of the key
+    fn differs(&self, source: &str, target: &str) -> Option<String> {
+        let values = self.value_columns();
+        (!values.is_empty()).then(|| {
+            values
+                .iter()
+                .map(|c| format!("{source}.{c} IS NOT {target}.{c}", c = quote_if_needed(c)))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        })
+    }
+
+    fn key_select(&self, alias: &str) -> String {
+        self.keys.iter().map(|k| format!("{}.{}", alias, quote_if_needed(k))).collect::<Vec<_>>().join(", ")
+    }
+
+    /// The target must have the table, with the same columns
+    fn check_target(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info({})", TARGET_ALIAS, quote_identifier(&self.table)))?;
+        let target_columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if target_columns.is_empty() {
+            return Err(anyhow!("The target has no {} table", self.table));
+        }
+        if target_columns != self.columns {
+            return Err(anyhow!("{} has different columns in the target; compare the schemas first", self.table));
+        }
+        Ok(())
+    }
+
+    fn added_query(&self) -> String {
+        format!(
+            "SELECT {} FROM {} AS s WHERE NOT EXISTS (SELECT 1 FROM {} AS d WHERE {})",
+            self.key_select("s"), self.source(), self.target(), self.key_match("s", "d")
+        )
+    }
+
+    fn removed_query(&self) -> String {
+        format!(
+            "SELECT {} FROM {} AS d WHERE NOT EXISTS (SELECT 1 FROM {} AS s WHERE {})",
+            self.key_select("d"), self.target(), self.source(), self.key_match("s", "d")
+        )
+    }
+
+    /// Keys, then each value column's source and target values side by side
+    fn changed_query(&self) -> Option<String> {
+        let pairs: Vec<String> = self
+            .value_columns()
+            .iter()
+            .map(|c| format!("s.{c}, d.{c}", c = quote_if_needed(c)))
+            .collect();
+        Some(format!(
+            "SELECT {}, {} FROM {} AS s JOIN {} AS d ON {} WHERE {}",
+            self.key_select("s"), pairs.join(", "), self.source(), self.target(), self.key_match("s", "d"), self.differs("s", "d")?
+        ))
+    }
+
+    fn diff(&self, conn: &Connection) -> Result<KeyedTableDiff> {
+        let count = |query: &str| -> Result<usize> {
+            Ok(conn.query_row(&format!("SELECT COUNT(*) FROM ({})", query), [], |row| row.get(0))?)
+        };
+        let key_count = self.keys.len();
+        let keys_of = |query: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(&format!("{} LIMIT {}", query, KEYED_DIFF_ROWS))?;
+            let keys = stmt
+                .query_map([], |row| {
+                    let cells = (0..key_count)
+                        .map(|ix| CellValue::from_row(row, ix).map(|c| c.display).unwrap_or_default())
+                        .collect::<Vec<_>>();
+                    Ok(cells.join(", "))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(keys)
+        };
+
+        let mut diff = KeyedTableDiff {
+            table_name: self.table.clone(),
+            key_columns: self.keys.clone(),
+            added_count: count(&self.added_query())?,
+            removed_count: count(&self.removed_query())?,
+            added: keys_of(&self.added_query())?,
+            removed: keys_of(&self.removed_query())?,
+            ..Default::default()
+        };
+
+        if let Some(query) = self.changed_query() {
+            diff.changed_count = count(&query)?;
+            let values = self.value_columns();
+            let mut stmt = conn.prepare(&format!("{} LIMIT {}", query, KEYED_DIFF_ROWS))?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let mut key = Vec::new();
+                for ix in 0..key_count {
+                    key.push(CellValue::from_row(row, ix)?.display);
+                }
+                let mut cells = Vec::new();
+                for (ix, column) in values.iter().enumerate() {
+                    let old = CellValue::from_row(row, key_count + ix * 2 + 1)?;
+                    let new = CellValue::from_row(row, key_count + ix * 2)?;
+                    if old.value != new.value || old.blob != new.blob {
+                        cells.push(CellChange { column: column.to_string(), before: old.display, after: new.display });
+                    }
+                }
+                diff.changed.push(KeyedRowChange { key: key.join(", "), cells });
+            }
+        }
+        Ok(diff)
+    }
+
+    fn delete_sql(&self) -> String {
+        let target = quote_identifier(&self.table);
+        format!(
+            "DELETE FROM {} WHERE NOT EXISTS (SELECT 1 FROM {} AS s WHERE {})",
+            self.target(), self.source(), self.key_match("s", &target)
+        )
+    }
+
+    fn update_sql(&self) -> Option<String> {
+        let target = quote_identifier(&self.table);
+        let values = self.value_columns();
+        let names: Vec<String> = values.iter().map(|c| quote_if_needed(c)).collect();
+        let sourced: Vec<String> = names.iter().map(|c| format!("s.{}", c)).collect();
+        Some(format!(
+            "UPDATE {} SET ({}) = (SELECT {} FROM {} AS s WHERE {match_}) WHERE EXISTS (SELECT 1 FROM {} AS s WHERE {match_} AND ({}))",
+            self.target(),
+            names.join(", "),
+            sourced.join(", "),
+            self.source(),
+            self.source(),
+            self.differs("s", &target)?,
+            match_ = self.key_match("s", &target),
+        ))
+    }
+
+    fn insert_sql(&self) -> String {
+        // A rowid-matched table keeps its rowids, so the rows still match afterwards
+        let mut columns: Vec<String> = self.columns.iter().map(|c| quote_if_needed(c)).collect();
+        if self.keys == ["rowid"] {
+            columns.insert(0, "rowid".to_string());
+        }
+        format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {} AS s WHERE NOT EXISTS (SELECT 1 FROM {} AS d WHERE {})",
+            self.target(),
+            columns.join(", "),
+            columns.iter().map(|c| format!("s.{}", c)).collect::<Vec<_>>().join(", "),
+            self.source(),
+            self.target(),
+            self.key_match("s", "d")
+        )
+    }
+}
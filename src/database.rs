@@ -1,15 +1,25 @@
 use anyhow::{Result, anyhow};
-use rusqlite::{Connection, params, Row, ToSql};
-use parking_lot::RwLock;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params, Row, ToSql};
+use rusqlite::types::Value as SqlValue;
+use parking_lot::{Mutex, RwLock};
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use serde_json::Value;
-use crate::reflection::{TypeSchema, SqlType};
+use crate::activity;
+use crate::provenance::Provenance;
+use crate::reflection::{ColumnHints, GeoPair, ReflectionProfile, TypeSchema, SqlType};
+use crate::sql_text::{mentions, quote_identifier};
 
 #[derive(Debug, Clone)]
 pub struct CellValue {
     pub value: Value,
     pub display: String,
+    /// Raw bytes for BLOB cells, which `value` only summarizes
+    pub blob: Option<Vec<u8>>,
 }
 
 impl CellValue {
@@ -22,7 +32,32 @@ impl CellValue {
             _ => value.to_string(),
         };
 
-        Self { value, display }
+        Self { value, display, blob: None }
+    }
+
+    /// Bytes of content: the BLOB's length, or that of the text form
+    pub fn size(&self) -> usize {
+        self.blob.as_ref().map_or(self.display.len(), Vec::len)
+    }
+
+    /// `<3.2 MB blob>`, shown in place of content over the cell size limit
+    pub fn size_placeholder(&self) -> String {
+        let kind = if self.blob.is_some() { "blob" } else { "text" };
+        format!("<{} {}>", format_bytes(self.size()), kind)
+    }
+
+    /// The value as a SQL literal, BLOBs as `X'..'` hex
+    pub fn sql_literal(&self) -> String {
+        if let Some(bytes) = &self.blob {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            return format!("X'{}'", hex);
+        }
+        match &self.value {
+            Value::Null => "NULL".to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => (*b as i32).to_string(),
+            _ => format!("'{}'", self.display.replace('\'', "''")),
+        }
     }
 
     pub fn from_row(row: &Row, idx: usize) -> Result<Self> {
@@ -40,7 +75,9 @@ impl CellValue {
                 Value::String(String::from_utf8_lossy(t).to_string())
             }
             rusqlite::types::ValueRef::Blob(b) => {
-                Value::String(format!("<blob {} bytes>", b.len()))
+                let mut cell = CellValue::new(Value::String(format!("<blob {} bytes>", b.len())));
+                cell.blob = Some(b.to_vec());
+                return Ok(cell);
             }
         };
 
@@ -48,36 +85,615 @@ impl CellValue {
     }
 }
 
+/// `512 B`, `48.0 KB`, `3.2 MB`
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 #[derive(Debug, Clone)]
 pub struct RowData {
     pub id: i64,
     pub cells: Vec<CellValue>,
+    /// Values of the table's key columns, which edits and deletes match on
+    pub key: Vec<Value>,
+}
+
+/// Receives rows as SQLite produces them, so callers never hold a whole result in memory
+pub trait RowSink {
+    fn begin(&mut self, columns: &[String]) -> Result<()>;
+    fn row(&mut self, cells: &[CellValue]) -> Result<()>;
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// How a table stores its rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    Rowid,
+    /// Keyed only by its primary key; there is no `rowid` column to address rows by
+    WithoutRowid,
+    /// A virtual table such as FTS, or one of the shadow tables behind it
+    Virtual,
+}
+
+/// Which rows of a table an operation covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowScope {
+    All,
+    /// Rows matching the grid's text filter
+    Matching(String),
+    /// Explicitly selected rows
+    Ids(Vec<i64>),
+    /// Rows matching a WHERE condition, such as the filter builder's
+    Where { condition: String, params: Vec<Value> },
+}
+
+impl RowScope {
+    pub fn describe(&self) -> String {
+        match self {
+            RowScope::All => "all rows".to_string(),
+            RowScope::Matching(search) => format!("rows matching \"{}\"", search),
+            RowScope::Ids(ids) => format!("{} selected rows", ids.len()),
+            RowScope::Where { condition, .. } => format!("rows where {}", condition),
+        }
+    }
+}
+
+/// One column of a multi-column sort order
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// A view, trigger or index as recorded in `sqlite_master`
+#[derive(Debug, Clone)]
+pub struct SchemaObject {
+    /// `view`, `trigger` or `index`
+    pub kind: String,
+    pub name: String,
+    /// Table the object belongs to; a view's own name
+    pub table_name: String,
+    pub sql: Option<String>,
+}
+
+/// An index from `PRAGMA index_list`, with its key columns
+#[derive(Debug, Clone)]
+pub struct IndexInfo {
+    pub name: String,
+    pub table_name: String,
+    /// Key columns in order; expression keys show as `<expr>`
+    pub columns: Vec<String>,
+    pub unique: bool,
+    /// Covers only the rows matching its WHERE clause
+    pub partial: bool,
+    /// `c` for CREATE INDEX, `u` for a UNIQUE constraint, `pk` for a PRIMARY KEY
+    pub origin: String,
+}
+
+impl IndexInfo {
+    /// Made by SQLite for a constraint, so it goes only with the constraint
+    pub fn is_automatic(&self) -> bool {
+        self.origin != "c"
+    }
+}
+
+/// A column from `PRAGMA table_info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub declared_type: String,
+    pub not_null: bool,
+    /// The default's SQL text, e.g. `'draft'` or `CURRENT_TIMESTAMP`
+    pub default_value: Option<String>,
+    /// Position in the primary key, 0 when not part of it
+    pub primary_key: i64,
+}
+
+/// A `REFERENCES` clause from `PRAGMA foreign_key_list`
+#[derive(Debug, Clone)]
+pub struct ForeignKey {
+    /// Shared by the columns of one multi-column constraint
+    pub id: i64,
+    pub column: String,
+    pub referenced_table: String,
+    /// `None` when the clause names only the table, meaning its primary key
+    pub referenced_column: Option<String>,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<CellValue>>,
+    /// More rows were available than the requested cap
+    pub truncated: bool,
+}
+
+/// What a query running on a worker connection sends back, in order
+#[derive(Debug, Clone)]
+pub enum QueryEvent {
+    Columns(Vec<String>),
+    Rows(Vec<Vec<CellValue>>),
+    Done { truncated: bool },
+    /// Stopped through its [`CancelHandle`]; the rows sent so far are all there is
+    Interrupted,
+    Failed(String),
+}
+
+/// Aborts the statement running on a connection via `sqlite3_interrupt`
+#[derive(Clone)]
+pub struct CancelHandle(Arc<rusqlite::InterruptHandle>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.interrupt();
+    }
+}
+
+/// A query running on a worker connection
+pub struct RunningQuery {
+    pub events: UnboundedReceiver<QueryEvent>,
+    pub cancel: CancelHandle,
+}
+
+/// Rows a background query sends back at a time
+const STREAM_BATCH_ROWS: usize = 500;
+
+/// Rows per multi-row INSERT statement when bulk inserting
+const INSERT_BATCH_ROWS: usize = 500;
+
+/// SQLite's default limit on bound parameters in one statement
+const MAX_SQL_VARIABLES: usize = 32766;
+
+/// How often reads re-validate the connection with a lightweight pragma
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the editor looks for writes to the file by other processes
+pub const DISK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What was last seen of the file on disk, to tell other processes' writes apart
+#[derive(Debug, Default)]
+struct DiskWatch {
+    /// Modification time and size of the database file and its `-wal`
+    stamp: Option<[Option<(SystemTime, u64)>; 2]>,
+    /// `PRAGMA data_version` when the data was last loaded
+    data_version: Option<i64>,
+    changed: bool,
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn disk_stamp(path: &Path) -> [Option<(SystemTime, u64)>; 2] {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    [file_stamp(path), file_stamp(Path::new(&wal))]
 }
 
 pub struct DatabaseManager {
     connection: Arc<RwLock<Connection>>,
     schemas: Arc<RwLock<HashMap<String, TypeSchema>>>,
+    /// Backing file, `None` for in-memory databases (which can't be reopened)
+    path: Option<PathBuf>,
+    read_only: bool,
+    /// Opened in safe mode; every connection to the file gets the defensive settings
+    quarantined: bool,
+    /// Change freeze: writes are refused by `PRAGMA query_only` until it's lifted
+    frozen: Arc<AtomicBool>,
+    last_health_check: Arc<Mutex<Instant>>,
+    profile: Arc<RwLock<ReflectionProfile>>,
+    /// Stamps for the provenance columns of rows the grid writes, when turned on
+    provenance: Arc<RwLock<Option<Provenance>>>,
+    /// Transactional editing: grid writes join one open transaction until committed
+    transactional: Arc<AtomicBool>,
+    /// The writes held in that transaction, oldest first
+    held: Arc<Mutex<Vec<HeldChange>>>,
+    /// Held edits a reconnect threw away, until the editor reports them
+    lost_edits: Arc<Mutex<usize>>,
+    disk: Arc<Mutex<DiskWatch>>,
+}
+
+/// A grid write run inside the held transaction, with what it replaced
+#[derive(Debug, Clone)]
+pub struct HeldChange {
+    pub table: String,
+    /// Key values of the row written; empty for batch inserts
+    pub key: Vec<Value>,
+    /// Column an update changed; `None` for whole-row inserts and deletes
+    pub column: Option<String>,
+    /// Values before and after, as displayed; `None` on the side without a row
+    pub old: Option<String>,
+    pub new: Option<String>,
+    /// The statement with its values bound into the SQL
+    pub sql: String,
+    /// Statement and values undoing the change; `None` for batch inserts, which only
+    /// roll back with the whole transaction
+    revert: Option<(String, Vec<SqlValue>)>,
+}
+
+impl HeldChange {
+    pub fn can_revert(&self) -> bool {
+        self.revert.is_some()
+    }
+
+    /// Whether both changes may have written the same row, so undoing the earlier one
+    /// alone would clobber the later
+    pub fn touches(&self, other: &HeldChange) -> bool {
+        self.table == other.table && (self.key.is_empty() || other.key.is_empty() || self.key == other.key)
+    }
+
+    /// `3`, or `2, sword` for a composite key
+    pub fn row_label(&self) -> String {
+        self.key.iter().map(value_label).collect::<Vec<_>>().join(", ")
+    }
 }
 
 impl DatabaseManager {
     pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self> {
-        let connection = Connection::open(path)?;
+        let path = path.as_ref().to_path_buf();
+        let connection = activity::instrument(Connection::open(&path)?);
 
-        Ok(Self {
+        let db = Self {
             connection: Arc::new(RwLock::new(connection)),
             schemas: Arc::new(RwLock::new(HashMap::new())),
-        })
+            path: Some(path),
+            read_only: false,
+            quarantined: false,
+            frozen: Arc::new(AtomicBool::new(false)),
+            last_health_check: Arc::new(Mutex::new(Instant::now())),
+            profile: Arc::new(RwLock::new(ReflectionProfile::default())),
+            provenance: Arc::new(RwLock::new(None)),
+            transactional: Arc::new(AtomicBool::new(false)),
+            held: Arc::new(Mutex::new(Vec::new())),
+            lost_edits: Arc::new(Mutex::new(0)),
+            disk: Arc::new(Mutex::new(DiskWatch::default())),
+        };
+        // Writes by other processes count from the moment it's opened
+        db.mark_disk_current()?;
+        Ok(db)
+    }
+
+    /// Open a database so that SQLite itself rejects every write
+    pub fn open_read_only(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let connection = activity::instrument(Connection::open_with_flags(&path, read_only_flags())?);
+
+        let db = Self {
+            connection: Arc::new(RwLock::new(connection)),
+            schemas: Arc::new(RwLock::new(HashMap::new())),
+            path: Some(path),
+            read_only: true,
+            quarantined: false,
+            frozen: Arc::new(AtomicBool::new(false)),
+            last_health_check: Arc::new(Mutex::new(Instant::now())),
+            profile: Arc::new(RwLock::new(ReflectionProfile::default())),
+            provenance: Arc::new(RwLock::new(None)),
+            transactional: Arc::new(AtomicBool::new(false)),
+            held: Arc::new(Mutex::new(Vec::new())),
+            lost_edits: Arc::new(Mutex::new(0)),
+            disk: Arc::new(Mutex::new(DiskWatch::default())),
+        };
+        // Writes by other processes count from the moment it's opened
+        db.mark_disk_current()?;
+        Ok(db)
+    }
+
+    /// Open a database from an untrusted source: read-only, with triggers, views and
+    /// schema-defined functions disabled and oversized values refused
+    pub fn open_quarantined(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let connection = Connection::open_with_flags(&path, read_only_flags())?;
+        harden(&connection)?;
+
+        let db = Self {
+            connection: Arc::new(RwLock::new(activity::instrument(connection))),
+            schemas: Arc::new(RwLock::new(HashMap::new())),
+            path: Some(path),
+            read_only: true,
+            quarantined: true,
+            frozen: Arc::new(AtomicBool::new(false)),
+            last_health_check: Arc::new(Mutex::new(Instant::now())),
+            profile: Arc::new(RwLock::new(ReflectionProfile::default())),
+            provenance: Arc::new(RwLock::new(None)),
+            transactional: Arc::new(AtomicBool::new(false)),
+            held: Arc::new(Mutex::new(Vec::new())),
+            lost_edits: Arc::new(Mutex::new(0)),
+            disk: Arc::new(Mutex::new(DiskWatch::default())),
+        };
+        // Writes by other processes count from the moment it's opened
+        db.mark_disk_current()?;
+        Ok(db)
     }
 
     pub fn in_memory() -> Result<Self> {
-        let connection = Connection::open_in_memory()?;
+        let connection = activity::instrument(Connection::open_in_memory()?);
 
         Ok(Self {
             connection: Arc::new(RwLock::new(connection)),
             schemas: Arc::new(RwLock::new(HashMap::new())),
+            path: None,
+            read_only: false,
+            quarantined: false,
+            frozen: Arc::new(AtomicBool::new(false)),
+            last_health_check: Arc::new(Mutex::new(Instant::now())),
+            profile: Arc::new(RwLock::new(ReflectionProfile::default())),
+            provenance: Arc::new(RwLock::new(None)),
+            transactional: Arc::new(AtomicBool::new(false)),
+            held: Arc::new(Mutex::new(Vec::new())),
+            lost_edits: Arc::new(Mutex::new(0)),
+            disk: Arc::new(Mutex::new(DiskWatch::default())),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Where exports and reports named `file_name` are written: next to the database
+    /// file rather than the engine's working directory, or the temp directory for an
+    /// in-memory database. Table names in `file_name` can't lead out of that directory.
+    pub fn output_path(&self, file_name: &str) -> PathBuf {
+        let dir = self
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(std::env::temp_dir);
+        dir.join(crate::export::table_file_name(file_name))
+    }
+
+    /// Whether writes are refused, because the file was opened read-only or a change
+    /// freeze is on
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.is_frozen()
+    }
+
+    /// Opened read-only (safe mode or save inspection), as opposed to frozen for a while
+    pub fn opened_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+
+    /// Start or lift a change freeze on every handle to this connection. SQLite itself
+    /// refuses writes while it's on, so hand-written SQL is blocked too.
+    pub fn set_frozen(&self, frozen: bool) -> Result<()> {
+        let conn = self.connection.write();
+        conn.pragma_update(None, "query_only", frozen)?;
+        self.frozen.store(frozen, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantined
+    }
+
+    /// Whether `sql` only reads, as judged by SQLite when preparing it
+    pub fn is_read_only_statement(&self, sql: &str) -> Result<bool> {
+        self.read(|conn| Ok(conn.prepare(sql)?.readonly()))
+    }
+
+    /// Changes as of now: `PRAGMA data_version` moves on other connections' commits,
+    /// the total change count on this connection's own writes
+    pub fn change_counter(&self) -> Result<(i64, u64)> {
+        self.read(|conn| {
+            let data_version: i64 = conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+            Ok((data_version, conn.total_changes()))
         })
     }
 
+    pub fn set_profile(&self, profile: ReflectionProfile) {
+        *self.profile.write() = profile;
+    }
+
+    pub fn profile(&self) -> ReflectionProfile {
+        self.profile.read().clone()
+    }
+
+    /// Stamp the provenance columns on every grid write from now on; `None` stops
+    pub fn set_provenance(&self, provenance: Option<Provenance>) {
+        *self.provenance.write() = provenance;
+    }
+
+    /// Provenance assignments for an update writing `written`, with the columns they set
+    fn update_stamps(&self, table_name: &str, written: &[&str]) -> (Vec<String>, Vec<String>) {
+        match (self.provenance.read().as_ref(), self.get_schema(table_name)) {
+            (Some(provenance), Some(schema)) => {
+                (provenance.update_stamps(&schema, written), provenance.update_columns(&schema, written))
+            }
+            _ => (Vec::new(), Vec::new()),
+        }
+    }
+
+    /// The VALUES entry for each of `schema`'s fields, and whether it binds the row's
+    /// value; provenance columns are filled in by SQL, so inserts never need the rowid
+    fn insert_placeholders(&self, schema: &TypeSchema) -> Vec<(String, bool)> {
+        let provenance = self.provenance.read();
+        schema
+            .fields
+            .iter()
+            .map(|field| {
+                provenance
+                    .as_ref()
+                    .and_then(|p| p.insert_placeholder(field))
+                    .unwrap_or_else(|| ("?".to_string(), true))
+            })
+            .collect()
+    }
+
+    pub fn column_hints(&self, table_name: &str, column: &str) -> Option<ColumnHints> {
+        self.profile.read().column(table_name, column).cloned()
+    }
+
+    pub fn geo_pairs(&self, table_name: &str) -> Vec<GeoPair> {
+        self.profile.read().tables.get(table_name).map(|t| t.geo.clone()).unwrap_or_default()
+    }
+
+    /// Run a cheap pragma against the connection and reopen it if it no longer responds
+    pub fn check_health(&self) -> Result<()> {
+        let healthy = {
+            let conn = self.connection.read();
+            conn.query_row("PRAGMA schema_version", [], |row| row.get::<_, i64>(0))
+                .is_ok()
+        };
+        *self.last_health_check.lock() = Instant::now();
+
+        if healthy {
+            return Ok(());
+        }
+
+        tracing::warn!("Database connection failed health check, reconnecting");
+        self.reconnect()
+    }
+
+    pub fn reconnect(&self) -> Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("Cannot reconnect an in-memory database"))?;
+
+        let connection = if self.read_only {
+            Connection::open_with_flags(path, read_only_flags())?
+        } else {
+            Connection::open(path)?
+        };
+        if self.quarantined {
+            harden(&connection)?;
+        }
+        if self.is_frozen() {
+            connection.pragma_update(None, "query_only", true)?;
+        }
+        // Data versions are per connection, so the new one starts a fresh baseline
+        let data_version: i64 = connection.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        // The old connection took its open transaction with it
+        let lost = std::mem::take(&mut *self.held.lock()).len();
+        *self.connection.write() = activity::instrument(connection);
+        let mut disk = self.disk.lock();
+        disk.stamp = Some(disk_stamp(path));
+        disk.data_version = Some(data_version);
+        drop(disk);
+
+        tracing::info!("Reconnected to database {:?}", path);
+        if lost > 0 {
+            // Reads reconnect on their own, so the loss is kept for the editor to report
+            tracing::error!("The connection was reset and {} uncommitted edits were lost", lost);
+            *self.lost_edits.lock() += lost;
+        }
+        Ok(())
+    }
+
+    /// Close the file for every clone sharing this connection, such as those open
+    /// table views hold, so it can be deleted; later queries see an empty in-memory
+    /// database instead
+    pub fn close(&self) -> Result<()> {
+        *self.connection.write() = Connection::open_in_memory()?;
+        Ok(())
+    }
+
+    /// Held edits lost to reconnects since the last call
+    pub fn take_lost_edits(&self) -> usize {
+        std::mem::take(&mut *self.lost_edits.lock())
+    }
+
+    /// Whether another process wrote to the file since the data was last loaded. The
+    /// file and `-wal` are only stat'ed while they stay unchanged; once they change,
+    /// `PRAGMA data_version` tells other connections' commits from this one's own.
+    pub fn changed_on_disk(&self) -> Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let stamp = disk_stamp(path);
+        if self.disk.lock().stamp == Some(stamp) {
+            return Ok(self.disk.lock().changed);
+        }
+
+        let version = self.data_version()?;
+        let mut disk = self.disk.lock();
+        disk.stamp = Some(stamp);
+        let baseline = *disk.data_version.get_or_insert(version);
+        disk.changed |= version != baseline;
+        Ok(disk.changed)
+    }
+
+    /// Take what's on disk now as seen, after reloading from it
+    pub fn mark_disk_current(&self) -> Result<()> {
+        let version = self.data_version()?;
+        let mut disk = self.disk.lock();
+        disk.stamp = self.path.as_deref().map(disk_stamp);
+        disk.data_version = Some(version);
+        disk.changed = false;
+        Ok(())
+    }
+
+    /// Snapshot the committed database into a new file at `target` with the online
+    /// backup API, so other readers and writers aren't held up. A second connection
+    /// reads it, which leaves out edits still held in this one's transaction.
+    pub fn backup_to(&self, target: &Path) -> Result<()> {
+        let conn = self.open_read_only_connection()?;
+        conn.backup(rusqlite::DatabaseName::Main, target, None)?;
+        Ok(())
+    }
+
+    fn data_version(&self) -> Result<i64> {
+        self.read(|conn| Ok(conn.query_row("PRAGMA data_version", [], |row| row.get(0))?))
+    }
+
+    /// Open an extra read-only connection to the same file, e.g. one per worker thread
+    pub fn open_read_only_connection(&self) -> Result<Connection> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("In-memory databases can't be shared across connections"))?;
+
+        let connection = Connection::open_with_flags(path, read_only_flags())?;
+        if self.quarantined {
+            harden(&connection)?;
+        }
+        Ok(activity::instrument(connection))
+    }
+
+    /// Run an idempotent read, transparently reconnecting and replaying it once
+    /// if the connection has gone bad (e.g. a network drive dropped the handle)
+    fn read<T>(&self, f: impl Fn(&Connection) -> Result<T>) -> Result<T> {
+        let due = self.last_health_check.lock().elapsed() >= HEALTH_CHECK_INTERVAL;
+        if due {
+            if let Err(e) = self.check_health() {
+                tracing::error!("Database health check failed: {}", e);
+            }
+        }
+
+        let result = {
+            let conn = self.connection.read();
+            f(&conn)
+        };
+
+        match result {
+            Err(e) if self.path.is_some() && is_connection_error(&e) => {
+                tracing::warn!("Read failed with connection error ({}), replaying after reconnect", e);
+                self.reconnect()?;
+                let conn = self.connection.read();
+                f(&conn)
+            }
+            result => result,
+        }
+    }
+
     pub fn register_type(&self, schema: TypeSchema) -> Result<()> {
         let create_sql = schema.to_create_table_sql();
 
@@ -115,52 +731,193 @@ impl DatabaseManager {
         None
     }
 
-    pub fn list_tables(&self) -> Result<Vec<String>> {
+    pub fn application_id(&self) -> Result<i32> {
+        self.read(|conn| Ok(conn.pragma_query_value(None, "application_id", |row| row.get(0))?))
+    }
+
+    pub fn user_version(&self) -> Result<i32> {
+        self.read(|conn| Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?))
+    }
+
+    pub fn set_application_id(&self, application_id: i32) -> Result<()> {
+        let conn = self.connection.write();
+        conn.pragma_update(None, "application_id", application_id)?;
+        Ok(())
+    }
+
+    pub fn set_user_version(&self, user_version: i32) -> Result<()> {
+        let conn = self.connection.write();
+        conn.pragma_update(None, "user_version", user_version)?;
+        Ok(())
+    }
+
+    /// Whether this connection enforces foreign keys, which SQLite leaves off by default
+    pub fn foreign_keys_enabled(&self) -> Result<bool> {
         let conn = self.connection.read();
-        let mut stmt = conn.prepare(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
-        )?;
+        Ok(conn.pragma_query_value(None, "foreign_keys", |row| row.get(0))?)
+    }
 
-        let tables = stmt
-            .query_map([], |row| row.get(0))?
-            .collect::<Result<Vec<String>, _>>()?;
+    pub fn set_foreign_keys(&self, enabled: bool) -> Result<()> {
+        let conn = self.connection.write();
+        conn.pragma_update(None, "foreign_keys", enabled)?;
+        Ok(())
+    }
+
+    /// User tables; the editor's own `__pulsar_*` bookkeeping tables are left out
+    pub fn list_tables(&self) -> Result<Vec<String>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' \
+                 AND name NOT LIKE '\\_\\_pulsar\\_%' ESCAPE '\\' ORDER BY name"
+            )?;
+
+            let tables = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+
+            Ok(tables)
+        })
+    }
+
+    /// How `table_name` stores its rows, as `PRAGMA table_list` reports it
+    pub fn table_kind(&self, table_name: &str) -> Result<TableKind> {
+        self.read(|conn| {
+            let (kind, without_rowid): (String, bool) = conn.query_row(
+                "SELECT type, wr FROM pragma_table_list WHERE schema = 'main' AND name = ?1",
+                [table_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            Ok(match kind.as_str() {
+                "virtual" | "shadow" => TableKind::Virtual,
+                _ if without_rowid => TableKind::WithoutRowid,
+                _ => TableKind::Rowid,
+            })
+        })
+    }
+
+    pub fn list_views(&self) -> Result<Vec<String>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type='view' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+            )?;
+
+            let views = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+
+            Ok(views)
+        })
+    }
+
+    pub fn is_view(&self, name: &str) -> bool {
+        self.get_schema(name).is_some_and(|schema| schema.is_view)
+    }
+
+    /// Views and `WITHOUT ROWID` tables, whose rows are numbered as they're read
+    fn numbers_rows(&self, name: &str) -> bool {
+        self.get_schema(name).is_some_and(|schema| !schema.has_row_ids())
+    }
+
+    /// A view's `CREATE VIEW` statement as stored in `sqlite_master`
+    pub fn view_sql(&self, name: &str) -> Result<String> {
+        self.read(|conn| {
+            let sql: Option<String> = conn
+                .query_row(
+                    "SELECT sql FROM sqlite_master WHERE type = 'view' AND name = ?1",
+                    [name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            sql.ok_or_else(|| anyhow!("No view named {}", name))
+        })
+    }
+
+    /// Create the view `name` from `definition` (`AS SELECT ...`, after any column list),
+    /// replacing `original` if given. The old view is dropped in the same transaction, so
+    /// a SELECT that doesn't compile leaves it in place. Dropping a view drops its
+    /// INSTEAD OF triggers, so they're created again on the new one.
+    pub fn save_view(&self, original: Option<&str>, name: &str, definition: &str) -> Result<()> {
+        let triggers: Vec<SchemaObject> = match original {
+            Some(original) => self
+                .schema_objects()?
+                .into_iter()
+                .filter(|o| o.kind == "trigger" && o.table_name.eq_ignore_ascii_case(original))
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(original) = original {
+            if !triggers.is_empty() && !original.eq_ignore_ascii_case(name) {
+                return Err(anyhow!(
+                    "{} has triggers ({}) that name it; keep its name when editing it",
+                    original,
+                    triggers.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
 
-        Ok(tables)
+        self.with_transaction(|tx| {
+            if let Some(original) = original {
+                tx.execute_batch(&format!("DROP VIEW {}", quote_identifier(original)))?;
+            }
+            tx.execute_batch(&format!("CREATE VIEW {} {}", quote_identifier(name), definition))?;
+            for trigger in triggers.iter().filter_map(|t| t.sql.as_deref()) {
+                tx.execute_batch(trigger)?;
+            }
+            Ok(())
+        })?;
+        self.refresh_schemas()
     }
     
     pub fn introspect_and_register_schemas(&self) -> Result<()> {
-        let tables = self.list_tables()?;
+        let tables = self.list_tables()?.into_iter().map(|name| (name, false));
+        let views = self.list_views()?.into_iter().map(|name| (name, true));
         
-        for table_name in tables {
+        for (table_name, is_view) in tables.chain(views) {
             // Skip if schema already registered
             if self.get_schema(&table_name).is_some() {
                 continue;
             }
             
             // Get table structure from SQLite
-            let conn = self.connection.read();
-            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
-            
-            let columns: Vec<(String, String, bool)> = stmt
-                .query_map([], |row| {
-                    Ok((
-                        row.get::<_, String>(1)?, // column name
-                        row.get::<_, String>(2)?, // column type
-                        row.get::<_, i32>(3)? == 0, // nullable (notnull == 0 means nullable)
-                    ))
-                })?
-                .collect::<rusqlite::Result<Vec<_>>>()?;
-            
-            drop(stmt);
-            drop(conn);
+            let columns: Vec<(String, String, bool, usize)> = self.read(|conn| {
+                let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_identifier(&table_name)))?;
+
+                let columns = stmt
+                    .query_map([], |row| {
+                        Ok((
+                            row.get::<_, String>(1)?, // column name
+                            row.get::<_, String>(2)?, // column type
+                            row.get::<_, i32>(3)? == 0, // nullable (notnull == 0 means nullable)
+                            row.get::<_, usize>(5)?, // position in the primary key, 0 if not part of it
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+
+                Ok(columns)
+            })?;
             
             // Create schema from introspected columns
             let mut schema = TypeSchema::new(table_name.clone());
             schema.table_name = table_name.clone(); // Override to use exact table name
+            schema.is_view = is_view;
+            schema.without_rowid = !is_view && self.table_kind(&table_name)? == TableKind::WithoutRowid;
+
+            let mut key: Vec<(usize, String)> = columns
+                .iter()
+                .filter(|(_, _, _, pk)| *pk > 0)
+                .map(|(name, _, _, pk)| (*pk, name.clone()))
+                .collect();
+            key.sort();
+            if !key.is_empty() && !(key.len() == 1 && key[0].1 == "id") {
+                schema.primary_key = key.into_iter().map(|(_, name)| name).collect();
+            }
+            if !is_view {
+                schema.unique_constraints = self.unique_constraints(&table_name)?;
+            }
             
-            for (col_name, col_type, nullable) in columns {
-                // Skip 'id' column as it's automatically added
-                if col_name == "id" {
+            for (col_name, col_type, nullable, _) in columns {
+                // Skip 'id' column as it's automatically added; a view's is just a column
+                if col_name == "id" && schema.primary_key.is_empty() && !is_view {
                     continue;
                 }
                 
@@ -185,47 +942,963 @@ impl DatabaseManager {
         Ok(())
     }
 
-    pub fn get_row_count(&self, table_name: &str) -> Result<usize> {
-        let conn = self.connection.read();
-        let count: usize = conn.query_row(
-            &format!("SELECT COUNT(*) FROM {}", table_name),
-            [],
-            |row| row.get(0),
-        )?;
-        Ok(count)
+    /// Every column of the table, including `id`, in declaration order
+    pub fn column_names(&self, table_name: &str) -> Result<Vec<String>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1) ORDER BY cid")?;
+            let columns = stmt
+                .query_map([table_name], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(columns)
+        })
     }
 
-    pub fn fetch_rows(&self, table_name: &str, offset: usize, limit: usize) -> Result<Vec<RowData>> {
-        let schema = self
-            .get_schema(table_name)
-            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+    /// Every column of the table with its declared type and constraints, in declaration order
+    pub fn column_info(&self, table_name: &str) -> Result<Vec<ColumnInfo>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid"
+            )?;
+            let columns = stmt
+                .query_map([table_name], |row| {
+                    Ok(ColumnInfo {
+                        name: row.get(0)?,
+                        declared_type: row.get(1)?,
+                        not_null: row.get(2)?,
+                        default_value: row.get(3)?,
+                        primary_key: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(columns)
+        })
+    }
 
-        let conn = self.connection.read();
-        let mut stmt = conn.prepare(&format!(
-            "SELECT id, {} FROM {} ORDER BY id LIMIT ? OFFSET ?",
-            schema.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(", "),
-            table_name
-        ))?;
-
-        let rows = stmt.query_map(params![limit, offset], |row| {
-            let id: i64 = row.get(0)?;
-            let mut cells = Vec::new();
+    /// `(name, declared type)` of the primary key columns, in key order
+    pub fn primary_key_columns(&self, table_name: &str) -> Result<Vec<(String, String)>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare("SELECT name, type FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")?;
+            let columns = stmt
+                .query_map([table_name], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(columns)
+        })
+    }
 
-            for i in 1..=schema.fields.len() {
-                cells.push(CellValue::from_row(row, i).unwrap());
+    /// The highest id an AUTOINCREMENT table has issued, from `sqlite_sequence`.
+    /// `None` until the table's first insert, or when no table uses AUTOINCREMENT.
+    pub fn sequence_value(&self, table_name: &str) -> Result<Option<i64>> {
+        self.read(|conn| {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence'",
+                [],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Ok(None);
             }
+            Ok(conn
+                .query_row("SELECT seq FROM sqlite_sequence WHERE name = ?1", [table_name], |row| row.get(0))
+                .optional()?)
+        })
+    }
 
-            Ok(RowData { id, cells })
-        })?;
+    /// Set the id the next AUTOINCREMENT insert counts up from. SQLite still never goes
+    /// below the table's current largest id.
+    pub fn set_sequence_value(&self, table_name: &str, value: i64) -> Result<()> {
+        let conn = self.connection.write();
+        let updated = conn.execute("UPDATE sqlite_sequence SET seq = ?1 WHERE name = ?2", params![value, table_name])?;
+        if updated == 0 {
+            conn.execute("INSERT INTO sqlite_sequence (name, seq) VALUES (?1, ?2)", params![table_name, value])?;
+        }
+        Ok(())
+    }
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
+    /// Column lists of the table's multi-column `UNIQUE` constraints
+    pub fn unique_constraints(&self, table_name: &str) -> Result<Vec<Vec<String>>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM pragma_index_list(?1) WHERE origin = 'u' ORDER BY seq"
+            )?;
+            let indexes = stmt
+                .query_map([table_name], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut constraints = Vec::new();
+            for index in indexes {
+                let mut stmt = conn.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+                let columns = stmt
+                    .query_map([&index], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                if columns.len() > 1 {
+                    constraints.push(columns);
+                }
+            }
+            Ok(constraints)
+        })
+    }
+
+    /// Re-read every table's schema, e.g. after an ALTER changed names or columns
+    pub fn refresh_schemas(&self) -> Result<()> {
+        self.schemas.write().clear();
+        self.introspect_and_register_schemas()
+    }
+
+    /// Views, triggers and indexes with their defining SQL; automatic indexes have none
+    pub fn schema_objects(&self) -> Result<Vec<SchemaObject>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT type, name, tbl_name, sql FROM sqlite_master \
+                 WHERE type IN ('view', 'trigger', 'index') AND name NOT LIKE 'sqlite_%' \
+                 ORDER BY type, name"
+            )?;
+
+            let objects = stmt
+                .query_map([], |row| {
+                    Ok(SchemaObject {
+                        kind: row.get(0)?,
+                        name: row.get(1)?,
+                        table_name: row.get(2)?,
+                        sql: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(objects)
+        })
+    }
+
+    /// Every index of every table, by table then name
+    pub fn list_indexes(&self) -> Result<Vec<IndexInfo>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT m.name, il.name, il.\"unique\", il.origin, il.partial \
+                 FROM sqlite_master m JOIN pragma_index_list(m.name) il \
+                 WHERE m.type = 'table' ORDER BY m.name, il.name"
+            )?;
+            let mut indexes = stmt
+                .query_map([], |row| {
+                    Ok(IndexInfo {
+                        table_name: row.get(0)?,
+                        name: row.get(1)?,
+                        unique: row.get(2)?,
+                        origin: row.get(3)?,
+                        partial: row.get(4)?,
+                        columns: Vec::new(),
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut columns = conn.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+            for index in indexes.iter_mut() {
+                index.columns = columns
+                    .query_map([&index.name], |row| {
+                        Ok(row.get::<_, Option<String>>(0)?.unwrap_or_else(|| "<expr>".to_string()))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+            }
+            Ok(indexes)
+        })
+    }
+
+    /// `CREATE [UNIQUE] INDEX name ON table (columns) [WHERE condition]`
+    pub fn create_index(
+        &self,
+        name: &str,
+        table_name: &str,
+        columns: &[String],
+        unique: bool,
+        condition: Option<&str>,
+    ) -> Result<()> {
+        if columns.is_empty() {
+            return Err(anyhow!("An index needs at least one column"));
+        }
+        let mut sql = format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            if unique { "UNIQUE " } else { "" },
+            quote_identifier(name),
+            quote_identifier(table_name),
+            columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+        );
+        if let Some(condition) = condition.filter(|c| !c.trim().is_empty()) {
+            sql.push_str(&format!(" WHERE {}", condition.trim()));
         }
+        self.connection.write().execute_batch(&sql)?;
+        tracing::info!("✓ {}", sql);
+        Ok(())
+    }
+
+    pub fn drop_index(&self, name: &str) -> Result<()> {
+        self.connection
+            .write()
+            .execute_batch(&format!("DROP INDEX {}", quote_identifier(name)))?;
+        Ok(())
+    }
+
+    /// Rebuild one index, or every index in the database
+    pub fn reindex(&self, name: Option<&str>) -> Result<()> {
+        let sql = match name {
+            Some(name) => format!("REINDEX {}", quote_identifier(name)),
+            None => "REINDEX".to_string(),
+        };
+        self.connection.write().execute_batch(&sql)?;
+        Ok(())
+    }
+
+    /// Triggers firing on `table_name`, by name
+    pub fn triggers(&self, table_name: &str) -> Result<Vec<SchemaObject>> {
+        Ok(self
+            .schema_objects()?
+            .into_iter()
+            .filter(|o| o.kind == "trigger" && o.table_name.eq_ignore_ascii_case(table_name))
+            .collect())
+    }
+
+    /// Compile a `CREATE TRIGGER` with `EXPLAIN`, as if `original` were already gone,
+    /// and roll back without creating anything
+    pub fn check_trigger(&self, original: Option<&str>, sql: &str) -> Result<()> {
+        let mut conn = self.connection.write();
+        // Dropped without a commit, so the DROP below is rolled back
+        let tx = conn.savepoint()?;
+        if let Some(original) = original {
+            tx.execute_batch(&format!("DROP TRIGGER {}", quote_identifier(original)))?;
+        }
+        tx.prepare(&format!("EXPLAIN {}", sql))?;
+        Ok(())
+    }
+
+    /// Run a `CREATE TRIGGER`, replacing `original` if given, in one transaction so a
+    /// trigger that fails to compile leaves the old one in place
+    pub fn save_trigger(&self, original: Option<&str>, sql: &str) -> Result<()> {
+        self.with_transaction(|tx| {
+            if let Some(original) = original {
+                tx.execute_batch(&format!("DROP TRIGGER {}", quote_identifier(original)))?;
+            }
+            tx.prepare(&format!("EXPLAIN {}", sql))?;
+            tx.execute_batch(sql)?;
+            Ok(())
+        })
+    }
+
+    pub fn drop_trigger(&self, name: &str) -> Result<()> {
+        self.connection
+            .write()
+            .execute_batch(&format!("DROP TRIGGER {}", quote_identifier(name)))?;
+        Ok(())
+    }
+
+    pub fn foreign_keys(&self, table_name: &str) -> Result<Vec<ForeignKey>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare("SELECT id, \"from\", \"table\", \"to\", on_delete FROM pragma_foreign_key_list(?1)")?;
+
+            let keys = stmt
+                .query_map([table_name], |row| {
+                    Ok(ForeignKey {
+                        id: row.get(0)?,
+                        column: row.get(1)?,
+                        referenced_table: row.get(2)?,
+                        referenced_column: row.get(3)?,
+                        on_delete: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
 
+            Ok(keys)
+        })
+    }
+
+    /// The `CREATE TABLE` statement SQLite recorded for `table_name`
+    pub fn table_sql(&self, table_name: &str) -> Result<String> {
+        self.read(|conn| {
+            Ok(conn.query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table_name],
+                |row| row.get(0),
+            )?)
+        })
+    }
+
+    /// Replace a table with one defined by `definition` (everything after the table
+    /// name in `CREATE TABLE`), following SQLite's documented rebuild procedure since
+    /// most schema changes can't be made in place. `columns` pairs each new column
+    /// with the old column copied into it. Indexes and triggers are recreated, views
+    /// using the table are dropped and recreated around the swap, and foreign keys are
+    /// checked before committing.
+    pub fn rebuild_table(&self, table_name: &str, definition: &str, columns: &[(String, String)]) -> Result<()> {
+        self.rebuild_table_with(table_name, definition, columns, |object| object.sql.clone())
+    }
+
+    /// [`rebuild_table`](Self::rebuild_table), recreating each dependent object from the
+    /// SQL `recreate` returns for it, e.g. with a renamed column, or leaving it out on
+    /// `None`
+    pub fn rebuild_table_with(
+        &self,
+        table_name: &str,
+        definition: &str,
+        columns: &[(String, String)],
+        recreate: impl Fn(&SchemaObject) -> Option<String>,
+    ) -> Result<()> {
+        let quoted = quote_identifier(table_name);
+        let temp_name = quote_identifier(&format!("{}__rebuild", table_name));
+        let owned = self
+            .schema_objects()?
+            .into_iter()
+            .filter(|o| o.sql.is_some())
+            .filter(|o| {
+                (o.kind != "view" && o.table_name == table_name)
+                    || (o.kind == "view" && o.sql.as_deref().is_some_and(|sql| mentions(sql, table_name)))
+            })
+            .collect::<Vec<_>>();
+
+        let new_columns = columns.iter().map(|(new, _)| quote_identifier(new)).collect::<Vec<_>>().join(", ");
+        let old_columns = columns.iter().map(|(_, old)| quote_identifier(old)).collect::<Vec<_>>().join(", ");
+
+        let mut conn = self.connection.write();
+        // `foreign_keys` can't change inside a transaction, and with it on, dropping the
+        // old table would cascade into child rows
+        if !conn.is_autocommit() {
+            return Err(anyhow!("Commit or roll back the pending edits before changing the schema of {}", table_name));
+        }
+        let foreign_keys: bool = conn.pragma_query_value(None, "foreign_keys", |row| row.get(0))?;
+        let legacy: bool = conn.pragma_query_value(None, "legacy_alter_table", |row| row.get(0))?;
+        conn.pragma_update(None, "foreign_keys", false)?;
+
+        let result = (|| -> Result<()> {
+            let tx = conn.savepoint()?;
+            for view in owned.iter().filter(|o| o.kind == "view") {
+                tx.execute_batch(&format!("DROP VIEW {};", quote_identifier(&view.name)))?;
+            }
+            // Legacy rename leaves other objects' references to the old name alone,
+            // which is what lets them find the rebuilt table
+            tx.pragma_update(None, "legacy_alter_table", true)?;
+            tx.execute_batch(&format!(
+                "CREATE TABLE {temp} {definition};\n\
+                 INSERT INTO {temp} ({new_columns}) SELECT {old_columns} FROM {table};\n\
+                 DROP TABLE {table};\n\
+                 ALTER TABLE {temp} RENAME TO {table};",
+                temp = temp_name,
+                table = quoted,
+            ))?;
+            for object in &owned {
+                if let Some(sql) = recreate(object) {
+                    tx.execute_batch(&sql)?;
+                }
+            }
+
+            let violations: i64 = tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))?;
+            if violations > 0 {
+                return Err(anyhow!("Rebuilding {} would break {} foreign key references", table_name, violations));
+            }
+            tx.commit()?;
+            Ok(())
+        })();
+
+        // Restored whatever happened; left on, later renames would stop updating references
+        let restored = conn
+            .pragma_update(None, "legacy_alter_table", legacy)
+            .and(conn.pragma_update(None, "foreign_keys", foreign_keys));
+        drop(conn);
+        result?;
+        restored?;
+
+        self.refresh_schemas()
+    }
+
+    /// Run `f` in a single transaction, committing only if it succeeds. Inside an open
+    /// transaction (transactional editing's, say) it nests as a savepoint.
+    pub fn with_transaction<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut conn = self.connection.write();
+        let tx = conn.savepoint()?;
+        let result = f(&tx)?;
+        tx.commit()?;
         Ok(result)
     }
 
+    /// Run `f`, which writes through this manager, as one transaction: batch inserts
+    /// nest inside it as savepoints, and nothing is kept if `f` fails. Under
+    /// transactional editing it joins the held transaction, to be committed with it.
+    pub fn in_transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let nested = {
+            let conn = self.connection.write();
+            self.join_held(&conn)?;
+            !conn.is_autocommit()
+        };
+        let (begin, commit, rollback) = if nested {
+            ("SAVEPOINT in_transaction", "RELEASE in_transaction", "ROLLBACK TO in_transaction; RELEASE in_transaction")
+        } else {
+            ("BEGIN IMMEDIATE", "COMMIT", "ROLLBACK")
+        };
+        let held_before = self.held.lock().len();
+        self.connection.write().execute_batch(begin)?;
+        match f() {
+            Ok(value) => {
+                self.connection.write().execute_batch(commit)?;
+                Ok(value)
+            }
+            Err(e) => {
+                if let Err(rollback) = self.connection.write().execute_batch(rollback) {
+                    tracing::error!("Failed to roll back: {}", rollback);
+                }
+                // The writes `f` held were rolled back with it
+                self.held.lock().truncate(held_before);
+                Err(e)
+            }
+        }
+    }
+
+    /// Whether a transaction is open on the connection, e.g. one a query tab began,
+    /// so writes since are not yet in the file
+    pub fn has_open_transaction(&self) -> bool {
+        !self.connection.read().is_autocommit()
+    }
+
+    /// Commit the open transaction, if there is one
+    pub fn commit_open_transaction(&self) -> Result<()> {
+        let conn = self.connection.write();
+        if !conn.is_autocommit() {
+            conn.execute_batch("COMMIT")?;
+        }
+        self.held.lock().clear();
+        Ok(())
+    }
+
+    /// Roll back the open transaction, if there is one, and reload the schemas it may have changed
+    pub fn rollback_open_transaction(&self) -> Result<()> {
+        {
+            let conn = self.connection.write();
+            self.held.lock().clear();
+            if conn.is_autocommit() {
+                return Ok(());
+            }
+            conn.execute_batch("ROLLBACK")?;
+        }
+        self.refresh_schemas()
+    }
+
+    /// Hold grid writes in one transaction until [`Self::commit_open_transaction`]
+    /// instead of committing each as it's made
+    pub fn set_transactional(&self, on: bool) {
+        self.transactional.store(on, Ordering::Relaxed);
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.transactional.load(Ordering::Relaxed)
+    }
+
+    /// The writes waiting for a commit, oldest first
+    pub fn held_changes(&self) -> Vec<HeldChange> {
+        self.sync_held();
+        self.held.lock().clone()
+    }
+
+    pub fn held_count(&self) -> usize {
+        self.sync_held();
+        self.held.lock().len()
+    }
+
+    /// Forget held writes once their transaction is gone, e.g. ended by a query tab's
+    /// own COMMIT or ROLLBACK
+    fn sync_held(&self) {
+        if self.connection.read().is_autocommit() {
+            self.held.lock().clear();
+        }
+    }
+
+    /// Begin the held transaction on the first grid write, under transactional editing;
+    /// whether the write is held
+    fn join_held(&self, conn: &Connection) -> Result<bool> {
+        if !self.is_transactional() {
+            return Ok(false);
+        }
+        if conn.is_autocommit() {
+            conn.execute_batch("BEGIN")?;
+        }
+        Ok(true)
+    }
+
+    /// Run one grid write. When it's held, `describe` reads what the write replaces
+    /// (before it runs) into the change kept for review.
+    fn execute_edit(
+        &self,
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+        describe: impl FnOnce(&Connection) -> Result<HeldChange>,
+    ) -> Result<usize> {
+        if !self.join_held(conn)? {
+            return Ok(conn.execute(sql, params)?);
+        }
+        let mut change = describe(conn)?;
+        let mut stmt = conn.prepare(sql)?;
+        let changed = stmt.execute(params)?;
+        change.sql = stmt.expanded_sql().unwrap_or_else(|| sql.to_string());
+        self.held.lock().push(change);
+        Ok(changed)
+    }
+
+    /// Undo one held change inside the transaction and drop it from the list. Refused
+    /// while a later change touches the same row, or once the transaction has ended
+    /// (a query tab's COMMIT or ROLLBACK, say), since the edit is no longer pending.
+    pub fn revert_held(&self, index: usize) -> Result<()> {
+        let conn = self.connection.write();
+        if conn.is_autocommit() {
+            self.held.lock().clear();
+            return Err(anyhow!("The pending edits were already committed or rolled back; nothing to revert"));
+        }
+        let change = self
+            .held
+            .lock()
+            .get(index)
+            .cloned()
+            .ok_or_else(|| anyhow!("No pending change {}", index + 1))?;
+        if self.held.lock()[index + 1..].iter().any(|later| later.touches(&change)) {
+            return Err(anyhow!("A later pending change touches this row; revert that one first"));
+        }
+        let (sql, params) = change
+            .revert
+            .ok_or_else(|| anyhow!("A batch import only rolls back with the whole transaction"))?;
+        conn.execute(&sql, rusqlite::params_from_iter(params.iter()))?;
+        self.held.lock().remove(index);
+        Ok(())
+    }
+
+    /// Run `f` on this connection with another database file attached read-only as
+    /// `alias`, so one statement can read from it and write here. Detached afterwards.
+    pub fn with_attached<T>(&self, path: &Path, alias: &str, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        self.with_attached_all(&[(alias, path)], f)
+    }
+
+    /// [`Self::with_attached`] for several `(alias, path)` databases at once
+    pub fn with_attached_all<T>(&self, databases: &[(&str, &Path)], f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let conn = self.connection.write();
+        let mut attached = Vec::new();
+        let result = (|| {
+            for (alias, path) in databases {
+                conn.execute(&format!("ATTACH DATABASE ?1 AS {}", quote_identifier(alias)), [read_only_uri(path)])?;
+                attached.push(*alias);
+            }
+            f(&conn)
+        })();
+        for alias in attached {
+            conn.execute(&format!("DETACH DATABASE {}", quote_identifier(alias)), [])?;
+        }
+        result
+    }
+
+    /// [`Self::with_attached`] with the other database writable, created if missing
+    pub fn with_attached_writable<T>(&self, path: &Path, alias: &str, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        if self.is_read_only() {
+            return Err(anyhow!("Database is open read-only"));
+        }
+        let conn = self.connection.write();
+        conn.execute(
+            &format!("ATTACH DATABASE ?1 AS {}", quote_identifier(alias)),
+            [path.to_string_lossy()],
+        )?;
+        let result = f(&conn);
+        conn.execute(&format!("DETACH DATABASE {}", quote_identifier(alias)), [])?;
+        result
+    }
+
+    pub fn get_row_count(&self, table_name: &str) -> Result<usize> {
+        self.read(|conn| {
+            let count: usize = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {}", quote_identifier(table_name)),
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(count)
+        })
+    }
+
+    pub fn fetch_rows(&self, table_name: &str, offset: usize, limit: usize) -> Result<Vec<RowData>> {
+        self.select_rows(table_name, "", &[], offset, limit)
+    }
+
+    /// Cheap upper bound on the row count (the largest rowid) that avoids a full COUNT(*) scan
+    pub fn estimate_row_count(&self, table_name: &str) -> Result<usize> {
+        if self.numbers_rows(table_name) {
+            return self.get_row_count(table_name);
+        }
+        self.read(|conn| {
+            let max_id: i64 = conn.query_row(
+                &format!("SELECT COALESCE(MAX({}), 0) FROM {}", self.row_id_column(table_name), quote_identifier(table_name)),
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(max_id.max(0) as usize)
+        })
+    }
+
+    /// Grid position (in rowid order) of the first row whose `column` starts with `prefix`.
+    /// Text columns seek with a range so an index on the column is used; other types
+    /// compare their text form, which has to scan.
+    pub fn first_row_with_prefix(&self, table_name: &str, column: &str, prefix: &str) -> Result<Option<usize>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        let is_text = schema
+            .fields
+            .iter()
+            .any(|f| f.name == column && matches!(f.sql_type, SqlType::Text));
+        let col = quote_identifier(column);
+
+        // Smallest string greater than every string starting with the prefix
+        let upper = prefix.chars().last().and_then(|last| {
+            char::from_u32(last as u32 + 1).map(|next| format!("{}{}", &prefix[..prefix.len() - last.len_utf8()], next))
+        });
+        let mut args: Vec<Box<dyn ToSql>> = vec![Box::new(prefix.to_string())];
+        let condition = match (is_text, upper) {
+            (true, Some(upper)) => {
+                args.push(Box::new(upper));
+                format!("{col} >= ?1 AND {col} < ?2")
+            }
+            (true, None) => format!("{col} >= ?1 AND substr({col}, 1, length(?1)) = ?1"),
+            (false, _) => format!("substr(CAST({col} AS TEXT), 1, length(?1)) = ?1"),
+        };
+        self.first_row_position(table_name, &condition, &args)
+    }
+
+    /// Grid position of the row with this primary key, read as text so column affinity
+    /// converts it to the key's type
+    pub fn row_position_by_key(&self, table_name: &str, key: &[Value]) -> Result<Option<usize>> {
+        let condition = self.key_clause(table_name, key)?;
+        let args: Vec<Box<dyn ToSql>> = key.iter().map(value_to_sql).collect();
+        self.first_row_position(table_name, &condition, &args)
+    }
+
+    pub fn row_position_by_rowid(&self, table_name: &str, rowid: i64) -> Result<Option<usize>> {
+        if self.numbers_rows(table_name) {
+            // Row ids are row numbers here
+            return Ok(usize::try_from(rowid - 1).ok());
+        }
+        let condition = format!("{} = ?1", self.row_id_column(table_name));
+        self.first_row_position(table_name, &condition, &[Box::new(rowid) as Box<dyn ToSql>])
+    }
+
+    /// Position in rowid order of the first row matching `condition`
+    fn first_row_position(&self, table_name: &str, condition: &str, args: &[Box<dyn ToSql>]) -> Result<Option<usize>> {
+        let quoted = quote_identifier(table_name);
+        if self.numbers_rows(table_name) {
+            // Number the rows first, since the condition can't see a window function
+            return self.read(|conn| {
+                let position: Option<i64> = conn
+                    .query_row(
+                        &format!(
+                            "SELECT n FROM (SELECT ROW_NUMBER() OVER () - 1 AS n, * FROM {quoted}) \
+                             WHERE {condition} ORDER BY n LIMIT 1"
+                        ),
+                        rusqlite::params_from_iter(args.iter()),
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                Ok(position.map(|p| p as usize))
+            });
+        }
+        let id = self.row_id_column(table_name);
+        self.read(|conn| {
+            let found: Option<i64> = conn
+                .query_row(
+                    &format!("SELECT {id} FROM {quoted} WHERE {condition} ORDER BY {id} LIMIT 1"),
+                    rusqlite::params_from_iter(args.iter()),
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(found) = found else {
+                return Ok(None);
+            };
+            let position: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {quoted} WHERE {id} < ?1"),
+                params![found],
+                |row| row.get(0),
+            )?;
+            Ok(Some(position as usize))
+        })
+    }
+
+    /// Pick up to `count` random row ids by probing random rowids, which stays fast on huge tables
+    pub fn sample_row_ids(&self, table_name: &str, count: usize) -> Result<Vec<i64>> {
+        let max_id = self.estimate_row_count(table_name)?;
+        if max_id == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Oversample since probes can land on deleted rowids or repeat
+        let probes = count * 2;
+        let id = self.row_id_column(table_name);
+        self.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "WITH RECURSIVE probes(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM probes WHERE n < ?2) \
+                 SELECT {id} FROM {table} WHERE {id} IN (SELECT (ABS(RANDOM()) % ?1) + 1 FROM probes) \
+                 ORDER BY {id} LIMIT ?3",
+                table = quote_identifier(table_name)
+            ))?;
+
+            let ids = stmt
+                .query_map(params![max_id, probes, count], |row| row.get(0))?
+                .collect::<Result<Vec<i64>, _>>()?;
+            Ok(ids)
+        })
+    }
+
+    pub fn fetch_rows_by_ids(&self, table_name: &str, ids: &[i64]) -> Result<Vec<RowData>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let id_params: Vec<&dyn ToSql> = ids.iter().map(|id| id as &dyn ToSql).collect();
+        self.select_rows(
+            table_name,
+            &format!(" WHERE {} IN ({})", self.row_id_column(table_name), placeholders),
+            &id_params,
+            0,
+            ids.len(),
+        )
+    }
+
+    /// Rows `offset..offset + limit` in id order. `anchor` is the grid position and id of
+    /// a row known to sit at or before `offset`; seeking from it instead of counting
+    /// through every earlier row keeps windows deep into huge tables cheap.
+    pub fn fetch_window(
+        &self,
+        table_name: &str,
+        offset: usize,
+        limit: usize,
+        anchor: Option<(usize, i64)>,
+    ) -> Result<Vec<RowData>> {
+        match anchor {
+            Some((position, id)) if position <= offset && !self.numbers_rows(table_name) => {
+                let where_clause = format!(" WHERE {} >= ?", self.row_id_column(table_name));
+                self.select_rows(table_name, &where_clause, params![id], offset - position, limit)
+            }
+            _ => self.fetch_rows(table_name, offset, limit),
+        }
+    }
+
+    /// The next `limit` rows after `after_id` in id order (keyset paging)
+    pub fn fetch_rows_after(&self, table_name: &str, after_id: i64, limit: usize) -> Result<Vec<RowData>> {
+        let where_clause = format!(" WHERE {} > ?", self.row_id_column(table_name));
+        self.select_rows(table_name, &where_clause, params![after_id], 0, limit)
+    }
+
+    /// The `limit` rows just before `before_id`, in ascending id order (keyset paging back)
+    pub fn fetch_rows_before(&self, table_name: &str, before_id: i64, limit: usize) -> Result<Vec<RowData>> {
+        let id = self.row_id_column(table_name);
+        let quoted = quote_identifier(table_name);
+        let ids = self.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {id} FROM {quoted} WHERE {id} < ?1 ORDER BY {id} DESC LIMIT ?2"
+            ))?;
+            let ids = stmt
+                .query_map(params![before_id, limit], |row| row.get(0))?
+                .collect::<Result<Vec<i64>, _>>()?;
+            Ok(ids)
+        })?;
+        self.fetch_rows_by_ids(table_name, &ids)
+    }
+
+    /// Rows where any column's text contains `search`, evaluated by SQLite instead of in memory
+    pub fn search_rows(&self, table_name: &str, search: &str, offset: usize, limit: usize) -> Result<Vec<RowData>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+
+        let (where_clause, params) = search_clause(&schema, search);
+        let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+        let refs: Vec<&dyn ToSql> = boxed.iter().map(|p| p.as_ref()).collect();
+        self.select_rows(table_name, &where_clause, &refs, offset, limit)
+    }
+
+    /// Rows ordered by `sort` (then rowid, so pages stay stable), optionally only those
+    /// matching `search` as in [`Self::search_rows`]
+    pub fn fetch_sorted_rows(
+        &self,
+        table_name: &str,
+        search: Option<&str>,
+        sort: &[SortKey],
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RowData>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        let order = order_clause(&schema, sort);
+
+        match search {
+            Some(search) => {
+                let (where_clause, params) = search_clause(&schema, search);
+                let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+                let refs: Vec<&dyn ToSql> = boxed.iter().map(|p| p.as_ref()).collect();
+                self.select_rows_ordered(table_name, &where_clause, &refs, &order, offset, limit)
+            }
+            None => self.select_rows_ordered(table_name, "", &[], &order, offset, limit),
+        }
+    }
+
+    /// Rows matching an arbitrary WHERE `condition` bound to `params`, ordered by `sort`
+    /// (then rowid)
+    pub fn fetch_where(
+        &self,
+        table_name: &str,
+        condition: &str,
+        params: &[Value],
+        sort: &[SortKey],
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RowData>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+        let refs: Vec<&dyn ToSql> = boxed.iter().map(|p| p.as_ref()).collect();
+        let where_clause = format!(" WHERE ({})", condition);
+        self.select_rows_ordered(table_name, &where_clause, &refs, &order_clause(&schema, sort), offset, limit)
+    }
+
+    /// `EXPLAIN QUERY PLAN` details of the grid's query for rows matching `condition`
+    /// in `sort` order, e.g. `SCAN items` and `USE TEMP B-TREE FOR ORDER BY`
+    pub fn grid_query_plan(
+        &self,
+        table_name: &str,
+        condition: Option<&str>,
+        params: &[Value],
+        sort: &[SortKey],
+    ) -> Result<Vec<String>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        let mut sql = format!("EXPLAIN QUERY PLAN SELECT * FROM {}", quote_identifier(table_name));
+        if let Some(condition) = condition {
+            sql.push_str(&format!(" WHERE ({})", condition));
+        }
+        sql.push_str(&format!(" ORDER BY {}", order_clause(&schema, sort)));
+        let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+        self.read(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let details = stmt
+                .query_map(rusqlite::params_from_iter(boxed.iter()), |row| row.get::<_, String>(3))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(details)
+        })
+    }
+
+    /// Compile `condition` as a WHERE clause on `table_name` without running it, so a
+    /// typo comes back as SQLite's message instead of an empty grid
+    pub fn check_condition(&self, table_name: &str, condition: &str) -> Result<()> {
+        self.read(|conn| {
+            let stmt = conn.prepare(&format!("SELECT 1 FROM {} WHERE ({})", quote_identifier(table_name), condition))?;
+            if !stmt.readonly() {
+                return Err(anyhow!("A filter can only read"));
+            }
+            if stmt.parameter_count() > 0 {
+                return Err(anyhow!("Write values inline; ? parameters aren't bound in a filter"));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn count_where(&self, table_name: &str, condition: &str, params: &[Value]) -> Result<usize> {
+        let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+        self.read(|conn| {
+            let count: usize = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE ({})", quote_identifier(table_name), condition),
+                rusqlite::params_from_iter(boxed.iter()),
+                |row| row.get(0),
+            )?;
+            Ok(count)
+        })
+    }
+
+    pub fn count_search_matches(&self, table_name: &str, search: &str) -> Result<usize> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+
+        let (where_clause, params) = search_clause(&schema, search);
+        let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+        self.read(|conn| {
+            let count: usize = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {}{}", quote_identifier(table_name), where_clause),
+                rusqlite::params_from_iter(boxed.iter()),
+                |row| row.get(0),
+            )?;
+            Ok(count)
+        })
+    }
+
+    fn select_rows(
+        &self,
+        table_name: &str,
+        where_clause: &str,
+        where_params: &[&dyn ToSql],
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RowData>> {
+        let id = self.row_id_column(table_name);
+        self.select_rows_ordered(table_name, where_clause, where_params, id, offset, limit)
+    }
+
+    fn select_rows_ordered(
+        &self,
+        table_name: &str,
+        where_clause: &str,
+        where_params: &[&dyn ToSql],
+        order_by: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RowData>> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+
+        let key_fields: Vec<usize> = schema
+            .primary_key
+            .iter()
+            .filter_map(|column| schema.fields.iter().position(|f| &f.name == column))
+            .collect();
+
+        self.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {id}, {} FROM {}{} ORDER BY {} LIMIT ? OFFSET ?",
+                schema.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(", "),
+                quote_identifier(table_name),
+                where_clause,
+                order_by,
+                id = schema.row_id_column()
+            ))?;
+
+            let mut all_params: Vec<&dyn ToSql> = where_params.to_vec();
+            all_params.push(&limit);
+            all_params.push(&offset);
+
+            let rows = stmt.query_map(all_params.as_slice(), |row| {
+                let id: i64 = row.get(0)?;
+                let mut cells = Vec::new();
+
+                for i in 1..=schema.fields.len() {
+                    cells.push(CellValue::from_row(row, i).unwrap());
+                }
+
+                let key = if key_fields.is_empty() {
+                    vec![Value::from(id)]
+                } else {
+                    key_fields.iter().map(|&i| cells[i].value.clone()).collect()
+                };
+
+                Ok(RowData { id, cells, key })
+            })?;
+
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row?);
+            }
+            activity::record_rows(result.len());
+
+            Ok(result)
+        })
+    }
+
     pub fn insert_row(&self, table_name: &str, values: Vec<Value>) -> Result<i64> {
         let schema = self
             .get_schema(table_name)
@@ -239,7 +1912,7 @@ impl DatabaseManager {
             ));
         }
 
-        let placeholders = vec!["?"; values.len()].join(", ");
+        let placeholders = self.insert_placeholders(&schema);
         let field_names = schema
             .fields
             .iter()
@@ -249,77 +1922,449 @@ impl DatabaseManager {
 
         let sql = format!(
             "INSERT INTO {} ({}) VALUES ({})",
-            table_name, field_names, placeholders
+            quote_identifier(table_name),
+            field_names,
+            placeholders.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", ")
         );
 
         let conn = self.connection.write();
         let params: Vec<Box<dyn ToSql>> = values
             .iter()
-            .map(|v| {
-                let param: Box<dyn ToSql> = match v {
-                    Value::Null => Box::new(None::<String>),
-                    Value::Bool(b) => Box::new(*b as i32),
-                    Value::Number(n) => {
-                        if let Some(i) = n.as_i64() {
-                            Box::new(i)
-                        } else if let Some(f) = n.as_f64() {
-                            Box::new(f)
-                        } else {
-                            Box::new(None::<String>)
-                        }
-                    }
-                    Value::String(s) => Box::new(s.clone()),
-                    _ => Box::new(v.to_string()),
-                };
-                param
-            })
+            .zip(&placeholders)
+            .filter(|(_, (_, binds))| *binds)
+            .map(|(value, _)| value_to_sql(value))
             .collect();
 
-        conn.execute(&sql, rusqlite::params_from_iter(params.iter()))?;
-        Ok(conn.last_insert_rowid())
+        // Undone by rowid, which is only known once the row is in, or by key
+        let held = self.join_held(&conn)?;
+        let mut stmt = conn.prepare(&sql)?;
+        stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+        let row_id = conn.last_insert_rowid();
+        if held {
+            let key: Vec<Value> = schema
+                .key_columns()
+                .iter()
+                .filter_map(|key| schema.fields.iter().position(|f| f.name == *key))
+                .map(|ix| values[ix].clone())
+                .collect();
+            let key = if key.is_empty() || key.iter().any(Value::is_null) { vec![Value::from(row_id)] } else { key };
+            // A WITHOUT ROWID table has no rowid to delete by, but its key is never NULL
+            let revert = if schema.without_rowid {
+                (
+                    format!("DELETE FROM {} WHERE {}", quote_identifier(table_name), self.key_clause(table_name, &key)?),
+                    key.iter().map(json_to_sql_value).collect(),
+                )
+            } else {
+                (format!("DELETE FROM {} WHERE rowid = ?", quote_identifier(table_name)), vec![SqlValue::Integer(row_id)])
+            };
+            self.held.lock().push(HeldChange {
+                table: table_name.to_string(),
+                key,
+                column: None,
+                old: None,
+                new: Some(values.iter().map(value_label).collect::<Vec<_>>().join(", ")),
+                sql: stmt.expanded_sql().unwrap_or_else(|| sql.clone()),
+                revert: Some(revert),
+            });
+        }
+        Ok(row_id)
+    }
+
+    /// Insert many rows in one transaction using multi-row INSERT statements
+    pub fn insert_rows(&self, table_name: &str, rows: &[Vec<Value>]) -> Result<usize> {
+        self.insert_rows_with(table_name, rows, "")
+    }
+
+    /// Batched `INSERT ... ON CONFLICT(key) DO UPDATE`, overwriting `update_columns` on rows
+    /// whose key already exists. The key must be covered by a PRIMARY KEY or UNIQUE constraint.
+    pub fn insert_rows_on_conflict(
+        &self,
+        table_name: &str,
+        key_columns: &[String],
+        update_columns: &[String],
+        rows: &[Vec<Value>],
+    ) -> Result<usize> {
+        if key_columns.is_empty() {
+            return Err(anyhow!("ON CONFLICT needs at least one key column"));
+        }
+
+        let mut assignments = update_columns
+            .iter()
+            .filter(|c| !key_columns.contains(c))
+            .map(|c| format!("{0} = excluded.{0}", c))
+            .collect::<Vec<_>>();
+        if !assignments.is_empty() {
+            let written: Vec<&str> = update_columns.iter().map(String::as_str).collect();
+            assignments.extend(self.update_stamps(table_name, &written).0);
+        }
+        let action = if assignments.is_empty() {
+            "DO NOTHING".to_string()
+        } else {
+            format!("DO UPDATE SET {}", assignments.join(", "))
+        };
+
+        let clause = format!(" ON CONFLICT({}) {}", key_columns.join(", "), action);
+        self.insert_rows_with(table_name, rows, &clause)
     }
 
+    /// Multi-row INSERT in one transaction, with `conflict_clause` appended to each statement
+    fn insert_rows_with(&self, table_name: &str, rows: &[Vec<Value>], conflict_clause: &str) -> Result<usize> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+
+        let field_count = schema.fields.len();
+        if field_count == 0 {
+            return Err(anyhow!("Table '{}' has no columns to insert into", table_name));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != field_count) {
+            return Err(anyhow!(
+                "Value count mismatch: expected {}, got {}",
+                field_count,
+                row.len()
+            ));
+        }
+
+        let batch_rows = INSERT_BATCH_ROWS.min(MAX_SQL_VARIABLES / field_count).max(1);
+        let field_names = schema
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = self.insert_placeholders(&schema);
+        let row_placeholders = format!(
+            "({})",
+            placeholders.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", ")
+        );
+
+        // A savepoint, so the batch joins an enclosing `in_transaction` when there is one
+        let mut conn = self.connection.write();
+        let held = self.join_held(&conn)?;
+        let tx = conn.savepoint()?;
+
+        for batch in rows.chunks(batch_rows) {
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES {}{}",
+                quote_identifier(table_name),
+                field_names,
+                vec![row_placeholders.as_str(); batch.len()].join(", "),
+                conflict_clause
+            );
+
+            // Every full batch shares the same SQL, so the prepared statement is reused
+            let mut stmt = tx.prepare_cached(&sql)?;
+            let params: Vec<Box<dyn ToSql>> = batch
+                .iter()
+                .flat_map(|row| row.iter().zip(&placeholders))
+                .filter(|(_, (_, binds))| *binds)
+                .map(|(value, _)| value_to_sql(value))
+                .collect();
+            stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+        }
+
+        tx.commit()?;
+        if held {
+            // The bound batches would be as large as the data, so only their shape is kept
+            self.held.lock().push(HeldChange {
+                table: table_name.to_string(),
+                key: Vec::new(),
+                column: None,
+                old: None,
+                new: Some(format!("{} rows", rows.len())),
+                sql: format!("INSERT INTO {} ({}) VALUES …{}", quote_identifier(table_name), field_names, conflict_clause),
+                revert: None,
+            });
+        }
+        Ok(rows.len())
+    }
+
+    /// Update rows matching `key_columns`, inserting those with no match. Only `update_columns`
+    /// are written on matched rows, so columns missing from the source keep their data.
+    /// `rows` hold values for every schema field in order. Returns `(inserted, updated)`.
+    pub fn upsert_rows(
+        &self,
+        table_name: &str,
+        key_columns: &[String],
+        update_columns: &[String],
+        rows: &[Vec<Value>],
+    ) -> Result<(usize, usize)> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        if key_columns.is_empty() {
+            return Err(anyhow!("Upsert needs at least one key column"));
+        }
+
+        let field_count = schema.fields.len();
+        if let Some(row) = rows.iter().find(|row| row.len() != field_count) {
+            return Err(anyhow!(
+                "Value count mismatch: expected {}, got {}",
+                field_count,
+                row.len()
+            ));
+        }
+
+        let index_of = |name: &String| -> Result<usize> {
+            schema
+                .fields
+                .iter()
+                .position(|f| &f.name == name)
+                .ok_or_else(|| anyhow!("Column '{}' not found in table '{}'", name, table_name))
+        };
+        let key_idx = key_columns.iter().map(index_of).collect::<Result<Vec<_>>>()?;
+        let update_idx = update_columns
+            .iter()
+            .filter(|c| !key_columns.contains(c))
+            .map(index_of)
+            .collect::<Result<Vec<_>>>()?;
+
+        // With nothing to update, a no-op assignment still reports whether the key matched
+        let set_clause = if update_idx.is_empty() {
+            format!("{0} = {0}", key_columns[0])
+        } else {
+            let written: Vec<&str> = update_idx.iter().map(|&i| schema.fields[i].name.as_str()).collect();
+            update_idx
+                .iter()
+                .map(|&i| format!("{} = ?", schema.fields[i].name))
+                .chain(self.update_stamps(table_name, &written).0)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let placeholders = self.insert_placeholders(&schema);
+        let where_clause = key_columns
+            .iter()
+            .map(|k| format!("{} = ?", k))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let field_names = schema
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Joins the held transaction like batch inserts do, rather than committing on its own
+        let mut conn = self.connection.write();
+        let held = self.join_held(&conn)?;
+        let tx = conn.savepoint()?;
+        let (mut inserted, mut updated) = (0, 0);
+        {
+            let mut update = tx.prepare(&format!(
+                "UPDATE {} SET {} WHERE {}",
+                quote_identifier(table_name), set_clause, where_clause
+            ))?;
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote_identifier(table_name),
+                field_names,
+                placeholders.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", ")
+            ))?;
+
+            for row in rows {
+                let params: Vec<Box<dyn ToSql>> = update_idx
+                    .iter()
+                    .chain(&key_idx)
+                    .map(|&i| value_to_sql(&row[i]))
+                    .collect();
+
+                if update.execute(rusqlite::params_from_iter(params.iter()))? > 0 {
+                    updated += 1;
+                } else {
+                    let params: Vec<Box<dyn ToSql>> = row
+                        .iter()
+                        .zip(&placeholders)
+                        .filter(|(_, (_, binds))| *binds)
+                        .map(|(value, _)| value_to_sql(value))
+                        .collect();
+                    insert.execute(rusqlite::params_from_iter(params.iter()))?;
+                    inserted += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        if held {
+            self.held.lock().push(HeldChange {
+                table: table_name.to_string(),
+                key: Vec::new(),
+                column: None,
+                old: None,
+                new: Some(format!("{} inserted, {} updated", inserted, updated)),
+                sql: format!("UPDATE {0} SET … / INSERT INTO {0} ({1}) VALUES …", table_name, field_names),
+                revert: None,
+            });
+        }
+
+        Ok((inserted, updated))
+    }
+
+    /// `key` holds the row's values for every key column, as in [`RowData::key`]
     pub fn update_cell(
         &self,
         table_name: &str,
-        row_id: i64,
+        key: &[Value],
         field_name: &str,
         value: Value,
     ) -> Result<()> {
+        let (stamps, stamped) = self.update_stamps(table_name, &[field_name]);
+        let mut assignments = vec![format!("{} = ?", field_name)];
+        assignments.extend(stamps);
         let sql = format!(
-            "UPDATE {} SET {} = ? WHERE id = ?",
-            table_name, field_name
+            "UPDATE {} SET {} WHERE {}",
+            quote_identifier(table_name), assignments.join(", "), self.key_clause(table_name, key)?
         );
 
-        let param: Box<dyn ToSql> = match value {
-            Value::Null => Box::new(None::<String>),
-            Value::Bool(b) => Box::new(b as i32),
-            Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    Box::new(i)
-                } else if let Some(f) = n.as_f64() {
-                    Box::new(f)
-                } else {
-                    Box::new(None::<String>)
-                }
-            }
-            Value::String(s) => Box::new(s),
-            _ => Box::new(value.to_string()),
-        };
+        let mut params = vec![value_to_sql(&value)];
+        params.extend(key.iter().map(value_to_sql));
 
         let conn = self.connection.write();
-        conn.execute(&sql, params![&param, row_id])?;
+        self.execute_edit(&conn, &sql, rusqlite::params_from_iter(params.iter()), |conn| {
+            let old = self.read_cell(conn, table_name, key, field_name)?;
+            let restore = self.read_stamps(conn, table_name, key, &stamped)?;
+            self.cell_change(table_name, key, field_name, old, value_label(&value), restore)
+        })?;
         Ok(())
     }
 
-    pub fn delete_row(&self, table_name: &str, row_id: i64) -> Result<()> {
-        let sql = format!("DELETE FROM {} WHERE id = ?", table_name);
+    /// Write raw bytes to a cell, which [`Self::update_cell`]'s JSON values can't carry
+    pub fn update_blob(&self, table_name: &str, key: &[Value], field_name: &str, bytes: &[u8]) -> Result<()> {
+        let (stamps, stamped) = self.update_stamps(table_name, &[field_name]);
+        let mut assignments = vec![format!("{} = ?", field_name)];
+        assignments.extend(stamps);
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            quote_identifier(table_name), assignments.join(", "), self.key_clause(table_name, key)?
+        );
+
+        let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(bytes.to_vec())];
+        params.extend(key.iter().map(value_to_sql));
+
         let conn = self.connection.write();
-        conn.execute(&sql, params![row_id])?;
+        self.execute_edit(&conn, &sql, rusqlite::params_from_iter(params.iter()), |conn| {
+            let old = self.read_cell(conn, table_name, key, field_name)?;
+            let restore = self.read_stamps(conn, table_name, key, &stamped)?;
+            let new = sql_value_label(&SqlValue::Blob(bytes.to_vec()));
+            self.cell_change(table_name, key, field_name, old, new, restore)
+        })?;
         Ok(())
     }
 
+    pub fn delete_row(&self, table_name: &str, key: &[Value]) -> Result<()> {
+        let sql = format!("DELETE FROM {} WHERE {}", quote_identifier(table_name), self.key_clause(table_name, key)?);
+        let params: Vec<Box<dyn ToSql>> = key.iter().map(value_to_sql).collect();
+        let conn = self.connection.write();
+        self.execute_edit(&conn, &sql, rusqlite::params_from_iter(params.iter()), |conn| {
+            // The whole row goes back in, key columns included
+            let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE {}", quote_identifier(table_name), self.key_clause(table_name, key)?))?;
+            let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+            let params: Vec<Box<dyn ToSql>> = key.iter().map(value_to_sql).collect();
+            let row: Vec<SqlValue> = stmt
+                .query_row(rusqlite::params_from_iter(params.iter()), |row| {
+                    (0..columns.len()).map(|ix| row.get::<_, SqlValue>(ix)).collect()
+                })
+                .optional()?
+                .unwrap_or_default();
+            let revert = (!row.is_empty()).then(|| {
+                (
+                    format!(
+                        "INSERT INTO {} ({}) VALUES ({})",
+                        quote_identifier(table_name),
+                        columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+                        vec!["?"; columns.len()].join(", ")
+                    ),
+                    row.clone(),
+                )
+            });
+            Ok(HeldChange {
+                table: table_name.to_string(),
+                key: key.to_vec(),
+                column: None,
+                old: Some(row.iter().map(sql_value_label).collect::<Vec<_>>().join(", ")),
+                new: None,
+                sql: String::new(),
+                revert,
+            })
+        })?;
+        Ok(())
+    }
+
+    /// A cell's current value, read before a held update overwrites it
+    fn read_cell(&self, conn: &Connection, table_name: &str, key: &[Value], field_name: &str) -> Result<SqlValue> {
+        let params: Vec<Box<dyn ToSql>> = key.iter().map(value_to_sql).collect();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM {} WHERE {}", field_name, quote_identifier(table_name), self.key_clause(table_name, key)?),
+                rusqlite::params_from_iter(params.iter()),
+                |row| row.get::<_, SqlValue>(0),
+            )
+            .optional()?
+            .unwrap_or(SqlValue::Null))
+    }
+
+    /// The current values of the `columns` an update is about to stamp
+    fn read_stamps(&self, conn: &Connection, table_name: &str, key: &[Value], columns: &[String]) -> Result<Vec<(String, SqlValue)>> {
+        columns
+            .iter()
+            .map(|column| Ok((column.clone(), self.read_cell(conn, table_name, key, &quote_identifier(column))?)))
+            .collect()
+    }
+
+    /// A held update of one cell from `old`, undone by writing `old` back along with the
+    /// provenance stamps in `restore`
+    fn cell_change(
+        &self,
+        table_name: &str,
+        key: &[Value],
+        field_name: &str,
+        old: SqlValue,
+        new: String,
+        restore: Vec<(String, SqlValue)>,
+    ) -> Result<HeldChange> {
+        let mut assignments = vec![format!("{} = ?", field_name)];
+        assignments.extend(restore.iter().map(|(column, _)| format!("{} = ?", quote_identifier(column))));
+        let mut params = vec![old.clone()];
+        params.extend(restore.into_iter().map(|(_, value)| value));
+        params.extend(key.iter().map(json_to_sql_value));
+        Ok(HeldChange {
+            table: table_name.to_string(),
+            key: key.to_vec(),
+            column: Some(field_name.to_string()),
+            old: Some(sql_value_label(&old)),
+            new: Some(new),
+            sql: String::new(),
+            revert: Some((
+                format!("UPDATE {} SET {} WHERE {}", quote_identifier(table_name), assignments.join(", "), self.key_clause(table_name, key)?),
+                params,
+            )),
+        })
+    }
+
+    /// `a = ? AND b = ?` over the table's key columns
+    fn key_clause(&self, table_name: &str, key: &[Value]) -> Result<String> {
+        let columns = self
+            .get_schema(table_name)
+            .map(|schema| schema.key_columns().into_iter().map(String::from).collect::<Vec<_>>())
+            .unwrap_or_else(|| vec!["id".to_string()]);
+        if columns.len() != key.len() {
+            return Err(anyhow!(
+                "Row key for {} has {} values, expected {} ({})",
+                table_name,
+                key.len(),
+                columns.len(),
+                columns.join(", ")
+            ));
+        }
+        Ok(columns.iter().map(|c| format!("{} = ?", c)).collect::<Vec<_>>().join(" AND "))
+    }
+
+    fn row_id_column(&self, table_name: &str) -> &'static str {
+        self.get_schema(table_name)
+            .map(|schema| schema.row_id_column())
+            .unwrap_or("id")
+    }
+
     pub fn execute_query(&self, sql: &str) -> Result<Vec<Vec<CellValue>>> {
+        // Arbitrary SQL may write, so it must not be replayed on reconnect
         let conn = self.connection.read();
         let mut stmt = conn.prepare(sql)?;
         let column_count = stmt.column_count();
@@ -336,48 +2381,390 @@ impl DatabaseManager {
         for row in rows {
             result.push(row?);
         }
+        activity::record_rows(result.len());
 
         Ok(result)
     }
 
-    pub fn get_foreign_key_options(&self, table_name: &str) -> Result<Vec<(i64, String)>> {
+    /// Run a query keeping at most `max_rows` rows, for display in a results grid
+    pub fn fetch_query(&self, sql: &str, max_rows: usize) -> Result<QueryRows> {
         let conn = self.connection.read();
-        let mut stmt = conn.prepare(&format!(
-            "SELECT id, * FROM {} ORDER BY id",
-            table_name
-        ))?;
+        fetch_statement(&conn, sql, max_rows)
+    }
 
-        let column_count = stmt.column_count();
-        let rows = stmt.query_map([], |row| {
-            let id: i64 = row.get(0)?;
-            let mut display_parts = Vec::new();
-
-            for i in 1..column_count {
-                if let Ok(value) = CellValue::from_row(row, i) {
-                    if value.display != "NULL" {
-                        display_parts.push(value.display);
-                        if display_parts.len() >= 3 {
-                            break;
+    /// Run a read-only query on a worker thread with its own read-only connection, so a
+    /// slow SELECT doesn't hold up the UI. Rows come back in batches of at most
+    /// `max_rows` in total; dropping the receiver stops the worker at its next batch,
+    /// and cancelling stops it mid-statement. `None` when the query has to run here
+    /// instead: in-memory databases, and statements that write.
+    pub fn spawn_query(&self, sql: &str, max_rows: usize) -> Result<Option<RunningQuery>> {
+        if self.path.is_none() || !self.is_read_only_statement(sql)? {
+            return Ok(None);
+        }
+        let conn = self.open_read_only_connection()?;
+        let cancel = CancelHandle(Arc::new(conn.get_interrupt_handle()));
+        let sql = sql.to_string();
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+
+        std::thread::Builder::new()
+            .name("table-editor-query".to_string())
+            .spawn(move || {
+                let event = match send_statement(&conn, &sql, max_rows, &tx) {
+                    Ok(()) => return,
+                    Err(e) if is_interrupted(&e) => QueryEvent::Interrupted,
+                    Err(e) => QueryEvent::Failed(e.to_string()),
+                };
+                let _ = tx.unbounded_send(event);
+            })?;
+        Ok(Some(RunningQuery { events: rx, cancel }))
+    }
+
+    /// Cancels whatever statement this manager's own connection is running
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle(Arc::new(self.connection.read().get_interrupt_handle()))
+    }
+
+    /// Stream every row of a read-only query into `sink`, returning the number of rows written
+    pub fn stream_query(&self, sql: &str, sink: &mut dyn RowSink) -> Result<usize> {
+        let conn = self.connection.read();
+        stream_statement(&conn, sql, sink)
+    }
+
+    /// `scope` as a WHERE condition on `table_name` and its parameters
+    pub fn scope_condition(&self, table_name: &str, scope: &RowScope) -> Result<(String, Vec<Value>)> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        Ok(match scope {
+            RowScope::All => ("1".to_string(), Vec::new()),
+            RowScope::Matching(search) => search_condition(&schema, search),
+            RowScope::Where { condition, params } => (format!("({})", condition), params.clone()),
+            RowScope::Ids(_) if !schema.has_row_ids() => {
+                return Err(anyhow!("{} has no row ids to pick selected rows by; filter them instead", table_name));
+            }
+            RowScope::Ids(ids) => (
+                format!("{} IN ({})", schema.row_id_column(), vec!["?"; ids.len()].join(", ")),
+                ids.iter().map(|id| Value::from(*id)).collect(),
+            ),
+        })
+    }
+
+    /// Stream the rows of a table covered by `scope`, in id order
+    pub fn stream_table(&self, table_name: &str, scope: &RowScope, sink: &mut dyn RowSink) -> Result<usize> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+        let (condition, params) = self.scope_condition(table_name, scope)?;
+        let conn = self.connection.read();
+
+        match scope {
+            RowScope::All => stream_statement(
+                &conn,
+                &format!("SELECT * FROM {} ORDER BY {}", quote_identifier(table_name), schema.row_id_column()),
+                sink,
+            ),
+            RowScope::Matching(_) | RowScope::Where { .. } => {
+                let boxed: Vec<Box<dyn ToSql>> = params.iter().map(value_to_sql).collect();
+                let refs: Vec<&dyn ToSql> = boxed.iter().map(|p| p.as_ref()).collect();
+                stream_statement_with(
+                    &conn,
+                    &format!("SELECT * FROM {} WHERE {} ORDER BY {}", quote_identifier(table_name), condition, schema.row_id_column()),
+                    &refs,
+                    sink,
+                )
+            }
+            RowScope::Ids(ids) => {
+                let placeholders = vec!["?"; ids.len()].join(", ");
+                let id_params: Vec<&dyn ToSql> = ids.iter().map(|id| id as &dyn ToSql).collect();
+                stream_statement_with(
+                    &conn,
+                    &format!("SELECT * FROM {table} WHERE {id} IN ({}) ORDER BY {id}", placeholders, table = quote_identifier(table_name), id = schema.row_id_column()),
+                    &id_params,
+                    sink,
+                )
+            }
+        }
+    }
+
+    pub fn get_foreign_key_options(&self, table_name: &str) -> Result<Vec<(i64, String)>> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, * FROM {} ORDER BY id",
+                quote_identifier(table_name)
+            ))?;
+
+            let column_count = stmt.column_count();
+            let rows = stmt.query_map([], |row| {
+                let id: i64 = row.get(0)?;
+                let mut display_parts = Vec::new();
+
+                for i in 1..column_count {
+                    if let Ok(value) = CellValue::from_row(row, i) {
+                        if value.display != "NULL" {
+                            display_parts.push(value.display);
+                            if display_parts.len() >= 3 {
+                                break;
+                            }
                         }
                     }
                 }
+
+                let display = if display_parts.is_empty() {
+                    format!("ID: {}", id)
+                } else {
+                    display_parts.join(" - ")
+                };
+
+                Ok((id, display))
+            })?;
+
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row?);
             }
 
-            let display = if display_parts.is_empty() {
-                format!("ID: {}", id)
-            } else {
-                display_parts.join(" - ")
-            };
+            Ok(result)
+        })
+    }
+}
 
-            Ok((id, display))
-        })?;
+/// Run a query on an arbitrary connection keeping at most `max_rows` rows
+pub fn fetch_statement(conn: &Connection, sql: &str, max_rows: usize) -> Result<QueryRows> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns = column_names(&stmt);
+    let column_count = columns.len();
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
+    let mut result = QueryRows {
+        columns,
+        ..Default::default()
+    };
+
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        if result.rows.len() >= max_rows {
+            result.truncated = true;
+            break;
         }
 
-        Ok(result)
+        let cells = (0..column_count)
+            .map(|i| CellValue::from_row(row, i))
+            .collect::<Result<Vec<_>>>()?;
+        result.rows.push(cells);
+    }
+    activity::record_rows(result.rows.len());
+
+    Ok(result)
+}
+
+fn is_interrupted(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<rusqlite::Error>()
+        .and_then(|e| e.sqlite_error_code())
+        == Some(rusqlite::ErrorCode::OperationInterrupted)
+}
+
+/// Send a query's rows over `tx` in batches, stopping early once nobody is listening
+fn send_statement(conn: &Connection, sql: &str, max_rows: usize, tx: &UnboundedSender<QueryEvent>) -> Result<()> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns = column_names(&stmt);
+    let column_count = columns.len();
+    tx.unbounded_send(QueryEvent::Columns(columns))?;
+
+    let mut sent = 0;
+    let mut truncated = false;
+    let mut batch = Vec::with_capacity(STREAM_BATCH_ROWS);
+    let mut rows = stmt.query([])?;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(e) => {
+                // Rows read before an interrupt are still worth showing
+                activity::record_rows(sent + batch.len());
+                if !batch.is_empty() {
+                    tx.unbounded_send(QueryEvent::Rows(batch))?;
+                }
+                return Err(e.into());
+            }
+        };
+        if sent + batch.len() >= max_rows {
+            truncated = true;
+            break;
+        }
+        batch.push(
+            (0..column_count)
+                .map(|i| CellValue::from_row(row, i))
+                .collect::<Result<Vec<_>>>()?,
+        );
+        if batch.len() == STREAM_BATCH_ROWS {
+            sent += batch.len();
+            tx.unbounded_send(QueryEvent::Rows(std::mem::take(&mut batch)))?;
+        }
+    }
+    sent += batch.len();
+    if !batch.is_empty() {
+        tx.unbounded_send(QueryEvent::Rows(batch))?;
+    }
+    activity::record_rows(sent);
+
+    tx.unbounded_send(QueryEvent::Done { truncated })?;
+    Ok(())
+}
+
+/// Stream a read-only query on an arbitrary connection, such as a worker's read-only one
+pub fn stream_statement(conn: &Connection, sql: &str, sink: &mut dyn RowSink) -> Result<usize> {
+    stream_statement_with(conn, sql, &[], sink)
+}
+
+fn stream_statement_with(
+    conn: &Connection,
+    sql: &str,
+    params: &[&dyn ToSql],
+    sink: &mut dyn RowSink,
+) -> Result<usize> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        return Err(anyhow!("Only read-only statements can be streamed"));
+    }
+
+    let columns = column_names(&stmt);
+    let column_count = columns.len();
+    sink.begin(&columns)?;
+
+    let mut count = 0;
+    let mut rows = stmt.query(params)?;
+    while let Some(row) = rows.next()? {
+        let cells = (0..column_count)
+            .map(|i| CellValue::from_row(row, i))
+            .collect::<Result<Vec<_>>>()?;
+        sink.row(&cells)?;
+        count += 1;
+    }
+    activity::record_rows(count);
+
+    sink.finish()?;
+    Ok(count)
+}
+
+/// URI opening `path` read-only, for ATTACH
+fn read_only_uri(path: &Path) -> String {
+    format!(
+        "file:{}?mode=ro",
+        path.to_string_lossy()
+            .replace('\\', "/")
+            .replace('%', "%25")
+            .replace('?', "%3f")
+            .replace('#', "%23")
+    )
+}
+
+fn read_only_flags() -> OpenFlags {
+    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI
+}
+
+/// Safe-mode settings for a connection to an untrusted file. Extension loading is
+/// never enabled on any connection, so `load_extension()` is already unavailable.
+fn harden(conn: &Connection) -> Result<()> {
+    use rusqlite::config::DbConfig;
+    use rusqlite::limits::Limit;
+
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DEFENSIVE, true)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_ENABLE_TRIGGER, false)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_ENABLE_VIEW, false)?;
+    // Functions with side effects can't be called from the schema (defaults, indexes, generated columns)
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_TRUSTED_SCHEMA, false)?;
+    conn.set_limit(Limit::SQLITE_LIMIT_LENGTH, crate::quarantine::MAX_VALUE_BYTES);
+    Ok(())
+}
+
+/// `NULL`, `42` or the text itself, for showing a JSON value in a change list
+fn value_label(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn sql_value_label(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Null => "NULL".to_string(),
+        SqlValue::Integer(i) => i.to_string(),
+        SqlValue::Real(f) => f.to_string(),
+        SqlValue::Text(s) => s.clone(),
+        SqlValue::Blob(bytes) => format!("<{} blob>", format_bytes(bytes.len())),
+    }
+}
+
+/// [`value_to_sql`] as an owned value, for statements kept to run later
+fn json_to_sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .or_else(|| n.as_f64().map(SqlValue::Real))
+            .unwrap_or(SqlValue::Null),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+pub(crate) fn value_to_sql(value: &Value) -> Box<dyn ToSql> {
+    match value {
+        Value::Null => Box::new(None::<String>),
+        Value::Bool(b) => Box::new(*b as i32),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Box::new(i)
+            } else if let Some(f) = n.as_f64() {
+                Box::new(f)
+            } else {
+                Box::new(None::<String>)
+            }
+        }
+        Value::String(s) => Box::new(s.clone()),
+        _ => Box::new(value.to_string()),
+    }
+}
+
+fn column_names(stmt: &rusqlite::Statement) -> Vec<String> {
+    stmt.column_names().into_iter().map(String::from).collect()
+}
+
+/// `"a" ASC, "b" DESC, id`; the rowid last keeps ties in a stable order across pages
+fn order_clause(schema: &TypeSchema, sort: &[SortKey]) -> String {
+    let mut order: Vec<String> = sort
+        .iter()
+        .map(|key| format!("{} {}", quote_identifier(&key.column), if key.descending { "DESC" } else { "ASC" }))
+        .collect();
+    order.push(schema.row_id_column().to_string());
+    order.join(", ")
+}
+
+/// Rows where any column's text contains `search`; the grid, exports and archiving all
+/// match through this so a search selects the same rows everywhere
+fn search_condition(schema: &TypeSchema, search: &str) -> (String, Vec<Value>) {
+    let columns: Vec<String> = schema.fields.iter().map(|f| f.name.clone()).collect();
+    crate::row_filter::search_condition(&columns, search)
+}
+
+fn search_clause(schema: &TypeSchema, search: &str) -> (String, Vec<Value>) {
+    let (condition, params) = search_condition(schema, search);
+    (format!(" WHERE {}", condition), params)
+}
+
+/// Errors that indicate the underlying file handle is unusable rather than a bad query
+fn is_connection_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(e, _)) => matches!(
+            e.code,
+            rusqlite::ErrorCode::CannotOpen
+                | rusqlite::ErrorCode::SystemIoFailure
+                | rusqlite::ErrorCode::NotADatabase
+        ),
+        _ => false,
     }
 }
 
@@ -386,6 +2773,17 @@ impl Clone for DatabaseManager {
         Self {
             connection: self.connection.clone(),
             schemas: self.schemas.clone(),
+            path: self.path.clone(),
+            read_only: self.read_only,
+            quarantined: self.quarantined,
+            frozen: self.frozen.clone(),
+            last_health_check: self.last_health_check.clone(),
+            profile: self.profile.clone(),
+            provenance: self.provenance.clone(),
+            transactional: self.transactional.clone(),
+            held: self.held.clone(),
+            lost_edits: self.lost_edits.clone(),
+            disk: self.disk.clone(),
         }
     }
 }
@@ -0,0 +1,181 @@
+//! Which schema objects depend on which: tables read by views, tables triggers fire
+//! on and write to, indexes and foreign keys. Used to show the blast radius of a
+//! schema change before it's made.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use crate::database::DatabaseManager;
+use crate::sql_text::{words, Word};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    /// A view or trigger selects from the table
+    Reads,
+    /// A trigger inserts into, updates or deletes from the table
+    Writes,
+    /// A trigger is attached to the table
+    FiresOn,
+    Indexes,
+    /// A foreign key points at the table
+    References,
+}
+
+impl DependencyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DependencyKind::Reads => "reads",
+            DependencyKind::Writes => "writes",
+            DependencyKind::FiresOn => "fires on",
+            DependencyKind::Indexes => "indexes",
+            DependencyKind::References => "references",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SchemaNode {
+    /// `table`, `view`, `trigger` or `index`
+    pub kind: String,
+    pub name: String,
+}
+
+/// `from` depends on `to`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    pub from: String,
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    pub nodes: Vec<SchemaNode>,
+    pub edges: Vec<Dependency>,
+}
+
+impl DependencyGraph {
+    pub fn build(db: &DatabaseManager) -> Result<Self> {
+        let tables = db.list_tables()?;
+        let objects = db.schema_objects()?;
+
+        let mut nodes: Vec<SchemaNode> = tables
+            .iter()
+            .map(|name| SchemaNode {
+                kind: "table".to_string(),
+                name: name.clone(),
+            })
+            .collect();
+        nodes.extend(objects.iter().map(|o| SchemaNode {
+            kind: o.kind.clone(),
+            name: o.name.clone(),
+        }));
+
+        // Only tables and views can be read from or written to
+        let relations: Vec<&str> = nodes
+            .iter()
+            .filter(|n| n.kind == "table" || n.kind == "view")
+            .map(|n| n.name.as_str())
+            .collect();
+        let resolve = |word: &Word| {
+            relations
+                .iter()
+                .find(|r| if word.quoted { **r == word.text } else { r.eq_ignore_ascii_case(&word.text) })
+                .map(|r| r.to_string())
+        };
+
+        let mut edges = Vec::new();
+        for table in &tables {
+            for key in db.foreign_keys(table)? {
+                edges.push(Dependency {
+                    from: table.clone(),
+                    to: key.referenced_table,
+                    kind: DependencyKind::References,
+                });
+            }
+        }
+
+        for object in &objects {
+            match object.kind.as_str() {
+                "index" => edges.push(Dependency {
+                    from: object.name.clone(),
+                    to: object.table_name.clone(),
+                    kind: DependencyKind::Indexes,
+                }),
+                "trigger" => edges.push(Dependency {
+                    from: object.name.clone(),
+                    to: object.table_name.clone(),
+                    kind: DependencyKind::FiresOn,
+                }),
+                _ => {}
+            }
+
+            let Some(sql) = &object.sql else {
+                continue;
+            };
+            let words = words(sql);
+            for (i, word) in words.iter().enumerate() {
+                let Some(relation) = resolve(word) else {
+                    continue;
+                };
+                if relation == object.name {
+                    continue;
+                }
+                let before = |n: usize| i.checked_sub(n).and_then(|j| words.get(j));
+                let after_keyword = |n: usize, keyword: &str| before(n).is_some_and(|w| w.is_keyword(keyword));
+
+                let kind = match object.kind.as_str() {
+                    // The trigger's own `ON table` is already a FiresOn edge
+                    "trigger" if after_keyword(1, "ON") && relation == object.table_name => continue,
+                    "trigger" if after_keyword(1, "INTO")
+                        || after_keyword(1, "UPDATE")
+                        || (after_keyword(1, "FROM") && after_keyword(2, "DELETE")) =>
+                    {
+                        DependencyKind::Writes
+                    }
+                    "trigger" | "view" => DependencyKind::Reads,
+                    _ => continue,
+                };
+                edges.push(Dependency {
+                    from: object.name.clone(),
+                    to: relation,
+                    kind,
+                });
+            }
+        }
+
+        let mut seen = HashSet::new();
+        edges.retain(|edge| seen.insert(edge.clone()));
+
+        Ok(Self { nodes, edges })
+    }
+
+    pub fn node(&self, name: &str) -> Option<&SchemaNode> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
+    /// What `name` needs in order to work
+    pub fn depends_on(&self, name: &str) -> Vec<&Dependency> {
+        self.edges.iter().filter(|e| e.from == name).collect()
+    }
+
+    /// What would be affected by changing `name`
+    pub fn dependents(&self, name: &str) -> Vec<&Dependency> {
+        self.edges.iter().filter(|e| e.to == name).collect()
+    }
+
+    /// Everything affected by changing `name`, directly or through other objects,
+    /// nearest first
+    pub fn transitive_dependents(&self, name: &str) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        let mut frontier = vec![name.to_string()];
+        while let Some(current) = frontier.pop() {
+            for edge in self.dependents(&current) {
+                if edge.from != name && !found.contains(&edge.from) {
+                    found.push(edge.from.clone());
+                    frontier.insert(0, edge.from.clone());
+                }
+            }
+        }
+        found
+    }
+}
@@ -3,16 +3,46 @@ use ui::{
     h_flex, v_flex, button::{Button, ButtonVariants}, label::Label, divider::Divider,
     table::Table, ActiveTheme, Sizable, StyledExt, Disableable,
     dock::{Panel, PanelEvent, DockChannel}, IconName, Icon,
+    input::{TextInput, InputState},
 };
 use crate::{
-    database::DatabaseManager,
+    archive,
+    branding::{ApplicationRegistry, parse_pragma_int},
+    change_capture::{self, CapturedChange},
+    codegen::{self, CodegenConfig, LookupEnum},
+    create_table_wizard::CreateTableWizard,
+    data_diff::{self, KeyedTableDiff},
+    database::{DatabaseManager, ForeignKey, RowScope, DISK_POLL_INTERVAL},
+    dependencies::DependencyGraph,
+    export::{CsvExportOptions, ExportKind},
+    import_wizard::CsvImportWizard,
+    interop::RecordPayload,
+    metadata_bundle::{BundleMerge, MetadataBundle},
+    migration::MigrationAssistant,
+    partition::{self, PartitionPlan},
+    presence::{self, EditorPresence, PRESENCE_HEARTBEAT},
+    project_query,
+    provenance::{Provenance, ProvenanceConfig},
     table_view::DataTableView,
     query_editor::QueryEditorView,
-    reflection::TypeSchema,
-    workspace_panels::{TablePanelWrapper, QueryPanelWrapper, WelcomePanelWrapper},
+    refactor::{self, ColumnChange, DropPlan, ForeignKeyDefinition, RenamePlan, RenameTarget, RowIdScheme, FOREIGN_KEY_ACTIONS},
+    reflection::{InputPattern, Lineage, NumericRange, ReflectionProfile, TypeSchema},
+    retention::{self, RetentionConfig, RetentionPolicy},
+    save_hooks::{SaveHooks, SavePhase},
+    schema_diff::{self, ObjectChange, SchemaDiff},
+    provider::{self, DataProvider},
+    settings::{KeyBindingMode, LayoutPreset, RowDensity, UserSettings},
+    trigger_editor::TriggerEditor,
+    view_editor::{ViewDefinition, ViewEditor},
+    workspace_panels::{ErDiagramEvent, ErDiagramPanel, IndexManagerPanel, TablePanelWrapper, QueryPanelWrapper, WelcomePanelWrapper},
 };
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Held changes listed in the review panel; the rest are counted
+const HELD_CHANGES_SHOWN: usize = 200;
 
 #[derive(Clone, Debug)]
 pub enum DataTableEvent {
@@ -28,6 +58,46 @@ enum TabType {
     Query { name: String, view: Entity<QueryEditorView> },
 }
 
+/// Primary key and UNIQUE edits for one table, applied together in a single rebuild
+#[derive(Clone, Debug)]
+struct KeyDraft {
+    table: String,
+    primary_key: Vec<String>,
+    unique: Vec<Vec<String>>,
+    /// Columns picked for a UNIQUE constraint not yet added
+    new_unique: Vec<String>,
+}
+
+/// Which edit the alter section's buttons make to the typed column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlterKind {
+    Add,
+    Rename,
+    Retype,
+    Drop,
+}
+
+/// A column being dragged in the sidebar's column list
+#[derive(Clone)]
+struct DraggedColumn {
+    table: String,
+    index: usize,
+    name: String,
+}
+
+impl Render for DraggedColumn {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .text_xs()
+            .bg(cx.theme().accent)
+            .text_color(cx.theme().accent_foreground)
+            .child(self.name.clone())
+    }
+}
+
 struct EditorTab {
     id: usize,
     tab_type: TabType,
@@ -36,6 +106,7 @@ struct EditorTab {
 pub struct DataTableEditor {
     pub db: DatabaseManager,
     available_tables: Vec<String>,
+    available_views: Vec<String>,
     open_tabs: Vec<EditorTab>,
     active_tab_idx: Option<usize>,
     next_tab_id: usize,
@@ -47,11 +118,183 @@ pub struct DataTableEditor {
     workspace_initialized: bool,
     /// Track which databases are expanded (using database name as key)
     expanded_databases: HashMap<String, bool>,
+    /// Project-registered application IDs used to label the database
+    app_registry: ApplicationRegistry,
+    /// Cached label such as "Pulsar Quest DB v12"
+    db_label: String,
+    application_id_input: Entity<InputState>,
+    user_version_input: Entity<InputState>,
+    /// `table` or `table.column` to rename
+    rename_from_input: Entity<InputState>,
+    rename_to_input: Entity<InputState>,
+    /// Previewed rename, applied only after the user has seen its diff
+    rename_plan: Option<RenamePlan>,
+    rename_message: Option<String>,
+    /// Column to rename, retype or drop
+    alter_column_input: Entity<InputState>,
+    /// New name, type, or a whole definition to add
+    alter_value_input: Entity<InputState>,
+    /// Table and column of a drop waiting for its confirming click
+    alter_drop_pending: Option<(String, String)>,
+    alter_message: Option<String>,
+    /// Trigger whose body is shown in the trigger list
+    trigger_shown: Option<String>,
+    /// Trigger awaiting a second click on Drop
+    trigger_drop_pending: Option<String>,
+    trigger_message: Option<String>,
+    /// Columns of the active table a new foreign key is on, comma-separated
+    fk_columns_input: Entity<InputState>,
+    /// `parent` or `parent(columns)` the new key references
+    fk_parent_input: Entity<InputState>,
+    /// Index into `FOREIGN_KEY_ACTIONS` for the new key's `ON DELETE`
+    fk_on_delete: usize,
+    /// Foreign key id awaiting a second click on Drop
+    fk_drop_pending: Option<i64>,
+    fk_message: Option<String>,
+    partition_column_input: Entity<InputState>,
+    partition_view_input: Entity<InputState>,
+    /// Split or merge script waiting for review
+    partition_plan: Option<PartitionPlan>,
+    partition_message: Option<String>,
+    /// Age limits per table, from the `retention` sidecar
+    retention: RetentionConfig,
+    /// Timestamp column and age in days for the active table's policy
+    retention_column_input: Entity<InputState>,
+    retention_days_input: Entity<InputState>,
+    retention_message: Option<String>,
+    archive_message: Option<String>,
+    dependencies: DependencyGraph,
+    /// Object shown in the dependencies section; the active table when unset
+    dependency_focus: Option<String>,
+    /// Reviewed before running; cleared when the focus changes
+    drop_plan: Option<DropPlan>,
+    drop_cascade: bool,
+    key_draft: Option<KeyDraft>,
+    sequence_input: Entity<InputState>,
+    /// Primary key or `#rowid` for "Go to row"
+    goto_input: Entity<InputState>,
+    /// Rowid the keyset page jumps to
+    page_jump_input: Entity<InputState>,
+    /// Capture triggers are installed; may be left over from an earlier session
+    recording_changes: bool,
+    /// Timeline of the current or last recording, oldest first
+    captured_changes: Vec<CapturedChange>,
+    /// Leftover triggers found on open, or tables a recording had to skip
+    recording_message: Option<String>,
+    show_activity: bool,
+    settings: UserSettings,
+    /// Serves this database to other tools' widgets; registered while the editor is open
+    /// and polled with the disk check, so writes from the game reach them too
+    data_provider: Option<std::rc::Rc<DataProvider>>,
+    /// Script to replay, typed as a path
+    script_path_input: Entity<InputState>,
+    script_message: Option<String>,
+    /// Shown in place of the tabs while open
+    migration: Option<Entity<MigrationAssistant>>,
+    csv_import: Option<Entity<CsvImportWizard>>,
+    create_table: Option<Entity<CreateTableWizard>>,
+    view_editor: Option<Entity<ViewEditor>>,
+    trigger_editor: Option<Entity<TriggerEditor>>,
+    /// Docked next to the tabs once opened from the table browser
+    index_panel: Option<Entity<IndexManagerPanel>>,
+    er_panel: Option<Entity<ErDiagramPanel>>,
+    /// Opens the tables clicked in the ER diagram
+    _er_subscription: Option<Subscription>,
+    /// Delimiter, quoting, header and NULL options for table exports
+    csv_export: CsvExportOptions,
+    export_kind: ExportKind,
+    /// Column to keep row hashes in; blank for the shadow table
+    hash_column_input: Entity<InputState>,
+    /// Other database to compare hashes against
+    hash_compare_input: Entity<InputState>,
+    /// Database file whose schema is compared with this one
+    schema_compare_input: Entity<InputState>,
+    schema_diff: Option<SchemaDiff>,
+    schema_diff_message: Option<String>,
+    /// Script turning the compared file's schema into this one, shown for review
+    migration_script: Option<String>,
+    /// "Run on Other" was clicked once and waits for the confirming click
+    migration_run_pending: bool,
+    /// Database file the active table's rows are compared with and synced to
+    sync_target_input: Entity<InputState>,
+    table_sync: Option<KeyedTableDiff>,
+    /// "Sync to Target" was clicked once and waits for the confirming click
+    sync_pending: bool,
+    sync_message: Option<String>,
+    hash_message: Option<String>,
+    bundle_path_input: Entity<InputState>,
+    /// Imported bundle waiting for its conflicts to be resolved
+    bundle_merge: Option<BundleMerge>,
+    bundle_message: Option<String>,
+    /// Last table usage analysis, shown in the sidebar
+    usage_report: Option<crate::usage::UsageReport>,
+    /// Column of the active table to annotate with its lineage
+    lineage_column_input: Entity<InputState>,
+    /// Comma-separated source columns, or `pipeline:<name>`
+    lineage_source_input: Entity<InputState>,
+    lineage_message: Option<String>,
+    /// Column of the active table to restrict to a pattern
+    pattern_column_input: Entity<InputState>,
+    /// Regex, or `mask:` and a mask, edits to the column must fit
+    pattern_input: Entity<InputState>,
+    /// `min..max step n` for a numeric column's spinner and slider
+    range_input: Entity<InputState>,
+    pattern_message: Option<String>,
+    /// Lookup tables designated for enum generation
+    codegen: CodegenConfig,
+    /// Why each designated table's generated files are out of date
+    codegen_drift: BTreeMap<String, String>,
+    codegen_message: Option<String>,
+    /// The project's SQL to run before and after each save
+    save_hooks: SaveHooks,
+    /// Why the database opened in safe mode, until the user trusts it
+    quarantine_reason: Option<String>,
+    /// Why the last save stopped for confirmation, with each changed table's summary
+    save_review: Option<(String, Vec<(String, usize, String)>)>,
+    /// Set by Save Anyway so the next save skips the threshold check
+    save_confirmed: bool,
+    /// Whether anything is unsaved, shared with the host's editor instance, which asks
+    /// without a context to read the tabs through
+    dirty: Arc<AtomicBool>,
+    /// Keep `dirty` current as the open tabs change
+    _tab_observers: Vec<Subscription>,
+    /// What the last reload would have discarded, shown until confirmed or cancelled
+    reload_review: Option<String>,
+    /// Set by Discard and Reload so the next reload goes ahead
+    reload_confirmed: bool,
+    /// Statements held by transactional editing when the toolbar badge last rendered
+    held_count: usize,
+    rollback_pending: bool,
+    transaction_message: Option<String>,
+    /// The list of held changes is open under the toolbar
+    show_held_changes: bool,
+    provenance: ProvenanceConfig,
+    identity_input: Entity<InputState>,
+    provenance_message: Option<String>,
+    /// Another process wrote to the file since the data was loaded
+    disk_changed: bool,
+    _disk_watch: Option<Task<()>>,
+    /// This editor's entry in the shared presence sidecar
+    presence_session: String,
+    /// Tables this editor last announced it's editing
+    presence_editing: Vec<String>,
+    /// Other editors with the same database open
+    co_editors: Vec<EditorPresence>,
+    _presence_heartbeat: Option<Task<()>>,
+    _presence_release: Option<Subscription>,
+    /// The original database, when this editor has a throwaway copy of it open
+    throwaway_of: Option<PathBuf>,
+    /// Deletes the throwaway copy once the editor using it is gone; last, so the
+    /// editor's connection has closed by then
+    _throwaway_file: Option<ThrowawayFile>,
 }
 
 impl DataTableEditor {
     pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         let db = DatabaseManager::in_memory().expect("Failed to create in-memory database");
+        let settings = UserSettings::load();
+        db.set_transactional(settings.transactional_editing);
+        let identity_input = identity_input(&settings, window, cx);
 
         // Create internal workspace for table/query tabs
         let workspace = cx.new(|cx| {
@@ -63,9 +306,13 @@ impl DataTableEditor {
             )
         });
 
+        let app_registry = ApplicationRegistry::default();
+        let db_label = app_registry.label(0, 0);
+
         Self {
             db,
             available_tables: Vec::new(),
+            available_views: Vec::new(),
             open_tabs: Vec::new(),
             active_tab_idx: None,
             next_tab_id: 0,
@@ -74,16 +321,169 @@ impl DataTableEditor {
             workspace: Some(workspace),
             workspace_initialized: false,
             expanded_databases: HashMap::new(),
+            app_registry,
+            db_label,
+            application_id_input: pragma_input(0, window, cx),
+            user_version_input: pragma_input(0, window, cx),
+            rename_from_input: name_input(window, cx),
+            rename_to_input: name_input(window, cx),
+            rename_plan: None,
+            rename_message: None,
+            alter_column_input: name_input(window, cx),
+            alter_value_input: name_input(window, cx),
+            alter_drop_pending: None,
+            alter_message: None,
+            trigger_shown: None,
+            trigger_drop_pending: None,
+            trigger_message: None,
+            fk_columns_input: name_input(window, cx),
+            fk_parent_input: name_input(window, cx),
+            fk_on_delete: 0,
+            fk_drop_pending: None,
+            fk_message: None,
+            partition_column_input: name_input(window, cx),
+            partition_view_input: name_input(window, cx),
+            partition_plan: None,
+            partition_message: None,
+            retention: RetentionConfig::default(),
+            retention_column_input: name_input(window, cx),
+            retention_days_input: name_input(window, cx),
+            retention_message: None,
+            archive_message: None,
+            dependencies: DependencyGraph::default(),
+            dependency_focus: None,
+            drop_plan: None,
+            drop_cascade: false,
+            key_draft: None,
+            sequence_input: name_input(window, cx),
+            goto_input: name_input(window, cx),
+            page_jump_input: name_input(window, cx),
+            recording_changes: false,
+            captured_changes: Vec::new(),
+            recording_message: None,
+            show_activity: false,
+            settings,
+            data_provider: None,
+            script_path_input: name_input(window, cx),
+            script_message: None,
+            migration: None,
+            csv_import: None,
+            create_table: None,
+            view_editor: None,
+            trigger_editor: None,
+            index_panel: None,
+            er_panel: None,
+            _er_subscription: None,
+            csv_export: CsvExportOptions::default(),
+            export_kind: ExportKind::Csv,
+            hash_column_input: name_input(window, cx),
+            hash_compare_input: name_input(window, cx),
+            schema_compare_input: name_input(window, cx),
+            schema_diff: None,
+            schema_diff_message: None,
+            migration_script: None,
+            migration_run_pending: false,
+            sync_target_input: name_input(window, cx),
+            table_sync: None,
+            sync_pending: false,
+            sync_message: None,
+            hash_message: None,
+            bundle_path_input: name_input(window, cx),
+            bundle_merge: None,
+            bundle_message: None,
+            usage_report: None,
+            lineage_column_input: name_input(window, cx),
+            lineage_source_input: name_input(window, cx),
+            lineage_message: None,
+            pattern_column_input: name_input(window, cx),
+            pattern_input: name_input(window, cx),
+            range_input: name_input(window, cx),
+            pattern_message: None,
+            codegen: CodegenConfig::default(),
+            codegen_drift: BTreeMap::new(),
+            codegen_message: None,
+            save_hooks: SaveHooks::default(),
+            quarantine_reason: None,
+            save_review: None,
+            save_confirmed: false,
+            dirty: Arc::new(AtomicBool::new(false)),
+            _tab_observers: Vec::new(),
+            reload_review: None,
+            reload_confirmed: false,
+            held_count: 0,
+            rollback_pending: false,
+            transaction_message: None,
+            show_held_changes: false,
+            identity_input,
+            provenance: ProvenanceConfig::default(),
+            provenance_message: None,
+            disk_changed: false,
+            _disk_watch: None,
+            presence_session: uuid::Uuid::new_v4().to_string(),
+            presence_editing: Vec::new(),
+            co_editors: Vec::new(),
+            _presence_heartbeat: None,
+            _presence_release: None,
+            throwaway_of: None,
+            _throwaway_file: None,
         }
     }
 
     pub fn open_database(path: PathBuf, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<Self> {
-        let db = DatabaseManager::new(&path)?;
+        let profile = crate::sidecar::load::<ReflectionProfile>(&path, "profile")
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to load reflection profile: {}", e);
+                None
+            })
+            .unwrap_or_default();
+
+        // Files from untrusted sources open in safe mode; save games are inspected, not edited
+        let settings = UserSettings::load();
+        let quarantine_reason = crate::quarantine::reason(&path, &settings);
+        let db = if let Some(reason) = &quarantine_reason {
+            tracing::warn!("Opening {:?} in safe mode: {}", path, reason);
+            DatabaseManager::open_quarantined(&path)?
+        } else if profile.save_game {
+            DatabaseManager::open_read_only(&path)?
+        } else {
+            DatabaseManager::new(&path)?
+        };
+        db.set_profile(profile);
+        db.set_transactional(settings.transactional_editing);
+        let identity_input = identity_input(&settings, window, cx);
         
         // Auto-discover schemas from existing tables
         db.introspect_and_register_schemas()?;
         
         let available_tables = db.list_tables()?;
+        let available_views = db.list_views()?;
+
+        let mut codegen = CodegenConfig::load(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to load enum generation settings: {}", e);
+            CodegenConfig::default()
+        });
+        let codegen_drift = codegen::check_all(&db, &mut codegen, &project_query::project_root(&path), false);
+
+        let save_hooks = SaveHooks::discover(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to load save hooks: {}", e);
+            SaveHooks::default()
+        });
+
+        let retention = RetentionConfig::load(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to load retention policies: {}", e);
+            RetentionConfig::default()
+        });
+        let expired = retention::run_on_open(&db, &retention);
+        let retention_message = (!expired.is_empty()).then(|| expired.join("\n"));
+
+        let provenance = ProvenanceConfig::load(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to load provenance settings: {}", e);
+            ProvenanceConfig::default()
+        });
+        db.set_provenance(provenance.enabled.then(|| Provenance {
+            config: provenance.clone(),
+            identity: settings.identity(),
+        }));
 
         // Create internal workspace for table/query tabs
         let workspace = cx.new(|cx| {
@@ -101,9 +501,45 @@ impl DataTableEditor {
             expanded_databases.insert(db_name.to_string(), true);
         }
 
+        let app_registry = ApplicationRegistry::discover(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to load application registry: {}", e);
+            ApplicationRegistry::default()
+        });
+        let application_id = db.application_id()?;
+        let user_version = db.user_version()?;
+        let db_label = app_registry.label(application_id, user_version);
+        let recording_changes = change_capture::is_recording(&db);
+        // Either another editor is recording or one crashed and left its triggers behind
+        let recording_message = recording_changes.then(|| {
+            "Change capture triggers are already installed and logging every write; \
+             stop recording to remove them if no other editor is recording"
+                .to_string()
+        });
+        let dependencies = DependencyGraph::build(&db).unwrap_or_else(|e| {
+            tracing::error!("Failed to build dependency graph: {}", e);
+            DependencyGraph::default()
+        });
+        let data_provider = DataProvider::new(db.clone());
+        provider::register(&path, &data_provider);
+
+        let disk_watch = cx.spawn(async move |editor, cx| loop {
+            cx.background_executor().timer(DISK_POLL_INTERVAL).await;
+            if editor.update(cx, |editor, cx| editor.check_disk(cx)).is_err() {
+                break;
+            }
+        });
+        let presence_heartbeat = cx.spawn(async move |editor, cx| loop {
+            if editor.update(cx, |editor, cx| editor.heartbeat_presence(cx)).is_err() {
+                break;
+            }
+            cx.background_executor().timer(PRESENCE_HEARTBEAT).await;
+        });
+        let presence_release = cx.on_release(|editor, _| editor.leave_presence());
+
         Ok(Self {
             db,
             available_tables,
+            available_views,
             open_tabs: Vec::new(),
             active_tab_idx: None,
             next_tab_id: 0,
@@ -112,430 +548,4884 @@ impl DataTableEditor {
             workspace: Some(workspace),
             workspace_initialized: false,
             expanded_databases,
+            app_registry,
+            db_label,
+            application_id_input: pragma_input(application_id, window, cx),
+            user_version_input: pragma_input(user_version, window, cx),
+            rename_from_input: name_input(window, cx),
+            rename_to_input: name_input(window, cx),
+            rename_plan: None,
+            rename_message: None,
+            alter_column_input: name_input(window, cx),
+            alter_value_input: name_input(window, cx),
+            alter_drop_pending: None,
+            alter_message: None,
+            trigger_shown: None,
+            trigger_drop_pending: None,
+            trigger_message: None,
+            fk_columns_input: name_input(window, cx),
+            fk_parent_input: name_input(window, cx),
+            fk_on_delete: 0,
+            fk_drop_pending: None,
+            fk_message: None,
+            partition_column_input: name_input(window, cx),
+            partition_view_input: name_input(window, cx),
+            partition_plan: None,
+            partition_message: None,
+            retention,
+            retention_column_input: name_input(window, cx),
+            retention_days_input: name_input(window, cx),
+            retention_message,
+            archive_message: None,
+            dependencies,
+            dependency_focus: None,
+            drop_plan: None,
+            drop_cascade: false,
+            key_draft: None,
+            sequence_input: name_input(window, cx),
+            goto_input: name_input(window, cx),
+            page_jump_input: name_input(window, cx),
+            recording_changes,
+            captured_changes: Vec::new(),
+            recording_message,
+            show_activity: false,
+            settings,
+            data_provider: Some(data_provider),
+            script_path_input: name_input(window, cx),
+            script_message: None,
+            migration: None,
+            csv_import: None,
+            create_table: None,
+            view_editor: None,
+            trigger_editor: None,
+            index_panel: None,
+            er_panel: None,
+            _er_subscription: None,
+            csv_export: CsvExportOptions::default(),
+            export_kind: ExportKind::Csv,
+            hash_column_input: name_input(window, cx),
+            hash_compare_input: name_input(window, cx),
+            schema_compare_input: name_input(window, cx),
+            schema_diff: None,
+            schema_diff_message: None,
+            migration_script: None,
+            migration_run_pending: false,
+            sync_target_input: name_input(window, cx),
+            table_sync: None,
+            sync_pending: false,
+            sync_message: None,
+            hash_message: None,
+            bundle_path_input: name_input(window, cx),
+            bundle_merge: None,
+            bundle_message: None,
+            usage_report: None,
+            lineage_column_input: name_input(window, cx),
+            lineage_source_input: name_input(window, cx),
+            lineage_message: None,
+            pattern_column_input: name_input(window, cx),
+            pattern_input: name_input(window, cx),
+            range_input: name_input(window, cx),
+            pattern_message: None,
+            codegen,
+            codegen_drift,
+            codegen_message: None,
+            save_hooks,
+            quarantine_reason,
+            save_review: None,
+            save_confirmed: false,
+            dirty: Arc::new(AtomicBool::new(false)),
+            _tab_observers: Vec::new(),
+            reload_review: None,
+            reload_confirmed: false,
+            held_count: 0,
+            rollback_pending: false,
+            transaction_message: None,
+            show_held_changes: false,
+            identity_input,
+            provenance,
+            provenance_message: None,
+            disk_changed: false,
+            _disk_watch: Some(disk_watch),
+            presence_session: uuid::Uuid::new_v4().to_string(),
+            presence_editing: Vec::new(),
+            co_editors: Vec::new(),
+            _presence_heartbeat: Some(presence_heartbeat),
+            _presence_release: Some(presence_release),
+            throwaway_of: None,
+            _throwaway_file: None,
         })
     }
 
-    pub fn register_type_schema(&mut self, schema: TypeSchema) -> anyhow::Result<()> {
-        self.db.register_type(schema)?;
-        self.available_tables = self.db.list_tables()?;
+    /// Write the application_id / user_version typed in the info panel
+    pub fn apply_database_info(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let application_id = parse_pragma_int(&self.application_id_input.read(cx).value().to_string())?;
+        let user_version = parse_pragma_int(&self.user_version_input.read(cx).value().to_string())?;
+
+        self.db.set_application_id(application_id)?;
+        self.db.set_user_version(user_version)?;
+        self.db_label = self.app_registry.label(application_id, user_version);
         Ok(())
     }
 
-    /// Add pending tabs to the first available TabPanel
-    fn add_pending_tabs_to_workspace(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.open_tabs.is_empty() || !self.workspace_initialized {
-            return;
-        }
-        
-        // Find the first TabPanel in the workspace and add new tabs to it
-        if let Some(workspace) = self.workspace.clone() {
-            let num_existing_panels = self.open_tabs.len() - 1; // All but the last one
-            
-            // Get the last tab that was just added
-            if let Some(last_tab) = self.open_tabs.last() {
-                let panel: std::sync::Arc<dyn ui::dock::PanelView> = match &last_tab.tab_type {
-                    TabType::Table { name, view } => {
-                        let panel = cx.new(|cx| {
-                            TablePanelWrapper::new(name.clone(), view.clone(), cx)
-                        });
-                        std::sync::Arc::new(panel)
-                    }
-                    TabType::Query { name, view } => {
-                        let panel = cx.new(|cx| {
-                            QueryPanelWrapper::new(name.clone(), view.clone(), cx)
-                        });
-                        std::sync::Arc::new(panel)
-                    }
-                };
-                
-                // Defer adding the panel to avoid reentrant updates
-                window.defer(cx, move |window, cx| {
-                    _ = workspace.update(cx, |workspace, cx| {
-                        let dock_area = workspace.dock_area();
-                        
-                        // Get the first TabPanel from the center items
-                        if let Some(tab_panel) = dock_area.read(cx).items().left_top_tab_panel(cx) {
-                            _ = tab_panel.update(cx, |tab_panel, cx| {
-                                tab_panel.add_panel(panel, window, cx);
-                            });
-                        }
-                    });
-                });
-            }
-        }
-    }
+    /// Work out what renaming the typed table or column would change, without applying it
+    pub fn preview_rename(&mut self, cx: &mut Context<Self>) {
+        let from = self.rename_from_input.read(cx).value().to_string();
+        let to = self.rename_to_input.read(cx).value().to_string();
 
-    /// Initialize/reinitialize workspace with current tabs
-    fn initialize_workspace_once(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.workspace_initialized {
-            return;
-        }
-        
-        if let Some(ref workspace) = self.workspace {
-            workspace.update(cx, |workspace, cx| {
-                let dock_area = workspace.dock_area().downgrade();
-                
-                if self.open_tabs.is_empty() {
-                    // Show welcome panel when no tabs
-                    let welcome_panel = cx.new(|cx| {
-                        WelcomePanelWrapper::new(cx)
-                    });
-                    
-                    workspace.initialize(
-                        ui::dock::DockItem::tabs(
-                            vec![std::sync::Arc::new(welcome_panel) as std::sync::Arc<dyn ui::dock::PanelView>],
-                            Some(0),
-                            &dock_area,
-                            window,
-                            cx,
-                        ),
-                        None,
-                        None,
-                        None,
-                        window,
-                        cx,
-                    );
-                } else {
-                    // Create panels for all open tabs
-                    let tab_panels: Vec<std::sync::Arc<dyn ui::dock::PanelView>> = self.open_tabs
-                        .iter()
-                        .map(|tab| {
-                            match &tab.tab_type {
-                                TabType::Table { name, view } => {
-                                    let panel = cx.new(|cx| {
-                                        TablePanelWrapper::new(name.clone(), view.clone(), cx)
-                                    });
-                                    std::sync::Arc::new(panel) as std::sync::Arc<dyn ui::dock::PanelView>
-                                }
-                                TabType::Query { name, view } => {
-                                    let panel = cx.new(|cx| {
-                                        QueryPanelWrapper::new(name.clone(), view.clone(), cx)
-                                    });
-                                    std::sync::Arc::new(panel) as std::sync::Arc<dyn ui::dock::PanelView>
-                                }
-                            }
-                        })
-                        .collect();
-                    
-                    workspace.initialize(
-                        ui::dock::DockItem::tabs(
-                            tab_panels,
-                            self.active_tab_idx,
-                            &dock_area,
-                            window,
-                            cx,
-                        ),
-                        None,
-                        None,
-                        None,
-                        window,
-                        cx,
-                    );
-                }
-            });
-            
-            self.workspace_initialized = true;
+        match RenameTarget::parse(&from, &to).and_then(|target| refactor::plan_rename(&self.db, target)) {
+            Ok(plan) => {
+                self.rename_message = Some(plan.preview());
+                self.rename_plan = Some(plan);
+            }
+            Err(e) => {
+                self.rename_message = Some(format!("Error: {}", e));
+                self.rename_plan = None;
+            }
         }
     }
 
-    pub fn select_table(&mut self, table_name: String, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
-        // Check if table is already open
-        if let Some(idx) = self.open_tabs.iter().position(|tab| {
-            matches!(&tab.tab_type, TabType::Table { name, .. } if name == &table_name)
-        }) {
-            self.active_tab_idx = Some(idx);
-            cx.notify();
+    pub fn apply_rename(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(plan) = self.rename_plan.take() else {
             return Ok(());
-        }
-        
-        // Check if schema exists for this table
-        if self.db.get_schema(&table_name).is_none() {
-            return Err(anyhow::anyhow!(
-                "No schema registered for table '{}'", table_name
-            ));
-        }
-        
-        // Create new tab
-        let delegate = DataTableView::new(self.db.clone(), table_name.clone())?;
-        let table_view = cx.new(|cx| {
-            let mut table = Table::new(delegate, window, cx);
-            table.col_fixed = true;
-            table.col_resizable = true;
-            table.sortable = true;
-            table
-        });
-        
-        let tab_type = TabType::Table { 
-            name: table_name.clone(), 
-            view: table_view 
         };
-        
-        let tab = EditorTab {
-            id: self.next_tab_id,
-            tab_type: tab_type.clone(),
-        };
-        
-        self.next_tab_id += 1;
-        self.open_tabs.push(tab);
-        self.active_tab_idx = Some(self.open_tabs.len() - 1);
-        
-        // Add the new tab to the workspace efficiently
-        self.add_pending_tabs_to_workspace(window, cx);
-        
-        cx.emit(DataTableEvent::TableOpened(table_name));
-        cx.notify();
-        
+        // The input may have changed since the preview was shown
+        let from = self.rename_from_input.read(cx).value().to_string();
+        let to = self.rename_to_input.read(cx).value().to_string();
+        if RenameTarget::parse(&from, &to).ok().as_ref() != Some(&plan.target) {
+            self.rename_message = Some("Rename changed since preview; preview it again".to_string());
+            return Ok(());
+        }
+
+        let recreated = refactor::apply_rename(&self.db, &plan)?;
+        self.refresh_table_list()?;
+        self.refresh_dependencies();
+        self.rename_message = Some(format!(
+            "✓ {} ({} dependent objects recreated, {} saved queries updated)",
+            plan.statement,
+            recreated,
+            plan.queries.len()
+        ));
+        tracing::info!("✓ {}", plan.statement);
         Ok(())
     }
-    
-    pub fn open_query_tab(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let query_view = cx.new(|cx| QueryEditorView::new(self.db.clone(), window, cx));
-        
-        let tab_type = TabType::Query {
-            name: format!("Query {}", self.next_tab_id),
-            view: query_view,
-        };
-        
-        let tab = EditorTab {
-            id: self.next_tab_id,
-            tab_type: tab_type.clone(),
+
+    /// Plan splitting the active table by the typed column, for review
+    pub fn preview_split(&mut self, cx: &mut Context<Self>) {
+        let Some(table) = self.active_table_name() else {
+            return;
         };
-        
-        self.next_tab_id += 1;
-        self.open_tabs.push(tab);
-        self.active_tab_idx = Some(self.open_tabs.len() - 1);
-        
-        // Add the new tab to the workspace efficiently
-        self.add_pending_tabs_to_workspace(window, cx);
-        
-        cx.notify();
+        let column = self.partition_column_input.read(cx).value().trim().to_string();
+        self.set_partition_plan(partition::plan_split(&self.db, &table, &column));
     }
-    
-    pub fn close_tab(&mut self, tab_idx: usize, cx: &mut Context<Self>) {
-        if tab_idx < self.open_tabs.len() {
-            let tab = self.open_tabs.remove(tab_idx);
-            cx.emit(DataTableEvent::TableClosed(tab.id));
-            
-            // Adjust active tab
-            if self.open_tabs.is_empty() {
-                self.active_tab_idx = None;
-            } else if let Some(active) = self.active_tab_idx {
-                if active >= self.open_tabs.len() {
-                    self.active_tab_idx = Some(self.open_tabs.len() - 1);
-                }
-            }
-            cx.notify();
-        }
+
+    /// Plan merging the tables behind the typed view into one table, for review
+    pub fn preview_merge(&mut self, cx: &mut Context<Self>) {
+        let view = self.partition_view_input.read(cx).value().trim().to_string();
+        self.set_partition_plan(partition::plan_merge(&self.db, &view));
     }
 
-    pub fn add_new_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, name, .. } = &tab.tab_type {
-                    view.update(cx, |table, cx| {
-                        if let Err(e) = table.delegate_mut().add_new_row() {
-                            tracing::error!("Failed to add row: {}", e);
-                        } else {
-                            cx.notify();
-                        }
-                    });
-                }
+    fn set_partition_plan(&mut self, plan: anyhow::Result<PartitionPlan>) {
+        match plan {
+            Ok(plan) => {
+                self.partition_message = Some(format!("{}\n{}", plan.describe(), plan.script));
+                self.partition_plan = Some(plan);
+            }
+            Err(e) => {
+                self.partition_message = Some(format!("Error: {}", e));
+                self.partition_plan = None;
             }
         }
-        Ok(())
     }
 
-    pub fn delete_selected_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, name, .. } = &tab.tab_type {
-                    view.update(cx, |table, cx| {
-                        let delegate = table.delegate_mut();
-                        if let Some(selected_row) = delegate.state.selected_row {
-                            if let Err(e) = delegate.delete_row(selected_row) {
-                                tracing::error!("Failed to delete row: {}", e);
-                            } else {
-                                delegate.state.selected_row = None;
-                                cx.notify();
-                            }
-                        }
-                    });
-                }
-            }
-        }
+    pub fn apply_partition(&mut self) -> anyhow::Result<()> {
+        let Some(plan) = self.partition_plan.take() else {
+            return Ok(());
+        };
+        partition::apply(&self.db, &plan)?;
+        self.refresh_table_list()?;
+        self.refresh_dependencies();
+        self.partition_message = Some(format!(
+            "✓ {} {} ({} tables)",
+            match plan.direction {
+                partition::PartitionDirection::Split => "Split",
+                partition::PartitionDirection::Merge => "Merged",
+            },
+            plan.name,
+            plan.partitions.len()
+        ));
+        tracing::info!("✓ Repartitioned {} ({} tables)", plan.name, plan.partitions.len());
         Ok(())
     }
 
-    pub fn refresh_data(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, .. } = &tab.tab_type {
-                    view.update(cx, |table, cx| {
-                        let delegate = table.delegate_mut();
-                        if let Err(e) = delegate.refresh_rows(0, 1000) {
-                            tracing::error!("Failed to refresh rows: {}", e);
-                        }
-                        cx.notify();
-                    });
-                }
+    /// Set the active table's age limit from the inputs, keeping its other options
+    pub fn set_retention_policy(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        let column = self.retention_column_input.read(cx).value().trim().to_string();
+        let days = self.retention_days_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column {}", table, column));
+        }
+        let max_age_days: u32 = days
+            .parse()
+            .ok()
+            .filter(|d| *d > 0)
+            .ok_or_else(|| anyhow::anyhow!("Age must be a whole number of days"))?;
+
+        match self.retention.get_mut(&table) {
+            Some(policy) => {
+                policy.column = column;
+                policy.max_age_days = max_age_days;
             }
+            None => self.retention.policies.push(RetentionPolicy {
+                table: table.clone(),
+                column,
+                max_age_days,
+                archive: false,
+                run_on_open: false,
+            }),
+        }
+        self.retention.save(&path)?;
+        if let Some(policy) = self.retention.get(&table) {
+            self.retention_message = Some(format!("{} rows past the limit", policy.expired_rows(&self.db)?));
         }
         Ok(())
     }
 
-    pub fn duplicate_selected_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, .. } = &tab.tab_type {
-                    view.update(cx, |table, cx| {
-                        let delegate = table.delegate_mut();
-                        if let Some(selected_row) = delegate.state.selected_row {
-                            if let Err(e) = delegate.duplicate_row(selected_row) {
-                                tracing::error!("Failed to duplicate row: {}", e);
-                            } else {
-                                tracing::debug!("✓ Row duplicated successfully");
-                            }
-                        }
-                        cx.notify();
-                    });
-                }
-            }
+    /// Change an option of the active table's policy and save it
+    pub fn update_retention_policy(&mut self, f: impl FnOnce(&mut RetentionPolicy)) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        if let Some(policy) = self.retention.get_mut(&table) {
+            f(policy);
         }
-        Ok(())
+        self.retention.save(&path)
     }
 
-    pub fn copy_row_as_sql(&mut self, cx: &mut Context<Self>) {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, .. } = &tab.tab_type {
-                    view.update(cx, |table, cx| {
-                        let delegate = table.delegate();
-                        if let Some(selected_row) = delegate.state.selected_row {
-                            if let Some(sql) = delegate.copy_row_as_insert(selected_row) {
-                                tracing::debug!("✓ Copied SQL: {}", sql);
-                                // TODO: Copy to clipboard when available
-                            }
-                        }
-                        cx.notify();
-                    });
+    pub fn remove_retention_policy(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        self.retention.policies.retain(|p| p.table != table);
+        self.retention_message = None;
+        self.retention.save(&path)
+    }
+
+    /// Apply the active table's policy now
+    pub fn run_retention(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(policy) = self.active_table_name().and_then(|t| self.retention.get(&t).cloned()) else {
+            return Ok(());
+        };
+        let removed = policy.apply(&self.db)?;
+        self.retention_message = Some(format!(
+            "✓ {} {} rows",
+            if policy.archive { "Archived" } else { "Deleted" },
+            removed
+        ));
+        self.refresh_data(cx)
+    }
+
+    /// Move the active table's selected rows, or the rows its filters match, to the
+    /// companion archive database
+    pub fn archive_active_rows(&mut self, selected_only: bool, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let (Some(path), Some(tab)) = (self.database_path.clone(), self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx))) else {
+            return Ok(());
+        };
+        let TabType::Table { view, name } = &tab.tab_type else {
+            return Ok(());
+        };
+        let delegate = view.read(cx).delegate();
+        let scope = if selected_only {
+            RowScope::Ids(delegate.selected_ids())
+        } else {
+            delegate.filter_scope()
+        };
+        match &scope {
+            RowScope::All => return Err(anyhow::anyhow!("Filter the table first; archiving every row isn't offered")),
+            RowScope::Ids(ids) if ids.is_empty() => return Err(anyhow::anyhow!("No rows selected")),
+            _ => {}
+        }
+
+        let name = name.clone();
+        let (condition, params) = self.db.scope_condition(&name, &scope)?;
+        let moved = archive::move_rows(&self.db, &archive::archive_path(&path), &name, &condition, &params)?;
+        self.archive_message = Some(format!("✓ Archived {} ({} rows)", scope.describe(), moved));
+        self.refresh_data(cx)
+    }
+
+    /// Move archived rows of the active table back, limited to those its filters match
+    pub fn restore_archived_rows(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let (Some(path), Some(tab)) = (self.database_path.clone(), self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx))) else {
+            return Ok(());
+        };
+        let TabType::Table { view, name } = &tab.tab_type else {
+            return Ok(());
+        };
+        let scope = view.read(cx).delegate().filter_scope();
+        let name = name.clone();
+        let (condition, params) = self.db.scope_condition(&name, &scope)?;
+        let restored = archive::restore_rows(&self.db, &archive::archive_path(&path), &name, &condition, &params)?;
+        self.archive_message = Some(format!("✓ Restored {} archived rows", restored));
+        self.refresh_data(cx)
+    }
+
+    /// Compare the active table with its archived rows
+    pub fn check_archive(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        self.archive_message = Some(match archive::check(&self.db, &archive::archive_path(&path), &table)? {
+            Some(check) => check.describe(),
+            None => format!("Nothing of {} has been archived", table),
+        });
+        Ok(())
+    }
+
+    pub fn refresh_dependencies(&mut self) {
+        match DependencyGraph::build(&self.db) {
+            Ok(graph) => self.dependencies = graph,
+            Err(e) => tracing::error!("Failed to build dependency graph: {}", e),
+        }
+        if let Some(focus) = &self.dependency_focus {
+            if self.dependencies.node(focus).is_none() {
+                self.dependency_focus = None;
+            }
+        }
+    }
+
+    pub fn plan_drop(&mut self, name: &str) -> anyhow::Result<()> {
+        self.refresh_dependencies();
+        self.drop_plan = Some(refactor::plan_drop(&self.db, &self.dependencies, name, self.drop_cascade)?);
+        Ok(())
+    }
+
+    pub fn run_drop(&mut self) -> anyhow::Result<()> {
+        let Some(plan) = self.drop_plan.take() else {
+            return Ok(());
+        };
+        refactor::apply_drop(&self.db, &plan)?;
+        self.refresh_table_list()?;
+        self.dependency_focus = None;
+        self.refresh_dependencies();
+        tracing::info!("✓ Dropped {} {}", plan.kind, plan.name);
+        Ok(())
+    }
+
+    /// Move a column of `table` to position `to` among its non-id columns
+    pub fn move_column(&mut self, table: &str, from: usize, to: usize) -> anyhow::Result<()> {
+        let schema = self
+            .db
+            .get_schema(table)
+            .ok_or_else(|| anyhow::anyhow!("No schema registered for table '{}'", table))?;
+        let mut order: Vec<String> = schema.fields.iter().map(|f| f.name.clone()).collect();
+        if from >= order.len() || to >= order.len() || from == to {
+            return Ok(());
+        }
+        let column = order.remove(from);
+        order.insert(to, column);
+
+        refactor::reorder_columns(&self.db, table, &order)?;
+        self.refresh_dependencies();
+        tracing::info!("✓ Reordered columns of {}", table);
+        Ok(())
+    }
+
+    /// Rebuild the active table with the column edit typed in the alter section. A drop
+    /// takes a second click, since the column's values go with it.
+    pub fn alter_active_column(&mut self, kind: AlterKind, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.alter_column_input.read(cx).value().trim().to_string();
+        let value = self.alter_value_input.read(cx).value().trim().to_string();
+        if value.is_empty() && kind != AlterKind::Drop {
+            return Err(anyhow::anyhow!("Type the new name, type or column definition"));
+        }
+        let change = match kind {
+            AlterKind::Add => ColumnChange::Add { definition: value },
+            AlterKind::Rename => ColumnChange::Rename { name: column, new_name: value },
+            AlterKind::Retype => ColumnChange::Retype { name: column, sql_type: value },
+            AlterKind::Drop => {
+                let target = (table.clone(), column.clone());
+                if self.alter_drop_pending.as_ref() != Some(&target) {
+                    self.alter_message = Some(format!("Click Drop again to drop {}.{} and its values", table, column));
+                    self.alter_drop_pending = Some(target);
+                    return Ok(());
+                }
+                ColumnChange::Drop { name: column }
+            }
+        };
+        self.alter_drop_pending = None;
+
+        let dropped = refactor::alter_column(&self.db, &table, &change)?;
+        self.refresh_dependencies();
+        self.reopen_table(&table, window, cx)?;
+        let mut message = format!("✓ {}", change.describe(&table));
+        if !dropped.is_empty() {
+            message.push_str(&format!(" and its indexes {}", dropped.join(", ")));
+        }
+        tracing::info!("{}", message);
+        self.alter_message = Some(message);
+        Ok(())
+    }
+
+    /// Close and reopen a table's tab after its columns changed, so the grid matches
+    fn reopen_table(&mut self, table: &str, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(idx) = self.open_tabs.iter().position(|tab| {
+            matches!(&tab.tab_type, TabType::Table { name, .. } if name == table)
+        }) else {
+            return Ok(());
+        };
+        self.close_tab(idx, cx);
+        self.select_table(table.to_string(), window, cx)
+    }
+
+    /// Record where a column of the active table comes from, or clear it when the
+    /// sources are blank, and save the profile sidecar
+    pub fn annotate_lineage(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.lineage_column_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+        }
+        let lineage = Lineage::parse(&self.lineage_source_input.read(cx).value());
+
+        let mut profile = self.db.profile();
+        self.lineage_message = Some(match &lineage {
+            Some(lineage) => format!("{}.{} {}", table, column, lineage.describe()),
+            None => format!("Cleared lineage of {}.{}", table, column),
+        });
+        profile.set_lineage(&table, &column, lineage);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        tracing::info!("✓ Updated lineage of {}.{}", table, column);
+        Ok(())
+    }
+
+    /// Require edits to a column of the active table to fit a regex or mask, or stop
+    /// requiring it when the pattern is blank, and save the profile sidecar
+    pub fn set_input_pattern(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.pattern_column_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+        }
+        let pattern = InputPattern::parse(&self.pattern_input.read(cx).value());
+        if let Some(pattern) = &pattern {
+            crate::cell_editors::CellPattern::compile(pattern.clone()).map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        let mut profile = self.db.profile();
+        self.pattern_message = Some(match &pattern {
+            Some(pattern) => format!("{}.{} {}", table, column, pattern.describe()),
+            None => format!("Cleared the pattern of {}.{}", table, column),
+        });
+        profile.set_pattern(&table, &column, pattern);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        tracing::info!("✓ Updated the input pattern of {}.{}", table, column);
+        Ok(())
+    }
+
+    /// Declare the valid range of a numeric column of the active table, overriding what
+    /// its `CHECK` constraints allow, or clear it when the range is blank
+    pub fn set_numeric_range(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.pattern_column_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+        }
+        let range = NumericRange::parse(&self.range_input.read(cx).value())?;
+
+        let mut profile = self.db.profile();
+        self.pattern_message = Some(match &range {
+            Some(range) => format!("{}.{} ranges over {}", table, column, range.describe()),
+            None => format!("Cleared the range of {}.{}", table, column),
+        });
+        profile.set_range(&table, &column, range);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        tracing::info!("✓ Updated the range of {}.{}", table, column);
+        Ok(())
+    }
+
+    /// Mark the column named in the input rules as holding comma-separated tags, or
+    /// unmark it
+    pub fn toggle_tag_column(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.pattern_column_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+        }
+        let tags = !self.db.column_hints(&table, &column).is_some_and(|h| h.tags);
+
+        let mut profile = self.db.profile();
+        profile.set_tags(&table, &column, tags);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        self.pattern_message = Some(if tags {
+            format!("{}.{} is edited as tags", table, column)
+        } else {
+            format!("{}.{} is edited as plain text", table, column)
+        });
+        tracing::info!("✓ Updated the tag editor of {}.{}", table, column);
+        Ok(())
+    }
+
+    /// Mark the column named in the input rules as holding Markdown, or unmark it
+    pub fn toggle_markdown_column(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let column = self.pattern_column_input.read(cx).value().trim().to_string();
+        if !self.db.column_names(&table)?.contains(&column) {
+            return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+        }
+        let markdown = !self.db.column_hints(&table, &column).is_some_and(|h| h.markdown);
+
+        let mut profile = self.db.profile();
+        profile.set_markdown(&table, &column, markdown);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        self.pattern_message = Some(if markdown {
+            format!("{}.{} is edited as Markdown with a preview", table, column)
+        } else {
+            format!("{}.{} is edited as plain text", table, column)
+        });
+        tracing::info!("✓ Updated the Markdown preview of {}.{}", table, column);
+        Ok(())
+    }
+
+    /// Declare the two columns named in the input rules, `lat, lon`, as a position
+    /// edited on a map, or undeclare them
+    pub fn toggle_geo_pair(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let text = self.pattern_column_input.read(cx).value().to_string();
+        let Some((lat, lon)) = text.split_once(',').map(|(a, b)| (a.trim(), b.trim())) else {
+            return Err(anyhow::anyhow!("Name the latitude and longitude columns as lat, lon"));
+        };
+        let columns = self.db.column_names(&table)?;
+        for column in [lat, lon] {
+            if !columns.iter().any(|c| c == column) {
+                return Err(anyhow::anyhow!("{} has no column '{}'", table, column));
+            }
+        }
+        let on = !self.db.geo_pairs(&table).iter().any(|p| p.lat == lat && p.lon == lon);
+
+        let mut profile = self.db.profile();
+        profile.set_geo_pair(&table, lat, lon, on);
+        if let Some(path) = &self.database_path {
+            crate::sidecar::save(path, "profile", &profile)?;
+        }
+        self.db.set_profile(profile);
+        self.pattern_message = Some(if on {
+            format!("{}.{} and {} are edited on a map", table, lat, lon)
+        } else {
+            format!("{}.{} and {} are edited as plain numbers", table, lat, lon)
+        });
+        tracing::info!("✓ Updated the geo pair {}.{}/{}", table, lat, lon);
+        Ok(())
+    }
+
+    /// Pending key edits for the table, or its current keys when there are none
+    fn key_draft_for(&self, schema: &TypeSchema) -> KeyDraft {
+        match &self.key_draft {
+            Some(draft) if draft.table == schema.table_name => draft.clone(),
+            _ => KeyDraft {
+                table: schema.table_name.clone(),
+                primary_key: schema.primary_key.clone(),
+                unique: schema.unique_constraints.clone(),
+                new_unique: Vec::new(),
+            },
+        }
+    }
+
+    fn edit_keys(&mut self, f: impl FnOnce(&mut KeyDraft)) {
+        let Some(schema) = self.active_table_name().and_then(|t| self.db.get_schema(&t)) else {
+            return;
+        };
+        let mut draft = self.key_draft_for(&schema);
+        f(&mut draft);
+        self.key_draft = Some(draft);
+    }
+
+    pub fn apply_keys(&mut self) -> anyhow::Result<()> {
+        let Some(draft) = self.key_draft.take() else {
+            return Ok(());
+        };
+        refactor::set_table_keys(&self.db, &draft.table, &draft.primary_key, &draft.unique)?;
+        self.refresh_dependencies();
+        tracing::info!("✓ Updated keys of {}", draft.table);
+        Ok(())
+    }
+
+    /// Start recording every write to the database's tables, or stop and keep the timeline
+    pub fn toggle_recording(&mut self) -> anyhow::Result<()> {
+        if self.recording_changes {
+            self.captured_changes = change_capture::stop(&self.db)?;
+            self.recording_changes = false;
+            self.recording_message = None;
+            tracing::info!("✓ Recorded {} changes", self.captured_changes.len());
+        } else {
+            let skipped = change_capture::start(&self.db, &self.available_tables)?;
+            self.recording_message = (!skipped.is_empty())
+                .then(|| format!("Not recorded (virtual or WITHOUT ROWID): {}", skipped.join(", ")));
+            self.captured_changes.clear();
+            self.recording_changes = true;
+        }
+        self.refresh_dependencies();
+        Ok(())
+    }
+
+    pub fn refresh_captured_changes(&mut self) -> anyhow::Result<()> {
+        let last = self.captured_changes.last().map(|c| c.seq).unwrap_or(0);
+        self.captured_changes.extend(change_capture::changes_since(&self.db, last)?);
+        Ok(())
+    }
+
+    /// Start recording a session script, or stop and write it next to the other exports
+    pub fn toggle_script_recording(&mut self) -> anyhow::Result<()> {
+        if !crate::session_script::is_recording() {
+            crate::session_script::start();
+            self.script_message = Some("Recording every write…".to_string());
+            return Ok(());
+        }
+
+        let script = crate::session_script::stop();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let db_name = self.database_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or("database");
+        let path = self.db.output_path(&format!("{}_session_{}.sql", db_name, timestamp));
+        std::fs::write(&path, script.to_sql())?;
+
+        tracing::info!("✓ Wrote session script to {:?}", path);
+        self.script_message = Some(format!(
+            "{} statements, {} parameters → {}",
+            script.statement_count(),
+            script.params.len(),
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// Write the whole database as an executable `.sql` script, like `sqlite3 .dump`
+    pub fn dump_database(&mut self) -> anyhow::Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let db_name = self.database_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or("database");
+        let path = self.db.output_path(&format!("{}_dump_{}.sql", db_name, timestamp));
+        let report = crate::sql_dump::dump_database(&self.db, &path)?;
+
+        tracing::info!("✓ Dumped database to {:?}", path);
+        self.script_message = Some(format!(
+            "{} tables, {} rows, {} other objects → {}",
+            report.tables,
+            report.rows,
+            report.objects,
+            path.display()
+        ));
+        Ok(())
+    }
+
+    pub fn replay_script(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let path = PathBuf::from(self.script_path_input.read(cx).value().trim());
+        let script = crate::session_script::SessionScript::parse(&std::fs::read_to_string(&path)?);
+        let count = crate::session_script::replay(&self.db, &script)?;
+
+        self.refresh_table_list()?;
+        self.refresh_dependencies();
+        self.script_message = Some(format!("Replayed {} statements from {}", count, path.display()));
+        tracing::info!("✓ Replayed {} statements from {:?}", count, path);
+        Ok(())
+    }
+
+    /// Recompute the active table's row hashes into the chosen column or the shadow table
+    pub fn store_row_hashes(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table_name) = self.active_table_name() else {
+            return Ok(());
+        };
+        let store = crate::row_hash::HashStore::parse(&self.hash_column_input.read(cx).value());
+        let count = crate::row_hash::store_hashes(&self.db, &table_name, &store)?;
+
+        self.hash_message = Some(format!("Hashed {} rows of {}", count, table_name));
+        if matches!(store, crate::row_hash::HashStore::Column(_)) {
+            self.refresh_data(cx)?;
+        }
+        Ok(())
+    }
+
+    /// Compare the active table's hashes with the same table in another database and
+    /// write the differing row ids to a Markdown report
+    pub fn compare_row_hashes(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table_name) = self.active_table_name() else {
+            return Ok(());
+        };
+        let store = crate::row_hash::HashStore::parse(&self.hash_column_input.read(cx).value());
+        let other_path = PathBuf::from(self.hash_compare_input.read(cx).value().trim());
+        let other = DatabaseManager::open_read_only(&other_path)?;
+        other.introspect_and_register_schemas()?;
+
+        let comparison = crate::row_hash::compare_hashes(&other, &self.db, &table_name, &store)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let report = self.db.output_path(&format!("{}_hashes_{}.md", table_name, timestamp));
+        std::fs::write(&report, comparison.to_markdown())?;
+
+        tracing::info!("✓ Wrote hash comparison against {:?} to {:?}", other_path, report);
+        self.hash_message = Some(if comparison.is_empty() {
+            format!("{} rows match {}", comparison.unchanged, other_path.display())
+        } else {
+            format!(
+                "{} added, {} removed, {} changed since {} → {}",
+                comparison.added.len(),
+                comparison.removed.len(),
+                comparison.changed.len(),
+                other_path.display(),
+                report.display()
+            )
+        });
+        Ok(())
+    }
+
+    /// Write every sidecar of this database into one file for sharing
+    pub fn export_metadata_bundle(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let path = self.database_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases have no metadata"))?;
+        let bundle = MetadataBundle::collect(&path)?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let db_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("database");
+        let bundle_path = self.db.output_path(&format!("{}_metadata_{}.json", db_name, timestamp));
+        bundle.save(&bundle_path)?;
+
+        tracing::info!("✓ Wrote {} sidecars to {:?}", bundle.sidecars.len(), bundle_path);
+        self.bundle_message = Some(format!("{} sidecars → {}", bundle.sidecars.len(), bundle_path.display()));
+        self.bundle_path_input.update(cx, |input, cx| {
+            input.set_value(&bundle_path.display().to_string(), window, cx);
+        });
+        Ok(())
+    }
+
+    /// Merge a teammate's bundle; conflicts wait for the user's choices
+    pub fn import_metadata_bundle(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let path = self.database_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases have no metadata"))?;
+        let bundle_path = PathBuf::from(self.bundle_path_input.read(cx).value().trim());
+        let merge = BundleMerge::plan(&path, &MetadataBundle::load(&bundle_path)?)?;
+
+        if merge.conflicts.is_empty() {
+            self.bundle_merge = Some(merge);
+            return self.apply_metadata_bundle();
+        }
+        self.bundle_message = Some(format!(
+            "{} new entries, {} conflicts to resolve",
+            merge.added,
+            merge.conflicts.len()
+        ));
+        self.bundle_merge = Some(merge);
+        Ok(())
+    }
+
+    pub fn apply_metadata_bundle(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(merge)) = (self.database_path.clone(), self.bundle_merge.take()) else {
+            return Ok(());
+        };
+        let written = merge.apply(&path)?;
+
+        // The profile is the only sidecar held in memory; query tabs read theirs when opened
+        if let Some(profile) = crate::sidecar::load::<ReflectionProfile>(&path, "profile")? {
+            self.db.set_profile(profile);
+        }
+        tracing::info!("✓ Merged metadata bundle into {} sidecars", written);
+        self.bundle_message = Some(format!(
+            "Merged {} new entries and {} resolved conflicts into {} sidecars",
+            merge.added,
+            merge.conflicts.len(),
+            written
+        ));
+        Ok(())
+    }
+
+    /// Rank tables and columns by how often queries touch them and write a Markdown report
+    pub fn analyze_usage(&mut self) -> anyhow::Result<()> {
+        let path = self.database_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases keep no query history"))?;
+        let stored: crate::query_editor::StoredQueries =
+            crate::sidecar::load(&path, crate::query_editor::QUERIES_SIDECAR)?.unwrap_or_default();
+        let schema = self
+            .available_tables
+            .iter()
+            .map(|table| Ok((table.clone(), self.db.column_names(table)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let report = crate::usage::UsageReport::analyze(&stored.usage, &schema, &stored.saved);
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let report_path = self.db.output_path(&format!("usage_{}.md", timestamp));
+        std::fs::write(&report_path, report.to_markdown())?;
+
+        tracing::info!("✓ Wrote table usage from {} queries to {:?}", report.queries, report_path);
+        self.usage_report = Some(report);
+        Ok(())
+    }
+
+    /// Generate the active table's enum files, designating it as a lookup table first
+    /// if it isn't one yet
+    pub fn generate_enum(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        if self.codegen.get(&table).is_none() {
+            let lookup = LookupEnum::detect(&self.db, &table)?;
+            self.codegen.enums.push(lookup);
+        }
+        let root = project_query::project_root(&path);
+        let Some(lookup) = self.codegen.get_mut(&table) else {
+            return Ok(());
+        };
+        let written = lookup.generate(&self.db, &root)?;
+        self.codegen_message = Some(format!(
+            "Wrote {} to {}",
+            lookup.enum_name,
+            written
+                .iter()
+                .filter_map(|p| p.strip_prefix(&root).ok())
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        self.codegen.save(&path)?;
+        self.codegen_drift.remove(&table);
+        Ok(())
+    }
+
+    /// Stop generating an enum for the active table; files already written are kept
+    pub fn remove_enum(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        self.codegen.enums.retain(|e| e.table != table);
+        self.codegen_drift.remove(&table);
+        self.codegen_message = None;
+        self.codegen.save(&path)
+    }
+
+    pub fn toggle_enum_sync(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(table)) = (self.database_path.clone(), self.active_table_name()) else {
+            return Ok(());
+        };
+        if let Some(lookup) = self.codegen.get_mut(&table) {
+            lookup.sync_on_save = !lookup.sync_on_save;
+        }
+        self.codegen.save(&path)
+    }
+
+    /// Re-check every generated enum against its table, regenerating those set to sync
+    /// when `regenerate` is given
+    pub fn sync_enums(&mut self, regenerate: bool) -> anyhow::Result<()> {
+        let Some(path) = self.database_path.clone() else {
+            return Ok(());
+        };
+        if self.codegen.enums.is_empty() {
+            return Ok(());
+        }
+        self.codegen_drift = codegen::check_all(&self.db, &mut self.codegen, &project_query::project_root(&path), regenerate);
+        if regenerate {
+            self.codegen.save(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Open the migration assistant over the tabs, or close it and pick up what it wrote
+    pub fn toggle_migration(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if self.migration.take().is_some() {
+            self.refresh_table_list()?;
+            self.refresh_dependencies();
+            return self.refresh_data(cx);
+        }
+        self.csv_import = None;
+        self.create_table = None;
+        self.view_editor = None;
+        self.trigger_editor = None;
+        let db = self.db.clone();
+        self.migration = Some(cx.new(|cx| MigrationAssistant::new(db, window, cx)));
+        Ok(())
+    }
+
+    /// Open the CSV/JSON import wizard over the tabs, targeting the active table, or close it
+    /// and pick up the tables it created
+    pub fn toggle_csv_import(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if self.csv_import.take().is_some() {
+            self.refresh_table_list()?;
+            self.refresh_dependencies();
+            return self.refresh_data(cx);
+        }
+        self.migration = None;
+        self.create_table = None;
+        self.view_editor = None;
+        self.trigger_editor = None;
+        let db = self.db.clone();
+        let target = self.active_table_name();
+        self.csv_import = Some(cx.new(|cx| CsvImportWizard::new(db, target, window, cx)));
+        Ok(())
+    }
+
+    /// Open the Create Table wizard over the tabs, or close it and open the table it created
+    pub fn toggle_create_table(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(wizard) = self.create_table.take() {
+            self.refresh_table_list()?;
+            self.refresh_dependencies();
+            if let Some(created) = wizard.read(cx).created.clone() {
+                return self.select_table(created, window, cx);
+            }
+            return Ok(());
+        }
+        self.migration = None;
+        self.csv_import = None;
+        self.view_editor = None;
+        self.trigger_editor = None;
+        let db = self.db.clone();
+        self.create_table = Some(cx.new(|cx| CreateTableWizard::new(db, window, cx)));
+        Ok(())
+    }
+
+    /// Open the view editor over the tabs on `view`, or on a new view when `None`; or
+    /// close it and reopen the view it saved, whose columns may have changed
+    pub fn toggle_view_editor(&mut self, view: Option<String>, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(editor) = self.view_editor.take() {
+            self.refresh_table_list()?;
+            self.refresh_dependencies();
+            if let Some(saved) = editor.read(cx).saved.clone() {
+                if let Some(idx) = self.open_tabs.iter().position(|tab| {
+                    matches!(&tab.tab_type, TabType::Table { name, .. } if name == &saved)
+                }) {
+                    self.close_tab(idx, cx);
                 }
+                return self.select_table(saved, window, cx);
             }
+            return Ok(());
         }
+        self.migration = None;
+        self.csv_import = None;
+        self.create_table = None;
+        self.trigger_editor = None;
+        let db = self.db.clone();
+        let definition = view.as_deref().map(|name| ViewDefinition::load(&db, name)).transpose()?;
+        self.view_editor = Some(cx.new(|cx| ViewEditor::new(db, view, definition, window, cx)));
+        Ok(())
+    }
+
+    /// Close the trigger editor, picking up what it saved
+    pub fn close_trigger_editor(&mut self, cx: &mut Context<Self>) {
+        if let Some(editor) = self.trigger_editor.take() {
+            if editor.read(cx).saved {
+                self.refresh_dependencies();
+            }
+        }
+    }
+
+    /// Open the trigger editor over the tabs on one of the active table's triggers, or
+    /// on a new one when `None`
+    pub fn open_trigger_editor(&mut self, trigger: Option<String>, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        self.close_trigger_editor(cx);
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let original = match trigger {
+            Some(name) => {
+                let sql = self
+                    .db
+                    .triggers(&table)?
+                    .into_iter()
+                    .find(|t| t.name == name)
+                    .and_then(|t| t.sql)
+                    .ok_or_else(|| anyhow::anyhow!("No trigger named {} on {}", name, table))?;
+                Some((name, sql))
+            }
+            None => None,
+        };
+        self.migration = None;
+        self.csv_import = None;
+        self.create_table = None;
+        self.view_editor = None;
+        let db = self.db.clone();
+        self.trigger_editor = Some(cx.new(|cx| TriggerEditor::new(db, table, original, window, cx)));
+        Ok(())
+    }
+
+    /// Drop one of the active table's triggers; the first click only asks to confirm
+    pub fn drop_trigger(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.trigger_drop_pending.as_deref() != Some(name) {
+            self.trigger_message = Some(format!("Click Drop again to drop trigger {}", name));
+            self.trigger_drop_pending = Some(name.to_string());
+            return Ok(());
+        }
+        self.trigger_drop_pending = None;
+        self.db.drop_trigger(name)?;
+        self.refresh_dependencies();
+        tracing::info!("✓ Dropped trigger {}", name);
+        self.trigger_message = Some(format!("✓ Dropped {}", name));
+        Ok(())
+    }
+
+    /// Rebuild the active table with the foreign key typed in the foreign keys section
+    pub fn add_foreign_key(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let split = |list: &str| -> Vec<String> {
+            list.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+        };
+        let columns = split(&self.fk_columns_input.read(cx).value());
+        let target = self.fk_parent_input.read(cx).value().trim().to_string();
+        let (parent, parent_columns) = match target.split_once('(') {
+            Some((parent, rest)) => (parent.trim().to_string(), split(rest.trim_end_matches(')'))),
+            None => (target, Vec::new()),
+        };
+        if parent.is_empty() {
+            return Err(anyhow::anyhow!("Type the table the key references"));
+        }
+        let key = ForeignKeyDefinition {
+            columns,
+            parent,
+            parent_columns,
+            on_delete: (self.fk_on_delete > 0).then(|| FOREIGN_KEY_ACTIONS[self.fk_on_delete].to_string()),
+        };
+
+        refactor::add_foreign_key(&self.db, &table, &key)?;
+        self.refresh_dependencies();
+        self.reopen_table(&table, window, cx)?;
+        tracing::info!("✓ Added {} to {}", key.to_sql(), table);
+        self.fk_message = Some(format!("✓ Added {}", key.to_sql()));
+        Ok(())
+    }
+
+    /// Rebuild the active table without foreign key `id`; the first click only asks to confirm
+    pub fn drop_foreign_key(&mut self, id: i64, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        if self.fk_drop_pending != Some(id) {
+            self.fk_message = Some("Click Drop again to rebuild the table without this key".to_string());
+            self.fk_drop_pending = Some(id);
+            return Ok(());
+        }
+        self.fk_drop_pending = None;
+
+        refactor::drop_foreign_key(&self.db, &table, id)?;
+        self.refresh_dependencies();
+        self.reopen_table(&table, window, cx)?;
+        tracing::info!("✓ Dropped foreign key {} of {}", id, table);
+        self.fk_message = Some("✓ Dropped the foreign key".to_string());
+        Ok(())
+    }
+
+    /// Turn enforcement of foreign keys on this connection on or off
+    pub fn toggle_foreign_keys(&mut self) -> anyhow::Result<()> {
+        let enabled = !self.db.foreign_keys_enabled()?;
+        self.db.set_foreign_keys(enabled)?;
+        tracing::info!("✓ Foreign keys {}", if enabled { "enforced" } else { "not enforced" });
+        Ok(())
+    }
+
+    /// Start or lift the change freeze, during which every write is refused while
+    /// browsing, queries, exports and analysis keep working
+    pub fn toggle_freeze(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let frozen = !self.db.is_frozen();
+        self.db.set_frozen(frozen)?;
+        // Open grids read the database's read-only state as they render
+        for tab in &self.open_tabs {
+            if let TabType::Table { view, .. } = &tab.tab_type {
+                view.update(cx, |_, cx| cx.notify());
+            }
+        }
+        tracing::info!("✓ Change freeze {}", if frozen { "started" } else { "lifted" });
+        Ok(())
+    }
+
+    /// Re-read the table and view names the browser lists
+    fn refresh_table_list(&mut self) -> anyhow::Result<()> {
+        self.available_tables = self.db.list_tables()?;
+        self.available_views = self.db.list_views()?;
+        Ok(())
+    }
+
+    /// Dock the index manager beside the open tabs, or refresh it if it's already there
+    pub fn open_index_manager(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let table = self.active_table_name();
+        if let Some(panel) = &self.index_panel {
+            panel.update(cx, |panel, cx| {
+                panel.refresh();
+                if let Some(table) = &table {
+                    panel.set_table(table, window, cx);
+                }
+                cx.notify();
+            });
+            return;
+        }
+
+        let db = self.db.clone();
+        let panel = cx.new(|cx| {
+            let mut panel = IndexManagerPanel::new(db, window, cx);
+            if let Some(table) = &table {
+                panel.set_table(table, window, cx);
+            }
+            panel
+        });
+        self.index_panel = Some(panel.clone());
+        let Some(workspace) = self.workspace.clone().filter(|_| self.workspace_initialized) else {
+            return;
+        };
+        let panel: std::sync::Arc<dyn ui::dock::PanelView> = std::sync::Arc::new(panel);
+        window.defer(cx, move |window, cx| {
+            _ = workspace.update(cx, |workspace, cx| {
+                let dock_area = workspace.dock_area();
+                if let Some(tab_panel) = dock_area.read(cx).items().left_top_tab_panel(cx) {
+                    _ = tab_panel.update(cx, |tab_panel, cx| {
+                        tab_panel.add_panel(panel, window, cx);
+                    });
+                }
+            });
+        });
+    }
+
+    /// Dock the ER diagram beside the open tabs, or refresh it if it's already there
+    pub fn open_er_diagram(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(panel) = &self.er_panel {
+            panel.update(cx, |panel, cx| {
+                panel.refresh();
+                cx.notify();
+            });
+            return;
+        }
+
+        let db = self.db.clone();
+        let panel = cx.new(|cx| ErDiagramPanel::new(db, cx));
+        self._er_subscription = Some(cx.subscribe_in(
+            &panel,
+            window,
+            |editor: &mut Self, _, event: &ErDiagramEvent, window, cx| match event {
+                ErDiagramEvent::OpenTable(table) => {
+                    if let Err(e) = editor.select_table(table.clone(), window, cx) {
+                        tracing::error!("Failed to open table {}: {}", table, e);
+                    }
+                    cx.notify();
+                }
+            },
+        ));
+        self.er_panel = Some(panel.clone());
+        let Some(workspace) = self.workspace.clone().filter(|_| self.workspace_initialized) else {
+            return;
+        };
+        let panel: std::sync::Arc<dyn ui::dock::PanelView> = std::sync::Arc::new(panel);
+        window.defer(cx, move |window, cx| {
+            _ = workspace.update(cx, |workspace, cx| {
+                let dock_area = workspace.dock_area();
+                if let Some(tab_panel) = dock_area.read(cx).items().left_top_tab_panel(cx) {
+                    _ = tab_panel.update(cx, |tab_panel, cx| {
+                        tab_panel.add_panel(panel, window, cx);
+                    });
+                }
+            });
+        });
+    }
+
+    pub fn register_type_schema(&mut self, schema: TypeSchema) -> anyhow::Result<()> {
+        self.db.register_type(schema)?;
+        self.refresh_table_list()?;
+        self.refresh_dependencies();
+        Ok(())
+    }
+
+    fn panel_for(tab: &EditorTab, cx: &mut App) -> std::sync::Arc<dyn ui::dock::PanelView> {
+        match &tab.tab_type {
+            TabType::Table { name, view } => {
+                let panel = cx.new(|cx| {
+                    TablePanelWrapper::new(name.clone(), view.clone(), cx)
+                });
+                std::sync::Arc::new(panel)
+            }
+            TabType::Query { name, view } => {
+                let panel = cx.new(|cx| {
+                    QueryPanelWrapper::new(name.clone(), view.clone(), cx)
+                });
+                std::sync::Arc::new(panel)
+            }
+        }
+    }
+
+    /// Add pending tabs to the first available TabPanel
+    fn add_pending_tabs_to_workspace(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.open_tabs.is_empty() || !self.workspace_initialized {
+            return;
+        }
+        
+        // Find the first TabPanel in the workspace and add new tabs to it
+        if let Some(workspace) = self.workspace.clone() {
+            // Get the last tab that was just added
+            if let Some(last_tab) = self.open_tabs.last() {
+                let panel = Self::panel_for(last_tab, cx);
+                
+                // Defer adding the panel to avoid reentrant updates
+                window.defer(cx, move |window, cx| {
+                    _ = workspace.update(cx, |workspace, cx| {
+                        let dock_area = workspace.dock_area();
+                        
+                        // Get the first TabPanel from the center items
+                        if let Some(tab_panel) = dock_area.read(cx).items().left_top_tab_panel(cx) {
+                            _ = tab_panel.update(cx, |tab_panel, cx| {
+                                tab_panel.add_panel(panel, window, cx);
+                            });
+                        }
+                    });
+                });
+            }
+        }
+    }
+
+    /// Arrange the open tabs for the current layout preset. Presets that separate
+    /// queries from tables fall back to a single group when one side is empty.
+    fn layout_dock_item(
+        &self,
+        dock_area: &WeakEntity<ui::dock::DockArea>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> ui::dock::DockItem {
+        if self.open_tabs.is_empty() && self.index_panel.is_none() && self.er_panel.is_none() {
+            // Show welcome panel when no tabs
+            let welcome_panel = cx.new(|cx| {
+                WelcomePanelWrapper::new(cx)
+            });
+            return ui::dock::DockItem::tabs(
+                vec![std::sync::Arc::new(welcome_panel) as std::sync::Arc<dyn ui::dock::PanelView>],
+                Some(0),
+                dock_area,
+                window,
+                cx,
+            );
+        }
+
+        let (queries, tables): (Vec<&EditorTab>, Vec<&EditorTab>) = self.open_tabs
+            .iter()
+            .partition(|tab| matches!(tab.tab_type, TabType::Query { .. }));
+        let axis = match self.settings.layout {
+            LayoutPreset::DataEntry => None,
+            LayoutPreset::QueryFocused => Some(Axis::Vertical),
+            LayoutPreset::SchemaDesign => Some(Axis::Horizontal),
+        };
+
+        match axis {
+            Some(axis) if !queries.is_empty() && !tables.is_empty() => {
+                let group = |tabs: Vec<&EditorTab>, window: &mut Window, cx: &mut App| {
+                    let panels = tabs.iter().map(|tab| Self::panel_for(tab, cx)).collect();
+                    ui::dock::DockItem::tabs(panels, Some(0), dock_area, window, cx)
+                };
+                // Queries lead when focusing on queries, tables otherwise
+                let groups = if self.settings.layout == LayoutPreset::QueryFocused {
+                    vec![group(queries, window, cx), group(tables, window, cx)]
+                } else {
+                    vec![group(tables, window, cx), group(queries, window, cx)]
+                };
+                ui::dock::DockItem::split(axis, groups, dock_area, window, cx)
+            }
+            _ => {
+                // Create panels for all open tabs, then the index manager and ER diagram if opened
+                let mut tab_panels: Vec<std::sync::Arc<dyn ui::dock::PanelView>> =
+                    self.open_tabs.iter().map(|tab| Self::panel_for(tab, cx)).collect();
+                if let Some(panel) = self.index_panel.clone() {
+                    tab_panels.push(std::sync::Arc::new(panel));
+                }
+                if let Some(panel) = self.er_panel.clone() {
+                    tab_panels.push(std::sync::Arc::new(panel));
+                }
+                ui::dock::DockItem::tabs(tab_panels, self.active_tab_idx, dock_area, window, cx)
+            }
+        }
+    }
+
+    /// Initialize/reinitialize workspace with current tabs
+    fn initialize_workspace_once(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.workspace_initialized {
+            return;
+        }
+        
+        if let Some(workspace) = self.workspace.clone() {
+            workspace.update(cx, |workspace, cx| {
+                let dock_area = workspace.dock_area().downgrade();
+                let item = self.layout_dock_item(&dock_area, window, cx);
+                workspace.initialize(item, None, None, None, window, cx);
+            });
+            
+            self.workspace_initialized = true;
+        }
+    }
+
+    /// Remember the safe-mode database as trusted; it opens normally from then on
+    pub fn trust_database(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = &self.database_path else {
+            return;
+        };
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if !self.settings.trusted_databases.contains(&canonical) {
+            self.settings.trusted_databases.push(canonical);
+        }
+        self.save_settings();
+        self.quarantine_reason = None;
+        tracing::info!("✓ Trusted {:?}; reopen it to edit", path);
+        cx.notify();
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.settings.save() {
+            tracing::error!("Failed to save editor settings: {}", e);
+        }
+    }
+
+    /// Switch layout preset, remembering it for next time
+    pub fn apply_layout(&mut self, layout: LayoutPreset, cx: &mut Context<Self>) {
+        self.settings.layout = layout;
+        self.save_settings();
+        // Rebuilt on the next render
+        self.workspace_initialized = false;
+        cx.notify();
+    }
+
+    pub fn set_density(&mut self, density: RowDensity, cx: &mut Context<Self>) {
+        self.settings.density = density;
+        self.save_settings();
+        self.apply_grid_style(cx);
+    }
+
+    pub fn toggle_high_contrast(&mut self, cx: &mut Context<Self>) {
+        self.settings.high_contrast = !self.settings.high_contrast;
+        self.save_settings();
+        self.apply_grid_style(cx);
+    }
+
+    pub fn zoom_by(&mut self, steps: i32, cx: &mut Context<Self>) {
+        self.settings.zoom_by(steps);
+        self.save_settings();
+        self.apply_grid_style(cx);
+    }
+
+    /// Switch the query editors' keybindings, remembering them for next time
+    pub fn set_key_bindings(&mut self, mode: KeyBindingMode, cx: &mut Context<Self>) {
+        self.settings.key_bindings = mode;
+        self.save_settings();
+        for tab in &self.open_tabs {
+            if let TabType::Query { view, .. } = &tab.tab_type {
+                view.update(cx, |view, cx| view.set_key_bindings(mode, cx));
+            }
+        }
+        cx.notify();
+    }
+
+    /// Push the density and zoom settings to every open grid
+    fn apply_grid_style(&mut self, cx: &mut Context<Self>) {
+        let grid = self.settings.grid_style();
+        for tab in &self.open_tabs {
+            match &tab.tab_type {
+                TabType::Table { view, .. } => view.update(cx, |table, cx| {
+                    table.set_size(grid.size, cx);
+                    table.delegate_mut().grid = grid;
+                }),
+                TabType::Query { view, .. } => view.update(cx, |view, cx| view.set_grid_style(grid, cx)),
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn select_table(&mut self, table_name: String, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        // Check if table is already open
+        if let Some(idx) = self.open_tabs.iter().position(|tab| {
+            matches!(&tab.tab_type, TabType::Table { name, .. } if name == &table_name)
+        }) {
+            self.active_tab_idx = Some(idx);
+            cx.notify();
+            return Ok(());
+        }
+        
+        // Check if schema exists for this table
+        if self.db.get_schema(&table_name).is_none() {
+            return Err(anyhow::anyhow!(
+                "No schema registered for table '{}'", table_name
+            ));
+        }
+        
+        // Create new tab
+        let mut delegate = DataTableView::new(self.db.clone(), table_name.clone())?;
+        let grid = self.settings.grid_style();
+        delegate.grid = grid;
+        delegate.thresholds = self.settings.change_thresholds;
+        let table_view = cx.new(|cx| {
+            let mut table = Table::new(delegate, window, cx);
+            table.set_size(grid.size, cx);
+            table.col_fixed = true;
+            table.col_resizable = true;
+            table.sortable = true;
+            table
+        });
+        self._tab_observers.push(cx.observe(&table_view, |editor, _, cx| editor.on_tab_changed(cx)));
+        
+        let tab_type = TabType::Table { 
+            name: table_name.clone(), 
+            view: table_view 
+        };
+        
+        let tab = EditorTab {
+            id: self.next_tab_id,
+            tab_type: tab_type.clone(),
+        };
+        
+        self.next_tab_id += 1;
+        self.open_tabs.push(tab);
+        self.active_tab_idx = Some(self.open_tabs.len() - 1);
+        
+        // Add the new tab to the workspace efficiently
+        self.add_pending_tabs_to_workspace(window, cx);
+        
+        cx.emit(DataTableEvent::TableOpened(table_name));
+        cx.notify();
+        
+        Ok(())
+    }
+    
+    pub fn open_query_tab(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let grid = self.settings.grid_style();
+        let key_bindings = self.settings.key_bindings;
+        let query_view = cx.new(|cx| {
+            let mut view = QueryEditorView::new(self.db.clone(), window, cx);
+            view.set_grid_style(grid, cx);
+            view.set_key_bindings(key_bindings, cx);
+            view
+        });
+        // A query tab can BEGIN a transaction the save then has to commit
+        self._tab_observers.push(cx.observe(&query_view, |editor, _, cx| editor.on_tab_changed(cx)));
+        
+        let tab_type = TabType::Query {
+            name: format!("Query {}", self.next_tab_id),
+            view: query_view,
+        };
+        
+        let tab = EditorTab {
+            id: self.next_tab_id,
+            tab_type: tab_type.clone(),
+        };
+        
+        self.next_tab_id += 1;
+        self.open_tabs.push(tab);
+        self.active_tab_idx = Some(self.open_tabs.len() - 1);
+        
+        // Add the new tab to the workspace efficiently
+        self.add_pending_tabs_to_workspace(window, cx);
+        
+        cx.notify();
+    }
+    
+    pub fn close_tab(&mut self, tab_idx: usize, cx: &mut Context<Self>) {
+        if tab_idx < self.open_tabs.len() {
+            let tab = self.open_tabs.remove(tab_idx);
+            cx.emit(DataTableEvent::TableClosed(tab.id));
+            
+            // Adjust active tab
+            if self.open_tabs.is_empty() {
+                self.active_tab_idx = None;
+            } else if let Some(active) = self.active_tab_idx {
+                if active >= self.open_tabs.len() {
+                    self.active_tab_idx = Some(self.open_tabs.len() - 1);
+                }
+            }
+            cx.notify();
+        }
+    }
+
+    pub fn add_new_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, name, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        if let Err(e) = table.delegate_mut().add_new_row() {
+                            tracing::error!("Failed to add row: {}", e);
+                        } else {
+                            cx.notify();
+                        }
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch the UUID version new rows of the active table get
+    pub fn toggle_new_row_uuid(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)) else {
+            return;
+        };
+        if let TabType::Table { view, .. } = &tab.tab_type {
+            view.update(cx, |table, cx| {
+                let state = &mut table.delegate_mut().state;
+                state.uuid_version = state.uuid_version.toggled();
+                cx.notify();
+            });
+        }
+    }
+
+    pub fn delete_selected_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, name, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        let delegate = table.delegate_mut();
+                        if let Some(selected_row) = delegate.state.selected_row {
+                            if let Err(e) = delegate.delete_row(selected_row) {
+                                tracing::error!("Failed to delete row: {}", e);
+                            } else {
+                                // Row indices shift after a delete
+                                delegate.clear_selection();
+                                cx.notify();
+                            }
+                        }
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn refresh_data(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        let delegate = table.delegate_mut();
+                        if let Err(e) = delegate.refresh_rows(0, 1000) {
+                            tracing::error!("Failed to refresh rows: {}", e);
+                        }
+                        cx.notify();
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Seek the active table to the row whose key is typed into the go-to box
+    pub fn go_to_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let key = self.goto_input.read(cx).value().to_string();
+        let Some(TabType::Table { view, .. }) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).map(|tab| &tab.tab_type) else {
+            return Ok(());
+        };
+        let found = view.update(cx, |table, cx| {
+            let found = table.delegate_mut().go_to_row(&key);
+            cx.notify();
+            found
+        })?;
+        if found.is_none() {
+            tracing::info!("No row with key {}", key);
+        }
+        Ok(())
+    }
+
+    /// Switch the active table between scrolling and keyset paging
+    pub fn toggle_paging(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(TabType::Table { view, .. }) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).map(|tab| &tab.tab_type) else {
+            return Ok(());
+        };
+        view.update(cx, |table, cx| {
+            let delegate = table.delegate_mut();
+            let paged = !delegate.is_paged();
+            delegate.set_paged(paged)?;
+            cx.notify();
+            anyhow::Ok(())
+        })
+    }
+
+    /// Show the next (or previous) keyset page of the active table
+    pub fn turn_page(&mut self, forward: bool, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(TabType::Table { view, .. }) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).map(|tab| &tab.tab_type) else {
+            return Ok(());
+        };
+        let moved = view.update(cx, |table, cx| {
+            let delegate = table.delegate_mut();
+            let moved = if forward { delegate.next_page() } else { delegate.prev_page() };
+            cx.notify();
+            moved
+        })?;
+        if !moved {
+            tracing::info!("Already on the {} page", if forward { "last" } else { "first" });
+        }
+        Ok(())
+    }
+
+    /// Start the active table's page at the rowid typed into the jump box
+    pub fn jump_to_rowid(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let text = self.page_jump_input.read(cx).value().trim().trim_start_matches('#').to_string();
+        if text.is_empty() {
+            return Ok(());
+        }
+        let rowid = text.parse::<i64>().map_err(|_| anyhow::anyhow!("'{}' is not a rowid", text))?;
+        let Some(TabType::Table { view, .. }) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).map(|tab| &tab.tab_type) else {
+            return Ok(());
+        };
+        view.update(cx, |table, cx| {
+            table.delegate_mut().jump_to_rowid(rowid)?;
+            cx.notify();
+            anyhow::Ok(())
+        })
+    }
+
+    pub fn duplicate_selected_row(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        let delegate = table.delegate_mut();
+                        if let Some(selected_row) = delegate.state.selected_row {
+                            if let Err(e) = delegate.duplicate_row(selected_row) {
+                                tracing::error!("Failed to duplicate row: {}", e);
+                            } else {
+                                tracing::debug!("✓ Row duplicated successfully");
+                            }
+                        }
+                        cx.notify();
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn copy_row_as_sql(&mut self, cx: &mut Context<Self>) {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        let delegate = table.delegate();
+                        if let Some(selected_row) = delegate.state.selected_row {
+                            if let Some(sql) = delegate.copy_row_as_insert(selected_row) {
+                                tracing::debug!("✓ Copied SQL: {}", sql);
+                                // TODO: Copy to clipboard when available
+                            }
+                        }
+                        cx.notify();
+                    });
+                }
+            }
+        }
+    }
+
+    /// Put the selected rows on the clipboard as text plus a payload engine editors understand
+    pub fn copy_rows(&mut self, cx: &mut Context<Self>) {
+        let Some(TabType::Table { view, .. }) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).map(|tab| &tab.tab_type) else {
+            return;
+        };
+        if let Some(placeholder) = view.read(cx).delegate().oversized_selection() {
+            tracing::error!("Failed to copy rows: the selection holds a {} value; open it to save it to a file", placeholder);
+            return;
+        }
+        if let Some(payload) = view.read(cx).delegate().selected_payload() {
+            let count = payload.records.len();
+            cx.write_to_clipboard(payload.to_clipboard());
+            tracing::info!("✓ Copied {} {} records", count, payload.type_name);
+        }
+    }
+
+    /// Stage the clipboard's rows as inserts into the active table, for review before writing.
+    /// Records copied from another Pulsar editor are preferred over the plain text.
+    pub fn paste_rows(&mut self, cx: &mut Context<Self>) {
+        let Some(item) = cx.read_from_clipboard() else {
+            return;
+        };
+        let payload = RecordPayload::from_clipboard(&item);
+        let Some(text) = item.text() else {
+            return;
+        };
+
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, .. } = &tab.tab_type {
+                    view.update(cx, |table, cx| {
+                        match &payload {
+                            Some(payload) => table.delegate_mut().stage_payload(payload),
+                            None => table.delegate_mut().stage_paste(&text),
+                        }
+                        cx.notify();
+                    });
+                }
+            }
+        }
+    }
+
+    fn active_table_name(&self) -> Option<String> {
+        let tab = self.open_tabs.get(self.active_tab_idx?)?;
+        match &tab.tab_type {
+            TabType::Table { name, .. } => Some(name.clone()),
+            TabType::Query { .. } => None,
+        }
+    }
+
+    /// Write Markdown and HTML profiling reports for the active table
+    pub fn profile_active_table(&self) -> anyhow::Result<()> {
+        let Some(table_name) = self.active_table_name() else {
+            return Ok(());
+        };
+
+        let profile = crate::profiling::profile_table(&self.db, &table_name)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        let md_path = self.db.output_path(&format!("{}_profile_{}.md", table_name, timestamp));
+        let html_path = self.db.output_path(&format!("{}_profile_{}.html", table_name, timestamp));
+        std::fs::write(&md_path, profile.to_markdown())?;
+        std::fs::write(&html_path, profile.to_html())?;
+
+        tracing::info!("✓ Wrote profile for {} to {:?} and {:?}", table_name, md_path, html_path);
+        Ok(())
+    }
+
+    /// Diff this database against another file (e.g. a later save) and write a Markdown report
+    pub fn compare_with(&self, other: PathBuf) -> anyhow::Result<PathBuf> {
+        let path = self.database_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases can't be compared"))?;
+
+        let diff = crate::data_diff::diff_database_files(&path, &other)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let report = self.db.output_path(&format!("diff_{}.md", timestamp));
+        std::fs::write(&report, diff.to_markdown())?;
+
+        tracing::info!("✓ Wrote diff against {:?} to {:?}", other, report);
+        Ok(report)
+    }
+
+    /// Diff the rows of the file typed in the compare section (e.g. a later save)
+    /// against this database and write the report
+    pub fn compare_data(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let other = PathBuf::from(self.schema_compare_input.read(cx).value().trim());
+        if !other.is_file() {
+            return Err(anyhow::anyhow!("No database file at {}", other.display()));
+        }
+        let report = self.compare_with(other.clone())?;
+        self.schema_diff_message = Some(format!("✓ Diffed rows with {} → {}", other.display(), report.display()));
+        Ok(())
+    }
+
+    /// Diff the schema of the file typed in the schema compare section against this database
+    pub fn compare_schema(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let other = PathBuf::from(self.schema_compare_input.read(cx).value().trim());
+        if !other.is_file() {
+            return Err(anyhow::anyhow!("No database file at {}", other.display()));
+        }
+        let diff = schema_diff::diff_schema_with_file(&self.db, &other)?;
+        self.migration_script = None;
+        self.migration_run_pending = false;
+        tracing::info!("✓ Compared schema with {:?}: {}", other, diff.summary());
+        self.schema_diff_message = Some(format!("{} → this database: {}", other.display(), diff.summary()));
+        self.schema_diff = Some(diff);
+        Ok(())
+    }
+
+    /// Write the last schema diff as a Markdown report
+    pub fn write_schema_diff(&mut self) -> anyhow::Result<()> {
+        let Some(diff) = &self.schema_diff else {
+            return Ok(());
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let report = self.db.output_path(&format!("schema_diff_{}.md", timestamp));
+        std::fs::write(&report, diff.to_markdown())?;
+        tracing::info!("✓ Wrote schema diff to {:?}", report);
+        self.schema_diff_message = Some(format!("✓ Wrote {}", report.display()));
+        Ok(())
+    }
+
+    /// Generate the script migrating the compared file's schema to this one, for preview
+    pub fn generate_migration_script(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(diff) = &self.schema_diff else {
+            return Ok(());
+        };
+        let other = PathBuf::from(self.schema_compare_input.read(cx).value().trim());
+        let script = schema_diff::migration_script_from_file(&self.db, &other, diff)?;
+        self.migration_run_pending = false;
+        self.migration_script = Some(script);
+        Ok(())
+    }
+
+    /// Save the previewed migration script as a .sql file
+    pub fn save_migration_script(&mut self) -> anyhow::Result<()> {
+        let Some(script) = &self.migration_script else {
+            return Ok(());
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let path = self.db.output_path(&format!("migration_{}.sql", timestamp));
+        std::fs::write(&path, script)?;
+        tracing::info!("✓ Wrote migration script to {:?}", path);
+        self.schema_diff_message = Some(format!("✓ Wrote {}", path.display()));
+        Ok(())
+    }
+
+    /// Run the previewed migration script against the compared file, asking for a second click first
+    pub fn run_migration_script(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(script) = self.migration_script.clone() else {
+            return Ok(());
+        };
+        if !self.migration_run_pending {
+            self.migration_run_pending = true;
+            return Ok(());
+        }
+        self.migration_run_pending = false;
+        let other = PathBuf::from(self.schema_compare_input.read(cx).value().trim());
+        let target = DatabaseManager::new(&other)?;
+        let report = crate::sql_dump::run_script(&target, &script)?;
+        if !report.committed() {
+            self.schema_diff_message = Some(report.summary());
+            return Ok(());
+        }
+        self.compare_schema(cx)?;
+        if let Some(diff) = &self.schema_diff {
+            self.schema_diff_message = Some(format!("{}; {} → this database: {}", report.summary(), other.display(), diff.summary()));
+        }
+        Ok(())
+    }
+
+    /// Compare the active table's rows with the same table in the sync target, by primary key
+    pub fn compare_table_rows(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        let target = PathBuf::from(self.sync_target_input.read(cx).value().trim());
+        if !target.is_file() {
+            return Err(anyhow::anyhow!("No database file at {}", target.display()));
+        }
+        self.sync_pending = false;
+        let diff = data_diff::diff_table_with_file(&self.db, &target, &table)?;
+        tracing::info!("✓ Compared rows of {} with {:?}: {}", table, target, diff.summary());
+        self.sync_message = Some(diff.summary());
+        self.table_sync = Some(diff);
+        Ok(())
+    }
+
+    /// Make the sync target's copy of the active table match this one, asking for a second click first
+    pub fn sync_table_to_target(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(table) = self.active_table_name() else {
+            return Ok(());
+        };
+        if !self.sync_pending {
+            self.sync_pending = true;
+            return Ok(());
+        }
+        self.sync_pending = false;
+        let target = PathBuf::from(self.sync_target_input.read(cx).value().trim());
+        let (inserted, updated, deleted) = data_diff::sync_table_to_file(&self.db, &target, &table)?;
+        self.sync_message = Some(format!(
+            "✓ Synced {} to {}: {} inserted, {} updated, {} deleted",
+            table,
+            target.display(),
+            inserted,
+            updated,
+            deleted
+        ));
+        self.table_sync = Some(data_diff::diff_table_with_file(&self.db, &target, &table)?);
+        Ok(())
+    }
+
+    /// Export every table to its own file, several tables at a time
+    pub fn export_all_tables(&self) -> anyhow::Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let db_name = self.database_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or("database");
+        let dir = self.db.output_path(&format!("{}_export_{}", db_name, timestamp));
+
+        let exported = crate::export::export_tables(
+            &self.db,
+            &self.available_tables,
+            &dir,
+            &self.export_kind.format(&self.csv_export),
+            crate::compression::Compression::None,
+        )?;
+        let total_rows: usize = exported.iter().map(|(_, count)| count).sum();
+
+        tracing::info!("✓ Exported {} tables ({} rows) to {:?}", exported.len(), total_rows, dir);
+        Ok(())
+    }
+
+    /// Export the active table's filtered rows, or only its selected rows, to CSV, TSV,
+    /// JSON or NDJSON
+    pub fn export_active_table(&self, selected_only: bool, cx: &App) -> anyhow::Result<()> {
+        let Some(tab) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)) else {
+            return Ok(());
+        };
+        let TabType::Table { view, name } = &tab.tab_type else {
+            return Ok(());
+        };
+
+        let delegate = view.read(cx).delegate();
+        let scope = if selected_only {
+            RowScope::Ids(delegate.selected_ids())
+        } else {
+            delegate.filter_scope()
+        };
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let kind = if selected_only { "selected" } else { "filtered" };
+        let format = self.export_kind.format(&self.csv_export);
+        let path = self.db.output_path(&format!("{}_{}_{}.{}", name, kind, timestamp, format.extension()));
+
+        let count = crate::export::export_table_rows(&self.db, name, &scope, &path, &format)?;
+
+        tracing::info!("✓ Exported {} ({}) to {:?}", count, scope.describe(), path);
+        Ok(())
+    }
+
+    /// Export the active table's filtered rows to Parquet, typed by its columns
+    pub fn export_active_table_to_parquet(&self, cx: &App) -> anyhow::Result<()> {
+        let Some(tab) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)) else {
+            return Ok(());
+        };
+        let TabType::Table { view, name } = &tab.tab_type else {
+            return Ok(());
+        };
+
+        let scope = view.read(cx).delegate().filter_scope();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let path = self.db.output_path(&format!("{}_filtered_{}.parquet", name, timestamp));
+
+        let count = crate::parquet_export::export_table_to_parquet(&self.db, name, &scope, &path)?;
+
+        tracing::info!("✓ Exported {} ({}) to {:?}", count, scope.describe(), path);
+        Ok(())
+    }
+
+    /// Write a printable HTML report of the rows the active table currently shows
+    pub fn report_active_table(&self, cx: &App) -> anyhow::Result<()> {
+        let Some(tab) = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)) else {
+            return Ok(());
+        };
+        let TabType::Table { view, name } = &tab.tab_type else {
+            return Ok(());
+        };
+
+        let scope = view.read(cx).delegate().filter_scope();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let path = self.db.output_path(&format!("{}_report_{}.html", name, timestamp));
+
+        let count = crate::report::write_table_report(&self.db, name, &scope, &path)?;
+        tracing::info!("✓ Wrote report of {} rows to {:?}", count, path);
+        Ok(())
+    }
+
+    pub fn get_table_stats(&self, cx: &App) -> String {
+        if let Some(active_idx) = self.active_tab_idx {
+            if let Some(tab) = self.open_tabs.get(active_idx) {
+                if let TabType::Table { view, .. } = &tab.tab_type {
+                    return view.read(cx).delegate().get_table_stats();
+                }
+            }
+        }
+        "No table selected".to_string()
+    }
+
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_table_tab = self.active_tab_idx.and_then(|idx| {
+            self.open_tabs.get(idx).map(|tab| matches!(tab.tab_type, TabType::Table { .. }))
+        }).unwrap_or(false);
+        // Views open read-only
+        let read_only_tab = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).is_some_and(|tab| match &tab.tab_type {
+            TabType::Table { view, .. } => view.read(cx).delegate().is_read_only(),
+            TabType::Query { .. } => false,
+        });
+        let can_write = is_table_tab && !read_only_tab;
+        let transactional = self.db.is_transactional();
+        let held = self.db.held_count();
+        let is_paged = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).is_some_and(|tab| match &tab.tab_type {
+            TabType::Table { view, .. } => view.read(cx).delegate().is_paged(),
+            TabType::Query { .. } => false,
+        });
+        // Version new rows generate for their required UUID keys, when the table has any
+        let new_row_uuid = self.active_tab_idx.and_then(|idx| self.open_tabs.get(idx)).and_then(|tab| match &tab.tab_type {
+            TabType::Table { view, .. } => {
+                let delegate = view.read(cx).delegate();
+                delegate.fills_uuids().then_some(delegate.state.uuid_version)
+            }
+            TabType::Query { .. } => None,
+        });
+        let foreign_keys = self.db.foreign_keys_enabled().unwrap_or(false);
+
+        v_flex()
+            .w_full()
+            .gap_0()
+            .child(
+                // Main toolbar
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .bg(cx.theme().muted.opacity(0.3))
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        Button::new("add-row")
+                            .icon(IconName::Plus)
+                            .label("Add Row")
+                            .small()
+                            .primary()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.add_new_row(cx) {
+                                    tracing::error!("Failed to add row: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(new_row_uuid, |bar, version| {
+                        bar.child(
+                            Button::new("new-row-uuid")
+                                .label(version.label())
+                                .tooltip("UUID version Add Row generates for required UUID columns")
+                                .small()
+                                .ghost()
+                                .disabled(!can_write)
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.toggle_new_row_uuid(cx);
+                                    cx.notify();
+                                }))
+                        )
+                    })
+                    .child(
+                        Button::new("duplicate-row")
+                            .icon(IconName::Copy)
+                            .label("Duplicate")
+                            .tooltip("Duplicate selected row")
+                            .small()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.duplicate_selected_row(cx) {
+                                    tracing::error!("Failed to duplicate row: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("delete-row")
+                            .icon(IconName::Close)
+                            .label("Delete")
+                            .small()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.delete_selected_row(cx) {
+                                    tracing::error!("Failed to delete row: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("copy-rows")
+                            .icon(IconName::Copy)
+                            .label("Copy Rows")
+                            .tooltip("Copy the selected rows for a spreadsheet or another Pulsar editor")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.copy_rows(cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("paste-rows")
+                            .icon(IconName::Copy)
+                            .label("Paste Rows")
+                            .tooltip("Stage clipboard rows as inserts, matching columns by header")
+                            .small()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.paste_rows(cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(Divider::vertical().h_6())
+                    .child(
+                        Button::new("copy-as-insert")
+                            .icon(IconName::Code)
+                            .label("Copy as SQL")
+                            .tooltip("Copy selected row as INSERT statement")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.copy_row_as_sql(cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("profile-table")
+                            .icon(IconName::Table)
+                            .label("Profile")
+                            .tooltip("Write a column profiling report for this table")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.profile_active_table() {
+                                    tracing::error!("Failed to profile table: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(Divider::vertical().h_6())
+                    .child(
+                        Button::new("refresh")
+                            .icon(IconName::Refresh)
+                            .label("Refresh")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.refresh_data(cx) {
+                                    tracing::error!("Failed to refresh: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("throwaway-copy")
+                            .icon(IconName::Copy)
+                            .label("Throwaway Copy")
+                            .tooltip("Snapshot the database to a temporary file and open that instead, for risky experiments")
+                            .small()
+                            .outline()
+                            .disabled(self.database_path.is_none())
+                            .on_click(cx.listener(|editor, _, window, cx| {
+                                if let Err(e) = editor.open_throwaway_copy(window, cx) {
+                                    tracing::error!("Failed to open throwaway copy: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        div()
+                            .w_32()
+                            .child(TextInput::new(&self.goto_input).xsmall())
+                    )
+                    .child(
+                        Button::new("go-to-row")
+                            .label("Go to row")
+                            .tooltip("Seek to the row with this primary key (comma-separated if composite), or #rowid")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.go_to_row(cx) {
+                                    tracing::error!("Failed to go to row: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("keyset-paging")
+                            .label("Pages")
+                            .tooltip("Page through the table by rowid instead of scrolling, which stays fast deep into huge tables")
+                            .small()
+                            .when(is_paged, |b| b.primary())
+                            .when(!is_paged, |b| b.outline())
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_paging(cx) {
+                                    tracing::error!("Failed to switch paging: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when(is_paged, |toolbar| {
+                        toolbar
+                            .child(
+                                Button::new("prev-page")
+                                    .icon(IconName::ChevronLeft)
+                                    .tooltip("Previous page")
+                                    .small()
+                                    .outline()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.turn_page(false, cx) {
+                                            tracing::error!("Failed to load page: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("next-page")
+                                    .icon(IconName::ChevronRight)
+                                    .tooltip("Next page")
+                                    .small()
+                                    .outline()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.turn_page(true, cx) {
+                                            tracing::error!("Failed to load page: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                div()
+                                    .w_24()
+                                    .child(TextInput::new(&self.page_jump_input).xsmall())
+                            )
+                            .child(
+                                Button::new("jump-to-rowid")
+                                    .label("Jump")
+                                    .tooltip("Start the page at this rowid")
+                                    .small()
+                                    .outline()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.jump_to_rowid(cx) {
+                                            tracing::error!("Failed to jump to rowid: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    })
+                    .child(
+                        Button::new("export-filtered")
+                            .icon(IconName::Download)
+                            .label("Export Filtered")
+                            .tooltip("Export the rows matching the current filter in the format set under table export")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.export_active_table(false, cx) {
+                                    tracing::error!("Failed to export rows: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("export-selected")
+                            .icon(IconName::Download)
+                            .label("Export Selected")
+                            .tooltip("Export the selected rows in the format set under table export")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.export_active_table(true, cx) {
+                                    tracing::error!("Failed to export rows: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when(cfg!(feature = "parquet"), |bar| {
+                        bar.child(
+                            Button::new("export-parquet")
+                                .icon(IconName::Download)
+                                .label("Export Parquet")
+                                .tooltip("Export the rows matching the current filter as a Parquet file")
+                                .small()
+                                .outline()
+                                .disabled(!is_table_tab)
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    if let Err(e) = editor.export_active_table_to_parquet(cx) {
+                                        tracing::error!("Failed to export Parquet: {}", e);
+                                    }
+                                    cx.notify();
+                                }))
+                        )
+                    })
+                    .child(
+                        Button::new("report-table")
+                            .icon(IconName::Download)
+                            .label("Report")
+                            .tooltip("Write a printable HTML report of the filtered rows")
+                            .small()
+                            .outline()
+                            .disabled(!is_table_tab)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.report_active_table(cx) {
+                                    tracing::error!("Failed to write report: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("export-all")
+                            .icon(IconName::Download)
+                            .label("Export All")
+                            .tooltip("Export every table in the format set under table export")
+                            .small()
+                            .outline()
+                            .disabled(self.available_tables.is_empty())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.export_all_tables() {
+                                    tracing::error!("Failed to export tables: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(Divider::vertical().h_6())
+                    .child(
+                        Button::new("new-query")
+                            .icon(IconName::Code)
+                            .label("New Query")
+                            .small()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, window, cx| {
+                                editor.open_query_tab(window, cx);
+                            }))
+                    )
+                    .child(
+                        Button::new("change-freeze")
+                            .label(if self.db.is_frozen() { "Lift Freeze" } else { "Freeze" })
+                            .tooltip("Block every write to this database for review; reads, exports and analysis still work")
+                            .small()
+                            .when(self.db.is_frozen(), |b| b.primary())
+                            .when(!self.db.is_frozen(), |b| b.outline())
+                            .disabled(self.db.is_read_only() && !self.db.is_frozen())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_freeze(cx) {
+                                    tracing::error!("Failed to toggle change freeze: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("foreign-keys-pragma")
+                            .label(if foreign_keys { "Foreign Keys: On" } else { "Foreign Keys: Off" })
+                            .tooltip("PRAGMA foreign_keys: whether this connection enforces REFERENCES constraints on writes")
+                            .small()
+                            .when(foreign_keys, |b| b.primary())
+                            .when(!foreign_keys, |b| b.outline())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_foreign_keys() {
+                                    tracing::error!("Failed to toggle foreign keys: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("record-changes")
+                            .icon(IconName::Refresh)
+                            .label(if self.recording_changes { "Stop Recording" } else { "Record Changes" })
+                            .tooltip("Log every insert, update and delete, including other programs', as a timeline")
+                            .small()
+                            .when(self.recording_changes, |b| b.primary())
+                            .when(!self.recording_changes, |b| b.outline())
+                            .disabled(self.db.is_read_only() || self.available_tables.is_empty())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_recording() {
+                                    tracing::error!("Failed to toggle change recording: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("migration-assistant")
+                            .label(if self.migration.is_some() { "Close Migration" } else { "Migrate From…" })
+                            .tooltip("Copy data from a database with the old schema, mapping tables and columns")
+                            .small()
+                            .when(self.migration.is_some(), |b| b.primary())
+                            .when(self.migration.is_none(), |b| b.outline())
+                            .disabled(self.db.is_read_only())
+                            .on_click(cx.listener(|editor, _, window, cx| {
+                                if let Err(e) = editor.toggle_migration(window, cx) {
+                                    tracing::error!("Failed to refresh after migration: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(Divider::vertical().h_6())
+                    .children(LayoutPreset::ALL.iter().enumerate().map(|(i, preset)| {
+                        let preset = *preset;
+                        let active = self.settings.layout == preset;
+                        Button::new(("layout", i))
+                            .label(preset.label())
+                            .tooltip("Rearrange the panels for this kind of work")
+                            .xsmall()
+                            .when(active, |b| b.primary())
+                            .when(!active, |b| b.ghost())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.apply_layout(preset, cx);
+                            }))
+                    }))
+                    .child(Divider::vertical().h_6())
+                    .children(RowDensity::ALL.iter().enumerate().map(|(i, density)| {
+                        let density = *density;
+                        let active = self.settings.density == density;
+                        Button::new(("density", i))
+                            .label(density.label())
+                            .tooltip("Row density of the data grids")
+                            .xsmall()
+                            .when(active, |b| b.primary())
+                            .when(!active, |b| b.ghost())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.set_density(density, cx);
+                            }))
+                    }))
+                    .child(
+                        Button::new("zoom-out")
+                            .label("−")
+                            .tooltip("Smaller grid text")
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.settings.zoom_percent <= crate::settings::MIN_ZOOM_PERCENT)
+                            .on_click(cx.listener(|editor, _, _, cx| editor.zoom_by(-1, cx)))
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{}%", self.settings.zoom_percent))
+                    )
+                    .child(
+                        Button::new("zoom-in")
+                            .label("+")
+                            .tooltip("Larger grid text")
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.settings.zoom_percent >= crate::settings::MAX_ZOOM_PERCENT)
+                            .on_click(cx.listener(|editor, _, _, cx| editor.zoom_by(1, cx)))
+                    )
+                    .child(
+                        Button::new("high-contrast")
+                            .label("High Contrast")
+                            .tooltip("Full-strength text, borders and selection in the grids")
+                            .xsmall()
+                            .when(self.settings.high_contrast, |b| b.primary())
+                            .when(!self.settings.high_contrast, |b| b.ghost())
+                            .on_click(cx.listener(|editor, _, _, cx| editor.toggle_high_contrast(cx)))
+                    )
+                    .child(Divider::vertical().h_6())
+                    .children(KeyBindingMode::ALL.iter().enumerate().map(|(i, mode)| {
+                        let mode = *mode;
+                        let active = self.settings.key_bindings == mode;
+                        Button::new(("key-bindings", i))
+                            .label(mode.label())
+                            .tooltip("Keybindings of the query editor")
+                            .xsmall()
+                            .when(active, |b| b.primary())
+                            .when(!active, |b| b.ghost())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.set_key_bindings(mode, cx);
+                            }))
+                    }))
+                    .child(Divider::vertical().h_6())
+                    .child(
+                        Button::new("transactional-editing")
+                            .label("Transactional")
+                            .tooltip("Hold grid edits in one transaction until Commit; off, each edit commits as it's made")
+                            .xsmall()
+                            .when(transactional, |b| b.primary())
+                            .when(!transactional, |b| b.ghost())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.set_transactional_editing(!transactional, cx);
+                            }))
+                    )
+                    .when(transactional, |this| {
+                        this.child(
+                            div()
+                                .px_1()
+                                .rounded_sm()
+                                .text_xs()
+                                .when(held > 0, |d| d.bg(cx.theme().warning.opacity(0.2)))
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!("{} pending", held))
+                        )
+                        .child(
+                            Button::new("review-held-changes")
+                                .label("Review")
+                                .tooltip("List the pending changes, reverting any one of them")
+                                .xsmall()
+                                .when(self.show_held_changes, |b| b.primary())
+                                .when(!self.show_held_changes, |b| b.ghost())
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.show_held_changes = !editor.show_held_changes;
+                                    cx.notify();
+                                }))
+                        )
+                        .child(
+                            Button::new("commit-edits")
+                                .label("Commit")
+                                .xsmall()
+                                .outline()
+                                .disabled(held == 0)
+                                .on_click(cx.listener(|editor, _, _, cx| editor.commit_edits(cx)))
+                        )
+                        .child(
+                            Button::new("rollback-edits")
+                                .label(if self.rollback_pending { "Confirm Rollback" } else { "Rollback" })
+                                .tooltip("Undo every pending edit")
+                                .xsmall()
+                                .when(self.rollback_pending, |b| b.primary())
+                                .when(!self.rollback_pending, |b| b.ghost())
+                                .disabled(held == 0)
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    if editor.rollback_pending {
+                                        editor.rollback_edits(cx);
+                                    } else {
+                                        editor.rollback_pending = true;
+                                        cx.notify();
+                                    }
+                                }))
+                        )
+                    })
+                    .when_some(self.transaction_message.clone(), |this, message| {
+                        this.child(div().text_xs().text_color(cx.theme().red).child(message))
+                    })
+            )
+    }
+
+    /// What the editor's own connections have been doing: statement rate, totals
+    /// and the slowest statements
+    fn render_activity_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let snapshot = self.show_activity.then(crate::activity::snapshot);
+
+        v_flex()
+            .w_full()
+            .gap_px()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        div()
+                            .id("toggle-activity")
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .cursor_pointer()
+                            .child(if self.show_activity { "▾ activity" } else { "▸ activity" })
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.show_activity = !editor.show_activity;
+                                cx.notify();
+                            }))
+                    )
+                    .when(self.show_activity, |header| {
+                        header.child(
+                            h_flex()
+                                .child(
+                                    Button::new("refresh-activity")
+                                        .icon(IconName::Refresh)
+                                        .tooltip("Refresh activity")
+                                        .xsmall()
+                                        .ghost()
+                                        .on_click(cx.listener(|_, _, _, cx| cx.notify()))
+                                )
+                                .child(
+                                    Button::new("reset-activity")
+                                        .icon(IconName::Close)
+                                        .xsmall()
+                                        .ghost()
+                                        .tooltip("Reset counters")
+                                        .on_click(cx.listener(|_, _, _, cx| {
+                                            crate::activity::reset();
+                                            cx.notify();
+                                        }))
+                                )
+                        )
+                    })
+            )
+            .when_some(snapshot, |section, snapshot| {
+                section
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().foreground)
+                            .child(format!(
+                                "{:.1} statements/s, {} total in {:.0} ms, {} rows read",
+                                snapshot.statements_per_second,
+                                snapshot.total_statements,
+                                snapshot.total_time.as_secs_f64() * 1000.0,
+                                snapshot.rows_read
+                            ))
+                    )
+                    .children(snapshot.slowest.iter().map(|statement| {
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(format!(
+                                "{:.1} ms  {}",
+                                statement.duration.as_secs_f64() * 1000.0,
+                                statement.sql
+                            ))
+                    }))
+            })
+    }
+
+    /// Timeline of recorded changes, newest first
+    fn render_changes_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let visible = self.recording_changes || !self.captured_changes.is_empty();
+
+        v_flex()
+            .w_full()
+            .gap_px()
+            .px_4()
+            .when(visible, |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "changes ({}){}",
+                                        self.captured_changes.len(),
+                                        if self.recording_changes { ", recording" } else { "" }
+                                    ))
+                            )
+                            .child(
+                                Button::new("refresh-changes")
+                                    .icon(IconName::Refresh)
+                                    .xsmall()
+                                    .ghost()
+                                    .disabled(!self.recording_changes)
+                                    .tooltip("Load changes logged since the last refresh")
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.refresh_captured_changes() {
+                                            tracing::error!("Failed to read captured changes: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(self.recording_message.clone(), |this, message| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child(message))
+                    })
+                    .child(
+                        div()
+                            .id("change-timeline")
+                            .max_h(px(200.0))
+                            .overflow_y_scroll()
+                            .children(
+                                self.captured_changes
+                                    .iter()
+                                    .rev()
+                                    .map(|change| {
+                                        div()
+                                            .text_xs()
+                                            .text_color(cx.theme().foreground)
+                                            .child(change.describe())
+                                    })
+                                    .collect::<Vec<_>>()
+                            )
+                    )
+            })
+    }
+
+    /// Content hashes of the active table's rows, for sync pipelines
+    fn render_row_hash_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(self.active_table_name(), |section, table_name| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("row hashes: {} (column, or blank for shadow table)", table_name))
+                    )
+                    .child(TextInput::new(&self.hash_column_input).w_full().text_xs())
+                    .child(
+                        Button::new("store-row-hashes")
+                            .label("Store hashes")
+                            .tooltip("Hash every row's cells and keep the hashes for later comparison")
+                            .xsmall()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.store_row_hashes(cx) {
+                                    tracing::error!("Failed to store row hashes: {}", e);
+                                    editor.hash_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(TextInput::new(&self.hash_compare_input).w_full().text_xs())
+                    .child(
+                        Button::new("compare-row-hashes")
+                            .label("Compare with database")
+                            .tooltip("Compare against the same table's hashes in another database file")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.compare_row_hashes(cx) {
+                                    tracing::error!("Failed to compare row hashes: {}", e);
+                                    editor.hash_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(self.hash_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// Compare schema with another database file, listed table by table
+    fn render_schema_diff_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let line = |text: String, color: Hsla| div().pl_2().text_xs().text_color(color).child(text);
+        let (added, removed, changed, muted) = (
+            cx.theme().success,
+            cx.theme().red,
+            cx.theme().warning,
+            cx.theme().muted_foreground,
+        );
+        let mut lines = Vec::new();
+        if let Some(diff) = &self.schema_diff {
+            lines.extend(diff.tables_added.iter().map(|t| line(format!("+ table {}", t), added)));
+            lines.extend(diff.tables_removed.iter().map(|t| line(format!("− table {}", t), removed)));
+            for table in &diff.tables {
+                lines.push(line(format!("~ table {}", table.table_name), changed));
+                lines.extend(table.columns_added.iter().map(|c| {
+                    line(format!("    + {} {}", c.name, schema_diff::describe_column(c)), added)
+                }));
+                lines.extend(table.columns_removed.iter().map(|c| line(format!("    − {}", c.name), removed)));
+                lines.extend(table.columns_changed.iter().map(|(before, after)| {
+                    line(
+                        format!(
+                            "    ~ {}: {} → {}",
+                            after.name,
+                            schema_diff::describe_column(before),
+                            schema_diff::describe_column(after)
+                        ),
+                        changed,
+                    )
+                }));
+                if table.reordered {
+                    lines.push(line("    columns reordered".to_string(), muted));
+                }
+                if table.definition_changed {
+                    lines.push(line("    table constraints changed".to_string(), muted));
+                }
+            }
+            lines.extend(diff.objects.iter().map(|object| {
+                let (mark, color) = match object.change {
+                    ObjectChange::Added => ("+", added),
+                    ObjectChange::Removed => ("−", removed),
+                    ObjectChange::Changed { .. } => ("~", changed),
+                };
+                line(format!("{} {} {}", mark, object.kind, object.name), color)
+            }));
+        }
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("compare with… (path to the other .db, e.g. another save)")
+            )
+            .child(TextInput::new(&self.schema_compare_input).w_full().text_xs())
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("compare-schema")
+                            .label("Compare Schema")
+                            .tooltip("Diff tables, columns, indexes, views and triggers; the other file is the before side")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.compare_schema(cx) {
+                                    tracing::error!("Failed to compare schema: {}", e);
+                                    editor.schema_diff_message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("compare-data")
+                            .label("Compare Data")
+                            .tooltip("Diff every table's rows against the other file, this database being the before side, and write a Markdown report")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.compare_data(cx) {
+                                    tracing::error!("Failed to compare data: {}", e);
+                                    editor.schema_diff_message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("generate-migration")
+                            .label("Migration Script")
+                            .tooltip("Preview SQL that turns the other file's schema into this one, rebuilding tables where needed")
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.schema_diff.as_ref().is_none_or(|d| d.is_empty()))
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.generate_migration_script(cx) {
+                                    tracing::error!("Failed to generate migration script: {}", e);
+                                    editor.schema_diff_message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("write-schema-diff")
+                            .label("Write Report")
+                            .tooltip("Save the diff, with changed SQL side by side, as Markdown")
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.schema_diff.as_ref().is_none_or(|d| d.is_empty()))
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.write_schema_diff() {
+                                    tracing::error!("Failed to write schema diff: {}", e);
+                                    editor.schema_diff_message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .when_some(self.schema_diff_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(message)
+                )
+            })
+            .children(lines)
+            .when_some(self.migration_script.clone(), |section, script| {
+                section
+                    .child(
+                        div()
+                            .id("migration-script")
+                            .max_h(px(240.))
+                            .overflow_y_scroll()
+                            .p_2()
+                            .rounded_md()
+                            .bg(cx.theme().muted.opacity(0.3))
+                            .text_xs()
+                            .font_family("monospace")
+                            .child(script)
+                    )
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("save-migration")
+                                    .label("Save .sql")
+                                    .xsmall()
+                                    .outline()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.save_migration_script() {
+                                            tracing::error!("Failed to save migration script: {}", e);
+                                            editor.schema_diff_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("run-migration")
+                                    .label(if self.migration_run_pending { "Confirm Run" } else { "Run on Other" })
+                                    .tooltip("Run the script against the other file in one transaction")
+                                    .xsmall()
+                                    .when(self.migration_run_pending, |b| b.primary())
+                                    .when(!self.migration_run_pending, |b| b.ghost())
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.run_migration_script(cx) {
+                                            tracing::error!("Failed to run migration script: {}", e);
+                                            editor.schema_diff_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+            })
+    }
+
+    /// Share saved queries, presets and profiles, resolving conflicts on import
+    fn render_bundle_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_file = self.database_path.is_some();
+        let conflicts = self
+            .bundle_merge
+            .as_ref()
+            .map(|merge| merge.conflicts.clone())
+            .unwrap_or_default();
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("metadata bundle")
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("export-bundle")
+                            .label("Export")
+                            .tooltip("Write every sidecar of this database into one file")
+                            .xsmall()
+                            .outline()
+                            .disabled(!has_file)
+                            .on_click(cx.listener(|editor, _, window, cx| {
+                                if let Err(e) = editor.export_metadata_bundle(window, cx) {
+                                    tracing::error!("Failed to export metadata bundle: {}", e);
+                                    editor.bundle_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("import-bundle")
+                            .label("Import")
+                            .tooltip("Merge the bundle at the path below into this database's sidecars")
+                            .xsmall()
+                            .outline()
+                            .disabled(!has_file || self.bundle_merge.is_some())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.import_metadata_bundle(cx) {
+                                    tracing::error!("Failed to import metadata bundle: {}", e);
+                                    editor.bundle_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(TextInput::new(&self.bundle_path_input).w_full().text_xs())
+            .children(conflicts.iter().enumerate().map(|(i, conflict)| {
+                let take_theirs = conflict.take_theirs;
+                v_flex()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().foreground)
+                            .child(conflict.describe())
+                    )
+                    .child(
+                        Button::new(("bundle-conflict", i))
+                            .label(if take_theirs { "Use theirs" } else { "Keep mine" })
+                            .xsmall()
+                            .when(take_theirs, |b| b.primary())
+                            .when(!take_theirs, |b| b.ghost())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                if let Some(conflict) = editor.bundle_merge.as_mut().and_then(|m| m.conflicts.get_mut(i)) {
+                                    conflict.take_theirs = !conflict.take_theirs;
+                                }
+                                cx.notify();
+                            }))
+                    )
+            }))
+            .when(!conflicts.is_empty(), |section| {
+                section.child(
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Button::new("apply-bundle")
+                                .label("Apply merge")
+                                .xsmall()
+                                .primary()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    if let Err(e) = editor.apply_metadata_bundle() {
+                                        tracing::error!("Failed to merge metadata bundle: {}", e);
+                                        editor.bundle_message = Some(e.to_string());
+                                    }
+                                    cx.notify();
+                                }))
+                        )
+                        .child(
+                            Button::new("cancel-bundle")
+                                .label("Cancel")
+                                .xsmall()
+                                .ghost()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.bundle_merge = None;
+                                    editor.bundle_message = None;
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
+            .when_some(self.bundle_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(message)
+                )
+            })
+    }
+
+    /// Automatic created/updated stamps on rows the grid writes, and who they name
+    fn render_provenance_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let enabled = self.provenance.enabled;
+        let columns = [&self.provenance.created_at, &self.provenance.updated_at, &self.provenance.updated_by]
+            .into_iter()
+            .filter(|c| !c.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("provenance")
+            )
+            .child(
+                Button::new("toggle-provenance")
+                    .label("Stamp edits")
+                    .tooltip(format!("Keep {} current on rows the editor writes, in tables that have them", columns))
+                    .xsmall()
+                    .when(enabled, |b| b.primary())
+                    .when(!enabled, |b| b.ghost())
+                    .disabled(self.database_path.is_none() || self.db.is_read_only())
+                    .on_click(cx.listener(move |editor, _, _, cx| {
+                        editor.set_provenance_enabled(!enabled, cx);
+                    }))
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(TextInput::new(&self.identity_input).w_full().text_xs())
+                    .child(
+                        Button::new("set-identity")
+                            .label("Set")
+                            .tooltip("Your name in updated_by; the login name when left empty")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.set_editor_identity(cx);
+                            }))
+                    )
+            )
+            .when_some(self.provenance_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(message)
+                )
+            })
+    }
+
+    /// Most and never queried tables, from the accumulated query history
+    fn render_usage_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("table usage")
+                    )
+                    .child(
+                        Button::new("analyze-usage")
+                            .label("Analyze")
+                            .tooltip("Rank tables and columns by how often queries touch them")
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.database_path.is_none())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.analyze_usage() {
+                                    tracing::error!("Failed to analyze table usage: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .when_some(self.usage_report.as_ref(), |section, report| {
+                let unused: Vec<String> = report.unused_tables().map(|t| t.table.clone()).collect();
+                section
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{} recorded queries", report.queries))
+                    )
+                    .children(report.tables.iter().filter(|t| t.queries > 0).take(5).map(|table| {
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().foreground)
+                            .child(format!("{}: {} queries", table.table, table.queries))
+                    }))
+                    .when(!unused.is_empty(), |section| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().warning)
+                                .child(format!("Never queried: {}", unused.join(", ")))
+                        )
+                    })
+            })
+    }
+
+    /// Dump the database as SQL, or record writes as a replayable script and replay one
+    fn render_script_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let recording = crate::session_script::is_recording();
+        let can_write = !self.db.is_read_only();
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("sql scripts")
+            )
+            .child(
+                Button::new("dump-database")
+                    .label("Dump database to .sql")
+                    .tooltip("Write schema and data as one executable SQL script; run it from a query tab to restore")
+                    .xsmall()
+                    .outline()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Err(e) = editor.dump_database() {
+                            tracing::error!("Failed to dump database: {}", e);
+                            editor.script_message = Some(e.to_string());
+                        }
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("record-script")
+                    .label(if recording { "Stop and save script" } else { "Record script" })
+                    .tooltip("Capture every write, with dates and repeated values as parameters")
+                    .xsmall()
+                    .when(recording, |b| b.primary())
+                    .when(!recording, |b| b.outline())
+                    .disabled(!can_write)
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Err(e) = editor.toggle_script_recording() {
+                            tracing::error!("Failed to save session script: {}", e);
+                            editor.script_message = Some(e.to_string());
+                        }
+                        cx.notify();
+                    }))
+            )
+            .child(TextInput::new(&self.script_path_input).w_full().text_xs())
+            .child(
+                Button::new("replay-script")
+                    .label("Replay script")
+                    .tooltip("Run a recorded script in one transaction, using the parameter values in its header")
+                    .xsmall()
+                    .outline()
+                    .disabled(!can_write || recording)
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Err(e) = editor.replay_script(cx) {
+                            tracing::error!("Failed to replay script: {}", e);
+                            editor.script_message = Some(e.to_string());
+                        }
+                        cx.notify();
+                    }))
+            )
+            .when_some(self.script_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(message)
+                )
+            })
+    }
+
+    /// The active table's columns, reordered by dragging one onto another, with
+    /// toggles for its primary key and multi-column UNIQUE constraints
+    fn render_columns_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let table = self.active_table_name();
+        let schema = table.as_deref().and_then(|t| self.db.get_schema(t));
+        let draft = schema.as_ref().map(|schema| self.key_draft_for(schema));
+        let fields = schema.map(|schema| schema.fields).unwrap_or_default();
+        let can_write = !self.db.is_read_only();
+
+        let mut section = v_flex()
+            .w_full()
+            .gap_px()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .when(table.is_some(), |section| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("columns (drag to reorder)")
+                )
+            })
+            .children(fields.into_iter().enumerate().map(|(index, field)| {
+                let table = table.clone().unwrap_or_default();
+                let dragged = DraggedColumn {
+                    table: table.clone(),
+                    index,
+                    name: field.name.clone(),
+                };
+                let in_key = draft.as_ref().is_some_and(|d| d.primary_key.contains(&field.name));
+                let in_unique = draft.as_ref().is_some_and(|d| d.new_unique.contains(&field.name));
+                let pk_column = field.name.clone();
+                let unique_column = field.name.clone();
+                let hints = self.db.column_hints(&table, &field.name).unwrap_or_default();
+
+                h_flex()
+                    .id(("column-item", index))
+                    .pl_2()
+                    .gap_1()
+                    .text_xs()
+                    .text_color(cx.theme().foreground)
+                    .rounded_md()
+                    .child(
+                        v_flex()
+                            .flex_1()
+                            .child(format!("{} {}", field.name, field.sql_type.to_sql_string()))
+                            .when_some(hints.lineage, |item, lineage| {
+                                item.child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(lineage.describe())
+                                )
+                            })
+                            .when(hints.tags, |item| {
+                                item.child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child("tags")
+                                )
+                            })
+                            .when(hints.markdown, |item| {
+                                item.child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child("markdown")
+                                )
+                            })
+                            .when_some(hints.range, |item, range| {
+                                item.child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(format!("range {}", range.describe()))
+                                )
+                            })
+                            .when_some(hints.pattern, |item, pattern| {
+                                item.child(
+                                    div()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(pattern.describe())
+                                )
+                            })
+                    )
+                    .child(
+                        Button::new(("key-pk", index))
+                            .label("PK")
+                            .xsmall()
+                            .when(in_key, |b| b.primary())
+                            .when(!in_key, |b| b.ghost())
+                            .disabled(!can_write)
+                            .tooltip("Part of the primary key")
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.edit_keys(|draft| {
+                                    if let Some(pos) = draft.primary_key.iter().position(|c| c == &pk_column) {
+                                        draft.primary_key.remove(pos);
+                                    } else {
+                                        draft.primary_key.push(pk_column.clone());
+                                    }
+                                });
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new(("key-unique", index))
+                            .label("U")
+                            .xsmall()
+                            .when(in_unique, |b| b.primary())
+                            .when(!in_unique, |b| b.ghost())
+                            .disabled(!can_write)
+                            .tooltip("Select columns for a new UNIQUE constraint")
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.edit_keys(|draft| {
+                                    if let Some(pos) = draft.new_unique.iter().position(|c| c == &unique_column) {
+                                        draft.new_unique.remove(pos);
+                                    } else {
+                                        draft.new_unique.push(unique_column.clone());
+                                    }
+                                });
+                                cx.notify();
+                            }))
+                    )
+                    .when(can_write, |item| {
+                        item.cursor_grab()
+                            .on_drag(dragged, |dragged, _, _, cx| cx.new(|_| dragged.clone()))
+                            .drag_over::<DraggedColumn>(|style, _, _, cx| style.bg(cx.theme().accent.opacity(0.2)))
+                            .on_drop(cx.listener(move |editor, dragged: &DraggedColumn, _, cx| {
+                                if dragged.table == table {
+                                    if let Err(e) = editor.move_column(&table, dragged.index, index) {
+                                        tracing::error!("Failed to reorder columns: {}", e);
+                                    }
+                                }
+                                cx.notify();
+                            }))
+                    })
+            }));
+
+        let Some(draft) = draft else {
+            return section;
+        };
+
+        for (i, group) in draft.unique.iter().enumerate() {
+            section = section.child(
+                h_flex()
+                    .pl_2()
+                    .gap_1()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(div().flex_1().child(format!("UNIQUE ({})", group.join(", "))))
+                    .child(
+                        Button::new(("remove-unique", i))
+                            .icon(IconName::Close)
+                            .tooltip("Remove this UNIQUE constraint")
+                            .xsmall()
+                            .ghost()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.edit_keys(|draft| {
+                                    draft.unique.remove(i);
+                                });
+                                cx.notify();
+                            }))
+                    )
+            );
+        }
+
+        let changed = self.key_draft.as_ref().is_some_and(|d| d.table == draft.table);
+        section.child(
+            h_flex()
+                .gap_1()
+                .pt_1()
+                .child(
+                    Button::new("add-unique")
+                        .label("Add UNIQUE")
+                        .xsmall()
+                        .outline()
+                        .disabled(!can_write || draft.new_unique.len() < 2)
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            editor.edit_keys(|draft| {
+                                let group = std::mem::take(&mut draft.new_unique);
+                                draft.unique.push(group);
+                            });
+                            cx.notify();
+                        }))
+                )
+                .child(
+                    Button::new("apply-keys")
+                        .label("Apply keys")
+                        .xsmall()
+                        .outline()
+                        .disabled(!can_write || !changed)
+                        .tooltip("Rebuild the table with these keys")
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            if let Err(e) = editor.apply_keys() {
+                                tracing::error!("Failed to change table keys: {}", e);
+                            }
+                            cx.notify();
+                        }))
+                )
+        )
+    }
+
+    /// How the active table identifies rows, with AUTOINCREMENT toggled by rebuild
+    fn render_row_id_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let table = self.active_table_name();
+        let scheme = table.as_deref().and_then(|t| refactor::row_id_scheme(&self.db, t).ok());
+        let can_write = !self.db.is_read_only();
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(scheme.zip(table), |section, (scheme, table)| {
+                let autoincrement = match &scheme {
+                    RowIdScheme::RowidAlias { autoincrement, .. } => Some(*autoincrement),
+                    _ => None,
+                };
+                section
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().foreground)
+                            .child(format!("row ids: {}", scheme.summary()))
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(scheme.explanation())
+                    )
+                    .when(autoincrement == Some(true), |section| {
+                        section.child(self.render_sequence_controls(&table, cx))
+                    })
+                    .when_some(autoincrement, |section, enabled| {
+                        section.child(
+                            Button::new("toggle-autoincrement")
+                                .label(if enabled { "Turn off AUTOINCREMENT" } else { "Turn on AUTOINCREMENT" })
+                                .xsmall()
+                                .outline()
+                                .disabled(!can_write)
+                                .tooltip(if enabled {
+                                    "Rebuilds the table; its sqlite_sequence entry is removed and deleted ids may be reused"
+                                } else {
+                                    "Rebuilds the table; sqlite_sequence starts tracking the highest id"
+                                })
+                                .on_click(cx.listener(move |editor, _, _, cx| {
+                                    match refactor::set_autoincrement(&editor.db, &table, !enabled) {
+                                        Ok(()) => {
+                                            editor.refresh_dependencies();
+                                            tracing::info!("✓ AUTOINCREMENT {} on {}", if enabled { "off" } else { "on" }, table);
+                                        }
+                                        Err(e) => tracing::error!("Failed to change AUTOINCREMENT: {}", e),
+                                    }
+                                    cx.notify();
+                                }))
+                        )
+                    })
+            })
+    }
+
+    /// The table's `sqlite_sequence` entry, editable e.g. to reset it after bulk deletes
+    fn render_sequence_controls(&self, table: &str, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = self.db.sequence_value(table).ok().flatten();
+        let max_id = self.db.estimate_row_count(table).unwrap_or(0) as i64;
+        let typed = self.sequence_input.read(cx).value().trim().parse::<i64>().ok();
+        let warning = typed.and_then(|value| refactor::sequence_warning(current, max_id, value));
+        let can_write = !self.db.is_read_only();
+        let set_table = table.to_string();
+        let reset_table = table.to_string();
+
+        v_flex()
+            .gap_1()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().foreground)
+                    .child(match current {
+                        Some(seq) => format!("sqlite_sequence: {} (largest id {})", seq, max_id),
+                        None => format!("sqlite_sequence: not set yet (largest id {})", max_id),
+                    })
+            )
+            .child(TextInput::new(&self.sequence_input).w_full().text_xs())
+            .when_some(warning, |controls, warning| {
+                controls.child(div().text_xs().text_color(cx.theme().red).child(warning))
+            })
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("set-sequence")
+                            .label("Set")
+                            .xsmall()
+                            .outline()
+                            .disabled(!can_write || typed.is_none())
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                let value = editor.sequence_input.read(cx).value().trim().parse::<i64>();
+                                match value.map_err(anyhow::Error::from).and_then(|v| editor.db.set_sequence_value(&set_table, v)) {
+                                    Ok(()) => tracing::info!("✓ Updated sqlite_sequence for {}", set_table),
+                                    Err(e) => tracing::error!("Failed to update sqlite_sequence: {}", e),
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("reset-sequence")
+                            .label("Reset to largest id")
+                            .xsmall()
+                            .ghost()
+                            .disabled(!can_write)
+                            .tooltip("Ids above the largest remaining row will be issued again")
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                let result = editor
+                                    .db
+                                    .estimate_row_count(&reset_table)
+                                    .and_then(|max_id| editor.db.set_sequence_value(&reset_table, max_id as i64));
+                                if let Err(e) = result {
+                                    tracing::error!("Failed to reset sqlite_sequence: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+    }
+
+    /// What the focused object depends on and what depends on it; entries can be
+    /// clicked to walk the graph
+    fn render_dependencies_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let focus = self.dependency_focus.clone().or_else(|| self.active_table_name());
+        let node = focus.as_deref().and_then(|name| self.dependencies.node(name));
+
+        let entry = |id: (&'static str, usize), text: String, target: String, cx: &mut Context<Self>| {
+            div()
+                .id(id)
+                .pl_2()
+                .text_xs()
+                .text_color(cx.theme().foreground)
+                .cursor_pointer()
+                .hover(|style| style.bg(cx.theme().accent.opacity(0.1)))
+                .child(text)
+                .on_mouse_down(gpui::MouseButton::Left, cx.listener(move |editor, _, _, cx| {
+                    editor.dependency_focus = Some(target.clone());
+                    editor.drop_plan = None;
+                    cx.notify();
+                }))
+        };
+
+        let mut section = v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("dependencies")
+                    )
+                    .child(
+                        Button::new("refresh-dependencies")
+                            .icon(IconName::Refresh)
+                            .xsmall()
+                            .ghost()
+                            .tooltip("Re-read views, triggers, indexes and foreign keys")
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.refresh_dependencies();
+                                cx.notify();
+                            }))
+                    )
+            );
+
+        let Some(node) = node else {
+            return section.child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("Open a table to see its dependencies")
+            );
+        };
+
+        let depends_on = self.dependencies.depends_on(&node.name);
+        let dependents = self.dependencies.dependents(&node.name);
+        let direct: std::collections::HashSet<&String> = dependents.iter().map(|d| &d.from).collect();
+        let indirect = self
+            .dependencies
+            .transitive_dependents(&node.name)
+            .iter()
+            .filter(|name| !direct.contains(name))
+            .count();
+
+        section = section
+            .child(
+                div()
+                    .text_sm()
+                    .font_semibold()
+                    .text_color(cx.theme().foreground)
+                    .child(format!("{} {}", node.kind, node.name))
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(if depends_on.is_empty() { "Depends on nothing" } else { "Depends on" })
+            );
+        for (i, dep) in depends_on.iter().enumerate() {
+            section = section.child(entry(("depends-on", i), format!("{} {}", dep.kind.label(), dep.to), dep.to.clone(), cx));
+        }
+
+        section = section.child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(if dependents.is_empty() { "Nothing depends on it" } else { "Used by" })
+        );
+        for (i, dep) in dependents.iter().enumerate() {
+            section = section.child(entry(("dependent", i), format!("{} {} it", dep.from, dep.kind.label()), dep.from.clone(), cx));
+        }
+        if indirect > 0 {
+            section = section.child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!("+{} more objects affected indirectly", indirect))
+            );
+        }
+
+        let name = node.name.clone();
+        let planned = self.drop_plan.as_ref().filter(|plan| plan.name == node.name);
+        section = section.child(
+            h_flex()
+                .gap_1()
+                .child(
+                    Button::new("plan-drop")
+                        .label("Plan DROP")
+                        .xsmall()
+                        .outline()
+                        .disabled(self.db.is_read_only())
+                        .tooltip("List dependents and row counts, and prepare a script to review")
+                        .on_click(cx.listener(move |editor, _, _, cx| {
+                            if let Err(e) = editor.plan_drop(&name) {
+                                tracing::error!("Failed to plan drop: {}", e);
+                            }
+                            cx.notify();
+                        }))
+                )
+                .child(
+                    Button::new("drop-cascade")
+                        .label(if self.drop_cascade { "Cascade" } else { "Restrict" })
+                        .xsmall()
+                        .ghost()
+                        .tooltip("Cascade drops dependents first; restrict refuses while any exist")
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            editor.drop_cascade = !editor.drop_cascade;
+                            editor.drop_plan = None;
+                            cx.notify();
+                        }))
+                )
+        );
+
+        if let Some(plan) = planned {
+            let blocked = !plan.cascade && plan.blocking().next().is_some();
+            let triggers = plan.dropped_triggers();
+            section = section
+                .when(!triggers.is_empty(), |section| {
+                    section.child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().warning)
+                            .child(format!("⚠ Also drops its triggers: {}", triggers.join(", ")))
+                    )
+                })
+                .child(
+                    div()
+                        .id("drop-script")
+                        .max_h(px(160.0))
+                        .overflow_y_scroll()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .children(
+                            plan.describe()
+                                .lines()
+                                .chain(plan.script.lines())
+                                .map(|line| div().child(line.to_string()))
+                                .collect::<Vec<_>>()
+                        )
+                )
+                .child(
+                    Button::new("run-drop")
+                        .label("Run script")
+                        .xsmall()
+                        .outline()
+                        .disabled(blocked)
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            if let Err(e) = editor.run_drop() {
+                                tracing::error!("Failed to drop: {}", e);
+                            }
+                            cx.notify();
+                        }))
+                );
+        }
+
+        section
+    }
+
+    /// Generate constant enums from the active lookup table and show whether they drifted
+    fn render_codegen_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_file = self.database_path.is_some();
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(self.active_table_name(), |section, table| {
+                let lookup = self.codegen.get(&table).cloned();
+                let drift = self.codegen_drift.get(&table).cloned();
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(match &lookup {
+                                Some(lookup) => format!(
+                                    "enum {} ({} → {})",
+                                    lookup.enum_name, lookup.name_column, lookup.id_column
+                                ),
+                                None => "enum codegen".to_string(),
+                            })
+                    )
+                    .when_some(drift, |section, reason| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().warning)
+                                .child(format!("⚠ {}", reason))
+                        )
+                    })
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("generate-enum")
+                                    .label(if lookup.is_some() { "Regenerate" } else { "Generate enums" })
+                                    .tooltip("Write Rust, C# and JSON constants under generated/ in the project")
+                                    .xsmall()
+                                    .outline()
+                                    .disabled(!has_file)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.generate_enum() {
+                                            tracing::error!("Failed to generate enum: {}", e);
+                                            editor.codegen_message = Some(e.to_string());
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .when_some(lookup, |row, lookup| {
+                                row.child(
+                                    Button::new("enum-sync-on-save")
+                                        .label("Sync on save")
+                                        .tooltip("Regenerate on save instead of only flagging drift")
+                                        .xsmall()
+                                        .when(lookup.sync_on_save, |b| b.primary())
+                                        .when(!lookup.sync_on_save, |b| b.ghost())
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.toggle_enum_sync() {
+                                                tracing::error!("Failed to save enum generation settings: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("remove-enum")
+                                        .icon(IconName::Close)
+                                        .tooltip("Stop generating this enum")
+                                        .xsmall()
+                                        .ghost()
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.remove_enum() {
+                                                tracing::error!("Failed to save enum generation settings: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                            })
+                    )
+                    .when_some(self.codegen_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// Mark a column as derived so the grid warns before it is edited by hand
+    fn render_lineage_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when(self.active_table_name().is_some(), |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("lineage: column derived from")
+                    )
+                    .child(TextInput::new(&self.lineage_column_input).w_full().text_xs())
+                    .child(TextInput::new(&self.lineage_source_input).w_full().text_xs())
+                    .child(
+                        Button::new("annotate-lineage")
+                            .label("Annotate")
+                            .tooltip("Columns (table.column) or pipeline:<name>; blank clears the annotation")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.annotate_lineage(cx) {
+                                    tracing::error!("Failed to annotate lineage: {}", e);
+                                    editor.lineage_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(self.lineage_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// Restrict a column's edits to a regex or input mask, or a numeric range
+    fn render_pattern_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when(self.active_table_name().is_some(), |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("input rules: column, pattern, range")
+                    )
+                    .child(TextInput::new(&self.pattern_column_input).w_full().text_xs())
+                    .child(TextInput::new(&self.pattern_input).w_full().text_xs())
+                    .child(TextInput::new(&self.range_input).w_full().text_xs())
+                    .child(
+                        Button::new("set-input-pattern")
+                            .label("Set Pattern")
+                            .tooltip("A regex, e.g. [A-Z]{3}-\\d{4}, or a mask, e.g. mask:AAA-9999 (A letter, 9 digit, * any); blank clears it")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.set_input_pattern(cx) {
+                                    tracing::error!("Failed to set input pattern: {}", e);
+                                    editor.pattern_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("set-numeric-range")
+                            .label("Set Range")
+                            .tooltip("min..max, optionally with step n, e.g. 0..100 step 5; blank falls back to the CHECK constraints")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.set_numeric_range(cx) {
+                                    tracing::error!("Failed to set numeric range: {}", e);
+                                    editor.pattern_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("toggle-tag-column")
+                            .label("Tags")
+                            .tooltip("Edit the column's comma-separated values as tag chips, or stop")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_tag_column(cx) {
+                                    tracing::error!("Failed to update tag column: {}", e);
+                                    editor.pattern_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("toggle-markdown-column")
+                            .label("Markdown")
+                            .tooltip("Open the column's values in the large editor with a rendered preview tab, or stop")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_markdown_column(cx) {
+                                    tracing::error!("Failed to update Markdown column: {}", e);
+                                    editor.pattern_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("toggle-geo-pair")
+                            .label("Geo Pair")
+                            .tooltip("With lat, lon in the column box: show the position on a mini map with a picker, or stop")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.toggle_geo_pair(cx) {
+                                    tracing::error!("Failed to update geo pair: {}", e);
+                                    editor.pattern_message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(self.pattern_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// Add, rename, retype or drop a column of the active table by rebuilding it
+    fn render_alter_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+        let confirming = self.alter_drop_pending.is_some();
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when(self.active_table_name().is_some(), |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("alter column: column, then new name, type or definition")
+                    )
+                    .child(TextInput::new(&self.alter_column_input).w_full().text_xs())
+                    .child(TextInput::new(&self.alter_value_input).w_full().text_xs())
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .flex_wrap()
+                            .children([
+                                (AlterKind::Add, "Add", "Add the definition below as a new column, e.g. score REAL NOT NULL DEFAULT 0"),
+                                (AlterKind::Rename, "Rename", "Rename the column, rewriting its indexes and triggers"),
+                                (AlterKind::Retype, "Retype", "Change the column's declared type; values convert as they're copied"),
+                                (AlterKind::Drop, if confirming { "Confirm Drop" } else { "Drop" }, "Drop the column and any index on it"),
+                            ].map(|(kind, label, tooltip)| {
+                                Button::new(("alter-column", kind as usize))
+                                    .label(label)
+                                    .tooltip(tooltip)
+                                    .xsmall()
+                                    .when(kind == AlterKind::Drop && confirming, |b| b.primary())
+                                    .when(kind != AlterKind::Drop || !confirming, |b| b.outline())
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(move |editor, _, window, cx| {
+                                        if let Err(e) = editor.alter_active_column(kind, window, cx) {
+                                            tracing::error!("Failed to alter column: {}", e);
+                                            editor.alter_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            }))
+                    )
+                    .when_some(self.alter_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
     }
 
-    pub fn get_table_stats(&self, cx: &App) -> String {
-        if let Some(active_idx) = self.active_tab_idx {
-            if let Some(tab) = self.open_tabs.get(active_idx) {
-                if let TabType::Table { view, .. } = &tab.tab_type {
-                    return view.read(cx).delegate().get_table_stats();
+    /// The active table's triggers, with their bodies on demand
+    fn render_triggers_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+        let triggers = self
+            .active_table_name()
+            .map(|table| self.db.triggers(&table).unwrap_or_else(|e| {
+                tracing::error!("Failed to list triggers: {}", e);
+                Vec::new()
+            }));
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(triggers, |section, triggers| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("triggers ({})", triggers.len()))
+                            )
+                            .child(
+                                Button::new("new-trigger")
+                                    .label(if self.trigger_editor.is_some() { "Close Editor" } else { "New…" })
+                                    .tooltip("Write a CREATE TRIGGER for this table")
+                                    .xsmall()
+                                    .when(self.trigger_editor.is_some(), |b| b.primary())
+                                    .when(self.trigger_editor.is_none(), |b| b.ghost())
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        if editor.trigger_editor.is_some() {
+                                            editor.close_trigger_editor(cx);
+                                        } else if let Err(e) = editor.open_trigger_editor(None, window, cx) {
+                                            tracing::error!("Failed to open the trigger editor: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .children(triggers.into_iter().enumerate().map(|(ix, trigger)| {
+                        let shown = self.trigger_shown.as_deref() == Some(trigger.name.as_str());
+                        let confirming = self.trigger_drop_pending.as_deref() == Some(trigger.name.as_str());
+                        let show_name = trigger.name.clone();
+                        let edit_name = trigger.name.clone();
+                        let drop_name = trigger.name.clone();
+                        v_flex()
+                            .gap_1()
+                            .child(
+                                h_flex()
+                                    .gap_1()
+                                    .items_center()
+                                    .child(
+                                        div()
+                                            .id(("trigger-name", ix))
+                                            .flex_1()
+                                            .text_xs()
+                                            .text_color(cx.theme().foreground)
+                                            .cursor_pointer()
+                                            .child(format!("{} {}", if shown { "▾" } else { "▸" }, trigger.name))
+                                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                                editor.trigger_shown = if editor.trigger_shown.as_deref() == Some(show_name.as_str()) {
+                                                    None
+                                                } else {
+                                                    Some(show_name.clone())
+                                                };
+                                                cx.notify();
+                                            }))
+                                    )
+                                    .child(
+                                        Button::new(("edit-trigger", ix))
+                                            .label("Edit")
+                                            .xsmall()
+                                            .ghost()
+                                            .disabled(!can_write)
+                                            .on_click(cx.listener(move |editor, _, window, cx| {
+                                                if let Err(e) = editor.open_trigger_editor(Some(edit_name.clone()), window, cx) {
+                                                    tracing::error!("Failed to open trigger: {}", e);
+                                                    editor.trigger_message = Some(format!("Error: {}", e));
+                                                }
+                                                cx.notify();
+                                            }))
+                                    )
+                                    .child(
+                                        Button::new(("drop-trigger", ix))
+                                            .label(if confirming { "Confirm Drop" } else { "Drop" })
+                                            .xsmall()
+                                            .when(confirming, |b| b.primary())
+                                            .when(!confirming, |b| b.ghost())
+                                            .disabled(!can_write)
+                                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                                if let Err(e) = editor.drop_trigger(&drop_name) {
+                                                    tracing::error!("Failed to drop trigger: {}", e);
+                                                    editor.trigger_message = Some(format!("Error: {}", e));
+                                                }
+                                                cx.notify();
+                                            }))
+                                    )
+                            )
+                            .when(shown, |this| {
+                                this.child(
+                                    div()
+                                        .pl_2()
+                                        .text_xs()
+                                        .font_family("monospace")
+                                        .text_color(cx.theme().muted_foreground)
+                                        .children(
+                                            trigger.sql.unwrap_or_default()
+                                                .lines()
+                                                .map(|line| div().child(line.to_string()))
+                                                .collect::<Vec<_>>()
+                                        )
+                                )
+                            })
+                    }))
+                    .when_some(self.trigger_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// The active table's foreign keys, one line per constraint, and a form to add one
+    fn render_foreign_keys_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+        let keys = self
+            .active_table_name()
+            .map(|table| self.db.foreign_keys(&table).unwrap_or_else(|e| {
+                tracing::error!("Failed to list foreign keys: {}", e);
+                Vec::new()
+            }));
+        // Multi-column keys come back as one row per column, sharing an id
+        let keys = keys.map(|keys| {
+            let mut grouped: Vec<Vec<ForeignKey>> = Vec::new();
+            for key in keys {
+                match grouped.iter_mut().find(|group| group[0].id == key.id) {
+                    Some(group) => group.push(key),
+                    None => grouped.push(vec![key]),
                 }
             }
-        }
-        "No table selected".to_string()
+            grouped
+                .into_iter()
+                .map(|group| {
+                    let from: Vec<&str> = group.iter().map(|k| k.column.as_str()).collect();
+                    let to: Vec<&str> = group.iter().map(|k| k.referenced_column.as_deref().unwrap_or("primary key")).collect();
+                    let mut line = format!("{} → {}({})", from.join(", "), group[0].referenced_table, to.join(", "));
+                    if group[0].on_delete != "NO ACTION" {
+                        line.push_str(&format!(" ON DELETE {}", group[0].on_delete));
+                    }
+                    (group[0].id, line)
+                })
+                .collect::<Vec<_>>()
+        });
+        let on_delete = FOREIGN_KEY_ACTIONS[self.fk_on_delete];
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(keys, |section, keys| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("foreign keys ({})", keys.len()))
+                    )
+                    .children(keys.into_iter().enumerate().map(|(ix, (id, line))| {
+                        let confirming = self.fk_drop_pending == Some(id);
+                        h_flex()
+                            .gap_1()
+                            .items_center()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_xs()
+                                    .text_color(cx.theme().foreground)
+                                    .child(line)
+                            )
+                            .child(
+                                Button::new(("drop-foreign-key", ix))
+                                    .label(if confirming { "Confirm Drop" } else { "Drop" })
+                                    .tooltip("Rebuild the table without this constraint")
+                                    .xsmall()
+                                    .when(confirming, |b| b.primary())
+                                    .when(!confirming, |b| b.ghost())
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(move |editor, _, window, cx| {
+                                        if let Err(e) = editor.drop_foreign_key(id, window, cx) {
+                                            tracing::error!("Failed to drop foreign key: {}", e);
+                                            editor.fk_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    }))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("add: columns, then parent or parent(columns)")
+                    )
+                    .child(TextInput::new(&self.fk_columns_input).w_full().text_xs())
+                    .child(TextInput::new(&self.fk_parent_input).w_full().text_xs())
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("fk-on-delete")
+                                    .label(format!("On delete: {}", on_delete))
+                                    .tooltip("What happens to these rows when the row they reference is deleted")
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        editor.fk_on_delete = (editor.fk_on_delete + 1) % FOREIGN_KEY_ACTIONS.len();
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("add-foreign-key")
+                                    .label("Add Key")
+                                    .tooltip("Rebuild the table with the new constraint; fails if existing rows break it")
+                                    .xsmall()
+                                    .outline()
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        if let Err(e) = editor.add_foreign_key(window, cx) {
+                                            tracing::error!("Failed to add foreign key: {}", e);
+                                            editor.fk_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(self.fk_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
     }
 
-
-    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        let is_table_tab = self.active_tab_idx.and_then(|idx| {
-            self.open_tabs.get(idx).map(|tab| matches!(tab.tab_type, TabType::Table { .. }))
-        }).unwrap_or(false);
-
+    fn render_rename_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
         v_flex()
             .w_full()
-            .gap_0()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("rename table or table.column")
+            )
+            .child(TextInput::new(&self.rename_from_input).w_full().text_xs())
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("to")
+            )
+            .child(TextInput::new(&self.rename_to_input).w_full().text_xs())
             .child(
-                // Main toolbar
                 h_flex()
-                    .w_full()
-                    .gap_2()
-                    .p_2()
-                    .bg(cx.theme().muted.opacity(0.3))
-                    .border_b_1()
-                    .border_color(cx.theme().border)
+                    .gap_1()
                     .child(
-                        Button::new("add-row")
-                            .icon(IconName::Plus)
-                            .label("Add Row")
-                            .small()
-                            .primary()
-                            .disabled(!is_table_tab)
+                        Button::new("preview-rename")
+                            .label("Preview")
+                            .xsmall()
+                            .outline()
                             .on_click(cx.listener(|editor, _, _, cx| {
-                                if let Err(e) = editor.add_new_row(cx) {
-                                    tracing::error!("Failed to add row: {}", e);
-                                }
+                                editor.preview_rename(cx);
                                 cx.notify();
                             }))
                     )
                     .child(
-                        Button::new("duplicate-row")
-                            .icon(IconName::Copy)
-                            .label("Duplicate")
-                            .tooltip("Duplicate selected row")
-                            .small()
+                        Button::new("apply-rename")
+                            .label("Rename")
+                            .xsmall()
                             .outline()
-                            .disabled(!is_table_tab)
+                            .disabled(self.db.is_read_only() || self.rename_plan.is_none())
                             .on_click(cx.listener(|editor, _, _, cx| {
-                                if let Err(e) = editor.duplicate_selected_row(cx) {
-                                    tracing::error!("Failed to duplicate row: {}", e);
+                                if let Err(e) = editor.apply_rename(cx) {
+                                    tracing::error!("Failed to rename: {}", e);
+                                    editor.rename_message = Some(format!("Error: {}", e));
                                 }
                                 cx.notify();
                             }))
                     )
+            )
+            .when_some(self.rename_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .id("rename-preview")
+                        .max_h(px(160.0))
+                        .overflow_y_scroll()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .children(message.lines().map(|line| div().child(line.to_string())).collect::<Vec<_>>())
+                )
+            })
+    }
+
+    /// Delete or archive the active table's rows past an age limit
+    fn render_retention_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only() && self.database_path.is_some();
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when_some(self.active_table_name(), |section, table| {
+                let policy = self.retention.get(&table).cloned();
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
                     .child(
-                        Button::new("delete-row")
-                            .icon(IconName::Close)
-                            .label("Delete")
-                            .small()
-                            .outline()
-                            .disabled(!is_table_tab)
-                            .on_click(cx.listener(|editor, _, _, cx| {
-                                if let Err(e) = editor.delete_selected_row(cx) {
-                                    tracing::error!("Failed to delete row: {}", e);
-                                }
-                                cx.notify();
-                            }))
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(match &policy {
+                                Some(policy) => format!("retention: {}", policy.describe()),
+                                None => "retention (timestamp column, max age in days)".to_string(),
+                            })
                     )
-                    .child(Divider::vertical().h_6())
                     .child(
-                        Button::new("copy-as-insert")
-                            .icon(IconName::Code)
-                            .label("Copy as SQL")
-                            .tooltip("Copy selected row as INSERT statement")
-                            .small()
+                        h_flex()
+                            .gap_1()
+                            .child(div().flex_1().child(TextInput::new(&self.retention_column_input).w_full().text_xs()))
+                            .child(div().w_12().child(TextInput::new(&self.retention_days_input).w_full().text_xs()))
+                            .child(
+                                Button::new("set-retention")
+                                    .label(if policy.is_some() { "Update" } else { "Set" })
+                                    .xsmall()
+                                    .outline()
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.set_retention_policy(cx) {
+                                            tracing::error!("Failed to save retention policy: {}", e);
+                                            editor.retention_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(policy, |section, policy| {
+                        section.child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Button::new("run-retention")
+                                        .label("Run now")
+                                        .xsmall()
+                                        .outline()
+                                        .disabled(!can_write)
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.run_retention(cx) {
+                                                tracing::error!("Failed to apply retention: {}", e);
+                                                editor.retention_message = Some(format!("Error: {}", e));
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("retention-archive")
+                                        .label("Archive")
+                                        .tooltip("Move expired rows to the .archive database instead of deleting them")
+                                        .xsmall()
+                                        .when(policy.archive, |b| b.primary())
+                                        .when(!policy.archive, |b| b.ghost())
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.update_retention_policy(|p| p.archive = !p.archive) {
+                                                tracing::error!("Failed to save retention policy: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("retention-on-open")
+                                        .label("On open")
+                                        .tooltip("Apply whenever the database is opened")
+                                        .xsmall()
+                                        .when(policy.run_on_open, |b| b.primary())
+                                        .when(!policy.run_on_open, |b| b.ghost())
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.update_retention_policy(|p| p.run_on_open = !p.run_on_open) {
+                                                tracing::error!("Failed to save retention policy: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("remove-retention")
+                                        .icon(IconName::Close)
+                                        .tooltip("Remove the retention policy")
+                                        .xsmall()
+                                        .ghost()
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            if let Err(e) = editor.remove_retention_policy() {
+                                                tracing::error!("Failed to save retention policy: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                )
+                        )
+                    })
+            })
+            .when_some(self.retention_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .children(message.lines().map(|line| div().child(line.to_string())).collect::<Vec<_>>())
+                )
+            })
+    }
+
+    /// How Export Filtered, Export Selected and Export All write their files
+    fn render_export_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let options = &self.csv_export;
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!("table export (.{})", self.export_kind.format(options).extension()))
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .flex_wrap()
+                    .child(
+                        Button::new("export-kind")
+                            .label(self.export_kind.label())
+                            .tooltip("File format")
+                            .xsmall()
                             .outline()
-                            .disabled(!is_table_tab)
                             .on_click(cx.listener(|editor, _, _, cx| {
-                                editor.copy_row_as_sql(cx);
+                                editor.export_kind = editor.export_kind.next();
                                 cx.notify();
                             }))
                     )
-                    .child(Divider::vertical().h_6())
+                    .when(self.export_kind == ExportKind::Csv, |row| {
+                        row
+                            .child(
+                                Button::new("export-delimiter")
+                                    .label(options.delimiter_label())
+                                    .tooltip("Delimiter; Tab writes TSV")
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        editor.csv_export.next_delimiter();
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("export-quoting")
+                                    .label(options.quoting.label())
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        editor.csv_export.quoting = editor.csv_export.quoting.next();
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("export-header")
+                                    .label(if options.header { "Header" } else { "No header" })
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        editor.csv_export.header = !editor.csv_export.header;
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("export-null")
+                                    .label(options.null_label())
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        editor.csv_export.next_null();
+                                        cx.notify();
+                                    }))
+                            )
+                    })
+            )
+    }
+
+    /// Move rows to and from the companion `.archive` database
+    fn render_archive_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only() && self.database_path.is_some();
+        let archive_name = self
+            .database_path
+            .as_ref()
+            .map(|p| archive::archive_path(p))
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when(self.active_table_name().is_some(), |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
                     .child(
-                        Button::new("refresh")
-                            .icon(IconName::Refresh)
-                            .label("Refresh")
-                            .small()
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("archive to {}", archive_name))
+                    )
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("archive-selected")
+                                    .label("Selected")
+                                    .tooltip("Move the selected rows to the archive")
+                                    .xsmall()
+                                    .outline()
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.archive_active_rows(true, cx) {
+                                            tracing::error!("Failed to archive rows: {}", e);
+                                            editor.archive_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("archive-filtered")
+                                    .label("Filtered")
+                                    .tooltip("Move the rows matching the current filters to the archive")
+                                    .xsmall()
+                                    .outline()
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.archive_active_rows(false, cx) {
+                                            tracing::error!("Failed to archive rows: {}", e);
+                                            editor.archive_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("restore-archived")
+                                    .label("Restore")
+                                    .tooltip("Move archived rows matching the current filters back")
+                                    .xsmall()
+                                    .ghost()
+                                    .disabled(!can_write)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.restore_archived_rows(cx) {
+                                            tracing::error!("Failed to restore archived rows: {}", e);
+                                            editor.archive_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("check-archive")
+                                    .label("Verify")
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.check_archive() {
+                                            tracing::error!("Failed to check archive: {}", e);
+                                            editor.archive_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(self.archive_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            })
+    }
+
+    /// Row-level diff of the active table against another database, and pushing it there
+    fn render_table_sync_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let line = |text: String, color: Hsla| div().pl_2().text_xs().text_color(color).child(text);
+        let mut lines = Vec::new();
+        if let Some(diff) = self.table_sync.as_ref().filter(|d| self.active_table_name().as_deref() == Some(d.table_name.as_str())) {
+            let key = diff.key_columns.join(", ");
+            lines.extend(diff.added.iter().map(|k| line(format!("+ {} {}", key, k), cx.theme().success)));
+            lines.extend(diff.removed.iter().map(|k| line(format!("− {} {}", key, k), cx.theme().red)));
+            for row in &diff.changed {
+                lines.push(line(format!("~ {} {}", key, row.key), cx.theme().warning));
+                lines.extend(row.cells.iter().map(|cell| {
+                    line(
+                        format!("    {}: {} → {}", cell.column, cell.before, cell.after),
+                        cx.theme().muted_foreground,
+                    )
+                }));
+            }
+            let listed = diff.added.len() + diff.removed.len() + diff.changed.len();
+            let total = diff.added_count + diff.removed_count + diff.changed_count;
+            if total > listed {
+                lines.push(line(format!("…and {} more", total - listed), cx.theme().muted_foreground));
+            }
+        }
+        let can_sync = !self.db.is_read_only() && self.table_sync.as_ref().is_some_and(|d| !d.is_empty());
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .when(self.active_table_name().is_some(), |section| {
+                section
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("compare rows with… (path to the target .db)")
+                    )
+                    .child(TextInput::new(&self.sync_target_input).w_full().text_xs())
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("compare-table-rows")
+                                    .label("Compare Rows")
+                                    .tooltip("Match rows by primary key; + only here, − only in the target, ~ different")
+                                    .xsmall()
+                                    .outline()
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.compare_table_rows(cx) {
+                                            tracing::error!("Failed to compare rows: {}", e);
+                                            editor.sync_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("sync-table-to-target")
+                                    .label(if self.sync_pending { "Confirm Sync" } else { "Sync to Target" })
+                                    .tooltip("Make the target's table match this one in a single transaction")
+                                    .xsmall()
+                                    .when(self.sync_pending, |b| b.primary())
+                                    .when(!self.sync_pending, |b| b.ghost())
+                                    .disabled(!can_sync)
+                                    .on_click(cx.listener(|editor, _, _, cx| {
+                                        if let Err(e) = editor.sync_table_to_target(cx) {
+                                            tracing::error!("Failed to sync table: {}", e);
+                                            editor.sync_message = Some(format!("Error: {}", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(self.sync_message.clone(), |section, message| {
+                        section.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+                    .children(lines)
+            })
+    }
+
+    fn render_partition_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_4()
+            .py_2()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("split the open table by column")
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(div().flex_1().child(TextInput::new(&self.partition_column_input).w_full().text_xs()))
+                    .child(
+                        Button::new("preview-split")
+                            .label("Preview")
+                            .xsmall()
                             .outline()
-                            .disabled(!is_table_tab)
+                            .disabled(self.active_table_name().is_none())
                             .on_click(cx.listener(|editor, _, _, cx| {
-                                if let Err(e) = editor.refresh_data(cx) {
-                                    tracing::error!("Failed to refresh: {}", e);
-                                }
+                                editor.preview_split(cx);
                                 cx.notify();
                             }))
                     )
-                    .child(Divider::vertical().h_6())
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("or merge the tables behind view")
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(div().flex_1().child(TextInput::new(&self.partition_view_input).w_full().text_xs()))
                     .child(
-                        Button::new("new-query")
-                            .icon(IconName::Code)
-                            .label("New Query")
-                            .small()
+                        Button::new("preview-merge")
+                            .label("Preview")
+                            .xsmall()
                             .outline()
-                            .on_click(cx.listener(|editor, _, window, cx| {
-                                editor.open_query_tab(window, cx);
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.preview_merge(cx);
+                                cx.notify();
                             }))
                     )
             )
+            .when_some(self.partition_message.clone(), |section, message| {
+                section.child(
+                    div()
+                        .id("partition-preview")
+                        .max_h(px(200.0))
+                        .overflow_y_scroll()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .children(message.lines().map(|line| div().child(line.to_string())).collect::<Vec<_>>())
+                )
+            })
+            .when(self.partition_plan.is_some(), |section| {
+                section.child(
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Button::new("apply-partition")
+                                .label("Run script")
+                                .xsmall()
+                                .outline()
+                                .disabled(!can_write)
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    if let Err(e) = editor.apply_partition() {
+                                        tracing::error!("Failed to repartition: {}", e);
+                                        editor.partition_message = Some(format!("Error: {}", e));
+                                    }
+                                    cx.notify();
+                                }))
+                        )
+                        .child(
+                            Button::new("discard-partition")
+                                .label("Discard")
+                                .xsmall()
+                                .ghost()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.partition_plan = None;
+                                    editor.partition_message = None;
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
     }
 
     fn render_sidebar(&self, cx: &mut Context<Self>) -> impl IntoElement {
@@ -636,26 +5526,235 @@ impl DataTableEditor {
                                     style.hover(|style| style.bg(cx.theme().accent.opacity(0.1)))
                                 })
                                 .child(
-                                    Icon::new(IconName::Table)
+                                    Icon::new(IconName::Table)
+                                        .size_4()
+                                        .when(is_open, |icon| icon.text_color(cx.theme().accent_foreground))
+                                        .when(!is_open, |icon| icon.text_color(cx.theme().foreground))
+                                )
+                                .child(
+                                    div()
+                                        .text_sm()
+                                        .when(is_open, |style| style.text_color(cx.theme().accent_foreground))
+                                        .when(!is_open, |style| style.text_color(cx.theme().foreground))
+                                        .child(table.clone())
+                                )
+                                .on_mouse_down(gpui::MouseButton::Left, cx.listener(move |editor, _, window, cx| {
+                                    if let Err(e) = editor.select_table(table_name.clone(), window, cx) {
+                                        tracing::error!("Failed to select table: {}", e);
+                                    }
+                                }))
+                        }))
+                        .when(!self.available_views.is_empty(), |content| {
+                            content.child(
+                                div()
+                                    .pl(px(28.0))
+                                    .pt_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("views")
+                            )
+                        })
+                        .children(self.available_views.iter().enumerate().map(|(idx, view)| {
+                            let is_open = self.open_tabs.iter().any(|tab| {
+                                matches!(&tab.tab_type, TabType::Table { name, .. } if name == view)
+                            });
+                            let view_name = view.clone();
+                            let edit_name = view.clone();
+
+                            h_flex()
+                                .id(("view-item", idx))
+                                .gap_2()
+                                .h(px(28.0))
+                                .pl(px(28.0))
+                                .pr_3()
+                                .rounded_md()
+                                .cursor_pointer()
+                                .when(is_open, |style| style.bg(cx.theme().accent))
+                                .when(!is_open, |style| {
+                                    style.hover(|style| style.bg(cx.theme().accent.opacity(0.1)))
+                                })
+                                .child(
+                                    Icon::new(IconName::Eye)
                                         .size_4()
                                         .when(is_open, |icon| icon.text_color(cx.theme().accent_foreground))
                                         .when(!is_open, |icon| icon.text_color(cx.theme().foreground))
                                 )
                                 .child(
                                     div()
+                                        .flex_1()
                                         .text_sm()
                                         .when(is_open, |style| style.text_color(cx.theme().accent_foreground))
                                         .when(!is_open, |style| style.text_color(cx.theme().foreground))
-                                        .child(table.clone())
+                                        .child(view.clone())
                                 )
-                                .on_mouse_down(gpui::MouseButton::Left, cx.listener(move |editor, _, window, cx| {
-                                    if let Err(e) = editor.select_table(table_name.clone(), window, cx) {
-                                        tracing::error!("Failed to select table: {}", e);
+                                .child(
+                                    Button::new(("edit-view", idx))
+                                        .label("Edit")
+                                        .tooltip("Edit the view's SELECT")
+                                        .xsmall()
+                                        .ghost()
+                                        .disabled(self.db.is_read_only())
+                                        .on_click(cx.listener(move |editor, _, window, cx| {
+                                            editor.view_editor = None;
+                                            if let Err(e) = editor.toggle_view_editor(Some(edit_name.clone()), window, cx) {
+                                                tracing::error!("Failed to open view: {}", e);
+                                            }
+                                            cx.stop_propagation();
+                                            cx.notify();
+                                        }))
+                                )
+                                .on_click(cx.listener(move |editor, _, window, cx| {
+                                    if let Err(e) = editor.select_table(view_name.clone(), window, cx) {
+                                        tracing::error!("Failed to select view: {}", e);
                                     }
                                 }))
                         }))
+                        .child(
+                            div().pl(px(28.0)).pt_1().child(
+                                Button::new("new-table")
+                                    .label(if self.create_table.is_some() { "Close New Table" } else { "New Table…" })
+                                    .tooltip("Define columns, keys and constraints and create the table")
+                                    .xsmall()
+                                    .when(self.create_table.is_some(), |b| b.primary())
+                                    .when(self.create_table.is_none(), |b| b.ghost())
+                                    .disabled(self.db.is_read_only())
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        if let Err(e) = editor.toggle_create_table(window, cx) {
+                                            tracing::error!("Failed to open the new table: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                        )
+                        .child(
+                            div().pl(px(28.0)).pt_1().child(
+                                Button::new("new-view")
+                                    .label(if self.view_editor.is_some() { "Close View Editor" } else { "New View…" })
+                                    .tooltip("Write a SELECT and save it as a view")
+                                    .xsmall()
+                                    .when(self.view_editor.is_some(), |b| b.primary())
+                                    .when(self.view_editor.is_none(), |b| b.ghost())
+                                    .disabled(self.db.is_read_only())
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        if let Err(e) = editor.toggle_view_editor(None, window, cx) {
+                                            tracing::error!("Failed to open the view editor: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                        )
+                        .child(
+                            div().pl(px(28.0)).pt_1().child(
+                                Button::new("index-manager")
+                                    .label("Indexes…")
+                                    .tooltip("List every table's indexes, create or drop them, and reindex")
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        editor.open_index_manager(window, cx);
+                                        cx.notify();
+                                    }))
+                            )
+                        )
+                        .child(
+                            div().pl(px(28.0)).pt_1().child(
+                                Button::new("er-diagram")
+                                    .label("ER Diagram…")
+                                    .tooltip("Show the tables and the foreign keys between them; click a table to open it")
+                                    .xsmall()
+                                    .ghost()
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        editor.open_er_diagram(window, cx);
+                                        cx.notify();
+                                    }))
+                            )
+                        )
+                        .child(
+                            div().pl(px(28.0)).py_1().child(
+                                Button::new("import-csv")
+                                    .label(if self.csv_import.is_some() { "Close Import" } else { "Import CSV/JSON…" })
+                                    .tooltip("Import a CSV or JSON file into a table, or into a new one")
+                                    .xsmall()
+                                    .when(self.csv_import.is_some(), |b| b.primary())
+                                    .when(self.csv_import.is_none(), |b| b.ghost())
+                                    .disabled(self.db.is_read_only())
+                                    .on_click(cx.listener(|editor, _, window, cx| {
+                                        if let Err(e) = editor.toggle_csv_import(window, cx) {
+                                            tracing::error!("Failed to refresh after import: {}", e);
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                        )
                     })
             )
+            .child(
+                // Database info: application_id / user_version
+                v_flex()
+                    .w_full()
+                    .gap_1()
+                    .px_4()
+                    .py_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_semibold()
+                            .text_color(cx.theme().foreground)
+                            .child(self.db_label.clone())
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("application_id")
+                    )
+                    .child(TextInput::new(&self.application_id_input).w_full().text_xs())
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("user_version")
+                    )
+                    .child(TextInput::new(&self.user_version_input).w_full().text_xs())
+                    .child(
+                        Button::new("apply-db-info")
+                            .label("Apply")
+                            .xsmall()
+                            .outline()
+                            .disabled(self.db.is_read_only())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.apply_database_info(cx) {
+                                    tracing::error!("Failed to update database info: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(self.render_changes_section(cx))
+            .child(self.render_activity_section(cx))
+            .child(self.render_script_section(cx))
+            .child(self.render_row_hash_section(cx))
+            .child(self.render_bundle_section(cx))
+            .child(self.render_provenance_section(cx))
+            .child(self.render_schema_diff_section(cx))
+            .child(self.render_usage_section(cx))
+            .child(self.render_columns_section(cx))
+            .child(self.render_alter_section(cx))
+            .child(self.render_triggers_section(cx))
+            .child(self.render_foreign_keys_section(cx))
+            .child(self.render_lineage_section(cx))
+            .child(self.render_pattern_section(cx))
+            .child(self.render_codegen_section(cx))
+            .child(self.render_row_id_section(cx))
+            .child(self.render_dependencies_section(cx))
+            .child(self.render_rename_section(cx))
+            .child(self.render_partition_section(cx))
+            .child(self.render_retention_section(cx))
+            .child(self.render_archive_section(cx))
+            .child(self.render_table_sync_section(cx))
+            .child(self.render_export_section(cx))
             .child(
                 // Footer with database path
                 div()
@@ -668,7 +5767,15 @@ impl DataTableEditor {
                         div()
                             .text_xs()
                             .text_color(cx.theme().muted_foreground)
-                            .child(db_name)
+                            .child(if self.db.is_quarantined() {
+                                format!("{} (safe mode)", db_name)
+                            } else if self.db.is_frozen() {
+                                format!("{} (frozen)", db_name)
+                            } else if self.db.is_read_only() {
+                                format!("{} (read-only)", db_name)
+                            } else {
+                                db_name
+                            })
                     )
             )
     }
@@ -680,8 +5787,8 @@ impl Panel for DataTableEditor {
         "Database Editor"
     }
 
-    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
-        let title = if let Some(path) = &self.database_path {
+    fn title(&self, _window: &Window, cx: &App) -> AnyElement {
+        let title = if let Some(path) = self.throwaway_of.as_ref().or(self.database_path.as_ref()) {
             path.file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("Database")
@@ -691,7 +5798,8 @@ impl Panel for DataTableEditor {
         };
 
         div()
-            .child(title)
+            .when(self.throwaway_of.is_some(), |d| d.text_color(cx.theme().warning))
+            .child(if self.throwaway_of.is_some() { format!("{} (throwaway copy)", title) } else { title })
             .into_any_element()
     }
 
@@ -705,15 +5813,705 @@ impl Panel for DataTableEditor {
 
 impl DataTableEditor {
     /// Plugin-specific save method
-    pub fn plugin_save(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> Result<(), plugin_editor_api::PluginError> {
-        // Database changes are auto-committed, so no explicit save needed
-        // This could be extended to save query tabs or editor state in the future
+    pub fn plugin_save(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> Result<(), plugin_editor_api::PluginError> {
+        // Database changes are auto-committed, so saving only runs the project's hooks,
+        // clears the change markers and brings generated enums up to date
         tracing::debug!("Table editor save called (changes auto-committed)");
+        let changed = self.changed_tables(cx);
+        if !std::mem::take(&mut self.save_confirmed) {
+            let review = self.save_review_for(cx);
+            let rows = review.iter().map(|(_, rows, _)| rows).sum();
+            if let Some(reason) = self.settings.change_thresholds.exceeded(rows, review.len()) {
+                tracing::warn!("Save of {} needs confirming", reason);
+                self.save_review = Some((reason.clone(), review));
+                cx.notify();
+                return Err(plugin_editor_api::PluginError::Other {
+                    message: format!("Save needs confirming: {}", reason),
+                });
+            }
+        }
+        self.save_review = None;
+        let mut ran = self.run_save_hooks(SavePhase::Pre, &changed)?;
+        self.db.commit_open_transaction().map_err(|e| {
+            tracing::error!("Failed to commit the open transaction: {}", e);
+            plugin_editor_api::PluginError::Other {
+                message: format!("Save aborted: {}", e),
+            }
+        })?;
+        if let Err(e) = self.sync_enums(true) {
+            tracing::error!("Failed to sync generated enums: {}", e);
+        }
+        ran.extend(self.run_save_hooks(SavePhase::Post, &changed)?);
+        if !ran.is_empty() {
+            tracing::info!("✓ Ran save hooks: {}", ran.join(", "));
+            // Hooks may have written to the open tables
+            if let Err(e) = self.refresh_data(cx) {
+                tracing::error!("Failed to refresh after save hooks: {}", e);
+            }
+        }
+        for tab in &self.open_tabs {
+            if let TabType::Table { view, .. } = &tab.tab_type {
+                view.update(cx, |table, cx| {
+                    if let Err(e) = table.delegate_mut().mark_saved() {
+                        tracing::error!("Failed to clear change markers: {}", e);
+                    }
+                    cx.notify();
+                });
+            }
+        }
+        self.update_dirty(cx);
+        Ok(())
+    }
+
+    /// The flag the host's editor instance reads for unsaved changes
+    pub fn dirty_flag(&self) -> Arc<AtomicBool> {
+        self.dirty.clone()
+    }
+
+    /// Unsaved means an open transaction, or edits marked in a table since the last save
+    pub fn is_dirty(&self, cx: &App) -> bool {
+        self.db.has_open_transaction() || !self.changed_tables(cx).is_empty()
+    }
+
+    fn update_dirty(&self, cx: &App) {
+        self.dirty.store(self.is_dirty(cx), Ordering::Relaxed);
+    }
+
+    /// A tab changed: keep the dirty flag current, and the toolbar's pending count when
+    /// an edit joined the held transaction
+    fn on_tab_changed(&mut self, cx: &mut Context<Self>) {
+        self.update_dirty(cx);
+        // Teammates hear about new edits right away rather than at the next heartbeat
+        if self.editing_tables(cx) != self.presence_editing {
+            self.heartbeat_presence(cx);
+        }
+        let held = self.db.held_count();
+        if held != self.held_count {
+            self.held_count = held;
+            cx.notify();
+        }
+    }
+
+    /// Switch between committing each grid edit as it's made and holding them all for
+    /// Commit. Held edits have to be committed or rolled back before switching back.
+    pub fn set_transactional_editing(&mut self, on: bool, cx: &mut Context<Self>) {
+        if !on && self.db.held_count() > 0 {
+            self.transaction_message = Some("Commit or roll back the pending edits first".to_string());
+            cx.notify();
+            return;
+        }
+        self.settings.transactional_editing = on;
+        self.save_settings();
+        self.db.set_transactional(on);
+        self.transaction_message = None;
+        cx.notify();
+    }
+
+    pub fn set_provenance_enabled(&mut self, on: bool, cx: &mut Context<Self>) {
+        let Some(path) = self.database_path.clone() else {
+            return;
+        };
+        let mut config = self.provenance.clone();
+        config.enabled = on;
+        match config.save(&path) {
+            Ok(()) => {
+                self.provenance = config;
+                self.apply_provenance();
+                self.provenance_message = None;
+            }
+            Err(e) => {
+                tracing::error!("Failed to save provenance settings: {}", e);
+                self.provenance_message = Some(format!("Error: {}", e));
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn set_editor_identity(&mut self, cx: &mut Context<Self>) {
+        self.settings.editor_identity = self.identity_input.read(cx).value().trim().to_string();
+        self.save_settings();
+        self.apply_provenance();
+        self.provenance_message = Some(format!("Edits are attributed to {}", self.settings.identity()));
+        cx.notify();
+    }
+
+    fn apply_provenance(&self) {
+        self.db.set_provenance(self.provenance.enabled.then(|| Provenance {
+            config: self.provenance.clone(),
+            identity: self.settings.identity(),
+        }));
+    }
+
+    pub fn commit_edits(&mut self, cx: &mut Context<Self>) {
+        let held = self.db.held_count();
+        match self.db.commit_open_transaction() {
+            Ok(()) => {
+                tracing::info!("✓ Committed {} held statements", held);
+                self.transaction_message = None;
+            }
+            Err(e) => {
+                tracing::error!("Failed to commit: {}", e);
+                self.transaction_message = Some(format!("Error: {}", e));
+            }
+        }
+        self.held_count = self.db.held_count();
+        self.update_dirty(cx);
+        cx.notify();
+    }
+
+    /// Undo one held change, leaving the rest pending
+    pub fn revert_held_change(&mut self, index: usize, cx: &mut Context<Self>) {
+        match self.db.revert_held(index) {
+            Ok(()) => {
+                tracing::info!("✓ Reverted pending change {}", index + 1);
+                self.transaction_message = None;
+                if let Err(e) = self.refresh_data(cx) {
+                    tracing::error!("Failed to refresh after revert: {}", e);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to revert change: {}", e);
+                self.transaction_message = Some(format!("Error: {}", e));
+            }
+        }
+        self.held_count = self.db.held_count();
+        self.update_dirty(cx);
+        cx.notify();
+    }
+
+    /// Every held change, oldest first, with what it replaced and its SQL
+    fn render_held_changes(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.show_held_changes || !self.db.is_transactional() {
+            return None;
+        }
+        let changes = self.db.held_changes();
+        Some(
+            v_flex()
+                .id("held-changes")
+                .w_full()
+                .max_h_64()
+                .overflow_y_scroll()
+                .gap_1()
+                .px_3()
+                .py_2()
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(div().text_xs().text_color(cx.theme().muted_foreground).child(format!("{} pending changes", changes.len())))
+                .when(changes.is_empty(), |this| {
+                    this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("Edits made in the grids wait here until Commit"))
+                })
+                .children(changes.iter().enumerate().take(HELD_CHANGES_SHOWN).map(|(ix, change)| {
+                    let blocked = changes[ix + 1..].iter().any(|later| later.touches(change));
+                    let what = match (&change.column, &change.old, &change.new) {
+                        (Some(column), Some(old), Some(new)) => format!("{}: {} → {}", column, old, new),
+                        (None, None, Some(new)) => format!("inserted: {}", new),
+                        (None, Some(old), None) => format!("deleted: {}", old),
+                        _ => String::new(),
+                    };
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .items_start()
+                        .child(
+                            v_flex()
+                                .flex_1()
+                                .min_w_0()
+                                .child(div().text_xs().child(format!("{} · row {} · {}", change.table, change.row_label(), what)))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .font_family("monospace")
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(change.sql.clone())
+                                )
+                        )
+                        .child(
+                            Button::new(("revert-held", ix))
+                                .label("Revert")
+                                .tooltip(if !change.can_revert() {
+                                    "Batch inserts only roll back with the whole transaction"
+                                } else if blocked {
+                                    "A later change touches this row; revert that one first"
+                                } else {
+                                    "Undo this change and keep the others pending"
+                                })
+                                .xsmall()
+                                .ghost()
+                                .disabled(blocked || !change.can_revert())
+                                .on_click(cx.listener(move |editor, _, _, cx| editor.revert_held_change(ix, cx)))
+                        )
+                }))
+                .when(changes.len() > HELD_CHANGES_SHOWN, |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("…and {} more", changes.len() - HELD_CHANGES_SHOWN))
+                    )
+                })
+        )
+    }
+
+    /// Roll back every held edit, reloading the grids to show the data as it was
+    pub fn rollback_edits(&mut self, cx: &mut Context<Self>) {
+        self.rollback_pending = false;
+        let held = self.db.held_count();
+        if let Err(e) = self.db.rollback_open_transaction() {
+            tracing::error!("Failed to roll back: {}", e);
+            self.transaction_message = Some(format!("Error: {}", e));
+            cx.notify();
+            return;
+        }
+        tracing::info!("✓ Rolled back {} held statements", held);
+        self.transaction_message = None;
+        for tab in &self.open_tabs {
+            if let TabType::Table { view, .. } = &tab.tab_type {
+                view.update(cx, |table, cx| {
+                    if let Err(e) = table.delegate_mut().mark_saved() {
+                        tracing::error!("Failed to clear change markers: {}", e);
+                    }
+                    cx.notify();
+                });
+            }
+        }
+        if let Err(e) = self.refresh_data(cx) {
+            tracing::error!("Failed to refresh after rollback: {}", e);
+        }
+        self.held_count = 0;
+        self.update_dirty(cx);
+        cx.notify();
+    }
+
+    /// What a reload would throw away, or `None` when there is nothing unsaved
+    fn reload_discards(&self, cx: &App) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.db.has_open_transaction() {
+            parts.push("the open transaction's uncommitted writes".to_string());
+        }
+        let changed = self.changed_tables(cx);
+        if !changed.is_empty() {
+            // Outside a transaction the edits were written as they were made
+            parts.push(format!("the change markers on {} (those edits are already written)", changed.join(", ")));
+        }
+        (!parts.is_empty()).then(|| parts.join(" and "))
+    }
+
+    /// Tables with unsaved change markers or held edits
+    fn editing_tables(&self, cx: &App) -> Vec<String> {
+        let mut tables = self.changed_tables(cx);
+        if self.db.held_count() > 0 {
+            for change in self.db.held_changes() {
+                if !tables.contains(&change.table) {
+                    tables.push(change.table);
+                }
+            }
+        }
+        tables
+    }
+
+    /// Refresh this editor's presence entry and pick up everyone else's. Read-only opens
+    /// (safe mode, save inspection) stay invisible, as they don't write beside the database.
+    fn heartbeat_presence(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.database_path.clone().filter(|_| !self.db.opened_read_only()) else {
+            return;
+        };
+        let editing = self.editing_tables(cx);
+        let me = EditorPresence::new(
+            self.presence_session.clone(),
+            self.settings.identity(),
+            self.active_table_name(),
+            editing.clone(),
+        );
+        match presence::announce(&path, me) {
+            Ok(others) => {
+                self.presence_editing = editing;
+                self.co_editors = others;
+                cx.notify();
+            }
+            Err(e) => tracing::error!("Failed to update editor presence: {}", e),
+        }
+    }
+
+    fn leave_presence(&self) {
+        let Some(path) = self.database_path.as_ref().filter(|_| !self.db.opened_read_only()) else {
+            return;
+        };
+        if let Err(e) = presence::leave(path, &self.presence_session) {
+            tracing::error!("Failed to clear editor presence: {}", e);
+        }
+    }
+
+    /// Who else has the database open, as a warning when one of them is editing the
+    /// active table
+    fn render_co_editors(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if self.co_editors.is_empty() {
+            return None;
+        }
+        let table = self.active_table_name();
+        let conflicting: Vec<&EditorPresence> = self
+            .co_editors
+            .iter()
+            .filter(|editor| table.as_deref().is_some_and(|t| editor.is_editing(t)))
+            .collect();
+        let text = if conflicting.is_empty() {
+            self.co_editors.iter().map(|editor| editor.describe()).collect::<Vec<_>>().join(" · ")
+        } else {
+            let names: Vec<&str> = conflicting.iter().map(|editor| editor.identity.as_str()).collect();
+            format!(
+                "{} {} editing `{}` too; edits to the same rows may overwrite each other.",
+                names.join(", "),
+                if names.len() == 1 { "is" } else { "are" },
+                table.unwrap_or_default()
+            )
+        };
+        let warn = !conflicting.is_empty();
+
+        Some(
+            h_flex()
+                .w_full()
+                .gap_2()
+                .px_3()
+                .py_1()
+                .items_center()
+                .when(warn, |bar| bar.bg(cx.theme().warning.opacity(0.1)))
+                .when(!warn, |bar| bar.bg(cx.theme().muted.opacity(0.2)))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(
+                    div()
+                        .flex_1()
+                        .text_xs()
+                        .when(!warn, |d| d.text_color(cx.theme().muted_foreground))
+                        .child(text)
+                )
+        )
+    }
+
+    /// Snapshot the database to a temporary file and open the copy in another editor
+    /// docked beside the tabs, for experiments that must never reach the original
+    pub fn open_throwaway_copy(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(path) = self.database_path.clone().filter(|_| self.workspace_initialized) else {
+            return Ok(());
+        };
+        let original = self.throwaway_of.clone().unwrap_or_else(|| path.clone());
+        let stem = original.file_stem().and_then(|s| s.to_str()).unwrap_or("database");
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let copy = std::env::temp_dir().join(format!("{}-throwaway-{}.db", stem, &suffix[..8]));
+        let mut file = ThrowawayFile { path: copy.clone(), db: None };
+        self.db.backup_to(&copy)?;
+        // The copy opens with this database's reflection profile, save-game flag and the rest
+        let bundle = MetadataBundle::collect(&path)?;
+        for (kind, value) in &bundle.sidecars {
+            crate::sidecar::save(&copy, kind, value)?;
+        }
+        tracing::info!("✓ Copied {:?} and {} sidecars to {:?} for experimenting", path, bundle.sidecars.len(), copy);
+        let held = self.db.held_count();
+        if held > 0 {
+            self.transaction_message = Some(format!("The throwaway copy leaves out the {} pending edits", held));
+        }
+
+        // The editor can only be built inside `cx.new`, so a failed open is handed out
+        // of it and the stand-in dropped (deleting the copy) rather than docked
+        let mut failed = None;
+        let editor = cx.new(|cx| match DataTableEditor::open_database(copy, window, cx) {
+            Ok(mut editor) => {
+                editor.throwaway_of = Some(original);
+                file.db = Some(editor.db.clone());
+                editor._throwaway_file = Some(file);
+                editor
+            }
+            Err(e) => {
+                failed = Some(e);
+                DataTableEditor::new(window, cx)
+            }
+        });
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        let Some(workspace) = self.workspace.clone() else {
+            return Ok(());
+        };
+        let panel: std::sync::Arc<dyn ui::dock::PanelView> = std::sync::Arc::new(editor);
+        window.defer(cx, move |window, cx| {
+            _ = workspace.update(cx, |workspace, cx| {
+                let dock_area = workspace.dock_area();
+                if let Some(tab_panel) = dock_area.read(cx).items().left_top_tab_panel(cx) {
+                    _ = tab_panel.update(cx, |tab_panel, cx| {
+                        tab_panel.add_panel(panel, window, cx);
+                    });
+                }
+            });
+        });
+        Ok(())
+    }
+
+    fn render_throwaway_banner(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let original = self.throwaway_of.as_ref()?;
+        let copy = self.database_path.as_ref()?;
+        Some(
+            h_flex()
+                .w_full()
+                .px_3()
+                .py_2()
+                .items_center()
+                .bg(cx.theme().warning.opacity(0.1))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(div().text_sm().child(format!(
+                    "Throwaway copy of {}: nothing done here reaches the original. The copy is {}.",
+                    original.display(),
+                    copy.display()
+                )))
+        )
+    }
+
+    fn check_disk(&mut self, cx: &mut Context<Self>) {
+        let lost = self.db.take_lost_edits();
+        if lost > 0 {
+            self.transaction_message = Some(format!(
+                "The connection was reset and {} uncommitted edits were lost",
+                lost
+            ));
+            cx.notify();
+        }
+        if let Some(data_provider) = &self.data_provider {
+            if let Err(e) = data_provider.poll() {
+                tracing::error!("Failed to refresh data sources: {}", e);
+            }
+        }
+        if self.disk_changed {
+            return;
+        }
+        match self.db.changed_on_disk() {
+            Ok(true) => {
+                tracing::warn!("Database {:?} was changed on disk by another process", self.database_path);
+                self.disk_changed = true;
+                cx.notify();
+            }
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to check the database file for changes: {}", e),
+        }
+    }
+
+    /// Reload schemas and every open table from disk, each keeping its filters, sort,
+    /// scroll position and selection
+    pub fn refresh_from_disk(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        // The other process may have altered registered tables too
+        self.db.refresh_schemas()?;
+        for tab in &self.open_tabs {
+            if let TabType::Table { view, .. } = &tab.tab_type {
+                view.update(cx, |table, cx| {
+                    if let Err(e) = table.delegate_mut().refresh_rows(0, 1000) {
+                        tracing::error!("Failed to refresh rows: {}", e);
+                    }
+                    cx.notify();
+                });
+            }
+        }
+        self.refresh_table_list()?;
+        self.db.mark_disk_current()?;
+        self.disk_changed = false;
+        tracing::info!("✓ Refreshed from disk");
         Ok(())
     }
 
+    fn render_disk_changed(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.disk_changed {
+            return None;
+        }
+        Some(
+            h_flex()
+                .w_full()
+                .gap_2()
+                .px_3()
+                .py_2()
+                .items_center()
+                .bg(cx.theme().warning.opacity(0.1))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(div().flex_1().text_sm().child("Database changed on disk; the rows shown may be stale."))
+                .child(
+                    Button::new("refresh-from-disk")
+                        .label("Refresh")
+                        .small()
+                        .primary()
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            if let Err(e) = editor.refresh_from_disk(cx) {
+                                tracing::error!("Failed to refresh from disk: {}", e);
+                            }
+                            cx.notify();
+                        }))
+                )
+                .child(
+                    Button::new("dismiss-disk-change")
+                        .label("Dismiss")
+                        .tooltip("Keep the rows shown until the file changes again")
+                        .small()
+                        .outline()
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            if let Err(e) = editor.db.mark_disk_current() {
+                                tracing::error!("Failed to check the database file for changes: {}", e);
+                            }
+                            editor.disk_changed = false;
+                            cx.notify();
+                        }))
+                )
+        )
+    }
+
+    fn render_reload_review(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let discards = self.reload_review.clone()?;
+        Some(
+            h_flex()
+                .w_full()
+                .gap_2()
+                .px_3()
+                .py_2()
+                .items_center()
+                .bg(cx.theme().warning.opacity(0.1))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(div().flex_1().text_sm().child(format!("Reloading discards {}.", discards)))
+                .child(
+                    Button::new("confirm-reload")
+                        .label("Discard and Reload")
+                        .small()
+                        .primary()
+                        .on_click(cx.listener(|editor, _, window, cx| {
+                            editor.reload_confirmed = true;
+                            // Failures log themselves
+                            _ = editor.plugin_reload(window, cx);
+                            cx.notify();
+                        }))
+                )
+                .child(
+                    Button::new("cancel-reload")
+                        .label("Cancel")
+                        .small()
+                        .outline()
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            editor.reload_review = None;
+                            cx.notify();
+                        }))
+                )
+        )
+    }
+
+    /// Each changed table with its changed row count and change summary
+    fn save_review_for(&self, cx: &App) -> Vec<(String, usize, String)> {
+        self.open_tabs
+            .iter()
+            .filter_map(|tab| match &tab.tab_type {
+                TabType::Table { name, view } => {
+                    let changes = &view.read(cx).delegate().state.changes;
+                    (!changes.is_empty()).then(|| (name.clone(), changes.row_count(), changes.summary()))
+                }
+                TabType::Query { .. } => None,
+            })
+            .collect()
+    }
+
+    fn render_save_review(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let (reason, tables) = self.save_review.clone()?;
+        Some(
+            v_flex()
+                .w_full()
+                .gap_1()
+                .px_3()
+                .py_2()
+                .bg(cx.theme().warning.opacity(0.1))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(div().text_sm().child(format!("This save covers {}. Check it's what you meant:", reason)))
+                .children(tables.into_iter().map(|(table, _, summary)| {
+                    div()
+                        .pl_2()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!("{}: {}", table, summary))
+                }))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .pt_1()
+                        .child(
+                            Button::new("confirm-save")
+                                .label("Save Anyway")
+                                .small()
+                                .primary()
+                                .on_click(cx.listener(|editor, _, window, cx| {
+                                    editor.save_confirmed = true;
+                                    // Hook failures log themselves and keep the changes marked
+                                    _ = editor.plugin_save(window, cx);
+                                    cx.notify();
+                                }))
+                        )
+                        .child(
+                            Button::new("cancel-save")
+                                .label("Cancel")
+                                .small()
+                                .outline()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.save_review = None;
+                                    cx.notify();
+                                }))
+                        )
+                )
+        )
+    }
+
+    /// Tables with edits since the last save, judged from their open tabs
+    fn changed_tables(&self, cx: &App) -> Vec<String> {
+        self.open_tabs
+            .iter()
+            .filter_map(|tab| match &tab.tab_type {
+                TabType::Table { name, view } if !view.read(cx).delegate().state.changes.is_empty() => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Run one phase of the save hooks; a failure aborts the save with the changes still marked
+    fn run_save_hooks(&self, phase: SavePhase, changed: &[String]) -> Result<Vec<String>, plugin_editor_api::PluginError> {
+        self.save_hooks.run(&self.db, phase, changed).map_err(|e| {
+            tracing::error!("Failed to run {} hooks: {}", phase.label(), e);
+            plugin_editor_api::PluginError::Other {
+                message: format!("Save aborted: {}", e),
+            }
+        })
+    }
+
     /// Plugin-specific reload method
     pub fn plugin_reload(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Result<(), plugin_editor_api::PluginError> {
+        if !std::mem::take(&mut self.reload_confirmed) {
+            if let Some(discards) = self.reload_discards(cx) {
+                tracing::warn!("Reload would discard {}, needs confirming", discards);
+                self.reload_review = Some(discards.clone());
+                cx.notify();
+                return Err(plugin_editor_api::PluginError::Other {
+                    message: format!("Reload needs confirming: it discards {}", discards),
+                });
+            }
+        }
+        self.reload_review = None;
+
+        if let Err(e) = self.db.rollback_open_transaction() {
+            tracing::error!("Failed to roll back the open transaction: {}", e);
+            return Err(plugin_editor_api::PluginError::Other {
+                message: format!("Failed to reload: {}", e),
+            });
+        }
+        for tab in &self.open_tabs {
+            if let TabType::Table { view, .. } = &tab.tab_type {
+                view.update(cx, |table, cx| {
+                    if let Err(e) = table.delegate_mut().mark_saved() {
+                        tracing::error!("Failed to clear change markers: {}", e);
+                    }
+                    cx.notify();
+                });
+            }
+        }
+
         // Refresh all open tables
         if let Err(e) = self.refresh_data(cx) {
             tracing::error!("Failed to reload table data: {}", e);
@@ -723,11 +6521,16 @@ impl DataTableEditor {
         }
 
         // Refresh the list of available tables
-        if let Ok(tables) = self.db.list_tables() {
-            self.available_tables = tables;
+        if let Err(e) = self.refresh_table_list() {
+            tracing::error!("Failed to list tables: {}", e);
+        }
+        match self.db.mark_disk_current() {
+            Ok(()) => self.disk_changed = false,
+            Err(e) => tracing::error!("Failed to check the database file for changes: {}", e),
         }
 
         tracing::debug!("Table editor reloaded successfully");
+        self.update_dirty(cx);
         cx.notify();
         Ok(())
     }
@@ -753,24 +6556,165 @@ impl Render for DataTableEditor {
         v_flex()
             .size_full()
             .bg(cx.theme().background)
+            .when_some(self.quarantine_reason.clone(), |this, reason| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_2()
+                        .items_center()
+                        .justify_between()
+                        .bg(cx.theme().warning.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_sm().child(format!(
+                            "Safe mode ({}): read-only, with triggers and views disabled and values over {} MB refused",
+                            reason,
+                            crate::quarantine::MAX_VALUE_BYTES / (1024 * 1024)
+                        )))
+                        .child(
+                            Button::new("trust-database")
+                                .label("Trust this database")
+                                .tooltip("Open it normally from now on (takes effect when reopened)")
+                                .small()
+                                .outline()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    editor.trust_database(cx);
+                                }))
+                        )
+                )
+            })
+            .children(self.render_throwaway_banner(cx))
+            .children(self.render_save_review(cx))
+            .children(self.render_co_editors(cx))
+            .children(self.render_disk_changed(cx))
+            .children(self.render_reload_review(cx))
+            .children(self.render_held_changes(cx))
+            .when(self.db.is_frozen(), |this| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_2()
+                        .items_center()
+                        .justify_between()
+                        .bg(cx.theme().warning.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_sm().child(
+                            "Change freeze: writes are blocked. Browsing, queries that only read, exports and analysis still work."
+                        ))
+                        .child(
+                            Button::new("lift-freeze")
+                                .label("Lift Freeze")
+                                .small()
+                                .outline()
+                                .on_click(cx.listener(|editor, _, _, cx| {
+                                    if let Err(e) = editor.toggle_freeze(cx) {
+                                        tracing::error!("Failed to lift change freeze: {}", e);
+                                    }
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
             .child(toolbar)
             .child(
                 h_flex()
                     .flex_1()
                     .w_full()
-                    .child(sidebar)
+                    .when(self.settings.layout.shows_sidebar(), |this| this.child(sidebar))
                     .child(
                         div()
                             .flex_1()
                             .h_full()
-                            .when_some(self.workspace.clone(), |this, workspace| {
-                                this.child(workspace)
+                            .when_some(self.migration.clone(), |this, migration| {
+                                this.child(migration)
+                            })
+                            .when_some(self.csv_import.clone(), |this, wizard| {
+                                this.child(wizard)
                             })
+                            .when_some(self.create_table.clone(), |this, wizard| {
+                                this.child(wizard)
+                            })
+                            .when_some(self.view_editor.clone(), |this, editor| {
+                                this.child(editor)
+                            })
+                            .when_some(self.trigger_editor.clone(), |this, editor| {
+                                this.child(editor)
+                            })
+                            .when(
+                                self.migration.is_none()
+                                    && self.csv_import.is_none()
+                                    && self.create_table.is_none()
+                                    && self.view_editor.is_none()
+                                    && self.trigger_editor.is_none(),
+                                |this| {
+                                    this.when_some(self.workspace.clone(), |this, workspace| {
+                                        this.child(workspace)
+                                    })
+                                },
+                            )
                     )
             )
     }
 }
 
+fn name_input(window: &mut Window, cx: &mut Context<DataTableEditor>) -> Entity<InputState> {
+    cx.new(|cx| InputState::new(window, cx))
+}
+
+/// A throwaway copy in the temp directory, removed with its `-wal`, `-shm` and
+/// sidecars on drop. Their names all start with the copy's unique file name.
+struct ThrowawayFile {
+    path: PathBuf,
+    /// Closed first: table and query views may still hold clones of the connection,
+    /// and Windows won't delete a file that is open
+    db: Option<DatabaseManager>,
+}
+
+impl Drop for ThrowawayFile {
+    fn drop(&mut self) {
+        if let Some(db) = &self.db {
+            if let Err(e) = db.close() {
+                tracing::warn!("Failed to close throwaway copy {:?}: {}", self.path, e);
+            }
+        }
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name().map(|n| n.to_string_lossy().to_string())) else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&name) {
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    tracing::warn!("Failed to delete throwaway copy {:?}: {}", entry.path(), e);
+                }
+            }
+        }
+    }
+}
+
+fn identity_input(settings: &UserSettings, window: &mut Window, cx: &mut Context<DataTableEditor>) -> Entity<InputState> {
+    let identity = settings.identity();
+    cx.new(|cx| {
+        let mut state = InputState::new(window, cx);
+        state.set_value(&identity, window, cx);
+        state
+    })
+}
+
+fn pragma_input(value: i32, window: &mut Window, cx: &mut Context<DataTableEditor>) -> Entity<InputState> {
+    cx.new(|cx| {
+        let mut state = InputState::new(window, cx);
+        state.set_value(&value.to_string(), window, cx);
+        state
+    })
+}
+
 pub fn create_data_table_editor(window: &mut Window, cx: &mut App) -> Entity<DataTableEditor> {
     cx.new(|cx| DataTableEditor::new(window, cx))
 }
@@ -0,0 +1,516 @@
+//! Streaming exporters for tables and query results.
+//!
+//! Exporters are [`RowSink`]s fed straight from the SQLite cursor, so exporting
+//! a multi-GB table never holds more than a single row in memory.
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::compression::{CompressedWriter, Compression};
+use crate::database::{stream_statement, CellValue, DatabaseManager, RowScope, RowSink};
+use crate::sql_text::quote_identifier;
+use crate::text_encoding::{EncodingWriter, Newline, TextEncoding};
+
+/// Upper bound on concurrent table exports, each holding its own read-only connection
+pub const MAX_EXPORT_WORKERS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv(CsvExportOptions),
+    /// One array of objects
+    Json,
+    /// One object per line
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv(options) if options.delimiter == '\t' => "tsv",
+            ExportFormat::Csv(_) => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// The formats the table export cycles through; CSV takes its options from elsewhere
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportKind {
+    #[default]
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl ExportKind {
+    pub const ALL: [ExportKind; 3] = [ExportKind::Csv, ExportKind::Json, ExportKind::Ndjson];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportKind::Csv => "CSV",
+            ExportKind::Json => "JSON",
+            ExportKind::Ndjson => "NDJSON",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|k| k == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn format(&self, csv: &CsvExportOptions) -> ExportFormat {
+        match self {
+            ExportKind::Csv => ExportFormat::Csv(csv.clone()),
+            ExportKind::Json => ExportFormat::Json,
+            ExportKind::Ndjson => ExportFormat::Ndjson,
+        }
+    }
+}
+
+/// Delimiters offered for CSV/TSV export
+pub const CSV_DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+/// NULL representations offered for CSV export; `\N` is what MySQL and Postgres load
+pub const CSV_NULLS: [&str; 3] = ["", "NULL", "\\N"];
+
+/// When fields are wrapped in quotes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvQuoting {
+    /// Only fields containing the delimiter, a quote or a line break
+    #[default]
+    Minimal,
+    /// Every field except NULLs, so NULL and the empty string stay distinguishable
+    All,
+    /// Never; fields are written as they are
+    Never,
+}
+
+impl CsvQuoting {
+    pub const ALL: [CsvQuoting; 3] = [CsvQuoting::Minimal, CsvQuoting::All, CsvQuoting::Never];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CsvQuoting::Minimal => "Quote as needed",
+            CsvQuoting::All => "Quote all",
+            CsvQuoting::Never => "No quotes",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|q| q == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// Output options for CSV; many spreadsheet tools expect UTF-16 or a BOM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvExportOptions {
+    pub encoding: TextEncoding,
+    pub write_bom: bool,
+    pub newline: Newline,
+    /// `\t` writes TSV
+    pub delimiter: char,
+    pub quoting: CsvQuoting,
+    pub header: bool,
+    /// Written, unquoted, for NULL cells
+    pub null_as: String,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            encoding: TextEncoding::Utf8,
+            write_bom: false,
+            newline: Newline::Lf,
+            delimiter: ',',
+            quoting: CsvQuoting::Minimal,
+            header: true,
+            null_as: String::new(),
+        }
+    }
+}
+
+impl CsvExportOptions {
+    pub fn delimiter_label(&self) -> &'static str {
+        match self.delimiter {
+            '\t' => "Tab",
+            ';' => "Semicolon",
+            '|' => "Pipe",
+            _ => "Comma",
+        }
+    }
+
+    pub fn next_delimiter(&mut self) {
+        let idx = CSV_DELIMITERS.iter().position(|d| *d == self.delimiter).map(|i| i + 1).unwrap_or(0);
+        self.delimiter = CSV_DELIMITERS[idx % CSV_DELIMITERS.len()];
+    }
+
+    pub fn null_label(&self) -> String {
+        if self.null_as.is_empty() {
+            "NULL as empty".to_string()
+        } else {
+            format!("NULL as {}", self.null_as)
+        }
+    }
+
+    pub fn next_null(&mut self) {
+        let idx = CSV_NULLS.iter().position(|n| *n == self.null_as).map(|i| i + 1).unwrap_or(0);
+        self.null_as = CSV_NULLS[idx % CSV_NULLS.len()].to_string();
+    }
+
+    fn field(&self, value: &str) -> String {
+        let needs_quotes = match self.quoting {
+            CsvQuoting::All => true,
+            CsvQuoting::Never => false,
+            CsvQuoting::Minimal => {
+                value.contains(self.delimiter) || value.contains('"') || value.contains('\n') || value.contains('\r')
+            }
+        };
+        if needs_quotes {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+/// Key/value pairs describing an export, such as the table and filter it came from
+pub type ExportMetadata = Vec<(String, String)>;
+
+/// The keys table exports write, the only ones the importer recognizes as metadata
+pub const METADATA_KEYS: [&str; 3] = ["table", "rows", "exported_at"];
+
+/// Whether `line` is a `# key: value` metadata line as written ahead of a CSV header,
+/// rather than a header or record that happens to start with `#`
+pub fn is_metadata_line(line: &str) -> bool {
+    line.strip_prefix("# ")
+        .and_then(|rest| rest.split_once(": "))
+        .is_some_and(|(key, _)| METADATA_KEYS.contains(&key))
+}
+
+pub struct CsvSink<W: Write> {
+    writer: W,
+    options: CsvExportOptions,
+    metadata: ExportMetadata,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W, options: &CsvExportOptions) -> Self {
+        Self {
+            writer,
+            options: options.clone(),
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Written as `# key: value` comment lines ahead of the header, which the importer skips
+    pub fn with_metadata(mut self, metadata: ExportMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+impl<W: Write> RowSink for CsvSink<W> {
+    fn begin(&mut self, columns: &[String]) -> Result<()> {
+        let newline = self.options.newline.as_str();
+        for (key, value) in &self.metadata {
+            write!(self.writer, "# {}: {}{}", key, value.replace(['\r', '\n'], " "), newline)?;
+        }
+
+        if self.options.header {
+            let header = columns
+                .iter()
+                .map(|c| self.options.field(c))
+                .collect::<Vec<_>>()
+                .join(&self.options.delimiter.to_string());
+            write!(self.writer, "{}{}", header, newline)?;
+        }
+        Ok(())
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        let row_str = cells
+            .iter()
+            .map(|cell| match cell.value {
+                serde_json::Value::Null => self.options.null_as.clone(),
+                _ => self.options.field(&cell.display),
+            })
+            .collect::<Vec<_>>()
+            .join(&self.options.delimiter.to_string());
+        write!(self.writer, "{}{}", row_str, self.options.newline.as_str())?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes a JSON array of objects keyed by column name, one row at a time.
+/// With metadata the array is wrapped as `{"metadata": {..}, "rows": [..]}`.
+pub struct JsonSink<W: Write> {
+    writer: W,
+    columns: Vec<String>,
+    rows_written: usize,
+    metadata: ExportMetadata,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            columns: Vec::new(),
+            rows_written: 0,
+            metadata: Vec::new(),
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: ExportMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+impl<W: Write> RowSink for JsonSink<W> {
+    fn begin(&mut self, columns: &[String]) -> Result<()> {
+        self.columns = columns.to_vec();
+        if !self.metadata.is_empty() {
+            let metadata: serde_json::Map<String, serde_json::Value> = self
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone())))
+                .collect();
+            self.writer.write_all(b"{\"metadata\": ")?;
+            serde_json::to_writer(&mut self.writer, &metadata)?;
+            self.writer.write_all(b", \"rows\": ")?;
+        }
+        self.writer.write_all(b"[")?;
+        Ok(())
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        let mut row_map = serde_json::Map::new();
+        for (col_name, cell) in self.columns.iter().zip(cells) {
+            row_map.insert(col_name.clone(), cell.value.clone());
+        }
+
+        if self.rows_written > 0 {
+            self.writer.write_all(b",")?;
+        }
+        self.writer.write_all(b"\n  ")?;
+        serde_json::to_writer(&mut self.writer, &row_map)?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.write_all(b"\n]")?;
+        if !self.metadata.is_empty() {
+            self.writer.write_all(b"}")?;
+        }
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes one JSON object per line, keyed by column name. There is nowhere to put
+/// metadata, so none is written.
+pub struct NdjsonSink<W: Write> {
+    writer: W,
+    columns: Vec<String>,
+}
+
+impl<W: Write> NdjsonSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            columns: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> RowSink for NdjsonSink<W> {
+    fn begin(&mut self, columns: &[String]) -> Result<()> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        let row_map: serde_json::Map<String, serde_json::Value> = self
+            .columns
+            .iter()
+            .zip(cells)
+            .map(|(col_name, cell)| (col_name.clone(), cell.value.clone()))
+            .collect();
+        serde_json::to_writer(&mut self.writer, &row_map)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn export_query_to_csv(
+    db: &DatabaseManager,
+    sql: &str,
+    path: &Path,
+    options: &CsvExportOptions,
+) -> Result<usize> {
+    export_to_file(path, &ExportFormat::Csv(options.clone()), |sink| db.stream_query(sql, sink))
+}
+
+pub fn export_query_to_json(db: &DatabaseManager, sql: &str, path: &Path) -> Result<usize> {
+    export_to_file(path, &ExportFormat::Json, |sink| db.stream_query(sql, sink))
+}
+
+pub fn export_query_to_ndjson(db: &DatabaseManager, sql: &str, path: &Path) -> Result<usize> {
+    export_to_file(path, &ExportFormat::Ndjson, |sink| db.stream_query(sql, sink))
+}
+
+/// Export the rows of one table covered by `scope`, recording the table and scope in the
+/// file's header metadata
+pub fn export_table_rows(
+    db: &DatabaseManager,
+    table_name: &str,
+    scope: &RowScope,
+    path: &Path,
+    format: &ExportFormat,
+) -> Result<usize> {
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let [table, rows, exported] = METADATA_KEYS;
+    let metadata = vec![
+        (table.to_string(), table_name.to_string()),
+        (rows.to_string(), scope.describe()),
+        (exported.to_string(), crate::reflection::format_unix_seconds(exported_at as i64)),
+    ];
+
+    write_export(path, format, metadata, |sink| db.stream_table(table_name, scope, sink))
+}
+
+/// Feed `stream` a sink writing `format` to `path`, compressed when the path ends in .gz/.zst
+pub fn export_to_file(
+    path: &Path,
+    format: &ExportFormat,
+    stream: impl FnOnce(&mut dyn RowSink) -> Result<usize>,
+) -> Result<usize> {
+    write_export(path, format, Vec::new(), stream)
+}
+
+fn write_export(
+    path: &Path,
+    format: &ExportFormat,
+    metadata: ExportMetadata,
+    stream: impl FnOnce(&mut dyn RowSink) -> Result<usize>,
+) -> Result<usize> {
+    let mut writer = CompressedWriter::create(path)?;
+    let count = match format {
+        ExportFormat::Csv(options) => {
+            // Transcode before compressing so the decompressed file has the requested encoding
+            let mut encoded = EncodingWriter::new(&mut writer, options.encoding, options.write_bom);
+            stream(&mut CsvSink::new(&mut encoded, options).with_metadata(metadata))?
+        }
+        ExportFormat::Json => stream(&mut JsonSink::new(&mut writer).with_metadata(metadata))?,
+        ExportFormat::Ndjson => stream(&mut NdjsonSink::new(&mut writer))?,
+    };
+    writer.finish()?;
+    Ok(count)
+}
+
+/// Export each table to `<dir>/<table>.<ext>[.gz|.zst]`, spreading tables over a bounded pool of
+/// workers. Returns `(table, rows)` pairs in the order the tables were given.
+pub fn export_tables(
+    db: &DatabaseManager,
+    tables: &[String],
+    dir: &Path,
+    format: &ExportFormat,
+    compression: Compression,
+) -> Result<Vec<(String, usize)>> {
+    std::fs::create_dir_all(dir)?;
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_EXPORT_WORKERS)
+        .min(tables.len());
+
+    // In-memory databases can't be opened from a second connection
+    if db.path().is_none() || workers <= 1 {
+        return tables
+            .iter()
+            .map(|table| {
+                let path = dir.join(format!("{}.{}{}", table_file_name(table), format.extension(), compression.suffix()));
+                let count = export_to_file(&path, format, |sink| {
+                    db.stream_query(&format!("SELECT * FROM {}", quote_identifier(table)), sink)
+                })?;
+                Ok((table.clone(), count))
+            })
+            .collect();
+    }
+
+    let connections = (0..workers)
+        .map(|_| db.open_read_only_connection())
+        .collect::<Result<Vec<_>>>()?;
+
+    let next_table = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = connections
+            .into_iter()
+            .map(|conn| {
+                let next_table = &next_table;
+                let results = &results;
+                scope.spawn(move || -> Result<()> {
+                    loop {
+                        let idx = next_table.fetch_add(1, Ordering::Relaxed);
+                        let Some(table) = tables.get(idx) else {
+                            return Ok(());
+                        };
+
+                        let path = dir.join(format!("{}.{}{}", table_file_name(table), format.extension(), compression.suffix()));
+                        let count = export_to_file(&path, format, |sink| {
+                            stream_statement(&conn, &format!("SELECT * FROM {}", quote_identifier(table)), sink)
+                        })?;
+                        results.lock().push((idx, table.clone(), count));
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().map_err(|_| anyhow!("Export worker panicked"))??;
+        }
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    let mut results = results.into_inner();
+    results.sort_by_key(|(idx, _, _)| *idx);
+    Ok(results.into_iter().map(|(_, table, count)| (table, count)).collect())
+}
+
+/// A table name made safe to use as a file name inside the export directory: path
+/// separators and characters Windows refuses become `_`, and so do leading dots, so
+/// names like `../x` can't point elsewhere
+pub(crate) fn table_file_name(table: &str) -> String {
+    let name: String = table
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '<' | '>' | '"' | '|' | '?' | '*') { '_' } else { c })
+        .collect();
+    let dots = name.len() - name.trim_start_matches('.').len();
+    let name = format!("{}{}", "_".repeat(dots), &name[dots..]);
+    if name.is_empty() { "_".to_string() } else { name }
+}
+
+pub fn escape_csv_field(value: &str) -> String {
+    CsvExportOptions::default().field(value)
+}
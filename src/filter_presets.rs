@@ -0,0 +1,82 @@
+//! Named filter and sort setups for a table, such as "Active quests only", kept in the
+//! `filter_presets` sidecar beside the database rather than in user settings, so they
+//! are committed with the project and the whole team picks the same ones.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::database::SortKey;
+use crate::row_filter::RowFilter;
+
+pub const FILTER_PRESETS_SIDECAR: &str = "filter_presets";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterPresets {
+    pub presets: Vec<FilterPreset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    pub table: String,
+    #[serde(default)]
+    pub filter: RowFilter,
+    /// Hand-written WHERE condition, ANDed with the filter conditions
+    #[serde(default)]
+    pub raw_where: Option<String>,
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+}
+
+impl FilterPreset {
+    /// `quest_state = active · ORDER BY level DESC`, for the preset's tooltip
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.filter.is_empty() {
+            parts.push(self.filter.describe());
+        }
+        if let Some(raw) = &self.raw_where {
+            parts.push(format!("WHERE {}", raw));
+        }
+        if !self.sort.is_empty() {
+            let keys: Vec<String> = self
+                .sort
+                .iter()
+                .map(|key| format!("{}{}", key.column, if key.descending { " DESC" } else { "" }))
+                .collect();
+            parts.push(format!("ORDER BY {}", keys.join(", ")));
+        }
+        if parts.is_empty() {
+            "No filter or sort".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+}
+
+impl FilterPresets {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(crate::sidecar::load(db_path, FILTER_PRESETS_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        crate::sidecar::save(db_path, FILTER_PRESETS_SIDECAR, self)
+    }
+
+    pub fn for_table<'a>(&'a self, table: &'a str) -> impl Iterator<Item = &'a FilterPreset> + 'a {
+        self.presets.iter().filter(move |p| p.table == table)
+    }
+
+    /// Add the preset, replacing one of the same name on the same table
+    pub fn remember(&mut self, preset: FilterPreset) {
+        match self.presets.iter_mut().find(|p| p.table == preset.table && p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, table: &str, name: &str) {
+        self.presets.retain(|p| !(p.table == table && p.name == name));
+    }
+}
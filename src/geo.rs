@@ -0,0 +1,133 @@
+//! Positions stored as a latitude/longitude column pair the reflection profile declares:
+//! reading them from cells, plotting them on a world thumbnail, a grid picker to move
+//! them, and `POINT(x y)` text for GIS tools.
+
+use crate::database::CellValue;
+
+/// A position in degrees, latitude north and longitude east
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// `None` outside ±90° latitude or ±180° longitude
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some(Self { lat, lon })
+    }
+
+    /// The position in two cells holding numbers, or numeric text
+    pub fn from_cells(lat: &CellValue, lon: &CellValue) -> Option<Self> {
+        Self::new(degrees(lat)?, degrees(lon)?)
+    }
+
+    /// Well-known text, which puts x (longitude) first
+    pub fn to_wkt(&self) -> String {
+        format!("POINT({} {})", round(self.lon), round(self.lat))
+    }
+
+    /// Where the point falls on an equirectangular world map, from the top left, 0..=1
+    pub fn map_fraction(&self) -> (f32, f32) {
+        (((self.lon + 180.0) / 360.0) as f32, ((90.0 - self.lat) / 180.0) as f32)
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.5}°{} {:.5}°{}",
+            self.lat.abs(),
+            if self.lat < 0.0 { "S" } else { "N" },
+            self.lon.abs(),
+            if self.lon < 0.0 { "W" } else { "E" }
+        )
+    }
+}
+
+fn degrees(cell: &CellValue) -> Option<f64> {
+    cell.value
+        .as_f64()
+        .or_else(|| cell.value.as_str().and_then(|s| s.trim().parse().ok()))
+        .filter(|v: &f64| v.is_finite())
+}
+
+/// Six decimals is about 10 cm, finer than the picker can place a point
+pub fn round(degrees: f64) -> f64 {
+    (degrees * 1e6).round() / 1e6
+}
+
+/// A window of the map centred on the point being edited, cut into a grid. Clicking a
+/// cell moves the point to its centre and recentres the window; zooming narrows the
+/// window so each click moves the point less.
+#[derive(Debug, Clone)]
+pub struct GeoPicker {
+    pub point: GeoPoint,
+    /// Degrees of longitude across the window; it covers half as many of latitude
+    pub span: f64,
+}
+
+impl GeoPicker {
+    /// Odd, so the point sits in the middle of a cell
+    pub const COLUMNS: usize = 25;
+    pub const ROWS: usize = 13;
+
+    const MIN_SPAN: f64 = 0.001;
+    const MAX_SPAN: f64 = 360.0;
+
+    pub fn new(point: GeoPoint) -> Self {
+        Self { point, span: Self::MAX_SPAN }
+    }
+
+    /// Degrees of longitude one grid cell covers
+    pub fn step(&self) -> f64 {
+        self.span / Self::COLUMNS as f64
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.span = (self.span / 4.0).max(Self::MIN_SPAN);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.span = (self.span * 4.0).min(Self::MAX_SPAN);
+    }
+
+    pub fn can_zoom_in(&self) -> bool {
+        self.span > Self::MIN_SPAN
+    }
+
+    pub fn can_zoom_out(&self) -> bool {
+        self.span < Self::MAX_SPAN
+    }
+
+    /// Whether `(row, col)` is the cell holding the point
+    pub fn is_marker(row: usize, col: usize) -> bool {
+        row == Self::ROWS / 2 && col == Self::COLUMNS / 2
+    }
+
+    /// The centre of a grid cell. Latitude stops at the poles; longitude wraps around
+    /// the antimeridian.
+    pub fn cell_point(&self, row: usize, col: usize) -> GeoPoint {
+        let step = self.step();
+        let dx = col as f64 - (Self::COLUMNS / 2) as f64;
+        let dy = (Self::ROWS / 2) as f64 - row as f64;
+        let lat = (self.point.lat + dy * step).clamp(-90.0, 90.0);
+        let mut lon = self.point.lon + dx * step;
+        while lon > 180.0 {
+            lon -= 360.0;
+        }
+        while lon < -180.0 {
+            lon += 360.0;
+        }
+        GeoPoint { lat: round(lat), lon: round(lon) }
+    }
+
+    /// Whether the equator or prime meridian runs through a grid cell, drawn as a guide
+    pub fn is_graticule(&self, row: usize, col: usize) -> bool {
+        let half = self.step() / 2.0;
+        let cell = self.cell_point(row, col);
+        cell.lat.abs() < half || cell.lon.abs() < half
+    }
+
+    pub fn pick(&mut self, row: usize, col: usize) {
+        self.point = self.cell_point(row, col);
+    }
+}
@@ -0,0 +1,130 @@
+//! Reading a `GROUP BY` query back into its parts, so its result can render as one
+//! header per group and each group can fetch the rows it was aggregated from. Only plain
+//! `SELECT … FROM … [WHERE …] GROUP BY …` statements are understood; anything else
+//! (compound selects, `SELECT *`, group terms that aren't result columns) renders flat.
+
+use crate::database::CellValue;
+use crate::sql_text::{self, Word};
+
+#[derive(Debug, Clone)]
+pub struct GroupedQuery {
+    /// Everything between `FROM` and `WHERE`/`GROUP BY`, joins included
+    pub source: String,
+    pub filter: Option<String>,
+    /// Result column index and expression of each group term, in `GROUP BY` order
+    pub keys: Vec<(usize, String)>,
+    /// Result columns that aren't group keys, i.e. the aggregates
+    pub aggregates: Vec<usize>,
+}
+
+impl GroupedQuery {
+    /// Parse `sql`, whose result has `columns`; `None` unless every group term maps to a column
+    pub fn parse(sql: &str, columns: &[String]) -> Option<Self> {
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        let words = sql_text::top_level_words(sql);
+        if !words.first()?.is_keyword("SELECT") {
+            return None;
+        }
+        if words.iter().any(|w| ["UNION", "INTERSECT", "EXCEPT"].iter().any(|k| w.is_keyword(k))) {
+            return None;
+        }
+
+        let find = |keyword: &str| words.iter().position(|w| w.is_keyword(keyword));
+        let from = find("FROM")?;
+        let group = words
+            .iter()
+            .enumerate()
+            .position(|(ix, w)| w.is_keyword("GROUP") && words.get(ix + 1).is_some_and(|n| n.is_keyword("BY")))?;
+        let filter = find("WHERE").filter(|&ix| from < ix && ix < group);
+        let group_end = words[group + 2..]
+            .iter()
+            .find(|w| ["HAVING", "WINDOW", "ORDER", "LIMIT"].iter().any(|k| w.is_keyword(k)))
+            .map(|w| w.range.start)
+            .unwrap_or(sql.len());
+
+        let mut select_start = words[0].range.end;
+        if words.get(1).is_some_and(|w| w.is_keyword("DISTINCT") || w.is_keyword("ALL")) {
+            select_start = words[1].range.end;
+        }
+        let items: Vec<SelectItem> = sql_text::split_top_level(&sql[select_start..words[from].range.start])
+            .into_iter()
+            .enumerate()
+            .map(|(ix, item)| SelectItem::parse(item, columns.get(ix).map(String::as_str)))
+            .collect();
+        if items.len() != columns.len() || items.iter().any(|item| item.expr.ends_with('*')) {
+            return None;
+        }
+
+        let source_end = filter.map(|ix| words[ix].range.start).unwrap_or(words[group].range.start);
+        let mut keys = Vec::new();
+        for term in sql_text::split_top_level(&sql[words[group + 1].range.end..group_end]) {
+            let ix = match term.parse::<usize>() {
+                Ok(ordinal) => ordinal.checked_sub(1).filter(|&ix| ix < items.len())?,
+                Err(_) => items.iter().position(|item| item.matches(term))?,
+            };
+            keys.push((ix, items[ix].expr.clone()));
+        }
+
+        Some(Self {
+            source: sql[words[from].range.end..source_end].trim().to_string(),
+            filter: filter.map(|ix| sql[words[ix].range.end..words[group].range.start].trim().to_string()),
+            aggregates: (0..columns.len()).filter(|ix| !keys.iter().any(|(key, _)| key == ix)).collect(),
+            keys,
+        })
+    }
+
+    /// The query for the rows behind one result row, matching its keys with `IS` so NULL groups work too
+    pub fn drill_down(&self, row: &[CellValue]) -> String {
+        let mut conditions: Vec<String> = self.filter.iter().map(|f| format!("({})", f)).collect();
+        for (ix, expr) in &self.keys {
+            let literal = row.get(*ix).map(CellValue::sql_literal).unwrap_or_else(|| "NULL".to_string());
+            conditions.push(format!("({}) IS {}", expr, literal));
+        }
+        format!("SELECT * FROM {} WHERE {}", self.source, conditions.join(" AND "))
+    }
+
+    /// `region = EU, kind = sword`, the header of a group
+    pub fn key_label(&self, columns: &[String], row: &[CellValue]) -> String {
+        self.keys
+            .iter()
+            .map(|(ix, _)| format!("{} = {}", columns[*ix], row.get(*ix).map(|c| c.display.as_str()).unwrap_or("")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// One result column of the select list
+struct SelectItem {
+    expr: String,
+    alias: Option<String>,
+}
+
+impl SelectItem {
+    /// Split off an alias, with or without `AS`, when it's what SQLite named the `column`
+    fn parse(item: &str, column: Option<&str>) -> Self {
+        let words: Vec<Word> = sql_text::top_level_words(item);
+        if let (Some(last), Some(column)) = (words.last(), column) {
+            // `t.name` is named `name` too, so the alias must stand apart from the expression
+            let apart = item[..last.range.start].ends_with(char::is_whitespace);
+            if words.len() > 1 && apart && last.range.end == item.len() && last.text.eq_ignore_ascii_case(column) {
+                let before = &words[words.len() - 2];
+                let expr_end = if before.is_keyword("AS") { before.range.start } else { last.range.start };
+                let expr = item[..expr_end].trim();
+                if !expr.is_empty() {
+                    return Self { expr: expr.to_string(), alias: Some(last.text.clone()) };
+                }
+            }
+        }
+        Self { expr: item.to_string(), alias: None }
+    }
+
+    fn matches(&self, term: &str) -> bool {
+        let term = normalize(term);
+        self.alias.as_ref().is_some_and(|alias| normalize(alias) == term) || normalize(&self.expr) == term
+    }
+}
+
+/// Expression text with case and whitespace ignored, so `COUNT( * )` matches `count(*)`
+fn normalize(expr: &str) -> String {
+    expr.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_lowercase()
+}
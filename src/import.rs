@@ -0,0 +1,856 @@
+//! CSV import into existing tables, or new ones created from the file's inferred types.
+//!
+//! A reader thread splits the file into chunks of whole records, a bounded pool of
+//! workers parses the chunks, and the calling thread is the single writer that
+//! inserts them in file order.
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+use crate::compression::open_reader;
+use crate::database::DatabaseManager;
+use crate::export::is_metadata_line;
+use crate::reflection::{FieldSchema, SqlType};
+use crate::sidecar;
+use crate::sql_text::quote_identifier;
+use crate::text_encoding::{DecodingReader, TextEncoding};
+
+/// Upper bound on CSV parsing workers
+pub const MAX_IMPORT_WORKERS: usize = 4;
+
+/// Records handed to a parsing worker at a time
+const CHUNK_RECORDS: usize = 1000;
+
+/// Rows committed per import transaction
+const TRANSACTION_ROWS: usize = 50_000;
+
+/// Candidate delimiters tried when sniffing, in tie-break order
+const SNIFF_DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Lines of the file's start inspected when sniffing the dialect
+const SNIFF_LINES: usize = 20;
+
+/// Malformed rows kept in the report; the total is still counted past this
+const MAX_REPORTED_MALFORMED: usize = 1000;
+
+/// Sidecar holding the remembered import settings of a database
+const PRESETS_SIDECAR: &str = "imports";
+
+/// Records read when previewing a file and inferring its column types
+pub const PREVIEW_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+    /// Escape character inside fields; `None` means quotes are escaped by doubling
+    pub escape: Option<char>,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            escape: None,
+        }
+    }
+}
+
+/// What to do with a row whose field count doesn't match the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MalformedRowPolicy {
+    /// Leave the row out and keep going
+    #[default]
+    Skip,
+    /// Import it anyway: missing fields become NULL and extra fields are dropped
+    Fix,
+    /// Stop at the first malformed row. Transactions already committed are kept.
+    Abort,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Insert every row
+    #[default]
+    Append,
+    /// Update rows whose key columns match, insert the rest
+    Upsert { key: Vec<String> },
+    /// Batched `INSERT ... ON CONFLICT(key) DO UPDATE`. Faster than `Upsert`, but the key
+    /// must have a PRIMARY KEY or UNIQUE constraint.
+    OnConflict { key: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvImportOptions {
+    /// Sniffed from the start of the file when `None`
+    pub dialect: Option<CsvDialect>,
+    /// Source encoding; overridden by a UTF-16 BOM if the file has one
+    pub encoding: TextEncoding,
+    pub on_malformed: MalformedRowPolicy,
+    /// CSV header → table column, for headers that don't match a column name
+    pub column_map: HashMap<String, String>,
+    pub mode: ImportMode,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            dialect: None,
+            encoding: TextEncoding::Utf8,
+            on_malformed: MalformedRowPolicy::Skip,
+            column_map: HashMap::new(),
+            mode: ImportMode::Append,
+        }
+    }
+}
+
+/// Options remembered for imports of files matching `file_pattern` into `table`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreset {
+    pub table: String,
+    pub file_pattern: String,
+    pub options: CsvImportOptions,
+}
+
+/// Remembered import settings, stored in the database's `imports` sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportPresets {
+    pub presets: Vec<ImportPreset>,
+}
+
+impl ImportPresets {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(sidecar::load(db_path, PRESETS_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        sidecar::save(db_path, PRESETS_SIDECAR, self)
+    }
+
+    /// Options last used for a file like `file_name` in `table`
+    pub fn find(&self, table: &str, file_name: &str) -> Option<&CsvImportOptions> {
+        self.presets
+            .iter()
+            .find(|p| p.table == table && pattern_matches(&p.file_pattern, file_name))
+            .map(|p| &p.options)
+    }
+
+    pub fn remember(&mut self, table: &str, file_name: &str, options: &CsvImportOptions) {
+        let file_pattern = file_name_pattern(file_name);
+        self.presets
+            .retain(|p| !(p.table == table && p.file_pattern == file_pattern));
+        self.presets.push(ImportPreset {
+            table: table.to_string(),
+            file_pattern,
+            options: options.clone(),
+        });
+    }
+}
+
+/// Generalise a file name so dated or numbered exports match each other:
+/// each run of digits becomes `*`, e.g. `prices_2024-05.csv` → `prices_*-*.csv`
+pub fn file_name_pattern(file_name: &str) -> String {
+    let mut pattern = String::new();
+    for c in file_name.chars() {
+        if c.is_ascii_digit() {
+            if !pattern.ends_with('*') {
+                pattern.push('*');
+            }
+        } else {
+            pattern.push(c);
+        }
+    }
+    pattern
+}
+
+/// Match `name` against a pattern where `*` stands for any run of characters
+pub fn pattern_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[derive(Debug, Clone)]
+pub struct MalformedRow {
+    /// 1-based line the record starts on
+    pub line: usize,
+    pub fields: usize,
+    pub expected: usize,
+    /// The file ended inside a quoted field
+    pub unterminated: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub rows_imported: usize,
+    /// Existing rows overwritten in [`ImportMode::Upsert`]; not included in `rows_imported`.
+    /// Always zero for [`ImportMode::OnConflict`], which can't tell the two apart.
+    pub rows_updated: usize,
+    pub elapsed_ms: u64,
+    pub dialect: CsvDialect,
+    /// Rows left out under [`MalformedRowPolicy::Skip`]
+    pub rows_skipped: usize,
+    pub malformed_total: usize,
+    /// The first [`MAX_REPORTED_MALFORMED`] malformed rows
+    pub malformed: Vec<MalformedRow>,
+}
+
+impl ImportReport {
+    fn note_malformed(&mut self, row: MalformedRow) {
+        self.malformed_total += 1;
+        if self.malformed.len() < MAX_REPORTED_MALFORMED {
+            self.malformed.push(row);
+        }
+    }
+}
+
+/// Column type guessed from a file's values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferredType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl InferredType {
+    pub const ALL: [InferredType; 3] = [InferredType::Integer, InferredType::Real, InferredType::Text];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InferredType::Integer => "INTEGER",
+            InferredType::Real => "REAL",
+            InferredType::Text => "TEXT",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// The narrowest type every non-empty value parses as
+    pub fn infer<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        let mut inferred = None;
+        for value in values.map(str::trim).filter(|v| !v.is_empty()) {
+            let this = if value.parse::<i64>().is_ok() {
+                InferredType::Integer
+            } else if value.parse::<f64>().is_ok() {
+                InferredType::Real
+            } else {
+                return InferredType::Text;
+            };
+            inferred = Some(match (inferred, this) {
+                (Some(InferredType::Real), _) | (_, InferredType::Real) => InferredType::Real,
+                _ => InferredType::Integer,
+            });
+        }
+        inferred.unwrap_or(InferredType::Text)
+    }
+}
+
+/// The start of a CSV file as the import would read it
+#[derive(Debug, Clone)]
+pub struct CsvPreview {
+    pub dialect: CsvDialect,
+    pub header: Vec<String>,
+    /// Up to [`PREVIEW_RECORDS`] records after the header
+    pub rows: Vec<Vec<String>>,
+    /// One per header column, from `rows`
+    pub types: Vec<InferredType>,
+}
+
+/// Read the header and first records of `path`, sniffing the dialect unless given
+pub fn preview_csv(path: &Path, encoding: TextEncoding, dialect: Option<CsvDialect>) -> Result<CsvPreview> {
+    let mut reader = BufReader::with_capacity(64 * 1024, DecodingReader::new(open_reader(path)?, encoding));
+    let dialect = match dialect {
+        Some(dialect) => dialect,
+        None => sniff_dialect(&utf8_prefix(reader.fill_buf()?)),
+    };
+
+    let mut header = None;
+    let mut rows = Vec::new();
+    while rows.len() < PREVIEW_RECORDS {
+        let Some(text) = read_record_text(&mut reader, &dialect)? else {
+            break;
+        };
+        if header.is_none() && is_metadata_line(&text) {
+            continue;
+        }
+        let Some(record) = parse_records(&text, &dialect).into_iter().next() else {
+            continue;
+        };
+        match header {
+            None => header = Some(record),
+            Some(_) => rows.push(record),
+        }
+    }
+    let header = header.ok_or_else(|| anyhow!("CSV file is empty"))?;
+    let types = (0..header.len())
+        .map(|col| InferredType::infer(rows.iter().filter_map(|r| r.get(col)).map(String::as_str)))
+        .collect();
+
+    Ok(CsvPreview {
+        dialect,
+        header,
+        rows,
+        types,
+    })
+}
+
+/// `CREATE TABLE` for a new table with one column per header, typed as given
+pub fn create_table_sql(table: &str, header: &[String], types: &[InferredType]) -> String {
+    let columns = header
+        .iter()
+        .zip(types)
+        .map(|(name, ty)| format!("{} {}", quote_identifier(name.trim()), ty.label()))
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+    format!("CREATE TABLE {} (\n    {}\n)", quote_identifier(table), columns)
+}
+
+/// A parsed record and the line it started on
+struct ParsedRecord {
+    line: usize,
+    fields: Vec<String>,
+    complete: bool,
+}
+
+/// Import a CSV file whose header row names columns of `table_name`.
+/// Columns not present in the file are inserted as NULL; unknown columns are ignored.
+pub fn import_csv(
+    db: &DatabaseManager,
+    path: &Path,
+    table_name: &str,
+    options: &CsvImportOptions,
+) -> Result<ImportReport> {
+    import_csv_with_progress(db, path, table_name, options, &mut |_| {})
+}
+
+/// [`import_csv`], calling `progress` with the rows written so far after each batch
+pub fn import_csv_with_progress(
+    db: &DatabaseManager,
+    path: &Path,
+    table_name: &str,
+    options: &CsvImportOptions,
+    progress: &mut dyn FnMut(usize),
+) -> Result<ImportReport> {
+    let start = Instant::now();
+    let schema = db
+        .get_schema(table_name)
+        .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+
+    // .gz/.zst files are decompressed while streaming, then transcoded to UTF-8 with any BOM
+    // stripped. Newlines need no option here since the parser accepts LF and CRLF alike.
+    let mut reader = BufReader::with_capacity(
+        64 * 1024,
+        DecodingReader::new(open_reader(path)?, options.encoding),
+    );
+
+    let dialect = match &options.dialect {
+        Some(dialect) => dialect.clone(),
+        None => sniff_dialect(&utf8_prefix(reader.fill_buf()?)),
+    };
+
+    // Exports may start with `# key: value` metadata lines ahead of the header
+    let mut metadata_lines = 0;
+    let header_text = loop {
+        let text = read_record_text(&mut reader, &dialect)?
+            .ok_or_else(|| anyhow!("CSV file is empty"))?;
+        if !is_metadata_line(&text) {
+            break text;
+        }
+        metadata_lines += 1;
+    };
+    let header = parse_records(&header_text, &dialect)
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let expected = header.len();
+
+    // Position of each schema field within a CSV record
+    let mapping: Vec<Option<usize>> = schema
+        .fields
+        .iter()
+        .map(|field| {
+            header.iter().position(|h| {
+                let h = h.trim();
+                options.column_map.get(h).map(String::as_str).unwrap_or(h) == field.name
+            })
+        })
+        .collect();
+
+    if mapping.iter().all(|m| m.is_none()) {
+        return Err(anyhow!("No CSV columns match the columns of '{}'", table_name));
+    }
+
+    // Columns the file provides; an upsert leaves the others untouched on existing rows
+    let mapped_columns: Vec<String> = schema
+        .fields
+        .iter()
+        .zip(&mapping)
+        .filter(|(_, idx)| idx.is_some())
+        .map(|(field, _)| field.name.clone())
+        .collect();
+    if let ImportMode::Upsert { key } | ImportMode::OnConflict { key } = &options.mode {
+        if let Some(missing) = key.iter().find(|k| !mapped_columns.contains(k)) {
+            return Err(anyhow!("Upsert key column '{}' is not in the CSV file", missing));
+        }
+    }
+    let mut write_batch = |report: &mut ImportReport, batch: &[Vec<Value>]| -> Result<()> {
+        match &options.mode {
+            ImportMode::Append => report.rows_imported += db.insert_rows(table_name, batch)?,
+            ImportMode::Upsert { key } => {
+                let (inserted, updated) = db.upsert_rows(table_name, key, &mapped_columns, batch)?;
+                report.rows_imported += inserted;
+                report.rows_updated += updated;
+            }
+            // SQLite doesn't say which rows hit the conflict, so all count as imported
+            ImportMode::OnConflict { key } => {
+                report.rows_imported += db.insert_rows_on_conflict(table_name, key, &mapped_columns, batch)?;
+            }
+        }
+        progress(report.rows_imported + report.rows_updated);
+        Ok(())
+    };
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_IMPORT_WORKERS);
+    let first_line = 1 + metadata_lines + header_text.matches('\n').count();
+
+    let (chunk_tx, chunk_rx) = mpsc::sync_channel::<(usize, usize, String)>(workers * 2);
+    let (parsed_tx, parsed_rx) = mpsc::sync_channel::<(usize, Vec<ParsedRecord>)>(workers * 2);
+    // Shared by the workers; once they all exit the reader sees a closed channel and stops
+    let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+
+    let mut report = std::thread::scope(|scope| -> Result<ImportReport> {
+        let reader_dialect = dialect.clone();
+        let reader_handle = scope.spawn(move || -> Result<()> {
+            let mut chunk = String::new();
+            let mut chunk_line = first_line;
+            let mut line = first_line;
+            let mut records = 0;
+            let mut index = 0;
+
+            while let Some(record) = read_record_text(&mut reader, &reader_dialect)? {
+                line += record.matches('\n').count();
+                chunk.push_str(&record);
+                records += 1;
+
+                if records == CHUNK_RECORDS {
+                    if chunk_tx.send((index, chunk_line, std::mem::take(&mut chunk))).is_err() {
+                        return Ok(());
+                    }
+                    chunk_line = line;
+                    records = 0;
+                    index += 1;
+                }
+            }
+
+            if !chunk.is_empty() {
+                let _ = chunk_tx.send((index, chunk_line, chunk));
+            }
+            Ok(())
+        });
+
+        for _ in 0..workers {
+            let chunk_rx = chunk_rx.clone();
+            let parsed_tx = parsed_tx.clone();
+            let dialect = dialect.clone();
+            scope.spawn(move || loop {
+                let next = chunk_rx.lock().recv();
+                let Ok((index, chunk_line, text)) = next else {
+                    return;
+                };
+                let records = parse_records_from(&text, &dialect, chunk_line);
+                if parsed_tx.send((index, records)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(parsed_tx);
+        drop(chunk_rx);
+
+        // Single writer: chunks can finish out of order, so hold them until their turn
+        let mut report = ImportReport::default();
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+        let mut batch = Vec::with_capacity(TRANSACTION_ROWS);
+
+        for (index, records) in parsed_rx {
+            pending.insert(index, records);
+
+            while let Some(records) = pending.remove(&next_index) {
+                for record in records {
+                    if record.fields.len() != expected || !record.complete {
+                        let row = MalformedRow {
+                            line: record.line,
+                            fields: record.fields.len(),
+                            expected,
+                            unterminated: !record.complete,
+                        };
+                        match options.on_malformed {
+                            MalformedRowPolicy::Abort => {
+                                return Err(anyhow!(
+                                    "Malformed row at line {}: {} fields, expected {}{}",
+                                    row.line,
+                                    row.fields,
+                                    row.expected,
+                                    if row.unterminated { " (unterminated quote)" } else { "" }
+                                ));
+                            }
+                            MalformedRowPolicy::Skip => {
+                                report.note_malformed(row);
+                                report.rows_skipped += 1;
+                                continue;
+                            }
+                            MalformedRowPolicy::Fix => report.note_malformed(row),
+                        }
+                    }
+                    batch.push(record_to_values(&schema.fields, &mapping, &record.fields));
+                }
+                next_index += 1;
+
+                if batch.len() >= TRANSACTION_ROWS {
+                    write_batch(&mut report, &batch)?;
+                    batch.clear();
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            write_batch(&mut report, &batch)?;
+        }
+
+        reader_handle
+            .join()
+            .map_err(|_| anyhow!("CSV reader thread panicked"))??;
+        Ok(report)
+    })?;
+
+    report.dialect = dialect;
+    report.elapsed_ms = start.elapsed().as_millis() as u64;
+    tracing::info!(
+        "Imported {} rows ({} updated) into {} in {} ms ({} malformed)",
+        report.rows_imported,
+        report.rows_updated,
+        table_name,
+        report.elapsed_ms,
+        report.malformed_total
+    );
+    Ok(report)
+}
+
+/// Guess delimiter, quote and escape characters from the first lines of a file.
+/// The delimiter is the candidate appearing most, and most consistently, per line.
+pub fn sniff_dialect(sample: &str) -> CsvDialect {
+    let mut lines: Vec<&str> = sample
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !is_metadata_line(l))
+        .collect();
+    // The sample may end mid-line
+    if lines.len() > 1 && !sample.ends_with('\n') {
+        lines.pop();
+    }
+    lines.truncate(SNIFF_LINES);
+
+    let quote = if !sample.contains('"') && lines.iter().any(|l| l.starts_with('\'')) {
+        '\''
+    } else {
+        '"'
+    };
+
+    let delimiter = SNIFF_DELIMITERS
+        .iter()
+        .copied()
+        .max_by_key(|&candidate| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .map(|l| count_unquoted(l, candidate, quote))
+                .collect();
+            let first = counts.first().copied().unwrap_or(0);
+            let consistent = counts.iter().filter(|&&n| n == first && n > 0).count();
+            // Prefer earlier candidates on ties, since max_by_key keeps the last maximum
+            let rank = SNIFF_DELIMITERS.len() - SNIFF_DELIMITERS.iter().position(|&d| d == candidate).unwrap_or(0);
+            (consistent, first, rank)
+        })
+        .unwrap_or(',');
+
+    let backslash_quote = format!("\\{}", quote);
+    let doubled_quote = format!("{0}{0}", quote);
+    let escape = if sample.contains(&backslash_quote) && !sample.contains(&doubled_quote) {
+        Some('\\')
+    } else {
+        None
+    };
+
+    CsvDialect {
+        delimiter,
+        quote,
+        escape,
+    }
+}
+
+fn count_unquoted(line: &str, target: char, quote: char) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == quote {
+            in_quotes = !in_quotes;
+        } else if c == target && !in_quotes {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// The longest valid UTF-8 prefix of a buffer that may end mid-character
+fn utf8_prefix(bytes: &[u8]) -> String {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) => e.valid_up_to(),
+    };
+    String::from_utf8_lossy(&bytes[..valid]).into_owned()
+}
+
+/// Read one complete record's raw text, which may span lines inside quoted fields
+fn read_record_text(reader: &mut impl BufRead, dialect: &CsvDialect) -> Result<Option<String>> {
+    let mut record = String::new();
+    let mut in_quotes = false;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(if record.is_empty() { None } else { Some(record) });
+        }
+
+        // Doubled quotes toggle twice, so only a lone quote changes state
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if Some(c) == dialect.escape {
+                chars.next();
+            } else if c == dialect.quote {
+                in_quotes = !in_quotes;
+            }
+        }
+        record.push_str(&line);
+
+        if !in_quotes {
+            return Ok(Some(record));
+        }
+    }
+}
+
+/// Parse RFC 4180 style records, skipping blank lines
+pub fn parse_records(text: &str, dialect: &CsvDialect) -> Vec<Vec<String>> {
+    parse_records_from(text, dialect, 1)
+        .into_iter()
+        .map(|record| record.fields)
+        .collect()
+}
+
+/// Parse records, numbering lines from `first_line`
+fn parse_records_from(text: &str, dialect: &CsvDialect, first_line: usize) -> Vec<ParsedRecord> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = first_line;
+    let mut record_line = first_line;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if Some(c) == dialect.escape {
+            if let Some(escaped) = chars.next() {
+                if escaped == '\n' {
+                    line += 1;
+                }
+                field.push(escaped);
+            }
+        } else if in_quotes {
+            if c == dialect.quote {
+                if chars.peek() == Some(&dialect.quote) {
+                    field.push(dialect.quote);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        } else if c == dialect.quote {
+            in_quotes = true;
+        } else if c == dialect.delimiter {
+            record.push(std::mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\n' || chars.next_if_eq(&'\n').is_some() {
+                line += 1;
+            }
+            record.push(std::mem::take(&mut field));
+            push_record(&mut records, record_line, std::mem::take(&mut record), true);
+            record_line = line;
+        } else {
+            field.push(c);
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() || in_quotes {
+        record.push(field);
+        push_record(&mut records, record_line, record, !in_quotes);
+    }
+
+    records
+}
+
+fn push_record(records: &mut Vec<ParsedRecord>, line: usize, fields: Vec<String>, complete: bool) {
+    let is_blank = fields.len() == 1 && fields[0].is_empty() && complete;
+    if !is_blank {
+        records.push(ParsedRecord {
+            line,
+            fields,
+            complete,
+        });
+    }
+}
+
+fn record_to_values(fields: &[FieldSchema], mapping: &[Option<usize>], record: &[String]) -> Vec<Value> {
+    fields
+        .iter()
+        .zip(mapping)
+        .map(|(field, idx)| {
+            match idx.and_then(|i| record.get(i)) {
+                Some(raw) => parse_field_value(field, raw),
+                None => Value::Null,
+            }
+        })
+        .collect()
+}
+
+/// Convert raw CSV text into a value matching the column's type, falling back to
+/// text so SQLite's type affinity can decide rather than rejecting the row
+pub fn parse_field_value(field: &FieldSchema, raw: &str) -> Value {
+    if raw.is_empty() && field.nullable {
+        return Value::Null;
+    }
+
+    match &field.sql_type {
+        SqlType::Integer | SqlType::ForeignKey { .. } => raw
+            .trim()
+            .parse::<i64>()
+            .map(|i| Value::Number(i.into()))
+            .unwrap_or_else(|_| Value::String(raw.to_string())),
+        SqlType::Real => raw
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        SqlType::Boolean => match raw.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "t" | "y" => Value::Bool(true),
+            "false" | "0" | "no" | "f" | "n" => Value::Bool(false),
+            _ => Value::String(raw.to_string()),
+        },
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_and_multiline_fields() {
+        let text = "a,b\n\"x,1\",\"say \"\"hi\"\"\"\r\n\n\"multi\nline\",2";
+        assert_eq!(
+            parse_records(text, &CsvDialect::default()),
+            [vec!["a", "b"], vec!["x,1", "say \"hi\""], vec!["multi\nline", "2"]],
+        );
+    }
+
+    #[test]
+    fn parses_escaped_quotes() {
+        let dialect = CsvDialect {
+            delimiter: ';',
+            quote: '"',
+            escape: Some('\\'),
+        };
+        assert_eq!(parse_records("\"a\\\"b\";c\n", &dialect), [vec!["a\"b", "c"]]);
+    }
+
+    #[test]
+    fn reads_records_spanning_lines() {
+        let mut reader = std::io::Cursor::new("\"a\nb\",1\nc,2\n");
+        let dialect = CsvDialect::default();
+        assert_eq!(read_record_text(&mut reader, &dialect).unwrap().as_deref(), Some("\"a\nb\",1\n"));
+        assert_eq!(read_record_text(&mut reader, &dialect).unwrap().as_deref(), Some("c,2\n"));
+        assert_eq!(read_record_text(&mut reader, &dialect).unwrap(), None);
+    }
+
+    #[test]
+    fn sniffs_dialects() {
+        let dialect = sniff_dialect("# table: t\nname;age\n\"A, B\";3\nC;4\n");
+        assert_eq!(dialect, CsvDialect { delimiter: ';', ..CsvDialect::default() });
+
+        let dialect = sniff_dialect("'a'|'b'\n'c'|'d'\n");
+        assert_eq!((dialect.delimiter, dialect.quote), ('|', '\''));
+
+        assert_eq!(sniff_dialect("a,b\n\"x\\\"y\",1\n").escape, Some('\\'));
+        assert_eq!(sniff_dialect("a,b\n\"x\"\"y\",1\n").escape, None);
+    }
+
+    #[test]
+    fn infers_column_types() {
+        assert_eq!(InferredType::infer(["1", " 2 "].into_iter()), InferredType::Integer);
+        assert_eq!(InferredType::infer(["1", "2.5", ""].into_iter()), InferredType::Real);
+        assert_eq!(InferredType::infer(["1", "x"].into_iter()), InferredType::Text);
+        assert_eq!(InferredType::infer(["", " "].into_iter()), InferredType::Text);
+    }
+
+    #[test]
+    fn matches_file_name_patterns() {
+        let pattern = file_name_pattern("prices_2024-05.csv");
+        assert_eq!(pattern, "prices_*-*.csv");
+        assert!(pattern_matches(&pattern, "prices_2025-11.csv"));
+        assert!(!pattern_matches(&pattern, "costs_2025-11.csv"));
+        assert!(!pattern_matches("a.csv", "a.csv.bak"));
+    }
+
+    #[test]
+    fn creates_table_from_header() {
+        let header = ["id".to_string(), " my name ".to_string()];
+        assert_eq!(
+            create_table_sql("t", &header, &[InferredType::Integer, InferredType::Text]),
+            "CREATE TABLE \"t\" (\n    \"id\" INTEGER,\n    \"my name\" TEXT\n)",
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Step-by-step CSV or JSON import: pick a CSV file's delimiter and encoding, check the
+//! first rows and the column types inferred from them, then map the columns (or JSON
+//! keys) onto an existing table or create a new one. Everything, including creating the
+//! table, runs in one transaction, so a failed import leaves the database untouched.
+
+use anyhow::{Result, anyhow};
+use gpui::{prelude::*, *};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use ui::{
+    h_flex, v_flex, button::{Button, ButtonVariants},
+    input::{TextInput, InputState},
+    ActiveTheme, Sizable, Disableable,
+};
+use crate::database::DatabaseManager;
+use crate::import::{self, CsvDialect, CsvImportOptions, CsvPreview, ImportPresets, ImportReport, InferredType};
+use crate::json_import;
+use crate::text_encoding::TextEncoding;
+
+/// Records shown in the preview grid; types are inferred from more
+const PREVIEW_SHOWN: usize = 10;
+
+/// Delimiters offered, in the order the button cycles through them
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+pub struct CsvImportWizard {
+    db: DatabaseManager,
+    path_input: Entity<InputState>,
+    /// Name for a new table, defaulting to the file name
+    table_input: Entity<InputState>,
+    encoding: TextEncoding,
+    /// The loaded file is JSON, whose keys stand in for the CSV header
+    json: bool,
+    preview: Option<CsvPreview>,
+    /// Column types for a new table, starting from the inferred ones
+    types: Vec<InferredType>,
+    tables: Vec<String>,
+    /// Existing table to import into; `None` creates a new one
+    target: Option<String>,
+    /// Per CSV column, the target column it fills; `None` skips it
+    mapping: Vec<Option<String>>,
+    target_columns: Vec<String>,
+    /// Rows written so far by the last run
+    progress: Option<usize>,
+    report: Option<ImportReport>,
+    message: Option<String>,
+}
+
+impl CsvImportWizard {
+    pub fn new(db: DatabaseManager, target: Option<String>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let tables = db.list_tables().unwrap_or_default();
+        Self {
+            db,
+            path_input: cx.new(|cx| InputState::new(window, cx)),
+            table_input: cx.new(|cx| InputState::new(window, cx)),
+            encoding: TextEncoding::Utf8,
+            json: false,
+            preview: None,
+            types: Vec::new(),
+            tables,
+            target,
+            mapping: Vec::new(),
+            target_columns: Vec::new(),
+            progress: None,
+            report: None,
+            message: None,
+        }
+    }
+
+    fn path(&self, cx: &App) -> PathBuf {
+        PathBuf::from(self.path_input.read(cx).value().trim())
+    }
+
+    /// Read the file's start, sniffing the delimiter unless `dialect` is given
+    pub fn load(&mut self, dialect: Option<CsvDialect>, window: &mut Window, cx: &mut Context<Self>) -> Result<()> {
+        let path = self.path(cx);
+        self.json = json_import::is_json_path(&path);
+        let preview = if self.json {
+            json_import::preview_json(&path)?
+        } else {
+            import::preview_csv(&path, self.encoding, dialect)?
+        };
+        if self.table_input.read(cx).value().trim().is_empty() {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            self.table_input.update(cx, |input, cx| input.set_value(&stem, window, cx));
+        }
+        self.types = preview.types.clone();
+        self.message = Some(format!(
+            "{} columns, {} rows read to infer types",
+            preview.header.len(),
+            preview.rows.len()
+        ));
+        self.preview = Some(preview);
+        self.report = None;
+        self.progress = None;
+        self.match_columns()
+    }
+
+    pub fn cycle_delimiter(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Result<()> {
+        let Some(mut dialect) = self.preview.as_ref().map(|p| p.dialect.clone()) else {
+            return Ok(());
+        };
+        let ix = DELIMITERS.iter().position(|d| *d == dialect.delimiter).map(|i| i + 1).unwrap_or(0);
+        dialect.delimiter = DELIMITERS[ix % DELIMITERS.len()];
+        self.load(Some(dialect), window, cx)
+    }
+
+    pub fn cycle_encoding(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Result<()> {
+        self.encoding = self.encoding.next();
+        let dialect = self.preview.as_ref().map(|p| p.dialect.clone());
+        if dialect.is_some() {
+            self.load(dialect, window, cx)?;
+        }
+        Ok(())
+    }
+
+    pub fn cycle_type(&mut self, col: usize) {
+        if let Some(ty) = self.types.get_mut(col) {
+            *ty = ty.next();
+        }
+    }
+
+    /// Import into the next existing table, or a new one after the last
+    pub fn cycle_target(&mut self) -> Result<()> {
+        let position = self.target.as_ref().and_then(|t| self.tables.iter().position(|name| name == t));
+        self.target = match position {
+            Some(p) => self.tables.get(p + 1).cloned(),
+            None => self.tables.first().cloned(),
+        };
+        self.match_columns()
+    }
+
+    /// Point a CSV column at the next target column, or skip it after the last
+    pub fn cycle_mapping(&mut self, col: usize) {
+        let Some(current) = self.mapping.get(col) else {
+            return;
+        };
+        let position = current.as_ref().and_then(|c| self.target_columns.iter().position(|t| t == c));
+        let next = match position {
+            Some(p) => self.target_columns.get(p + 1).cloned(),
+            None => self.target_columns.first().cloned(),
+        };
+        self.mapping[col] = next;
+    }
+
+    /// Match CSV columns to the target table's columns by name, ignoring case
+    fn match_columns(&mut self) -> Result<()> {
+        self.target_columns = match &self.target {
+            Some(table) => self.db.column_names(table)?,
+            None => Vec::new(),
+        };
+        self.mapping = self
+            .preview
+            .as_ref()
+            .map(|p| {
+                p.header
+                    .iter()
+                    .map(|h| self.target_columns.iter().find(|c| c.eq_ignore_ascii_case(h.trim())).cloned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(())
+    }
+
+    pub fn run(&mut self, cx: &mut Context<Self>) -> Result<ImportReport> {
+        let Some(preview) = self.preview.as_ref() else {
+            return Err(anyhow!("Load a file first"));
+        };
+        let path = self.path(cx);
+        let (table, create_sql) = match &self.target {
+            Some(table) => (table.clone(), None),
+            None => {
+                let table = self.table_input.read(cx).value().trim().to_string();
+                if table.is_empty() {
+                    return Err(anyhow!("Name the new table"));
+                }
+                if self.tables.iter().any(|t| t.eq_ignore_ascii_case(&table)) {
+                    return Err(anyhow!("{} already exists; choose it as the target instead", table));
+                }
+                let sql = import::create_table_sql(&table, &preview.header, &self.types);
+                (table, Some(sql))
+            }
+        };
+
+        // Skipped columns map to no name, which no table column matches
+        let column_map: HashMap<String, String> = match &self.target {
+            Some(_) => preview
+                .header
+                .iter()
+                .zip(&self.mapping)
+                .map(|(header, target)| (header.trim().to_string(), target.clone().unwrap_or_default()))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let options = CsvImportOptions {
+            dialect: Some(preview.dialect.clone()),
+            encoding: self.encoding,
+            column_map,
+            ..Default::default()
+        };
+
+        let mut written = 0;
+        let json = self.json;
+        let result = self.db.in_transaction(|| {
+            if let Some(sql) = &create_sql {
+                self.db.execute_query(sql)?;
+                self.db.refresh_schemas()?;
+            }
+            if json {
+                json_import::import_json(&self.db, &path, &table, &options.column_map, &mut |rows| written = rows)
+            } else {
+                import::import_csv_with_progress(&self.db, &path, &table, &options, &mut |rows| written = rows)
+            }
+        });
+        self.progress = Some(written);
+        if create_sql.is_some() {
+            // A rolled-back table must not linger in the registered schemas
+            self.db.refresh_schemas()?;
+            self.tables = self.db.list_tables()?;
+        }
+        let report = result?;
+
+        if let (false, Some(db_path), Some(file_name)) = (json, self.db.path(), path.file_name()) {
+            let mut presets = ImportPresets::load(db_path).unwrap_or_default();
+            presets.remember(&table, &file_name.to_string_lossy(), &options);
+            if let Err(e) = presets.save(db_path) {
+                tracing::error!("Failed to remember import settings: {}", e);
+            }
+        }
+        tracing::info!("✓ Imported {} rows from {:?} into {}", report.rows_imported, path, table);
+        self.message = None;
+        self.report = Some(report.clone());
+        Ok(report)
+    }
+
+    fn render_preview(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(preview) = self.preview.as_ref() else {
+            return div()
+                .p_2()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child("Enter the path of a .csv, .tsv, .json or .ndjson file (.gz and .zst work too) and load it")
+                .into_any_element();
+        };
+        let creating = self.target.is_none();
+
+        div()
+            .id("csv-preview")
+            .flex_1()
+            .min_h_0()
+            .overflow_scroll()
+            .p_2()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .children(preview.header.iter().enumerate().map(|(col, header)| {
+                        let column = v_flex()
+                            .w_32()
+                            .gap_1()
+                            .child(div().text_sm().text_color(cx.theme().foreground).child(header.clone()));
+                        let column = if creating {
+                            let ty = self.types.get(col).copied().unwrap_or(InferredType::Text);
+                            column.child(
+                                Button::new(("csv-type", col))
+                                    .label(ty.label())
+                                    .tooltip("Inferred type; click to change it")
+                                    .xsmall()
+                                    .outline()
+                                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                                        wizard.cycle_type(col);
+                                        cx.notify();
+                                    }))
+                            )
+                        } else {
+                            let target = self.mapping.get(col).cloned().flatten();
+                            column.child(
+                                Button::new(("csv-map", col))
+                                    .label(match target {
+                                        Some(target) => format!("→ {}", target),
+                                        None => "skip".to_string(),
+                                    })
+                                    .tooltip("Click to choose the column it fills")
+                                    .xsmall()
+                                    .outline()
+                                    .on_click(cx.listener(move |wizard, _, _, cx| {
+                                        wizard.cycle_mapping(col);
+                                        cx.notify();
+                                    }))
+                            )
+                        };
+                        column.children(preview.rows.iter().take(PREVIEW_SHOWN).map(|row| {
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .child(row.get(col).cloned().unwrap_or_default())
+                        }))
+                    }))
+            )
+            .into_any_element()
+    }
+}
+
+impl Render for CsvImportWizard {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let loaded = self.preview.is_some();
+        let can_run = loaded && !self.db.is_read_only();
+        let delimiter = self.preview.as_ref().map(|p| match p.dialect.delimiter {
+            '\t' => "tab".to_string(),
+            d => d.to_string(),
+        });
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(div().text_sm().child("Import CSV or JSON from"))
+                    .child(TextInput::new(&self.path_input).w_64().text_xs())
+                    .child(
+                        Button::new("csv-load")
+                            .label("Load")
+                            .tooltip("Read the first rows, sniffing the delimiter")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|wizard, _, window, cx| {
+                                if let Err(e) = wizard.load(None, window, cx) {
+                                    tracing::error!("Failed to read import file: {}", e);
+                                    wizard.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("csv-delimiter")
+                            .label(format!("Delimiter: {}", delimiter.unwrap_or_else(|| "auto".to_string())))
+                            .xsmall()
+                            .ghost()
+                            .disabled(!loaded || self.json)
+                            .on_click(cx.listener(|wizard, _, window, cx| {
+                                if let Err(e) = wizard.cycle_delimiter(window, cx) {
+                                    tracing::error!("Failed to read CSV file: {}", e);
+                                    wizard.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("csv-encoding")
+                            .label(format!("Encoding: {}", self.encoding.label()))
+                            .xsmall()
+                            .ghost()
+                            .disabled(self.json)
+                            .on_click(cx.listener(|wizard, _, window, cx| {
+                                if let Err(e) = wizard.cycle_encoding(window, cx) {
+                                    tracing::error!("Failed to read CSV file: {}", e);
+                                    wizard.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(self.render_preview(cx))
+            .child(
+                v_flex()
+                    .w_full()
+                    .gap_1()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(div().text_sm().child("Into"))
+                            .child(
+                                Button::new("csv-target")
+                                    .label(self.target.clone().unwrap_or_else(|| "New table".to_string()))
+                                    .tooltip("Cycle through the existing tables")
+                                    .xsmall()
+                                    .outline()
+                                    .on_click(cx.listener(|wizard, _, _, cx| {
+                                        if let Err(e) = wizard.cycle_target() {
+                                            tracing::error!("Failed to read table columns: {}", e);
+                                            wizard.message = Some(e.to_string());
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .when(self.target.is_none(), |row| {
+                                row.child(TextInput::new(&self.table_input).w_48().text_xs())
+                            })
+                            .child(
+                                Button::new("csv-import")
+                                    .label("Import")
+                                    .tooltip("Insert every row in one transaction")
+                                    .xsmall()
+                                    .primary()
+                                    .disabled(!can_run)
+                                    .on_click(cx.listener(|wizard, _, _, cx| {
+                                        if let Err(e) = wizard.run(cx) {
+                                            tracing::error!("Failed to import file: {}", e);
+                                            wizard.message = Some(format!("{}; nothing was imported", e));
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .when_some(self.progress, |footer, written| {
+                        footer.when(self.report.is_none(), |footer| {
+                            footer.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("{} rows written before stopping", written))
+                            )
+                        })
+                    })
+                    .when_some(self.report.as_ref(), |footer, report| {
+                        footer.child(
+                            div()
+                                .text_xs()
+                                .text_color(if report.malformed_total > 0 { cx.theme().warning } else { cx.theme().success })
+                                .child(format!(
+                                    "Imported {} rows in {} ms ({} malformed, {} skipped)",
+                                    report.rows_imported,
+                                    report.elapsed_ms,
+                                    report.malformed_total,
+                                    report.rows_skipped
+                                ))
+                        )
+                    })
+                    .when_some(self.message.clone(), |footer, message| {
+                        footer.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            )
+    }
+}
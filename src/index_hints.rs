@@ -0,0 +1,106 @@
+//! Hints for slow grid sorts and filters: when applying one took too long and SQLite's
+//! plan shows it scanning the whole table (or sorting in a temporary b-tree), the grid
+//! offers to create an index on the columns involved.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::time::Duration;
+use crate::database::{DatabaseManager, SortKey};
+use crate::row_filter::{Connective, FilterOp, RowFilter};
+
+/// Sorts and filters faster than this never hint
+pub const SLOW_GRID_QUERY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexHint {
+    pub table: String,
+    /// Filtered columns first, then the sort columns, as the index would list them
+    pub columns: Vec<String>,
+    /// `sorting` or `filtering`
+    pub action: &'static str,
+    pub scanned_rows: usize,
+    pub elapsed: Duration,
+}
+
+impl IndexHint {
+    /// `sorting by level scanned ~2.1M rows in 840 ms — create index?`
+    pub fn message(&self) -> String {
+        format!(
+            "{} by {} scanned ~{} rows in {} ms — create index?",
+            self.action,
+            self.columns.join(", "),
+            compact_count(self.scanned_rows),
+            self.elapsed.as_millis()
+        )
+    }
+
+    /// `ix_items_level`, as the index manager names them
+    pub fn index_name(&self) -> String {
+        let name = format!("ix_{}_{}", self.table, self.columns.join("_"));
+        name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+    }
+
+    pub fn create(&self, db: &DatabaseManager) -> Result<()> {
+        db.create_index(&self.index_name(), &self.table, &self.columns, false, None)
+    }
+}
+
+/// Look for a hint after the grid took `elapsed` to load `table` through `condition`
+/// in `sort` order. Only the filter bar's indexable conditions count toward the columns;
+/// `contains` and the raw WHERE can't be served by a plain index.
+pub fn check(
+    db: &DatabaseManager,
+    table: &str,
+    filter: &RowFilter,
+    condition: Option<(&str, &[Value])>,
+    sort: &[SortKey],
+    elapsed: Duration,
+) -> Result<Option<IndexHint>> {
+    if elapsed < SLOW_GRID_QUERY {
+        return Ok(None);
+    }
+    let (sql, params) = condition.unwrap_or(("", &[]));
+    let plan = db.grid_query_plan(table, Some(sql).filter(|s| !s.is_empty()), params, sort)?;
+    let full_scan = plan.iter().any(|detail| detail.starts_with("SCAN ") && !detail.contains(" USING "));
+    let temp_sort = plan.iter().any(|detail| detail.contains("TEMP B-TREE FOR ORDER BY"));
+
+    let mut columns: Vec<String> = Vec::new();
+    // An OR can't use one index for both sides
+    let conjunctive = filter.conditions.iter().skip(1).all(|c| c.connective == Connective::And);
+    if full_scan && conjunctive {
+        for condition in &filter.conditions {
+            let indexable = matches!(condition.op, FilterOp::Equals | FilterOp::Between | FilterOp::InList | FilterOp::IsNull);
+            if indexable && !columns.contains(&condition.column) {
+                columns.push(condition.column.clone());
+            }
+        }
+    }
+    let filtering = !columns.is_empty();
+    if temp_sort {
+        for key in sort {
+            if !columns.contains(&key.column) {
+                columns.push(key.column.clone());
+            }
+        }
+    }
+    if columns.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(IndexHint {
+        table: table.to_string(),
+        columns,
+        action: if filtering { "filtering" } else { "sorting" },
+        scanned_rows: db.estimate_row_count(table)?,
+        elapsed,
+    }))
+}
+
+/// `2.1M`, `34K`, `812`
+fn compact_count(count: usize) -> String {
+    match count {
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1_000_000.0),
+        n if n >= 10_000 => format!("{}K", n / 1_000),
+        n => n.to_string(),
+    }
+}
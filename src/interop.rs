@@ -0,0 +1,118 @@
+//! Rows exchanged with other Pulsar editors. Copies put tab-separated text on the
+//! clipboard for spreadsheets, with a typed payload attached as metadata that engine
+//! editors (scene, spawn tables) read instead. The same payload is accepted back by
+//! paste, in-app drags and dropped `.json` files.
+
+use anyhow::{Result, anyhow};
+use gpui::{ClipboardEntry, ClipboardItem};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+use crate::reflection::TypeSchema;
+
+pub const PAYLOAD_KIND: &str = "pulsar.records";
+pub const PAYLOAD_VERSION: u32 = 1;
+
+/// Records of one engine type, keyed by field name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordPayload {
+    pub kind: String,
+    pub version: u32,
+    /// Engine type the records describe, e.g. `SpawnEntry`
+    pub type_name: String,
+    /// Table the records were copied from, if any
+    #[serde(default)]
+    pub source_table: Option<String>,
+    pub records: Vec<Map<String, Value>>,
+}
+
+impl RecordPayload {
+    pub fn from_rows(schema: &TypeSchema, rows: &[Vec<Value>]) -> Self {
+        let records = rows
+            .iter()
+            .map(|row| {
+                schema
+                    .fields
+                    .iter()
+                    .zip(row)
+                    .map(|(field, value)| (field.name.clone(), value.clone()))
+                    .collect()
+            })
+            .collect();
+        Self {
+            kind: PAYLOAD_KIND.to_string(),
+            version: PAYLOAD_VERSION,
+            type_name: schema.type_name.clone(),
+            source_table: Some(schema.table_name.clone()),
+            records,
+        }
+    }
+
+    /// Field names in first-seen order across all records
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns: Vec<String> = Vec::new();
+        for record in &self.records {
+            for name in record.keys() {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
+        }
+        columns
+    }
+
+    /// Header row plus one line per record, as spreadsheets expect on the clipboard.
+    /// NULLs become empty cells; nested values are written as JSON.
+    pub fn to_tsv(&self) -> String {
+        let columns = self.columns();
+        let clean = |text: String| text.replace(['\t', '\n', '\r'], " ");
+        let mut lines = vec![columns.join("\t")];
+        for record in &self.records {
+            let cells: Vec<String> = columns
+                .iter()
+                .map(|name| match record.get(name) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(s)) => clean(s.clone()),
+                    Some(Value::Bool(b)) => if *b { "1" } else { "0" }.to_string(),
+                    Some(other) => clean(other.to_string()),
+                })
+                .collect();
+            lines.push(cells.join("\t"));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_clipboard(&self) -> ClipboardItem {
+        ClipboardItem::new_string_with_json_metadata(self.to_tsv(), self)
+    }
+
+    /// The payload attached to a clipboard item, if another Pulsar editor put one there
+    pub fn from_clipboard(item: &ClipboardItem) -> Option<Self> {
+        item.entries().iter().find_map(|entry| match entry {
+            ClipboardEntry::String(string) => string
+                .metadata_json::<Self>()
+                .filter(|payload| payload.is_supported()),
+            _ => None,
+        })
+    }
+
+    /// A payload written out as JSON, e.g. a file dragged in from the asset browser
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let payload: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if !payload.is_supported() {
+            return Err(anyhow!(
+                "{} is a {} v{} payload, expected {} v{}",
+                path.display(),
+                payload.kind,
+                payload.version,
+                PAYLOAD_KIND,
+                PAYLOAD_VERSION
+            ));
+        }
+        Ok(payload)
+    }
+
+    fn is_supported(&self) -> bool {
+        self.kind == PAYLOAD_KIND && self.version <= PAYLOAD_VERSION
+    }
+}
@@ -0,0 +1,170 @@
+//! JSON import: an array of objects, newline-delimited objects, or a JSON export of this
+//! editor (`{"metadata": .., "rows": [..]}`), into an existing table by key name or a new
+//! table with one column per key. Nested objects and arrays are stored as JSON text.
+
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::time::Instant;
+use crate::compression::open_reader;
+use crate::database::DatabaseManager;
+use crate::import::{CsvDialect, CsvPreview, ImportReport, InferredType, PREVIEW_RECORDS};
+
+/// Rows inserted per batch
+const BATCH_ROWS: usize = 50_000;
+
+/// Whether `path` names a JSON or NDJSON file, compressed or not
+pub fn is_json_path(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let name = name.trim_end_matches(".gz").trim_end_matches(".zst");
+    name.ends_with(".json") || name.ends_with(".ndjson") || name.ends_with(".jsonl")
+}
+
+/// Every object in the file, in order
+pub fn read_records(path: &Path) -> Result<Vec<Map<String, Value>>> {
+    let mut text = String::new();
+    open_reader(path)?.read_to_string(&mut text)?;
+    let text = text.trim_start_matches('\u{feff}').trim();
+
+    // A whole document is an array or an export wrapper; anything else is read line by line
+    let values = match serde_json::from_str::<Value>(text) {
+        Ok(Value::Array(values)) => values,
+        Ok(Value::Object(mut object)) if object.contains_key("metadata") => match object.remove("rows") {
+            Some(Value::Array(values)) => values,
+            _ => return Err(anyhow!("Expected a \"rows\" array")),
+        },
+        Ok(value) => vec![value],
+        Err(_) => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(ix, line)| serde_json::from_str(line).map_err(|e| anyhow!("Line {}: {}", ix + 1, e)))
+            .collect::<Result<_>>()?,
+    };
+
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(ix, value)| match value {
+            Value::Object(object) => Ok(object),
+            other => Err(anyhow!("Record {} is not an object: {}", ix + 1, other)),
+        })
+        .collect()
+}
+
+/// Keys in the order they first appear across `records`
+pub fn record_keys(records: &[Map<String, Value>]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in records.iter().flat_map(|r| r.keys()) {
+        if !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+    keys
+}
+
+/// The file as the import wizard shows it: its keys as the header, values as text and
+/// the types inferred from the first [`PREVIEW_RECORDS`] records
+pub fn preview_json(path: &Path) -> Result<CsvPreview> {
+    let records = read_records(path)?;
+    if records.is_empty() {
+        return Err(anyhow!("JSON file has no records"));
+    }
+    let header = record_keys(&records);
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .take(PREVIEW_RECORDS)
+        .map(|record| header.iter().map(|key| record.get(key).map(display_value).unwrap_or_default()).collect())
+        .collect();
+    let types = header
+        .iter()
+        .map(|key| infer_type(records.iter().take(PREVIEW_RECORDS).filter_map(|r| r.get(key))))
+        .collect();
+
+    Ok(CsvPreview {
+        dialect: CsvDialect::default(),
+        header,
+        rows,
+        types,
+    })
+}
+
+/// Import every record into `table_name`, matching keys to columns by name unless
+/// `key_map` names another column. Keys mapped to no column are dropped and columns no
+/// key fills are inserted as NULL.
+pub fn import_json(
+    db: &DatabaseManager,
+    path: &Path,
+    table_name: &str,
+    key_map: &HashMap<String, String>,
+    progress: &mut dyn FnMut(usize),
+) -> Result<ImportReport> {
+    let start = Instant::now();
+    let schema = db
+        .get_schema(table_name)
+        .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+    let records = read_records(path)?;
+    let keys = record_keys(&records);
+
+    // The key filling each schema field
+    let mapping: Vec<Option<&String>> = schema
+        .fields
+        .iter()
+        .map(|field| keys.iter().find(|k| key_map.get(*k).unwrap_or(*k) == &field.name))
+        .collect();
+    if mapping.iter().all(|m| m.is_none()) {
+        return Err(anyhow!("No JSON keys match the columns of '{}'", table_name));
+    }
+
+    let mut report = ImportReport::default();
+    for chunk in records.chunks(BATCH_ROWS) {
+        let batch: Vec<Vec<Value>> = chunk
+            .iter()
+            .map(|record| {
+                mapping
+                    .iter()
+                    .map(|key| match key.and_then(|k| record.get(k)) {
+                        Some(value @ (Value::Array(_) | Value::Object(_))) => Value::String(value.to_string()),
+                        Some(value) => value.clone(),
+                        None => Value::Null,
+                    })
+                    .collect()
+            })
+            .collect();
+        report.rows_imported += db.insert_rows(table_name, &batch)?;
+        progress(report.rows_imported);
+    }
+
+    report.elapsed_ms = start.elapsed().as_millis() as u64;
+    tracing::info!("Imported {} JSON records into {} in {} ms", report.rows_imported, table_name, report.elapsed_ms);
+    Ok(report)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Booleans count as integers, which is how SQLite stores them
+fn infer_type<'a>(values: impl Iterator<Item = &'a Value>) -> InferredType {
+    let mut inferred = None;
+    for value in values {
+        let this = match value {
+            Value::Null => continue,
+            Value::Bool(_) => InferredType::Integer,
+            Value::Number(n) if n.is_i64() || n.is_u64() => InferredType::Integer,
+            Value::Number(_) => InferredType::Real,
+            _ => return InferredType::Text,
+        };
+        inferred = Some(match (inferred, this) {
+            (Some(InferredType::Real), _) | (_, InferredType::Real) => InferredType::Real,
+            _ => InferredType::Integer,
+        });
+    }
+    inferred.unwrap_or(InferredType::Text)
+}
@@ -0,0 +1,401 @@
+//! Vim and Emacs style editing for the query editor. Keys are turned into cursor moves
+//! and edits over the query text here, and the query editor applies them to its input;
+//! keys a mode doesn't bind pass through to the input as usual. Undo stays on Ctrl+Z.
+
+use gpui::Keystroke;
+use std::ops::Range;
+use crate::settings::KeyBindingMode;
+
+/// What a key press does to the query text. Offsets are bytes into the text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyAction {
+    /// Not bound in this mode; the input handles it
+    Pass,
+    /// Swallowed, e.g. an unbound letter in vim's normal mode or the first key of `dd`
+    Consume,
+    MoveTo(usize),
+    /// Replace `range` with `text`, leaving the cursor at `cursor`, which falls within
+    /// the inserted text
+    Edit { range: Range<usize>, text: String, cursor: usize },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModalKeys {
+    pub mode: KeyBindingMode,
+    /// Vim's insert mode; vim starts in normal mode
+    inserting: bool,
+    /// First key of a two-key vim command: `d`, `y` or `g`
+    pending: Option<char>,
+    /// Last yanked, deleted or killed text
+    register: String,
+    /// The register holds whole lines, which vim puts above or below the cursor's line
+    linewise: bool,
+}
+
+impl ModalKeys {
+    pub fn set_mode(&mut self, mode: KeyBindingMode) {
+        self.mode = mode;
+        self.inserting = false;
+        self.pending = None;
+    }
+
+    /// Shown beside the editor title, e.g. `-- INSERT --`
+    pub fn status(&self) -> Option<String> {
+        match self.mode {
+            KeyBindingMode::Standard => None,
+            KeyBindingMode::Emacs => Some("emacs".to_string()),
+            KeyBindingMode::Vim if self.inserting => Some("-- INSERT --".to_string()),
+            KeyBindingMode::Vim => Some(match self.pending {
+                Some(key) => format!("-- NORMAL -- {}", key),
+                None => "-- NORMAL --".to_string(),
+            }),
+        }
+    }
+
+    /// Interpret `keystroke` with the cursor at `cursor` in `text`
+    pub fn handle(&mut self, keystroke: &Keystroke, text: &str, cursor: usize) -> KeyAction {
+        match self.mode {
+            KeyBindingMode::Standard => KeyAction::Pass,
+            KeyBindingMode::Vim => self.vim(keystroke, text, cursor),
+            KeyBindingMode::Emacs => self.emacs(keystroke, text, cursor),
+        }
+    }
+
+    fn vim(&mut self, keystroke: &Keystroke, text: &str, cursor: usize) -> KeyAction {
+        let modifiers = &keystroke.modifiers;
+        let escape = keystroke.key == "escape" || (modifiers.control && keystroke.key == "[");
+        if self.inserting {
+            if !escape {
+                return KeyAction::Pass;
+            }
+            // Leaving insert mode steps back onto the last character typed
+            self.inserting = false;
+            return KeyAction::MoveTo(if cursor > line_start(text, cursor) { prev(text, cursor) } else { cursor });
+        }
+        if escape {
+            self.pending = None;
+            return KeyAction::Consume;
+        }
+        // Shortcuts such as Ctrl+Enter and Ctrl+C keep working in normal mode
+        if modifiers.control || modifiers.alt || modifiers.platform {
+            return KeyAction::Pass;
+        }
+        match keystroke.key.as_str() {
+            "backspace" => return KeyAction::MoveTo(left(text, cursor)),
+            "enter" => return KeyAction::MoveTo(line_down(text, cursor)),
+            "tab" | "delete" => return KeyAction::Consume,
+            _ => {}
+        }
+        let Some(key) = keystroke.key_char.as_deref().and_then(single_char) else {
+            return KeyAction::Pass;
+        };
+        if let Some(first) = self.pending.take() {
+            return self.vim_pair(first, key, text, cursor);
+        }
+
+        match key {
+            'h' => KeyAction::MoveTo(left(text, cursor)),
+            'l' => KeyAction::MoveTo(right(text, cursor)),
+            'j' => KeyAction::MoveTo(line_down(text, cursor)),
+            'k' => KeyAction::MoveTo(line_up(text, cursor)),
+            'w' => KeyAction::MoveTo(word_forward(text, cursor)),
+            'b' => KeyAction::MoveTo(word_back(text, cursor)),
+            'e' => KeyAction::MoveTo(word_end(text, cursor)),
+            '0' => KeyAction::MoveTo(line_start(text, cursor)),
+            '^' => KeyAction::MoveTo(first_non_blank(text, cursor)),
+            '$' => KeyAction::MoveTo(line_end(text, cursor)),
+            'G' => KeyAction::MoveTo(line_start(text, text.len())),
+            'd' | 'y' | 'g' => {
+                self.pending = Some(key);
+                KeyAction::Consume
+            }
+            'x' => {
+                let end = line_end(text, cursor);
+                if cursor == end {
+                    return KeyAction::Consume;
+                }
+                self.delete(text, cursor..next(text, cursor))
+            }
+            'D' => self.delete(text, cursor..line_end(text, cursor)),
+            'i' => self.insert_at(cursor),
+            'a' => {
+                let at = if cursor < line_end(text, cursor) { next(text, cursor) } else { cursor };
+                self.insert_at(at)
+            }
+            'I' => self.insert_at(first_non_blank(text, cursor)),
+            'A' => self.insert_at(line_end(text, cursor)),
+            'o' => {
+                self.inserting = true;
+                let end = line_end(text, cursor);
+                KeyAction::Edit { range: end..end, text: "\n".to_string(), cursor: end + 1 }
+            }
+            'O' => {
+                self.inserting = true;
+                let start = line_start(text, cursor);
+                KeyAction::Edit { range: start..start, text: "\n".to_string(), cursor: start }
+            }
+            'p' | 'P' => self.put(key == 'p', text, cursor),
+            _ => KeyAction::Consume,
+        }
+    }
+
+    fn vim_pair(&mut self, first: char, key: char, text: &str, cursor: usize) -> KeyAction {
+        match (first, key) {
+            ('g', 'g') => KeyAction::MoveTo(0),
+            ('d', 'd') => {
+                let (start, end) = (line_start(text, cursor), line_end(text, cursor));
+                self.register = format!("{}\n", &text[start..end]);
+                self.linewise = true;
+                // The last line takes the newline before it instead of after
+                let range = if end < text.len() {
+                    start..end + 1
+                } else {
+                    start.saturating_sub(1)..end
+                };
+                let at = range.start;
+                KeyAction::Edit { range, text: String::new(), cursor: at }
+            }
+            ('d', 'w') => self.delete(text, cursor..word_forward(text, cursor)),
+            ('d', '$') => self.delete(text, cursor..line_end(text, cursor)),
+            ('y', 'y') => {
+                self.register = format!("{}\n", &text[line_start(text, cursor)..line_end(text, cursor)]);
+                self.linewise = true;
+                KeyAction::Consume
+            }
+            ('y', 'w') => {
+                self.register = text[cursor..word_forward(text, cursor)].to_string();
+                self.linewise = false;
+                KeyAction::Consume
+            }
+            _ => KeyAction::Consume,
+        }
+    }
+
+    fn emacs(&mut self, keystroke: &Keystroke, text: &str, cursor: usize) -> KeyAction {
+        let modifiers = &keystroke.modifiers;
+        let key = keystroke.key.as_str();
+        if modifiers.control && !modifiers.alt {
+            return match key {
+                "a" => KeyAction::MoveTo(line_start(text, cursor)),
+                "e" => KeyAction::MoveTo(line_end(text, cursor)),
+                "f" => KeyAction::MoveTo(next(text, cursor)),
+                "b" => KeyAction::MoveTo(prev(text, cursor)),
+                "n" => KeyAction::MoveTo(line_down(text, cursor)),
+                "p" => KeyAction::MoveTo(line_up(text, cursor)),
+                "d" if cursor < text.len() => KeyAction::Edit { range: cursor..next(text, cursor), text: String::new(), cursor },
+                "k" => {
+                    // At the end of a line, C-k joins it with the next
+                    let end = line_end(text, cursor);
+                    let end = if end == cursor { next(text, cursor) } else { end };
+                    self.delete(text, cursor..end)
+                }
+                "y" => KeyAction::Edit {
+                    range: cursor..cursor,
+                    text: self.register.clone(),
+                    cursor: cursor + self.register.len(),
+                },
+                "g" => KeyAction::Consume,
+                _ => KeyAction::Pass,
+            };
+        }
+        if modifiers.alt && !modifiers.control {
+            let shifted = |plain: &str, shifted: &str| key == shifted || (modifiers.shift && key == plain);
+            return match key {
+                "f" => KeyAction::MoveTo(word_end_exclusive(text, cursor)),
+                "b" => KeyAction::MoveTo(word_back(text, cursor)),
+                "d" => self.delete(text, cursor..word_end_exclusive(text, cursor)),
+                "backspace" => self.delete(text, word_back(text, cursor)..cursor),
+                _ if shifted(",", "<") => KeyAction::MoveTo(0),
+                _ if shifted(".", ">") => KeyAction::MoveTo(text.len()),
+                _ => KeyAction::Pass,
+            };
+        }
+        KeyAction::Pass
+    }
+
+    fn insert_at(&mut self, at: usize) -> KeyAction {
+        self.inserting = true;
+        KeyAction::MoveTo(at)
+    }
+
+    /// Remove `range`, keeping it in the register
+    fn delete(&mut self, text: &str, range: Range<usize>) -> KeyAction {
+        if range.is_empty() {
+            return KeyAction::Consume;
+        }
+        self.register = text[range.clone()].to_string();
+        self.linewise = false;
+        let at = range.start;
+        KeyAction::Edit { range, text: String::new(), cursor: at }
+    }
+
+    /// Vim's `p` (`after`) and `P`
+    fn put(&self, after: bool, text: &str, cursor: usize) -> KeyAction {
+        if self.register.is_empty() {
+            return KeyAction::Consume;
+        }
+        if self.linewise {
+            let line = self.register.trim_end_matches('\n');
+            return if after {
+                let end = line_end(text, cursor);
+                KeyAction::Edit { range: end..end, text: format!("\n{}", line), cursor: end + 1 }
+            } else {
+                let start = line_start(text, cursor);
+                KeyAction::Edit { range: start..start, text: format!("{}\n", line), cursor: start }
+            };
+        }
+        let at = if after && cursor < line_end(text, cursor) { next(text, cursor) } else { cursor };
+        let last = prev(&self.register, self.register.len());
+        KeyAction::Edit { range: at..at, text: self.register.clone(), cursor: at + last }
+    }
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+/// The character boundary before `i`
+pub fn prev(text: &str, i: usize) -> usize {
+    text[..i].char_indices().next_back().map_or(0, |(ix, _)| ix)
+}
+
+/// The character boundary after `i`
+pub fn next(text: &str, i: usize) -> usize {
+    text[i..].chars().next().map_or(i, |c| i + c.len_utf8())
+}
+
+/// One character left, not past the start of the line
+fn left(text: &str, i: usize) -> usize {
+    if i > line_start(text, i) { prev(text, i) } else { i }
+}
+
+/// One character right, stopping on the line's last character
+fn right(text: &str, i: usize) -> usize {
+    let end = line_end(text, i);
+    let to = next(text, i);
+    if to < end { to } else { i }
+}
+
+pub fn line_start(text: &str, i: usize) -> usize {
+    text[..i].rfind('\n').map_or(0, |n| n + 1)
+}
+
+pub fn line_end(text: &str, i: usize) -> usize {
+    text[i..].find('\n').map_or(text.len(), |n| i + n)
+}
+
+fn first_non_blank(text: &str, i: usize) -> usize {
+    let start = line_start(text, i);
+    let line = &text[start..line_end(text, i)];
+    start + (line.len() - line.trim_start().len())
+}
+
+/// `column` characters into the line starting at `line`, or that line's end if it's shorter
+pub fn at_column(text: &str, line: usize, column: usize) -> usize {
+    let end = line_end(text, line);
+    text[line..end]
+        .char_indices()
+        .nth(column)
+        .map_or(end, |(ix, _)| line + ix)
+}
+
+/// Characters between the start of `i`'s line and `i`
+pub fn column(text: &str, i: usize) -> usize {
+    text[line_start(text, i)..i].chars().count()
+}
+
+fn line_down(text: &str, i: usize) -> usize {
+    let end = line_end(text, i);
+    if end == text.len() {
+        return i;
+    }
+    at_column(text, end + 1, column(text, i))
+}
+
+fn line_up(text: &str, i: usize) -> usize {
+    let start = line_start(text, i);
+    if start == 0 {
+        return i;
+    }
+    at_column(text, line_start(text, start - 1), column(text, i))
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Start of the next word, like vim's `w`
+fn word_forward(text: &str, i: usize) -> usize {
+    let mut chars = text[i..].char_indices();
+    let Some((_, first)) = chars.next() else {
+        return i;
+    };
+    let start_class = class(first);
+    let mut in_start_word = start_class != CharClass::Space;
+    for (ix, c) in chars {
+        let c_class = class(c);
+        if in_start_word && c_class == start_class {
+            continue;
+        }
+        in_start_word = false;
+        if c_class != CharClass::Space {
+            return i + ix;
+        }
+    }
+    text.len()
+}
+
+/// Start of this or the previous word, like vim's `b`
+fn word_back(text: &str, i: usize) -> usize {
+    let before: Vec<(usize, char)> = text[..i].char_indices().collect();
+    let mut ix = before.len();
+    while ix > 0 && class(before[ix - 1].1) == CharClass::Space {
+        ix -= 1;
+    }
+    if ix == 0 {
+        return 0;
+    }
+    let word_class = class(before[ix - 1].1);
+    while ix > 0 && class(before[ix - 1].1) == word_class {
+        ix -= 1;
+    }
+    before.get(ix).map_or(i, |(offset, _)| *offset)
+}
+
+/// Last character of this or the next word, like vim's `e`
+fn word_end(text: &str, i: usize) -> usize {
+    let end = word_end_exclusive(text, next(text, i));
+    if end > i { prev(text, end) } else { i }
+}
+
+/// Just past the end of this or the next word, like Emacs' `M-f`
+fn word_end_exclusive(text: &str, i: usize) -> usize {
+    let rest: Vec<(usize, char)> = text[i..].char_indices().collect();
+    let mut ix = 0;
+    while ix < rest.len() && class(rest[ix].1) == CharClass::Space {
+        ix += 1;
+    }
+    let Some(&(_, first)) = rest.get(ix) else {
+        return text.len();
+    };
+    let word_class = class(first);
+    while ix < rest.len() && class(rest[ix].1) == word_class {
+        ix += 1;
+    }
+    rest.get(ix).map_or(text.len(), |(offset, _)| i + offset)
+}
@@ -29,6 +29,61 @@ pub mod reflection;
 pub mod query_editor;
 pub mod table_view;
 pub mod cell_editors;
+pub mod activity;
+pub mod archive;
+pub mod audio;
+pub mod branding;
+pub mod change_capture;
+pub mod codegen;
+pub mod completion;
+pub mod compression;
+pub mod create_table_wizard;
+pub mod data_diff;
+pub mod dependencies;
+pub mod export;
+pub mod filter_presets;
+pub mod geo;
+pub mod grouped_results;
+pub mod import;
+pub mod import_wizard;
+pub mod index_hints;
+pub mod interop;
+pub mod json_import;
+pub mod key_bindings;
+pub mod macros;
+pub mod markdown;
+pub mod metadata_bundle;
+pub mod migration;
+pub mod multi_caret;
+pub mod parquet_export;
+pub mod partition;
+pub mod paste;
+pub mod presence;
+pub mod profiling;
+pub mod project_query;
+pub mod provenance;
+pub mod provider;
+pub mod quarantine;
+pub mod refactor;
+pub mod report;
+pub mod retention;
+pub mod row_actions;
+pub mod row_filter;
+pub mod row_hash;
+pub mod save_hooks;
+pub mod schema_diff;
+pub mod session_script;
+pub mod settings;
+pub mod sidecar;
+pub mod sql_dump;
+pub mod sql_text;
+pub mod tags;
+pub mod template;
+pub mod text_encoding;
+pub mod trigger_editor;
+pub mod usage;
+pub mod uuids;
+pub mod view_editor;
 mod workspace_panels;
 
 // Re-export main types
@@ -135,8 +190,10 @@ impl EditorPlugin for TableEditorPlugin {
             });
 
             let panel_arc: Arc<dyn ui::dock::PanelView> = Arc::new(panel.clone());
+            let dirty = panel.read(cx).dirty_flag();
             let wrapper = Box::new(TableEditorWrapper {
                 panel: panel.into(),
+                dirty,
                 file_path: file_path.clone(),
             });
 
@@ -175,6 +232,8 @@ impl EditorPlugin for TableEditorPlugin {
 pub struct TableEditorWrapper {
     panel: Entity<DataTableEditor>,
     file_path: std::path::PathBuf,
+    /// Kept current by the editor, since `is_dirty` gets no context to read it with
+    dirty: Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl plugin_editor_api::EditorInstance for TableEditorWrapper {
@@ -195,7 +254,7 @@ impl plugin_editor_api::EditorInstance for TableEditorWrapper {
     }
 
     fn is_dirty(&self) -> bool {
-        false
+        self.dirty.load(std::sync::atomic::Ordering::Relaxed)
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -0,0 +1,190 @@
+//! Text macros for the query editor, defined by the project and expanded before a
+//! query runs. `#items_in_season(3)` expands the `items_in_season` body with `$n`
+//! replaced by `3`; macros may use other macros.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+/// Macro file looked up in the database's directory and its ancestors
+pub const MACROS_FILE_NAME: &str = "sql_macros.json";
+
+/// Nested expansions allowed before a macro is assumed to be recursive
+const MAX_EXPANSION_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlMacro {
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MacroSet {
+    pub macros: Vec<SqlMacro>,
+}
+
+/// A macro invocation found in query text
+#[derive(Debug, Clone)]
+pub struct MacroCall {
+    pub name: String,
+    pub args: Vec<String>,
+    /// Byte range of the whole invocation, `#` through the closing parenthesis
+    pub range: Range<usize>,
+}
+
+impl MacroSet {
+    /// Load the nearest macro file above `db_path`, or an empty set if the project has none
+    pub fn discover(db_path: &Path) -> Result<Self> {
+        for dir in db_path.ancestors().skip(1) {
+            let candidate = dir.join(MACROS_FILE_NAME);
+            if candidate.is_file() {
+                let text = std::fs::read_to_string(&candidate)?;
+                let macros = serde_json::from_str(&text)?;
+                return Ok(Self { macros });
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SqlMacro> {
+        self.macros.iter().find(|m| m.name == name)
+    }
+
+    /// Invocations of known macros, outside string literals and comments
+    pub fn calls(&self, sql: &str) -> Vec<MacroCall> {
+        find_calls(sql)
+            .into_iter()
+            .filter(|call| self.get(&call.name).is_some())
+            .collect()
+    }
+
+    pub fn expand(&self, sql: &str) -> Result<String> {
+        self.expand_depth(sql, 0)
+    }
+
+    fn expand_depth(&self, sql: &str, depth: usize) -> Result<String> {
+        let calls = self.calls(sql);
+        if calls.is_empty() {
+            return Ok(sql.to_string());
+        }
+        if depth >= MAX_EXPANSION_DEPTH {
+            return Err(anyhow!("Macro #{} expands too deeply; is it recursive?", calls[0].name));
+        }
+
+        let mut expanded = String::with_capacity(sql.len());
+        let mut last = 0;
+        for call in calls {
+            let def = self.get(&call.name).expect("calls() only returns known macros");
+            if call.args.len() != def.params.len() {
+                return Err(anyhow!(
+                    "Macro #{} takes {} arguments, got {}",
+                    def.name,
+                    def.params.len(),
+                    call.args.len()
+                ));
+            }
+
+            // Longest names first so `$item` doesn't clobber `$item_id`
+            let mut params: Vec<(&String, &String)> = def.params.iter().zip(&call.args).collect();
+            params.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+            let mut body = def.body.clone();
+            for (name, value) in params {
+                body = body.replace(&format!("${}", name), value.trim());
+            }
+
+            expanded.push_str(&sql[last..call.range.start]);
+            expanded.push_str(&self.expand_depth(&body, depth + 1)?);
+            last = call.range.end;
+        }
+        expanded.push_str(&sql[last..]);
+
+        Ok(expanded)
+    }
+}
+
+fn find_calls(sql: &str) -> Vec<MacroCall> {
+    let bytes = sql.as_bytes();
+    let mut calls = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' => {
+                let quote = bytes[i];
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'#' => {
+                let start = i;
+                i += 1;
+                let name_start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if i == name_start {
+                    continue;
+                }
+                let name = sql[name_start..i].to_string();
+
+                let mut args = Vec::new();
+                if bytes.get(i) == Some(&b'(') {
+                    if let Some((parsed, end)) = parse_args(sql, i) {
+                        args = parsed;
+                        i = end;
+                    }
+                }
+                calls.push(MacroCall {
+                    name,
+                    args,
+                    range: start..i,
+                });
+            }
+            _ => i += 1,
+        }
+    }
+
+    calls
+}
+
+/// Split `(a, f(b, c))` at top-level commas. `open` is the index of the opening
+/// parenthesis; returns the arguments and the index just past the closing one.
+fn parse_args(sql: &str, open: usize) -> Option<(Vec<String>, usize)> {
+    let mut depth = 0;
+    let mut args = Vec::new();
+    let mut arg_start = open + 1;
+
+    for (offset, c) in sql[open..].char_indices() {
+        let i = open + offset;
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    let last = &sql[arg_start..i];
+                    if !last.trim().is_empty() || !args.is_empty() {
+                        args.push(last.to_string());
+                    }
+                    return Some((args, i + 1));
+                }
+            }
+            ',' if depth == 1 => {
+                args.push(sql[arg_start..i].to_string());
+                arg_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
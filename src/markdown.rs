@@ -0,0 +1,198 @@
+//! The Markdown preview for text columns the profile marks as Markdown: headings,
+//! paragraphs, lists, quotes, code blocks and rules, with bold, italic and code spans.
+//! Dialogue and item descriptions seldom need more, so links and tables show as text.
+
+use gpui::{prelude::*, *};
+use ui::{v_flex, ActiveTheme};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading { level: usize, text: String },
+    Paragraph(String),
+    /// A list item, with its number for ordered lists
+    Item { number: Option<String>, text: String },
+    Quote(String),
+    Code(String),
+    Rule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emphasis {
+    Bold,
+    Italic,
+    Code,
+}
+
+/// The blocks of `text`. Consecutive plain lines join into one paragraph, as Markdown
+/// renderers do; a blank line starts the next.
+pub fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            match code.take() {
+                Some(lines) => blocks.push(Block::Code(lines.join("\n"))),
+                None => {
+                    flush(&mut paragraph, &mut blocks);
+                    code = Some(Vec::new());
+                }
+            }
+            continue;
+        }
+        if let Some(lines) = code.as_mut() {
+            lines.push(line);
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            continue;
+        }
+        let block = if let Some(heading) = heading(trimmed) {
+            heading
+        } else if trimmed.len() >= 3 && ['-', '*', '_'].iter().any(|&c| trimmed.chars().all(|t| t == c)) {
+            Block::Rule
+        } else if let Some(text) = ["- ", "* ", "+ "].iter().find_map(|m| trimmed.strip_prefix(m)) {
+            Block::Item { number: None, text: text.trim().to_string() }
+        } else if let Some((number, text)) = numbered(trimmed) {
+            Block::Item { number: Some(number.to_string()), text: text.to_string() }
+        } else if let Some(text) = trimmed.strip_prefix('>') {
+            Block::Quote(text.trim().to_string())
+        } else {
+            paragraph.push(trimmed);
+            continue;
+        };
+        flush(&mut paragraph, &mut blocks);
+        blocks.push(block);
+    }
+    flush(&mut paragraph, &mut blocks);
+    // An unclosed fence runs to the end of the text
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    blocks
+}
+
+fn heading(line: &str) -> Option<Block> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| Block::Heading { level, text: text.trim().to_string() })
+}
+
+fn numbered(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let text = line[digits..].strip_prefix(". ")?;
+    (digits > 0).then(|| (&line[..digits], text.trim()))
+}
+
+/// `text` with its emphasis markers removed, and the byte ranges of the result each
+/// marker pair covered. Markers don't nest, and one without a partner stays as typed.
+pub fn parse_inline(text: &str) -> (String, Vec<(Range<usize>, Emphasis)>) {
+    const MARKERS: [(&str, Emphasis); 4] = [
+        ("**", Emphasis::Bold),
+        ("__", Emphasis::Bold),
+        ("`", Emphasis::Code),
+        ("*", Emphasis::Italic),
+    ];
+    let mut out = String::with_capacity(text.len());
+    let mut spans = Vec::new();
+    let mut rest = text;
+
+    while let Some(ch) = rest.chars().next() {
+        let closed = MARKERS.iter().find_map(|&(marker, emphasis)| {
+            let after = rest.strip_prefix(marker)?;
+            let end = after.find(marker).filter(|&end| end > 0)?;
+            Some((marker, emphasis, &after[..end]))
+        });
+        match closed {
+            Some((marker, emphasis, inner)) => {
+                let start = out.len();
+                out.push_str(inner);
+                spans.push((start..out.len(), emphasis));
+                rest = &rest[marker.len() * 2 + inner.len()..];
+            }
+            None => {
+                out.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    (out, spans)
+}
+
+fn styled(text: &str, cx: &App) -> StyledText {
+    let (plain, spans) = parse_inline(text);
+    let code_background = cx.theme().muted;
+    let highlights = spans.into_iter().map(move |(range, emphasis)| {
+        let style = match emphasis {
+            Emphasis::Bold => HighlightStyle {
+                font_weight: Some(FontWeight::BOLD),
+                ..Default::default()
+            },
+            Emphasis::Italic => HighlightStyle {
+                font_style: Some(FontStyle::Italic),
+                ..Default::default()
+            },
+            Emphasis::Code => HighlightStyle {
+                background_color: Some(code_background),
+                ..Default::default()
+            },
+        };
+        (range, style)
+    });
+    StyledText::new(plain).with_highlights(highlights)
+}
+
+/// `text` rendered for reading
+pub fn render_preview(text: &str, cx: &App) -> Div {
+    v_flex()
+        .w_full()
+        .gap_2()
+        .text_sm()
+        .text_color(cx.theme().foreground)
+        .children(parse_blocks(text).into_iter().map(|block| match block {
+            Block::Heading { level, text } => div()
+                .font_weight(FontWeight::BOLD)
+                .map(|this| match level {
+                    1 => this.text_xl(),
+                    2 => this.text_lg(),
+                    _ => this.text_base(),
+                })
+                .child(styled(&text, cx)),
+            Block::Paragraph(text) => div().child(styled(&text, cx)),
+            Block::Item { number, text } => div()
+                .flex()
+                .gap_2()
+                .pl_2()
+                .child(div().flex_none().text_color(cx.theme().muted_foreground).child(
+                    number.map_or("•".to_string(), |n| format!("{}.", n)),
+                ))
+                .child(div().flex_1().child(styled(&text, cx))),
+            Block::Quote(text) => div()
+                .pl_3()
+                .border_l_2()
+                .border_color(cx.theme().border)
+                .italic()
+                .text_color(cx.theme().muted_foreground)
+                .child(styled(&text, cx)),
+            Block::Code(code) => div()
+                .p_2()
+                .rounded_sm()
+                .bg(cx.theme().muted)
+                .font_family("monospace")
+                .text_xs()
+                .children(code.lines().map(|line| div().child(line.to_string())).collect::<Vec<_>>()),
+            Block::Rule => div().w_full().h_px().bg(cx.theme().border),
+        }))
+}
@@ -0,0 +1,239 @@
+//! One portable file holding every sidecar of a database (saved queries, import
+//! presets, reflection profile and any other `<db file>.<kind>.json`), for sharing
+//! editor setup with teammates. Importing merges the bundle into the local sidecars:
+//! new entries are added, and entries both sides changed become conflicts the user
+//! resolves one by one before anything is written.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::presence::PRESENCE_SIDECAR;
+use crate::sidecar;
+
+pub const BUNDLE_KIND: &str = "pulsar.table-editor.metadata";
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Fields that identify an object in a list, so two versions of the same saved
+/// query or preset are recognized as one entry
+const IDENTITY_FIELDS: [&str; 4] = ["id", "name", "table", "file_pattern"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataBundle {
+    pub kind: String,
+    pub version: u32,
+    /// File name of the database the bundle was taken from
+    pub database: String,
+    /// Sidecar contents by kind, e.g. `queries`
+    pub sidecars: BTreeMap<String, Value>,
+}
+
+impl MetadataBundle {
+    /// Collect every sidecar next to `db_path`, except the live editor presence
+    pub fn collect(db_path: &Path) -> Result<Self> {
+        let mut sidecars = BTreeMap::new();
+        for kind in sidecar_kinds(db_path)?.into_iter().filter(|kind| kind != PRESENCE_SIDECAR) {
+            if let Some(value) = sidecar::load::<Value>(db_path, &kind)? {
+                sidecars.insert(kind, value);
+            }
+        }
+        Ok(Self {
+            kind: BUNDLE_KIND.to_string(),
+            version: BUNDLE_VERSION,
+            database: db_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            sidecars,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if bundle.kind != BUNDLE_KIND || bundle.version > BUNDLE_VERSION {
+            return Err(anyhow!(
+                "{} is a {} v{} file, expected {} v{}",
+                path.display(),
+                bundle.kind,
+                bundle.version,
+                BUNDLE_KIND,
+                BUNDLE_VERSION
+            ));
+        }
+        Ok(bundle)
+    }
+}
+
+/// Kinds of the `<db file>.<kind>.json` files beside a database
+fn sidecar_kinds(db_path: &Path) -> Result<Vec<String>> {
+    let Some(file_name) = db_path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Ok(Vec::new());
+    };
+    let dir = match db_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", file_name);
+
+    let mut kinds = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(kind) = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(".json")) {
+            if !kind.is_empty() && !kind.contains('.') {
+                kinds.push(kind.to_string());
+            }
+        }
+    }
+    kinds.sort();
+    Ok(kinds)
+}
+
+/// An entry both the local sidecar and the bundle have, with different contents
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    pub kind: String,
+    /// JSON pointer to the entry within the sidecar
+    pub pointer: String,
+    pub ours: Value,
+    pub theirs: Value,
+    pub take_theirs: bool,
+}
+
+impl MergeConflict {
+    pub fn describe(&self) -> String {
+        let location = if self.pointer.is_empty() { "(whole file)" } else { self.pointer.as_str() };
+        format!("{} {}: {} ↔ {}", self.kind, location, preview(&self.ours), preview(&self.theirs))
+    }
+}
+
+/// A bundle merged into the local sidecars, waiting for conflicts to be resolved
+#[derive(Debug, Clone, Default)]
+pub struct BundleMerge {
+    /// Merged sidecars, holding our side of every conflict until applied
+    pub merged: BTreeMap<String, Value>,
+    pub conflicts: Vec<MergeConflict>,
+    /// Entries the bundle adds without conflict
+    pub added: usize,
+}
+
+impl BundleMerge {
+    pub fn plan(db_path: &Path, bundle: &MetadataBundle) -> Result<Self> {
+        let mut merge = BundleMerge::default();
+        for (kind, theirs) in &bundle.sidecars {
+            let merged = match sidecar::load::<Value>(db_path, kind)? {
+                Some(mut ours) => {
+                    merge_value(kind, "", &mut ours, theirs, &mut merge);
+                    ours
+                }
+                None => {
+                    merge.added += 1;
+                    theirs.clone()
+                }
+            };
+            merge.merged.insert(kind.clone(), merged);
+        }
+        Ok(merge)
+    }
+
+    /// Write the merged sidecars, with each conflict resolved as chosen. Returns the
+    /// number of sidecars written.
+    pub fn apply(&self, db_path: &Path) -> Result<usize> {
+        let mut merged = self.merged.clone();
+        for conflict in self.conflicts.iter().filter(|c| c.take_theirs) {
+            let Some(sidecar) = merged.get_mut(&conflict.kind) else {
+                continue;
+            };
+            match sidecar.pointer_mut(&conflict.pointer) {
+                Some(slot) => *slot = conflict.theirs.clone(),
+                None => return Err(anyhow!("Conflict at {} no longer applies", conflict.pointer)),
+            }
+        }
+        for (kind, value) in &merged {
+            sidecar::save(db_path, kind, value)?;
+        }
+        Ok(merged.len())
+    }
+}
+
+/// Merge `theirs` into `ours`: objects key by key, lists by entry identity with
+/// their new entries appended, and anything else that differs becomes a conflict
+fn merge_value(kind: &str, pointer: &str, ours: &mut Value, theirs: &Value, merge: &mut BundleMerge) {
+    if *ours == *theirs {
+        return;
+    }
+    match (ours, theirs) {
+        (Value::Object(ours), Value::Object(theirs)) => {
+            for (key, value) in theirs {
+                let child = format!("{}/{}", pointer, escape_pointer(key));
+                match ours.get_mut(key) {
+                    Some(existing) => merge_value(kind, &child, existing, value, merge),
+                    None => {
+                        ours.insert(key.clone(), value.clone());
+                        merge.added += 1;
+                    }
+                }
+            }
+        }
+        (Value::Array(ours), Value::Array(theirs)) => {
+            for value in theirs {
+                let key = identity(value);
+                match ours.iter().position(|existing| key.is_some() && identity(existing) == key) {
+                    Some(ix) if ours[ix] != *value => merge.conflicts.push(MergeConflict {
+                        kind: kind.to_string(),
+                        pointer: format!("{}/{}", pointer, ix),
+                        ours: ours[ix].clone(),
+                        theirs: value.clone(),
+                        take_theirs: false,
+                    }),
+                    Some(_) => {}
+                    None if ours.contains(value) => {}
+                    None => {
+                        ours.push(value.clone());
+                        merge.added += 1;
+                    }
+                }
+            }
+        }
+        (ours, theirs) => merge.conflicts.push(MergeConflict {
+            kind: kind.to_string(),
+            pointer: pointer.to_string(),
+            ours: ours.clone(),
+            theirs: theirs.clone(),
+            take_theirs: false,
+        }),
+    }
+}
+
+/// What makes a list entry the same entry on both sides: its identifying fields for
+/// objects, the first element for pairs such as `(name, sql)`
+fn identity(value: &Value) -> Option<Value> {
+    match value {
+        Value::Object(object) => {
+            let fields: Map<String, Value> = IDENTITY_FIELDS
+                .iter()
+                .filter_map(|field| object.get(*field).map(|v| (field.to_string(), v.clone())))
+                .collect();
+            (!fields.is_empty()).then_some(Value::Object(fields))
+        }
+        Value::Array(items) if items.len() == 2 => items.first().cloned(),
+        _ => None,
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn preview(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(60) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
@@ -0,0 +1,663 @@
+//! Guided data migration from a database with the old schema into the open one with
+//! the new schema. Each target column is mapped to a SQL expression over a source
+//! table's columns, so renames, type changes and derived values are all transforms.
+//! The source is attached read-only and copied in rowid batches inside one
+//! transaction, then checked and summarized in a validation report.
+
+use anyhow::{Result, anyhow};
+use gpui::{prelude::*, *};
+use rusqlite::{Connection, params};
+use ui::{
+    h_flex, v_flex, button::{Button, ButtonVariants},
+    input::{TextInput, InputState},
+    ActiveTheme, Sizable, Disableable,
+};
+use std::path::{Path, PathBuf};
+use crate::database::DatabaseManager;
+use crate::sql_text::quote_identifier;
+
+/// Schema name the source database is attached under while migrating
+pub const SOURCE_SCHEMA: &str = "migration_source";
+
+/// Source rows copied per `INSERT ... SELECT`
+const MIGRATION_BATCH_ROWS: i64 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    pub target_column: String,
+    /// SQL over the source table's columns, e.g. `"price" * 100`; empty leaves the
+    /// column to its default
+    pub expression: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableMapping {
+    pub target_table: String,
+    /// `None` leaves the target table untouched
+    pub source_table: Option<String>,
+    pub columns: Vec<ColumnMapping>,
+}
+
+impl TableMapping {
+    /// Switch to another source table, re-matching columns by name (ignoring case)
+    pub fn set_source(&mut self, source: &DatabaseManager, source_table: Option<String>) -> Result<()> {
+        let source_columns = match &source_table {
+            Some(table) => source.column_names(table)?,
+            None => Vec::new(),
+        };
+        for column in &mut self.columns {
+            column.expression = source_columns
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(&column.target_column))
+                .map(|c| quote_identifier(c))
+                .unwrap_or_default();
+        }
+        self.source_table = source_table;
+        Ok(())
+    }
+
+    pub fn mapped(&self) -> impl Iterator<Item = &ColumnMapping> {
+        self.columns.iter().filter(|c| !c.expression.trim().is_empty())
+    }
+
+    /// Copies the source rows with rowids in `(?1, ?2]`
+    fn insert_sql(&self, source_table: &str) -> String {
+        let columns = self
+            .mapped()
+            .map(|c| quote_identifier(&c.target_column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let expressions = self
+            .mapped()
+            .map(|c| c.expression.trim().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "INSERT INTO main.{} ({}) SELECT {} FROM {}.{} WHERE rowid > ?1 AND rowid <= ?2 ORDER BY rowid",
+            quote_identifier(&self.target_table),
+            columns,
+            expressions,
+            SOURCE_SCHEMA,
+            quote_identifier(source_table)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationPlan {
+    pub tables: Vec<TableMapping>,
+    /// Empty each mapped target table before copying into it
+    pub clear_targets: bool,
+}
+
+impl MigrationPlan {
+    /// One mapping per target table, matched to the source table and columns of the
+    /// same name where there is one
+    pub fn suggest(source: &DatabaseManager, target: &DatabaseManager) -> Result<Self> {
+        let source_tables = source.list_tables()?;
+        let mut tables = Vec::new();
+        for target_table in target.list_tables()? {
+            let mut mapping = TableMapping {
+                columns: target
+                    .column_names(&target_table)?
+                    .into_iter()
+                    .map(|target_column| ColumnMapping {
+                        target_column,
+                        expression: String::new(),
+                    })
+                    .collect(),
+                target_table,
+                source_table: None,
+            };
+            let same_name = source_tables
+                .iter()
+                .find(|t| t.eq_ignore_ascii_case(&mapping.target_table))
+                .cloned();
+            if same_name.is_some() {
+                mapping.set_source(source, same_name)?;
+            }
+            tables.push(mapping);
+        }
+        Ok(Self {
+            tables,
+            clear_targets: false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableProgress {
+    pub target_table: String,
+    pub copied: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableValidation {
+    pub target_table: String,
+    pub source_table: String,
+    pub source_rows: usize,
+    pub copied: usize,
+    /// Rows in the target afterwards, including any it already had
+    pub target_rows: usize,
+    /// Mapped columns whose transform gave NULL, with how many rows
+    pub null_columns: Vec<(String, usize)>,
+}
+
+impl TableValidation {
+    pub fn is_complete(&self) -> bool {
+        self.copied == self.source_rows
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub source: PathBuf,
+    pub tables: Vec<TableValidation>,
+}
+
+impl MigrationReport {
+    pub fn copied(&self) -> usize {
+        self.tables.iter().map(|t| t.copied).sum()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Migration from {}\n\n", self.source.display());
+        md.push_str("| Target | Source | Source rows | Copied | Target rows | Status |\n");
+        md.push_str("|---|---|---|---|---|---|\n");
+        for table in &self.tables {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                table.target_table,
+                table.source_table,
+                table.source_rows,
+                table.copied,
+                table.target_rows,
+                if table.is_complete() { "ok" } else { "incomplete" }
+            ));
+        }
+
+        let nulls: Vec<_> = self.tables.iter().filter(|t| !t.null_columns.is_empty()).collect();
+        if !nulls.is_empty() {
+            md.push_str("\n## NULL values produced\n\n");
+            for table in nulls {
+                for (column, count) in &table.null_columns {
+                    md.push_str(&format!("- `{}.{}`: {} rows\n", table.target_table, column, count));
+                }
+            }
+        }
+        md
+    }
+}
+
+/// Copy every mapped table from `source_path` into `target` in one transaction.
+/// Foreign keys are checked once at the end, so tables may be copied in any order;
+/// if any reference is broken nothing is written. Source tables need rowids.
+pub fn run_migration(
+    target: &DatabaseManager,
+    source_path: &Path,
+    plan: &MigrationPlan,
+    progress: &mut dyn FnMut(&TableProgress),
+) -> Result<MigrationReport> {
+    target.with_attached(source_path, SOURCE_SCHEMA, |conn| {
+        let tx = conn.unchecked_transaction()?;
+        let mut report = MigrationReport {
+            source: source_path.to_path_buf(),
+            tables: Vec::new(),
+        };
+
+        for mapping in &plan.tables {
+            let Some(source_table) = mapping.source_table.as_deref() else {
+                continue;
+            };
+            if mapping.mapped().next().is_none() {
+                continue;
+            }
+            let validation = migrate_table(&tx, mapping, source_table, plan.clear_targets, progress)
+                .map_err(|e| anyhow!("Migrating {} into {} failed: {}", source_table, mapping.target_table, e))?;
+            report.tables.push(validation);
+        }
+
+        let violations = tx.prepare("PRAGMA main.foreign_key_check")?.query([])?.mapped(|_| Ok(())).count();
+        if violations > 0 {
+            return Err(anyhow!("The migrated rows would break {} foreign key references; nothing was written", violations));
+        }
+        tx.commit()?;
+        Ok(report)
+    })
+}
+
+fn migrate_table(
+    conn: &Connection,
+    mapping: &TableMapping,
+    source_table: &str,
+    clear: bool,
+    progress: &mut dyn FnMut(&TableProgress),
+) -> Result<TableValidation> {
+    let source = format!("{}.{}", SOURCE_SCHEMA, quote_identifier(source_table));
+    let target = format!("main.{}", quote_identifier(&mapping.target_table));
+    if clear {
+        conn.execute(&format!("DELETE FROM {}", target), [])?;
+    }
+
+    let total: usize = conn.query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| row.get(0))?;
+    let mut state = TableProgress {
+        target_table: mapping.target_table.clone(),
+        copied: 0,
+        total,
+    };
+    progress(&state);
+
+    let mut insert = conn.prepare(&mapping.insert_sql(source_table))?;
+    let mut batch_end = conn.prepare(&format!(
+        "SELECT MAX(rowid) FROM (SELECT rowid FROM {} WHERE rowid > ?1 ORDER BY rowid LIMIT ?2)",
+        source
+    ))?;
+    let mut last = i64::MIN;
+    while let Some(end) = batch_end.query_row(params![last, MIGRATION_BATCH_ROWS], |row| row.get::<_, Option<i64>>(0))? {
+        state.copied += insert.execute(params![last, end])?;
+        last = end;
+        progress(&state);
+        tracing::debug!("Migrated {}/{} rows into {}", state.copied, total, mapping.target_table);
+    }
+
+    let mut null_columns = Vec::new();
+    for column in mapping.mapped() {
+        let nulls: usize = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE ({}) IS NULL", source, column.expression.trim()),
+            [],
+            |row| row.get(0),
+        )?;
+        if nulls > 0 {
+            null_columns.push((column.target_column.clone(), nulls));
+        }
+    }
+
+    Ok(TableValidation {
+        target_table: mapping.target_table.clone(),
+        source_table: source_table.to_string(),
+        source_rows: total,
+        copied: state.copied,
+        target_rows: conn.query_row(&format!("SELECT COUNT(*) FROM {}", target), [], |row| row.get(0))?,
+        null_columns,
+    })
+}
+
+/// Source tables on the left for reference; target tables on the right, each mapped
+/// to a source table with one transform per column
+pub struct MigrationAssistant {
+    target: DatabaseManager,
+    source_path_input: Entity<InputState>,
+    source: Option<(PathBuf, DatabaseManager)>,
+    /// Source tables with their columns, for reference while writing transforms
+    source_tables: Vec<(String, Vec<String>)>,
+    plan: Option<MigrationPlan>,
+    /// Target table whose columns are being mapped
+    selected: Option<usize>,
+    /// Transform inputs for the selected table's columns, in column order
+    expression_inputs: Vec<Entity<InputState>>,
+    progress: Vec<TableProgress>,
+    report: Option<(MigrationReport, PathBuf)>,
+    message: Option<String>,
+}
+
+impl MigrationAssistant {
+    pub fn new(target: DatabaseManager, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        Self {
+            target,
+            source_path_input: cx.new(|cx| InputState::new(window, cx)),
+            source: None,
+            source_tables: Vec::new(),
+            plan: None,
+            selected: None,
+            expression_inputs: Vec::new(),
+            progress: Vec::new(),
+            report: None,
+            message: None,
+        }
+    }
+
+    /// Open the old-schema database and suggest a mapping by name
+    pub fn open_source(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Result<()> {
+        let path = PathBuf::from(self.source_path_input.read(cx).value().trim());
+        let source = DatabaseManager::open_read_only(&path)?;
+        let plan = MigrationPlan::suggest(&source, &self.target)?;
+
+        self.source_tables = source
+            .list_tables()?
+            .into_iter()
+            .map(|table| Ok((table.clone(), source.column_names(&table)?)))
+            .collect::<Result<_>>()?;
+        self.selected = plan.tables.iter().position(|t| t.source_table.is_some()).or(Some(0));
+        self.message = Some(format!(
+            "{} of {} target tables matched by name",
+            plan.tables.iter().filter(|t| t.source_table.is_some()).count(),
+            plan.tables.len()
+        ));
+        self.plan = Some(plan);
+        self.source = Some((path, source));
+        self.progress.clear();
+        self.report = None;
+        self.rebuild_inputs(window, cx);
+        Ok(())
+    }
+
+    pub fn select(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.store_expressions(cx);
+        self.selected = Some(ix);
+        self.rebuild_inputs(window, cx);
+    }
+
+    /// Map a target table to the next source table, or to none after the last
+    pub fn cycle_source(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) -> Result<()> {
+        self.store_expressions(cx);
+        let (Some(plan), Some((_, source))) = (self.plan.as_mut(), self.source.as_ref()) else {
+            return Ok(());
+        };
+        let Some(mapping) = plan.tables.get_mut(ix) else {
+            return Ok(());
+        };
+
+        let position = mapping
+            .source_table
+            .as_ref()
+            .and_then(|current| self.source_tables.iter().position(|(t, _)| t == current));
+        let next = match position {
+            Some(p) => self.source_tables.get(p + 1),
+            None => self.source_tables.first(),
+        };
+        mapping.set_source(source, next.map(|(t, _)| t.clone()))?;
+
+        self.selected = Some(ix);
+        self.rebuild_inputs(window, cx);
+        Ok(())
+    }
+
+    pub fn run(&mut self, cx: &mut Context<Self>) -> Result<()> {
+        self.store_expressions(cx);
+        let (Some(plan), Some((path, _))) = (self.plan.as_ref(), self.source.as_ref()) else {
+            return Ok(());
+        };
+
+        let mut progress: Vec<TableProgress> = Vec::new();
+        let result = run_migration(&self.target, path, plan, &mut |state| {
+            match progress.last_mut() {
+                Some(last) if last.target_table == state.target_table => *last = state.clone(),
+                _ => progress.push(state.clone()),
+            }
+        });
+        self.progress = progress;
+        let report = result?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let report_path = self.target.output_path(&format!("migration_{}.md", timestamp));
+        std::fs::write(&report_path, report.to_markdown())?;
+
+        tracing::info!("✓ Migrated {} rows from {:?}, report at {:?}", report.copied(), path, report_path);
+        self.message = None;
+        self.report = Some((report, report_path));
+        Ok(())
+    }
+
+    fn store_expressions(&mut self, cx: &App) {
+        let (Some(plan), Some(ix)) = (self.plan.as_mut(), self.selected) else {
+            return;
+        };
+        let Some(mapping) = plan.tables.get_mut(ix) else {
+            return;
+        };
+        for (column, input) in mapping.columns.iter_mut().zip(&self.expression_inputs) {
+            column.expression = input.read(cx).value().trim().to_string();
+        }
+    }
+
+    fn rebuild_inputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let mapping = self
+            .plan
+            .as_ref()
+            .zip(self.selected)
+            .and_then(|(plan, ix)| plan.tables.get(ix));
+        self.expression_inputs = mapping
+            .map(|mapping| {
+                mapping
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        cx.new(|cx| {
+                            let mut state = InputState::new(window, cx);
+                            state.set_value(&column.expression, window, cx);
+                            state
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    fn render_source_pane(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("migration-source")
+            .w_1_3()
+            .h_full()
+            .gap_2()
+            .p_2()
+            .border_r_1()
+            .border_color(cx.theme().border)
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("source (old schema)")
+            )
+            .children(self.source_tables.iter().map(|(table, columns)| {
+                v_flex()
+                    .child(div().text_sm().text_color(cx.theme().foreground).child(table.clone()))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(columns.join(", "))
+                    )
+            }))
+    }
+
+    fn render_target_pane(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let tables = self.plan.as_ref().map(|p| p.tables.clone()).unwrap_or_default();
+        let selected = self.selected.and_then(|ix| tables.get(ix).map(|t| (ix, t.clone())));
+
+        v_flex()
+            .id("migration-target")
+            .flex_1()
+            .h_full()
+            .gap_2()
+            .p_2()
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("target (this database)")
+            )
+            .children(tables.iter().enumerate().map(|(ix, mapping)| {
+                let active = self.selected == Some(ix);
+                let mapped = mapping.mapped().count();
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new(("migration-target", ix))
+                            .label(mapping.target_table.clone())
+                            .xsmall()
+                            .when(active, |b| b.primary())
+                            .when(!active, |b| b.ghost())
+                            .on_click(cx.listener(move |assistant, _, window, cx| {
+                                assistant.select(ix, window, cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(div().text_xs().text_color(cx.theme().muted_foreground).child("←"))
+                    .child(
+                        Button::new(("migration-source", ix))
+                            .label(mapping.source_table.clone().unwrap_or_else(|| "skip".to_string()))
+                            .tooltip("Cycle through the source tables")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(move |assistant, _, window, cx| {
+                                if let Err(e) = assistant.cycle_source(ix, window, cx) {
+                                    tracing::error!("Failed to map source table: {}", e);
+                                    assistant.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{} of {} columns mapped", mapped, mapping.columns.len()))
+                    )
+            }))
+            .when_some(selected, |pane, (_, mapping)| {
+                pane.child(
+                    v_flex()
+                        .gap_1()
+                        .pt_2()
+                        .border_t_1()
+                        .border_color(cx.theme().border)
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!(
+                                    "{} columns: SQL over {} columns, empty for the default",
+                                    mapping.target_table,
+                                    mapping.source_table.as_deref().unwrap_or("source")
+                                ))
+                        )
+                        .children(mapping.columns.iter().zip(&self.expression_inputs).map(|(column, input)| {
+                            h_flex()
+                                .gap_2()
+                                .child(div().w_32().text_sm().child(column.target_column.clone()))
+                                .child(TextInput::new(input).flex_1().text_xs())
+                        }))
+                )
+            })
+    }
+}
+
+impl Render for MigrationAssistant {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_run = self.plan.is_some() && !self.target.is_read_only();
+        let clear_targets = self.plan.as_ref().is_some_and(|p| p.clear_targets);
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(div().text_sm().child("Migrate data from"))
+                    .child(TextInput::new(&self.source_path_input).w_64().text_xs())
+                    .child(
+                        Button::new("open-migration-source")
+                            .label("Open")
+                            .tooltip("Open the old-schema database read-only and map tables by name")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(|assistant, _, window, cx| {
+                                if let Err(e) = assistant.open_source(window, cx) {
+                                    tracing::error!("Failed to open migration source: {}", e);
+                                    assistant.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .w_full()
+                    .child(self.render_source_pane(cx))
+                    .child(self.render_target_pane(cx))
+            )
+            .child(
+                v_flex()
+                    .w_full()
+                    .gap_1()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                Button::new("migration-clear-targets")
+                                    .label("Empty targets first")
+                                    .xsmall()
+                                    .when(clear_targets, |b| b.primary())
+                                    .when(!clear_targets, |b| b.ghost())
+                                    .disabled(!can_run)
+                                    .on_click(cx.listener(|assistant, _, _, cx| {
+                                        if let Some(plan) = assistant.plan.as_mut() {
+                                            plan.clear_targets = !plan.clear_targets;
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                            .child(
+                                Button::new("run-migration")
+                                    .label("Run migration")
+                                    .tooltip("Copy every mapped table in one transaction and write a validation report")
+                                    .xsmall()
+                                    .primary()
+                                    .disabled(!can_run)
+                                    .on_click(cx.listener(|assistant, _, _, cx| {
+                                        if let Err(e) = assistant.run(cx) {
+                                            tracing::error!("Failed to migrate data: {}", e);
+                                            assistant.message = Some(e.to_string());
+                                        }
+                                        cx.notify();
+                                    }))
+                            )
+                    )
+                    .children(self.progress.iter().map(|state| {
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{}: {} of {} rows", state.target_table, state.copied, state.total))
+                    }))
+                    .when_some(self.report.as_ref(), |footer, (report, path)| {
+                        let incomplete = report.tables.iter().filter(|t| !t.is_complete()).count();
+                        let with_nulls = report.tables.iter().filter(|t| !t.null_columns.is_empty()).count();
+                        footer.child(
+                            div()
+                                .text_xs()
+                                .text_color(if incomplete > 0 { cx.theme().warning } else { cx.theme().success })
+                                .child(format!(
+                                    "Copied {} rows into {} tables ({} incomplete, {} with NULLs produced) — report at {}",
+                                    report.copied(),
+                                    report.tables.len(),
+                                    incomplete,
+                                    with_nulls,
+                                    path.display()
+                                ))
+                        )
+                    })
+                    .when_some(self.message.clone(), |footer, message| {
+                        footer.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            )
+    }
+}
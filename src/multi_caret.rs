@@ -0,0 +1,168 @@
+//! Several carets or selections in the query editor at once, for editing long IN-lists
+//! and aligned value tuples. The input itself only has one selection, so the query
+//! editor keeps these alongside it and applies each typed key to all of them.
+
+use std::ops::Range;
+use crate::key_bindings::{at_column, column, line_end, line_start, next, prev};
+
+/// Byte ranges into the query, sorted and not overlapping; an empty range is a caret.
+/// The cursor of each range is its end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiCaret {
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl MultiCaret {
+    fn new(mut ranges: Vec<Range<usize>>) -> Option<Self> {
+        ranges.sort_by_key(|r| (r.start, r.end));
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start < last.end || range == *last => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        (merged.len() > 1).then_some(Self { ranges: merged })
+    }
+
+    /// The rectangle between `from` and `to`: the same columns on every line between
+    /// them, as carets when both are in the same column
+    pub fn column_block(text: &str, from: usize, to: usize) -> Option<Self> {
+        let (from, to) = (from.min(to), from.max(to));
+        let (left, right) = {
+            let (a, b) = (column(text, from), column(text, to));
+            (a.min(b), a.max(b))
+        };
+        Self::new(
+            lines(text, from, to)
+                .map(|line| at_column(text, line, left)..at_column(text, line, right))
+                .collect(),
+        )
+    }
+
+    /// A caret at the end of every line from `from` to `to`
+    pub fn line_ends(text: &str, from: usize, to: usize) -> Option<Self> {
+        let (from, to) = (from.min(to), from.max(to));
+        Self::new(lines(text, from, to).map(|line| {
+            let end = line_end(text, line);
+            end..end
+        }).collect())
+    }
+
+    /// The single selection `range` plus a caret in the same column on the next or previous line
+    pub fn from_selection(text: &str, range: Range<usize>, down: bool) -> Option<Self> {
+        let mut carets = Self { ranges: vec![range] };
+        carets.add_line(text, down);
+        (carets.ranges.len() > 1).then_some(carets)
+    }
+
+    /// The caret the input's own cursor follows: the last one
+    pub fn primary(&self) -> usize {
+        self.ranges.last().map_or(0, |r| r.end)
+    }
+
+    /// Add a caret below the last caret, or above the first, in the same column
+    pub fn add_line(&mut self, text: &str, down: bool) {
+        let (Some(first), Some(last)) = (self.ranges.first(), self.ranges.last()) else {
+            return;
+        };
+        let at = if down {
+            let end = line_end(text, last.end);
+            if end == text.len() {
+                return;
+            }
+            at_column(text, end + 1, column(text, last.end))
+        } else {
+            let start = line_start(text, first.end);
+            if start == 0 {
+                return;
+            }
+            at_column(text, line_start(text, start - 1), column(text, first.end))
+        };
+        let mut ranges = self.ranges.clone();
+        ranges.push(at..at);
+        if let Some(carets) = Self::new(ranges) {
+            *self = carets;
+        }
+    }
+
+    /// Select the next occurrence of the last selection's text too, wrapping around
+    pub fn add_next_occurrence(text: &str, ranges: &[Range<usize>]) -> Option<Self> {
+        let last = ranges.last()?;
+        let needle = &text[last.clone()];
+        if needle.is_empty() {
+            return None;
+        }
+        let taken = |start: usize| ranges.iter().any(|r| r.start == start);
+        let found = text[last.end..]
+            .match_indices(needle)
+            .map(|(ix, _)| last.end + ix)
+            .chain(text[..last.start].match_indices(needle).map(|(ix, _)| ix))
+            .find(|&start| !taken(start))?;
+        let mut all = ranges.to_vec();
+        all.push(found..found + needle.len());
+        Self::new(all)
+    }
+
+    /// Type `typed` at every caret, replacing each selection
+    pub fn insert(&self, text: &str, typed: &str) -> (String, Option<Self>) {
+        self.edit(text, |_, range, _| (range, typed.to_string()))
+    }
+
+    /// Put one piece at each caret, e.g. the lines of a paste with one line per caret
+    pub fn insert_each(&self, text: &str, pieces: &[&str]) -> (String, Option<Self>) {
+        self.edit(text, |_, range, ix| (range, pieces.get(ix).copied().unwrap_or_default().to_string()))
+    }
+
+    /// Delete each selection, or the character before each caret
+    pub fn backspace(&self, text: &str) -> (String, Option<Self>) {
+        self.edit(text, |text, range, _| {
+            let range = if range.is_empty() { prev(text, range.start)..range.end } else { range };
+            (range, String::new())
+        })
+    }
+
+    /// Delete each selection, or the character after each caret
+    pub fn delete(&self, text: &str) -> (String, Option<Self>) {
+        self.edit(text, |text, range, _| {
+            let range = if range.is_empty() { range.start..next(text, range.end) } else { range };
+            (range, String::new())
+        })
+    }
+
+    /// Every caret moved by `motion`, collapsing selections
+    pub fn moved(&self, text: &str, motion: impl Fn(&str, usize) -> usize) -> Option<Self> {
+        Self::new(self.ranges.iter().map(|r| {
+            let at = motion(text, r.end);
+            at..at
+        }).collect())
+    }
+
+    /// Replace each range with what `f` returns for it (which may widen it backward or
+    /// forward), leaving a caret after each replacement. `None` once carets have merged
+    /// into one.
+    fn edit(&self, text: &str, f: impl Fn(&str, Range<usize>, usize) -> (Range<usize>, String)) -> (String, Option<Self>) {
+        let mut out = String::with_capacity(text.len());
+        let mut carets = Vec::new();
+        let mut copied = 0;
+        for (ix, range) in self.ranges.iter().enumerate() {
+            let (range, replacement) = f(text, range.clone(), ix);
+            // A widened range can't eat into the previous replacement
+            let start = range.start.max(copied);
+            out.push_str(&text[copied..start]);
+            out.push_str(&replacement);
+            carets.push(out.len()..out.len());
+            copied = range.end.max(start);
+        }
+        out.push_str(&text[copied..]);
+        (out, Self::new(carets))
+    }
+}
+
+/// Start offsets of the lines from the one holding `from` to the one holding `to`
+fn lines(text: &str, from: usize, to: usize) -> impl Iterator<Item = usize> + '_ {
+    let last = line_start(text, to);
+    std::iter::successors(Some(line_start(text, from)), move |&line| {
+        (line < last).then(|| line_end(text, line) + 1)
+    })
+}
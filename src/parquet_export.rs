@@ -0,0 +1,284 @@
+//! Parquet export for handing tables and query results to analytics tools. Built only
+//! with the `parquet` feature, which pulls in arrow and parquet; without it the export
+//! functions return an error saying so.
+//!
+//! Table columns take their Parquet type from their SQLite affinity. Query results have
+//! no declared types to go on, so each column is typed from the values in the first
+//! batch. Values that don't fit the column's type (SQLite doesn't enforce them) are
+//! converted where they can be and written as NULL otherwise.
+
+use anyhow::Result;
+use std::path::Path;
+use crate::database::{DatabaseManager, RowScope};
+
+/// Rows buffered per record batch; undeclared column types are inferred from the first
+pub const BATCH_ROWS: usize = 8192;
+
+#[cfg(feature = "parquet")]
+pub use enabled::*;
+
+#[cfg(feature = "parquet")]
+mod enabled {
+    use anyhow::{Result, anyhow};
+    use arrow_array::{
+        ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    };
+    use arrow_schema::{DataType, Field, Schema, SchemaRef};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Compression, ZstdLevel};
+    use parquet::file::properties::WriterProperties;
+    use serde_json::Value;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+    use crate::database::{CellValue, RowSink};
+    use crate::reflection::SqlType;
+    use super::BATCH_ROWS;
+
+    /// A column's type in the file
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ParquetKind {
+        Int64,
+        Float64,
+        Boolean,
+        Utf8,
+        Binary,
+    }
+
+    impl ParquetKind {
+        pub fn from_sql_type(sql_type: &SqlType) -> Self {
+            match sql_type {
+                SqlType::Integer | SqlType::ForeignKey { .. } => ParquetKind::Int64,
+                SqlType::Real => ParquetKind::Float64,
+                SqlType::Boolean => ParquetKind::Boolean,
+                SqlType::Text | SqlType::DateTime => ParquetKind::Utf8,
+                SqlType::Blob => ParquetKind::Binary,
+            }
+        }
+
+        /// The narrowest type holding every non-NULL value of column `col`; text wins
+        /// over numbers, and a column of only NULLs is text
+        fn infer(rows: &[Vec<CellValue>], col: usize) -> Self {
+            let mut kind = None;
+            for cell in rows.iter().filter_map(|r| r.get(col)) {
+                let this = match (&cell.value, &cell.blob) {
+                    (_, Some(_)) => ParquetKind::Binary,
+                    (Value::Null, _) => continue,
+                    (Value::Bool(_), _) => ParquetKind::Boolean,
+                    (Value::Number(n), _) if n.is_i64() => ParquetKind::Int64,
+                    (Value::Number(_), _) => ParquetKind::Float64,
+                    _ => ParquetKind::Utf8,
+                };
+                kind = Some(match (kind, this) {
+                    (None, this) => this,
+                    (Some(a), b) if a == b => a,
+                    (Some(ParquetKind::Int64), ParquetKind::Float64) | (Some(ParquetKind::Float64), ParquetKind::Int64) => {
+                        ParquetKind::Float64
+                    }
+                    _ => return ParquetKind::Utf8,
+                });
+            }
+            kind.unwrap_or(ParquetKind::Utf8)
+        }
+
+        fn data_type(&self) -> DataType {
+            match self {
+                ParquetKind::Int64 => DataType::Int64,
+                ParquetKind::Float64 => DataType::Float64,
+                ParquetKind::Boolean => DataType::Boolean,
+                ParquetKind::Utf8 => DataType::Utf8,
+                ParquetKind::Binary => DataType::Binary,
+            }
+        }
+
+        /// Column `col` of `rows` as an array of this type, counting values that had to
+        /// be dropped to NULL in `coerced`
+        fn build(&self, rows: &[Vec<CellValue>], col: usize, coerced: &mut usize) -> ArrayRef {
+            let mut dropped = 0;
+            let mut cells = rows.iter().map(|r| r.get(col).filter(|c| !c.value.is_null() || c.blob.is_some()));
+            let array: ArrayRef = match self {
+                ParquetKind::Int64 => Arc::new(Int64Array::from_iter(cells.by_ref().map(|cell| {
+                    let cell = cell?;
+                    let value = match &cell.value {
+                        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+                        Value::Bool(b) => Some(*b as i64),
+                        Value::String(s) => s.trim().parse().ok(),
+                        _ => None,
+                    };
+                    dropped += value.is_none() as usize;
+                    value
+                }))),
+                ParquetKind::Float64 => Arc::new(Float64Array::from_iter(cells.by_ref().map(|cell| {
+                    let cell = cell?;
+                    let value = match &cell.value {
+                        Value::Number(n) => n.as_f64(),
+                        Value::String(s) => s.trim().parse().ok(),
+                        _ => None,
+                    };
+                    dropped += value.is_none() as usize;
+                    value
+                }))),
+                ParquetKind::Boolean => Arc::new(BooleanArray::from_iter(cells.by_ref().map(|cell| {
+                    let cell = cell?;
+                    let value = match &cell.value {
+                        Value::Bool(b) => Some(*b),
+                        Value::Number(n) => n.as_f64().map(|f| f != 0.0),
+                        Value::String(s) => match s.trim().to_lowercase().as_str() {
+                            "true" | "1" | "yes" | "t" | "y" => Some(true),
+                            "false" | "0" | "no" | "f" | "n" => Some(false),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    dropped += value.is_none() as usize;
+                    value
+                }))),
+                ParquetKind::Utf8 => Arc::new(StringArray::from_iter(
+                    cells.by_ref().map(|cell| cell.map(|c| c.display.clone())),
+                )),
+                ParquetKind::Binary => Arc::new(BinaryArray::from_iter(cells.by_ref().map(|cell| {
+                    cell.map(|c| c.blob.clone().unwrap_or_else(|| c.display.clone().into_bytes()))
+                }))),
+            };
+            *coerced += dropped;
+            array
+        }
+    }
+
+    /// Buffers rows into batches and writes them to a zstd-compressed Parquet file
+    pub struct ParquetSink {
+        file: Option<File>,
+        writer: Option<ArrowWriter<File>>,
+        /// Known column types by name; other columns are inferred from the first batch
+        declared: Vec<(String, ParquetKind)>,
+        columns: Vec<String>,
+        kinds: Vec<ParquetKind>,
+        schema: Option<SchemaRef>,
+        pending: Vec<Vec<CellValue>>,
+        /// Values written as NULL because they didn't fit their column's type
+        pub coerced: usize,
+    }
+
+    impl ParquetSink {
+        pub fn create(path: &Path, declared: Vec<(String, ParquetKind)>) -> Result<Self> {
+            Ok(Self {
+                file: Some(File::create(path)?),
+                writer: None,
+                declared,
+                columns: Vec::new(),
+                kinds: Vec::new(),
+                schema: None,
+                pending: Vec::with_capacity(BATCH_ROWS),
+                coerced: 0,
+            })
+        }
+
+        fn flush_batch(&mut self) -> Result<()> {
+            if self.writer.is_none() {
+                self.kinds = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(col, name)| match self.declared.iter().find(|(n, _)| n == name) {
+                        Some((_, kind)) => *kind,
+                        None => ParquetKind::infer(&self.pending, col),
+                    })
+                    .collect();
+                let fields: Vec<Field> = self
+                    .columns
+                    .iter()
+                    .zip(&self.kinds)
+                    .map(|(name, kind)| Field::new(name, kind.data_type(), true))
+                    .collect();
+                let schema = Arc::new(Schema::new(fields));
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                    .build();
+                let file = self.file.take().ok_or_else(|| anyhow!("Parquet file already closed"))?;
+                self.writer = Some(ArrowWriter::try_new(file, schema.clone(), Some(properties))?);
+                self.schema = Some(schema);
+            }
+            if self.pending.is_empty() {
+                return Ok(());
+            }
+
+            let mut coerced = 0;
+            let arrays: Vec<ArrayRef> = self
+                .kinds
+                .iter()
+                .enumerate()
+                .map(|(col, kind)| kind.build(&self.pending, col, &mut coerced))
+                .collect();
+            self.coerced += coerced;
+            let schema = self.schema.clone().ok_or_else(|| anyhow!("Parquet schema missing"))?;
+            let batch = RecordBatch::try_new(schema, arrays)?;
+            self.writer
+                .as_mut()
+                .ok_or_else(|| anyhow!("Parquet writer missing"))?
+                .write(&batch)?;
+            self.pending.clear();
+            Ok(())
+        }
+    }
+
+    impl RowSink for ParquetSink {
+        fn begin(&mut self, columns: &[String]) -> Result<()> {
+            self.columns = columns.to_vec();
+            Ok(())
+        }
+
+        fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+            self.pending.push(cells.to_vec());
+            if self.pending.len() >= BATCH_ROWS {
+                self.flush_batch()?;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            self.flush_batch()?;
+            if let Some(writer) = self.writer.take() {
+                writer.close()?;
+            }
+            if self.coerced > 0 {
+                tracing::warn!("{} values didn't fit their Parquet column type and were written as NULL", self.coerced);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Export the rows of `table_name` covered by `scope`, typed by the table's columns
+#[cfg(feature = "parquet")]
+pub fn export_table_to_parquet(db: &DatabaseManager, table_name: &str, scope: &RowScope, path: &Path) -> Result<usize> {
+    let schema = db
+        .get_schema(table_name)
+        .ok_or_else(|| anyhow::anyhow!("Schema not found for table: {}", table_name))?;
+    let mut declared: Vec<(String, ParquetKind)> = schema
+        .fields
+        .iter()
+        .map(|f| (f.name.clone(), ParquetKind::from_sql_type(&f.sql_type)))
+        .collect();
+    if schema.primary_key.is_empty() {
+        declared.push(("id".to_string(), ParquetKind::Int64));
+    }
+    let mut sink = ParquetSink::create(path, declared)?;
+    db.stream_table(table_name, scope, &mut sink)
+}
+
+/// Export a query's result, typing each column from its first rows
+#[cfg(feature = "parquet")]
+pub fn export_query_to_parquet(db: &DatabaseManager, sql: &str, path: &Path) -> Result<usize> {
+    let mut sink = ParquetSink::create(path, Vec::new())?;
+    db.stream_query(sql, &mut sink)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn export_table_to_parquet(_db: &DatabaseManager, _table_name: &str, _scope: &RowScope, _path: &Path) -> Result<usize> {
+    Err(anyhow::anyhow!("This build has no Parquet support; rebuild with --features parquet"))
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn export_query_to_parquet(_db: &DatabaseManager, _sql: &str, _path: &Path) -> Result<usize> {
+    Err(anyhow::anyhow!("This build has no Parquet support; rebuild with --features parquet"))
+}
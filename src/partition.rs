@@ -0,0 +1,265 @@
+//! Splitting a huge table into one table per value of a key column, behind a
+//! `UNION ALL` view that keeps the original name so existing reads still work, and
+//! merging such partitions back into a single table. Each direction is planned as a
+//! script to review, then run in one transaction.
+
+use anyhow::{Result, anyhow};
+use crate::database::{CellValue, DatabaseManager};
+use crate::sql_text::{mentions, quote_identifier, replace_identifier, TableDefinition};
+
+/// More distinct values than this is almost certainly the wrong column
+pub const MAX_PARTITIONS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionDirection {
+    Split,
+    Merge,
+}
+
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub table: String,
+    /// Key value the partition holds, as shown in the grid; empty when merging
+    pub value: String,
+    pub rows: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartitionPlan {
+    pub direction: PartitionDirection,
+    /// The table being split, or the view being merged; the name survives either way
+    pub name: String,
+    pub column: Option<String>,
+    pub partitions: Vec<Partition>,
+    /// Triggers on the original that won't exist afterwards
+    pub lost_triggers: Vec<String>,
+    pub script: String,
+}
+
+impl PartitionPlan {
+    /// Summary shown above the script
+    pub fn describe(&self) -> String {
+        let mut out = match (self.direction, &self.column) {
+            (PartitionDirection::Split, Some(column)) => format!(
+                "Split {} by {} into {} tables behind a view named {}\n",
+                self.name,
+                column,
+                self.partitions.len(),
+                self.name
+            ),
+            _ => format!("Merge {} tables into a table named {}\n", self.partitions.len(), self.name),
+        };
+        for partition in &self.partitions {
+            match self.direction {
+                PartitionDirection::Split => out.push_str(&format!(
+                    "  {} = {} ({} rows)\n",
+                    partition.table, partition.value, partition.rows
+                )),
+                PartitionDirection::Merge => out.push_str(&format!("  {} ({} rows)\n", partition.table, partition.rows)),
+            }
+        }
+        if !self.lost_triggers.is_empty() {
+            out.push_str(&format!("Triggers dropped with the table: {}\n", self.lost_triggers.join(", ")));
+        }
+        out
+    }
+}
+
+/// Plan splitting `table` into one table per distinct value of `column`
+pub fn plan_split(db: &DatabaseManager, table: &str, column: &str) -> Result<PartitionPlan> {
+    let definition = TableDefinition::parse(&db.table_sql(table)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table))?;
+    if !definition.column_names().iter().any(|c| c.eq_ignore_ascii_case(column)) {
+        return Err(anyhow!("{} has no column {}", table, column));
+    }
+
+    let groups = db
+        .fetch_query(
+            &format!(
+                "SELECT {col}, COUNT(*) FROM {table} GROUP BY {col} ORDER BY {col} LIMIT {limit}",
+                col = quote_identifier(column),
+                table = quote_identifier(table),
+                limit = MAX_PARTITIONS + 1
+            ),
+            MAX_PARTITIONS + 1,
+        )?
+        .rows;
+    if groups.len() > MAX_PARTITIONS {
+        return Err(anyhow!(
+            "{} has more than {} distinct values; partition by a coarser column",
+            column,
+            MAX_PARTITIONS
+        ));
+    }
+    if groups.len() < 2 {
+        return Err(anyhow!("{} has fewer than two distinct values, nothing to split", column));
+    }
+
+    let existing = db.list_tables()?;
+    let objects = db.schema_objects()?;
+    let indexes: Vec<(String, String)> = objects
+        .iter()
+        .filter(|o| o.kind == "index" && o.table_name == table)
+        .filter_map(|o| Some((o.name.clone(), o.sql.clone()?)))
+        .collect();
+    let lost_triggers = objects
+        .iter()
+        .filter(|o| o.kind == "trigger" && o.table_name == table)
+        .map(|o| o.name.clone())
+        .collect();
+
+    let mut partitions: Vec<Partition> = Vec::new();
+    let mut script = String::new();
+    let mut selects = Vec::new();
+    for group in &groups {
+        let (Some(value), Some(rows)) = (group.first(), group.get(1).and_then(|c| c.value.as_u64())) else {
+            continue;
+        };
+        let suffix = unique_name(table, &suffix_for(value), &existing, &partitions);
+        let name = format!("{}_{}", table, suffix);
+        let condition = match value.value {
+            serde_json::Value::Null => format!("{} IS NULL", quote_identifier(column)),
+            _ => format!("{} = {}", quote_identifier(column), value.sql_literal()),
+        };
+
+        script.push_str(&format!("CREATE TABLE {} {};\n", quote_identifier(&name), definition.to_sql()));
+        script.push_str(&format!(
+            "INSERT INTO {} SELECT * FROM {} WHERE {};\n",
+            quote_identifier(&name),
+            quote_identifier(table),
+            condition
+        ));
+        for (index, sql) in &indexes {
+            let (sql, _) = replace_identifier(sql, table, &name);
+            let (sql, _) = replace_identifier(&sql, index, &format!("{}_{}", index, suffix));
+            script.push_str(&format!("{};\n", sql));
+        }
+        selects.push(format!("SELECT * FROM {}", quote_identifier(&name)));
+        partitions.push(Partition {
+            table: name,
+            value: value.display.clone(),
+            rows: rows as usize,
+        });
+    }
+    script.push_str(&format!("DROP TABLE {};\n", quote_identifier(table)));
+    script.push_str(&format!(
+        "CREATE VIEW {} AS\n{};\n",
+        quote_identifier(table),
+        selects.join("\nUNION ALL\n")
+    ));
+
+    Ok(PartitionPlan {
+        direction: PartitionDirection::Split,
+        name: table.to_string(),
+        column: Some(column.to_string()),
+        partitions,
+        lost_triggers,
+        script,
+    })
+}
+
+/// Plan replacing the view `view` with one table holding the rows of every table it
+/// unions, which are dropped. The tables must have the same columns.
+pub fn plan_merge(db: &DatabaseManager, view: &str) -> Result<PartitionPlan> {
+    let objects = db.schema_objects()?;
+    let view_sql = objects
+        .iter()
+        .find(|o| o.kind == "view" && o.name == view)
+        .and_then(|o| o.sql.clone())
+        .ok_or_else(|| anyhow!("No view named \"{}\"", view))?;
+
+    let tables: Vec<String> = db.list_tables()?.into_iter().filter(|t| mentions(&view_sql, t)).collect();
+    let Some(first) = tables.first() else {
+        return Err(anyhow!("{} doesn't read from any table", view));
+    };
+    let columns = db.column_names(first)?;
+    if let Some(mismatch) = tables.iter().find(|t| db.column_names(t).ok().as_ref() != Some(&columns)) {
+        return Err(anyhow!("{} doesn't have the same columns as {}", mismatch, first));
+    }
+    let definition = TableDefinition::parse(&db.table_sql(first)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", first))?;
+
+    let mut script = format!("DROP VIEW {};\n", quote_identifier(view));
+    script.push_str(&format!("CREATE TABLE {} {};\n", quote_identifier(view), definition.to_sql()));
+    let mut partitions = Vec::new();
+    for table in &tables {
+        script.push_str(&format!(
+            "INSERT INTO {} SELECT * FROM {};\n",
+            quote_identifier(view),
+            quote_identifier(table)
+        ));
+        partitions.push(Partition {
+            table: table.clone(),
+            value: String::new(),
+            rows: db.get_row_count(table)?,
+        });
+    }
+    for table in &tables {
+        script.push_str(&format!("DROP TABLE {};\n", quote_identifier(table)));
+    }
+
+    // The first partition's indexes come back on the merged table, minus the suffix a split gave them
+    let suffix = first.strip_prefix(&format!("{}_", view)).map(|s| format!("_{}", s));
+    for (index, sql) in objects
+        .iter()
+        .filter(|o| o.kind == "index" && &o.table_name == first)
+        .filter_map(|o| Some((o.name.clone(), o.sql.clone()?)))
+    {
+        let name = match suffix.as_deref().and_then(|s| index.strip_suffix(s)) {
+            Some(base) => base.to_string(),
+            None => format!("{}_merged", index),
+        };
+        let (sql, _) = replace_identifier(&sql, first, view);
+        let (sql, _) = replace_identifier(&sql, &index, &name);
+        script.push_str(&format!("{};\n", sql));
+    }
+
+    Ok(PartitionPlan {
+        direction: PartitionDirection::Merge,
+        name: view.to_string(),
+        column: None,
+        partitions,
+        lost_triggers: objects
+            .iter()
+            .filter(|o| o.kind == "trigger" && tables.contains(&o.table_name))
+            .map(|o| o.name.clone())
+            .collect(),
+        script,
+    })
+}
+
+/// Run the plan's script in one transaction, so a failure leaves the table as it was
+pub fn apply(db: &DatabaseManager, plan: &PartitionPlan) -> Result<()> {
+    db.with_transaction(|tx| Ok(tx.execute_batch(&plan.script)?))?;
+    db.refresh_schemas()
+}
+
+/// `eu_west` for the value `EU-West`
+fn suffix_for(value: &CellValue) -> String {
+    if value.value.is_null() {
+        return "null".to_string();
+    }
+    let suffix: String = value
+        .display
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let suffix = suffix.trim_matches('_').to_string();
+    if suffix.is_empty() { "empty".to_string() } else { suffix }
+}
+
+/// `suffix`, numbered if `table_suffix` is already a table or another partition
+fn unique_name(table: &str, suffix: &str, existing: &[String], partitions: &[Partition]) -> String {
+    let taken = |candidate: &str| {
+        let name = format!("{}_{}", table, candidate);
+        existing.iter().any(|t| t.eq_ignore_ascii_case(&name)) || partitions.iter().any(|p| p.table.eq_ignore_ascii_case(&name))
+    };
+    let mut candidate = suffix.to_string();
+    let mut n = 2;
+    while taken(&candidate) {
+        candidate = format!("{}_{}", suffix, n);
+        n += 1;
+    }
+    candidate
+}
@@ -0,0 +1,100 @@
+//! Pasted spreadsheet text staged as inserts. When the first pasted row looks like
+//! headers, columns are matched to the table by name rather than by position.
+
+use serde_json::Value;
+use crate::import::{parse_field_value, parse_records, sniff_dialect, CsvDialect};
+use crate::reflection::TypeSchema;
+
+#[derive(Debug, Clone)]
+pub struct PastedRows {
+    /// The first pasted row, when it named columns
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    /// Schema field filled by each pasted column; `None` leaves the column out
+    pub targets: Vec<Option<usize>>,
+    /// Named columns that didn't match any table column, for the user to map or skip
+    pub unmatched: Vec<usize>,
+}
+
+impl PastedRows {
+    pub fn parse(schema: &TypeSchema, text: &str) -> Self {
+        // Spreadsheets put tab-separated text on the clipboard
+        let dialect = if text.contains('\t') {
+            CsvDialect {
+                delimiter: '\t',
+                ..Default::default()
+            }
+        } else {
+            sniff_dialect(text)
+        };
+
+        let mut rows = parse_records(text, &dialect);
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let field_index = |name: &str| {
+            schema
+                .fields
+                .iter()
+                .position(|f| f.name.eq_ignore_ascii_case(name.trim()))
+        };
+
+        let has_header = rows
+            .first()
+            .is_some_and(|first| first.iter().any(|cell| field_index(cell).is_some()));
+
+        if has_header {
+            let headers = rows.remove(0);
+            let targets: Vec<Option<usize>> = (0..width)
+                .map(|i| headers.get(i).and_then(|h| field_index(h)))
+                .collect();
+            let unmatched = (0..width)
+                .filter(|&i| targets[i].is_none() && headers.get(i).is_some_and(|h| !h.trim().is_empty()))
+                .collect();
+            Self {
+                headers: Some(headers),
+                rows,
+                targets,
+                unmatched,
+            }
+        } else {
+            let targets = (0..width)
+                .map(|i| (i < schema.fields.len()).then_some(i))
+                .collect();
+            Self {
+                headers: None,
+                rows,
+                targets,
+                unmatched: Vec::new(),
+            }
+        }
+    }
+
+    pub fn column_label(&self, col: usize) -> String {
+        self.headers
+            .as_ref()
+            .and_then(|h| h.get(col))
+            .cloned()
+            .unwrap_or_else(|| format!("Column {}", col + 1))
+    }
+
+    /// Point a pasted column at the next schema field no other column fills, or back to none
+    pub fn cycle_target(&mut self, col: usize, field_count: usize) {
+        let start = self.targets[col].map(|t| t + 1).unwrap_or(0);
+        self.targets[col] = (start..field_count).find(|&field| !self.targets.contains(&Some(field)));
+    }
+
+    /// One value per schema field for every pasted row; unfilled fields are NULL
+    pub fn to_values(&self, schema: &TypeSchema) -> Vec<Vec<Value>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut values = vec![Value::Null; schema.fields.len()];
+                for (col, target) in self.targets.iter().enumerate() {
+                    if let (Some(field), Some(raw)) = (target, row.get(col)) {
+                        values[*field] = parse_field_value(&schema.fields[*field], raw);
+                    }
+                }
+                values
+            })
+            .collect()
+    }
+}
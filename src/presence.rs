@@ -0,0 +1,104 @@
+//! Co-editing presence for databases shared over a network drive. Each open editor
+//! keeps an entry in the `presence` sidecar current with a heartbeat, naming the
+//! tables it's on and editing, so teammates see "Alice is editing `items`" before
+//! their edits collide. Entries nobody refreshed lately are dropped as stale.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const PRESENCE_SIDECAR: &str = "presence";
+
+/// How often an open editor refreshes its own entry
+pub const PRESENCE_HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Entries older than this belong to editors that closed or crashed
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Presence {
+    pub editors: Vec<EditorPresence>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorPresence {
+    /// One per open editor, so the same person in two windows shows twice
+    pub session: String,
+    pub identity: String,
+    /// Table in the active tab
+    #[serde(default)]
+    pub table: Option<String>,
+    /// Tables with edits not yet saved or committed
+    #[serde(default)]
+    pub editing: Vec<String>,
+    /// Unix seconds of the last heartbeat
+    pub seen_at: u64,
+}
+
+impl EditorPresence {
+    pub fn new(session: String, identity: String, table: Option<String>, editing: Vec<String>) -> Self {
+        Self { session, identity, table, editing, seen_at: unix_now() }
+    }
+
+    /// `Alice is editing `items``, or what she has open when she isn't editing
+    pub fn describe(&self) -> String {
+        match (self.editing.as_slice(), &self.table) {
+            ([], Some(table)) => format!("{} has `{}` open", self.identity, table),
+            ([], None) => format!("{} has the database open", self.identity),
+            (tables, _) => {
+                let tables: Vec<String> = tables.iter().map(|t| format!("`{}`", t)).collect();
+                format!("{} is editing {}", self.identity, tables.join(", "))
+            }
+        }
+    }
+
+    pub fn is_editing(&self, table: &str) -> bool {
+        self.editing.iter().any(|t| t == table)
+    }
+
+    fn is_stale(&self) -> bool {
+        unix_now().saturating_sub(self.seen_at) > STALE_AFTER.as_secs()
+    }
+}
+
+impl Presence {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(crate::sidecar::load(db_path, PRESENCE_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        crate::sidecar::save(db_path, PRESENCE_SIDECAR, self)
+    }
+}
+
+/// Refresh `me` in the sidecar, dropping stale entries, and return everyone else
+pub fn announce(db_path: &Path, me: EditorPresence) -> Result<Vec<EditorPresence>> {
+    let mut presence = read_shared(db_path);
+    presence.editors.retain(|e| e.session != me.session && !e.is_stale());
+    let others = presence.editors.clone();
+    presence.editors.push(me);
+    presence.save(db_path)?;
+    Ok(others)
+}
+
+/// Remove this editor's entry when it closes
+pub fn leave(db_path: &Path, session: &str) -> Result<()> {
+    let mut presence = read_shared(db_path);
+    presence.editors.retain(|e| e.session != session && !e.is_stale());
+    presence.save(db_path)
+}
+
+/// Another editor may be halfway through writing the file; its next heartbeat
+/// rewrites what's lost, so an unreadable sidecar counts as empty
+fn read_shared(db_path: &Path) -> Presence {
+    Presence::load(db_path).unwrap_or_else(|e| {
+        tracing::warn!("Failed to read editor presence, starting it over: {}", e);
+        Presence::default()
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
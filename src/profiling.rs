@@ -0,0 +1,244 @@
+//! Column-level data profiling: completeness, cardinality, value distribution,
+//! lengths and numeric outliers, all computed with aggregate queries in SQLite.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use crate::database::DatabaseManager;
+use crate::reflection::SqlType;
+
+/// Most frequent values listed per column
+const TOP_VALUES: usize = 5;
+
+/// Numeric values further than this many standard deviations from the mean are flagged
+const OUTLIER_SIGMA: f64 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub sql_type: String,
+    pub non_null: usize,
+    pub distinct: usize,
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub mean: Option<f64>,
+    pub outliers: usize,
+    pub top_values: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableProfile {
+    pub table_name: String,
+    pub row_count: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+impl TableProfile {
+    pub fn completeness(&self, column: &ColumnProfile) -> f64 {
+        ratio(column.non_null, self.row_count)
+    }
+
+    pub fn distinct_ratio(&self, column: &ColumnProfile) -> f64 {
+        ratio(column.distinct, column.non_null)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Profile: {}\n\n{} rows\n\n", self.table_name, self.row_count);
+        md.push_str("| Column | Type | Complete | Distinct | Length | Min | Max | Outliers | Top values |\n");
+        md.push_str("|---|---|---|---|---|---|---|---|---|\n");
+
+        for col in &self.columns {
+            md.push_str(&format!(
+                "| {} | {} | {:.1}% | {:.1}% | {} | {} | {} | {} | {} |\n",
+                col.name,
+                col.sql_type,
+                self.completeness(col) * 100.0,
+                self.distinct_ratio(col) * 100.0,
+                length_range(col),
+                col.min.as_deref().unwrap_or("—").replace('|', "\\|"),
+                col.max.as_deref().unwrap_or("—").replace('|', "\\|"),
+                col.outliers,
+                top_values(col).replace('|', "\\|"),
+            ));
+        }
+
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Profile: {0}</title>\n</head>\n<body>\n<h1>Profile: {0}</h1>\n<p>{1} rows</p>\n<table border=\"1\" cellspacing=\"0\" cellpadding=\"4\">\n",
+            escape_html(&self.table_name),
+            self.row_count
+        );
+        html.push_str("<tr><th>Column</th><th>Type</th><th>Complete</th><th>Distinct</th><th>Length</th><th>Min</th><th>Max</th><th>Outliers</th><th>Top values</th></tr>\n");
+
+        for col in &self.columns {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.1}%</td><td>{:.1}%</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&col.name),
+                escape_html(&col.sql_type),
+                self.completeness(col) * 100.0,
+                self.distinct_ratio(col) * 100.0,
+                length_range(col),
+                escape_html(col.min.as_deref().unwrap_or("—")),
+                escape_html(col.max.as_deref().unwrap_or("—")),
+                col.outliers,
+                escape_html(&top_values(col)),
+            ));
+        }
+
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+pub fn profile_table(db: &DatabaseManager, table_name: &str) -> Result<TableProfile> {
+    let schema = db
+        .get_schema(table_name)
+        .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+    let row_count = db.get_row_count(table_name)?;
+
+    let mut columns = Vec::new();
+    for field in &schema.fields {
+        let col = &field.name;
+        let stats = db.fetch_query(
+            &format!(
+                "SELECT COUNT({col}), COUNT(DISTINCT {col}), MIN(LENGTH({col})), MAX(LENGTH({col})), MIN({col}), MAX({col}) FROM {table}",
+                col = col,
+                table = table_name
+            ),
+            1,
+        )?;
+        let stats = stats.rows.into_iter().next().unwrap_or_default();
+        let stat = |i: usize| stats.get(i).map(|c| c.value.clone()).unwrap_or(Value::Null);
+
+        let is_numeric = matches!(field.sql_type, SqlType::Integer | SqlType::Real);
+        let (mean, outliers) = if is_numeric {
+            numeric_outliers(db, table_name, col)?
+        } else {
+            (None, 0)
+        };
+
+        let top = db.fetch_query(
+            &format!(
+                "SELECT {col}, COUNT(*) AS n FROM {table} WHERE {col} IS NOT NULL GROUP BY {col} ORDER BY n DESC LIMIT {limit}",
+                col = col,
+                table = table_name,
+                limit = TOP_VALUES
+            ),
+            TOP_VALUES,
+        )?;
+        let top_values = top
+            .rows
+            .iter()
+            .filter_map(|row| {
+                let value = row.first()?.display.clone();
+                let count = row.get(1)?.value.as_u64()? as usize;
+                Some((value, count))
+            })
+            .collect();
+
+        columns.push(ColumnProfile {
+            name: col.clone(),
+            sql_type: field.sql_type.to_sql_string(),
+            non_null: stat(0).as_u64().unwrap_or(0) as usize,
+            distinct: stat(1).as_u64().unwrap_or(0) as usize,
+            min_length: stat(2).as_i64(),
+            max_length: stat(3).as_i64(),
+            min: non_null_display(stat(4)),
+            max: non_null_display(stat(5)),
+            mean,
+            outliers,
+            top_values,
+        });
+    }
+
+    Ok(TableProfile {
+        table_name: table_name.to_string(),
+        row_count,
+        columns,
+    })
+}
+
+/// Mean of a numeric column and how many values sit outside `OUTLIER_SIGMA` deviations
+fn numeric_outliers(db: &DatabaseManager, table_name: &str, col: &str) -> Result<(Option<f64>, usize)> {
+    let moments = db.fetch_query(
+        &format!(
+            "SELECT AVG({col}), AVG({col} * {col}) FROM {table} WHERE {col} IS NOT NULL",
+            col = col,
+            table = table_name
+        ),
+        1,
+    )?;
+    let row = moments.rows.into_iter().next().unwrap_or_default();
+    let (Some(mean), Some(mean_sq)) = (
+        row.first().and_then(|c| c.value.as_f64()),
+        row.get(1).and_then(|c| c.value.as_f64()),
+    ) else {
+        return Ok((None, 0));
+    };
+
+    let std_dev = (mean_sq - mean * mean).max(0.0).sqrt();
+    if std_dev == 0.0 {
+        return Ok((Some(mean), 0));
+    }
+
+    let outliers = db.fetch_query(
+        &format!(
+            "SELECT COUNT(*) FROM {table} WHERE ABS({col} - {mean}) > {limit}",
+            col = col,
+            table = table_name,
+            mean = mean,
+            limit = OUTLIER_SIGMA * std_dev
+        ),
+        1,
+    )?;
+    let count = outliers
+        .rows
+        .first()
+        .and_then(|row| row.first())
+        .and_then(|c| c.value.as_u64())
+        .unwrap_or(0) as usize;
+
+    Ok((Some(mean), count))
+}
+
+fn non_null_display(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn length_range(col: &ColumnProfile) -> String {
+    match (col.min_length, col.max_length) {
+        (Some(min), Some(max)) => format!("{}–{}", min, max),
+        _ => "—".to_string(),
+    }
+}
+
+fn top_values(col: &ColumnProfile) -> String {
+    col.top_values
+        .iter()
+        .map(|(value, count)| format!("{} ({})", value, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,148 @@
+//! Project-wide queries. The other databases found in the project are attached
+//! read-only to the open one under aliases taken from their file names, so a single
+//! query can join or UNION across them (`SELECT * FROM region_eu.items`). The open
+//! database stays `main`.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use crate::database::{fetch_statement, DatabaseManager, QueryRows};
+use crate::macros::MACROS_FILE_NAME;
+use crate::sql_text::quote_identifier;
+
+pub const DATABASE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+/// SQLite's default `SQLITE_MAX_ATTACHED`
+pub const MAX_ATTACHED: usize = 10;
+
+/// Column union queries add, naming the database each row came from
+pub const SOURCE_COLUMN: &str = "source_db";
+
+/// How deep below the project root to look for databases
+const MAX_SCAN_DEPTH: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectDatabase {
+    pub alias: String,
+    pub path: PathBuf,
+}
+
+/// The directory holding the project's macro file, or else the database's own directory
+pub fn project_root(db_path: &Path) -> PathBuf {
+    db_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(MACROS_FILE_NAME).is_file())
+        .or_else(|| db_path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Every other database under the project root, up to what SQLite can attach at once
+pub fn discover(db_path: &Path) -> Result<Vec<ProjectDatabase>> {
+    let mut paths = Vec::new();
+    scan(&project_root(db_path), 0, &mut paths)?;
+    let own = db_path.canonicalize().unwrap_or_else(|_| db_path.to_path_buf());
+    paths.retain(|p| p.canonicalize().map_or(true, |p| p != own));
+    paths.sort();
+
+    if paths.len() > MAX_ATTACHED {
+        tracing::warn!(
+            "Found {} project databases, attaching the first {} only",
+            paths.len(),
+            MAX_ATTACHED
+        );
+        paths.truncate(MAX_ATTACHED);
+    }
+
+    let mut databases: Vec<ProjectDatabase> = Vec::new();
+    for path in paths {
+        let alias = alias_for(&path, &databases);
+        databases.push(ProjectDatabase { alias, path });
+    }
+    Ok(databases)
+}
+
+fn scan(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with('.') || name == "target" || name == "node_modules" {
+            continue;
+        }
+        if path.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                scan(&path, depth + 1, found)?;
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| DATABASE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The file stem as a plain identifier, made unique among `taken`
+fn alias_for(path: &Path, taken: &[ProjectDatabase]) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mut base: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) || base == "main" || base == "temp" {
+        base = format!("db_{}", base);
+    }
+
+    let mut alias = base.clone();
+    let mut n = 2;
+    while taken.iter().any(|d| d.alias == alias) {
+        alias = format!("{}_{}", base, n);
+        n += 1;
+    }
+    alias
+}
+
+/// Run `sql` with every project database attached
+pub fn run(db: &DatabaseManager, databases: &[ProjectDatabase], sql: &str, max_rows: usize) -> Result<QueryRows> {
+    let attached: Vec<(&str, &Path)> = databases.iter().map(|d| (d.alias.as_str(), d.path.as_path())).collect();
+    db.with_attached_all(&attached, |conn| fetch_statement(conn, sql, max_rows))
+}
+
+/// `UNION ALL` of `table` from every database that has it, each row tagged with its
+/// database in [`SOURCE_COLUMN`]. Only databases with all of the first one's columns
+/// are included, so the parts line up. `None` if no database has the table.
+pub fn union_query(db: &DatabaseManager, databases: &[ProjectDatabase], table: &str) -> Result<Option<String>> {
+    let mut parts: Vec<(String, Vec<String>)> = Vec::new();
+    let main_columns = db.column_names(table)?;
+    if !main_columns.is_empty() {
+        parts.push(("main".to_string(), main_columns));
+    }
+    for database in databases {
+        let columns = DatabaseManager::open_read_only(&database.path)?.column_names(table)?;
+        if !columns.is_empty() {
+            parts.push((database.alias.clone(), columns));
+        }
+    }
+
+    let Some((_, columns)) = parts.first().cloned() else {
+        return Ok(None);
+    };
+    let column_list = columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+    let selects: Vec<String> = parts
+        .iter()
+        .filter(|(_, have)| columns.iter().all(|c| have.contains(c)))
+        .map(|(alias, _)| {
+            format!(
+                "SELECT '{alias}' AS {source}, {columns} FROM {schema}.{table}",
+                alias = alias,
+                source = SOURCE_COLUMN,
+                columns = column_list,
+                schema = quote_identifier(alias),
+                table = quote_identifier(table),
+            )
+        })
+        .collect();
+    Ok(Some(selects.join("\nUNION ALL\n")))
+}
@@ -0,0 +1,106 @@
+//! Row provenance: `created_at`, `updated_at` and `updated_by` kept current on every
+//! row the editor writes, so hand edits stay traceable. Turned on per database in the
+//! `provenance` sidecar; `DatabaseManager` adds the stamps to the grid's own writes,
+//! so held edits, pastes and single-cell saves all carry them.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::reflection::{FieldSchema, SqlType, TypeSchema};
+use crate::sql_text::quote_identifier;
+
+pub const PROVENANCE_SIDECAR: &str = "provenance";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProvenanceConfig {
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    pub updated_by: String,
+}
+
+impl Default for ProvenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            created_at: "created_at".to_string(),
+            updated_at: "updated_at".to_string(),
+            updated_by: "updated_by".to_string(),
+        }
+    }
+}
+
+impl ProvenanceConfig {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(crate::sidecar::load(db_path, PROVENANCE_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        crate::sidecar::save(db_path, PROVENANCE_SIDECAR, self)
+    }
+}
+
+/// The stamping in force: which columns, and who to record as the editor
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    pub config: ProvenanceConfig,
+    pub identity: String,
+}
+
+impl Provenance {
+    /// The stamp columns an update of `schema`'s table sets, leaving out any the edit
+    /// itself writes
+    pub fn update_columns(&self, schema: &TypeSchema, written: &[&str]) -> Vec<String> {
+        [&self.config.updated_at, &self.config.updated_by]
+            .into_iter()
+            .filter(|column| !written.contains(&column.as_str()))
+            .filter_map(|column| field(schema, column).map(|f| f.name.clone()))
+            .collect()
+    }
+
+    /// `SET` assignments for those columns; empty when the table has none of them
+    pub fn update_stamps(&self, schema: &TypeSchema, written: &[&str]) -> Vec<String> {
+        self.update_columns(schema, written)
+            .iter()
+            .filter_map(|column| field(schema, column))
+            .map(|field| format!("{} = {}", quote_identifier(&field.name), self.value(field)))
+            .collect()
+    }
+
+    /// What to put in an INSERT's VALUES for `field` in place of the plain `?`, and
+    /// whether it still binds the row's value; `None` for anything but a stamp column.
+    /// `created_at` keeps a value the row came with.
+    pub fn insert_placeholder(&self, field: &FieldSchema) -> Option<(String, bool)> {
+        if field.name.is_empty() {
+            return None;
+        }
+        if field.name == self.config.created_at {
+            Some((format!("COALESCE(?, {})", now(field)), true))
+        } else if field.name == self.config.updated_at || field.name == self.config.updated_by {
+            Some((self.value(field), false))
+        } else {
+            None
+        }
+    }
+
+    fn value(&self, field: &FieldSchema) -> String {
+        if field.name == self.config.updated_by {
+            format!("'{}'", self.identity.replace('\'', "''"))
+        } else {
+            now(field).to_string()
+        }
+    }
+}
+
+fn field<'a>(schema: &'a TypeSchema, column: &str) -> Option<&'a FieldSchema> {
+    (!column.is_empty()).then(|| schema.fields.iter().find(|f| f.name == column)).flatten()
+}
+
+/// Unix seconds for integer columns, ISO 8601 UTC text otherwise
+fn now(field: &FieldSchema) -> &'static str {
+    match field.sql_type {
+        SqlType::Integer => "CAST(strftime('%s', 'now') AS INTEGER)",
+        _ => "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')",
+    }
+}
@@ -0,0 +1,180 @@
+//! Data-source provider for other Pulsar tools. Widgets such as leaderboards or item
+//! browsers bind to a table or query through the provider for the open database and
+//! are called back with fresh rows when the data changes, instead of opening SQLite
+//! themselves. Bindings live on the UI thread alongside the editor's connection.
+//! Polls wait while transactional editing holds uncommitted edits, so widgets only
+//! see committed rows (a binding made meanwhile starts from the pending ones).
+
+use anyhow::Result;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use crate::database::DatabaseManager;
+use crate::sql_text::quote_identifier;
+
+/// Rows delivered per binding unless the subscriber asks for another cap
+pub const DEFAULT_ROW_LIMIT: usize = 1_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    Table(String),
+    /// A read-only query; writes are rejected
+    Query(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSnapshot {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// More rows matched than the binding's limit
+    pub truncated: bool,
+}
+
+pub type ChangeCallback = Rc<dyn Fn(&DataSource, &DataSnapshot)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BindingId(u64);
+
+struct Binding {
+    source: DataSource,
+    limit: usize,
+    callback: ChangeCallback,
+    last: Option<DataSnapshot>,
+}
+
+pub struct DataProvider {
+    db: DatabaseManager,
+    bindings: RefCell<HashMap<BindingId, Binding>>,
+    next_id: Cell<u64>,
+    /// Change counter as of the last poll
+    seen: Cell<Option<(i64, u64)>>,
+}
+
+impl DataProvider {
+    pub fn new(db: DatabaseManager) -> Rc<Self> {
+        Rc::new(Self {
+            db,
+            bindings: RefCell::new(HashMap::new()),
+            next_id: Cell::new(0),
+            seen: Cell::new(None),
+        })
+    }
+
+    pub fn tables(&self) -> Result<Vec<String>> {
+        self.db.list_tables()
+    }
+
+    pub fn fetch(&self, source: &DataSource, limit: usize) -> Result<DataSnapshot> {
+        let sql = match source {
+            DataSource::Table(table) => format!("SELECT * FROM {}", quote_identifier(table)),
+            DataSource::Query(sql) => {
+                if !self.db.is_read_only_statement(sql)? {
+                    return Err(anyhow::anyhow!("Data-source queries must not write"));
+                }
+                sql.clone()
+            }
+        };
+        let output = self.db.fetch_query(&sql, limit)?;
+        Ok(DataSnapshot {
+            columns: output.columns,
+            rows: output
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(|cell| cell.value).collect())
+                .collect(),
+            truncated: output.truncated,
+        })
+    }
+
+    /// Bind to a source; `callback` runs right away with the current rows and again
+    /// whenever a poll finds they changed
+    pub fn bind(&self, source: DataSource, limit: usize, callback: ChangeCallback) -> Result<BindingId> {
+        let snapshot = self.fetch(&source, limit)?;
+        callback(&source, &snapshot);
+
+        self.next_id.set(self.next_id.get() + 1);
+        let id = BindingId(self.next_id.get());
+        self.bindings.borrow_mut().insert(
+            id,
+            Binding {
+                source,
+                limit,
+                callback,
+                last: Some(snapshot),
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn unbind(&self, id: BindingId) {
+        self.bindings.borrow_mut().remove(&id);
+    }
+
+    /// Re-fetch bound sources if the database changed since the last poll, calling back
+    /// only those whose rows differ. Cheap when nothing changed, and skipped while a
+    /// transaction is open; the commit is picked up by the next poll.
+    pub fn poll(&self) -> Result<usize> {
+        if self.db.has_open_transaction() {
+            return Ok(0);
+        }
+        let counter = self.db.change_counter()?;
+        if self.seen.replace(Some(counter)) == Some(counter) {
+            return Ok(0);
+        }
+
+        // Callbacks run after the borrow ends so they may bind or unbind
+        let mut changed = Vec::new();
+        for binding in self.bindings.borrow_mut().values_mut() {
+            let snapshot = match self.fetch(&binding.source, binding.limit) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    tracing::error!("Failed to refresh data source {:?}: {}", binding.source, e);
+                    continue;
+                }
+            };
+            if binding.last.as_ref() != Some(&snapshot) {
+                binding.last = Some(snapshot.clone());
+                changed.push((binding.callback.clone(), binding.source.clone(), snapshot));
+            }
+        }
+        for (callback, source, snapshot) in &changed {
+            callback(source, snapshot);
+        }
+        Ok(changed.len())
+    }
+}
+
+thread_local! {
+    /// Providers for databases open in the table editor, keyed by file path
+    static PROVIDERS: RefCell<Vec<(PathBuf, Weak<DataProvider>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Make `provider` discoverable by other tools until it's dropped
+pub fn register(path: &Path, provider: &Rc<DataProvider>) {
+    PROVIDERS.with_borrow_mut(|providers| {
+        providers.retain(|(p, weak)| weak.strong_count() > 0 && p != path);
+        providers.push((path.to_path_buf(), Rc::downgrade(provider)));
+    });
+}
+
+/// Tell bound widgets about changes made through `db`, if a provider is registered for it
+pub fn notify(db: &DatabaseManager) {
+    let Some(provider) = db.path().and_then(provider_for) else {
+        return;
+    };
+    if let Err(e) = provider.poll() {
+        tracing::error!("Failed to refresh data sources: {}", e);
+    }
+}
+
+/// The provider for a database open in the table editor, if any
+pub fn provider_for(path: &Path) -> Option<Rc<DataProvider>> {
+    PROVIDERS.with_borrow(|providers| {
+        providers
+            .iter()
+            .find(|(p, _)| p == path)
+            .and_then(|(_, weak)| weak.upgrade())
+    })
+}
@@ -0,0 +1,45 @@
+//! Safe-mode ("quarantine") opening for databases from untrusted sources. A crafted
+//! file can hide SQL in its schema (triggers, views, generated columns) that runs as
+//! soon as a table is read, or carry values big enough to stall the editor, so files
+//! that look downloaded are opened read-only with SQLite's defensive settings on until
+//! the user trusts them.
+
+use std::path::{Path, PathBuf};
+use crate::settings::UserSettings;
+
+/// Largest string or BLOB a quarantined connection will produce
+pub const MAX_VALUE_BYTES: i32 = 16 * 1024 * 1024;
+
+/// Why `path` should open in safe mode, or `None` if it is trusted
+pub fn reason(path: &Path, settings: &UserSettings) -> Option<String> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if settings.trusted_databases.iter().any(|p| *p == canonical) {
+        return None;
+    }
+    if has_download_mark(path) {
+        return Some("the file is marked as downloaded from the internet".to_string());
+    }
+    untrusted_dirs(settings)
+        .into_iter()
+        .find(|dir| canonical.starts_with(dir.canonicalize().unwrap_or_else(|_| dir.clone())))
+        .map(|dir| format!("the file is under {}", dir.display()))
+}
+
+/// The configured untrusted directories, or the user's Downloads folder by default
+fn untrusted_dirs(settings: &UserSettings) -> Vec<PathBuf> {
+    if !settings.untrusted_dirs.is_empty() {
+        return settings.untrusted_dirs.clone();
+    }
+    let home = if cfg!(windows) { std::env::var_os("USERPROFILE") } else { std::env::var_os("HOME") };
+    home.map(|h| vec![PathBuf::from(h).join("Downloads")]).unwrap_or_default()
+}
+
+/// Browsers tag downloads with a `Zone.Identifier` stream on NTFS
+fn has_download_mark(path: &Path) -> bool {
+    if !cfg!(windows) {
+        return false;
+    }
+    let mut stream = path.as_os_str().to_os_string();
+    stream.push(":Zone.Identifier");
+    Path::new(&stream).is_file()
+}
@@ -4,11 +4,51 @@ use ui::{
     input::{TextInput, InputState, TabSize, InputEvent},
     divider::Divider, IconName,
     table::{Table, TableDelegate, Column, ColumnSort, TableEvent},
-    ActiveTheme, Sizable, Size, StyleSized, StyledExt, Disableable,
+    ActiveTheme, Sizable, StyleSized, StyledExt, Disableable,
 };
-use crate::database::{DatabaseManager, CellValue};
+use crate::database::{DatabaseManager, CancelHandle, CellValue, QueryEvent, RunningQuery};
+use crate::export::CsvExportOptions;
+use crate::template::TemplateSource;
+use crate::completion::{complete, current_word, CompletionItem, CompletionKind, CompletionSources};
+use crate::sql_text;
+use crate::macros::MacroSet;
+use crate::project_query::{self, ProjectDatabase};
+use crate::settings::{GridStyle, KeyBindingMode};
+use crate::sql_dump::ScriptReport;
+use crate::usage::UsageCounts;
+use crate::table_view::column_in_view;
+use crate::grouped_results::GroupedQuery;
+use crate::key_bindings::{self, KeyAction, ModalKeys};
+use crate::multi_caret::MultiCaret;
+use serde::{Deserialize, Serialize};
+use crate::text_encoding::Newline;
 use std::time::Instant;
+use futures::StreamExt;
 use std::ops::Range;
+use std::collections::HashMap;
+
+/// Rows kept in memory for the results grid; exports re-stream the full result
+const MAX_RESULT_ROWS: usize = 10_000;
+
+/// Executed queries remembered for completion
+const MAX_RECENT_QUERIES: usize = 50;
+
+/// Rows shown in the inline result popover
+const INLINE_RESULT_ROWS: usize = 20;
+
+/// Rows fetched when a group is expanded in the grouped view
+const GROUP_ROWS: usize = 200;
+
+/// Sidecar holding saved snippets and recent queries, so they survive restarts
+pub const QUERIES_SIDECAR: &str = "queries";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoredQueries {
+    pub saved: Vec<(String, String)>,
+    pub recent: Vec<String>,
+    pub usage: UsageCounts,
+}
 
 pub struct QueryEditor {
     db: DatabaseManager,
@@ -17,10 +57,48 @@ pub struct QueryEditor {
     results_table: Option<Entity<Table<QueryResultsTableView>>>,
     error: Option<String>,
     is_executing: bool,
+    /// Receives a background query's rows; dropping it stops the query
+    query_task: Option<Task<()>>,
+    /// Interrupts the background query while it runs
+    query_cancel: Option<CancelHandle>,
     query_history: Vec<SavedQuery>,
     focus_handle: FocusHandle,
     show_schema_sidebar: bool,
     available_tables: Vec<String>,
+    csv_options: CsvExportOptions,
+    /// Built-in and project export templates, and the one the export button uses
+    templates: Vec<TemplateSource>,
+    template_ix: usize,
+    /// Project-defined macros, expanded before each query runs
+    macros: MacroSet,
+    /// Executed queries, most recent first
+    recent_queries: Vec<String>,
+    inline_result: Option<InlineResult>,
+    /// Text as of the last edit, to tell what was just typed
+    last_query: String,
+    /// Closers inserted automatically that typing the same character should step over
+    auto_closers: usize,
+    grid: GridStyle,
+    /// Other project databases attached to every query; `None` outside project mode
+    project: Option<Vec<ProjectDatabase>>,
+    /// Dump or seed script to run, typed as a path
+    script_path_input: Entity<InputState>,
+    script_report: Option<ScriptReport>,
+    /// Show a GROUP BY result as collapsible groups rather than a flat grid
+    grouped_view: bool,
+    /// Underlying rows of the expanded groups, by result row
+    expanded_groups: HashMap<usize, InlineResult>,
+    /// Vim or Emacs bindings of the query buffer, from the user's settings
+    keys: ModalKeys,
+    /// Extra carets or column selection; typed keys apply at every one
+    carets: Option<MultiCaret>,
+    _subscriptions: Vec<Subscription>,
+}
+
+/// Result of running part of the query, shown in a popover until the next edit
+pub struct InlineResult {
+    pub sql: String,
+    pub outcome: Result<crate::database::QueryRows, String>,
 }
 
 #[derive(Clone, Debug)]
@@ -32,21 +110,28 @@ pub struct SavedQuery {
 
 #[derive(Clone)]
 pub struct QueryResult {
+    pub sql: String,
     pub columns: Vec<String>,
     pub rows: Vec<Vec<CellValue>>,
     pub row_count: usize,
+    pub truncated: bool,
+    /// Stopped by the user before the statement finished
+    pub stopped: bool,
     pub execution_time_ms: u64,
 }
 
 pub struct QueryResultsTableView {
     result: QueryResult,
     columns: Vec<Column>,
-    size: Size,
+    grid: GridStyle,
     visible_range: Range<usize>,
+    visible_columns: Range<usize>,
+    /// Rows are still arriving from a background query
+    loading: bool,
 }
 
 impl QueryResultsTableView {
-    pub fn new(result: QueryResult) -> Self {
+    pub fn new(result: QueryResult, grid: GridStyle) -> Self {
         let mut columns = Vec::new();
 
         // Add columns based on result columns
@@ -68,8 +153,32 @@ impl QueryResultsTableView {
         Self {
             result,
             columns,
-            size: Size::default(),
+            grid,
             visible_range: 0..0,
+            visible_columns: 0..0,
+            loading: false,
+        }
+    }
+
+    pub fn append_rows(&mut self, rows: Vec<Vec<CellValue>>) {
+        self.result.row_count += rows.len();
+        self.result.rows.extend(rows);
+    }
+
+    pub fn finish(&mut self, truncated: bool, execution_time_ms: u64) {
+        self.result.truncated = truncated;
+        self.result.execution_time_ms = execution_time_ms;
+        self.loading = false;
+    }
+}
+
+impl QueryResultsTableView {
+    /// Colour for NULLs and other secondary cell text
+    fn placeholder_color(&self, cx: &App) -> Hsla {
+        if self.grid.high_contrast {
+            cx.theme().foreground
+        } else {
+            cx.theme().muted_foreground
         }
     }
 }
@@ -83,6 +192,10 @@ impl TableDelegate for QueryResultsTableView {
         self.result.rows.len()
     }
 
+    fn loading(&self, _: &App) -> bool {
+        self.loading
+    }
+
     fn column(&self, col_ix: usize, _: &App) -> &Column {
         &self.columns[col_ix]
     }
@@ -91,14 +204,19 @@ impl TableDelegate for QueryResultsTableView {
         &self,
         col_ix: usize,
         _: &mut Window,
-        _: &mut Context<Table<Self>>,
+        cx: &mut Context<Table<Self>>,
     ) -> impl IntoElement {
         if let Some(col) = self.result.columns.get(col_ix) {
             div()
                 .px_3()
-                .py_2()
-                .text_sm()
+                .py(self.grid.cell_padding_y * 2.)
+                .text_size(self.grid.text_size)
                 .font_semibold()
+                .when(self.grid.high_contrast, |this| {
+                    this.text_color(cx.theme().foreground)
+                        .border_b_2()
+                        .border_color(cx.theme().foreground)
+                })
                 .child(col.clone())
         } else {
             div()
@@ -114,7 +232,10 @@ impl TableDelegate for QueryResultsTableView {
         div()
             .id(("result-row", row_ix))
             .when(row_ix % 2 == 1, |this| {
-                this.bg(cx.theme().muted.opacity(0.3))
+                this.bg(cx.theme().muted.opacity(if self.grid.high_contrast { 0.8 } else { 0.3 }))
+            })
+            .when(self.grid.high_contrast, |this| {
+                this.border_b_1().border_color(cx.theme().foreground.opacity(0.5))
             })
     }
 
@@ -125,17 +246,33 @@ impl TableDelegate for QueryResultsTableView {
         _: &mut Window,
         cx: &mut Context<Table<Self>>,
     ) -> impl IntoElement {
+        // The first two columns are pinned and always visible
+        if col_ix >= 2 && !column_in_view(&self.visible_columns, col_ix) {
+            return div().into_any_element();
+        }
+
         if let Some(row) = self.result.rows.get(row_ix) {
             if let Some(cell) = row.get(col_ix) {
+                // Huge values would stall layout, so they only show their size
+                if cell.size() > self.grid.max_cell_bytes {
+                    return div()
+                        .px_3()
+                        .py(self.grid.cell_padding_y * 2.)
+                        .text_size(self.grid.text_size)
+                        .italic()
+                        .text_color(self.placeholder_color(cx))
+                        .child(cell.size_placeholder())
+                        .into_any_element();
+                }
                 let display = cell.display.clone();
 
                 return div()
                     .id(("result-cell", row_ix * 10000 + col_ix))
                     .px_3()
-                    .py_2()
-                    .text_sm()
+                    .py(self.grid.cell_padding_y * 2.)
+                    .text_size(self.grid.text_size)
                     .when(display.is_empty() || display == "NULL", |this| {
-                        this.text_color(cx.theme().muted_foreground)
+                        this.text_color(self.placeholder_color(cx))
                             .italic()
                             .child(if display.is_empty() { "empty" } else { "NULL" })
                     })
@@ -148,9 +285,9 @@ impl TableDelegate for QueryResultsTableView {
 
         div()
             .px_3()
-            .py_2()
-            .text_sm()
-            .text_color(cx.theme().muted_foreground)
+            .py(self.grid.cell_padding_y * 2.)
+            .text_size(self.grid.text_size)
+            .text_color(self.placeholder_color(cx))
             .child("—")
             .into_any_element()
     }
@@ -163,12 +300,30 @@ impl TableDelegate for QueryResultsTableView {
     ) {
         self.visible_range = visible_range;
     }
+
+    fn visible_columns_changed(
+        &mut self,
+        visible_range: Range<usize>,
+        _: &mut Window,
+        _: &mut Context<Table<Self>>,
+    ) {
+        self.visible_columns = visible_range;
+    }
 }
 
 impl QueryEditor {
     pub fn new(db: DatabaseManager, window: &mut Window, cx: &mut Context<Self>) -> Self {
         // Get available tables for schema browser
         let available_tables = db.list_tables().unwrap_or_default();
+        let macros = db
+            .path()
+            .map(|path| {
+                MacroSet::discover(path).unwrap_or_else(|e| {
+                    tracing::error!("Failed to load SQL macros: {}", e);
+                    MacroSet::default()
+                })
+            })
+            .unwrap_or_default();
 
         // Create query input with SQL syntax highlighting
         let query_input = cx.new(|cx| {
@@ -186,6 +341,31 @@ impl QueryEditor {
             state
         });
 
+        // Re-render on every edit so completions follow the text; edits also dismiss inline results
+        let subscriptions = vec![cx.subscribe_in(
+            &query_input,
+            window,
+            |editor: &mut Self, _, event: &InputEvent, window, cx| {
+                if matches!(event, InputEvent::Change { .. }) {
+                    editor.inline_result = None;
+                    // An edit the carets didn't make leaves their offsets meaningless
+                    if editor.carets.is_some() && editor.get_query(cx) != editor.last_query {
+                        editor.carets = None;
+                    }
+                    editor.auto_close(window, cx);
+                    cx.notify();
+                }
+            },
+        )];
+
+        let script_path_input = cx.new(|cx| InputState::new(window, cx));
+
+        let templates = TemplateSource::discover(db.path());
+        let stored: StoredQueries = db
+            .path()
+            .and_then(|path| crate::sidecar::load(path, QUERIES_SIDECAR).ok().flatten())
+            .unwrap_or_default();
+
         Self {
             db,
             query_input,
@@ -193,11 +373,438 @@ impl QueryEditor {
             results_table: None,
             error: None,
             is_executing: false,
-            query_history: Vec::new(),
+            query_task: None,
+            query_cancel: None,
+            query_history: stored
+                .saved
+                .into_iter()
+                .map(|(name, sql)| SavedQuery {
+                    name,
+                    sql,
+                    timestamp: Instant::now(),
+                })
+                .collect(),
             focus_handle: cx.focus_handle(),
             show_schema_sidebar: true,
             available_tables,
+            csv_options: CsvExportOptions::default(),
+            templates,
+            template_ix: 0,
+            macros,
+            recent_queries: stored.recent,
+            inline_result: None,
+            last_query: "SELECT * FROM ".to_string(),
+            auto_closers: 0,
+            grid: GridStyle::default(),
+            project: None,
+            script_path_input,
+            script_report: None,
+            grouped_view: true,
+            expanded_groups: HashMap::new(),
+            keys: ModalKeys::default(),
+            carets: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    /// Restyle the results grid, including the one already showing
+    pub fn set_grid_style(&mut self, grid: GridStyle, cx: &mut Context<Self>) {
+        self.grid = grid;
+        if let Some(table) = &self.results_table {
+            table.update(cx, |table, cx| {
+                table.set_size(grid.size, cx);
+                table.delegate_mut().grid = grid;
+            });
+        }
+        cx.notify();
+    }
+
+    pub fn set_key_bindings(&mut self, mode: KeyBindingMode, cx: &mut Context<Self>) {
+        self.keys.set_mode(mode);
+        cx.notify();
+    }
+
+    /// The input's selection as byte offsets: anchor, then the end the cursor is at
+    fn selection(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<(usize, usize)> {
+        let query = self.get_query(cx);
+        let selection = self
+            .query_input
+            .update(cx, |state, cx| state.selected_text_range(false, window, cx))?;
+        let start = sql_text::utf16_to_byte_offset(&query, selection.range.start);
+        let end = sql_text::utf16_to_byte_offset(&query, selection.range.end);
+        Some(if selection.reversed { (end, start) } else { (start, end) })
+    }
+
+    /// Turn the selection into a rectangle: the same columns on each of its lines
+    pub fn column_select(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some((anchor, head)) = self.selection(window, cx) {
+            self.carets = MultiCaret::column_block(&self.get_query(cx), anchor, head);
+            self.show_primary_caret(window, cx);
+        }
+    }
+
+    /// A caret at the end of each selected line, e.g. to add commas down a list
+    pub fn carets_at_line_ends(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some((anchor, head)) = self.selection(window, cx) {
+            self.carets = MultiCaret::line_ends(&self.get_query(cx), anchor, head);
+            self.show_primary_caret(window, cx);
+        }
+    }
+
+    /// Add a caret on the line below the last one, or above the first
+    pub fn add_caret_line(&mut self, down: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        match &mut self.carets {
+            Some(carets) => carets.add_line(&query, down),
+            None => {
+                let Some((anchor, head)) = self.selection(window, cx) else {
+                    return;
+                };
+                self.carets = MultiCaret::from_selection(&query, anchor.min(head)..anchor.max(head), down);
+            }
+        }
+    }
+
+    /// Also select the next occurrence of the selected text
+    pub fn add_next_occurrence(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        let ranges = match &self.carets {
+            Some(carets) => carets.ranges.clone(),
+            None => match self.selection(window, cx) {
+                Some((anchor, head)) => vec![anchor.min(head)..anchor.max(head)],
+                None => return,
+            },
+        };
+        if let Some(carets) = MultiCaret::add_next_occurrence(&query, &ranges) {
+            self.carets = Some(carets);
+            self.show_primary_caret(window, cx);
+        }
+    }
+
+    /// Leave the input's own cursor on the last caret, where typing is seen
+    fn show_primary_caret(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(carets) = &self.carets else {
+            return;
+        };
+        let query = self.get_query(cx);
+        let at = sql_text::byte_to_utf16_offset(&query, carets.primary());
+        self.query_input.update(cx, |state, cx| {
+            state.replace_and_mark_text_in_range(Some(at..at), "", Some(0..0), window, cx);
+            state.unmark_text(window, cx);
+        });
+    }
+
+    /// Keys that add carets, and typing while there are several; false leaves the key to the input
+    fn handle_caret_key(&mut self, keystroke: &Keystroke, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let modifiers = &keystroke.modifiers;
+        let key = keystroke.key.as_str();
+        if modifiers.control && modifiers.alt && (key == "up" || key == "down") {
+            self.add_caret_line(key == "down", window, cx);
+            self.show_primary_caret(window, cx);
+            return true;
+        }
+        if modifiers.shift && modifiers.alt && key == "i" {
+            self.carets_at_line_ends(window, cx);
+            return true;
+        }
+        // Ctrl+D is delete-char under the emacs bindings
+        if modifiers.control && !modifiers.alt && key == "d" && self.keys.mode != KeyBindingMode::Emacs {
+            self.add_next_occurrence(window, cx);
+            return true;
+        }
+
+        let Some(carets) = self.carets.clone() else {
+            return false;
+        };
+        let query = self.get_query(cx);
+        let shortcut = modifiers.control || modifiers.platform;
+        let (text, next) = match key {
+            "escape" => {
+                self.carets = None;
+                return true;
+            }
+            "v" if shortcut => {
+                let pasted = cx.read_from_clipboard().and_then(|item| item.text()).unwrap_or_default();
+                let lines: Vec<&str> = pasted.lines().collect();
+                // One line per caret spreads the lines out, like pasting a column
+                if lines.len() == carets.ranges.len() {
+                    carets.insert_each(&query, &lines)
+                } else {
+                    carets.insert(&query, &pasted)
+                }
+            }
+            _ if shortcut || modifiers.alt => {
+                self.carets = None;
+                return false;
+            }
+            "backspace" => carets.backspace(&query),
+            "delete" => carets.delete(&query),
+            "enter" => carets.insert(&query, "\n"),
+            "tab" => carets.insert(&query, "  "),
+            "left" | "right" | "home" | "end" => {
+                let motion: fn(&str, usize) -> usize = match key {
+                    "left" => key_bindings::prev,
+                    "right" => key_bindings::next,
+                    "home" => key_bindings::line_start,
+                    _ => key_bindings::line_end,
+                };
+                self.carets = carets.moved(&query, motion);
+                self.show_primary_caret(window, cx);
+                return true;
+            }
+            _ => match keystroke.key_char.as_deref() {
+                Some(typed) => carets.insert(&query, typed),
+                None => {
+                    self.carets = None;
+                    return false;
+                }
+            },
+        };
+
+        let caret = next.as_ref().map_or(text.len(), MultiCaret::primary);
+        let end = sql_text::byte_to_utf16_offset(&query, query.len());
+        let caret = text[..caret].encode_utf16().count();
+        self.query_input.update(cx, |state, cx| {
+            state.replace_and_mark_text_in_range(Some(0..end), &text, Some(caret..caret), window, cx);
+            state.unmark_text(window, cx);
+        });
+        self.last_query = text;
+        self.carets = next;
+        true
+    }
+
+    /// Apply a vim or emacs binding to the query; false when the input should handle the key
+    fn handle_modal_key(&mut self, keystroke: &Keystroke, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        if self.keys.mode == KeyBindingMode::Standard {
+            return false;
+        }
+        let query = self.get_query(cx);
+        let cursor = self
+            .query_input
+            .update(cx, |state, cx| state.selected_text_range(false, window, cx))
+            .map(|selection| {
+                let at = if selection.reversed { selection.range.start } else { selection.range.end };
+                sql_text::utf16_to_byte_offset(&query, at)
+            })
+            .unwrap_or(query.len());
+
+        let (range, text, cursor) = match self.keys.handle(keystroke, &query, cursor) {
+            KeyAction::Pass => return false,
+            KeyAction::Consume => return true,
+            KeyAction::MoveTo(at) => (at..at, String::new(), at),
+            KeyAction::Edit { range, text, cursor } => (range, text, cursor),
+        };
+        let start = sql_text::byte_to_utf16_offset(&query, range.start);
+        let end = sql_text::byte_to_utf16_offset(&query, range.end);
+        let caret = text[..cursor - range.start].encode_utf16().count();
+        self.query_input.update(cx, |state, cx| {
+            // Marking the replacement is what places the cursor inside it
+            state.replace_and_mark_text_in_range(Some(start..end), &text, Some(caret..caret), window, cx);
+            state.unmark_text(window, cx);
+        });
+        // Not typing, so auto-closing shouldn't react to it
+        self.last_query = self.get_query(cx);
+        true
+    }
+
+    /// Save the history, counting `executed` toward table usage
+    fn persist_queries(&self, executed: Option<&str>) {
+        let Some(path) = self.db.path() else {
+            return;
+        };
+        // Usage accumulates across every query tab, so it's re-read rather than held here
+        let mut usage = crate::sidecar::load::<StoredQueries>(path, QUERIES_SIDECAR)
+            .ok()
+            .flatten()
+            .map(|stored| stored.usage)
+            .unwrap_or_default();
+        if let Some(sql) = executed {
+            usage.record(sql, &self.usage_schema());
+        }
+
+        let stored = StoredQueries {
+            saved: self
+                .query_history
+                .iter()
+                .map(|q| (q.name.clone(), q.sql.clone()))
+                .collect(),
+            recent: self.recent_queries.clone(),
+            usage,
+        };
+        if let Err(e) = crate::sidecar::save(path, QUERIES_SIDECAR, &stored) {
+            tracing::error!("Failed to save query history: {}", e);
+        }
+    }
+
+    fn record_recent(&mut self, sql: &str) {
+        let sql = sql.trim();
+        if sql.is_empty() {
+            return;
+        }
+        self.recent_queries.retain(|q| q != sql);
+        self.recent_queries.insert(0, sql.to_string());
+        self.recent_queries.truncate(MAX_RECENT_QUERIES);
+    }
+
+    /// Tables with their registered columns, for counting usage
+    fn usage_schema(&self) -> Vec<(String, Vec<String>)> {
+        self.available_tables
+            .iter()
+            .map(|table| {
+                let columns = self
+                    .db
+                    .get_schema(table)
+                    .map(|schema| schema.fields.into_iter().map(|f| f.name).collect())
+                    .unwrap_or_default();
+                (table.clone(), columns)
+            })
+            .collect()
+    }
+
+    /// Completions for the word at the end of the query
+    pub fn completions(&self, cx: &App) -> Vec<CompletionItem> {
+        let query = self.get_query(cx);
+        let snippets: Vec<(String, String)> = self
+            .query_history
+            .iter()
+            .map(|q| (q.name.clone(), q.sql.clone()))
+            .collect();
+        let columns: Vec<String> = self
+            .available_tables
+            .iter()
+            .filter_map(|t| self.db.get_schema(t))
+            .flat_map(|schema| schema.fields.into_iter().map(|f| f.name))
+            .collect();
+
+        complete(
+            current_word(&query),
+            &CompletionSources {
+                tables: &self.available_tables,
+                columns: &columns,
+                snippets: &snippets,
+                history: &self.recent_queries,
+            },
+        )
+    }
+
+    /// Replace the word being typed with the completion's text. Table and column names
+    /// that aren't plain identifiers are quoted.
+    pub fn apply_completion(&mut self, item: &CompletionItem, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        let prefix_start = query.len() - current_word(&query).len();
+        let insert_text = match item.kind {
+            CompletionKind::Table | CompletionKind::Column => sql_text::quote_if_needed(&item.insert_text),
+            _ => item.insert_text.clone(),
+        };
+        let new_value = format!("{}{}", &query[..prefix_start], insert_text);
+        self.set_query(new_value, window, cx);
+    }
+
+    /// Pair brackets and quotes as they're typed, and step over an auto-inserted closer
+    /// when the same character is typed again
+    fn auto_close(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        let previous = std::mem::replace(&mut self.last_query, query.clone());
+        let Some((offset, typed)) = sql_text::single_insertion(&previous, &query) else {
+            return;
+        };
+        let after = offset + typed.len_utf8();
+        let next = query[after..].chars().next();
+        let at = sql_text::byte_to_utf16_offset(&query, after);
+
+        if self.auto_closers > 0 && next == Some(typed) && matches!(typed, ')' | ']' | '\'' | '"') {
+            self.auto_closers -= 1;
+            self.query_input.update(cx, |state, cx| {
+                state.replace_text_in_range(Some(at..at + 1), "", window, cx);
+            });
+        } else if let Some(closer) = sql_text::closing_pair(typed) {
+            let previous_char = query[..offset].chars().next_back();
+            let touches_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            // An apostrophe inside a word, or a bracket right before one, isn't opening a pair
+            if touches_word(next) || (typed == '\'' && touches_word(previous_char)) {
+                return;
+            }
+
+            self.auto_closers += 1;
+            self.query_input.update(cx, |state, cx| {
+                // Marking the insertion lets the cursor stay before the closer
+                state.replace_and_mark_text_in_range(Some(at..at), &closer.to_string(), Some(0..0), window, cx);
+                state.unmark_text(window, cx);
+            });
+        } else {
+            return;
+        }
+
+        self.last_query = self.get_query(cx);
+    }
+
+    /// Wrap the selected text in identifier quotes
+    pub fn quote_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        let Some(selection) = self
+            .query_input
+            .update(cx, |state, cx| state.selected_text_range(false, window, cx))
+        else {
+            return;
+        };
+
+        let start = sql_text::utf16_to_byte_offset(&query, selection.range.start);
+        let end = sql_text::utf16_to_byte_offset(&query, selection.range.end);
+        let selected = query[start..end].trim();
+        if selected.is_empty() || (selected.starts_with('"') && selected.ends_with('"')) {
+            return;
+        }
+
+        let quoted = sql_text::quote_identifier(selected);
+        self.query_input.update(cx, |state, cx| {
+            state.replace_text_in_range(Some(selection.range.clone()), &quoted, window, cx);
+        });
+    }
+
+    /// Attach every other database in the project to queries, or go back to this one alone
+    pub fn toggle_project_mode(&mut self) -> anyhow::Result<()> {
+        if self.project.take().is_some() {
+            return Ok(());
+        }
+        let path = self
+            .db
+            .path()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases aren't part of a project"))?;
+        let databases = project_query::discover(path)?;
+        tracing::info!("✓ Attaching {} project databases to queries", databases.len());
+        self.project = Some(databases);
+        Ok(())
+    }
+
+    /// Replace the query with a UNION of the selected table across project databases
+    pub fn union_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(databases) = self.project.clone() else {
+            return Ok(());
+        };
+        let query = self.get_query(cx);
+        let selection = self
+            .query_input
+            .update(cx, |state, cx| state.selected_text_range(false, window, cx));
+        let table = selection
+            .map(|selection| {
+                let start = sql_text::utf16_to_byte_offset(&query, selection.range.start);
+                let end = sql_text::utf16_to_byte_offset(&query, selection.range.end);
+                query[start..end].trim().trim_matches('"').to_string()
+            })
+            .unwrap_or_default();
+        if table.is_empty() {
+            self.error = Some("Select a table name to combine across project databases".to_string());
+            return Ok(());
+        }
+
+        match project_query::union_query(&self.db, &databases, &table)? {
+            Some(sql) => {
+                self.error = None;
+                self.set_query(sql, window, cx);
+            }
+            None => self.error = Some(format!("No project database has a table named {}", table)),
         }
+        Ok(())
     }
 
     pub fn set_query(&mut self, query: String, window: &mut Window, cx: &mut Context<Self>) {
@@ -221,6 +828,7 @@ impl QueryEditor {
             sql,
             timestamp: Instant::now(),
         });
+        self.persist_queries(None);
     }
 
     pub fn load_query(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
@@ -240,34 +848,64 @@ impl QueryEditor {
     pub fn execute_query(&mut self, window: &mut Window, cx: &mut Context<Self>) -> anyhow::Result<()> {
         self.is_executing = true;
         self.error = None;
+        self.expanded_groups.clear();
 
         let start = std::time::Instant::now();
-        let query = self.get_query(cx);
+        let typed = self.get_query(cx);
+        self.record_recent(&typed);
+        let query = match self.macros.expand(&typed) {
+            Ok(query) => query,
+            Err(e) => {
+                self.persist_queries(None);
+                self.error = Some(format!("Macro error: {}", e));
+                self.is_executing = false;
+                return Ok(());
+            }
+        };
+        self.persist_queries(Some(&query));
 
-        match self.db.execute_query(&query) {
-            Ok(rows) => {
-                let execution_time_ms = start.elapsed().as_millis() as u64;
-                let row_count = rows.len();
+        // Attached project databases live on this connection, so only plain reads move off it
+        if self.project.is_none() {
+            match self.db.spawn_query(&query, MAX_RESULT_ROWS) {
+                Ok(Some(running)) => {
+                    self.watch_query(query, running, start, window, cx);
+                    return Ok(());
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.error = Some(format!("Query error: {}", e));
+                    self.results = None;
+                    self.results_table = None;
+                    self.is_executing = false;
+                    return Ok(());
+                }
+            }
+        }
 
-                let columns = if !rows.is_empty() && !rows[0].is_empty() {
-                    (0..rows[0].len())
-                        .map(|i| format!("Column {}", i + 1))
-                        .collect()
-                } else {
-                    Vec::new()
-                };
+        let output = match &self.project {
+            Some(databases) => project_query::run(&self.db, databases, &query, MAX_RESULT_ROWS),
+            None => self.db.fetch_query(&query, MAX_RESULT_ROWS),
+        };
+        match output {
+            Ok(output) => {
+                let execution_time_ms = start.elapsed().as_millis() as u64;
+                let row_count = output.rows.len();
 
                 let result = QueryResult {
-                    columns,
-                    rows,
+                    sql: query,
+                    columns: output.columns,
+                    rows: output.rows,
                     row_count,
+                    truncated: output.truncated,
+                    stopped: false,
                     execution_time_ms,
                 };
 
                 // Create virtualized table for results
-                let table_view = QueryResultsTableView::new(result.clone());
+                let table_view = QueryResultsTableView::new(result.clone(), self.grid);
                 let results_table = cx.new(|cx| {
                     let mut table = Table::new(table_view, window, cx);
+                    table.set_size(self.grid.size, cx);
                     table.col_fixed = true;
                     table.col_resizable = true;
                     table.sortable = true;
@@ -276,6 +914,8 @@ impl QueryEditor {
 
                 self.results = Some(result);
                 self.results_table = Some(results_table);
+                // The query may have written
+                crate::provider::notify(&self.db);
             }
             Err(e) => {
                 self.error = Some(format!("Query error: {}", e));
@@ -288,14 +928,277 @@ impl QueryEditor {
         Ok(())
     }
 
+    /// Show a background query's rows as its worker sends them
+    fn watch_query(
+        &mut self,
+        sql: String,
+        running: RunningQuery,
+        start: Instant,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.results = Some(QueryResult {
+            sql,
+            columns: Vec::new(),
+            rows: Vec::new(),
+            row_count: 0,
+            truncated: false,
+            stopped: false,
+            execution_time_ms: 0,
+        });
+        self.results_table = None;
+        self.query_cancel = Some(running.cancel);
+        let mut events = running.events;
+        self.query_task = Some(cx.spawn_in(window, async move |editor, cx| {
+            while let Some(event) = events.next().await {
+                let finished = !matches!(event, QueryEvent::Columns(_) | QueryEvent::Rows(_));
+                let applied = editor.update_in(cx, |editor, window, cx| {
+                    editor.apply_query_event(event, start, window, cx);
+                    cx.notify();
+                });
+                if applied.is_err() || finished {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Abort the running background query, keeping the rows it has returned so far
+    pub fn stop_query(&mut self) {
+        if let Some(cancel) = &self.query_cancel {
+            cancel.cancel();
+            tracing::info!("Stopping query");
+        }
+    }
+
+    fn apply_query_event(&mut self, event: QueryEvent, start: Instant, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(result) = self.results.as_mut() else {
+            return;
+        };
+        if !matches!(event, QueryEvent::Columns(_) | QueryEvent::Rows(_)) {
+            self.query_cancel = None;
+            self.is_executing = false;
+        }
+
+        match event {
+            QueryEvent::Columns(columns) => {
+                result.columns = columns;
+                let mut table_view = QueryResultsTableView::new(result.clone(), self.grid);
+                table_view.loading = true;
+                let grid = self.grid;
+                self.results_table = Some(cx.new(|cx| {
+                    let mut table = Table::new(table_view, window, cx);
+                    table.set_size(grid.size, cx);
+                    table.col_fixed = true;
+                    table.col_resizable = true;
+                    table.sortable = true;
+                    table
+                }));
+            }
+            QueryEvent::Rows(rows) => {
+                result.row_count += rows.len();
+                result.rows.extend(rows.iter().cloned());
+                if let Some(table) = &self.results_table {
+                    table.update(cx, |table, cx| {
+                        table.delegate_mut().append_rows(rows);
+                        cx.notify();
+                    });
+                }
+            }
+            QueryEvent::Done { .. } | QueryEvent::Interrupted => {
+                result.truncated = matches!(event, QueryEvent::Done { truncated: true });
+                result.stopped = matches!(event, QueryEvent::Interrupted);
+                result.execution_time_ms = start.elapsed().as_millis() as u64;
+                let (truncated, execution_time_ms) = (result.truncated, result.execution_time_ms);
+                if let Some(table) = &self.results_table {
+                    table.update(cx, |table, cx| {
+                        table.delegate_mut().finish(truncated, execution_time_ms);
+                        cx.notify();
+                    });
+                }
+                if result.stopped {
+                    tracing::info!("Query stopped after {} rows in {} ms", result.row_count, execution_time_ms);
+                } else {
+                    tracing::debug!("Background query returned {} rows in {} ms", result.row_count, execution_time_ms);
+                }
+            }
+            QueryEvent::Failed(e) => {
+                self.error = Some(format!("Query error: {}", e));
+                self.results = None;
+                self.results_table = None;
+            }
+        }
+    }
+
+    /// Run the selected text, or the subquery under the cursor, into the inline popover
+    pub fn run_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.get_query(cx);
+        let Some(selection) = self
+            .query_input
+            .update(cx, |state, cx| state.selected_text_range(false, window, cx))
+        else {
+            return;
+        };
+
+        let start = sql_text::utf16_to_byte_offset(&query, selection.range.start);
+        let end = sql_text::utf16_to_byte_offset(&query, selection.range.end);
+        let sql = if start < end {
+            query[start..end].trim()
+        } else {
+            match sql_text::subquery_at(&query, start) {
+                Some(sql) => sql,
+                None => return,
+            }
+        };
+
+        let outcome = self
+            .macros
+            .expand(sql)
+            .and_then(|expanded| match &self.project {
+                Some(databases) => project_query::run(&self.db, databases, &expanded, INLINE_RESULT_ROWS),
+                None => self.db.fetch_query(&expanded, INLINE_RESULT_ROWS),
+            })
+            .map_err(|e| e.to_string());
+        self.inline_result = Some(InlineResult {
+            sql: sql.to_string(),
+            outcome,
+        });
+    }
+
+    fn render_inline_result(&self, result: &InlineResult, cx: &mut Context<QueryEditor>) -> impl IntoElement {
+        let body = match &result.outcome {
+            Err(e) => div()
+                .text_xs()
+                .text_color(cx.theme().red)
+                .child(e.clone())
+                .into_any_element(),
+            Ok(rows) => v_flex()
+                .gap_px()
+                .text_xs()
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .font_semibold()
+                        .children(rows.columns.iter().map(|c| div().w_24().truncate().child(c.clone())))
+                )
+                .children(rows.rows.iter().map(|row| {
+                    h_flex()
+                        .gap_2()
+                        .children(row.iter().map(|cell| div().w_24().truncate().child(cell.display.clone())))
+                }))
+                .when(rows.truncated, |this| {
+                    this.child(
+                        div()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("first {} rows", INLINE_RESULT_ROWS))
+                    )
+                })
+                .into_any_element(),
+        };
+
+        v_flex()
+            .absolute()
+            .top_8()
+            .right_2()
+            .w(px(520.))
+            .max_h(px(280.))
+            .overflow_hidden()
+            .gap_2()
+            .p_2()
+            .bg(cx.theme().background)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded_md()
+            .shadow_lg()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .flex_1()
+                            .truncate()
+                            .text_xs()
+                            .font_family("monospace")
+                            .text_color(cx.theme().muted_foreground)
+                            .child(result.sql.clone())
+                    )
+                    .child(
+                        Button::new("close-inline-result")
+                            .icon(IconName::Close)
+                            .tooltip("Close result")
+                            .ghost()
+                            .xsmall()
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                editor.inline_result = None;
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(body)
+    }
+
     pub fn clear_results(&mut self) {
+        self.stop_query();
+        self.query_task = None;
+        self.query_cancel = None;
+        self.is_executing = false;
         self.results = None;
         self.results_table = None;
         self.error = None;
+        self.expanded_groups.clear();
+    }
+
+    /// The finished result read as a GROUP BY query, when it is one
+    fn grouping(&self) -> Option<GroupedQuery> {
+        let result = self.results.as_ref().filter(|_| !self.is_executing)?;
+        GroupedQuery::parse(&result.sql, &result.columns)
+    }
+
+    /// Expand a group, fetching the rows it aggregates, or collapse it again
+    pub fn toggle_group(&mut self, row_ix: usize) {
+        if self.expanded_groups.remove(&row_ix).is_some() {
+            return;
+        }
+        let (Some(grouping), Some(row)) = (self.grouping(), self.results.as_ref().and_then(|r| r.rows.get(row_ix))) else {
+            return;
+        };
+        let sql = grouping.drill_down(row);
+        let outcome = match &self.project {
+            Some(databases) => project_query::run(&self.db, databases, &sql, GROUP_ROWS),
+            None => self.db.fetch_query(&sql, GROUP_ROWS),
+        };
+        if let Err(e) = &outcome {
+            tracing::error!("Failed to fetch group rows: {}", e);
+        }
+        self.expanded_groups.insert(row_ix, InlineResult {
+            sql,
+            outcome: outcome.map_err(|e| e.to_string()),
+        });
     }
 
     pub fn render_query_input(&self, cx: &mut Context<QueryEditor>) -> impl IntoElement {
+        let completions = self.completions(cx);
+
+        // Macro calls are listed under the editor with what they expand to
+        let query = self.get_query(cx);
+        let macro_calls: Vec<(String, String)> = self
+            .macros
+            .calls(&query)
+            .into_iter()
+            .map(|call| {
+                let text = query[call.range.clone()].to_string();
+                let expansion = match self.macros.expand(&text) {
+                    Ok(sql) if sql.len() > 80 => format!("→ {}…", sql.chars().take(80).collect::<String>()),
+                    Ok(sql) => format!("→ {}", sql),
+                    Err(e) => e.to_string(),
+                };
+                (text, expansion)
+            })
+            .collect();
+
         v_flex()
+            .relative()
             .size_full()
             .gap_2()
             .child(
@@ -310,6 +1213,31 @@ impl QueryEditor {
                     .child(
                         h_flex()
                             .gap_2()
+                            .when_some(self.carets.as_ref(), |this, carets| {
+                                let query = self.get_query(cx);
+                                let first = sql_text::line_number(&query, carets.ranges[0].start);
+                                let last = sql_text::line_number(&query, carets.primary());
+                                this.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(cx.theme().accent_foreground)
+                                        .child(format!(
+                                            "{} carets on lines {}–{} · Esc to leave",
+                                            carets.ranges.len(),
+                                            first,
+                                            last
+                                        ))
+                                )
+                            })
+                            .when_some(self.keys.status(), |this, status| {
+                                this.child(
+                                    div()
+                                        .text_xs()
+                                        .font_family("monospace")
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(status)
+                                )
+                            })
                             .child(
                                 Button::new("toggle-schema")
                                     .icon(if self.show_schema_sidebar { IconName::PanelLeft } else { IconName::PanelRight })
@@ -332,6 +1260,21 @@ impl QueryEditor {
                     .border_color(cx.theme().border)
                     .rounded_md()
                     .overflow_hidden()
+                    // Clicking places the one cursor the input has
+                    .capture_any_mouse_down(cx.listener(|editor, _: &MouseDownEvent, _, cx| {
+                        if editor.carets.take().is_some() {
+                            cx.notify();
+                        }
+                    }))
+                    // Ahead of the input, so bound keys never reach it
+                    .capture_key_down(cx.listener(|editor, event: &KeyDownEvent, window, cx| {
+                        if editor.handle_caret_key(&event.keystroke, window, cx)
+                            || editor.handle_modal_key(&event.keystroke, window, cx)
+                        {
+                            cx.stop_propagation();
+                            cx.notify();
+                        }
+                    }))
                     .child(
                         TextInput::new(&self.query_input)
                             .size_full()
@@ -347,6 +1290,52 @@ impl QueryEditor {
                             .border_0()
                     )
             )
+            .when_some(self.inline_result.as_ref(), |this, result| {
+                this.child(self.render_inline_result(result, cx))
+            })
+            .when(!completions.is_empty(), |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .flex_wrap()
+                        .children(completions.into_iter().enumerate().map(|(idx, item)| {
+                            let label = format!("{} {}", item.kind.badge(), item.label);
+                            div()
+                                .id(("completion", idx))
+                                .px_2()
+                                .py_0p5()
+                                .rounded_md()
+                                .text_xs()
+                                .cursor_pointer()
+                                .border_1()
+                                .border_color(cx.theme().border)
+                                .hover(|this| this.bg(cx.theme().muted))
+                                .on_click(cx.listener(move |editor, _, window, cx| {
+                                    editor.apply_completion(&item, window, cx);
+                                    cx.notify();
+                                }))
+                                .child(label)
+                        }))
+                )
+            })
+            .when(!macro_calls.is_empty(), |this| {
+                this.child(
+                    h_flex()
+                        .gap_2()
+                        .flex_wrap()
+                        .children(macro_calls.into_iter().map(|(call, expansion)| {
+                            h_flex()
+                                .gap_1()
+                                .px_2()
+                                .py_0p5()
+                                .rounded_md()
+                                .bg(cx.theme().accent.opacity(0.15))
+                                .text_xs()
+                                .child(div().font_semibold().text_color(cx.theme().accent_foreground).child(call))
+                                .child(div().text_color(cx.theme().muted_foreground).child(expansion))
+                        }))
+                )
+            })
     }
 
     pub fn render_controls(&self, cx: &Context<QueryEditor>) -> impl IntoElement {
@@ -363,6 +1352,7 @@ impl QueryEditor {
                     .label(if self.is_executing { "Executing..." } else { "Execute" })
                     .tooltip("Execute Query (F5 or Ctrl+Enter)")
                     .disabled(self.is_executing)
+                    .loading(self.is_executing)
                     .primary()
                     .small()
                     .on_click(cx.listener(|editor, _, window, cx| {
@@ -372,6 +1362,99 @@ impl QueryEditor {
                         cx.notify();
                     }))
             )
+            .child(
+                Button::new("stop-query")
+                    .icon(IconName::Close)
+                    .label("Stop")
+                    .tooltip("Abort the running query, keeping the rows returned so far")
+                    .outline()
+                    .small()
+                    .disabled(self.query_cancel.is_none())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.stop_query();
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("run-selection")
+                    .icon(IconName::ArrowRight)
+                    .label("Run Selection")
+                    .tooltip("Run the selected text, or the subquery under the cursor, in a popover")
+                    .outline()
+                    .small()
+                    .on_click(cx.listener(|editor, _, window, cx| {
+                        editor.run_selection(window, cx);
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("quote-identifier")
+                    .label("Quote")
+                    .tooltip("Quote the selected identifier")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, window, cx| {
+                        editor.quote_selection(window, cx);
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("column-select")
+                    .label("Column Select")
+                    .tooltip("Edit the selected columns of every selected line at once; Ctrl+Alt+Up/Down adds carets, Ctrl+D the next match")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, window, cx| {
+                        editor.column_select(window, cx);
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("line-end-carets")
+                    .label("Line Ends")
+                    .tooltip("A caret at the end of every selected line (Shift+Alt+I)")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, window, cx| {
+                        editor.carets_at_line_ends(window, cx);
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("project-mode")
+                    .label(match &self.project {
+                        Some(databases) => format!("Project ({})", databases.len()),
+                        None => "Project".to_string(),
+                    })
+                    .tooltip("Attach every other database in the project, named after its file (e.g. region_eu.items)")
+                    .when(self.project.is_some(), |b| b.primary())
+                    .when(self.project.is_none(), |b| b.ghost())
+                    .small()
+                    .disabled(self.db.path().is_none())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Err(e) = editor.toggle_project_mode() {
+                            tracing::error!("Failed to find project databases: {}", e);
+                            editor.error = Some(e.to_string());
+                        }
+                        cx.notify();
+                    }))
+            )
+            .when(self.project.is_some(), |this| {
+                this.child(
+                    Button::new("union-across-project")
+                        .label("Union")
+                        .tooltip("Combine the selected table from every project database, tagging rows with their database")
+                        .ghost()
+                        .small()
+                        .on_click(cx.listener(|editor, _, window, cx| {
+                            if let Err(e) = editor.union_selection(window, cx) {
+                                tracing::error!("Failed to build union query: {}", e);
+                                editor.error = Some(e.to_string());
+                            }
+                            cx.notify();
+                        }))
+                )
+            })
             .child(
                 Button::new("clear")
                     .icon(IconName::Close)
@@ -415,6 +1498,89 @@ impl QueryEditor {
                         cx.notify();
                     }))
             )
+            .child(
+                Button::new("csv-encoding")
+                    .label(self.csv_options.encoding.label())
+                    .tooltip("CSV Text Encoding")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.encoding = editor.csv_options.encoding.next();
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-bom")
+                    .label(if self.csv_options.write_bom { "BOM" } else { "No BOM" })
+                    .tooltip("Write a Byte Order Mark")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.write_bom = !editor.csv_options.write_bom;
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-newline")
+                    .label(match self.csv_options.newline {
+                        Newline::Lf => "LF",
+                        Newline::CrLf => "CRLF",
+                    })
+                    .tooltip("CSV Line Endings")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.newline = match editor.csv_options.newline {
+                            Newline::Lf => Newline::CrLf,
+                            Newline::CrLf => Newline::Lf,
+                        };
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-delimiter")
+                    .label(self.csv_options.delimiter_label())
+                    .tooltip("CSV Delimiter; Tab writes TSV")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.next_delimiter();
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-quoting")
+                    .label(self.csv_options.quoting.label())
+                    .tooltip("CSV Quoting")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.quoting = editor.csv_options.quoting.next();
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-header")
+                    .label(if self.csv_options.header { "Header" } else { "No header" })
+                    .tooltip("Write a Header Row of Column Names")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.header = !editor.csv_options.header;
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("csv-null")
+                    .label(self.csv_options.null_label())
+                    .tooltip("How NULL Cells Are Written")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.csv_options.next_null();
+                        cx.notify();
+                    }))
+            )
             .child(
                 Button::new("export-json")
                     .icon(IconName::Download)
@@ -432,6 +1598,125 @@ impl QueryEditor {
                         cx.notify();
                     }))
             )
+            .child(
+                Button::new("export-ndjson")
+                    .icon(IconName::Download)
+                    .label("Export NDJSON")
+                    .tooltip("Export Results as One JSON Object per Line")
+                    .outline()
+                    .small()
+                    .disabled(self.results.is_none())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Some(ref results) = editor.results {
+                            if let Err(e) = editor.export_to_ndjson(results) {
+                                tracing::error!("Failed to export NDJSON: {}", e);
+                            }
+                        }
+                        cx.notify();
+                    }))
+            )
+            .when(cfg!(feature = "parquet"), |bar| {
+                bar.child(
+                    Button::new("export-parquet")
+                        .icon(IconName::Download)
+                        .label("Export Parquet")
+                        .tooltip("Export Results as a Parquet File for Analytics Tools")
+                        .outline()
+                        .small()
+                        .disabled(self.results.is_none())
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            if let Some(ref results) = editor.results {
+                                if let Err(e) = editor.export_to_parquet(results) {
+                                    tracing::error!("Failed to export Parquet: {}", e);
+                                }
+                            }
+                            cx.notify();
+                        }))
+                )
+            })
+            .child(
+                Button::new("cycle-template")
+                    .label(
+                        self.templates
+                            .get(self.template_ix)
+                            .map(|t| t.name.clone())
+                            .unwrap_or_default()
+                    )
+                    .tooltip("Template for Export Template; add your own to export_templates/<name>.<ext>.tmpl")
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        editor.template_ix = (editor.template_ix + 1) % editor.templates.len().max(1);
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("export-template")
+                    .icon(IconName::Download)
+                    .label("Export Template")
+                    .tooltip("Export Results Through the Selected Template")
+                    .outline()
+                    .small()
+                    .disabled(self.results.is_none())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Some(ref results) = editor.results {
+                            if let Err(e) = editor.export_to_template(results) {
+                                tracing::error!("Failed to export through template: {}", e);
+                                editor.error = Some(format!("Template error: {}", e));
+                            }
+                        }
+                        cx.notify();
+                    }))
+            )
+            .child(
+                Button::new("export-report")
+                    .icon(IconName::Download)
+                    .label("Report")
+                    .tooltip("Write a Printable HTML Report")
+                    .outline()
+                    .small()
+                    .disabled(self.results.is_none())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Some(ref results) = editor.results {
+                            if let Err(e) = editor.export_to_report(results) {
+                                tracing::error!("Failed to write report: {}", e);
+                            }
+                        }
+                        cx.notify();
+                    }))
+            )
+            .child(Divider::vertical().h_6())
+            .child(TextInput::new(&self.script_path_input).w_48().text_xs())
+            .child(
+                Button::new("run-script-file")
+                    .icon(IconName::ArrowRight)
+                    .label("Run Script File")
+                    .tooltip("Run a dump or seed .sql file in one transaction, reporting every failing statement")
+                    .outline()
+                    .small()
+                    .disabled(self.is_executing || self.db.is_read_only())
+                    .on_click(cx.listener(|editor, _, _, cx| {
+                        if let Err(e) = editor.run_script_file(cx) {
+                            tracing::error!("Failed to run script file: {}", e);
+                            editor.error = Some(e.to_string());
+                        }
+                        cx.notify();
+                    }))
+            )
+            .when(self.grouping().is_some(), |this| {
+                this.child(
+                    Button::new("grouped-view")
+                        .label(if self.grouped_view { "Grouped" } else { "Flat" })
+                        .tooltip("Show the GROUP BY result as collapsible groups that expand to the rows behind them")
+                        .when(self.grouped_view, |b| b.primary())
+                        .when(!self.grouped_view, |b| b.ghost())
+                        .small()
+                        .on_click(cx.listener(|editor, _, _, cx| {
+                            editor.grouped_view = !editor.grouped_view;
+                            cx.notify();
+                        }))
+                )
+            })
             .when(self.results.is_some(), |this| {
                 let result = self.results.as_ref().unwrap();
                 this.child(Divider::vertical().h_6())
@@ -439,73 +1724,117 @@ impl QueryEditor {
                         div()
                             .text_sm()
                             .text_color(cx.theme().muted_foreground)
-                            .child(format!(
-                                "📊 {} rows in {} ms",
-                                result.row_count, result.execution_time_ms
-                            ))
+                            .child(if self.is_executing {
+                                format!("⏳ {} rows so far…", result.row_count)
+                            } else if result.stopped {
+                                format!("⏹ Stopped after {} rows in {} ms", result.row_count, result.execution_time_ms)
+                            } else {
+                                format!(
+                                    "📊 {}{} rows in {} ms",
+                                    if result.truncated { "first " } else { "" },
+                                    result.row_count,
+                                    result.execution_time_ms
+                                )
+                            })
                     )
             })
     }
 
     pub fn export_to_csv(&self, results: &QueryResult) -> anyhow::Result<()> {
-        use std::fs::File;
-        use std::io::Write;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let extension = crate::export::ExportFormat::Csv(self.csv_options.clone()).extension();
+        let filename = self.db.output_path(&format!("query_results_{}.{}", timestamp, extension));
+
+        // Re-run the query so the export isn't limited to the rows held by the grid
+        let count = crate::export::export_query_to_csv(
+            &self.db,
+            &results.sql,
+            &filename,
+            &self.csv_options,
+        )?;
 
+        tracing::debug!("✓ Exported {} rows to {}", count, filename.display());
+        Ok(())
+    }
+
+    pub fn export_to_report(&self, results: &QueryResult) -> anyhow::Result<()> {
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs();
-        let filename = format!("query_results_{}.csv", timestamp);
-
-        let mut file = File::create(&filename)?;
-
-        // Write header
-        writeln!(file, "{}", results.columns.join(","))?;
-
-        // Write rows
-        for row in &results.rows {
-            let row_str = row.iter()
-                .map(|cell| {
-                    let val = cell.display.replace("\"", "\"\"");
-                    if val.contains(',') || val.contains('"') || val.contains('\n') {
-                        format!("\"{}\"", val)
-                    } else {
-                        val
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(",");
-            writeln!(file, "{}", row_str)?;
-        }
+        let filename = self.db.output_path(&format!("query_report_{}.html", timestamp));
 
-            tracing::debug!("✓ Exported {} rows to {}", results.row_count, filename);
+        let count = crate::report::write_query_report(&self.db, &results.sql, &filename)?;
+
+        tracing::info!("✓ Wrote report of {} rows to {}", count, filename.display());
         Ok(())
     }
 
     pub fn export_to_json(&self, results: &QueryResult) -> anyhow::Result<()> {
-        use std::fs::File;
-        use std::io::Write;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let filename = self.db.output_path(&format!("query_results_{}.json", timestamp));
+
+        let count = crate::export::export_query_to_json(&self.db, &results.sql, &filename)?;
+
+        tracing::info!("✓ Exported {} rows to {}", count, filename.display());
+        Ok(())
+    }
 
+    pub fn export_to_ndjson(&self, results: &QueryResult) -> anyhow::Result<()> {
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs();
-        let filename = format!("query_results_{}.json", timestamp);
-
-        let mut rows_json = Vec::new();
-        for row in &results.rows {
-            let mut row_map = serde_json::Map::new();
-            for (i, cell) in row.iter().enumerate() {
-                if let Some(col_name) = results.columns.get(i) {
-                    row_map.insert(col_name.clone(), cell.value.clone());
-                }
-            }
-            rows_json.push(serde_json::Value::Object(row_map));
+        let filename = self.db.output_path(&format!("query_results_{}.ndjson", timestamp));
+
+        let count = crate::export::export_query_to_ndjson(&self.db, &results.sql, &filename)?;
+
+        tracing::info!("✓ Exported {} rows to {}", count, filename.display());
+        Ok(())
+    }
+
+    /// Run the script file named in the path box in one transaction, keeping its
+    /// per-statement report
+    pub fn run_script_file(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let path = std::path::PathBuf::from(self.script_path_input.read(cx).value().trim());
+        if path.as_os_str().is_empty() {
+            return Err(anyhow::anyhow!("Type the path of a .sql file to run"));
+        }
+        let report = crate::sql_dump::run_script_file(&self.db, &path)?;
+        if report.committed() {
+            self.available_tables = self.db.list_tables()?;
         }
+        self.script_report = Some(report);
+        Ok(())
+    }
+
+    pub fn export_to_parquet(&self, results: &QueryResult) -> anyhow::Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let filename = self.db.output_path(&format!("query_results_{}.parquet", timestamp));
 
-        let json = serde_json::to_string_pretty(&rows_json)?;
-        let mut file = File::create(&filename)?;
-        file.write_all(json.as_bytes())?;
+        let count = crate::parquet_export::export_query_to_parquet(&self.db, &results.sql, &filename)?;
 
-        tracing::info!("✓ Exported {} rows to {}", results.row_count, filename);
+        tracing::info!("✓ Exported {} rows to {}", count, filename.display());
+        Ok(())
+    }
+
+    pub fn export_to_template(&self, results: &QueryResult) -> anyhow::Result<()> {
+        let template = self
+            .templates
+            .get(self.template_ix)
+            .ok_or_else(|| anyhow::anyhow!("No export template selected"))?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let filename = self.db.output_path(&format!("query_results_{}.{}", timestamp, template.extension));
+
+        let count = crate::template::export_query(&self.db, &results.sql, template, &filename)?;
+
+        tracing::info!("✓ Exported {} rows through the {} template to {}", count, template.name, filename.display());
         Ok(())
     }
 
@@ -602,10 +1931,151 @@ impl QueryEditor {
             )
     }
 
+    /// One collapsible header per group, showing its keys and aggregates
+    fn render_groups(&self, grouping: &GroupedQuery, result: &QueryResult, cx: &Context<QueryEditor>) -> impl IntoElement {
+        v_flex()
+            .id("grouped-results")
+            .w_full()
+            .flex_1()
+            .min_h_0()
+            .overflow_y_scroll()
+            .gap_1()
+            .p_2()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded_md()
+            .children(result.rows.iter().enumerate().map(|(row_ix, row)| {
+                let expanded = self.expanded_groups.get(&row_ix);
+                let aggregates = grouping
+                    .aggregates
+                    .iter()
+                    .map(|&ix| format!("{}: {}", result.columns[ix], row.get(ix).map(|c| c.display.as_str()).unwrap_or("")))
+                    .collect::<Vec<_>>()
+                    .join("   ");
+
+                v_flex()
+                    .w_full()
+                    .child(
+                        h_flex()
+                            .id(("group-header", row_ix))
+                            .w_full()
+                            .gap_2()
+                            .px_2()
+                            .py_1()
+                            .rounded_md()
+                            .text_sm()
+                            .cursor_pointer()
+                            .when(row_ix % 2 == 1, |this| this.bg(cx.theme().muted.opacity(0.3)))
+                            .hover(|this| this.bg(cx.theme().muted))
+                            .child(div().w_4().child(if expanded.is_some() { "▾" } else { "▸" }))
+                            .child(div().font_semibold().child(grouping.key_label(&result.columns, row)))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .truncate()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(aggregates)
+                            )
+                            .on_click(cx.listener(move |editor, _, _, cx| {
+                                editor.toggle_group(row_ix);
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(expanded, |this, group| {
+                        let body = match &group.outcome {
+                            Err(e) => div()
+                                .text_xs()
+                                .text_color(cx.theme().red)
+                                .child(e.clone())
+                                .into_any_element(),
+                            Ok(rows) => v_flex()
+                                .gap_px()
+                                .text_xs()
+                                .child(
+                                    h_flex()
+                                        .gap_2()
+                                        .font_semibold()
+                                        .children(rows.columns.iter().map(|c| div().w_24().truncate().child(c.clone())))
+                                )
+                                .children(rows.rows.iter().map(|row| {
+                                    h_flex()
+                                        .gap_2()
+                                        .children(row.iter().map(|cell| div().w_24().truncate().child(cell.display.clone())))
+                                }))
+                                .when(rows.truncated, |this| {
+                                    this.child(
+                                        div()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(format!("first {} rows", GROUP_ROWS))
+                                    )
+                                })
+                                .into_any_element(),
+                        };
+                        this.child(
+                            v_flex()
+                                .ml_6()
+                                .gap_1()
+                                .py_1()
+                                .child(
+                                    div()
+                                        .truncate()
+                                        .text_xs()
+                                        .font_family("monospace")
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(group.sql.clone())
+                                )
+                                .child(body)
+                        )
+                    })
+            }))
+    }
+
     pub fn render_results(&self, cx: &Context<QueryEditor>) -> impl IntoElement {
+        let grouped = self.grouped_view.then(|| self.grouping()).flatten();
         v_flex()
             .size_full()
             .gap_2()
+            .when_some(self.script_report.as_ref(), |this, report| {
+                let color = if report.committed() { cx.theme().success } else { cx.theme().red };
+                this.child(
+                    v_flex()
+                        .w_full()
+                        .p_2()
+                        .gap_1()
+                        .border_1()
+                        .border_color(color)
+                        .rounded_md()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(div().flex_1().text_sm().text_color(color).child(report.summary()))
+                                .child(
+                                    Button::new("dismiss-script-report")
+                                        .icon(IconName::Close)
+                                        .ghost()
+                                        .xsmall()
+                                        .on_click(cx.listener(|editor, _, _, cx| {
+                                            editor.script_report = None;
+                                            cx.notify();
+                                        }))
+                                )
+                        )
+                        .children(report.failures.iter().map(|failure| {
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().red)
+                                .child(failure.describe())
+                        }))
+                        .when(report.failed > report.failures.len(), |this| {
+                            this.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("…and {} more", report.failed - report.failures.len()))
+                            )
+                        })
+                )
+            })
             .when_some(self.error.as_ref(), |this, error| {
                 this.child(
                     div()
@@ -634,7 +2104,10 @@ impl QueryEditor {
                         )
                 )
             })
-            .when_some(self.results_table.as_ref(), |this, table| {
+            .when_some(grouped.as_ref().zip(self.results.as_ref()), |this, (grouping, result)| {
+                this.child(self.render_groups(grouping, result, cx))
+            })
+            .when_some(self.results_table.as_ref().filter(|_| grouped.is_none()), |this, table| {
                 this.child(
                     div()
                         .w_full()
@@ -662,14 +2135,20 @@ impl QueryEditor {
                                         .text_lg()
                                         .font_semibold()
                                         .text_color(cx.theme().muted_foreground)
-                                        .child("💻 Ready to execute query")
-                                )
-                                .child(
-                                    div()
-                                        .text_sm()
-                                        .text_color(cx.theme().muted_foreground)
-                                        .child("Press F5 or click Execute to run your SQL query")
+                                        .child(if self.is_executing {
+                                            "⏳ Running query…"
+                                        } else {
+                                            "💻 Ready to execute query"
+                                        })
                                 )
+                                .when(!self.is_executing, |this| {
+                                    this.child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child("Press F5 or click Execute to run your SQL query")
+                                    )
+                                })
                         )
                 )
             })
@@ -685,6 +2164,16 @@ impl QueryEditorView {
         let editor = cx.new(|cx| QueryEditor::new(db, window, cx));
         Self { editor }
     }
+
+    pub fn set_grid_style(&mut self, grid: GridStyle, cx: &mut Context<Self>) {
+        self.editor.update(cx, |editor, cx| editor.set_grid_style(grid, cx));
+        cx.notify();
+    }
+
+    pub fn set_key_bindings(&mut self, mode: KeyBindingMode, cx: &mut Context<Self>) {
+        self.editor.update(cx, |editor, cx| editor.set_key_bindings(mode, cx));
+        cx.notify();
+    }
 }
 
 impl Focusable for QueryEditorView {
@@ -0,0 +1,893 @@
+//! Schema changes that account for everything depending on the changed object.
+//! Renames rewrite views, triggers and indexes in the same transaction as the ALTER
+//! (saved queries once it commits); drops list dependents and row counts and
+//! produce a script to review before anything runs. Column reorders, key changes
+//! and column edits SQLite's ALTER TABLE can't make rebuild the table.
+
+use anyhow::{Result, anyhow};
+use crate::database::{DatabaseManager, SchemaObject};
+use crate::dependencies::{DependencyGraph, DependencyKind};
+use crate::query_editor::{StoredQueries, QUERIES_SIDECAR};
+use crate::sql_text::{mentions, quote_identifier, replace_identifier, words, TableDefinition};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameTarget {
+    Table { name: String, new_name: String },
+    Column { table: String, name: String, new_name: String },
+}
+
+impl RenameTarget {
+    /// `from` is `table` or `table.column`; `to` is the new bare name
+    pub fn parse(from: &str, to: &str) -> Result<Self> {
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(anyhow!("New name is empty"));
+        }
+        match from.trim().split_once('.') {
+            Some((table, column)) if !table.is_empty() && !column.is_empty() => Ok(RenameTarget::Column {
+                table: table.to_string(),
+                name: column.to_string(),
+                new_name: to.to_string(),
+            }),
+            None if !from.trim().is_empty() => Ok(RenameTarget::Table {
+                name: from.trim().to_string(),
+                new_name: to.to_string(),
+            }),
+            _ => Err(anyhow!("Expected `table` or `table.column`, got \"{}\"", from)),
+        }
+    }
+
+    pub fn table(&self) -> &str {
+        match self {
+            RenameTarget::Table { name, .. } | RenameTarget::Column { table: name, .. } => name,
+        }
+    }
+
+    fn names(&self) -> (&str, &str) {
+        match self {
+            RenameTarget::Table { name, new_name } | RenameTarget::Column { name, new_name, .. } => {
+                (name, new_name)
+            }
+        }
+    }
+
+    fn alter_sql(&self) -> String {
+        match self {
+            RenameTarget::Table { name, new_name } => format!(
+                "ALTER TABLE {} RENAME TO {}",
+                quote_identifier(name),
+                quote_identifier(new_name)
+            ),
+            RenameTarget::Column { table, name, new_name } => format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                quote_identifier(table),
+                quote_identifier(name),
+                quote_identifier(new_name)
+            ),
+        }
+    }
+
+    /// Whether `sql` could refer to the renamed name. Column names are only
+    /// meaningful next to their table, so other tables' objects are left alone.
+    fn affects(&self, sql: &str, owner: &str) -> bool {
+        match self {
+            RenameTarget::Table { .. } => true,
+            RenameTarget::Column { table, .. } => {
+                owner.eq_ignore_ascii_case(table) || mentions(sql, table)
+            }
+        }
+    }
+
+    fn rewrite(&self, sql: &str, owner: &str) -> Option<String> {
+        if !self.affects(sql, owner) {
+            return None;
+        }
+        let (old, new) = self.names();
+        let (rewritten, count) = replace_identifier(sql, old, new);
+        (count > 0).then_some(rewritten)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextChange {
+    /// `view`, `trigger`, `index` or `query`
+    pub kind: String,
+    pub name: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenamePlan {
+    pub target: RenameTarget,
+    pub statement: String,
+    pub objects: Vec<TextChange>,
+    pub queries: Vec<TextChange>,
+}
+
+impl RenamePlan {
+    /// Every change as a line diff, for review before applying
+    pub fn preview(&self) -> String {
+        let mut out = format!("{};\n", self.statement);
+        for change in self.objects.iter().chain(&self.queries) {
+            out.push_str(&format!("\n{} {}\n", change.kind, change.name));
+            for line in change.before.lines() {
+                out.push_str(&format!("- {}\n", line));
+            }
+            for line in change.after.lines() {
+                out.push_str(&format!("+ {}\n", line));
+            }
+        }
+        if self.objects.is_empty() && self.queries.is_empty() {
+            out.push_str("\nNo dependent objects or saved queries reference this name.\n");
+        }
+        out
+    }
+}
+
+pub fn plan_rename(db: &DatabaseManager, target: RenameTarget) -> Result<RenamePlan> {
+    if !db.list_tables()?.iter().any(|t| t == target.table()) {
+        return Err(anyhow!("No table named \"{}\"", target.table()));
+    }
+
+    let objects = db
+        .schema_objects()?
+        .into_iter()
+        .filter_map(|object| {
+            let sql = object.sql?;
+            let after = target.rewrite(&sql, &object.table_name)?;
+            Some(TextChange {
+                kind: object.kind,
+                name: object.name,
+                before: sql,
+                after,
+            })
+        })
+        .collect();
+
+    let stored: StoredQueries = match db.path() {
+        Some(path) => crate::sidecar::load(path, QUERIES_SIDECAR)?.unwrap_or_default(),
+        None => StoredQueries::default(),
+    };
+    let queries = stored
+        .saved
+        .iter()
+        .filter_map(|(name, sql)| {
+            Some(TextChange {
+                kind: "query".to_string(),
+                name: name.clone(),
+                before: sql.clone(),
+                after: target.rewrite(sql, "")?,
+            })
+        })
+        .collect();
+
+    Ok(RenamePlan {
+        statement: target.alter_sql(),
+        target,
+        objects,
+        queries,
+    })
+}
+
+/// Run the rename and recreate any dependent object SQLite didn't update itself
+/// (e.g. with `legacy_alter_table` on), all in one transaction
+pub fn apply_rename(db: &DatabaseManager, plan: &RenamePlan) -> Result<usize> {
+    let recreated = db.with_transaction(|tx| {
+        tx.execute_batch(&plan.statement)?;
+
+        let mut recreated = 0;
+        for change in &plan.objects {
+            let current: Option<String> = tx
+                .query_row(
+                    "SELECT sql FROM sqlite_master WHERE type = ?1 AND name = ?2",
+                    [&change.kind, &change.name],
+                    |row| row.get(0),
+                )
+                .ok();
+            // SQLite normally rewrites dependents itself; only stale ones need recreating
+            let Some(sql) = current.and_then(|sql| plan.target.rewrite(&sql, plan.target.table())) else {
+                continue;
+            };
+            tx.execute_batch(&format!(
+                "DROP {} IF EXISTS {}; {}",
+                change.kind.to_uppercase(),
+                quote_identifier(&change.name),
+                sql
+            ))?;
+            recreated += 1;
+        }
+        Ok(recreated)
+    })?;
+
+    if !plan.queries.is_empty() {
+        if let Some(path) = db.path() {
+            let mut stored: StoredQueries = crate::sidecar::load(path, QUERIES_SIDECAR)?.unwrap_or_default();
+            for (name, sql) in stored.saved.iter_mut() {
+                if let Some(change) = plan.queries.iter().find(|c| &c.name == name && &c.before == sql) {
+                    *sql = change.after.clone();
+                }
+            }
+            crate::sidecar::save(path, QUERIES_SIDECAR, &stored)?;
+        }
+    }
+
+    db.refresh_schemas()?;
+    Ok(recreated)
+}
+
+#[derive(Debug, Clone)]
+pub struct DropDependent {
+    pub kind: String,
+    pub name: String,
+    /// Rows held, for dependent tables
+    pub row_count: Option<usize>,
+    /// Indexes and triggers on the dropped table go with it without being named
+    pub dropped_with_table: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DropPlan {
+    pub kind: String,
+    pub name: String,
+    pub row_count: Option<usize>,
+    pub cascade: bool,
+    /// Farthest dependents first, so each DROP only removes unused objects
+    pub dependents: Vec<DropDependent>,
+    pub script: String,
+}
+
+impl DropPlan {
+    /// Dependents that would be left broken unless the drop cascades
+    pub fn blocking(&self) -> impl Iterator<Item = &DropDependent> {
+        self.dependents.iter().filter(|d| !d.dropped_with_table)
+    }
+
+    /// Triggers that silently go with a dropped table, worth a warning before they're lost
+    pub fn dropped_triggers(&self) -> Vec<&str> {
+        self.dependents
+            .iter()
+            .filter(|d| d.dropped_with_table && d.kind == "trigger")
+            .map(|d| d.name.as_str())
+            .collect()
+    }
+
+    /// Summary shown above the script
+    pub fn describe(&self) -> String {
+        let mut out = match self.row_count {
+            Some(rows) => format!("{} {} ({} rows)\n", self.kind, self.name, rows),
+            None => format!("{} {}\n", self.kind, self.name),
+        };
+        for dep in &self.dependents {
+            out.push_str(&format!("  {} {}", dep.kind, dep.name));
+            if let Some(rows) = dep.row_count {
+                out.push_str(&format!(" ({} rows)", rows));
+            }
+            if dep.dropped_with_table {
+                out.push_str(" - dropped with the table");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Work out what dropping `name` involves. Without `cascade` the script refuses to
+/// run while other objects still depend on it.
+pub fn plan_drop(db: &DatabaseManager, graph: &DependencyGraph, name: &str, cascade: bool) -> Result<DropPlan> {
+    let node = graph
+        .node(name)
+        .ok_or_else(|| anyhow!("No schema object named \"{}\"", name))?;
+    let row_count = |kind: &str, name: &str| (kind == "table").then(|| db.get_row_count(name).ok()).flatten();
+
+    let mut dependents: Vec<DropDependent> = graph
+        .transitive_dependents(name)
+        .into_iter()
+        .filter_map(|dependent| graph.node(&dependent))
+        .map(|dep| {
+            let owned = graph.depends_on(&dep.name).iter().any(|e| {
+                e.to == name && matches!(e.kind, DependencyKind::FiresOn | DependencyKind::Indexes)
+            });
+            DropDependent {
+                kind: dep.kind.clone(),
+                name: dep.name.clone(),
+                row_count: row_count(&dep.kind, &dep.name),
+                dropped_with_table: node.kind == "table" && owned,
+            }
+        })
+        .collect();
+    dependents.reverse();
+
+    let mut plan = DropPlan {
+        kind: node.kind.clone(),
+        name: node.name.clone(),
+        row_count: row_count(&node.kind, &node.name),
+        cascade,
+        dependents,
+        script: String::new(),
+    };
+
+    let drop_statement = |kind: &str, name: &str| format!("DROP {} IF EXISTS {};\n", kind.to_uppercase(), quote_identifier(name));
+    let mut script = String::new();
+    let blocking: Vec<&DropDependent> = plan.blocking().collect();
+    if !blocking.is_empty() && !cascade {
+        script.push_str(&format!(
+            "-- {} objects depend on {}; drop them first or cascade:\n",
+            blocking.len(),
+            plan.name
+        ));
+        for dep in &blocking {
+            script.push_str(&format!("--   {} {}\n", dep.kind, dep.name));
+        }
+    } else {
+        for dep in &blocking {
+            script.push_str(&drop_statement(&dep.kind, &dep.name));
+        }
+        script.push_str(&drop_statement(&plan.kind, &plan.name));
+    }
+    plan.script = script;
+
+    Ok(plan)
+}
+
+/// Run a drop script in one transaction, so a failure part way leaves nothing half-dropped
+pub fn apply_drop(db: &DatabaseManager, plan: &DropPlan) -> Result<()> {
+    if !plan.cascade && plan.blocking().next().is_some() {
+        return Err(anyhow!(
+            "{} still has dependents: {}",
+            plan.name,
+            plan.blocking().map(|d| d.name.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    db.with_transaction(|tx| Ok(tx.execute_batch(&plan.script)?))?;
+    db.refresh_schemas()
+}
+
+/// Rebuild `table_name` with its columns in a new order. Columns missing from `order`
+/// (such as `id`) keep their positions; the others fill the remaining slots in order.
+pub fn reorder_columns(db: &DatabaseManager, table_name: &str, order: &[String]) -> Result<()> {
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+
+    let names: Vec<Option<String>> = table
+        .definitions
+        .iter()
+        .map(|d| TableDefinition::column_name(d))
+        .collect();
+    let find = |name: &str| names.iter().position(|n| n.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(name)));
+
+    let mut moved = Vec::new();
+    for name in order {
+        let index = find(name).ok_or_else(|| anyhow!("{} has no column {}", table_name, name))?;
+        if moved.contains(&index) {
+            return Err(anyhow!("Column {} is listed twice", name));
+        }
+        moved.push(index);
+    }
+    let mut slots = moved.clone();
+    slots.sort_unstable();
+
+    let original = table.definitions.clone();
+    for (slot, from) in slots.into_iter().zip(moved) {
+        table.definitions[slot] = original[from].clone();
+    }
+
+    let columns: Vec<(String, String)> = names.into_iter().flatten().map(|n| (n.clone(), n)).collect();
+    db.rebuild_table(table_name, &table.to_sql(), &columns)
+}
+
+/// Rebuild `table_name` with a new primary key and multi-column `UNIQUE` constraints.
+/// An empty `primary_key` leaves the table's declared key as it is.
+pub fn set_table_keys(
+    db: &DatabaseManager,
+    table_name: &str,
+    primary_key: &[String],
+    unique: &[Vec<String>],
+) -> Result<()> {
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    let columns = table.column_names();
+    for name in primary_key.iter().chain(unique.iter().flatten()) {
+        if !columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            return Err(anyhow!("{} has no column {}", table_name, name));
+        }
+    }
+
+    let replace_primary_key = !primary_key.is_empty();
+    table.definitions.retain(|d| match constraint_keyword(d) {
+        Some(KeyConstraint::PrimaryKey) => !replace_primary_key,
+        Some(KeyConstraint::Unique { columns }) => columns < 2,
+        None => true,
+    });
+    if replace_primary_key {
+        for definition in table.definitions.iter_mut() {
+            *definition = without_primary_key(definition);
+        }
+        table.definitions.push(format!(
+            "PRIMARY KEY ({})",
+            primary_key.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    for group in unique.iter().filter(|g| g.len() > 1) {
+        table.definitions.push(format!(
+            "UNIQUE ({})",
+            group.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let pairs: Vec<(String, String)> = columns.into_iter().map(|c| (c.clone(), c)).collect();
+    db.rebuild_table(table_name, &table.to_sql(), &pairs)
+}
+
+/// A column edit made by rebuilding the table, which works where ALTER TABLE doesn't:
+/// dropping an indexed or constrained column, or changing a type at all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnChange {
+    /// A full column definition, e.g. `score REAL NOT NULL DEFAULT 0`
+    Add { definition: String },
+    Rename { name: String, new_name: String },
+    Drop { name: String },
+    /// Replace the declared type, keeping the column's constraints
+    Retype { name: String, sql_type: String },
+}
+
+impl ColumnChange {
+    pub fn describe(&self, table_name: &str) -> String {
+        match self {
+            ColumnChange::Add { definition } => format!("Added {} to {}", definition, table_name),
+            ColumnChange::Rename { name, new_name } => format!("Renamed {}.{} to {}", table_name, name, new_name),
+            ColumnChange::Drop { name } => format!("Dropped {}.{}", table_name, name),
+            ColumnChange::Retype { name, sql_type } => format!("Changed {}.{} to {}", table_name, name, sql_type),
+        }
+    }
+}
+
+/// Keywords that end a column's type and start its constraints
+const COLUMN_CONSTRAINTS: &[&str] = &[
+    "CONSTRAINT", "PRIMARY", "NOT", "NULL", "UNIQUE", "CHECK", "DEFAULT", "COLLATE", "REFERENCES", "GENERATED", "AS",
+];
+
+/// Rebuild `table_name` with one column added, renamed, dropped or retyped. Rows are
+/// copied across, a retyped column's values converting by its new affinity, and the
+/// table's indexes and triggers are recreated; a rename rewrites them to the new name.
+/// Returns the indexes dropped along with a dropped column.
+pub fn alter_column(db: &DatabaseManager, table_name: &str, change: &ColumnChange) -> Result<Vec<String>> {
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    let columns = table.column_names();
+    let exists = |name: &str| columns.iter().any(|c| c.eq_ignore_ascii_case(name));
+    let find = |table: &TableDefinition, name: &str| {
+        table
+            .definitions
+            .iter()
+            .position(|d| TableDefinition::column_name(d).is_some_and(|c| c.eq_ignore_ascii_case(name)))
+            .ok_or_else(|| anyhow!("{} has no column {}", table_name, name))
+    };
+    let mut pairs: Vec<(String, String)> = columns.iter().map(|c| (c.clone(), c.clone())).collect();
+
+    match change {
+        ColumnChange::Add { definition } => {
+            let name = TableDefinition::column_name(definition)
+                .ok_or_else(|| anyhow!("\"{}\" is not a column definition", definition))?;
+            if exists(&name) {
+                return Err(anyhow!("{} already has a column {}", table_name, name));
+            }
+            // After the last column, ahead of any table constraints
+            let at = table
+                .definitions
+                .iter()
+                .rposition(|d| TableDefinition::column_name(d).is_some())
+                .map_or(0, |i| i + 1);
+            table.definitions.insert(at, definition.trim().to_string());
+            db.rebuild_table(table_name, &table.to_sql(), &pairs)?;
+            Ok(Vec::new())
+        }
+        ColumnChange::Rename { name, new_name } => {
+            let new_name = new_name.trim();
+            if new_name.is_empty() {
+                return Err(anyhow!("New name is empty"));
+            }
+            if !new_name.eq_ignore_ascii_case(name) && exists(new_name) {
+                return Err(anyhow!("{} already has a column {}", table_name, new_name));
+            }
+            find(&table, name)?;
+            for definition in table.definitions.iter_mut() {
+                *definition = rename_in_definition(definition, name, new_name);
+            }
+            for pair in pairs.iter_mut().filter(|(_, old)| old.eq_ignore_ascii_case(name)) {
+                pair.0 = new_name.to_string();
+            }
+            let target = RenameTarget::Column {
+                table: table_name.to_string(),
+                name: name.clone(),
+                new_name: new_name.to_string(),
+            };
+            db.rebuild_table_with(table_name, &table.to_sql(), &pairs, |object| {
+                let sql = object.sql.as_deref()?;
+                Some(target.rewrite(sql, &object.table_name).unwrap_or_else(|| sql.to_string()))
+            })?;
+            Ok(Vec::new())
+        }
+        ColumnChange::Drop { name } => {
+            let index = find(&table, name)?;
+            if columns.len() == 1 {
+                return Err(anyhow!("{} is the only column of {}", name, table_name));
+            }
+            table.definitions.remove(index);
+            if let Some(user) = table.definitions.iter().find(|d| mentions(d, name)) {
+                return Err(anyhow!("{} is still used by {}; change that first", name, user));
+            }
+            pairs.retain(|(_, old)| !old.eq_ignore_ascii_case(name));
+
+            // Indexes on the column go with it; anything else still using it fails to recreate
+            let dropped: Vec<String> = db
+                .schema_objects()?
+                .into_iter()
+                .filter(|o| o.kind == "index" && o.table_name == table_name)
+                .filter(|o| o.sql.as_deref().is_some_and(|sql| mentions(sql, name)))
+                .map(|o| o.name)
+                .collect();
+            let keep = |object: &SchemaObject| !(object.kind == "index" && dropped.contains(&object.name));
+            db.rebuild_table_with(table_name, &table.to_sql(), &pairs, |object| {
+                keep(object).then(|| object.sql.clone()).flatten()
+            })?;
+            Ok(dropped)
+        }
+        ColumnChange::Retype { name, sql_type } => {
+            let index = find(&table, name)?;
+            table.definitions[index] = retyped(&table.definitions[index], sql_type.trim());
+            db.rebuild_table(table_name, &table.to_sql(), &pairs)?;
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// A definition with references to column `old` renamed, leaving `REFERENCES other(old)`,
+/// which names another table's column, alone
+fn rename_in_definition(definition: &str, old: &str, new: &str) -> String {
+    let split = words(definition)
+        .iter()
+        .find(|w| w.is_keyword("REFERENCES"))
+        .map_or(definition.len(), |w| w.range.start);
+    let (own, references) = definition.split_at(split);
+    let (renamed, _) = replace_identifier(own, old, new);
+    format!("{}{}", renamed, references)
+}
+
+/// A column definition with its declared type replaced by `sql_type`, or added after
+/// the name if it had none
+fn retyped(definition: &str, sql_type: &str) -> String {
+    let words = words(definition);
+    let Some(name) = words.first() else {
+        return definition.to_string();
+    };
+    let end = words
+        .iter()
+        .skip(1)
+        .find(|w| COLUMN_CONSTRAINTS.iter().any(|k| w.is_keyword(k)))
+        .map_or(definition.len(), |w| w.range.start);
+    let start = name.range.end.min(end);
+    format!("{} {} {}", &definition[..start], sql_type, &definition[end..])
+        .trim()
+        .to_string()
+}
+
+enum KeyConstraint {
+    PrimaryKey,
+    Unique { columns: usize },
+}
+
+/// Which key a table-level constraint declares, if any
+fn constraint_keyword(definition: &str) -> Option<KeyConstraint> {
+    if TableDefinition::column_name(definition).is_some() {
+        return None;
+    }
+    let words = words(definition);
+    let skip = if words.first().is_some_and(|w| w.is_keyword("CONSTRAINT")) { 2 } else { 0 };
+    let keyword = words.get(skip)?;
+    if keyword.is_keyword("PRIMARY") {
+        Some(KeyConstraint::PrimaryKey)
+    } else if keyword.is_keyword("UNIQUE") {
+        let list = definition[keyword.range.end..].split(')').next().unwrap_or_default();
+        Some(KeyConstraint::Unique { columns: list.split(',').count() })
+    } else {
+        None
+    }
+}
+
+/// A column definition with any `[CONSTRAINT name] PRIMARY KEY [ASC|DESC] [ON CONFLICT x]
+/// [AUTOINCREMENT]` clause removed
+fn without_primary_key(definition: &str) -> String {
+    let words = words(definition);
+    let Some(i) = (0..words.len().saturating_sub(1))
+        .find(|&i| words[i].is_keyword("PRIMARY") && words[i + 1].is_keyword("KEY"))
+    else {
+        return definition.to_string();
+    };
+
+    let start = if i >= 2 && words[i - 2].is_keyword("CONSTRAINT") { i - 2 } else { i };
+    let mut end = i + 1;
+    loop {
+        match words.get(end + 1) {
+            Some(w) if w.is_keyword("ASC") || w.is_keyword("DESC") || w.is_keyword("AUTOINCREMENT") => end += 1,
+            Some(w) if w.is_keyword("ON") && words.get(end + 2).is_some_and(|c| c.is_keyword("CONFLICT")) => end += 3,
+            _ => break,
+        }
+    }
+    let end = words.get(end).map(|w| w.range.end).unwrap_or(definition.len());
+
+    format!("{} {}", definition[..words[start].range.start].trim_end(), definition[end..].trim_start())
+        .trim()
+        .to_string()
+}
+
+/// A `FOREIGN KEY` table constraint to add
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyDefinition {
+    pub columns: Vec<String>,
+    pub parent: String,
+    /// Empty to reference the parent's primary key
+    pub parent_columns: Vec<String>,
+    /// e.g. `CASCADE` or `SET NULL`; `None` keeps SQLite's `NO ACTION`
+    pub on_delete: Option<String>,
+}
+
+impl ForeignKeyDefinition {
+    pub fn to_sql(&self) -> String {
+        let list = |names: &[String]| names.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+        let mut sql = format!("FOREIGN KEY ({}) REFERENCES {}", list(&self.columns), quote_identifier(&self.parent));
+        if !self.parent_columns.is_empty() {
+            sql.push_str(&format!(" ({})", list(&self.parent_columns)));
+        }
+        if let Some(action) = &self.on_delete {
+            sql.push_str(&format!(" ON DELETE {}", action));
+        }
+        sql
+    }
+}
+
+/// Actions SQLite accepts after `ON DELETE`
+pub const FOREIGN_KEY_ACTIONS: &[&str] = &["NO ACTION", "RESTRICT", "SET NULL", "SET DEFAULT", "CASCADE"];
+
+/// Rebuild `table_name` with a new foreign key. The rebuild's foreign key check fails,
+/// leaving the table as it was, if existing rows don't satisfy it.
+pub fn add_foreign_key(db: &DatabaseManager, table_name: &str, key: &ForeignKeyDefinition) -> Result<()> {
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    let columns = table.column_names();
+    if key.columns.is_empty() {
+        return Err(anyhow!("Name the column the key is on"));
+    }
+    for name in &key.columns {
+        if !columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            return Err(anyhow!("{} has no column {}", table_name, name));
+        }
+    }
+    if !db.list_tables()?.iter().any(|t| t.eq_ignore_ascii_case(&key.parent)) {
+        return Err(anyhow!("No table {} to reference", key.parent));
+    }
+    if !key.parent_columns.is_empty() && key.parent_columns.len() != key.columns.len() {
+        return Err(anyhow!("{} columns can't reference {}", key.columns.len(), key.parent_columns.len()));
+    }
+    if let Some(action) = &key.on_delete {
+        if !FOREIGN_KEY_ACTIONS.iter().any(|a| a.eq_ignore_ascii_case(action)) {
+            return Err(anyhow!("Unknown ON DELETE action {}", action));
+        }
+    }
+
+    table.definitions.push(key.to_sql());
+    let pairs: Vec<(String, String)> = columns.into_iter().map(|c| (c.clone(), c)).collect();
+    db.rebuild_table(table_name, &table.to_sql(), &pairs)
+}
+
+/// Rebuild `table_name` without the foreign key `id` from `PRAGMA foreign_key_list`,
+/// whether it was declared as a table constraint or inline on its column
+pub fn drop_foreign_key(db: &DatabaseManager, table_name: &str, id: i64) -> Result<()> {
+    let keys: Vec<_> = db.foreign_keys(table_name)?.into_iter().filter(|k| k.id == id).collect();
+    let parent = keys
+        .first()
+        .map(|k| k.referenced_table.clone())
+        .ok_or_else(|| anyhow!("{} has no foreign key {}", table_name, id))?;
+    let key_columns: Vec<&str> = keys.iter().map(|k| k.column.as_str()).collect();
+
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    let columns = table.column_names();
+
+    if let Some(index) = table
+        .definitions
+        .iter()
+        .position(|d| foreign_key_constraint(d).is_some_and(|(from, to)| {
+            to.eq_ignore_ascii_case(&parent)
+                && from.len() == key_columns.len()
+                && from.iter().zip(&key_columns).all(|(a, b)| a.eq_ignore_ascii_case(b))
+        }))
+    {
+        table.definitions.remove(index);
+    } else if let [column] = key_columns.as_slice() {
+        let definition = table
+            .definitions
+            .iter_mut()
+            .find(|d| TableDefinition::column_name(d).is_some_and(|c| c.eq_ignore_ascii_case(column)))
+            .ok_or_else(|| anyhow!("{} has no column {}", table_name, column))?;
+        *definition = without_references(definition)
+            .ok_or_else(|| anyhow!("Couldn't find the REFERENCES clause on {}.{}", table_name, column))?;
+    } else {
+        return Err(anyhow!("Couldn't find the foreign key on {} in the definition of {}", key_columns.join(", "), table_name));
+    }
+
+    let pairs: Vec<(String, String)> = columns.into_iter().map(|c| (c.clone(), c)).collect();
+    db.rebuild_table(table_name, &table.to_sql(), &pairs)
+}
+
+/// The columns and parent table of a `[CONSTRAINT name] FOREIGN KEY (...) REFERENCES parent`
+/// table constraint
+fn foreign_key_constraint(definition: &str) -> Option<(Vec<String>, String)> {
+    if TableDefinition::column_name(definition).is_some() {
+        return None;
+    }
+    let words = words(definition);
+    let skip = if words.first().is_some_and(|w| w.is_keyword("CONSTRAINT")) { 2 } else { 0 };
+    if !words.get(skip)?.is_keyword("FOREIGN") {
+        return None;
+    }
+    let references = words.iter().position(|w| w.is_keyword("REFERENCES"))?;
+    let columns = words.get(skip + 2..references)?.iter().map(|w| w.text.clone()).collect();
+    Some((columns, words.get(references + 1)?.text.clone()))
+}
+
+/// A column definition with its `[CONSTRAINT name] REFERENCES parent [(columns)]` clause
+/// and the clause's actions removed, or `None` if it has none
+fn without_references(definition: &str) -> Option<String> {
+    let words = words(definition);
+    let i = words.iter().position(|w| w.is_keyword("REFERENCES"))?;
+    let start = if i >= 2 && words[i - 2].is_keyword("CONSTRAINT") { i - 2 } else { i };
+
+    // The parent's column list, if any, ends at its closing parenthesis
+    let mut end = (i + 1).min(words.len() - 1);
+    let mut offset = words[end].range.end;
+    if definition[offset..].trim_start().starts_with('(') {
+        offset += definition[offset..].find(')')? + 1;
+        end = words.iter().rposition(|w| w.range.end <= offset).unwrap_or(end);
+    }
+    let is = |ix: usize, keyword: &str| words.get(ix).is_some_and(|w| w.is_keyword(keyword));
+    loop {
+        let next = end + 1;
+        if is(next, "ON") && (is(next + 1, "DELETE") || is(next + 1, "UPDATE")) {
+            end = next + if is(next + 2, "SET") || is(next + 2, "NO") { 3 } else { 2 };
+        } else if is(next, "MATCH") {
+            end = next + 1;
+        } else if is(next, "NOT") && is(next + 1, "DEFERRABLE") {
+            end = next + 1;
+        } else if is(next, "DEFERRABLE") {
+            end = next;
+        } else if is(next, "INITIALLY") {
+            end = next + 1;
+        } else {
+            break;
+        }
+    }
+    let end = words.get(end).map_or(definition.len(), |w| w.range.end.max(offset));
+
+    Some(
+        format!("{} {}", definition[..words[start].range.start].trim_end(), definition[end..].trim_start())
+            .trim()
+            .to_string(),
+    )
+}
+
+/// How a table's rows are identified, which decides whether ids can be reused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowIdScheme {
+    /// No primary key; rows only have the hidden rowid
+    ImplicitRowid,
+    /// An `INTEGER PRIMARY KEY` column is another name for the rowid
+    RowidAlias { column: String, autoincrement: bool },
+    /// Another kind of key, with the hidden rowid alongside it
+    SeparateKey { columns: Vec<String> },
+    WithoutRowid,
+}
+
+impl RowIdScheme {
+    pub fn summary(&self) -> String {
+        match self {
+            RowIdScheme::ImplicitRowid => "hidden rowid".to_string(),
+            RowIdScheme::RowidAlias { column, autoincrement: true } => format!("{} = rowid, AUTOINCREMENT", column),
+            RowIdScheme::RowidAlias { column, autoincrement: false } => format!("{} = rowid", column),
+            RowIdScheme::SeparateKey { columns } => format!("key ({}) plus hidden rowid", columns.join(", ")),
+            RowIdScheme::WithoutRowid => "WITHOUT ROWID".to_string(),
+        }
+    }
+
+    /// What the scheme means for id reuse, shown alongside the summary
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            RowIdScheme::ImplicitRowid => {
+                "Rows have no key of their own. The rowid can change on VACUUM, so don't store it elsewhere."
+            }
+            RowIdScheme::RowidAlias { autoincrement: true, .. } => {
+                "Ids are never reused: the highest id ever issued is kept in sqlite_sequence, \
+                 so deleted ids stay gone. Inserts do a little extra work to maintain it."
+            }
+            RowIdScheme::RowidAlias { autoincrement: false, .. } => {
+                "New rows get the current largest id + 1, so deleting the newest rows lets \
+                 their ids be handed out again. Turn on AUTOINCREMENT if ids are stored elsewhere."
+            }
+            RowIdScheme::SeparateKey { .. } => {
+                "The key isn't the rowid, so lookups by key go through an index."
+            }
+            RowIdScheme::WithoutRowid => "Rows are stored by their primary key and have no rowid.",
+        }
+    }
+}
+
+pub fn row_id_scheme(db: &DatabaseManager, table_name: &str) -> Result<RowIdScheme> {
+    let sql = db.table_sql(table_name)?;
+    let table = TableDefinition::parse(&sql)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    if table.options.to_ascii_uppercase().contains("WITHOUT ROWID") {
+        return Ok(RowIdScheme::WithoutRowid);
+    }
+
+    let key = db.primary_key_columns(table_name)?;
+    Ok(match key.as_slice() {
+        [] => RowIdScheme::ImplicitRowid,
+        [(column, declared)] if declared.eq_ignore_ascii_case("INTEGER") => RowIdScheme::RowidAlias {
+            column: column.clone(),
+            autoincrement: words(&sql).iter().any(|w| w.is_keyword("AUTOINCREMENT")),
+        },
+        columns => RowIdScheme::SeparateKey {
+            columns: columns.iter().map(|(name, _)| name.clone()).collect(),
+        },
+    })
+}
+
+/// Rebuild a rowid-alias table with AUTOINCREMENT turned on or off. Copying the rows
+/// seeds `sqlite_sequence` with the current largest id when turning it on.
+pub fn set_autoincrement(db: &DatabaseManager, table_name: &str, enabled: bool) -> Result<()> {
+    let RowIdScheme::RowidAlias { column, autoincrement } = row_id_scheme(db, table_name)? else {
+        return Err(anyhow!("AUTOINCREMENT needs an INTEGER PRIMARY KEY column"));
+    };
+    if autoincrement == enabled {
+        return Ok(());
+    }
+
+    let mut table = TableDefinition::parse(&db.table_sql(table_name)?)
+        .ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table_name))?;
+    // AUTOINCREMENT is only allowed on a column-level key, so move any table-level one there
+    table
+        .definitions
+        .retain(|d| !matches!(constraint_keyword(d), Some(KeyConstraint::PrimaryKey)));
+    for definition in table.definitions.iter_mut() {
+        let stripped = without_primary_key(definition);
+        *definition = if TableDefinition::column_name(&stripped).is_some_and(|c| c == column) {
+            format!("{} PRIMARY KEY{}", stripped, if enabled { " AUTOINCREMENT" } else { "" })
+        } else {
+            stripped
+        };
+    }
+
+    let pairs: Vec<(String, String)> = table.column_names().into_iter().map(|c| (c.clone(), c)).collect();
+    db.rebuild_table(table_name, &table.to_sql(), &pairs)
+}
+
+/// Why setting an AUTOINCREMENT table's sequence to `value` may be a problem, given the
+/// currently recorded sequence and largest id
+pub fn sequence_warning(current: Option<i64>, max_id: i64, value: i64) -> Option<String> {
+    if value < max_id {
+        return Some(format!(
+            "Below the largest id ({}), so new rows still start at {}.",
+            max_id,
+            max_id + 1
+        ));
+    }
+    match current {
+        Some(seq) if value < seq => Some(format!(
+            "Ids {}-{} were issued before and will be issued again; anything still \
+             referring to them (other tables, save files) would point at the new rows.",
+            value + 1,
+            seq
+        )),
+        _ => None,
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use crate::database::CellValue;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SqlType {
@@ -42,8 +43,24 @@ pub struct TypeSchema {
     pub table_name: String,
     pub fields: Vec<FieldSchema>,
     pub has_sub_structs: bool,
+    /// Columns of a composite or explicit primary key; empty means the implicit `id`
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    /// Multi-column `UNIQUE` constraints
+    #[serde(default)]
+    pub unique_constraints: Vec<Vec<String>>,
+    /// A view, which the grid shows read-only
+    #[serde(default)]
+    pub is_view: bool,
+    /// A `WITHOUT ROWID` table, keyed only by its primary key
+    #[serde(default)]
+    pub without_rowid: bool,
 }
 
+/// Row ids for views and `WITHOUT ROWID` tables, which only work where window
+/// functions are allowed: the selected columns and ORDER BY, not WHERE
+pub const VIEW_ROW_NUMBER: &str = "ROW_NUMBER() OVER ()";
+
 impl TypeSchema {
     pub fn new(type_name: impl Into<String>) -> Self {
         let type_name = type_name.into();
@@ -54,6 +71,10 @@ impl TypeSchema {
             table_name,
             fields: vec![],
             has_sub_structs: false,
+            primary_key: vec![],
+            unique_constraints: vec![],
+            is_view: false,
+            without_rowid: false,
         }
     }
 
@@ -77,23 +98,424 @@ impl TypeSchema {
         }
     }
 
+    /// Columns identifying a row, which edits and deletes are keyed on
+    pub fn key_columns(&self) -> Vec<&str> {
+        if self.primary_key.is_empty() {
+            vec!["id"]
+        } else {
+            self.primary_key.iter().map(|c| c.as_str()).collect()
+        }
+    }
+
+    /// Column the grid pages and selects by: `id`, or the rowid for tables keyed
+    /// otherwise. Views and `WITHOUT ROWID` tables have neither, so their rows are
+    /// numbered as they're read.
+    pub fn row_id_column(&self) -> &'static str {
+        if !self.has_row_ids() {
+            VIEW_ROW_NUMBER
+        } else if self.primary_key.is_empty() {
+            "id"
+        } else {
+            "rowid"
+        }
+    }
+
+    /// Whether rows have ids of their own to page, sample and select by
+    pub fn has_row_ids(&self) -> bool {
+        !self.is_view && !self.without_rowid
+    }
+
     pub fn to_create_table_sql(&self) -> String {
-        let mut sql = format!("CREATE TABLE IF NOT EXISTS {} (\n", self.table_name);
-        sql.push_str("    id INTEGER PRIMARY KEY AUTOINCREMENT,\n");
+        let mut definitions = Vec::new();
+        if self.primary_key.is_empty() {
+            definitions.push("id INTEGER PRIMARY KEY AUTOINCREMENT".to_string());
+        }
 
         for field in &self.fields {
             let null_constraint = if field.nullable { "" } else { " NOT NULL" };
-            sql.push_str(&format!(
-                "    {} {}{},\n",
+            definitions.push(format!(
+                "{} {}{}",
                 field.name,
                 field.sql_type.to_sql_string(),
                 null_constraint
             ));
         }
 
-        sql.push_str(");");
-        sql
+        if !self.primary_key.is_empty() {
+            definitions.push(format!("PRIMARY KEY ({})", self.primary_key.join(", ")));
+        }
+        for columns in &self.unique_constraints {
+            definitions.push(format!("UNIQUE ({})", columns.join(", ")));
+        }
+
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n    {}\n);",
+            self.table_name,
+            definitions.join(",\n    ")
+        )
+    }
+}
+
+/// Per-database presentation metadata, loaded from the `profile` sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReflectionProfile {
+    /// The database is a game save: open it read-only for inspection
+    #[serde(default)]
+    pub save_game: bool,
+    #[serde(default)]
+    pub tables: HashMap<String, TableHints>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableHints {
+    #[serde(default)]
+    pub columns: HashMap<String, ColumnHints>,
+    /// Latitude/longitude column pairs, edited on a map
+    #[serde(default)]
+    pub geo: Vec<GeoPair>,
+}
+
+/// Two numeric columns that together hold a position in degrees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoPair {
+    pub lat: String,
+    pub lon: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnHints {
+    #[serde(default)]
+    pub decoder: Option<ValueDecoder>,
+    #[serde(default)]
+    pub lineage: Option<Lineage>,
+    #[serde(default)]
+    pub pattern: Option<InputPattern>,
+    #[serde(default)]
+    pub range: Option<NumericRange>,
+    /// Comma-separated tags, edited as chips
+    #[serde(default)]
+    pub tags: bool,
+    /// Markdown text, edited with a rendered preview
+    #[serde(default)]
+    pub markdown: bool,
+}
+
+impl ColumnHints {
+    fn is_empty(&self) -> bool {
+        self.decoder.is_none()
+            && self.lineage.is_none()
+            && self.pattern.is_none()
+            && self.range.is_none()
+            && !self.tags
+            && !self.markdown
+    }
+}
+
+impl ReflectionProfile {
+    pub fn column(&self, table_name: &str, column: &str) -> Option<&ColumnHints> {
+        self.tables.get(table_name)?.columns.get(column)
+    }
+
+    /// Annotate (or with `None`, un-annotate) where a column's values come from
+    pub fn set_lineage(&mut self, table_name: &str, column: &str, lineage: Option<Lineage>) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let hints = table.columns.entry(column.to_string()).or_default();
+        hints.lineage = lineage;
+        if hints.is_empty() {
+            table.columns.remove(column);
+        }
+    }
+
+    /// Declare (or with `None`, stop declaring) the valid range of a numeric column
+    pub fn set_range(&mut self, table_name: &str, column: &str, range: Option<NumericRange>) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let hints = table.columns.entry(column.to_string()).or_default();
+        hints.range = range;
+        if hints.is_empty() {
+            table.columns.remove(column);
+        }
+    }
+
+    /// Mark (or unmark) a text column as holding comma-separated tags
+    pub fn set_tags(&mut self, table_name: &str, column: &str, tags: bool) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let hints = table.columns.entry(column.to_string()).or_default();
+        hints.tags = tags;
+        if hints.is_empty() {
+            table.columns.remove(column);
+        }
+    }
+
+    /// Mark (or unmark) a text column as holding Markdown
+    pub fn set_markdown(&mut self, table_name: &str, column: &str, markdown: bool) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let hints = table.columns.entry(column.to_string()).or_default();
+        hints.markdown = markdown;
+        if hints.is_empty() {
+            table.columns.remove(column);
+        }
+    }
+
+    /// Require (or with `None`, stop requiring) edits to a column to fit a pattern
+    pub fn set_pattern(&mut self, table_name: &str, column: &str, pattern: Option<InputPattern>) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let hints = table.columns.entry(column.to_string()).or_default();
+        hints.pattern = pattern;
+        if hints.is_empty() {
+            table.columns.remove(column);
+        }
+    }
+
+    /// Declare (or undeclare) two columns as a latitude/longitude pair. A column
+    /// belongs to at most one pair, so declaring replaces any pair sharing a column.
+    pub fn set_geo_pair(&mut self, table_name: &str, lat: &str, lon: &str, on: bool) {
+        let table = self.tables.entry(table_name.to_string()).or_default();
+        let shares = |pair: &GeoPair| [lat, lon].iter().any(|c| pair.lat == *c || pair.lon == *c);
+        table.geo.retain(|pair| !shares(pair));
+        if on {
+            table.geo.push(GeoPair { lat: lat.to_string(), lon: lon.to_string() });
+        }
+    }
+}
+
+/// Where a derived column's values come from, so hand edits can be flagged as likely
+/// to be overwritten
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// Columns it is computed from, `column` or `table.column`
+    #[serde(default)]
+    pub derived_from: Vec<String>,
+    /// External job that writes it, e.g. `nightly-balance`
+    #[serde(default)]
+    pub pipeline: Option<String>,
+}
+
+impl Lineage {
+    /// Comma-separated sources, with `pipeline:<name>` naming an external job.
+    /// `None` when nothing is given.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lineage = Lineage::default();
+        for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.strip_prefix("pipeline:") {
+                Some(name) => lineage.pipeline = Some(name.trim().to_string()),
+                None => lineage.derived_from.push(part.to_string()),
+            }
+        }
+        (!lineage.derived_from.is_empty() || lineage.pipeline.is_some()).then_some(lineage)
     }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.derived_from.is_empty() {
+            parts.push(self.derived_from.join(", "));
+        }
+        if let Some(pipeline) = &self.pipeline {
+            parts.push(format!("pipeline {}", pipeline));
+        }
+        format!("← {}", parts.join("; "))
+    }
+}
+
+/// Valid values of a numeric column, declared in the profile or read from its `CHECK`
+/// constraints, and the step the spinner and arrow keys move by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NumericRange {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub step: Option<f64>,
+}
+
+impl NumericRange {
+    /// `0..100`, `..100` or `0..`, optionally followed by `step 5`. `None` when nothing
+    /// is given.
+    pub fn parse(text: &str) -> Result<Option<Self>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let (bounds, step) = match text.split_once("step") {
+            Some((bounds, step)) => (bounds.trim(), Some(step.trim())),
+            None => (text, None),
+        };
+        let (min, max) = bounds
+            .split_once("..")
+            .ok_or_else(|| anyhow!("Expected a range like 0..100, got '{}'", bounds))?;
+        let number = |s: &str| -> Result<Option<f64>> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse().map(Some).map_err(|_| anyhow!("'{}' is not a number", s))
+        };
+        let range = Self {
+            min: number(min)?,
+            max: number(max)?,
+            step: step.map(number).transpose()?.flatten(),
+        };
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                return Err(anyhow!("{} is above {}", min, max));
+            }
+        }
+        if range.step.is_some_and(|s| s <= 0.0) {
+            return Err(anyhow!("The step must be positive"));
+        }
+        Ok(Some(range))
+    }
+
+    pub fn step(&self) -> f64 {
+        self.step.unwrap_or(1.0)
+    }
+
+    /// Both ends are known, so a slider can show the value's position
+    pub fn is_bounded(&self) -> bool {
+        self.min.is_some() && self.max.is_some()
+    }
+
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    pub fn describe(&self) -> String {
+        let bound = |b: Option<f64>| b.map(|b| b.to_string()).unwrap_or_default();
+        let mut text = format!("{}..{}", bound(self.min), bound(self.max));
+        if let Some(step) = self.step {
+            text.push_str(&format!(" step {}", step));
+        }
+        text
+    }
+}
+
+/// What edits to a column must look like before they are staged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InputPattern {
+    /// A regex the whole value must match, e.g. `^[A-Z]{3}-\d{4}$` for item codes
+    Regex {
+        pattern: String,
+        /// Shown instead of the pattern when a value doesn't match
+        #[serde(default)]
+        hint: Option<String>,
+    },
+    /// `A` a letter, `9` a digit, `*` any character, anything else literal, e.g. `AAA-9999`
+    Mask { mask: String },
+}
+
+impl InputPattern {
+    /// `mask:AAA-9999` for a mask, a regex otherwise. `None` when nothing is given.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        Some(match text.strip_prefix("mask:") {
+            Some(mask) => InputPattern::Mask { mask: mask.trim().to_string() },
+            None => InputPattern::Regex { pattern: text.to_string(), hint: None },
+        })
+    }
+
+    /// The pattern as an anchored regex
+    pub fn to_regex(&self) -> String {
+        match self {
+            InputPattern::Regex { pattern, .. } => format!("^(?:{})$", pattern),
+            InputPattern::Mask { mask } => {
+                let mut regex = String::from("^");
+                for c in mask.chars() {
+                    match c {
+                        'A' => regex.push_str("[A-Za-z]"),
+                        '9' => regex.push_str("[0-9]"),
+                        '*' => regex.push('.'),
+                        c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                    }
+                }
+                regex.push('$');
+                regex
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            InputPattern::Regex { hint: Some(hint), .. } => hint.clone(),
+            InputPattern::Regex { pattern, .. } => format!("must match {}", pattern),
+            InputPattern::Mask { mask } => format!("must look like {}", mask),
+        }
+    }
+}
+
+/// Known engine encodings that are unreadable as raw values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValueDecoder {
+    UnixSeconds,
+    UnixMillis,
+    /// Little-endian packed f32s, e.g. positions stored as 12-byte blobs
+    F32Array,
+    /// Integer or text codes mapped to names, e.g. quest states
+    Enum { values: HashMap<String, String> },
+}
+
+impl ValueDecoder {
+    /// Human-readable form of `cell`, or `None` if it doesn't fit this encoding
+    pub fn decode(&self, cell: &CellValue) -> Option<String> {
+        match self {
+            ValueDecoder::UnixSeconds => cell.value.as_i64().map(format_unix_seconds),
+            ValueDecoder::UnixMillis => cell.value.as_i64().map(|ms| format_unix_seconds(ms.div_euclid(1000))),
+            ValueDecoder::F32Array => {
+                let bytes = cell.blob.as_ref()?;
+                if bytes.len() % 4 != 0 {
+                    return None;
+                }
+                let floats = bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).to_string())
+                    .collect::<Vec<_>>();
+                Some(format!("[{}]", floats.join(", ")))
+            }
+            ValueDecoder::Enum { values } => {
+                let key = match &cell.value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                values.get(&key).map(|name| format!("{} ({})", name, key))
+            }
+        }
+    }
+}
+
+/// Format a unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_unix_seconds(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
 }
 
 pub fn to_snake_case(s: &str) -> String {
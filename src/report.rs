@@ -0,0 +1,159 @@
+//! Printable HTML reports of tables and query results, for sign-off and reference
+//! documents. Pages are sized for print, so "Print to PDF" in any browser produces
+//! the PDF version.
+
+use anyhow::Result;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::database::{CellValue, DatabaseManager, RowScope, RowSink};
+use crate::profiling::escape_html;
+use crate::reflection::format_unix_seconds;
+
+/// Rows per printed page
+pub const DEFAULT_ROWS_PER_PAGE: usize = 40;
+
+const REPORT_STYLE: &str = "\
+body { font-family: sans-serif; font-size: 10pt; }
+h1 { font-size: 16pt; margin-bottom: 4pt; }
+.meta { color: #555; margin: 0 0 12pt 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 12pt; }
+th, td { border: 1px solid #999; padding: 2pt 4pt; text-align: left; vertical-align: top; }
+th { background: #eee; }
+.page { page-break-after: always; }
+.page:last-of-type { page-break-after: auto; }
+.page-number { color: #555; font-size: 8pt; text-align: right; }
+@page { size: A4 landscape; margin: 12mm; }
+";
+
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub title: String,
+    /// Lines shown under the title, such as the applied filter
+    pub details: Vec<String>,
+    pub rows_per_page: usize,
+}
+
+impl ReportOptions {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            details: Vec::new(),
+            rows_per_page: DEFAULT_ROWS_PER_PAGE,
+        }
+    }
+}
+
+/// Streams rows into paginated HTML tables, repeating the column header on every page
+pub struct ReportSink<W: Write> {
+    writer: W,
+    options: ReportOptions,
+    header: String,
+    rows_in_page: usize,
+    pages: usize,
+    rows_written: usize,
+}
+
+impl<W: Write> ReportSink<W> {
+    pub fn new(writer: W, options: ReportOptions) -> Self {
+        Self {
+            writer,
+            options,
+            header: String::new(),
+            rows_in_page: 0,
+            pages: 0,
+            rows_written: 0,
+        }
+    }
+
+    fn open_page(&mut self) -> Result<()> {
+        self.pages += 1;
+        write!(
+            self.writer,
+            "<div class=\"page\">\n<div class=\"page-number\">Page {}</div>\n<table>\n{}",
+            self.pages, self.header
+        )?;
+        Ok(())
+    }
+
+    fn close_page(&mut self) -> Result<()> {
+        self.writer.write_all(b"</table>\n</div>\n")?;
+        Ok(())
+    }
+}
+
+impl<W: Write> RowSink for ReportSink<W> {
+    fn begin(&mut self, columns: &[String]) -> Result<()> {
+        let generated = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        write!(
+            self.writer,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n<p class=\"meta\">Generated {2}",
+            escape_html(&self.options.title),
+            REPORT_STYLE,
+            format_unix_seconds(generated as i64)
+        )?;
+        for detail in &self.options.details {
+            write!(self.writer, "<br>{}", escape_html(detail))?;
+        }
+        self.writer.write_all(b"</p>\n")?;
+
+        self.header = format!(
+            "<tr>{}</tr>\n",
+            columns
+                .iter()
+                .map(|c| format!("<th>{}</th>", escape_html(c)))
+                .collect::<String>()
+        );
+        self.open_page()
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        if self.rows_in_page == self.options.rows_per_page.max(1) {
+            self.close_page()?;
+            self.open_page()?;
+            self.rows_in_page = 0;
+        }
+
+        let row = cells
+            .iter()
+            .map(|cell| format!("<td>{}</td>", escape_html(&cell.display)))
+            .collect::<String>();
+        writeln!(self.writer, "<tr>{}</tr>", row)?;
+        self.rows_in_page += 1;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.close_page()?;
+        writeln!(
+            self.writer,
+            "<p class=\"meta\">{} rows on {} pages</p>\n</body>\n</html>",
+            self.rows_written, self.pages
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Report on the rows of a table covered by `scope`; the scope is printed under the title
+pub fn write_table_report(db: &DatabaseManager, table_name: &str, scope: &RowScope, path: &Path) -> Result<usize> {
+    let mut options = ReportOptions::new(format!("Table: {}", table_name));
+    options.details.push(format!("Rows: {}", scope.describe()));
+    if let Some(db_path) = db.path() {
+        options.details.push(format!("Database: {}", db_path.display()));
+    }
+
+    let mut sink = ReportSink::new(BufWriter::new(std::fs::File::create(path)?), options);
+    db.stream_table(table_name, scope, &mut sink)
+}
+
+pub fn write_query_report(db: &DatabaseManager, sql: &str, path: &Path) -> Result<usize> {
+    let mut options = ReportOptions::new("Query results");
+    options.details.push(format!("Query: {}", sql));
+
+    let mut sink = ReportSink::new(BufWriter::new(std::fs::File::create(path)?), options);
+    db.stream_query(sql, &mut sink)
+}
@@ -0,0 +1,126 @@
+//! Age-based retention for telemetry-style tables: rows whose timestamp column is
+//! older than a table's limit are deleted, or moved to the companion archive. Policies
+//! live in the `retention` sidecar and can run whenever the database is opened.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::archive;
+use crate::database::DatabaseManager;
+use crate::sql_text::quote_identifier;
+
+pub const RETENTION_SIDECAR: &str = "retention";
+
+/// Integer timestamps above this are taken as milliseconds rather than seconds
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub policies: Vec<RetentionPolicy>,
+}
+
+impl RetentionConfig {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        Ok(crate::sidecar::load(db_path, RETENTION_SIDECAR)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        crate::sidecar::save(db_path, RETENTION_SIDECAR, self)
+    }
+
+    pub fn get(&self, table: &str) -> Option<&RetentionPolicy> {
+        self.policies.iter().find(|p| p.table == table)
+    }
+
+    pub fn get_mut(&mut self, table: &str) -> Option<&mut RetentionPolicy> {
+        self.policies.iter_mut().find(|p| p.table == table)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub table: String,
+    /// Unix seconds or milliseconds, or text SQLite's date functions understand
+    pub column: String,
+    pub max_age_days: u32,
+    /// Move expired rows to the archive database instead of deleting them
+    #[serde(default)]
+    pub archive: bool,
+    #[serde(default)]
+    pub run_on_open: bool,
+}
+
+impl RetentionPolicy {
+    /// WHERE condition matching the rows past the age limit
+    pub fn expired_condition(&self) -> String {
+        let column = quote_identifier(&self.column);
+        format!(
+            "(CASE WHEN typeof({c}) IN ('integer', 'real') THEN \
+             (CASE WHEN {c} > {millis} THEN {c} / 1000 ELSE {c} END) \
+             ELSE CAST(strftime('%s', {c}) AS INTEGER) END) \
+             < CAST(strftime('%s', 'now', '-{days} days') AS INTEGER)",
+            c = column,
+            millis = MILLIS_THRESHOLD,
+            days = self.max_age_days
+        )
+    }
+
+    pub fn expired_rows(&self, db: &DatabaseManager) -> Result<usize> {
+        db.count_where(&self.table, &self.expired_condition(), &[])
+    }
+
+    /// Delete or archive the expired rows, returning how many went
+    pub fn apply(&self, db: &DatabaseManager) -> Result<usize> {
+        if !db.column_names(&self.table)?.contains(&self.column) {
+            return Err(anyhow!("{} has no column {}", self.table, self.column));
+        }
+        let removed = if self.archive {
+            let path = db.path().ok_or_else(|| anyhow!("An in-memory database has nowhere to archive to"))?;
+            archive::move_rows(db, &archive::archive_path(path), &self.table, &self.expired_condition(), &[])?
+        } else {
+            db.with_transaction(|tx| {
+                Ok(tx.execute(
+                    &format!("DELETE FROM {} WHERE {}", quote_identifier(&self.table), self.expired_condition()),
+                    [],
+                )?)
+            })?
+        };
+        tracing::info!(
+            "✓ {} {} rows of {} older than {} days",
+            if self.archive { "Archived" } else { "Deleted" },
+            removed,
+            self.table,
+            self.max_age_days
+        );
+        Ok(removed)
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} older than {} days by {}",
+            if self.archive { "Archive rows" } else { "Delete rows" },
+            self.max_age_days,
+            self.column
+        )
+    }
+}
+
+/// Apply every policy set to run on open; one line per table that lost rows or failed
+pub fn run_on_open(db: &DatabaseManager, config: &RetentionConfig) -> Vec<String> {
+    if db.is_read_only() {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    for policy in config.policies.iter().filter(|p| p.run_on_open) {
+        match policy.apply(db) {
+            Ok(0) => {}
+            Ok(removed) => lines.push(format!("{}: {} expired rows removed", policy.table, removed)),
+            Err(e) => {
+                tracing::error!("Failed to apply retention to {}: {}", policy.table, e);
+                lines.push(format!("{}: {}", policy.table, e));
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,108 @@
+//! Row actions the project declares for the grid's context menu, such as "Grant this
+//! item to test account". Each is SQL, inline or in a script file beside the config,
+//! whose named parameters (`:column`, `@column` or `$column`) take the right-clicked
+//! row's values.
+
+use anyhow::{Result, anyhow};
+use rusqlite::types::ToSql;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::database::{value_to_sql, DatabaseManager, RowData};
+use crate::reflection::TypeSchema;
+
+/// Action file looked up in the database's directory and its ancestors
+pub const ROW_ACTIONS_FILE_NAME: &str = "row_actions.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowAction {
+    /// Table the action applies to, or `*` for every table
+    pub table: String,
+    pub label: String,
+    #[serde(default)]
+    pub sql: Option<String>,
+    /// SQL file, relative to the action file
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+}
+
+impl RowAction {
+    pub fn applies_to(&self, table: &str) -> bool {
+        self.table == "*" || self.table.eq_ignore_ascii_case(table)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RowActions {
+    pub actions: Vec<RowAction>,
+    /// Directory of the action file, which script paths are relative to
+    dir: PathBuf,
+}
+
+impl RowActions {
+    /// Load the nearest action file above `db_path`, or no actions if the project has none
+    pub fn discover(db_path: &Path) -> Result<Self> {
+        for dir in db_path.ancestors().skip(1) {
+            let candidate = dir.join(ROW_ACTIONS_FILE_NAME);
+            if candidate.is_file() {
+                let text = std::fs::read_to_string(&candidate)?;
+                let actions = serde_json::from_str(&text)?;
+                return Ok(Self { actions, dir: dir.to_path_buf() });
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Only the actions offered on `table`'s rows
+    pub fn for_table(mut self, table: &str) -> Self {
+        self.actions.retain(|a| a.applies_to(table));
+        self
+    }
+
+    /// The action's SQL, read from its script when it has no inline SQL
+    pub fn sql(&self, action: &RowAction) -> Result<String> {
+        match (&action.sql, &action.script) {
+            (Some(sql), _) => Ok(sql.clone()),
+            (None, Some(script)) => Ok(std::fs::read_to_string(self.dir.join(script))?),
+            (None, None) => Err(anyhow!("Row action '{}' has neither sql nor script", action.label)),
+        }
+    }
+}
+
+/// Run every statement of `sql` against `row` in one transaction, returning the rows
+/// changed. Parameters name the row's columns, case-insensitively, or `rowid`.
+pub fn run(db: &DatabaseManager, schema: &TypeSchema, row: &RowData, sql: &str) -> Result<usize> {
+    let value_for = |name: &str| -> Result<Box<dyn ToSql>> {
+        let name = name.trim_start_matches([':', '@', '$']);
+        if let Some(idx) = schema.fields.iter().position(|f| f.name.eq_ignore_ascii_case(name)) {
+            let cell = row.cells.get(idx).ok_or_else(|| anyhow!("Row has no value for '{}'", name))?;
+            return Ok(match &cell.blob {
+                Some(bytes) => Box::new(bytes.clone()),
+                None => value_to_sql(&cell.value),
+            });
+        }
+        if name.eq_ignore_ascii_case("rowid") {
+            return Ok(Box::new(row.id));
+        }
+        Err(anyhow!("Parameter '{}' names no column of {}", name, schema.table_name))
+    };
+
+    db.with_transaction(|tx| {
+        let mut changed = 0;
+        for range in crate::sql_text::script_statements(sql) {
+            let statement = sql[range].trim();
+            if statement.is_empty() {
+                continue;
+            }
+            let mut stmt = tx.prepare(statement)?;
+            for i in 1..=stmt.parameter_count() {
+                let name = stmt
+                    .parameter_name(i)
+                    .ok_or_else(|| anyhow!("Row actions take named parameters, not '?'"))?
+                    .to_string();
+                stmt.raw_bind_parameter(i, value_for(&name)?)?;
+            }
+            changed += stmt.raw_execute()?;
+        }
+        Ok(changed)
+    })
+}
@@ -0,0 +1,257 @@
+//! Structured grid filters: per-column conditions joined by AND/OR that compile to a
+//! parameterized WHERE clause, so filtering runs in SQLite like the text search does.
+//! Values are bound as text and left to column affinity, as typed keys are elsewhere;
+//! byte values are typed as hex and compared against `hex(column)`.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::sql_text::quote_identifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOp {
+    Equals,
+    Contains,
+    Between,
+    IsNull,
+    InList,
+    /// A comma-separated tag column has the tag
+    HasTag,
+    /// The value's bytes begin with the typed hex
+    StartsWithBytes,
+    /// Compare the value's size in bytes, e.g. `>1024`
+    ByteLength,
+}
+
+impl FilterOp {
+    pub const ALL: [FilterOp; 8] = [
+        FilterOp::Equals,
+        FilterOp::Contains,
+        FilterOp::Between,
+        FilterOp::IsNull,
+        FilterOp::InList,
+        FilterOp::HasTag,
+        FilterOp::StartsWithBytes,
+        FilterOp::ByteLength,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterOp::Equals => "=",
+            FilterOp::Contains => "contains",
+            FilterOp::Between => "between",
+            FilterOp::IsNull => "is null",
+            FilterOp::InList => "in",
+            FilterOp::HasTag => "has tag",
+            FilterOp::StartsWithBytes => "starts with bytes",
+            FilterOp::ByteLength => "byte length",
+        }
+    }
+
+    /// Values the operator takes; `in` takes one comma-separated list
+    pub fn operands(&self) -> usize {
+        match self {
+            FilterOp::IsNull => 0,
+            FilterOp::Between => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let ix = Self::ALL.iter().position(|op| op == self).unwrap_or(0);
+        Self::ALL[(ix + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Connective {
+    #[default]
+    And,
+    Or,
+}
+
+impl Connective {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Connective::And => "AND",
+            Connective::Or => "OR",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            Connective::And => Connective::Or,
+            Connective::Or => Connective::And,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterCondition {
+    /// How the condition joins the one before it; ignored on the first
+    #[serde(default)]
+    pub connective: Connective,
+    pub column: String,
+    pub op: FilterOp,
+    pub value: String,
+    /// Upper bound for `between`
+    #[serde(default)]
+    pub upper: String,
+}
+
+impl FilterCondition {
+    fn to_sql(&self, params: &mut Vec<Value>) -> Result<String> {
+        let column = quote_identifier(&self.column);
+        Ok(match self.op {
+            // A hex literal compares bytes, which can't be typed as text
+            FilterOp::Equals if is_hex_literal(&self.value) => {
+                params.push(Value::String(parse_hex(&self.value)?));
+                format!("hex({}) = ?", column)
+            }
+            FilterOp::Equals => {
+                params.push(Value::String(self.value.clone()));
+                format!("{} = ?", column)
+            }
+            FilterOp::Contains => {
+                params.push(Value::String(format!("%{}%", self.value)));
+                format!("CAST({} AS TEXT) LIKE ?", column)
+            }
+            FilterOp::Between => {
+                if self.value.trim().is_empty() || self.upper.trim().is_empty() {
+                    return Err(anyhow!("{} between needs both bounds", self.column));
+                }
+                params.push(Value::String(self.value.trim().to_string()));
+                params.push(Value::String(self.upper.trim().to_string()));
+                format!("{} BETWEEN ? AND ?", column)
+            }
+            FilterOp::IsNull => format!("{} IS NULL", column),
+            FilterOp::InList => {
+                let items: Vec<&str> = self.value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect();
+                if items.is_empty() {
+                    return Err(anyhow!("{} in needs at least one value", self.column));
+                }
+                params.extend(items.iter().map(|v| Value::String(v.to_string())));
+                format!("{} IN ({})", column, vec!["?"; items.len()].join(", "))
+            }
+            FilterOp::HasTag => {
+                if self.value.trim().is_empty() {
+                    return Err(anyhow!("{} has tag needs a tag", self.column));
+                }
+                let (sql, tag) = crate::tags::has_tag_condition(&self.column, &self.value);
+                params.extend(tag);
+                sql
+            }
+            FilterOp::StartsWithBytes => {
+                let prefix = parse_hex(&self.value)?;
+                if prefix.is_empty() {
+                    return Err(anyhow!("{} starts with bytes needs some hex", self.column));
+                }
+                params.push(Value::String(format!("{}%", prefix)));
+                format!("hex({}) LIKE ?", column)
+            }
+            FilterOp::ByteLength => {
+                let value = self.value.trim();
+                let (op, number) = ["<=", ">=", "!=", "<", ">", "="]
+                    .iter()
+                    .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest)))
+                    .unwrap_or(("=", value));
+                let length: u64 = number
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("{} byte length needs a size such as 16 or >1024", self.column))?;
+                params.push(Value::from(length));
+                format!("length(CAST({} AS BLOB)) {} ?", column, op)
+            }
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self.op {
+            FilterOp::IsNull => format!("{} is null", self.column),
+            FilterOp::Between => format!("{} between {} and {}", self.column, self.value, self.upper),
+            FilterOp::InList => format!("{} in ({})", self.column, self.value),
+            op => format!("{} {} {}", self.column, op.label(), self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowFilter {
+    pub conditions: Vec<FilterCondition>,
+}
+
+impl RowFilter {
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// The conditions as one WHERE condition (without `WHERE`) and its parameters.
+    /// AND binds tighter than OR, as in SQL.
+    pub fn to_sql(&self) -> Result<Option<(String, Vec<Value>)>> {
+        let mut params = Vec::new();
+        let mut sql = String::new();
+        for (ix, condition) in self.conditions.iter().enumerate() {
+            if ix > 0 {
+                sql.push_str(&format!(" {} ", condition.connective.keyword()));
+            }
+            sql.push_str(&condition.to_sql(&mut params)?);
+        }
+        Ok((!sql.is_empty()).then_some((sql, params)))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = String::new();
+        for (ix, condition) in self.conditions.iter().enumerate() {
+            if ix > 0 {
+                text.push_str(&format!(" {} ", condition.connective.keyword()));
+            }
+            text.push_str(&condition.describe());
+        }
+        text
+    }
+}
+
+/// Whether `text` is written as bytes: `x'89504E47'` or `0x89504E47`
+pub fn is_hex_literal(text: &str) -> bool {
+    literal_digits(text.trim()).is_some()
+}
+
+/// The digits inside a hex literal
+fn literal_digits(text: &str) -> Option<&str> {
+    let prefix = text.get(..2)?;
+    if prefix.eq_ignore_ascii_case("x'") {
+        text[2..].strip_suffix('\'')
+    } else if prefix.eq_ignore_ascii_case("0x") {
+        Some(&text[2..])
+    } else {
+        None
+    }
+}
+
+/// Upper-case hex digits, as SQLite's `hex()` writes them, from a hex literal or bare
+/// hex; spaces between bytes are allowed
+pub fn parse_hex(text: &str) -> Result<String> {
+    let text = text.trim();
+    let digits: String = literal_digits(text).unwrap_or(text).chars().filter(|c| !c.is_whitespace()).collect();
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(anyhow!("'{}' is not a hex digit", c));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(anyhow!("Hex needs two digits per byte"));
+    }
+    Ok(digits.to_ascii_uppercase())
+}
+
+/// The grid's text search as a condition over `columns`, with one parameter per column
+pub fn search_condition(columns: &[String], search: &str) -> (String, Vec<Value>) {
+    if columns.is_empty() {
+        return ("0".to_string(), Vec::new());
+    }
+    let pattern = Value::String(format!("%{}%", search));
+    let sql = columns
+        .iter()
+        .map(|c| format!("CAST({} AS TEXT) LIKE ?", quote_identifier(c)))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    (format!("({})", sql), vec![pattern; columns.len()])
+}
@@ -0,0 +1,279 @@
+//! Per-row content hashes for sync pipelines. Each row's cells are hashed into a
+//! chosen column or the `__pulsar_row_hashes` shadow table, so a pipeline can tell
+//! which rows changed by comparing short hashes instead of whole rows. The hash is
+//! FNV-1a over a typed encoding of the cells, stable across platforms and releases.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::database::{CellValue, DatabaseManager};
+use crate::sql_text::quote_identifier;
+
+pub const HASH_TABLE: &str = "__pulsar_row_hashes";
+
+/// Rows read per keyset page while hashing
+const HASH_PAGE_ROWS: usize = 1000;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Where a table's hashes are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashStore {
+    /// A TEXT column of the table itself, added if missing and left out of the hash
+    Column(String),
+    ShadowTable,
+}
+
+impl HashStore {
+    /// A column name, or the shadow table when blank
+    pub fn parse(column: &str) -> Self {
+        match column.trim() {
+            "" => HashStore::ShadowTable,
+            name => HashStore::Column(name.to_string()),
+        }
+    }
+
+    fn excluded_column(&self) -> Option<&str> {
+        match self {
+            HashStore::Column(name) => Some(name),
+            HashStore::ShadowTable => None,
+        }
+    }
+}
+
+/// 16 hex digits over every cell, with type tags so `1`, `1.0` and `'1'` differ
+pub fn hash_row<'a>(cells: impl IntoIterator<Item = &'a CellValue>) -> String {
+    let mut hash = FNV_OFFSET;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+
+    for cell in cells {
+        if let Some(blob) = &cell.blob {
+            feed(b"b");
+            feed(&(blob.len() as u64).to_le_bytes());
+            feed(blob);
+            continue;
+        }
+        match &cell.value {
+            Value::Null => feed(b"n"),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => {
+                    feed(b"i");
+                    feed(&i.to_le_bytes());
+                }
+                None => {
+                    feed(b"r");
+                    feed(&n.as_f64().unwrap_or_default().to_bits().to_le_bytes());
+                }
+            },
+            Value::String(s) => {
+                feed(b"s");
+                feed(&(s.len() as u64).to_le_bytes());
+                feed(s.as_bytes());
+            }
+            other => {
+                let text = other.to_string();
+                feed(b"s");
+                feed(&(text.len() as u64).to_le_bytes());
+                feed(text.as_bytes());
+            }
+        }
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// Hash every row of a table, keyed by row id, without storing anything
+pub fn compute_hashes(db: &DatabaseManager, table_name: &str, store: &HashStore) -> Result<BTreeMap<i64, String>> {
+    let schema = db
+        .get_schema(table_name)
+        .ok_or_else(|| anyhow!("Schema not found for table: {}", table_name))?;
+    let excluded = store
+        .excluded_column()
+        .and_then(|column| schema.fields.iter().position(|f| f.name == column));
+
+    let mut hashes = BTreeMap::new();
+    let mut last_id = i64::MIN;
+    loop {
+        let rows = db.fetch_rows_after(table_name, last_id, HASH_PAGE_ROWS)?;
+        for row in &rows {
+            let cells = row
+                .cells
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| Some(*i) != excluded)
+                .map(|(_, cell)| cell);
+            hashes.insert(row.id, hash_row(cells));
+        }
+        match rows.last() {
+            Some(row) if rows.len() == HASH_PAGE_ROWS => last_id = row.id,
+            _ => break,
+        }
+    }
+    Ok(hashes)
+}
+
+/// Recompute and store a table's hashes, returning how many rows were hashed
+pub fn store_hashes(db: &DatabaseManager, table_name: &str, store: &HashStore) -> Result<usize> {
+    if let HashStore::Column(column) = store {
+        if !db.column_names(table_name)?.iter().any(|c| c == column) {
+            db.with_transaction(|tx| {
+                tx.execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} TEXT", quote_identifier(table_name), quote_identifier(column)),
+                    [],
+                )?;
+                Ok(())
+            })?;
+            db.refresh_schemas()?;
+        }
+    }
+
+    let hashes = compute_hashes(db, table_name, store)?;
+    let id_column = id_column(db, table_name);
+
+    db.with_transaction(|tx| {
+        match store {
+            HashStore::Column(column) => {
+                let mut update = tx.prepare(&format!(
+                    "UPDATE {} SET {} = ?1 WHERE {} = ?2",
+                    quote_identifier(table_name),
+                    quote_identifier(column),
+                    id_column
+                ))?;
+                for (id, hash) in &hashes {
+                    update.execute(rusqlite::params![hash, id])?;
+                }
+            }
+            HashStore::ShadowTable => {
+                tx.execute_batch(&format!(
+                    "CREATE TABLE IF NOT EXISTS {HASH_TABLE} (
+                         table_name TEXT NOT NULL,
+                         row_id INTEGER NOT NULL,
+                         hash TEXT NOT NULL,
+                         PRIMARY KEY (table_name, row_id)
+                     ) WITHOUT ROWID;"
+                ))?;
+                tx.execute(&format!("DELETE FROM {HASH_TABLE} WHERE table_name = ?1"), [table_name])?;
+                let mut insert = tx.prepare(&format!(
+                    "INSERT INTO {HASH_TABLE} (table_name, row_id, hash) VALUES (?1, ?2, ?3)"
+                ))?;
+                for (id, hash) in &hashes {
+                    insert.execute(rusqlite::params![table_name, id, hash])?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    tracing::info!("✓ Stored {} row hashes for {}", hashes.len(), table_name);
+    Ok(hashes.len())
+}
+
+fn id_column(db: &DatabaseManager, table_name: &str) -> &'static str {
+    db.get_schema(table_name)
+        .map(|schema| schema.row_id_column())
+        .unwrap_or("id")
+}
+
+/// Hashes as last stored, `None` for rows added since. Computed on the fly when the
+/// table has never been hashed.
+pub fn stored_hashes(db: &DatabaseManager, table_name: &str, store: &HashStore) -> Result<BTreeMap<i64, Option<String>>> {
+    let sql = match store {
+        HashStore::Column(column) if db.column_names(table_name)?.iter().any(|c| c == column) => {
+            let id_column = id_column(db, table_name);
+            format!("SELECT {}, {} FROM {}", id_column, quote_identifier(column), quote_identifier(table_name))
+        }
+        HashStore::ShadowTable if !db.column_names(HASH_TABLE)?.is_empty() => {
+            // Rows without a stored hash still need to show up as unhashed
+            let id_column = id_column(db, table_name);
+            format!(
+                "SELECT t.{id}, h.hash FROM {table} AS t LEFT JOIN {HASH_TABLE} AS h \
+                 ON h.table_name = '{name}' AND h.row_id = t.{id}",
+                id = id_column,
+                table = quote_identifier(table_name),
+                name = table_name.replace('\'', "''"),
+            )
+        }
+        _ => {
+            return Ok(compute_hashes(db, table_name, store)?
+                .into_iter()
+                .map(|(id, hash)| (id, Some(hash)))
+                .collect());
+        }
+    };
+
+    let output = db.fetch_query(&sql, usize::MAX)?;
+    Ok(output
+        .rows
+        .into_iter()
+        .filter_map(|row| {
+            let id = row.first()?.value.as_i64()?;
+            let hash = row.get(1).and_then(|cell| cell.value.as_str().map(String::from));
+            Some((id, hash))
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HashComparison {
+    pub table_name: String,
+    pub added: Vec<i64>,
+    pub removed: Vec<i64>,
+    /// Hashes differ, or one side hasn't been hashed since the row was added
+    pub changed: Vec<i64>,
+    pub unchanged: usize,
+}
+
+impl HashComparison {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let ids = |ids: &[i64]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+        let mut md = format!(
+            "# Row hashes: {}\n\n{} added, {} removed, {} changed, {} unchanged\n",
+            self.table_name,
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        );
+        for (label, list) in [("Added", &self.added), ("Removed", &self.removed), ("Changed", &self.changed)] {
+            if !list.is_empty() {
+                md.push_str(&format!("\n## {}\n\n{}\n", label, ids(list)));
+            }
+        }
+        md
+    }
+}
+
+/// Compare a table's stored hashes in two databases, matching rows by id
+pub fn compare_hashes(
+    before: &DatabaseManager,
+    after: &DatabaseManager,
+    table_name: &str,
+    store: &HashStore,
+) -> Result<HashComparison> {
+    let old = stored_hashes(before, table_name, store)?;
+    let new = stored_hashes(after, table_name, store)?;
+
+    let mut comparison = HashComparison {
+        table_name: table_name.to_string(),
+        ..Default::default()
+    };
+    for (id, hash) in &new {
+        match old.get(id) {
+            None => comparison.added.push(*id),
+            Some(Some(old_hash)) if hash.as_ref() == Some(old_hash) => comparison.unchanged += 1,
+            Some(_) => comparison.changed.push(*id),
+        }
+    }
+    comparison.removed = old.keys().filter(|id| !new.contains_key(id)).copied().collect();
+    Ok(comparison)
+}
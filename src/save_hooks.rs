@@ -0,0 +1,115 @@
+//! SQL the project runs around a save, such as bumping `updated_at`, recomputing
+//! aggregate tables or `PRAGMA optimize`. Pre-save hooks run before the change markers
+//! are cleared and post-save hooks after the save's own work; a failing hook rolls its
+//! phase back and aborts the save, leaving the changes marked.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::database::DatabaseManager;
+
+/// Hook file looked up in the database's directory and its ancestors
+pub const SAVE_HOOKS_FILE_NAME: &str = "save_hooks.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveHook {
+    pub label: String,
+    #[serde(default)]
+    pub sql: Option<String>,
+    /// SQL file, relative to the hook file
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+    /// Run only when one of these tables has unsaved changes; empty runs on every save
+    #[serde(default)]
+    pub tables: Vec<String>,
+}
+
+impl SaveHook {
+    pub fn applies_to(&self, changed_tables: &[String]) -> bool {
+        self.tables.is_empty()
+            || self.tables.iter().any(|t| changed_tables.iter().any(|c| c.eq_ignore_ascii_case(t)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SavePhase {
+    Pre,
+    Post,
+}
+
+impl SavePhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SavePhase::Pre => "pre-save",
+            SavePhase::Post => "post-save",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveHooks {
+    #[serde(default)]
+    pub pre_save: Vec<SaveHook>,
+    #[serde(default)]
+    pub post_save: Vec<SaveHook>,
+    /// Directory of the hook file, which script paths are relative to
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl SaveHooks {
+    /// Load the nearest hook file above `db_path`, or no hooks if the project has none
+    pub fn discover(db_path: &Path) -> Result<Self> {
+        for dir in db_path.ancestors().skip(1) {
+            let candidate = dir.join(SAVE_HOOKS_FILE_NAME);
+            if candidate.is_file() {
+                let text = std::fs::read_to_string(&candidate)?;
+                let mut hooks: Self = serde_json::from_str(&text)?;
+                hooks.dir = dir.to_path_buf();
+                return Ok(hooks);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn hooks(&self, phase: SavePhase) -> &[SaveHook] {
+        match phase {
+            SavePhase::Pre => &self.pre_save,
+            SavePhase::Post => &self.post_save,
+        }
+    }
+
+    fn sql(&self, hook: &SaveHook) -> Result<String> {
+        match (&hook.sql, &hook.script) {
+            (Some(sql), _) => Ok(sql.clone()),
+            (None, Some(script)) => Ok(std::fs::read_to_string(self.dir.join(script))?),
+            (None, None) => Err(anyhow!("Save hook '{}' has neither sql nor script", hook.label)),
+        }
+    }
+
+    /// Run the phase's hooks that apply to `changed_tables` in one transaction,
+    /// returning their labels
+    pub fn run(&self, db: &DatabaseManager, phase: SavePhase, changed_tables: &[String]) -> Result<Vec<String>> {
+        let hooks: Vec<&SaveHook> = self.hooks(phase).iter().filter(|h| h.applies_to(changed_tables)).collect();
+        if hooks.is_empty() {
+            return Ok(Vec::new());
+        }
+        if db.is_read_only() {
+            return Err(anyhow!("Database is open read-only, so {} hooks can't run", phase.label()));
+        }
+
+        // Scripts are read up front so a missing file fails before anything runs
+        let scripts = hooks
+            .iter()
+            .map(|hook| self.sql(hook).map(|sql| (hook.label.clone(), sql)))
+            .collect::<Result<Vec<_>>>()?;
+        db.with_transaction(|tx| {
+            for (label, sql) in &scripts {
+                tx.execute_batch(sql)
+                    .map_err(|e| anyhow!("{} hook '{}' failed: {}", phase.label(), label, e))?;
+            }
+            Ok(())
+        })?;
+        Ok(scripts.into_iter().map(|(label, _)| label).collect())
+    }
+}
@@ -0,0 +1,394 @@
+//! Structural comparison of two databases: tables and their columns, then indexes,
+//! views and triggers by their stored SQL. Rows aren't read, so this is quick even
+//! between large files, e.g. a shipped game database and a working copy.
+
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use crate::database::{ColumnInfo, DatabaseManager, SchemaObject};
+use crate::sql_text::{mentions, quote_identifier, words, TableDefinition};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectChange {
+    Added,
+    Removed,
+    /// The stored SQL differs beyond whitespace
+    Changed { before: String, after: String },
+}
+
+impl ObjectChange {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ObjectChange::Added => "added",
+            ObjectChange::Removed => "removed",
+            ObjectChange::Changed { .. } => "changed",
+        }
+    }
+}
+
+/// An index, view or trigger that differs
+#[derive(Debug, Clone)]
+pub struct ObjectDiff {
+    /// `index`, `view` or `trigger`
+    pub kind: String,
+    pub name: String,
+    pub change: ObjectChange,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableSchemaDiff {
+    pub table_name: String,
+    pub columns_added: Vec<ColumnInfo>,
+    pub columns_removed: Vec<ColumnInfo>,
+    /// Before and after of columns whose type, nullability, default or key changed
+    pub columns_changed: Vec<(ColumnInfo, ColumnInfo)>,
+    /// The shared columns are in a different order
+    pub reordered: bool,
+    /// Table constraints or options differ, beyond the column changes above
+    pub definition_changed: bool,
+}
+
+impl TableSchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.columns_added.is_empty()
+            && self.columns_removed.is_empty()
+            && self.columns_changed.is_empty()
+            && !self.reordered
+            && !self.definition_changed
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDiff {
+    pub tables_added: Vec<String>,
+    pub tables_removed: Vec<String>,
+    /// Tables on both sides that differ
+    pub tables: Vec<TableSchemaDiff>,
+    pub objects: Vec<ObjectDiff>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.tables_added.is_empty() && self.tables_removed.is_empty() && self.tables.is_empty() && self.objects.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "Schemas match".to_string();
+        }
+        format!(
+            "{} tables added, {} removed, {} changed; {} indexes, views or triggers differ",
+            self.tables_added.len(),
+            self.tables_removed.len(),
+            self.tables.len(),
+            self.objects.len()
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("# Schema diff\n\n");
+        for table in &self.tables_added {
+            md.push_str(&format!("- Table `{}` added\n", table));
+        }
+        for table in &self.tables_removed {
+            md.push_str(&format!("- Table `{}` removed\n", table));
+        }
+
+        for table in &self.tables {
+            md.push_str(&format!("\n## {}\n\n", table.table_name));
+            for column in &table.columns_added {
+                md.push_str(&format!("- Column `{}` added: {}\n", column.name, describe_column(column)));
+            }
+            for column in &table.columns_removed {
+                md.push_str(&format!("- Column `{}` removed\n", column.name));
+            }
+            for (before, after) in &table.columns_changed {
+                md.push_str(&format!(
+                    "- Column `{}` changed: {} → {}\n",
+                    after.name,
+                    describe_column(before),
+                    describe_column(after)
+                ));
+            }
+            if table.reordered {
+                md.push_str("- Columns reordered\n");
+            }
+            if table.definition_changed {
+                md.push_str("- Table constraints changed\n");
+            }
+        }
+
+        if !self.objects.is_empty() {
+            md.push_str("\n## Indexes, views and triggers\n\n");
+            for object in &self.objects {
+                md.push_str(&format!("- {} `{}` {}\n", object.kind, object.name, object.change.label()));
+                if let ObjectChange::Changed { before, after } = &object.change {
+                    md.push_str(&format!("\n```sql\n-- before\n{}\n-- after\n{}\n```\n\n", before, after));
+                }
+            }
+        }
+        md
+    }
+}
+
+/// A column's declared type and constraints, e.g. `TEXT NOT NULL DEFAULT 'draft'`
+pub fn describe_column(column: &ColumnInfo) -> String {
+    let mut parts = vec![if column.declared_type.is_empty() { "(no type)".to_string() } else { column.declared_type.clone() }];
+    if column.primary_key > 0 {
+        parts.push("PRIMARY KEY".to_string());
+    }
+    if column.not_null {
+        parts.push("NOT NULL".to_string());
+    }
+    if let Some(default) = &column.default_value {
+        parts.push(format!("DEFAULT {}", default));
+    }
+    parts.join(" ")
+}
+
+/// Diff `other`'s schema against the open database, which is the "after" side
+pub fn diff_schema_with_file(db: &DatabaseManager, other: &Path) -> Result<SchemaDiff> {
+    let before = DatabaseManager::open_read_only(other)?;
+    diff_schemas(&before, db)
+}
+
+pub fn diff_schemas(before: &DatabaseManager, after: &DatabaseManager) -> Result<SchemaDiff> {
+    let before_tables = before.list_tables()?;
+    let after_tables = after.list_tables()?;
+
+    let mut diff = SchemaDiff {
+        tables_added: after_tables.iter().filter(|t| !before_tables.contains(t)).cloned().collect(),
+        tables_removed: before_tables.iter().filter(|t| !after_tables.contains(t)).cloned().collect(),
+        ..Default::default()
+    };
+
+    for table in before_tables.iter().filter(|t| after_tables.contains(t)) {
+        let table_diff = diff_table_schema(before, after, table)?;
+        if !table_diff.is_empty() {
+            diff.tables.push(table_diff);
+        }
+    }
+
+    let before_objects = before.schema_objects()?;
+    let after_objects = after.schema_objects()?;
+    let find = |objects: &[SchemaObject], object: &SchemaObject| {
+        objects.iter().find(|o| o.kind == object.kind && o.name == object.name).cloned()
+    };
+    for object in &after_objects {
+        let change = match find(&before_objects, object) {
+            None => Some(ObjectChange::Added),
+            Some(old) => {
+                let (before_sql, after_sql) = (old.sql.unwrap_or_default(), object.sql.clone().unwrap_or_default());
+                (normalize(&before_sql) != normalize(&after_sql))
+                    .then_some(ObjectChange::Changed { before: before_sql, after: after_sql })
+            }
+        };
+        if let Some(change) = change {
+            diff.objects.push(ObjectDiff { kind: object.kind.clone(), name: object.name.clone(), change });
+        }
+    }
+    for object in before_objects.iter().filter(|o| find(&after_objects, o).is_none()) {
+        diff.objects.push(ObjectDiff {
+            kind: object.kind.clone(),
+            name: object.name.clone(),
+            change: ObjectChange::Removed,
+        });
+    }
+    diff.objects.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+
+    Ok(diff)
+}
+
+pub fn diff_table_schema(before: &DatabaseManager, after: &DatabaseManager, table_name: &str) -> Result<TableSchemaDiff> {
+    let old = before.column_info(table_name)?;
+    let new = after.column_info(table_name)?;
+    let find = |columns: &[ColumnInfo], name: &str| columns.iter().find(|c| c.name.eq_ignore_ascii_case(name)).cloned();
+
+    let mut diff = TableSchemaDiff {
+        table_name: table_name.to_string(),
+        columns_added: new.iter().filter(|c| find(&old, &c.name).is_none()).cloned().collect(),
+        columns_removed: old.iter().filter(|c| find(&new, &c.name).is_none()).cloned().collect(),
+        ..Default::default()
+    };
+    for column in &new {
+        if let Some(previous) = find(&old, &column.name) {
+            let same = previous.declared_type.eq_ignore_ascii_case(&column.declared_type)
+                && previous.not_null == column.not_null
+                && previous.default_value == column.default_value
+                && previous.primary_key == column.primary_key;
+            if !same {
+                diff.columns_changed.push((previous, column.clone()));
+            }
+        }
+    }
+
+    let shared = |columns: &[ColumnInfo], other: &[ColumnInfo]| -> Vec<String> {
+        columns
+            .iter()
+            .filter(|c| find(other, &c.name).is_some())
+            .map(|c| c.name.to_lowercase())
+            .collect()
+    };
+    diff.reordered = shared(&old, &new) != shared(&new, &old);
+
+    // Column changes already show in the CREATE TABLE, so only flag it when they don't explain it
+    if diff.columns_added.is_empty() && diff.columns_removed.is_empty() && diff.columns_changed.is_empty() && !diff.reordered {
+        diff.definition_changed = normalize(&before.table_sql(table_name)?) != normalize(&after.table_sql(table_name)?);
+    }
+    Ok(diff)
+}
+
+/// A migration script turning `before`'s schema into `after`'s, in the order SQLite needs:
+/// changed and orphaned views, triggers and indexes are dropped, tables removed and created,
+/// tables whose columns can't simply be appended are rebuilt copying their shared columns,
+/// then `after`'s objects are created again. Rows of removed columns and tables are lost.
+pub fn migration_script(before: &DatabaseManager, after: &DatabaseManager, diff: &SchemaDiff) -> Result<String> {
+    let after_definition = |table: &str| -> Result<TableDefinition> {
+        TableDefinition::parse(&after.table_sql(table)?).ok_or_else(|| anyhow!("Couldn't parse the definition of {}", table))
+    };
+
+    // ALTER TABLE ADD COLUMN only takes trailing columns that aren't keys or unique, with
+    // a constant default when NOT NULL
+    let mut appended: Vec<(&TableSchemaDiff, Vec<String>)> = Vec::new();
+    let mut rebuilt: Vec<&TableSchemaDiff> = Vec::new();
+    for table in &diff.tables {
+        let new_columns = after.column_info(&table.table_name)?;
+        let first_added = new_columns.len() - table.columns_added.len();
+        let columns: Vec<String> = after_definition(&table.table_name)?
+            .definitions
+            .into_iter()
+            .filter(|d| {
+                TableDefinition::column_name(d).is_some_and(|name| table.columns_added.iter().any(|a| a.name == name))
+            })
+            .collect();
+        let appendable = table.columns_removed.is_empty()
+            && table.columns_changed.is_empty()
+            && !table.reordered
+            && !table.definition_changed
+            && new_columns.iter().skip(first_added).all(|c| {
+                let constant_default = c
+                    .default_value
+                    .as_deref()
+                    .is_some_and(|d| !d.starts_with('(') && !d.to_ascii_uppercase().starts_with("CURRENT_"));
+                table.columns_added.iter().any(|a| a.name == c.name)
+                    && c.primary_key == 0
+                    && (c.default_value.is_none() || constant_default)
+                    && (!c.not_null || constant_default)
+            })
+            && columns.iter().all(|d| !words(d).iter().any(|w| w.is_keyword("UNIQUE")));
+        if appendable {
+            appended.push((table, columns));
+        } else {
+            rebuilt.push(table);
+        }
+    }
+
+    let replaced: Vec<&str> = rebuilt
+        .iter()
+        .map(|t| t.table_name.as_str())
+        .chain(diff.tables_removed.iter().map(String::as_str))
+        .collect();
+    let differs = |object: &SchemaObject| {
+        diff.objects.iter().any(|o| o.kind == object.kind && o.name == object.name)
+    };
+    let touches_replaced = |object: &SchemaObject| {
+        replaced.iter().any(|table| {
+            if object.kind == "view" {
+                object.sql.as_deref().is_some_and(|sql| mentions(sql, table))
+            } else {
+                object.table_name == *table
+            }
+        })
+    };
+    // Triggers can be on views, so views are created before triggers and dropped after them
+    let rank = |object: &SchemaObject| match object.kind.as_str() {
+        "index" => 0,
+        "view" => 1,
+        _ => 2,
+    };
+
+    let mut script = String::from("-- Migration generated from a schema diff\nPRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\n");
+
+    let mut dropped: Vec<SchemaObject> = before
+        .schema_objects()?
+        .into_iter()
+        .filter(|o| o.sql.is_some() && (differs(o) || touches_replaced(o)))
+        .collect();
+    dropped.sort_by_key(|o| std::cmp::Reverse(rank(o)));
+    if !dropped.is_empty() {
+        script.push_str("\n-- Indexes, triggers and views that change or depend on rebuilt tables\n");
+        for object in &dropped {
+            script.push_str(&format!("DROP {} IF EXISTS {};\n", object.kind.to_uppercase(), quote_identifier(&object.name)));
+        }
+    }
+
+    for table in &diff.tables_removed {
+        script.push_str(&format!("\n-- Table {} removed\nDROP TABLE {};\n", table, quote_identifier(table)));
+    }
+    for table in &diff.tables_added {
+        script.push_str(&format!("\n-- Table {} added\n{};\n", table, after.table_sql(table)?));
+    }
+
+    for table in &rebuilt {
+        let name = quote_identifier(&table.table_name);
+        let temp = quote_identifier(&format!("{}__migrate", table.table_name));
+        let old_columns = before.column_info(&table.table_name)?;
+        let shared: Vec<(String, String)> = after
+            .column_info(&table.table_name)?
+            .into_iter()
+            .filter_map(|new| {
+                old_columns
+                    .iter()
+                    .find(|old| old.name.eq_ignore_ascii_case(&new.name))
+                    .map(|old| (quote_identifier(&new.name), quote_identifier(&old.name)))
+            })
+            .collect();
+
+        script.push_str(&format!("\n-- Rebuild {}\n", table.table_name));
+        for column in table.columns_added.iter().filter(|c| c.not_null && c.default_value.is_none() && c.primary_key == 0) {
+            script.push_str(&format!("-- New column {} is NOT NULL without a default: the copy fails on existing rows\n", column.name));
+        }
+        script.push_str(&format!("CREATE TABLE {} {};\n", temp, after_definition(&table.table_name)?.to_sql()));
+        script.push_str(&format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};\n",
+            temp,
+            shared.iter().map(|(new, _)| new.as_str()).collect::<Vec<_>>().join(", "),
+            shared.iter().map(|(_, old)| old.as_str()).collect::<Vec<_>>().join(", "),
+            name
+        ));
+        script.push_str(&format!("DROP TABLE {};\nALTER TABLE {} RENAME TO {};\n", name, temp, name));
+    }
+
+    for (table, columns) in &appended {
+        script.push_str(&format!("\n-- New columns of {}\n", table.table_name));
+        for column in columns {
+            script.push_str(&format!("ALTER TABLE {} ADD COLUMN {};\n", quote_identifier(&table.table_name), column));
+        }
+    }
+
+    let mut created: Vec<SchemaObject> = after
+        .schema_objects()?
+        .into_iter()
+        .filter(|o| o.sql.is_some() && (differs(o) || touches_replaced(o)))
+        .collect();
+    created.sort_by_key(rank);
+    if !created.is_empty() {
+        script.push_str("\n-- Indexes, triggers and views as they are now\n");
+        for object in &created {
+            script.push_str(&format!("{};\n", object.sql.as_deref().unwrap_or_default()));
+        }
+    }
+
+    script.push_str("\nPRAGMA foreign_key_check;\nCOMMIT;\nPRAGMA foreign_keys=ON;\n");
+    Ok(script)
+}
+
+/// [`migration_script`] from the database file `other` to the open one
+pub fn migration_script_from_file(db: &DatabaseManager, other: &Path, diff: &SchemaDiff) -> Result<String> {
+    let before = DatabaseManager::open_read_only(other)?;
+    migration_script(&before, db, diff)
+}
+
+/// SQL with runs of whitespace collapsed, so reformatting alone isn't a change
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,451 @@
+//! Record an editing session as a replayable SQL script. Every write the editor's
+//! connections make while recording is captured with its values already bound, so a
+//! data-preparation pass done by hand can be re-run against next month's database.
+//! Only writes that committed are kept.
+//! Dates and values repeated across statements are hoisted into named parameters in
+//! the script header, where they can be changed before replaying.
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use rusqlite::types::Value;
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::database::DatabaseManager;
+
+const HEADER: &str = "-- Pulsar table editor session script";
+const PARAMS_MARKER: &str = "-- Parameters (edit before replaying):";
+
+static RECORDING: Mutex<Option<Recording>> = parking_lot::const_mutex(None);
+
+/// Writes are buffered until their transaction commits, so a rolled-back import or
+/// savepoint leaves nothing in the script. The hooks are process-wide, which assumes
+/// one connection writes at a time, as the editor's write lock ensures per database.
+#[derive(Default)]
+struct Recording {
+    /// Writes whose transaction committed, in order
+    statements: Vec<String>,
+    /// Writes of the transaction in progress
+    pending: Vec<String>,
+    /// `(name, pending writes when it began)` of each open savepoint, innermost last
+    savepoints: Vec<(String, usize)>,
+    /// Inside BEGIN ... COMMIT; otherwise each statement is its own transaction
+    explicit: bool,
+}
+
+impl Recording {
+    fn end_transaction(&mut self, committed: bool) {
+        if committed {
+            self.statements.append(&mut self.pending);
+        }
+        self.pending.clear();
+        self.savepoints.clear();
+        self.explicit = false;
+    }
+
+    fn savepoint(&self, name: &str) -> Option<usize> {
+        let name = savepoint_name(name);
+        self.savepoints.iter().rposition(|(n, _)| *n == name)
+    }
+}
+
+/// SQLite's trace hook, installed on every editor connection
+pub fn trace_statement(sql: &str) {
+    let mut recording = RECORDING.lock();
+    let Some(recording) = recording.as_mut() else {
+        return;
+    };
+    let sql = sql.trim().trim_end_matches(';').trim();
+    let words: Vec<&str> = sql.split_whitespace().collect();
+    let keyword = |i: usize| words.get(i).map(|w| w.to_ascii_uppercase()).unwrap_or_default();
+
+    match keyword(0).as_str() {
+        "BEGIN" => recording.explicit = true,
+        "SAVEPOINT" => {
+            let name = savepoint_name(words.get(1).copied().unwrap_or(""));
+            recording.savepoints.push((name, recording.pending.len()));
+        }
+        "RELEASE" => {
+            let name = if keyword(1) == "SAVEPOINT" { words.get(2) } else { words.get(1) };
+            if let Some(index) = recording.savepoint(name.copied().unwrap_or("")) {
+                recording.savepoints.truncate(index);
+            }
+        }
+        "ROLLBACK" if keyword(1) == "TO" => {
+            let name = if keyword(2) == "SAVEPOINT" { words.get(3) } else { words.get(2) };
+            if let Some(index) = recording.savepoint(name.copied().unwrap_or("")) {
+                let kept = recording.savepoints[index].1;
+                recording.pending.truncate(kept);
+                recording.savepoints.truncate(index + 1);
+            }
+        }
+        "ROLLBACK" => recording.end_transaction(false),
+        // The commit hook keeps the writes once SQLite has accepted the commit
+        "COMMIT" | "END" => {}
+        _ if is_recordable(sql) => {
+            if !recording.explicit && recording.savepoints.is_empty() {
+                // An earlier statement on its own that never committed had failed
+                recording.pending.clear();
+            }
+            recording.pending.push(sql.to_string());
+        }
+        _ => {}
+    }
+}
+
+/// SQLite's commit hook; returning `false` lets the commit go ahead
+pub fn transaction_committed() -> bool {
+    if let Some(recording) = RECORDING.lock().as_mut() {
+        recording.end_transaction(true);
+    }
+    false
+}
+
+/// SQLite's rollback hook, which also fires for rollbacks SQLite makes on its own
+pub fn transaction_rolled_back() {
+    if let Some(recording) = RECORDING.lock().as_mut() {
+        recording.end_transaction(false);
+    }
+}
+
+fn savepoint_name(name: &str) -> String {
+    name.trim_matches(|c| c == '"' || c == '`' || c == '[' || c == ']' || c == '\'').to_ascii_lowercase()
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().is_some()
+}
+
+pub fn start() {
+    *RECORDING.lock() = Some(Recording::default());
+}
+
+/// Stop recording and return the committed part of the session
+pub fn stop() -> SessionScript {
+    let statements = RECORDING.lock().take().map(|r| r.statements).unwrap_or_default();
+    SessionScript::from_statements(&statements)
+}
+
+/// Writes, minus transaction control (replay runs in its own transaction), trigger
+/// bodies and the editor's own bookkeeping tables
+fn is_recordable(sql: &str) -> bool {
+    if sql.starts_with("--") || sql.contains("__pulsar_") {
+        return false;
+    }
+    let keyword = sql
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or("")
+        .to_ascii_uppercase();
+    match keyword.as_str() {
+        "INSERT" | "UPDATE" | "DELETE" | "REPLACE" | "UPSERT" | "CREATE" | "DROP" | "ALTER" => true,
+        // Only pragma assignments change anything
+        "PRAGMA" => sql.contains('='),
+        // A WITH prefix may lead into a write
+        "WITH" => ["INSERT", "UPDATE", "DELETE"].iter().any(|w| sql.to_ascii_uppercase().contains(w)),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionScript {
+    /// `(name, SQL literal)`, bound as `:name` on replay
+    pub params: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl SessionScript {
+    pub fn from_statements(statements: &[String]) -> Self {
+        // Literals worth naming: date-like strings, and values used in more than one statement
+        let mut uses: HashMap<String, usize> = HashMap::new();
+        for sql in statements {
+            let mut seen: Vec<&str> = Vec::new();
+            for range in literals(sql) {
+                let literal = &sql[range];
+                if !seen.contains(&literal) {
+                    seen.push(literal);
+                    *uses.entry(literal.to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        let mut params: Vec<(String, String)> = Vec::new();
+        let mut dates = 0;
+        let mut values = 0;
+        let mut body = String::new();
+        for sql in statements {
+            let mut statement = sql.clone();
+            for range in literals(sql).into_iter().rev() {
+                let literal = &sql[range.clone()];
+                let name = match params.iter().find(|(_, value)| value == literal) {
+                    Some((name, _)) => name.clone(),
+                    None if is_date_literal(literal) => {
+                        dates += 1;
+                        params.push((format!("date_{}", dates), literal.to_string()));
+                        format!("date_{}", dates)
+                    }
+                    None if uses.get(literal).copied().unwrap_or(0) > 1 && !is_trivial(literal) => {
+                        values += 1;
+                        params.push((format!("value_{}", values), literal.to_string()));
+                        format!("value_{}", values)
+                    }
+                    None => continue,
+                };
+                statement.replace_range(range, &format!(":{}", name));
+            }
+            body.push_str(&statement);
+            body.push_str(";\n");
+        }
+
+        params.sort_by(|a, b| a.0.cmp(&b.0));
+        Self { params, body }
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut sql = format!("{}\n", HEADER);
+        if !self.params.is_empty() {
+            sql.push_str(PARAMS_MARKER);
+            sql.push('\n');
+            for (name, value) in &self.params {
+                sql.push_str(&format!("-- :{} = {}\n", name, value));
+            }
+        }
+        sql.push('\n');
+        sql.push_str(&self.body);
+        sql
+    }
+
+    /// Read a script written by [`Self::to_sql`], or any plain SQL script
+    pub fn parse(text: &str) -> Self {
+        let mut params = Vec::new();
+        for line in text.lines().take_while(|line| line.starts_with("--") || line.trim().is_empty()) {
+            let Some(assignment) = line.strip_prefix("-- :") else {
+                continue;
+            };
+            if let Some((name, value)) = assignment.split_once('=') {
+                params.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        Self {
+            params,
+            body: text.to_string(),
+        }
+    }
+
+    pub fn statement_count(&self) -> usize {
+        self.body.lines().filter(|line| line.trim_end().ends_with(';')).count()
+    }
+}
+
+/// Run a script against `db` in one transaction, returning the statements executed.
+/// PRAGMAs SQLite ignores inside a transaction, such as `foreign_keys`, have no effect.
+pub fn replay(db: &DatabaseManager, script: &SessionScript) -> Result<usize> {
+    let params: Vec<(String, Value)> = script
+        .params
+        .iter()
+        .map(|(name, literal)| Ok((format!(":{}", name), parse_literal(literal)?)))
+        .collect::<Result<_>>()?;
+
+    let count = db.with_transaction(|tx| {
+        let mut batch = rusqlite::Batch::new(tx, &script.body);
+        let mut count = 0;
+        while let Some(mut stmt) = batch.next()? {
+            for (name, value) in &params {
+                if let Some(index) = stmt.parameter_index(name)? {
+                    stmt.raw_bind_parameter(index, value)?;
+                }
+            }
+            stmt.raw_execute()
+                .map_err(|e| anyhow!("Statement {} failed: {}", count + 1, e))?;
+            count += 1;
+        }
+        Ok(count)
+    })?;
+    db.refresh_schemas()?;
+    Ok(count)
+}
+
+fn parse_literal(literal: &str) -> Result<Value> {
+    if let Some(inner) = literal.strip_prefix('\'').and_then(|l| l.strip_suffix('\'')) {
+        return Ok(Value::Text(inner.replace("''", "'")));
+    }
+    if let Some(hex) = literal
+        .strip_prefix(['x', 'X'])
+        .and_then(|l| l.strip_prefix('\''))
+        .and_then(|l| l.strip_suffix('\''))
+    {
+        return parse_hex(hex).map(Value::Blob);
+    }
+    if literal.eq_ignore_ascii_case("NULL") {
+        return Ok(Value::Null);
+    }
+    if let Ok(i) = literal.parse::<i64>() {
+        return Ok(Value::Integer(i));
+    }
+    literal
+        .parse::<f64>()
+        .map(Value::Real)
+        .map_err(|_| anyhow!("Parameter value {} is not a string, blob, number or NULL", literal))
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(anyhow!("Blob X'{}' has an odd number of hex digits", hex));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("Blob X'{}' is not hex", hex)))
+        .collect()
+}
+
+fn is_date_literal(literal: &str) -> bool {
+    let bytes = literal.as_bytes();
+    bytes.len() >= 12
+        && bytes[0] == b'\''
+        && bytes[1..5].iter().all(u8::is_ascii_digit)
+        && bytes[5] == b'-'
+        && bytes[6..8].iter().all(u8::is_ascii_digit)
+        && bytes[8] == b'-'
+        && bytes[9..11].iter().all(u8::is_ascii_digit)
+}
+
+/// Values too common to be worth naming: empty strings, 0 and 1
+fn is_trivial(literal: &str) -> bool {
+    matches!(literal, "''" | "0" | "1" | "-1")
+}
+
+/// Byte ranges of string and numeric literals, skipping identifiers and comments
+fn literals(sql: &str) -> Vec<Range<usize>> {
+    let bytes = sql.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\'' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == b'\'' {
+                        if bytes.get(i + 1) == Some(&b'\'') {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                found.push(start..i);
+            }
+            b'"' | b'`' | b'[' => {
+                let close = if c == b'[' { b']' } else { c };
+                i += 1;
+                while i < bytes.len() && bytes[i] != close {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'0'..=b'9' => {
+                let start = i;
+                // A leading minus belongs to the number unless it's subtraction
+                let signed = start > 0
+                    && bytes[start - 1] == b'-'
+                    && sql[..start - 1].trim_end().ends_with(['(', ',', '=', '<', '>']);
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || bytes[i] == b'_') {
+                    i += 1;
+                }
+                found.push(if signed { start - 1..i } else { start..i });
+            }
+            b'x' | b'X' if bytes.get(i + 1) == Some(&b'\'') => {
+                // A blob, X'00ff', is one literal rather than an identifier and a string
+                let start = i;
+                i += 2;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                found.push(start..i);
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b':' || c == b'@' || c == b'$' || c == b'?' => {
+                // Identifiers and parameters may contain digits
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal_texts(sql: &str) -> Vec<&str> {
+        literals(sql).into_iter().map(|range| &sql[range]).collect()
+    }
+
+    #[test]
+    fn blob_literal_is_one_token() {
+        assert_eq!(literal_texts("UPDATE t SET b = X'00ff' WHERE id = 7"), ["X'00ff'", "7"]);
+        assert_eq!(literal_texts("SELECT x'AB'"), ["x'AB'"]);
+    }
+
+    #[test]
+    fn repeated_blob_becomes_a_parameter() {
+        let script = SessionScript::from_statements(&[
+            "UPDATE t SET icon = X'cafe' WHERE id = 5".to_string(),
+            "UPDATE t SET icon = X'cafe' WHERE id = 6".to_string(),
+        ]);
+        assert_eq!(script.params, [("value_1".to_string(), "X'cafe'".to_string())]);
+        assert!(script.body.contains("icon = :value_1 WHERE id = 5"));
+        assert_eq!(parse_literal("X'cafe'").unwrap(), Value::Blob(vec![0xca, 0xfe]));
+    }
+
+    #[test]
+    fn identifiers_and_comments_are_not_literals() {
+        assert_eq!(literal_texts(r#"INSERT INTO "t2" (x1) VALUES ('it''s', -3) -- 'note'"#), ["'it''s'", "-3"]);
+        assert_eq!(literal_texts("SELECT a - 1 FROM t"), ["1"]);
+    }
+
+    #[test]
+    fn parses_literals() {
+        assert_eq!(parse_literal("'a''b'").unwrap(), Value::Text("a'b".to_string()));
+        assert_eq!(parse_literal("null").unwrap(), Value::Null);
+        assert_eq!(parse_literal("-4").unwrap(), Value::Integer(-4));
+        assert_eq!(parse_literal("2.5").unwrap(), Value::Real(2.5));
+        assert!(parse_literal("X'abc'").is_err());
+        assert!(parse_literal("abc").is_err());
+    }
+
+    #[test]
+    fn dates_are_named() {
+        let script = SessionScript::from_statements(&["DELETE FROM log WHERE at < '2026-01-01 00:00'".to_string()]);
+        assert_eq!(script.params, [("date_1".to_string(), "'2026-01-01 00:00'".to_string())]);
+        assert_eq!(script.body, "DELETE FROM log WHERE at < :date_1;\n");
+    }
+
+    #[test]
+    fn rolled_back_writes_are_dropped() {
+        start();
+        trace_statement("INSERT INTO t VALUES (1)");
+        transaction_committed();
+        trace_statement("SAVEPOINT sp");
+        trace_statement("DROP TRIGGER tr");
+        trace_statement("ROLLBACK TO sp");
+        trace_statement("RELEASE sp");
+        transaction_committed();
+        trace_statement("BEGIN");
+        trace_statement("DELETE FROM t");
+        transaction_rolled_back();
+        let script = stop();
+        assert_eq!(script.body, "INSERT INTO t VALUES (1);\n");
+    }
+}
@@ -0,0 +1,255 @@
+//! Per-user editor preferences. Unlike sidecars these follow the user rather than
+//! the database, so they live in the user's config directory.
+
+use anyhow::{Result, anyhow};
+use gpui::{px, Pixels};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ui::Size;
+
+pub const SETTINGS_FILE_NAME: &str = "table_editor.json";
+
+/// Grid text size at 100% zoom, matching `text_sm`
+const BASE_TEXT_SIZE: f32 = 14.0;
+
+/// Default cell size limit for display and inline editing
+pub const DEFAULT_MAX_CELL_BYTES: usize = 64 * 1024;
+
+pub const MIN_ZOOM_PERCENT: u16 = 60;
+pub const MAX_ZOOM_PERCENT: u16 = 250;
+pub const ZOOM_STEP_PERCENT: u16 = 10;
+
+/// Panel arrangements for different kinds of work
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutPreset {
+    /// Query tabs on top with result tables below; the explorer is hidden
+    QueryFocused,
+    /// Every tab in one group beside the explorer
+    #[default]
+    DataEntry,
+    /// Tables and queries side by side beside the explorer
+    SchemaDesign,
+}
+
+impl LayoutPreset {
+    pub const ALL: [LayoutPreset; 3] = [
+        LayoutPreset::QueryFocused,
+        LayoutPreset::DataEntry,
+        LayoutPreset::SchemaDesign,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LayoutPreset::QueryFocused => "Query focused",
+            LayoutPreset::DataEntry => "Data entry",
+            LayoutPreset::SchemaDesign => "Schema design",
+        }
+    }
+
+    pub fn shows_sidebar(&self) -> bool {
+        !matches!(self, LayoutPreset::QueryFocused)
+    }
+}
+
+/// How much room each grid row gets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RowDensity {
+    Compact,
+    #[default]
+    Normal,
+    Comfortable,
+}
+
+impl RowDensity {
+    pub const ALL: [RowDensity; 3] = [RowDensity::Compact, RowDensity::Normal, RowDensity::Comfortable];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RowDensity::Compact => "Compact",
+            RowDensity::Normal => "Normal",
+            RowDensity::Comfortable => "Comfortable",
+        }
+    }
+}
+
+/// Keybindings of the query editor's text buffer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyBindingMode {
+    #[default]
+    Standard,
+    /// Modal: starts in normal mode, `i`/`a`/`o` to insert, Escape back
+    Vim,
+    /// Ctrl/Alt movement and kill/yank
+    Emacs,
+}
+
+impl KeyBindingMode {
+    pub const ALL: [KeyBindingMode; 3] = [KeyBindingMode::Standard, KeyBindingMode::Vim, KeyBindingMode::Emacs];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyBindingMode::Standard => "Standard",
+            KeyBindingMode::Vim => "Vim",
+            KeyBindingMode::Emacs => "Emacs",
+        }
+    }
+}
+
+/// Row height and text size shared by the table grid and query results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridStyle {
+    /// Drives the row height of the virtualized table
+    pub size: Size,
+    pub cell_padding_y: Pixels,
+    pub text_size: Pixels,
+    /// Full-strength text, borders and selection instead of muted tones
+    pub high_contrast: bool,
+    /// Cells larger than this show their size instead of their content
+    pub max_cell_bytes: usize,
+}
+
+impl Default for GridStyle {
+    fn default() -> Self {
+        UserSettings::default().grid_style()
+    }
+}
+
+/// How big a single paste or save can be before it asks for confirmation, against
+/// pasting into the wrong table or saving a runaway bulk edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeThresholds {
+    pub max_rows: usize,
+    pub max_tables: usize,
+}
+
+impl Default for ChangeThresholds {
+    fn default() -> Self {
+        Self { max_rows: 1000, max_tables: 5 }
+    }
+}
+
+impl ChangeThresholds {
+    /// Why a change of `rows` rows across `tables` tables needs confirming, if it does
+    pub fn exceeded(&self, rows: usize, tables: usize) -> Option<String> {
+        let mut reasons = Vec::new();
+        if rows > self.max_rows {
+            reasons.push(format!("{} rows (limit {})", rows, self.max_rows));
+        }
+        if tables > self.max_tables {
+            reasons.push(format!("{} tables (limit {})", tables, self.max_tables));
+        }
+        (!reasons.is_empty()).then(|| reasons.join(" and "))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub layout: LayoutPreset,
+    pub density: RowDensity,
+    pub zoom_percent: u16,
+    pub high_contrast: bool,
+    /// Cells larger than this are shown as a size placeholder and edited in the
+    /// large-value editor instead of inline
+    pub max_cell_bytes: usize,
+    /// Databases opened from here start in safe mode; the Downloads folder when empty
+    pub untrusted_dirs: Vec<PathBuf>,
+    /// Canonical paths of databases the user trusted after a safe-mode open
+    pub trusted_databases: Vec<PathBuf>,
+    pub change_thresholds: ChangeThresholds,
+    pub key_bindings: KeyBindingMode,
+    /// Hold grid edits in one transaction until Commit, instead of auto-committing each
+    pub transactional_editing: bool,
+    /// Name recorded in `updated_by` and shown to co-editors; the login name when empty
+    pub editor_identity: String,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            layout: LayoutPreset::default(),
+            density: RowDensity::default(),
+            zoom_percent: 100,
+            high_contrast: false,
+            max_cell_bytes: DEFAULT_MAX_CELL_BYTES,
+            untrusted_dirs: Vec::new(),
+            trusted_databases: Vec::new(),
+            change_thresholds: ChangeThresholds::default(),
+            key_bindings: KeyBindingMode::default(),
+            transactional_editing: false,
+            editor_identity: String::new(),
+        }
+    }
+}
+
+impl UserSettings {
+    pub fn grid_style(&self) -> GridStyle {
+        let zoom = self.zoom_percent.clamp(MIN_ZOOM_PERCENT, MAX_ZOOM_PERCENT) as f32 / 100.0;
+        let (size, padding) = match self.density {
+            RowDensity::Compact => (Size::Small, 1.0),
+            RowDensity::Normal => (Size::Medium, 4.0),
+            RowDensity::Comfortable => (Size::Large, 8.0),
+        };
+        GridStyle {
+            size,
+            cell_padding_y: px(padding * zoom),
+            text_size: px(BASE_TEXT_SIZE * zoom),
+            high_contrast: self.high_contrast,
+            max_cell_bytes: self.max_cell_bytes,
+        }
+    }
+
+    /// Zoom in (`steps > 0`) or out by whole steps, staying within the supported range
+    pub fn zoom_by(&mut self, steps: i32) {
+        let zoom = self.zoom_percent as i32 + steps * ZOOM_STEP_PERCENT as i32;
+        self.zoom_percent = zoom.clamp(MIN_ZOOM_PERCENT as i32, MAX_ZOOM_PERCENT as i32) as u16;
+    }
+
+    /// Who edits are attributed to: the configured identity, else `USER`/`USERNAME`
+    pub fn identity(&self) -> String {
+        let configured = self.editor_identity.trim();
+        if !configured.is_empty() {
+            return configured.to_string();
+        }
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    /// `%APPDATA%\Pulsar` on Windows, otherwise `$XDG_CONFIG_HOME/pulsar` or `~/.config/pulsar`
+    pub fn path() -> Option<PathBuf> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join("Pulsar"))
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+                .map(|d| d.join("pulsar"))
+        };
+        dir.map(|d| d.join(SETTINGS_FILE_NAME))
+    }
+
+    /// The saved settings, or defaults when there are none or they can't be read
+    pub fn load() -> Self {
+        let Some(path) = Self::path().filter(|p| p.is_file()) else {
+            return Self::default();
+        };
+        std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(serde_json::from_str(&text)?))
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to load editor settings from {:?}: {}", path, e);
+                Self::default()
+            })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config directory for editor settings"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+//! Metadata files stored next to a database, named `<db file>.<kind>.json`
+//! (e.g. `save01.db.profile.json`), so they travel with the database.
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+
+pub fn sidecar_path(db_path: &Path, kind: &str) -> PathBuf {
+    let mut name = db_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(format!(".{}.json", kind));
+    db_path.with_file_name(name)
+}
+
+pub fn load<T: DeserializeOwned>(db_path: &Path, kind: &str) -> Result<Option<T>> {
+    let path = sidecar_path(db_path, kind);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = std::fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&text)?))
+}
+
+/// Written to a temporary file beside the sidecar, then renamed over it, so editors
+/// sharing the database never read a half-written file
+pub fn save<T: Serialize>(db_path: &Path, kind: &str, value: &T) -> Result<()> {
+    let text = serde_json::to_string_pretty(value)?;
+    let path = sidecar_path(db_path, kind);
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    std::fs::write(&temp, text)?;
+    if let Err(e) = std::fs::rename(&temp, &path) {
+        _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
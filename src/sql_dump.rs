@@ -0,0 +1,294 @@
+//! Whole-database SQL dumps, like `sqlite3 .dump`: every table's CREATE statement and
+//! rows as INSERTs, then its indexes, triggers and views, in one transaction. Also runs
+//! such a dump, or any seed script, back against a database, reporting each statement
+//! that fails.
+
+use anyhow::{Result, anyhow};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
+use crate::compression::{open_reader, CompressedWriter};
+use crate::database::{CellValue, DatabaseManager, RowSink};
+use crate::sql_text::{line_number, quote_identifier, script_statements, words, Word};
+
+const HEADER: &str = "-- Pulsar table editor SQL dump";
+
+/// Failures listed in a script report before the rest are only counted
+pub const MAX_REPORTED_FAILURES: usize = 50;
+
+/// Characters of a failing statement quoted in its report
+const EXCERPT_CHARS: usize = 80;
+
+/// Rows of `sqlite_master` and `pragma_table_xinfo` read at most
+const MAX_SCHEMA_ROWS: usize = 100_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct DumpReport {
+    pub tables: usize,
+    pub rows: usize,
+    /// Indexes, triggers and views
+    pub objects: usize,
+}
+
+/// Write `db` to `path` as an executable SQL script, gzip or zstd compressed if the
+/// name ends in `.gz` or `.zst`
+pub fn dump_database(db: &DatabaseManager, path: &Path) -> Result<DumpReport> {
+    let mut out = CompressedWriter::create(path)?;
+    writeln!(out, "{}", HEADER)?;
+    writeln!(out, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+
+    let tables = db.fetch_query(
+        "SELECT name, sql FROM sqlite_master \
+         WHERE type = 'table' AND sql IS NOT NULL AND name NOT LIKE 'sqlite_%' ORDER BY rowid",
+        MAX_SCHEMA_ROWS,
+    )?;
+    let tables: Vec<(String, String)> = tables
+        .rows
+        .iter()
+        .map(|row| (row[0].display.clone(), row[1].display.clone()))
+        .collect();
+
+    // A virtual table's shadow tables are recreated with it and hold its rows
+    let virtual_tables: Vec<&str> = tables
+        .iter()
+        .filter(|(_, sql)| is_virtual_table(sql))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let is_shadow = |name: &str| virtual_tables.iter().any(|v| name.starts_with(&format!("{}_", v)));
+
+    let mut report = DumpReport::default();
+    for (name, sql) in &tables {
+        if is_shadow(name) {
+            continue;
+        }
+        writeln!(out, "{};", sql)?;
+        report.tables += 1;
+        if is_virtual_table(sql) {
+            continue;
+        }
+        report.rows += dump_rows(db, name, &mut out)?;
+    }
+
+    let has_sequence = !db
+        .fetch_query("SELECT 1 FROM sqlite_master WHERE name = 'sqlite_sequence'", 1)?
+        .rows
+        .is_empty();
+    if has_sequence {
+        writeln!(out, "DELETE FROM sqlite_sequence;")?;
+        dump_rows(db, "sqlite_sequence", &mut out)?;
+    }
+
+    let objects = db.fetch_query(
+        "SELECT name, sql FROM sqlite_master \
+         WHERE type IN ('index', 'trigger', 'view') AND sql IS NOT NULL AND name NOT LIKE 'sqlite_%' \
+         ORDER BY rowid",
+        MAX_SCHEMA_ROWS,
+    )?;
+    for row in &objects.rows {
+        if is_shadow(&row[0].display) {
+            continue;
+        }
+        writeln!(out, "{};", row[1].display)?;
+        report.objects += 1;
+    }
+
+    writeln!(out, "COMMIT;")?;
+    out.finish()?;
+    tracing::info!(
+        "Dumped {} tables ({} rows) and {} indexes, triggers and views to {:?}",
+        report.tables, report.rows, report.objects, path
+    );
+    Ok(report)
+}
+
+fn is_virtual_table(create_sql: &str) -> bool {
+    let words = words(create_sql);
+    words.len() > 1 && words[0].is_keyword("CREATE") && words[1].is_keyword("VIRTUAL")
+}
+
+/// One INSERT per row of `table`, leaving out generated columns
+fn dump_rows(db: &DatabaseManager, table: &str, out: &mut CompressedWriter) -> Result<usize> {
+    let columns: Vec<String> = db
+        .fetch_query(
+            &format!(
+                "SELECT name FROM pragma_table_xinfo('{}') WHERE hidden = 0 ORDER BY cid",
+                table.replace('\'', "''")
+            ),
+            MAX_SCHEMA_ROWS,
+        )?
+        .rows
+        .into_iter()
+        .map(|row| row[0].display.clone())
+        .collect();
+    if columns.is_empty() {
+        return Ok(0);
+    }
+
+    let column_list = columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+    let mut sink = InsertSink {
+        out,
+        prefix: format!("INSERT INTO {} ({}) VALUES (", quote_identifier(table), column_list),
+    };
+    db.stream_query(&format!("SELECT {} FROM {}", column_list, quote_identifier(table)), &mut sink)
+}
+
+struct InsertSink<'a> {
+    out: &'a mut CompressedWriter,
+    prefix: String,
+}
+
+impl RowSink for InsertSink<'_> {
+    fn begin(&mut self, _columns: &[String]) -> Result<()> {
+        Ok(())
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        let values: Vec<String> = cells.iter().map(CellValue::sql_literal).collect();
+        writeln!(self.out, "{}{});", self.prefix, values.join(", "))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A statement of a script that SQLite rejected
+#[derive(Debug, Clone)]
+pub struct StatementFailure {
+    /// 1-based position among the script's statements
+    pub index: usize,
+    pub line: usize,
+    /// The statement's first line, shortened
+    pub excerpt: String,
+    pub error: String,
+}
+
+impl StatementFailure {
+    pub fn describe(&self) -> String {
+        format!("Statement {} (line {}): {} — {}", self.index, self.line, self.excerpt, self.error)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScriptReport {
+    pub statements: usize,
+    pub executed: usize,
+    /// BEGIN/COMMIT/ROLLBACK, which the surrounding transaction replaces
+    pub skipped: usize,
+    /// At most [`MAX_REPORTED_FAILURES`]; `failed` counts them all
+    pub failures: Vec<StatementFailure>,
+    pub failed: usize,
+    /// The transaction failed as a whole, e.g. its commit on a deferred foreign key violation
+    pub commit_error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+impl ScriptReport {
+    /// Whether the script's changes were kept
+    pub fn committed(&self) -> bool {
+        self.failed == 0 && self.commit_error.is_none()
+    }
+
+    pub fn summary(&self) -> String {
+        if let Some(error) = &self.commit_error {
+            return format!("Ran {} statements but the commit failed, nothing was kept: {}", self.executed, error);
+        }
+        if self.failed > 0 {
+            return format!(
+                "{} of {} statements failed; rolled back, nothing was kept",
+                self.failed, self.statements
+            );
+        }
+        format!("✓ Ran {} statements in {} ms", self.executed, self.elapsed_ms)
+    }
+}
+
+/// Run the script at `path`, which may be compressed
+pub fn run_script_file(db: &DatabaseManager, path: &Path) -> Result<ScriptReport> {
+    let mut text = String::new();
+    open_reader(path)?.read_to_string(&mut text)?;
+    run_script(db, text.trim_start_matches('\u{feff}'))
+}
+
+/// Run every statement of `sql` in one transaction. A failing statement is undone on
+/// its own and the rest still run, so one pass reports every failure; the transaction
+/// is then rolled back if any failed. Foreign keys are checked at commit, so a dump's
+/// tables can be filled in any order.
+pub fn run_script(db: &DatabaseManager, sql: &str) -> Result<ScriptReport> {
+    if db.is_read_only() {
+        return Err(anyhow!("The database is open read-only"));
+    }
+    let start = Instant::now();
+    let statements = script_statements(sql);
+    let mut report = ScriptReport {
+        statements: statements.len(),
+        ..ScriptReport::default()
+    };
+
+    let outcome = db.with_transaction(|tx| {
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        for (ix, range) in statements.iter().enumerate() {
+            let statement = &sql[range.clone()];
+            let words = words(statement);
+            if is_transaction_control(&words) {
+                report.skipped += 1;
+                continue;
+            }
+
+            tx.execute_batch("SAVEPOINT script_statement")?;
+            match tx.execute_batch(statement) {
+                Ok(()) => {
+                    tx.execute_batch("RELEASE script_statement")?;
+                    report.executed += 1;
+                }
+                Err(e) => {
+                    tx.execute_batch("ROLLBACK TO script_statement; RELEASE script_statement")?;
+                    report.failed += 1;
+                    if report.failures.len() < MAX_REPORTED_FAILURES {
+                        let first = words.first().map_or(0, |w| w.range.start);
+                        let text = statement[first..].lines().next().unwrap_or_default().trim();
+                        let mut excerpt: String = text.chars().take(EXCERPT_CHARS).collect();
+                        if excerpt.len() < text.len() {
+                            excerpt.push('…');
+                        }
+                        report.failures.push(StatementFailure {
+                            index: ix + 1,
+                            line: line_number(sql, range.start + first),
+                            excerpt,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        if report.failed > 0 {
+            // Dropping the transaction rolls it back
+            return Err(anyhow!("script had failing statements"));
+        }
+        Ok(())
+    });
+
+    match outcome {
+        Ok(()) => db.refresh_schemas()?,
+        Err(e) if report.failed == 0 => report.commit_error = Some(e.to_string()),
+        Err(_) => {}
+    }
+    report.elapsed_ms = start.elapsed().as_millis() as u64;
+    if report.committed() {
+        tracing::info!("✓ Ran script: {} statements in {} ms", report.executed, report.elapsed_ms);
+    } else {
+        tracing::error!("Failed to run script: {}", report.summary());
+    }
+    Ok(report)
+}
+
+fn is_transaction_control(words: &[Word]) -> bool {
+    match words.first() {
+        Some(w) if w.is_keyword("BEGIN") || w.is_keyword("COMMIT") || w.is_keyword("END") => true,
+        // `ROLLBACK TO savepoint` stays; it only undoes part of the script
+        Some(w) if w.is_keyword("ROLLBACK") => !words.iter().any(|w| w.is_keyword("TO")),
+        _ => false,
+    }
+}
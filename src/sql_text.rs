@@ -0,0 +1,659 @@
+//! Lightweight SQL text helpers for editor actions. These only track parentheses,
+//! string literals and comments; they are not a full SQL parser.
+
+use std::ops::Range;
+
+/// Byte ranges of `(`..`)` pairs (inclusive of the parentheses) and `;`-separated
+/// statements, ignoring anything inside string literals, quoted identifiers and comments
+struct Structure {
+    groups: Vec<Range<usize>>,
+    statements: Vec<Range<usize>>,
+}
+
+fn scan(sql: &str) -> Structure {
+    let bytes = sql.as_bytes();
+    let mut groups = Vec::new();
+    let mut statements = Vec::new();
+    let mut open = Vec::new();
+    let mut statement_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'[' => {
+                while i < bytes.len() && bytes[i] != b']' {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'(' => open.push(i),
+            b')' => {
+                if let Some(start) = open.pop() {
+                    groups.push(start..i + 1);
+                }
+            }
+            b';' => {
+                statements.push(statement_start..i);
+                statement_start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    statements.push(statement_start..sql.len());
+
+    Structure { groups, statements }
+}
+
+/// The innermost parenthesized SELECT around `offset`, without its parentheses,
+/// or else the whole statement containing `offset`
+pub fn subquery_at(sql: &str, offset: usize) -> Option<&str> {
+    let structure = scan(sql);
+
+    let subquery = structure
+        .groups
+        .iter()
+        .filter(|g| g.start < offset && offset < g.end)
+        .filter(|g| {
+            let inner = sql[g.start + 1..g.end - 1].trim_start().to_ascii_uppercase();
+            inner.starts_with("SELECT") || inner.starts_with("WITH")
+        })
+        .min_by_key(|g| g.len());
+    if let Some(group) = subquery {
+        return Some(sql[group.start + 1..group.end - 1].trim());
+    }
+
+    structure
+        .statements
+        .iter()
+        .find(|s| s.start <= offset && offset <= s.end)
+        .map(|s| sql[s.clone()].trim())
+        .filter(|s| !s.is_empty())
+}
+
+/// Byte ranges of the statements in a script, without their `;`, skipping ones that are
+/// only whitespace and comments. A trigger body's `;`s don't end the trigger: it runs on
+/// to the `;` after the `END` closing its `BEGIN`, past any `CASE … END` inside.
+pub fn script_statements(sql: &str) -> Vec<Range<usize>> {
+    let mut statements: Vec<Range<usize>> = Vec::new();
+    let mut open_trigger: Option<usize> = None;
+    for range in scan(sql).statements {
+        let start = open_trigger.take().unwrap_or(range.start);
+        let statement = start..range.end;
+        let words = words(&sql[statement.clone()]);
+        if words.is_empty() {
+            continue;
+        }
+        let keyword_at = |ix: usize, keyword: &str| words.get(ix).is_some_and(|w| w.is_keyword(keyword));
+        let is_trigger = keyword_at(0, "CREATE")
+            && (keyword_at(1, "TRIGGER") || ((keyword_at(1, "TEMP") || keyword_at(1, "TEMPORARY")) && keyword_at(2, "TRIGGER")));
+        let ended = words.last().is_some_and(|w| w.is_keyword("END")) && open_blocks(&words) == 0;
+        if is_trigger && !ended && range.end < sql.len() {
+            open_trigger = Some(start);
+            continue;
+        }
+        statements.push(statement);
+    }
+    statements
+}
+
+/// How many `BEGIN` and `CASE` blocks are still waiting for their `END`
+fn open_blocks(words: &[Word]) -> i32 {
+    words.iter().fold(0, |depth, w| {
+        if w.is_keyword("BEGIN") || w.is_keyword("CASE") {
+            depth + 1
+        } else if w.is_keyword("END") {
+            depth - 1
+        } else {
+            depth
+        }
+    })
+}
+
+/// The 1-based line `offset` falls on
+pub fn line_number(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Convert a UTF-16 offset, as used by input handlers, to a byte offset into `text`
+pub fn utf16_to_byte_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (byte, c) in text.char_indices() {
+        if utf16 >= utf16_offset {
+            return byte;
+        }
+        utf16 += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Words SQLite treats as keywords, which can't be used as bare identifiers
+const RESERVED_WORDS: &[&str] = &[
+    "ABORT", "ACTION", "ADD", "AFTER", "ALL", "ALTER", "ANALYZE", "AND", "AS", "ASC",
+    "ATTACH", "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE", "CAST",
+    "CHECK", "COLLATE", "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS",
+    "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "DATABASE", "DEFAULT", "DEFERRABLE",
+    "DEFERRED", "DELETE", "DESC", "DETACH", "DISTINCT", "DROP", "EACH", "ELSE", "END",
+    "ESCAPE", "EXCEPT", "EXCLUSIVE", "EXISTS", "EXPLAIN", "FAIL", "FOR", "FOREIGN", "FROM",
+    "FULL", "GLOB", "GROUP", "HAVING", "IF", "IGNORE", "IMMEDIATE", "IN", "INDEX", "INDEXED",
+    "INITIALLY", "INNER", "INSERT", "INSTEAD", "INTERSECT", "INTO", "IS", "ISNULL", "JOIN",
+    "KEY", "LEFT", "LIKE", "LIMIT", "MATCH", "NATURAL", "NO", "NOT", "NOTNULL", "NULL", "OF",
+    "OFFSET", "ON", "OR", "ORDER", "OUTER", "PLAN", "PRAGMA", "PRIMARY", "QUERY", "RAISE",
+    "RECURSIVE", "REFERENCES", "REGEXP", "REINDEX", "RELEASE", "RENAME", "REPLACE", "RESTRICT",
+    "RIGHT", "ROLLBACK", "ROW", "SAVEPOINT", "SELECT", "SET", "TABLE", "TEMP", "TEMPORARY",
+    "THEN", "TO", "TRANSACTION", "TRIGGER", "UNION", "UNIQUE", "UPDATE", "USING", "VACUUM",
+    "VALUES", "VIEW", "VIRTUAL", "WHEN", "WHERE", "WITH", "WITHOUT",
+];
+
+/// Whether `name` must be quoted to be used as an identifier
+pub fn needs_quoting(name: &str) -> bool {
+    let mut chars = name.chars();
+    let plain = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    !plain || RESERVED_WORDS.iter().any(|w| w.eq_ignore_ascii_case(name))
+}
+
+/// `name` as a double-quoted identifier, with embedded quotes doubled
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub fn quote_if_needed(name: &str) -> String {
+    if needs_quoting(name) {
+        quote_identifier(name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Closing character inserted after typing `opener`
+pub fn closing_pair(opener: char) -> Option<char> {
+    match opener {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+/// When `new` is `old` with exactly one character inserted, the byte offset and character
+pub fn single_insertion(old: &str, new: &str) -> Option<(usize, char)> {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or(old.len());
+    let inserted = new[prefix..].chars().next()?;
+
+    let mut rebuilt = String::with_capacity(new.len());
+    rebuilt.push_str(&old[..prefix]);
+    rebuilt.push(inserted);
+    rebuilt.push_str(&old[prefix..]);
+    (rebuilt == new).then_some((prefix, inserted))
+}
+
+/// A byte offset into `text` as a UTF-16 offset, as used by input handlers
+pub fn byte_to_utf16_offset(text: &str, byte_offset: usize) -> usize {
+    text[..byte_offset].encode_utf16().count()
+}
+
+/// A bare word (keyword or identifier) or quoted identifier in SQL text
+#[derive(Debug, Clone)]
+pub struct Word {
+    /// Byte range including any quotes
+    pub range: Range<usize>,
+    /// The name with quotes removed and escaped quotes collapsed
+    pub text: String,
+    pub quoted: bool,
+}
+
+impl Word {
+    /// Whether this is the keyword `keyword`; quoted words never are
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        !self.quoted && self.text.eq_ignore_ascii_case(keyword)
+    }
+}
+
+/// Every word in `sql`, skipping string literals, numbers and comments
+pub fn words(sql: &str) -> Vec<Word> {
+    let bytes = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            open @ (b'"' | b'`' | b'[') => {
+                let close = if open == b'[' { b']' } else { open };
+                let start = i;
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == close {
+                        // A doubled quote is an escaped quote inside the name
+                        if close != b']' && bytes.get(i + 1) == Some(&close) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                let inner = &sql[start + 1..i.min(sql.len())];
+                let text = if close == b']' {
+                    inner.to_string()
+                } else {
+                    let quote = close as char;
+                    inner.replace(&format!("{0}{0}", quote), &quote.to_string())
+                };
+                i = (i + 1).min(sql.len());
+                words.push(Word {
+                    range: start..i,
+                    text,
+                    quoted: true,
+                });
+            }
+            c if c.is_ascii_digit() => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$') {
+                    i += 1;
+                }
+                words.push(Word {
+                    range: start..i,
+                    text: sql[start..i].to_string(),
+                    quoted: false,
+                });
+            }
+            _ => i += 1,
+        }
+    }
+
+    words
+}
+
+/// Whether `sql` refers to the identifier `name` anywhere outside literals and comments
+pub fn mentions(sql: &str, name: &str) -> bool {
+    words(sql)
+        .iter()
+        .any(|w| if w.quoted { w.text == name } else { w.text.eq_ignore_ascii_case(name) })
+}
+
+/// Replace every reference to the identifier `old` with `new`, matching bare words
+/// case-insensitively and `"quoted"`, `` `backticked` `` or `[bracketed]` names exactly.
+/// String literals and comments are left alone. Returns the new text and the number of
+/// references replaced.
+pub fn replace_identifier(sql: &str, old: &str, new: &str) -> (String, usize) {
+    let mut out = String::with_capacity(sql.len());
+    let mut replaced = 0;
+    let mut last = 0;
+
+    for word in words(sql) {
+        let matches = if word.quoted {
+            word.text == old
+        } else {
+            word.text.eq_ignore_ascii_case(old)
+        };
+        if matches {
+            out.push_str(&sql[last..word.range.start]);
+            out.push_str(&if word.quoted { quote_identifier(new) } else { quote_if_needed(new) });
+            last = word.range.end;
+            replaced += 1;
+        }
+    }
+    out.push_str(&sql[last..]);
+
+    (out, replaced)
+}
+
+/// Words outside any parentheses, i.e. a statement's own clauses but not its subqueries' or calls'
+pub fn top_level_words(sql: &str) -> Vec<Word> {
+    let groups = scan(sql).groups;
+    words(sql)
+        .into_iter()
+        .filter(|w| !groups.iter().any(|g| g.start < w.range.start && w.range.start < g.end))
+        .collect()
+}
+
+/// `sql` split at commas outside nested parentheses, each part trimmed
+pub fn split_top_level(sql: &str) -> Vec<&str> {
+    let nested = scan(sql).groups;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for comma in comma_positions(sql) {
+        if nested.iter().any(|g| g.start < comma && comma < g.end) {
+            continue;
+        }
+        parts.push(sql[start..comma].trim());
+        start = comma + 1;
+    }
+    parts.push(sql[start..].trim());
+    parts
+}
+
+/// The parts of a `CREATE TABLE` statement: the column and constraint definitions
+/// between the outer parentheses, and anything after them such as `WITHOUT ROWID`
+#[derive(Debug, Clone)]
+pub struct TableDefinition {
+    pub definitions: Vec<String>,
+    pub options: String,
+}
+
+impl TableDefinition {
+    pub fn parse(create_sql: &str) -> Option<Self> {
+        let outer = scan(create_sql).groups.into_iter().min_by_key(|g| g.start)?;
+        let body = &create_sql[outer.start + 1..outer.end - 1];
+
+        // Split at commas outside nested parentheses, e.g. not inside `CHECK (a IN (1, 2))`
+        let mut definitions: Vec<String> = split_top_level(body).into_iter().map(str::to_string).collect();
+        definitions.retain(|d| !d.is_empty());
+
+        Some(Self {
+            definitions,
+            options: create_sql[outer.end..].trim().trim_end_matches(';').trim().to_string(),
+        })
+    }
+
+    /// Column name of a definition, or `None` for table constraints
+    pub fn column_name(definition: &str) -> Option<String> {
+        let first = words(definition).into_iter().next()?;
+        let constraint = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+            .iter()
+            .any(|k| first.is_keyword(k));
+        (!constraint).then_some(first.text)
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        self.definitions.iter().filter_map(|d| Self::column_name(d)).collect()
+    }
+
+    /// Everything after `CREATE TABLE name`, ready to create a table under another name
+    pub fn to_sql(&self) -> String {
+        let mut sql = format!("(\n    {}\n)", self.definitions.join(",\n    "));
+        if !self.options.is_empty() {
+            sql.push(' ');
+            sql.push_str(&self.options);
+        }
+        sql
+    }
+}
+
+/// Lower and upper bounds the `CHECK` constraints of a `CREATE TABLE` statement put on
+/// `column`, read from comparisons with numbers and `BETWEEN` joined by `AND`. Checks
+/// using `OR` or `NOT` are skipped, since their bounds can't be read one comparison at a
+/// time. Strict comparisons on an integer column are tightened by one.
+pub fn check_bounds(create_sql: &str, column: &str, integer: bool) -> (Option<f64>, Option<f64>) {
+    let groups = scan(create_sql).groups;
+    let mut min: Option<f64> = None;
+    let mut max: Option<f64> = None;
+    let strict = if integer { 1.0 } else { 0.0 };
+
+    for word in words(create_sql).iter().filter(|w| w.is_keyword("CHECK")) {
+        let Some(group) = groups
+            .iter()
+            .filter(|g| g.start >= word.range.end && create_sql[word.range.end..g.start].trim().is_empty())
+            .min_by_key(|g| g.start)
+        else {
+            continue;
+        };
+        let tokens = check_tokens(&create_sql[group.start + 1..group.end - 1]);
+        let keyword = |t: &CheckToken, k: &str| matches!(t, CheckToken::Word(w, false) if w.eq_ignore_ascii_case(k));
+        if tokens.iter().any(|t| keyword(t, "OR") || keyword(t, "NOT")) {
+            continue;
+        }
+        let is_column = |t: &CheckToken| match t {
+            CheckToken::Word(w, quoted) => if *quoted { w == column } else { w.eq_ignore_ascii_case(column) },
+            _ => false,
+        };
+
+        let mut raise_min = |n: f64| min = Some(min.map_or(n, |m| m.max(n)));
+        let mut lower_max = |n: f64| max = Some(max.map_or(n, |m| m.min(n)));
+        for i in 0..tokens.len() {
+            match &tokens[i..] {
+                [c, between, CheckToken::Number(lo), and, CheckToken::Number(hi), ..]
+                    if is_column(c) && keyword(between, "BETWEEN") && keyword(and, "AND") =>
+                {
+                    raise_min(*lo);
+                    lower_max(*hi);
+                }
+                [c, CheckToken::Op(op), CheckToken::Number(n), ..] if is_column(c) => match op.as_str() {
+                    ">=" => raise_min(*n),
+                    ">" => raise_min(n + strict),
+                    "<=" => lower_max(*n),
+                    "<" => lower_max(n - strict),
+                    "=" | "==" => {
+                        raise_min(*n);
+                        lower_max(*n);
+                    }
+                    _ => {}
+                },
+                [CheckToken::Number(n), CheckToken::Op(op), c, ..] if is_column(c) => match op.as_str() {
+                    "<=" => raise_min(*n),
+                    "<" => raise_min(n + strict),
+                    ">=" => lower_max(*n),
+                    ">" => lower_max(n - strict),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    (min, max)
+}
+
+/// A token of a `CHECK` expression; words carry whether they were quoted
+#[derive(Debug, Clone, PartialEq)]
+enum CheckToken {
+    Word(String, bool),
+    Number(f64),
+    Op(String),
+    Other,
+}
+
+fn check_tokens(expr: &str) -> Vec<CheckToken> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // A minus sign starts a number unless it follows an operand
+        let after_operand = match tokens.last() {
+            Some(CheckToken::Number(_)) => true,
+            Some(CheckToken::Word(w, quoted)) => *quoted || !(w.eq_ignore_ascii_case("AND") || w.eq_ignore_ascii_case("BETWEEN")),
+            _ => false,
+        };
+        let negative = c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()) && !after_operand;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || negative || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(text.parse().map(CheckToken::Number).unwrap_or(CheckToken::Other));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                i += 1;
+            }
+            tokens.push(CheckToken::Word(chars[start..i].iter().collect(), false));
+        } else if matches!(c, '"' | '`' | '[') {
+            let close = if c == '[' { ']' } else { c };
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != close {
+                i += 1;
+            }
+            tokens.push(CheckToken::Word(chars[start..i.min(chars.len())].iter().collect(), true));
+            i += 1;
+        } else if c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i += 1;
+            tokens.push(CheckToken::Other);
+        } else if matches!(c, '<' | '>' | '=' | '!') {
+            let two = chars.get(i + 1).is_some_and(|n| matches!(n, '=' | '>'));
+            let len = if two { 2 } else { 1 };
+            tokens.push(CheckToken::Op(chars[i..i + len].iter().collect()));
+            i += len;
+        } else {
+            tokens.push(CheckToken::Other);
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Byte offsets of commas outside string literals, quoted identifiers and comments
+fn comma_positions(sql: &str) -> Vec<usize> {
+    let bytes = sql.as_bytes();
+    let mut commas = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'[' => {
+                while i < bytes.len() && bytes[i] != b']' {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b',' => commas.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    commas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement_texts(sql: &str) -> Vec<&str> {
+        script_statements(sql).into_iter().map(|range| sql[range].trim()).collect()
+    }
+
+    #[test]
+    fn quotes_identifiers_only_when_needed() {
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert!(needs_quoting("order"));
+        assert!(needs_quoting("my col"));
+        assert!(needs_quoting("1st"));
+        assert!(!needs_quoting("item_1"));
+        assert_eq!(quote_if_needed("item_1"), "item_1");
+        assert_eq!(quote_if_needed("Group"), "\"Group\"");
+    }
+
+    #[test]
+    fn splits_statements_outside_literals() {
+        assert_eq!(
+            statement_texts("INSERT INTO t VALUES ('a;b'); -- ;\nSELECT \";\" FROM t;"),
+            ["INSERT INTO t VALUES ('a;b')", "-- ;\nSELECT \";\" FROM t"],
+        );
+    }
+
+    #[test]
+    fn trigger_body_stays_one_statement() {
+        let sql = "CREATE TRIGGER tr AFTER INSERT ON t BEGIN \
+                   UPDATE t SET a = CASE WHEN 1 THEN 2 END; DELETE FROM u; END; SELECT 1;";
+        let statements = statement_texts(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("CREATE TRIGGER") && statements[0].ends_with("END"));
+        assert_eq!(statements[1], "SELECT 1");
+    }
+
+    #[test]
+    fn replaces_identifiers_but_not_strings() {
+        let (sql, replaced) = replace_identifier("SELECT name, 'name' FROM t WHERE \"name\" = 1 -- name", "name", "title");
+        assert_eq!(sql, "SELECT title, 'name' FROM t WHERE \"title\" = 1 -- name");
+        assert_eq!(replaced, 2);
+        assert!(mentions("SELECT a FROM [Order Items]", "Order Items"));
+        assert!(!mentions("SELECT 'Order Items'", "Order Items"));
+    }
+
+    #[test]
+    fn parses_table_definitions() {
+        let definition =
+            TableDefinition::parse("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT CHECK (name IN ('a', 'b')), UNIQUE (name)) WITHOUT ROWID;")
+                .unwrap();
+        assert_eq!(definition.definitions.len(), 3);
+        assert_eq!(definition.options, "WITHOUT ROWID");
+        assert_eq!(definition.column_names(), ["id", "name"]);
+        assert_eq!(split_top_level("a, f(b, c), d"), ["a", "f(b, c)", "d"]);
+    }
+
+    #[test]
+    fn reads_check_bounds() {
+        let sql = "CREATE TABLE t (age INTEGER CHECK (age >= 0 AND age < 150), t REAL CHECK (t BETWEEN -5 AND 40.5))";
+        assert_eq!(check_bounds(sql, "age", true), (Some(0.0), Some(149.0)));
+        assert_eq!(check_bounds(sql, "t", false), (Some(-5.0), Some(40.5)));
+        assert_eq!(check_bounds("CREATE TABLE t (n CHECK (n > 0 OR n IS NULL))", "n", true), (None, None));
+    }
+
+    #[test]
+    fn finds_single_insertions_and_offsets() {
+        assert_eq!(single_insertion("ab", "a(b"), Some((1, '(')));
+        assert_eq!(single_insertion("ab", "abc"), Some((2, 'c')));
+        assert_eq!(single_insertion("ab", "xy"), None);
+
+        let text = "é😀a";
+        assert_eq!(byte_to_utf16_offset(text, 6), 3);
+        assert_eq!(utf16_to_byte_offset(text, 3), 6);
+        assert_eq!(line_number("a\nb\nc", 4), 3);
+    }
+}
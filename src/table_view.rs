@@ -1,37 +1,262 @@
 use gpui::{prelude::*, *};
 use ui::{
-    h_flex, v_flex, button::Button, table::{Column, ColumnSort, Table, TableDelegate, TableEvent},
-    input::{TextInput, InputState, TabSize},
-    label::Label, IconName,
-    ActiveTheme, Sizable, Size, StyleSized, StyledExt, Selectable,
+    h_flex, v_flex, button::{Button, ButtonVariants}, table::{Column, ColumnSort, Table, TableDelegate, TableEvent},
+    input::{TextInput, InputEvent, InputState, TabSize},
+    label::Label, tooltip::Tooltip, IconName,
+    ActiveTheme, Sizable, StyleSized, StyledExt, Selectable,
 };
 use std::ops::Range;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::{Duration, Instant};
 use crate::{
-    database::{DatabaseManager, RowData, CellValue},
-    reflection::TypeSchema,
-    cell_editors::{CellEditor, CellEditorView},
+    database::{DatabaseManager, RowData, RowScope, CellValue, SortKey},
+    reflection::{NumericRange, TypeSchema, ValueDecoder},
+    audio::{AudioFormat, AudioPlayer, Playback},
+    geo::{GeoPicker, GeoPoint},
+    uuids::UuidVersion,
+    cell_editors::{CellEditor, CellEditorView, CellPattern, NumericEditor, TagEditor, SLIDER_STEPS},
+    interop::RecordPayload,
+    paste::PastedRows,
+    row_actions::{self, RowActions},
+    index_hints::{self, IndexHint},
+    row_filter::{self, RowFilter},
+    settings::{ChangeThresholds, GridStyle},
 };
 
+/// Tables whose estimated size exceeds this open with a random sample instead
+const SAMPLE_THRESHOLD_ROWS: usize = 1_000_000;
+
+/// Rows shown in a sampled preview
+const SAMPLE_ROWS: usize = 200;
+
+/// Rows held in memory around the viewport; the rest of the table is fetched as it
+/// scrolls into view, so a table of millions of rows costs no more than a small one
+const WINDOW_ROWS: usize = 500;
+
+/// Rows kept loaded above the viewport, so short scrolls back up need no fetch
+const WINDOW_MARGIN: usize = 100;
+
+/// Rows per page in keyset paging mode
+pub const PAGE_ROWS: usize = 500;
+
+/// A pause longer than this starts a new type-ahead prefix
+const TYPE_AHEAD_RESET: Duration = Duration::from_millis(1000);
+
+/// Columns either side of the viewport rendered ahead of horizontal scrolling
+pub const COLUMN_OVERSCAN: usize = 4;
+
+/// Leading bytes of a BLOB shown as hex in the large-value editor
+const BLOB_PREVIEW_BYTES: usize = 256;
+
+/// A world map thumbnail with the equator, prime meridian and a dot at `point`
+fn geo_thumbnail(point: Option<GeoPoint>, cx: &App) -> Div {
+    div()
+        .relative()
+        .w(px(28.0))
+        .h(px(14.0))
+        .flex_none()
+        .overflow_hidden()
+        .rounded_sm()
+        .border_1()
+        .border_color(cx.theme().border)
+        .bg(cx.theme().muted)
+        .cursor_pointer()
+        .child(div().absolute().left_0().w_full().top(relative(0.5)).h_px().bg(cx.theme().border))
+        .child(div().absolute().top_0().h_full().left(relative(0.5)).w_px().bg(cx.theme().border))
+        .when_some(point, |this, point| {
+            let (x, y) = point.map_fraction();
+            this.child(
+                div()
+                    .absolute()
+                    .left(relative(x))
+                    .top(relative(y))
+                    .ml(px(-2.0))
+                    .mt(px(-2.0))
+                    .size(px(4.0))
+                    .rounded_full()
+                    .bg(cx.theme().accent)
+            )
+        })
+}
+
+/// Whether `col_ix` is close enough to the visible columns to be worth rendering.
+/// Before the first layout pass nothing is known, so everything renders.
+pub fn column_in_view(visible: &Range<usize>, col_ix: usize) -> bool {
+    visible.is_empty()
+        || (visible.start.saturating_sub(COLUMN_OVERSCAN)..visible.end + COLUMN_OVERSCAN).contains(&col_ix)
+}
+
+/// Edits made through this grid since the last save. Writes are auto-committed,
+/// so these are markers for review rather than a staging area.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeMarks {
+    /// Row id to the schema field indices edited in it
+    pub modified: BTreeMap<i64, BTreeSet<usize>>,
+    pub inserted: BTreeSet<i64>,
+    pub deleted: usize,
+}
+
+impl ChangeMarks {
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.inserted.is_empty() && self.deleted == 0
+    }
+
+    pub fn is_modified(&self, row_id: i64, field_idx: usize) -> bool {
+        self.modified.get(&row_id).is_some_and(|fields| fields.contains(&field_idx))
+    }
+
+    fn mark_modified(&mut self, row_id: i64, field_idx: usize) {
+        // A new row is already marked as a whole
+        if !self.inserted.contains(&row_id) {
+            self.modified.entry(row_id).or_default().insert(field_idx);
+        }
+    }
+
+    fn mark_deleted(&mut self, row_id: i64) {
+        self.modified.remove(&row_id);
+        if !self.inserted.remove(&row_id) {
+            self.deleted += 1;
+        }
+    }
+
+    /// Rows inserted, edited or deleted
+    pub fn row_count(&self) -> usize {
+        self.modified.len() + self.inserted.len() + self.deleted
+    }
+
+    /// Ids of rows still present that were inserted or edited
+    pub fn changed_ids(&self) -> Vec<i64> {
+        self.inserted.iter().chain(self.modified.keys()).copied().collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let cells: usize = self.modified.values().map(|fields| fields.len()).sum();
+        let mut parts = Vec::new();
+        if cells > 0 {
+            parts.push(format!("{} cells edited in {} rows", cells, self.modified.len()));
+        }
+        if !self.inserted.is_empty() {
+            parts.push(format!("{} rows added", self.inserted.len()));
+        }
+        if self.deleted > 0 {
+            parts.push(format!("{} rows deleted", self.deleted));
+        }
+        format!("{} since last save", parts.join(", "))
+    }
+}
+
+/// A BLOB or oversized text cell opened in the large-value editor instead of inline
+pub struct LargeCell {
+    pub row_idx: usize,
+    pub col_idx: usize,
+    pub row_id: i64,
+    pub column: String,
+    pub size: usize,
+    pub is_blob: bool,
+    /// Hex dump of the first bytes of a BLOB
+    pub preview: String,
+    /// The full text for text cells; the file path to replace a BLOB from otherwise
+    pub input: Entity<InputState>,
+    /// The column holds Markdown, so the editor has a preview tab
+    pub markdown: bool,
+    pub show_preview: bool,
+    /// A BLOB holding a sound the editor can play
+    pub audio: Option<AudioFormat>,
+}
+
+/// A row's latitude/longitude pair open in the map picker
+pub struct GeoCell {
+    pub row_idx: usize,
+    pub lat_field: usize,
+    pub lon_field: usize,
+    pub picker: GeoPicker,
+}
+
 pub struct DataTableState {
     pub editing_cell: Option<(usize, usize)>, // (row_idx, col_idx)
     pub selected_row: Option<usize>,
     pub selected_rows: Vec<usize>, // Multi-select support
+    /// Row shift-click ranges extend from
+    pub selection_anchor: Option<usize>,
     pub edit_input: Option<Entity<InputState>>,
     pub filter_text: String,
     pub validation_error: Option<String>,
+    /// The editing column's input pattern, checked as the value is typed
+    pub edit_pattern: Option<CellPattern>,
+    /// Spinner and slider for the editing column, when it has a range
+    pub edit_numeric: Option<NumericEditor>,
+    /// Chips and suggestions for the editing column, when it holds tags
+    pub edit_tags: Option<TagEditor>,
+    /// Offer UUID generation for the editing column
+    pub edit_uuid: bool,
+    edit_subscription: Option<Subscription>,
+    /// Shown while editing a column the profile marks as derived
+    pub lineage_warning: Option<String>,
     pub show_only_modified: bool,
     pub copied_cell: Option<String>,
+    /// Row ids of a random preview sample; `None` once the full table is loaded
+    pub sample_ids: Option<Vec<i64>>,
+    pub estimated_rows: usize,
+    /// Clipboard rows waiting for the user to confirm the column mapping
+    pub pending_paste: Option<PastedRows>,
+    /// The staged paste is over the row threshold and Insert was clicked once
+    pub paste_confirming: bool,
+    pub changes: ChangeMarks,
+    /// Grid column of the last clicked cell, which type-ahead searches
+    pub focused_column: Option<usize>,
+    pub type_ahead: String,
+    pub type_ahead_at: Option<Instant>,
+    /// Row the grid should scroll to on its next render, after a refresh moved it
+    pub pending_scroll: Option<usize>,
+    pub large_cell: Option<LargeCell>,
+    /// Plays the open audio BLOB
+    pub audio: AudioPlayer,
+    pub geo_cell: Option<GeoCell>,
+    /// Row whose context menu of project row actions is open
+    pub row_menu: Option<usize>,
+    /// Cells the user chose to show in full despite the size limit, by row id and field
+    pub full_cells: HashSet<(i64, usize)>,
+    /// Conditions from the filter bar, kept across refreshes until cleared
+    pub row_filter: RowFilter,
+    /// Hand-written WHERE condition, ANDed with the filter bar
+    pub raw_where: Option<String>,
+    /// ORDER BY columns, first key first; empty for rowid order. Header clicks set a
+    /// single key and shift-clicks add to (or flip, or drop from) the list.
+    pub sort: Vec<SortKey>,
+    /// Keyset paging: the page holds the rows with ids after this one. `None` when the
+    /// grid scrolls the whole table by position instead.
+    pub page_after: Option<i64>,
+    /// Version generated for the required UUID columns of new rows
+    pub uuid_version: UuidVersion,
+    /// Offer of an index after the last sort or filter scanned slowly
+    pub index_hint: Option<IndexHint>,
+    /// Column lists whose hint the user waved away, not offered again
+    pub dismissed_hints: HashSet<Vec<String>>,
 }
 
 pub struct DataTableView {
     db: DatabaseManager,
     table_name: String,
     schema: TypeSchema,
+    /// Profile decoders per schema field, for values stored in engine encodings
+    decoders: Vec<Option<ValueDecoder>>,
+    /// Schema fields holding UUIDs, judged from the first rows loaded
+    uuid_columns: Vec<bool>,
+    /// The project's row actions for this table, offered on right-click
+    row_actions: RowActions,
+    /// The loaded window of rows, starting at grid position `window_start`
     rows: Vec<RowData>,
+    window_start: usize,
+    /// First row id of windows fetched since the last reload, by grid position, so
+    /// far-off windows seek by id instead of counting from the start of the table
+    anchors: BTreeMap<usize, i64>,
     columns: Vec<Column>,
-    size: Size,
+    pub grid: GridStyle,
+    /// Paste size past which inserting asks for a second click
+    pub thresholds: ChangeThresholds,
     total_rows: usize,
     visible_range: Range<usize>,
+    visible_columns: Range<usize>,
     pub state: DataTableState,
 }
 
@@ -41,17 +266,53 @@ impl DataTableView {
             .get_schema(&table_name)
             .ok_or_else(|| anyhow::anyhow!("Schema not found for table: {}", table_name))?;
 
-        let total_rows = db.get_row_count(&table_name)?;
-        let rows = db.fetch_rows(&table_name, 0, 100)?;
+        // Counting a huge table is itself a full scan, so size it from the rowid first
+        let estimated_rows = db.estimate_row_count(&table_name)?;
+        // Views' and WITHOUT ROWID tables' rows are numbered as they're read, so there
+        // are no ids to probe
+        let sample_ids = if estimated_rows > SAMPLE_THRESHOLD_ROWS && schema.has_row_ids() {
+            Some(db.sample_row_ids(&table_name, SAMPLE_ROWS)?)
+        } else {
+            None
+        };
+
+        let (rows, total_rows) = match &sample_ids {
+            Some(ids) => {
+                let rows = db.fetch_rows_by_ids(&table_name, ids)?;
+                let count = rows.len();
+                (rows, count)
+            }
+            None => (db.fetch_rows(&table_name, 0, 100)?, db.get_row_count(&table_name)?),
+        };
 
+        // Gutter with 1-based row numbers; the rowid shows on hover
         let mut columns = vec![
-            Column::new("id", "ID")
+            Column::new("row", "#")
                 .width(60.)
                 .resizable(false)
                 .fixed(ui::table::ColumnFixed::Left)
                 .sortable(),
         ];
 
+        let decoders = schema
+            .fields
+            .iter()
+            .map(|field| db.column_hints(&table_name, &field.name).and_then(|h| h.decoder))
+            .collect();
+
+        let uuid_columns = crate::uuids::detect_columns(&schema, &rows);
+
+        let row_actions = db
+            .path()
+            .map(|path| {
+                RowActions::discover(path).unwrap_or_else(|e| {
+                    tracing::error!("Failed to load row actions: {}", e);
+                    RowActions::default()
+                })
+            })
+            .unwrap_or_default()
+            .for_table(&table_name);
+
         for field in &schema.fields {
             columns.push(
                 Column::new(&field.name, &field.name)
@@ -64,49 +325,88 @@ impl DataTableView {
             db,
             table_name,
             schema,
+            decoders,
+            uuid_columns,
+            row_actions,
             rows,
+            window_start: 0,
+            anchors: BTreeMap::new(),
             columns,
-            size: Size::default(),
+            grid: GridStyle::default(),
+            thresholds: ChangeThresholds::default(),
             total_rows,
             visible_range: 0..0,
+            visible_columns: 0..0,
             state: DataTableState {
                 editing_cell: None,
                 selected_row: None,
                 selected_rows: Vec::new(),
+                selection_anchor: None,
                 edit_input: None,
                 filter_text: String::new(),
                 validation_error: None,
+                edit_pattern: None,
+                edit_numeric: None,
+                edit_tags: None,
+                edit_uuid: false,
+                edit_subscription: None,
+                lineage_warning: None,
                 show_only_modified: false,
                 copied_cell: None,
+                sample_ids,
+                estimated_rows,
+                pending_paste: None,
+                paste_confirming: false,
+                changes: ChangeMarks::default(),
+                focused_column: None,
+                type_ahead: String::new(),
+                type_ahead_at: None,
+                pending_scroll: None,
+                large_cell: None,
+                audio: AudioPlayer::default(),
+                geo_cell: None,
+                row_menu: None,
+                full_cells: HashSet::new(),
+                row_filter: RowFilter::default(),
+                raw_where: None,
+                sort: Vec::new(),
+                page_after: None,
+                uuid_version: UuidVersion::default(),
+                index_hint: None,
+                dismissed_hints: HashSet::new(),
             },
         })
     }
 
     pub fn set_filter(&mut self, filter: String) -> anyhow::Result<()> {
-        self.state.filter_text = filter.clone();
-
-        // If filter is empty, fetch all rows
-        if filter.is_empty() {
-            self.refresh_rows(0, 100)?;
-            return Ok(());
-        }
+        self.state.filter_text = filter;
+        self.state.sample_ids = None;
+        self.state.page_after = None;
 
-        // Apply filter by fetching rows that match
-        // This is a simple implementation - could be improved with SQL WHERE clauses
-        self.refresh_rows(0, 1000)?; // Fetch more rows for filtering
-
-        // Filter rows based on text match in any column
-        self.rows.retain(|row| {
-            row.cells.iter().any(|cell| {
-                cell.display.to_lowercase().contains(&filter.to_lowercase())
-            })
-        });
-
-        Ok(())
+        // Matching happens in SQLite so huge tables are never pulled into memory
+        self.refresh_rows(0, 100)
     }
 
     pub fn start_edit_cell(&mut self, row_idx: usize, col_idx: usize, window: &mut Window, cx: &mut Context<Table<Self>>) {
-        if let Some(row) = self.rows.get(row_idx) {
+        // BLOBs can't round-trip through text, and huge text would stall the grid, so
+        // both go to the large-value editor, which can also just save them to a file.
+        // Markdown goes there too, for its preview.
+        let routed = self
+            .row(row_idx)
+            .and_then(|row| Some((row.id, row.cells.get(col_idx.checked_sub(1)?)?)))
+            .is_some_and(|(row_id, cell)| {
+                cell.blob.is_some() || self.is_oversized(row_id, col_idx - 1, cell) || self.is_markdown(col_idx - 1)
+            });
+        if routed {
+            self.open_large_cell(row_idx, col_idx, window, cx);
+            return;
+        }
+
+        if self.is_read_only() {
+            return;
+        }
+
+        if let Some(row) = self.row(row_idx) {
             if col_idx > 0 && col_idx <= self.schema.fields.len() {
                 let cell_idx = col_idx - 1;
                 if let Some(cell) = row.cells.get(cell_idx) {
@@ -121,20 +421,414 @@ impl DataTableView {
                         state
                     });
 
+                    let edit_subscription = cx.subscribe(&edit_input, |table, input, event, cx| {
+                        if matches!(event, InputEvent::Change { .. }) {
+                            let value = input.read(cx).value().to_string();
+                            let state = &mut table.delegate_mut().state;
+                            state.validation_error = state
+                                .edit_pattern
+                                .as_ref()
+                                .filter(|_| !value.trim().is_empty())
+                                .and_then(|p| p.mismatch(&value))
+                                .or_else(|| state.edit_numeric.as_ref().and_then(|n| n.out_of_range(&value)));
+                            cx.notify();
+                        }
+                    });
+
                     self.state.editing_cell = Some((row_idx, col_idx));
                     self.state.edit_input = Some(edit_input);
+                    self.state.edit_pattern = self.input_pattern(cell_idx);
+                    self.state.edit_numeric = self.numeric_editor(cell_idx);
+                    self.state.edit_tags = self.tag_editor(cell_idx);
+                    self.state.edit_uuid = self.generates_uuids(cell_idx);
+                    self.state.edit_subscription = Some(edit_subscription);
                     self.state.validation_error = None;
+                    self.state.lineage_warning = self.lineage_warning(cell_idx);
                 }
             }
         }
     }
 
+    /// Whether the cell is over the size limit and hasn't been let through
+    fn is_oversized(&self, row_id: i64, field_idx: usize, cell: &CellValue) -> bool {
+        cell.size() > self.grid.max_cell_bytes && !self.state.full_cells.contains(&(row_id, field_idx))
+    }
+
+    fn open_large_cell(&mut self, row_idx: usize, col_idx: usize, window: &mut Window, cx: &mut Context<Table<Self>>) {
+        let Some((row_id, cell)) = self
+            .row(row_idx)
+            .and_then(|row| Some((row.id, row.cells.get(col_idx - 1)?.clone())))
+        else {
+            return;
+        };
+        let is_blob = cell.blob.is_some();
+        let size = cell.size();
+        let audio = cell.blob.as_deref().and_then(AudioFormat::detect);
+        let (preview, text) = match &cell.blob {
+            Some(bytes) => (hex_preview(bytes), String::new()),
+            None => (String::new(), cell.display),
+        };
+        let input = cx.new(|cx| {
+            let mut state = if is_blob {
+                InputState::new(window, cx)
+            } else {
+                // The code editor's rope buffer copes with megabytes where a cell can't
+                InputState::new(window, cx).code_editor("text").line_number(false)
+            };
+            state.set_value(&text, window, cx);
+            state
+        });
+
+        self.cancel_edit();
+        self.state.large_cell = Some(LargeCell {
+            row_idx,
+            col_idx,
+            row_id,
+            column: self.schema.fields[col_idx - 1].name.clone(),
+            size,
+            is_blob,
+            preview,
+            input,
+            markdown: !is_blob && self.is_markdown(col_idx - 1),
+            show_preview: false,
+            audio,
+        });
+    }
+
+    /// Play the open audio BLOB from the start
+    pub fn play_large_cell(&mut self) -> anyhow::Result<()> {
+        let Some(large) = &self.state.large_cell else {
+            return Ok(());
+        };
+        let bytes = self
+            .row(large.row_idx)
+            .and_then(|row| row.cells.get(large.col_idx - 1)?.blob.clone())
+            .ok_or_else(|| anyhow::anyhow!("Row {} is no longer loaded", large.row_id))?;
+        self.state.audio.play(bytes)
+    }
+
+    pub fn toggle_audio_pause(&mut self) {
+        self.state.audio.toggle_pause();
+    }
+
+    pub fn playback(&self) -> Playback {
+        self.state.audio.playback()
+    }
+
+    /// Whether the profile marks a column as holding Markdown
+    fn is_markdown(&self, field_idx: usize) -> bool {
+        self.schema
+            .fields
+            .get(field_idx)
+            .and_then(|field| self.db.column_hints(&self.table_name, &field.name))
+            .is_some_and(|hints| hints.markdown)
+    }
+
+    /// Switch the large-value editor between its text and the rendered Markdown
+    pub fn toggle_markdown_preview(&mut self) {
+        if let Some(large) = self.state.large_cell.as_mut() {
+            large.show_preview = !large.show_preview;
+        }
+    }
+
+    /// Views can't be written, and neither can any table of a read-only database
+    pub fn is_read_only(&self) -> bool {
+        self.db.is_read_only() || self.schema.is_view
+    }
+
+    pub fn close_large_cell(&mut self) {
+        self.state.audio.stop();
+        self.state.large_cell = None;
+    }
+
+    /// Let the open text cell through the size limit so the grid shows and edits it in full
+    pub fn show_large_cell_in_full(&mut self) {
+        if let Some(large) = self.state.large_cell.take() {
+            self.state.full_cells.insert((large.row_id, large.col_idx - 1));
+        }
+    }
+
+    /// Write the large-value editor's text back to its cell
+    pub fn save_large_cell(&mut self, cx: &App) -> anyhow::Result<()> {
+        let Some(large) = &self.state.large_cell else {
+            return Ok(());
+        };
+        if large.is_blob {
+            return Err(anyhow::anyhow!("BLOBs are replaced from a file"));
+        }
+        let text = large.input.read(cx).value().to_string();
+        let value = self
+            .validate_cell_value(large.col_idx, &text)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let (row_idx, col_idx) = (large.row_idx, large.col_idx);
+        self.update_cell(row_idx, col_idx, value)?;
+        self.state.large_cell = None;
+        Ok(())
+    }
+
+    /// Save the open cell's full value to `<table>_<column>_<rowid>.bin` (or `.txt`)
+    pub fn export_large_cell(&self) -> anyhow::Result<std::path::PathBuf> {
+        let Some(large) = &self.state.large_cell else {
+            return Err(anyhow::anyhow!("No large value is open"));
+        };
+        let cell = self
+            .row(large.row_idx)
+            .and_then(|row| row.cells.get(large.col_idx - 1))
+            .ok_or_else(|| anyhow::anyhow!("Row {} is no longer loaded", large.row_id))?;
+        let extension = if large.is_blob { "bin" } else { "txt" };
+        let path = self.db.output_path(&format!(
+            "{}_{}_{}.{}",
+            self.table_name, large.column, large.row_id, extension
+        ));
+        match &cell.blob {
+            Some(bytes) => std::fs::write(&path, bytes)?,
+            None => std::fs::write(&path, &cell.display)?,
+        }
+        tracing::info!("✓ Saved {} of {}.{} to {:?}", crate::database::format_bytes(large.size), self.table_name, large.column, path);
+        Ok(path)
+    }
+
+    /// Replace the open BLOB with the contents of the file named in the editor
+    pub fn replace_blob_from_file(&mut self, cx: &App) -> anyhow::Result<()> {
+        let Some(large) = &self.state.large_cell else {
+            return Ok(());
+        };
+        let path = large.input.read(cx).value().trim().to_string();
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("Enter the path of the file to load"));
+        }
+        let bytes = std::fs::read(&path)?;
+        let (row_idx, field_idx, row_id) = (large.row_idx, large.col_idx - 1, large.row_id);
+        let key = self
+            .row(row_idx)
+            .map(|row| row.key.clone())
+            .ok_or_else(|| anyhow::anyhow!("Row {} is no longer loaded", row_id))?;
+        let field = self.schema.fields[field_idx].name.clone();
+        self.db.update_blob(&self.table_name, &key, &field, &bytes)?;
+        self.state.changes.mark_modified(row_id, field_idx);
+        self.state.audio.stop();
+        self.state.large_cell = None;
+        self.refresh_rows(self.window_start, WINDOW_ROWS)?;
+        tracing::info!("✓ Replaced {}.{} with {} from {}", self.table_name, field, crate::database::format_bytes(bytes.len()), path);
+        Ok(())
+    }
+
+    /// Placeholder of the first selected cell over the size limit, which copying the
+    /// selection would put on the clipboard whole
+    pub fn oversized_selection(&self) -> Option<String> {
+        let mut indices = self.state.selected_rows.clone();
+        indices.extend(self.state.selected_row);
+        indices.iter().filter_map(|&idx| self.row(idx)).find_map(|row| {
+            row.cells
+                .iter()
+                .enumerate()
+                .find(|(field_idx, cell)| self.is_oversized(row.id, *field_idx, cell))
+                .map(|(_, cell)| cell.size_placeholder())
+        })
+    }
+
+    /// The profile's input pattern for a column, compiled; an invalid one is logged and
+    /// not enforced
+    fn input_pattern(&self, field_idx: usize) -> Option<CellPattern> {
+        let field = self.schema.fields.get(field_idx)?;
+        let pattern = self.db.column_hints(&self.table_name, &field.name)?.pattern?;
+        CellPattern::compile(pattern)
+            .map_err(|e| tracing::error!("Failed to compile the pattern of {}.{}: {}", self.table_name, field.name, e))
+            .ok()
+    }
+
+    /// The valid range of a numeric column: the profile's, or else what its `CHECK`
+    /// constraints allow
+    fn numeric_range(&self, field_idx: usize) -> Option<NumericRange> {
+        let field = self.schema.fields.get(field_idx)?;
+        let integer = match field.sql_type {
+            crate::reflection::SqlType::Integer => true,
+            crate::reflection::SqlType::Real => false,
+            _ => return None,
+        };
+        if let Some(range) = self.db.column_hints(&self.table_name, &field.name).and_then(|h| h.range) {
+            return Some(range);
+        }
+        let sql = self.db.table_sql(&self.table_name).ok()?;
+        match crate::sql_text::check_bounds(&sql, &field.name, integer) {
+            (None, None) => None,
+            (min, max) => Some(NumericRange { min, max, step: None }),
+        }
+    }
+
+    fn numeric_editor(&self, field_idx: usize) -> Option<NumericEditor> {
+        let integer = matches!(self.schema.fields.get(field_idx)?.sql_type, crate::reflection::SqlType::Integer);
+        Some(NumericEditor {
+            range: self.numeric_range(field_idx)?,
+            integer,
+        })
+    }
+
+    /// Tag chips for a column the profile marks as holding tags
+    fn tag_editor(&self, field_idx: usize) -> Option<TagEditor> {
+        let field = self.schema.fields.get(field_idx)?;
+        if !self.db.column_hints(&self.table_name, &field.name)?.tags {
+            return None;
+        }
+        Some(TagEditor {
+            known: self.known_tags(field_idx),
+        })
+    }
+
+    /// The latitude and longitude fields of the profile's geo pair led by `field_idx`,
+    /// whose column shows the map thumbnail
+    fn geo_pair(&self, field_idx: usize) -> Option<(usize, usize)> {
+        let field = self.schema.fields.get(field_idx)?;
+        let pair = self.db.geo_pairs(&self.table_name).into_iter().find(|p| p.lat == field.name)?;
+        let lon_field = self.schema.fields.iter().position(|f| f.name == pair.lon)?;
+        Some((field_idx, lon_field))
+    }
+
+    /// Open the map picker on a row's geo pair, at null island when it has no position yet
+    pub fn open_geo_picker(&mut self, row_idx: usize, lat_field: usize, lon_field: usize) {
+        let point = self
+            .row(row_idx)
+            .and_then(|row| GeoPoint::from_cells(row.cells.get(lat_field)?, row.cells.get(lon_field)?))
+            .unwrap_or_default();
+        self.cancel_edit();
+        self.state.geo_cell = Some(GeoCell {
+            row_idx,
+            lat_field,
+            lon_field,
+            picker: GeoPicker::new(point),
+        });
+    }
+
+    /// Write the picked position to both columns
+    pub fn apply_geo_picker(&mut self) -> anyhow::Result<()> {
+        let Some(geo) = self.state.geo_cell.take() else {
+            return Ok(());
+        };
+        let point = geo.picker.point;
+        tracing::info!("Moving row {} to {}", geo.row_idx, point.to_wkt());
+        self.update_cell(geo.row_idx, geo.lat_field + 1, serde_json::json!(point.lat))?;
+        self.update_cell(geo.row_idx, geo.lon_field + 1, serde_json::json!(point.lon))
+    }
+
+    /// Open the row action menu on `row_idx`, selecting it unless it already is
+    pub fn open_row_menu(&mut self, row_idx: usize) {
+        if self.row_actions.actions.is_empty() {
+            return;
+        }
+        if !self.is_row_selected(row_idx) {
+            self.select_row(row_idx, Modifiers::default());
+        }
+        self.state.row_menu = Some(row_idx);
+    }
+
+    /// Run a row action on the row its menu was opened on, returning the rows changed
+    pub fn run_row_action(&mut self, action_idx: usize) -> anyhow::Result<usize> {
+        let Some(row_idx) = self.state.row_menu.take() else {
+            return Ok(0);
+        };
+        if self.is_read_only() {
+            return Err(anyhow::anyhow!("Database is open read-only"));
+        }
+        let action = self
+            .row_actions
+            .actions
+            .get(action_idx)
+            .ok_or_else(|| anyhow::anyhow!("No row action {}", action_idx))?;
+        let row = self.row(row_idx).ok_or_else(|| anyhow::anyhow!("Row {} is not loaded", row_idx))?;
+        let sql = self.row_actions.sql(action)?;
+        tracing::info!("Running row action '{}' on row {}", action.label, row.id);
+        let changed = row_actions::run(&self.db, &self.schema, row, &sql)?;
+        tracing::info!("✓ {}: {} rows changed", action.label, changed);
+        self.refresh_rows(self.window_start, self.rows.len().max(100))?;
+        Ok(changed)
+    }
+
+    /// Whether a column takes generated UUIDs as text; BLOB keys are edited as files
+    fn generates_uuids(&self, field_idx: usize) -> bool {
+        self.uuid_columns.get(field_idx).copied().unwrap_or(false)
+            && self
+                .schema
+                .fields
+                .get(field_idx)
+                .is_some_and(|f| matches!(f.sql_type, crate::reflection::SqlType::Text))
+    }
+
+    /// Whether new rows get a generated UUID in some required column
+    pub fn fills_uuids(&self) -> bool {
+        self.schema.fields.iter().enumerate().any(|(ix, f)| !f.nullable && self.generates_uuids(ix))
+    }
+
+    /// Replace the editing cell's value with a new UUID
+    pub fn fill_editing_uuid(&mut self, version: UuidVersion, window: &mut Window, cx: &mut App) {
+        if let Some(input) = &self.state.edit_input {
+            let value = version.generate();
+            input.update(cx, |input, cx| input.set_value(&value, window, cx));
+        }
+    }
+
+    /// Every tag used in a column, most used first
+    pub fn known_tags(&self, field_idx: usize) -> Vec<String> {
+        let Some(field) = self.schema.fields.get(field_idx) else {
+            return Vec::new();
+        };
+        crate::tags::known_tags(&self.db, &self.table_name, &field.name).unwrap_or_else(|e| {
+            tracing::error!("Failed to collect the tags of {}.{}: {}", self.table_name, field.name, e);
+            Vec::new()
+        })
+    }
+
+    /// Add `tag` to the editing cell, completing the tag being typed
+    pub fn add_tag(&mut self, tag: &str, window: &mut Window, cx: &mut App) {
+        let (Some(tags), Some(input)) = (&self.state.edit_tags, &self.state.edit_input) else {
+            return;
+        };
+        let value = tags.with_tag(&input.read(cx).value().to_string(), tag);
+        input.update(cx, |input, cx| input.set_value(&value, window, cx));
+    }
+
+    pub fn remove_tag(&mut self, ix: usize, window: &mut Window, cx: &mut App) {
+        let (Some(tags), Some(input)) = (&self.state.edit_tags, &self.state.edit_input) else {
+            return;
+        };
+        let value = tags.without_tag(&input.read(cx).value().to_string(), ix);
+        input.update(cx, |input, cx| input.set_value(&value, window, cx));
+    }
+
+    /// Move the editing cell's value by `steps` steps of its range
+    pub fn step_editing_cell(&mut self, steps: f64, window: &mut Window, cx: &mut App) {
+        let (Some(numeric), Some(input)) = (&self.state.edit_numeric, &self.state.edit_input) else {
+            return;
+        };
+        let value = numeric.step(&input.read(cx).value().to_string(), steps);
+        input.update(cx, |input, cx| input.set_value(&value, window, cx));
+        self.state.validation_error = None;
+    }
+
+    /// Set the editing cell's value from a slider position between 0 and 1
+    pub fn slide_editing_cell(&mut self, fraction: f64, window: &mut Window, cx: &mut App) {
+        let (Some(numeric), Some(input)) = (&self.state.edit_numeric, &self.state.edit_input) else {
+            return;
+        };
+        let value = numeric.at_fraction(fraction);
+        input.update(cx, |input, cx| input.set_value(&value, window, cx));
+        self.state.validation_error = None;
+    }
+
     pub fn validate_cell_value(&self, col_idx: usize, value: &str) -> Result<serde_json::Value, String> {
         if col_idx == 0 || col_idx > self.schema.fields.len() {
             return Err("Invalid column index".to_string());
         }
 
         let field = &self.schema.fields[col_idx - 1];
+        // Clearing a nullable column is always allowed
+        if !(field.nullable && value.trim().is_empty()) {
+            if let Some(mismatch) = self.input_pattern(col_idx - 1).and_then(|p| p.mismatch(value)) {
+                return Err(mismatch);
+            }
+            if let Some(outside) = self.numeric_editor(col_idx - 1).and_then(|n| n.out_of_range(value)) {
+                return Err(outside);
+            }
+        }
 
         match &field.sql_type {
             crate::reflection::SqlType::Integer => {
@@ -166,19 +860,648 @@ impl DataTableView {
         }
     }
 
+    pub fn rows_loaded(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The row at a grid position, if it is in the loaded window
+    pub fn row(&self, row_idx: usize) -> Option<&RowData> {
+        self.rows.get(row_idx.checked_sub(self.window_start)?)
+    }
+
+    /// Grid position of the loaded row with this key
+    fn position_of(&self, key: &[serde_json::Value]) -> Option<usize> {
+        self.rows.iter().position(|row| row.key == key).map(|i| i + self.window_start)
+    }
+
+    /// Whether the grid shows the whole table in id order, not a filter, sample or
+    /// only the changed rows
+    fn is_unrestricted(&self) -> bool {
+        self.state.filter_text.is_empty()
+            && self.state.sample_ids.is_none()
+            && !self.state.show_only_modified
+            && self.state.page_after.is_none()
+            && self.state.sort.is_empty()
+            && self.state.row_filter.is_empty()
+            && self.state.raw_where.is_none()
+    }
+
+    pub fn is_sampled(&self) -> bool {
+        self.state.sample_ids.is_some()
+    }
+
+    pub fn is_row_selected(&self, row_idx: usize) -> bool {
+        self.state.selected_row == Some(row_idx) || self.state.selected_rows.contains(&row_idx)
+    }
+
+    /// Click selects one row, shift-click extends from the anchor and ctrl/cmd-click toggles
+    pub fn select_row(&mut self, row_idx: usize, modifiers: Modifiers) {
+        let state = &mut self.state;
+        if modifiers.shift {
+            let anchor = state.selection_anchor.or(state.selected_row).unwrap_or(row_idx);
+            state.selected_rows = (anchor.min(row_idx)..=anchor.max(row_idx)).collect();
+            state.selection_anchor = Some(anchor);
+            state.selected_row = Some(row_idx);
+        } else if modifiers.secondary() {
+            // Carry a plain single selection into the set before toggling
+            if let Some(current) = state.selected_row {
+                if !state.selected_rows.contains(&current) {
+                    state.selected_rows.push(current);
+                }
+            }
+            if let Some(pos) = state.selected_rows.iter().position(|&r| r == row_idx) {
+                state.selected_rows.remove(pos);
+                state.selected_row = state.selected_rows.last().copied();
+            } else {
+                state.selected_rows.push(row_idx);
+                state.selected_row = Some(row_idx);
+            }
+            state.selection_anchor = Some(row_idx);
+        } else {
+            state.selected_rows = vec![row_idx];
+            state.selected_row = Some(row_idx);
+            state.selection_anchor = Some(row_idx);
+        }
+    }
+
+    /// Extend the type-ahead prefix and select the first row in the focused column
+    /// starting with it, returning that row's index in the grid
+    pub fn type_ahead(&mut self, text: &str) -> anyhow::Result<Option<usize>> {
+        let Some(col_idx) = self
+            .state
+            .focused_column
+            .filter(|&col| col > 0 && col <= self.schema.fields.len())
+        else {
+            return Ok(None);
+        };
+        if self.state.editing_cell.is_some() {
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        if self.state.type_ahead_at.is_none_or(|at| now - at > TYPE_AHEAD_RESET) {
+            self.state.type_ahead.clear();
+        }
+        self.state.type_ahead.push_str(text);
+        self.state.type_ahead_at = Some(now);
+        let prefix = self.state.type_ahead.clone();
+        let cell_idx = col_idx - 1;
+
+        let position = if self.is_unrestricted() {
+            let column = self.schema.fields[cell_idx].name.clone();
+            let Some(position) = self.db.first_row_with_prefix(&self.table_name, &column, &prefix)? else {
+                return Ok(None);
+            };
+            self.ensure_loaded(position)?;
+            position
+        } else {
+            // Filtered and sampled views only search the rows they hold
+            let found = self.rows.iter().position(|row| {
+                row.cells.get(cell_idx).is_some_and(|cell| cell.display.starts_with(&prefix))
+            });
+            let Some(position) = found else {
+                return Ok(None);
+            };
+            position + self.window_start
+        };
+
+        self.select_row(position, Modifiers::default());
+        self.state.pending_scroll = Some(position);
+        Ok(Some(position))
+    }
+
+    /// Seek to a row by primary key (comma-separated when composite) or `#rowid`,
+    /// loading the grid up to it and selecting it. Returns the row's index in the grid.
+    pub fn go_to_row(&mut self, key: &str) -> anyhow::Result<Option<usize>> {
+        let key = key.trim();
+        if key.is_empty() {
+            return Ok(None);
+        }
+        let position = match key.strip_prefix('#') {
+            Some(rowid) => {
+                let rowid = rowid.trim().parse::<i64>().map_err(|_| anyhow::anyhow!("'{}' is not a rowid", rowid))?;
+                self.db.row_position_by_rowid(&self.table_name, rowid)?
+            }
+            None => {
+                let values: Vec<serde_json::Value> = key
+                    .split(',')
+                    .map(|part| serde_json::Value::String(part.trim().to_string()))
+                    .collect();
+                self.db.row_position_by_key(&self.table_name, &values)?
+            }
+        };
+        let Some(position) = position else {
+            return Ok(None);
+        };
+
+        // Positions are in whole-table order, so leave any filter, sample or page first
+        if !self.is_unrestricted() {
+            self.state.filter_text.clear();
+            self.state.sample_ids = None;
+            self.state.show_only_modified = false;
+            self.state.page_after = None;
+            self.state.sort.clear();
+            self.state.row_filter = RowFilter::default();
+            self.state.raw_where = None;
+            self.refresh_rows(0, WINDOW_ROWS)?;
+        }
+        self.ensure_loaded(position)?;
+        self.select_row(position, Modifiers::default());
+        self.state.pending_scroll = Some(position);
+        Ok(Some(position))
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.state.selected_row = None;
+        self.state.selected_rows.clear();
+        self.state.selection_anchor = None;
+    }
+
+    /// Ids of the selected rows, in grid order
+    pub fn selected_ids(&self) -> Vec<i64> {
+        let mut indices = self.state.selected_rows.clone();
+        indices.extend(self.state.selected_row);
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .filter_map(|idx| self.row(idx).map(|row| row.id))
+            .collect()
+    }
+
+    /// The rows the grid currently shows: the whole table, or those matching the filter
+    pub fn filter_scope(&self) -> RowScope {
+        match self.where_condition() {
+            Ok(Some((condition, params))) => RowScope::Where { condition, params },
+            _ if self.state.filter_text.is_empty() => RowScope::All,
+            _ => RowScope::Matching(self.state.filter_text.clone()),
+        }
+    }
+
+    /// Apply the filter bar's conditions, checking they compile first
+    pub fn set_row_filter(&mut self, filter: RowFilter) -> anyhow::Result<()> {
+        filter.to_sql()?;
+        self.state.row_filter = filter;
+        self.state.sample_ids = None;
+        self.state.page_after = None;
+        self.clear_selection();
+        self.reload_ordered()?;
+        Ok(())
+    }
+
+    /// Filter the table by a hand-written condition; a leading `WHERE` is optional and
+    /// an empty one clears it. The condition is compiled first so mistakes are reported.
+    pub fn set_raw_where(&mut self, text: &str) -> anyhow::Result<()> {
+        let text = text.trim();
+        let condition = match text.get(..6) {
+            Some(keyword) if keyword.eq_ignore_ascii_case("where ") => text[6..].trim(),
+            _ => text,
+        };
+        let condition = condition.trim_end_matches(';').trim();
+        if condition.is_empty() {
+            self.state.raw_where = None;
+        } else {
+            self.db.check_condition(&self.table_name, condition)?;
+            self.state.raw_where = Some(condition.to_string());
+        }
+        self.state.sample_ids = None;
+        self.state.page_after = None;
+        self.clear_selection();
+        self.reload_ordered()?;
+        Ok(())
+    }
+
+    /// Replace the filter conditions, raw WHERE and sort order at once, as a saved preset
+    /// holds them, refreshing only once. Columns the table no longer has are reported.
+    pub fn set_view(&mut self, filter: RowFilter, raw_where: Option<&str>, sort: Vec<SortKey>) -> anyhow::Result<()> {
+        let fields = self.field_names();
+        let missing: Vec<&str> = filter
+            .conditions
+            .iter()
+            .map(|c| c.column.as_str())
+            .chain(sort.iter().map(|key| key.column.as_str()))
+            .filter(|column| !fields.iter().any(|f| f == column))
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("{} has no column {}", self.table_name, missing.join(", ")));
+        }
+        filter.to_sql()?;
+        let raw_where = raw_where.map(str::trim).filter(|raw| !raw.is_empty());
+        if let Some(raw) = raw_where {
+            self.db.check_condition(&self.table_name, raw)?;
+        }
+
+        self.state.row_filter = filter;
+        self.state.raw_where = raw_where.map(str::to_string);
+        self.state.sort = sort;
+        self.state.sample_ids = None;
+        self.state.page_after = None;
+        self.clear_selection();
+        self.reload_ordered()?;
+        Ok(())
+    }
+
+    /// Reload from the top after the sort or filter changed, hinting at an index when
+    /// that was slow
+    fn reload_ordered(&mut self) -> anyhow::Result<()> {
+        let started = Instant::now();
+        self.refresh_rows(0, WINDOW_ROWS)?;
+        self.state.pending_scroll = Some(0);
+        self.state.index_hint = self.index_hint(started.elapsed());
+        Ok(())
+    }
+
+    fn index_hint(&self, elapsed: Duration) -> Option<IndexHint> {
+        if self.schema.is_view || self.db.is_read_only() {
+            return None;
+        }
+        let condition = self.where_condition().ok()?;
+        let hint = index_hints::check(
+            &self.db,
+            &self.table_name,
+            &self.state.row_filter,
+            condition.as_ref().map(|(sql, params)| (sql.as_str(), params.as_slice())),
+            &self.state.sort,
+            elapsed,
+        )
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to check the query plan: {}", e);
+            None
+        })?;
+        (!self.state.dismissed_hints.contains(&hint.columns)).then_some(hint)
+    }
+
+    /// Create the index the hint offers
+    pub fn create_hinted_index(&mut self) -> anyhow::Result<()> {
+        let Some(hint) = self.state.index_hint.take() else {
+            return Ok(());
+        };
+        hint.create(&self.db)?;
+        self.db.refresh_schemas()
+    }
+
+    pub fn dismiss_index_hint(&mut self) {
+        if let Some(hint) = self.state.index_hint.take() {
+            self.state.dismissed_hints.insert(hint.columns);
+        }
+    }
+
+    /// The database file, for the sidecars beside it; `None` in memory
+    pub fn db_path(&self) -> Option<&std::path::Path> {
+        self.db.path()
+    }
+
+    /// The filter bar's conditions and the raw WHERE ANDed with the text search, or
+    /// `None` when neither is set and plain search (or no filter) applies
+    fn where_condition(&self) -> anyhow::Result<Option<(String, Vec<serde_json::Value>)>> {
+        let mut parts = Vec::new();
+        let mut params = Vec::new();
+        if let Some((condition, filter_params)) = self.state.row_filter.to_sql()? {
+            parts.push(format!("({})", condition));
+            params.extend(filter_params);
+        }
+        if let Some(raw) = &self.state.raw_where {
+            parts.push(format!("({})", raw));
+        }
+        if parts.is_empty() {
+            return Ok(None);
+        }
+        let mut condition = parts.join(" AND ");
+        if !self.state.filter_text.is_empty() {
+            let (search, search_params) = row_filter::search_condition(&self.field_names(), &self.state.filter_text);
+            condition = format!("{} AND {}", condition, search);
+            params.extend(search_params);
+        }
+        Ok(Some((condition, params)))
+    }
+
+    /// Leave the sampled preview and page through the whole table
+    pub fn load_full_table(&mut self) -> anyhow::Result<()> {
+        self.state.sample_ids = None;
+        self.refresh_rows(0, 100)
+    }
+
+    pub fn is_paged(&self) -> bool {
+        self.state.page_after.is_some()
+    }
+
+    /// Switch between scrolling the whole table and keyset paging, where each page is
+    /// fetched by seeking to an id, so paging deep into a huge table stays as fast as
+    /// the first page. Paging starts from the top loaded row.
+    pub fn set_paged(&mut self, paged: bool) -> anyhow::Result<()> {
+        if paged && !self.schema.has_row_ids() {
+            return Err(anyhow::anyhow!("Views and WITHOUT ROWID tables have no ids to page by"));
+        }
+        if paged {
+            let top = self.visible_range.start.max(self.window_start);
+            let after = self.row(top).or(self.rows.first()).map_or(i64::MIN, |row| row.id - 1);
+            self.state.filter_text.clear();
+            self.state.sample_ids = None;
+            self.state.show_only_modified = false;
+            self.state.sort.clear();
+            self.state.page_after = Some(after);
+        } else {
+            self.state.page_after = None;
+        }
+        self.clear_selection();
+        self.refresh_rows(0, PAGE_ROWS)?;
+        self.state.pending_scroll = Some(0);
+        Ok(())
+    }
+
+    /// Move to the next page. Returns false, staying put, on the last page.
+    pub fn next_page(&mut self) -> anyhow::Result<bool> {
+        let Some(last) = self.rows.last().map(|row| row.id) else {
+            return Ok(false);
+        };
+        let rows = self.db.fetch_rows_after(&self.table_name, last, PAGE_ROWS)?;
+        if rows.is_empty() {
+            return Ok(false);
+        }
+        self.show_page(last, rows);
+        Ok(true)
+    }
+
+    /// Move to the previous page. Returns false, staying put, on the first page.
+    pub fn prev_page(&mut self) -> anyhow::Result<bool> {
+        let Some(first) = self.rows.first().map(|row| row.id).or(self.state.page_after.map(|id| id + 1)) else {
+            return Ok(false);
+        };
+        let rows = self.db.fetch_rows_before(&self.table_name, first, PAGE_ROWS)?;
+        let Some(after) = rows.first().map(|row| row.id - 1) else {
+            return Ok(false);
+        };
+        self.show_page(after, rows);
+        Ok(true)
+    }
+
+    /// Start the page at the first row with an id of at least `rowid`
+    pub fn jump_to_rowid(&mut self, rowid: i64) -> anyhow::Result<()> {
+        let after = rowid.saturating_sub(1);
+        let rows = self.db.fetch_rows_after(&self.table_name, after, PAGE_ROWS)?;
+        self.show_page(after, rows);
+        Ok(())
+    }
+
+    fn show_page(&mut self, after: i64, rows: Vec<RowData>) {
+        self.state.filter_text.clear();
+        self.state.sample_ids = None;
+        self.state.show_only_modified = false;
+        self.state.sort.clear();
+        self.state.page_after = Some(after);
+        self.total_rows = rows.len();
+        self.rows = rows;
+        self.window_start = 0;
+        self.clear_selection();
+        self.state.pending_scroll = Some(0);
+    }
+
+    /// Reload rows, keeping the top visible row and the selection on the same keys
+    /// so inserts and deletes above them don't shift the viewport to other rows
     pub fn refresh_rows(&mut self, offset: usize, limit: usize) -> anyhow::Result<()> {
-        self.rows = self.db.fetch_rows(&self.table_name, offset, limit)?;
-        self.total_rows = self.db.get_row_count(&self.table_name)?;
+        let key_at = |view: &Self, idx: usize| view.row(idx).map(|row| row.key.clone());
+        let top = key_at(self, self.visible_range.start).filter(|_| !self.visible_range.is_empty());
+        let selected: Vec<Vec<serde_json::Value>> = self
+            .state
+            .selected_rows
+            .iter()
+            .filter_map(|&idx| key_at(self, idx))
+            .collect();
+        let primary = self.state.selected_row.and_then(|idx| key_at(self, idx));
+        let anchor = self.state.selection_anchor.and_then(|idx| key_at(self, idx));
+
+        // Edits can shift every position, so windows seek from scratch again
+        self.anchors.clear();
+        self.load_rows(offset, limit)?;
+
+        // The top row may now sit outside the reloaded window
+        if let Some(key) = &top {
+            if self.position_of(key).is_none() {
+                let position = if self.is_unrestricted() {
+                    self.db.row_position_by_key(&self.table_name, key)?
+                } else {
+                    None
+                };
+                self.scroll_window_to(position.unwrap_or(self.visible_range.start))?;
+            }
+        }
+
+        let new_top = top.as_deref().and_then(|key| self.position_of(key));
+        let selected_rows = selected.iter().filter_map(|key| self.position_of(key)).collect();
+        let selected_row = primary.as_deref().and_then(|key| self.position_of(key));
+        let selection_anchor = anchor.as_deref().and_then(|key| self.position_of(key));
+        if let Some(new_top) = new_top {
+            if new_top != self.visible_range.start {
+                self.state.pending_scroll = Some(new_top);
+            }
+        }
+        self.state.selected_rows = selected_rows;
+        self.state.selected_row = selected_row;
+        self.state.selection_anchor = selection_anchor;
+
+        // Every edit reloads the grid, so this is where bound widgets hear about it
+        crate::provider::notify(&self.db);
         Ok(())
     }
 
+    fn load_rows(&mut self, offset: usize, limit: usize) -> anyhow::Result<()> {
+        if self.state.show_only_modified {
+            self.rows = self.db.fetch_rows_by_ids(&self.table_name, &self.state.changes.changed_ids())?;
+            self.window_start = 0;
+            self.total_rows = self.rows.len();
+        } else if let Some(ids) = &self.state.sample_ids {
+            // Keep showing the same sample so edits stay visible
+            self.rows = self.db.fetch_rows_by_ids(&self.table_name, ids)?;
+            self.window_start = 0;
+            self.total_rows = self.rows.len();
+        } else if let Some(after) = self.state.page_after {
+            self.rows = self.db.fetch_rows_after(&self.table_name, after, PAGE_ROWS)?;
+            self.window_start = 0;
+            self.total_rows = self.rows.len();
+        } else {
+            self.total_rows = if let Some((condition, params)) = self.where_condition()? {
+                self.db.count_where(&self.table_name, &condition, &params)?
+            } else if self.state.filter_text.is_empty() {
+                self.db.get_row_count(&self.table_name)?
+            } else {
+                self.db.count_search_matches(&self.table_name, &self.state.filter_text)?
+            };
+            self.fetch_window(offset, limit.min(WINDOW_ROWS))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the loaded window with rows `offset..offset + limit`, without recounting
+    fn fetch_window(&mut self, offset: usize, limit: usize) -> anyhow::Result<()> {
+        if let Some((condition, params)) = self.where_condition()? {
+            self.rows = self.db.fetch_where(&self.table_name, &condition, &params, &self.state.sort, offset, limit)?;
+        } else if !self.state.sort.is_empty() {
+            let search = Some(self.state.filter_text.as_str()).filter(|s| !s.is_empty());
+            self.rows = self.db.fetch_sorted_rows(&self.table_name, search, &self.state.sort, offset, limit)?;
+        } else if self.state.filter_text.is_empty() {
+            let anchor = self.anchors.range(..=offset).next_back().map(|(&position, &id)| (position, id));
+            self.rows = self.db.fetch_window(&self.table_name, offset, limit, anchor)?;
+            if let Some(first) = self.rows.first() {
+                self.anchors.insert(offset, first.id);
+            }
+        } else {
+            self.rows = self.db.search_rows(&self.table_name, &self.state.filter_text, offset, limit)?;
+        }
+        self.window_start = offset;
+        Ok(())
+    }
+
+    /// Move the loaded window so it covers `position` with some rows above it
+    fn scroll_window_to(&mut self, position: usize) -> anyhow::Result<()> {
+        if self.state.show_only_modified || self.state.sample_ids.is_some() || self.state.page_after.is_some() {
+            // Id lists and pages are loaded whole
+            return Ok(());
+        }
+        self.fetch_window(position.saturating_sub(WINDOW_MARGIN), WINDOW_ROWS)
+    }
+
+    /// Load the window around `position` unless it is already loaded
+    fn ensure_loaded(&mut self, position: usize) -> anyhow::Result<()> {
+        if self.row(position).is_none() {
+            self.scroll_window_to(position)?;
+        }
+        Ok(())
+    }
+
+    /// Sort by a grid column. `extend` (shift-click) adds the column to the current
+    /// order, or changes its direction there, instead of replacing the order;
+    /// `None` drops it. The gutter column means rowid order.
+    pub fn sort_by(&mut self, col_ix: usize, direction: Option<bool>, extend: bool) -> anyhow::Result<()> {
+        let Some(field) = col_ix.checked_sub(1).and_then(|idx| self.schema.fields.get(idx)) else {
+            self.state.sort.clear();
+            return self.refresh_rows(0, WINDOW_ROWS);
+        };
+        let column = field.name.clone();
+        if !extend {
+            self.state.sort.clear();
+        }
+        match (self.state.sort.iter().position(|key| key.column == column), direction) {
+            (Some(ix), Some(descending)) => self.state.sort[ix].descending = descending,
+            (Some(ix), None) => {
+                self.state.sort.remove(ix);
+            }
+            (None, Some(descending)) => self.state.sort.push(SortKey { column, descending }),
+            (None, None) => {}
+        }
+
+        // Sorted rows come by OFFSET, so keyset pages and the sample don't apply
+        self.state.page_after = None;
+        self.state.sample_ids = None;
+        self.clear_selection();
+        self.reload_ordered()?;
+        Ok(())
+    }
+
+    /// `level ▲` or `level ▼` for a column in the sort order, numbered when there are several keys
+    fn sort_marker(&self, col_ix: usize) -> Option<String> {
+        let field = self.schema.fields.get(col_ix.checked_sub(1)?)?;
+        let level = self.state.sort.iter().position(|key| key.column == field.name)?;
+        let arrow = if self.state.sort[level].descending { "▼" } else { "▲" };
+        Some(if self.state.sort.len() > 1 { format!("{}{}", level + 1, arrow) } else { arrow.to_string() })
+    }
+
+    pub fn toggle_only_modified(&mut self) -> anyhow::Result<()> {
+        self.state.show_only_modified = !self.state.show_only_modified;
+        self.refresh_rows(0, 100)
+    }
+
+    /// Forget the change markers once the user has saved
+    pub fn mark_saved(&mut self) -> anyhow::Result<()> {
+        self.state.changes = ChangeMarks::default();
+        if self.state.show_only_modified {
+            self.state.show_only_modified = false;
+            self.refresh_rows(0, 100)?;
+        }
+        Ok(())
+    }
+
+    pub fn field_names(&self) -> Vec<String> {
+        self.schema.fields.iter().map(|f| f.name.clone()).collect()
+    }
+
+    /// Stage pasted text as inserts; nothing is written until [`Self::commit_paste`]
+    pub fn stage_paste(&mut self, text: &str) {
+        let pasted = PastedRows::parse(&self.schema, text);
+        self.state.pending_paste = (!pasted.rows.is_empty()).then_some(pasted);
+        self.state.paste_confirming = false;
+    }
+
+    /// Why the staged paste needs confirming before it's inserted, if it does
+    pub fn paste_over_threshold(&self) -> Option<String> {
+        let pasted = self.state.pending_paste.as_ref()?;
+        self.thresholds.exceeded(pasted.rows.len(), 1)
+    }
+
+    /// Selected rows as a typed payload for other Pulsar editors
+    pub fn selected_payload(&self) -> Option<RecordPayload> {
+        let mut indices = self.state.selected_rows.clone();
+        indices.extend(self.state.selected_row);
+        indices.sort_unstable();
+        indices.dedup();
+        let rows: Vec<Vec<serde_json::Value>> = indices
+            .iter()
+            .filter_map(|&idx| self.row(idx))
+            .map(|row| row.cells.iter().map(|cell| cell.value.clone()).collect())
+            .collect();
+        (!rows.is_empty()).then(|| RecordPayload::from_rows(&self.schema, &rows))
+    }
+
+    /// Stage records from another editor through the usual paste review, matched by field name
+    pub fn stage_payload(&mut self, payload: &RecordPayload) {
+        if payload.type_name != self.schema.type_name {
+            tracing::debug!(
+                "Pasting {} records into {}; fields are matched by name",
+                payload.type_name,
+                self.schema.type_name
+            );
+        }
+        self.stage_paste(&payload.to_tsv());
+    }
+
+    pub fn cycle_paste_target(&mut self, col: usize) {
+        let field_count = self.schema.fields.len();
+        if let Some(pasted) = &mut self.state.pending_paste {
+            pasted.cycle_target(col, field_count);
+        }
+    }
+
+    /// Insert the staged paste. One over the row threshold takes a second call, so the
+    /// review banner can show what's about to be written; `None` means it's waiting for it.
+    pub fn commit_paste(&mut self) -> anyhow::Result<Option<usize>> {
+        if self.paste_over_threshold().is_some() && !self.state.paste_confirming {
+            self.state.paste_confirming = true;
+            return Ok(None);
+        }
+        let Some(pasted) = self.state.pending_paste.take() else {
+            return Ok(Some(0));
+        };
+        self.state.paste_confirming = false;
+
+        let count = self.db.insert_rows(&self.table_name, &pasted.to_values(&self.schema))?;
+        self.refresh_rows(self.window_start, self.rows.len().max(100))?;
+        Ok(Some(count))
+    }
+
+    pub fn discard_paste(&mut self) {
+        self.state.pending_paste = None;
+        self.state.paste_confirming = false;
+    }
+
     pub fn add_new_row(&mut self) -> anyhow::Result<()> {
         let default_values: Vec<serde_json::Value> = self
             .schema
             .fields
             .iter()
-            .map(|field| {
-                if field.nullable {
+            .enumerate()
+            .map(|(ix, field)| {
+                if !field.nullable && self.generates_uuids(ix) {
+                    serde_json::Value::String(self.state.uuid_version.generate())
+                } else if field.nullable {
                     serde_json::Value::Null
                 } else {
                     match field.sql_type {
@@ -195,29 +1518,47 @@ impl DataTableView {
             })
             .collect();
 
-        self.db.insert_row(&self.table_name, default_values)?;
+        let id = self.db.insert_row(&self.table_name, default_values)?;
+        self.state.changes.inserted.insert(id);
         self.refresh_rows(0, 100)?;
         Ok(())
     }
 
     pub fn delete_row(&mut self, row_idx: usize) -> anyhow::Result<()> {
-        if let Some(row) = self.rows.get(row_idx) {
-            self.db.delete_row(&self.table_name, row.id)?;
+        if let Some(row) = self.row(row_idx) {
+            self.db.delete_row(&self.table_name, &row.key)?;
+            self.state.changes.mark_deleted(row.id);
             self.refresh_rows(0, 100)?;
         }
         Ok(())
     }
 
+    /// Warning for hand edits to a derived column, read from the live profile so new
+    /// annotations apply to open tabs
+    fn lineage_warning(&self, field_idx: usize) -> Option<String> {
+        let field = self.schema.fields.get(field_idx)?;
+        let lineage = self.db.column_hints(&self.table_name, &field.name)?.lineage?;
+        Some(format!(
+            "{} is derived ({}); a manual edit may be overwritten",
+            field.name,
+            lineage.describe()
+        ))
+    }
+
     pub fn update_cell(
         &mut self,
         row_idx: usize,
         col_idx: usize,
         value: serde_json::Value,
     ) -> anyhow::Result<()> {
-        if let Some(row) = self.rows.get(row_idx) {
+        if let Some(row) = self.row(row_idx) {
             if col_idx > 0 && col_idx <= self.schema.fields.len() {
                 let field = &self.schema.fields[col_idx - 1];
-                self.db.update_cell(&self.table_name, row.id, &field.name, value)?;
+                if let Some(warning) = self.lineage_warning(col_idx - 1) {
+                    tracing::warn!("{}.{}: {}", self.table_name, field.name, warning);
+                }
+                self.db.update_cell(&self.table_name, &row.key, &field.name, value)?;
+                self.state.changes.mark_modified(row.id, col_idx - 1);
                 self.refresh_rows(0, 100)?;
             }
         }
@@ -231,17 +1572,24 @@ impl DataTableView {
             // Validate the value
             match self.validate_cell_value(col_idx, &value_str) {
                 Ok(value) => {
-                    if let Some(row) = self.rows.get(row_idx) {
+                    if let Some(row) = self.row(row_idx) {
                         if col_idx > 0 && col_idx <= self.schema.fields.len() {
                             let field = &self.schema.fields[col_idx - 1];
-                            self.db.update_cell(&self.table_name, row.id, &field.name, value)?;
+                            self.db.update_cell(&self.table_name, &row.key, &field.name, value)?;
+                            self.state.changes.mark_modified(row.id, col_idx - 1);
                             self.refresh_rows(0, 100)?;
                         }
                     }
 
                     self.state.editing_cell = None;
                     self.state.edit_input = None;
+                    self.state.edit_pattern = None;
+                    self.state.edit_numeric = None;
+                    self.state.edit_tags = None;
+                    self.state.edit_uuid = false;
+                    self.state.edit_subscription = None;
                     self.state.validation_error = None;
+                    self.state.lineage_warning = None;
                 }
                 Err(err) => {
                     self.state.validation_error = Some(err);
@@ -255,33 +1603,45 @@ impl DataTableView {
     pub fn cancel_edit(&mut self) {
         self.state.editing_cell = None;
         self.state.edit_input = None;
+        self.state.edit_pattern = None;
+        self.state.edit_numeric = None;
+        self.state.edit_tags = None;
+        self.state.edit_uuid = false;
+        self.state.edit_subscription = None;
         self.state.validation_error = None;
+        self.state.lineage_warning = None;
     }
 
     pub fn copy_cell_value(&mut self, row_idx: usize, col_idx: usize) {
-        if let Some(row) = self.rows.get(row_idx) {
+        if let Some(row) = self.row(row_idx) {
             if col_idx == 0 {
                 self.state.copied_cell = Some(row.id.to_string());
             } else if let Some(cell) = row.cells.get(col_idx - 1) {
-                self.state.copied_cell = Some(cell.display.clone());
+                if self.is_oversized(row.id, col_idx - 1, cell) {
+                    tracing::warn!("Not copying {}; open it to save it to a file", cell.size_placeholder());
+                    self.state.copied_cell = None;
+                } else {
+                    self.state.copied_cell = Some(cell.display.clone());
+                }
             }
         }
     }
 
     pub fn duplicate_row(&mut self, row_idx: usize) -> anyhow::Result<()> {
-        if let Some(row) = self.rows.get(row_idx) {
+        if let Some(row) = self.row(row_idx) {
             let values: Vec<serde_json::Value> = row.cells.iter()
                 .map(|cell| cell.value.clone())
                 .collect();
 
-            self.db.insert_row(&self.table_name, values)?;
+            let id = self.db.insert_row(&self.table_name, values)?;
+            self.state.changes.inserted.insert(id);
             self.refresh_rows(0, 1000)?;
         }
         Ok(())
     }
 
     pub fn copy_row_as_insert(&self, row_idx: usize) -> Option<String> {
-        if let Some(row) = self.rows.get(row_idx) {
+        if let Some(row) = self.row(row_idx) {
             let field_names: Vec<String> = self.schema.fields.iter()
                 .map(|f| f.name.clone())
                 .collect();
@@ -308,15 +1668,67 @@ impl DataTableView {
     }
 
     pub fn get_table_stats(&self) -> String {
-        format!(
-            "Total: {} rows | Virtual Scrolling (infinite)",
-            self.total_rows
-        )
+        let stats = if self.is_sampled() {
+            format!(
+                "Sample: {} of ~{} rows",
+                self.rows.len(),
+                self.state.estimated_rows
+            )
+        } else if self.is_paged() {
+            match (self.rows.first(), self.rows.last()) {
+                (Some(first), Some(last)) => format!("Page: {} rows | rowid {}-{}", self.rows.len(), first.id, last.id),
+                _ => "Page: no rows".to_string(),
+            }
+        } else {
+            format!(
+                "Total: {} rows | Showing {}-{}",
+                self.total_rows,
+                (self.window_start + 1).min(self.total_rows),
+                self.window_start + self.rows.len()
+            )
+        };
+
+        let stats = if self.state.sort.is_empty() {
+            stats
+        } else {
+            let order: Vec<String> = self
+                .state
+                .sort
+                .iter()
+                .map(|key| format!("{} {}", key.column, if key.descending { "▼" } else { "▲" }))
+                .collect();
+            format!("{} | Sorted by {}", stats, order.join(", "))
+        };
+
+        let stats = if self.state.row_filter.is_empty() {
+            stats
+        } else {
+            format!("{} | Filter: {}", stats, self.state.row_filter.describe())
+        };
+
+        let stats = match &self.state.raw_where {
+            Some(raw) => format!("{} | WHERE {}", stats, raw),
+            None => stats,
+        };
+
+        match self.selected_ids().len() {
+            0 | 1 => stats,
+            selected => format!("{} | {} selected", stats, selected),
+        }
     }
 
 }
 
 impl DataTableView {
+    /// Colour for ids, NULLs and other secondary cell text
+    fn placeholder_color(&self, cx: &App) -> Hsla {
+        if self.grid.high_contrast {
+            cx.theme().foreground
+        } else {
+            cx.theme().muted_foreground
+        }
+    }
+
     pub fn enable_features(&self, table: &mut ui::table::Table<Self>, cx: &mut Context<ui::table::Table<Self>>) {
         table.col_fixed = true;
         table.col_resizable = true;
@@ -331,7 +1743,7 @@ impl TableDelegate for DataTableView {
     }
 
     fn rows_count(&self, _: &App) -> usize {
-        self.rows.len()
+        self.total_rows
     }
 
     fn column(&self, col_ix: usize, _: &App) -> &Column {
@@ -342,16 +1754,25 @@ impl TableDelegate for DataTableView {
         &self,
         col_ix: usize,
         _: &mut Window,
-        _cx: &mut Context<Table<Self>>,
+        cx: &mut Context<Table<Self>>,
     ) -> impl IntoElement {
         let col = &self.columns[col_ix];
 
-        div()
+        h_flex()
+            .gap_1()
             .child(col.name.clone())
-            .text_sm()
+            .when_some(self.sort_marker(col_ix), |this, marker| {
+                this.child(div().text_xs().text_color(cx.theme().accent).child(marker))
+            })
+            .text_size(self.grid.text_size)
             .font_semibold()
             .px_2()
-            .py_1()
+            .py(self.grid.cell_padding_y)
+            .when(self.grid.high_contrast, |this| {
+                this.text_color(cx.theme().foreground)
+                    .border_b_2()
+                    .border_color(cx.theme().foreground)
+            })
     }
 
     fn render_tr(
@@ -360,17 +1781,186 @@ impl TableDelegate for DataTableView {
         _: &mut Window,
         cx: &mut Context<Table<Self>>,
     ) -> Stateful<Div> {
-        let is_selected = self.state.selected_row == Some(row_ix);
+        let is_selected = self.is_row_selected(row_ix);
+        let is_inserted = self
+            .rows
+            .get(row_ix)
+            .is_some_and(|row| self.state.changes.inserted.contains(&row.id));
         div()
             .id(row_ix)
+            .when(is_inserted && !is_selected, |this| {
+                this.bg(cx.theme().success.opacity(0.12))
+            })
             .cursor_pointer()
-            .on_click(cx.listener(move |table, _, _, cx| {
-                table.delegate_mut().state.selected_row = Some(row_ix);
+            .on_click(cx.listener(move |table, event: &ClickEvent, _, cx| {
+                table.delegate_mut().select_row(row_ix, event.modifiers());
+                cx.notify();
+            }))
+            .on_mouse_down(MouseButton::Right, cx.listener(move |table, _, _, cx| {
+                table.delegate_mut().open_row_menu(row_ix);
                 cx.notify();
             }))
-            .when(is_selected, |this| {
+            .when(is_selected && !self.grid.high_contrast, |this| {
                 this.bg(cx.theme().accent.opacity(0.1))
             })
+            // Selection is shown by outline as well as colour so it survives any palette
+            .when(is_selected && self.grid.high_contrast, |this| {
+                this.bg(cx.theme().accent.opacity(0.35))
+                    .border_y_2()
+                    .border_color(cx.theme().foreground)
+            })
+    }
+
+    /// The project's row actions, below the gutter of the right-clicked row
+    fn render_row_menu(&self, cx: &mut Context<Table<Self>>) -> impl IntoElement {
+        let read_only = self.is_read_only();
+        v_flex()
+            .absolute()
+            .top_full()
+            .left_0()
+            .mt_1()
+            .p_1()
+            .min_w(px(180.0))
+            .bg(cx.theme().background)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded_sm()
+            .shadow_lg()
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .on_mouse_down_out(cx.listener(|table, _, _, cx| {
+                table.delegate_mut().state.row_menu = None;
+                cx.notify();
+            }))
+            .children(self.row_actions.actions.iter().enumerate().map(|(ix, action)| {
+                Button::new(("row-action", ix))
+                    .label(action.label.clone())
+                    .xsmall()
+                    .ghost()
+                    .w_full()
+                    .disabled(read_only)
+                    .on_click(cx.listener(move |table, _, _, cx| {
+                        if let Err(e) = table.delegate_mut().run_row_action(ix) {
+                            tracing::error!("Failed to run row action: {}", e);
+                        }
+                        cx.stop_propagation();
+                        cx.notify();
+                    }))
+            }))
+    }
+
+    /// The grid window of the map picker, below the latitude cell
+    fn render_geo_picker(&self, picker: GeoPicker, cx: &mut Context<Table<Self>>) -> impl IntoElement {
+        let point = picker.point;
+        let hover = cx.theme().accent.opacity(0.5);
+        v_flex()
+            .absolute()
+            .top_full()
+            .left_0()
+            .mt_1()
+            .p_2()
+            .gap_1()
+            .bg(cx.theme().background)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded_sm()
+            .shadow_lg()
+            // Clicks inside the picker don't start editing the cell under it
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(
+                div()
+                    .text_xs()
+                    .child(format!("{} · {:.4}° per square", point.describe(), picker.step()))
+            )
+            .children((0..GeoPicker::ROWS).map(|row| {
+                h_flex().gap_px().children((0..GeoPicker::COLUMNS).map(|col| {
+                    let target = picker.cell_point(row, col).describe();
+                    div()
+                        .id(("geo-square", row * GeoPicker::COLUMNS + col))
+                        .size(px(9.0))
+                        .cursor_pointer()
+                        .bg(if GeoPicker::is_marker(row, col) {
+                            cx.theme().accent
+                        } else if picker.is_graticule(row, col) {
+                            cx.theme().border
+                        } else {
+                            cx.theme().muted
+                        })
+                        .hover(move |this| this.bg(hover))
+                        .tooltip(move |window, cx| Tooltip::new(target.clone()).build(window, cx))
+                        .on_mouse_down(MouseButton::Left, cx.listener(move |table, _, _, cx| {
+                            if let Some(geo) = table.delegate_mut().state.geo_cell.as_mut() {
+                                geo.picker.pick(row, col);
+                            }
+                            cx.stop_propagation();
+                            cx.notify();
+                        }))
+                }))
+            }))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("geo-zoom-out")
+                            .label("−")
+                            .tooltip("Zoom out")
+                            .xsmall()
+                            .ghost()
+                            .disabled(!picker.can_zoom_out())
+                            .on_click(cx.listener(|table, _, _, cx| {
+                                if let Some(geo) = table.delegate_mut().state.geo_cell.as_mut() {
+                                    geo.picker.zoom_out();
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("geo-zoom-in")
+                            .label("+")
+                            .tooltip("Zoom in")
+                            .xsmall()
+                            .ghost()
+                            .disabled(!picker.can_zoom_in())
+                            .on_click(cx.listener(|table, _, _, cx| {
+                                if let Some(geo) = table.delegate_mut().state.geo_cell.as_mut() {
+                                    geo.picker.zoom_in();
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("geo-copy")
+                            .label("Copy POINT")
+                            .tooltip(point.to_wkt())
+                            .xsmall()
+                            .ghost()
+                            .on_click(move |_, _, cx| {
+                                cx.write_to_clipboard(ClipboardItem::new_string(point.to_wkt()));
+                            })
+                    )
+                    .child(
+                        Button::new("geo-apply")
+                            .label("Apply")
+                            .xsmall()
+                            .primary()
+                            .disabled(self.is_read_only())
+                            .on_click(cx.listener(|table, _, _, cx| {
+                                if let Err(e) = table.delegate_mut().apply_geo_picker() {
+                                    tracing::error!("Failed to update position: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("geo-cancel")
+                            .label("Cancel")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(|table, _, _, cx| {
+                                table.delegate_mut().state.geo_cell = None;
+                                cx.notify();
+                            }))
+                    )
+            )
     }
 
     fn render_td(
@@ -380,35 +1970,95 @@ impl TableDelegate for DataTableView {
         window: &mut Window,
         cx: &mut Context<Table<Self>>,
     ) -> impl IntoElement {
-        if let Some(row) = self.rows.get(row_ix) {
+        if let Some(row) = self.row(row_ix) {
             if col_ix == 0 {
+                let rowid = format!("rowid {}", row.id);
                 return div()
+                    .id(("gutter", row_ix))
                     .px_2()
-                    .py_1()
-                    .text_sm()
+                    .py(self.grid.cell_padding_y)
+                    .text_size(self.grid.text_size)
                     .font_semibold()
-                    .text_color(cx.theme().muted_foreground)
-                    .child(row.id.to_string())
+                    .text_color(self.placeholder_color(cx))
+                    .when(self.is_row_selected(row_ix), |this| {
+                        this.bg(cx.theme().accent).text_color(cx.theme().accent_foreground)
+                    })
+                    .tooltip(move |window, cx| Tooltip::new(rowid.clone()).build(window, cx))
+                    .child((row_ix + 1).to_string())
+                    .when(self.state.row_menu == Some(row_ix), |this| {
+                        this.relative().child(self.render_row_menu(cx))
+                    })
                     .into_any_element();
             }
 
+            // Off-screen columns in very wide tables skip decoding and styling entirely
+            if !column_in_view(&self.visible_columns, col_ix) {
+                return div().into_any_element();
+            }
+
             let cell_idx = col_ix - 1;
             if let Some(cell) = row.cells.get(cell_idx) {
                 let is_editing = self.state.editing_cell == Some((row_ix, col_ix));
-                let display = cell.display.clone();
+                if self.is_oversized(row.id, cell_idx, cell) {
+                    return div()
+                        .id(("cell", row_ix * 1000 + col_ix))
+                        .px_2()
+                        .py(self.grid.cell_padding_y)
+                        .text_size(self.grid.text_size)
+                        .italic()
+                        .text_color(self.placeholder_color(cx))
+                        .cursor_pointer()
+                        .on_click(cx.listener(move |table, _, window, cx| {
+                            let delegate = table.delegate_mut();
+                            delegate.state.focused_column = Some(col_ix);
+                            delegate.start_edit_cell(row_ix, col_ix, window, cx);
+                            cx.notify();
+                        }))
+                        .child(cell.size_placeholder())
+                        .into_any_element();
+                }
+                let display = self
+                    .decoders
+                    .get(cell_idx)
+                    .and_then(|d| d.as_ref())
+                    .and_then(|d| d.decode(cell))
+                    .unwrap_or_else(|| cell.display.clone());
 
                 if is_editing {
                     if let Some(ref edit_input) = self.state.edit_input {
                         // Show proper text input for editing
                         let has_error = self.state.validation_error.is_some();
 
+                        let numeric = self.state.edit_numeric.as_ref();
+                        let tag_chips = self.state.edit_tags.as_ref().filter(|_| !has_error).map(|tags| {
+                            let text = edit_input.read(cx).value().to_string();
+                            (crate::tags::split_tags(&text), tags.suggestions(&text))
+                        });
+                        let slider = numeric
+                            .filter(|n| n.range.is_bounded())
+                            .map(|n| n.fraction(&edit_input.read(cx).value().to_string()).unwrap_or(0.0));
+
                         return div()
                             .id(("cell-edit", row_ix * 1000 + col_ix))
                             .w_full()
                             .h_full()
                             .relative()
+                            // Up/Down step a ranged value before the input moves the caret
+                            .when(numeric.is_some(), |this| {
+                                this.capture_key_down(cx.listener(|table, event: &KeyDownEvent, window, cx| {
+                                    let steps = match event.keystroke.key.as_str() {
+                                        "up" => 1.0,
+                                        "down" => -1.0,
+                                        _ => return,
+                                    };
+                                    let steps = steps * NumericEditor::step_multiplier(&event.keystroke.modifiers);
+                                    table.delegate_mut().step_editing_cell(steps, window, cx);
+                                    cx.stop_propagation();
+                                    cx.notify();
+                                }))
+                            })
                             .child(
-                                div()
+                                h_flex()
                                     .size_full()
                                     .border_2()
                                     .border_color(if has_error {
@@ -420,13 +2070,139 @@ impl TableDelegate for DataTableView {
                                     .overflow_hidden()
                                     .child(
                                         TextInput::new(edit_input)
-                                            .w_full()
+                                            .flex_1()
                                             .h_full()
-                                            .text_sm()
+                                            .text_size(self.grid.text_size)
                                             .px_2()
-                                            .py_1()
+                                            .py(self.grid.cell_padding_y)
                                             .border_0()
                                     )
+                                    .when_some(numeric, |this, numeric| {
+                                        this.child(
+                                            Button::new("spin-down")
+                                                .label("−")
+                                                .tooltip(format!("Step down ({}); Shift ×10, Alt ×0.1", numeric.range.describe()))
+                                                .xsmall()
+                                                .ghost()
+                                                .on_click(cx.listener(|table, _, window, cx| {
+                                                    table.delegate_mut().step_editing_cell(-1.0, window, cx);
+                                                    cx.notify();
+                                                }))
+                                        )
+                                        .child(
+                                            Button::new("spin-up")
+                                                .label("+")
+                                                .xsmall()
+                                                .ghost()
+                                                .on_click(cx.listener(|table, _, window, cx| {
+                                                    table.delegate_mut().step_editing_cell(1.0, window, cx);
+                                                    cx.notify();
+                                                }))
+                                        )
+                                    })
+                                    .when(self.state.edit_uuid, |this| {
+                                        this.children([UuidVersion::V4, UuidVersion::V7].map(|version| {
+                                            Button::new(version.label())
+                                                .label(match version {
+                                                    UuidVersion::V4 => "v4",
+                                                    UuidVersion::V7 => "v7",
+                                                })
+                                                .tooltip(format!("Generate a {}", version.label()))
+                                                .xsmall()
+                                                .ghost()
+                                                .on_click(cx.listener(move |table, _, window, cx| {
+                                                    table.delegate_mut().fill_editing_uuid(version, window, cx);
+                                                    cx.notify();
+                                                }))
+                                        }))
+                                    })
+                            )
+                            .when_some(tag_chips, |this, (chips, suggestions)| {
+                                this.child(
+                                    h_flex()
+                                        .absolute()
+                                        .top_full()
+                                        .left_0()
+                                        .mt_1()
+                                        .p_1()
+                                        .gap_1()
+                                        .flex_wrap()
+                                        .w_64()
+                                        .bg(cx.theme().background)
+                                        .border_1()
+                                        .border_color(cx.theme().border)
+                                        .rounded_sm()
+                                        .shadow_lg()
+                                        .children(chips.into_iter().enumerate().map(|(ix, tag)| {
+                                            Button::new(("tag-chip", ix))
+                                                .label(format!("{} ×", tag))
+                                                .tooltip("Remove this tag")
+                                                .xsmall()
+                                                .primary()
+                                                .on_click(cx.listener(move |table, _, window, cx| {
+                                                    table.delegate_mut().remove_tag(ix, window, cx);
+                                                    cx.notify();
+                                                }))
+                                        }))
+                                        .children(suggestions.into_iter().enumerate().map(|(ix, tag)| {
+                                            Button::new(("tag-suggestion", ix))
+                                                .label(format!("+ {}", tag))
+                                                .xsmall()
+                                                .ghost()
+                                                .on_click(cx.listener(move |table, _, window, cx| {
+                                                    table.delegate_mut().add_tag(&tag, window, cx);
+                                                    cx.notify();
+                                                }))
+                                        }))
+                                )
+                            })
+                            .when_some(slider, |this, fraction| {
+                                let filled = (fraction * (SLIDER_STEPS - 1) as f32).round() as usize;
+                                this.child(
+                                    h_flex()
+                                        .absolute()
+                                        .bottom_full()
+                                        .left_0()
+                                        .w_full()
+                                        .h_2()
+                                        .mb_1()
+                                        .gap_px()
+                                        .children((0..SLIDER_STEPS).map(|segment| {
+                                            div()
+                                                .id(("slider", segment))
+                                                .flex_1()
+                                                .h_full()
+                                                .rounded_sm()
+                                                .cursor_pointer()
+                                                .bg(if segment <= filled { cx.theme().accent } else { cx.theme().border })
+                                                .on_mouse_down(MouseButton::Left, cx.listener(move |table, _, window, cx| {
+                                                    let fraction = segment as f64 / (SLIDER_STEPS - 1) as f64;
+                                                    table.delegate_mut().slide_editing_cell(fraction, window, cx);
+                                                    cx.stop_propagation();
+                                                    cx.notify();
+                                                }))
+                                        }))
+                                )
+                            })
+                            .when_some(
+                                self.state.lineage_warning.as_ref().filter(|_| !has_error),
+                                |this, warning| {
+                                    this.child(
+                                        div()
+                                            .absolute()
+                                            .top_full()
+                                            .left_0()
+                                            .mt_1()
+                                            .px_2()
+                                            .py_1()
+                                            .bg(cx.theme().warning)
+                                            .text_color(cx.theme().background)
+                                            .text_xs()
+                                            .rounded_sm()
+                                            .shadow_lg()
+                                            .child(warning.clone())
+                                    )
+                                },
                             )
                             .when_some(self.state.validation_error.as_ref(), |this, error| {
                                 this.child(
@@ -449,25 +2225,90 @@ impl TableDelegate for DataTableView {
                     }
                 }
 
+                let is_modified = self.state.changes.is_modified(row.id, cell_idx);
+                let uuid = self
+                    .uuid_columns
+                    .get(cell_idx)
+                    .is_some_and(|&is_uuid| is_uuid)
+                    .then(|| crate::uuids::cell_uuid(cell))
+                    .flatten();
+                let geo_pair = self.geo_pair(cell_idx);
+                let geo_point = geo_pair
+                    .and_then(|(lat, lon)| GeoPoint::from_cells(row.cells.get(lat)?, row.cells.get(lon)?));
+                let geo_picker = self
+                    .state
+                    .geo_cell
+                    .as_ref()
+                    .filter(|geo| geo.row_idx == row_ix && geo.lat_field == cell_idx)
+                    .map(|geo| geo.picker.clone());
+
                 // Regular cell display
                 return div()
                     .id(("cell", row_ix * 1000 + col_ix))
+                    .when(is_modified, |this| {
+                        this.bg(cx.theme().warning.opacity(0.15))
+                            .border_l_2()
+                            .border_color(cx.theme().warning)
+                    })
                     .px_2()
-                    .py_1()
-                    .text_sm()
+                    .py(self.grid.cell_padding_y)
+                    .text_size(self.grid.text_size)
                     .cursor_pointer()
                     .on_click(cx.listener(move |table, _, window, cx| {
                         let delegate = table.delegate_mut();
+                        delegate.state.focused_column = Some(col_ix);
+                        delegate.state.type_ahead.clear();
                         delegate.start_edit_cell(row_ix, col_ix, window, cx);
                         cx.notify();
                     }))
-                    .hover(|this| this.bg(cx.theme().muted.opacity(0.5)))
+                    .hover(|this| this.bg(cx.theme().muted.opacity(if self.grid.high_contrast { 0.9 } else { 0.5 })))
                     .when(display.is_empty() || display == "NULL", |this| {
-                        this.text_color(cx.theme().muted_foreground)
+                        this.text_color(self.placeholder_color(cx))
                             .italic()
                             .child(if display.is_empty() { "empty" } else { "NULL" })
                     })
-                    .when(!display.is_empty() && display != "NULL", |this| {
+                    .when_some(uuid, |this, uuid| {
+                        let full = uuid.hyphenated().to_string();
+                        let tooltip = full.clone();
+                        this.tooltip(move |window, cx| Tooltip::new(tooltip.clone()).build(window, cx))
+                            .child(
+                                h_flex()
+                                    .gap_1()
+                                    .child(div().font_family("monospace").child(crate::uuids::compact(&uuid)))
+                                    .child(
+                                        Button::new(("copy-uuid", row_ix * 1000 + col_ix))
+                                            .icon(IconName::Copy)
+                                            .tooltip("Copy the full UUID")
+                                            .xsmall()
+                                            .ghost()
+                                            .on_click(cx.listener(move |_, _, _, cx| {
+                                                cx.write_to_clipboard(ClipboardItem::new_string(full.clone()));
+                                                cx.stop_propagation();
+                                            }))
+                                    )
+                            )
+                    })
+                    .when_some(geo_pair, |this, (lat_field, lon_field)| {
+                        let tooltip = geo_point.map_or("No position; click to place".to_string(), |p| p.to_wkt());
+                        this.relative().child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    div()
+                                        .id(("geo-thumb", row_ix * 1000 + col_ix))
+                                        .tooltip(move |window, cx| Tooltip::new(tooltip.clone()).build(window, cx))
+                                        .on_click(cx.listener(move |table, _, _, cx| {
+                                            table.delegate_mut().open_geo_picker(row_ix, lat_field, lon_field);
+                                            cx.stop_propagation();
+                                            cx.notify();
+                                        }))
+                                        .child(geo_thumbnail(geo_point, cx))
+                                )
+                                .when(!display.is_empty() && display != "NULL", |this| this.child(display.clone()))
+                        )
+                    })
+                    .when_some(geo_picker, |this, picker| this.child(self.render_geo_picker(picker, cx)))
+                    .when(uuid.is_none() && geo_pair.is_none() && !display.is_empty() && display != "NULL", |this| {
                         this.child(display)
                     })
                     .into_any_element();
@@ -476,9 +2317,9 @@ impl TableDelegate for DataTableView {
 
         div()
             .px_2()
-            .py_1()
-            .text_sm()
-            .text_color(cx.theme().muted_foreground)
+            .py(self.grid.cell_padding_y)
+            .text_size(self.grid.text_size)
+            .text_color(self.placeholder_color(cx))
             .child("—")
             .into_any_element()
     }
@@ -491,21 +2332,40 @@ impl TableDelegate for DataTableView {
     ) {
         self.visible_range = visible_range.clone();
 
-        if visible_range.end > self.rows.len() && self.rows.len() < self.total_rows {
-            if let Err(e) = self.refresh_rows(0, visible_range.end + 50) {
-                eprintln!("Failed to load more rows: {}", e);
+        let loaded = self.window_start..self.window_start + self.rows.len();
+        let end = visible_range.end.min(self.total_rows);
+        if visible_range.start < loaded.start || end > loaded.end {
+            if let Err(e) = self.scroll_window_to(visible_range.start) {
+                tracing::error!("Failed to load rows {}..{}: {}", visible_range.start, end, e);
             }
         }
     }
 
+    fn visible_columns_changed(
+        &mut self,
+        visible_range: Range<usize>,
+        _: &mut Window,
+        _cx: &mut Context<Table<Self>>,
+    ) {
+        self.visible_columns = visible_range;
+    }
+
     fn perform_sort(
         &mut self,
         col_ix: usize,
         sort: ColumnSort,
-        _: &mut Window,
+        window: &mut Window,
         _: &mut Context<Table<Self>>,
     ) {
-        tracing::debug!("Sort column {} {:?}", col_ix, sort);
+        let direction = match sort {
+            ColumnSort::Ascending => Some(false),
+            ColumnSort::Descending => Some(true),
+            ColumnSort::Default => None,
+        };
+        // Sorting happens in SQLite via ORDER BY, never on the loaded window
+        if let Err(e) = self.sort_by(col_ix, direction, window.modifiers().shift) {
+            tracing::error!("Failed to sort by column {}: {}", col_ix, e);
+        }
     }
 
     fn loading(&self, _: &App) -> bool {
@@ -513,6 +2373,24 @@ impl TableDelegate for DataTableView {
     }
 
     fn is_eof(&self, _: &App) -> bool {
-        self.rows.len() >= self.total_rows
+        // Every row is addressable up front; windows load as they scroll into view
+        true
+    }
+}
+
+/// `00 1f a0 …` rows of 16 bytes, for a glance at a BLOB's header
+fn hex_preview(bytes: &[u8]) -> String {
+    let mut lines: Vec<String> = bytes
+        .chunks(16)
+        .take(BLOB_PREVIEW_BYTES / 16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{:08x}  {}", i * 16, hex.join(" "))
+        })
+        .collect();
+    if bytes.len() > BLOB_PREVIEW_BYTES {
+        lines.push("…".to_string());
     }
+    lines.join("\n")
 }
@@ -0,0 +1,58 @@
+//! Columns holding comma-separated tags, e.g. `fire, rare, boss`. The profile marks
+//! them; the grid then edits them as chips with suggestions from the tags already in
+//! the column, and the filter builder can ask for rows that have a tag.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::database::DatabaseManager;
+use crate::sql_text::quote_identifier;
+
+pub const TAG_SEPARATOR: char = ',';
+
+/// Distinct values read when collecting a column's tags
+const MAX_SCANNED_VALUES: usize = 10_000;
+
+/// The tags in `text`, trimmed, without empties
+pub fn split_tags(text: &str) -> Vec<String> {
+    text.split(TAG_SEPARATOR)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn join_tags(tags: &[String]) -> String {
+    tags.join(&format!("{} ", TAG_SEPARATOR))
+}
+
+/// Every tag used in `table.column`, most used first
+pub fn known_tags(db: &DatabaseManager, table: &str, column: &str) -> Result<Vec<String>> {
+    let sql = format!(
+        "SELECT {col}, COUNT(*) FROM {table} WHERE {col} IS NOT NULL GROUP BY {col} LIMIT {limit}",
+        col = quote_identifier(column),
+        table = quote_identifier(table),
+        limit = MAX_SCANNED_VALUES
+    );
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for row in db.fetch_query(&sql, MAX_SCANNED_VALUES)?.rows {
+        let rows = row.get(1).and_then(|c| c.value.as_u64()).unwrap_or(1);
+        for tag in row.first().map(|c| split_tags(&c.display)).unwrap_or_default() {
+            *counts.entry(tag).or_default() += rows;
+        }
+    }
+    let mut tags: Vec<(String, u64)> = counts.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(tags.into_iter().map(|(tag, _)| tag).collect())
+}
+
+/// A condition matching rows whose `column` has `tag` as a whole tag, ignoring the
+/// spaces around separators
+pub fn has_tag_condition(column: &str, tag: &str) -> (String, Vec<Value>) {
+    let sql = format!(
+        "instr('{sep}' || REPLACE(REPLACE(CAST({col} AS TEXT), ' {sep}', '{sep}'), '{sep} ', '{sep}') || '{sep}', '{sep}' || ? || '{sep}') > 0",
+        col = quote_identifier(column),
+        sep = TAG_SEPARATOR
+    );
+    (sql, vec![Value::String(tag.trim().to_string())])
+}
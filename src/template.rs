@@ -0,0 +1,539 @@
+//! Export templates: a small handlebars-style language for writing query results as
+//! source code game code reads directly (Lua tables, C# arrays, GDScript dictionaries).
+//! Templates render through a [`RowSink`], so they stream like the built-in formats.
+//!
+//! ```text
+//! return {
+//! {{#each rows}}  { id = {{id}}, name = {{name|lua}} }{{#unless @last}},{{/unless}}
+//! {{/each}}}
+//! ```
+//!
+//! Inside `{{#each rows}}`, `{{column}}` is a cell's text and `{{column|lua}}` the cell
+//! as a literal (`lua`, `cs`, `gd`, `json`, or `str` for a quoted string). Within a row,
+//! `{{#each columns}}` loops over the cells as `{{name}}` and `{{value}}`. `@index`,
+//! `@first` and `@last` describe the innermost loop, and `{{#if x}}` / `{{#unless x}}`
+//! (with an optional `{{else}}`) test them or a cell. Outside the row loop `{{source}}`
+//! is the query, and after it `{{row_count}}` the number of rows written.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::compression::CompressedWriter;
+use crate::database::{CellValue, DatabaseManager, RowSink};
+
+/// Directory under the project root holding user templates, named `<name>.<ext>.tmpl`
+pub const TEMPLATE_DIR: &str = "export_templates";
+
+const LUA_TEMPLATE: &str = "\
+-- Generated from {{source}}
+return {
+{{#each rows}}  { {{#each columns}}{{name}} = {{value|lua}}{{#unless @last}}, {{/unless}}{{/each}} }{{#unless @last}},{{/unless}}
+{{/each}}}
+";
+
+const CSHARP_TEMPLATE: &str = "\
+// Generated from {{source}}
+public static readonly object[][] Rows = new object[][]
+{
+{{#each rows}}    new object[] { {{#each columns}}{{value|cs}}{{#unless @last}}, {{/unless}}{{/each}} },
+{{/each}}};
+";
+
+const GDSCRIPT_TEMPLATE: &str = "\
+# Generated from {{source}}
+const ROWS = [
+{{#each rows}}\t{ {{#each columns}}\"{{name}}\": {{value|gd}}{{#unless @last}}, {{/unless}}{{/each}} },
+{{/each}}]
+";
+
+/// A template as picked in the UI: built in, or read from the project's template directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSource {
+    pub name: String,
+    /// Extension of the files it produces
+    pub extension: String,
+    pub text: String,
+}
+
+impl TemplateSource {
+    pub fn builtin() -> Vec<Self> {
+        [("Lua", "lua", LUA_TEMPLATE), ("C#", "cs", CSHARP_TEMPLATE), ("GDScript", "gd", GDSCRIPT_TEMPLATE)]
+            .into_iter()
+            .map(|(name, extension, text)| Self {
+                name: name.to_string(),
+                extension: extension.to_string(),
+                text: text.to_string(),
+            })
+            .collect()
+    }
+
+    /// Built-in templates followed by `<project root>/export_templates/*.tmpl`
+    pub fn discover(db_path: Option<&Path>) -> Vec<Self> {
+        let mut templates = Self::builtin();
+        let Some(dir) = db_path.map(|p| crate::project_query::project_root(p).join(TEMPLATE_DIR)) else {
+            return templates;
+        };
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return templates;
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "tmpl"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => {
+                    // `items.lua.tmpl` is named `items` and writes `.lua` files
+                    let stem = Path::new(path.file_stem().unwrap_or_default());
+                    templates.push(Self {
+                        name: stem.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                        extension: stem
+                            .extension()
+                            .map(|e| e.to_string_lossy().to_string())
+                            .unwrap_or_else(|| "txt".to_string()),
+                        text,
+                    });
+                }
+                Err(e) => tracing::error!("Failed to read template {}: {}", path.display(), e),
+            }
+        }
+        templates
+    }
+}
+
+/// Literal syntax a `{{cell|filter}}` renders in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Literal {
+    Lua,
+    CSharp,
+    GdScript,
+    Json,
+    /// Any value as a double-quoted string
+    Str,
+}
+
+impl Literal {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "lua" => Ok(Literal::Lua),
+            "cs" | "csharp" => Ok(Literal::CSharp),
+            "gd" | "gdscript" => Ok(Literal::GdScript),
+            "json" => Ok(Literal::Json),
+            "str" => Ok(Literal::Str),
+            other => Err(anyhow!("Unknown template filter '{}'", other)),
+        }
+    }
+
+    fn render(self, cell: &CellValue) -> String {
+        if self == Literal::Json {
+            return cell.value.to_string();
+        }
+        match &cell.value {
+            _ if self == Literal::Str => quote(&cell.display),
+            Value::Null => match self {
+                Literal::Lua => "nil".to_string(),
+                _ => "null".to_string(),
+            },
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => quote(s),
+            other => quote(&other.to_string()),
+        }
+    }
+}
+
+/// Double-quoted with the escapes Lua, C# and GDScript share
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Var { name: String, literal: Option<Literal> },
+    /// Only at top level, where [`Template::parse`] splits the template around it
+    EachRow(Vec<Node>),
+    EachColumn(Vec<Node>),
+    If { name: String, negate: bool, then: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// A parsed template: what comes before the row loop, its body, and what follows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    header: Vec<Node>,
+    row: Vec<Node>,
+    footer: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut header = parse_nodes(&mut Tokens::new(text), None)?;
+        let mut loops = header.iter().filter(|n| matches!(n, Node::EachRow(_)));
+        if loops.next().is_none() || loops.next().is_some() {
+            return Err(anyhow!("Template needs exactly one {{{{#each rows}}}} block"));
+        }
+
+        let at = header.iter().position(|n| matches!(n, Node::EachRow(_))).unwrap_or_default();
+        let footer = header.split_off(at + 1);
+        let row = match header.pop() {
+            Some(Node::EachRow(row)) => row,
+            _ => Vec::new(),
+        };
+        Ok(Self { header, row, footer })
+    }
+}
+
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { rest: text }
+    }
+
+    fn next(&mut self) -> Result<Option<Token<'a>>> {
+        if self.rest.is_empty() {
+            return Ok(None);
+        }
+        match self.rest.find("{{") {
+            Some(0) => {
+                let end = self.rest.find("}}").ok_or_else(|| anyhow!("Unclosed {{{{ in template"))?;
+                let tag = self.rest[2..end].trim();
+                self.rest = &self.rest[end + 2..];
+                Ok(Some(Token::Tag(tag)))
+            }
+            Some(start) => {
+                let text = &self.rest[..start];
+                self.rest = &self.rest[start..];
+                Ok(Some(Token::Text(text)))
+            }
+            None => {
+                let text = self.rest;
+                self.rest = "";
+                Ok(Some(Token::Text(text)))
+            }
+        }
+    }
+}
+
+/// Parse until the closing tag of `block` (`each`, `if`, `unless`), or the end at top level
+fn parse_nodes(tokens: &mut Tokens, block: Option<&str>) -> Result<Vec<Node>> {
+    Ok(parse_until(tokens, block)?.0)
+}
+
+/// Nodes up to the block's closing tag, and whether an `{{else}}` stopped them instead
+fn parse_until(tokens: &mut Tokens, block: Option<&str>) -> Result<(Vec<Node>, bool)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next()? {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text.to_string()));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        if let Some(closed) = tag.strip_prefix('/') {
+            return match block {
+                Some(open) if open == closed.trim() => Ok((nodes, false)),
+                _ => Err(anyhow!("Unexpected {{{{/{}}}}} in template", closed.trim())),
+            };
+        }
+        if tag == "else" {
+            return match block {
+                Some("if") | Some("unless") => Ok((nodes, true)),
+                _ => Err(anyhow!("{{{{else}}}} outside of an if block")),
+            };
+        }
+
+        let mut words = tag.split_whitespace();
+        match words.next() {
+            Some("#each") => match words.next() {
+                Some("rows") if block.is_none() => nodes.push(Node::EachRow(parse_nodes(tokens, Some("each"))?)),
+                Some("rows") => return Err(anyhow!("{{{{#each rows}}}} can't be nested")),
+                Some("columns") => nodes.push(Node::EachColumn(parse_nodes(tokens, Some("each"))?)),
+                other => return Err(anyhow!("Can't loop over '{}'", other.unwrap_or_default())),
+            },
+            Some(kind @ ("#if" | "#unless")) => {
+                let name = words.next().ok_or_else(|| anyhow!("{{{{{}}}}} needs a value to test", kind))?;
+                let block = &kind[1..];
+                let (then, has_else) = parse_until(tokens, Some(block))?;
+                let otherwise = if has_else { parse_nodes(tokens, Some(block))? } else { Vec::new() };
+                nodes.push(Node::If {
+                    name: name.to_string(),
+                    negate: kind == "#unless",
+                    then,
+                    otherwise,
+                });
+            }
+            _ => {
+                let (name, literal) = match tag.split_once('|') {
+                    Some((name, filter)) => (name.trim(), Some(Literal::parse(filter.trim())?)),
+                    None => (tag, None),
+                };
+                nodes.push(Node::Var { name: name.to_string(), literal });
+            }
+        }
+    }
+
+    match block {
+        Some(open) => Err(anyhow!("Missing {{{{/{}}}}} in template", open)),
+        None => Ok((nodes, false)),
+    }
+}
+
+/// What names resolve to while rendering
+struct Scope<'a> {
+    source: &'a str,
+    row_count: Option<usize>,
+    columns: &'a [String],
+    cells: Option<&'a [CellValue]>,
+    /// Name and value of the cell `{{#each columns}}` is on
+    cell: Option<(&'a str, Option<&'a CellValue>)>,
+    index: usize,
+    last: bool,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Option<CellValue> {
+        match name {
+            "@index" => return Some(CellValue::new(Value::from(self.index))),
+            "@first" => return Some(CellValue::new(Value::Bool(self.index == 0))),
+            "@last" => return Some(CellValue::new(Value::Bool(self.last))),
+            _ => {}
+        }
+        if let Some((column, value)) = self.cell {
+            match name {
+                "name" => return Some(CellValue::new(Value::String(column.to_string()))),
+                "value" => return Some(value.cloned().unwrap_or_else(|| CellValue::new(Value::Null))),
+                _ => {}
+            }
+        }
+        if let Some(cells) = self.cells {
+            if let Some(cell) = self.columns.iter().position(|c| c == name).and_then(|i| cells.get(i)) {
+                return Some(cell.clone());
+            }
+        }
+        match name {
+            "source" => Some(CellValue::new(Value::String(self.source.to_string()))),
+            "row_count" => self.row_count.map(|n| CellValue::new(Value::from(n))),
+            _ => None,
+        }
+    }
+
+    fn render(&self, nodes: &[Node], out: &mut String) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Var { name, literal } => {
+                    let cell = self
+                        .lookup(name)
+                        .ok_or_else(|| anyhow!("Template refers to unknown value '{}'", name))?;
+                    match literal {
+                        Some(literal) => out.push_str(&literal.render(&cell)),
+                        None => out.push_str(&cell.display),
+                    }
+                }
+                // Rows are looped over by TemplateSink as they stream in
+                Node::EachRow(_) => {}
+                Node::EachColumn(body) => {
+                    for (i, column) in self.columns.iter().enumerate() {
+                        let scope = Scope {
+                            cell: Some((column, self.cells.and_then(|cells| cells.get(i)))),
+                            index: i,
+                            last: i + 1 == self.columns.len(),
+                            ..*self
+                        };
+                        scope.render(body, out)?;
+                    }
+                }
+                Node::If { name, negate, then, otherwise } => {
+                    let truthy = self.lookup(name).is_some_and(|cell| is_truthy(&cell.value));
+                    self.render(if truthy != *negate { then } else { otherwise }, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
+/// Renders rows through a template, holding one row back to know which is `@last`
+pub struct TemplateSink<W: Write> {
+    writer: W,
+    template: Template,
+    source: String,
+    columns: Vec<String>,
+    pending: Option<Vec<CellValue>>,
+    rows_written: usize,
+}
+
+impl<W: Write> TemplateSink<W> {
+    pub fn new(writer: W, template: Template, source: impl Into<String>) -> Self {
+        Self {
+            writer,
+            template,
+            source: source.into(),
+            columns: Vec::new(),
+            pending: None,
+            rows_written: 0,
+        }
+    }
+
+    fn scope<'a>(&'a self, cells: Option<&'a [CellValue]>, last: bool, row_count: Option<usize>) -> Scope<'a> {
+        Scope {
+            source: &self.source,
+            row_count,
+            columns: &self.columns,
+            cells,
+            cell: None,
+            index: self.rows_written,
+            last,
+        }
+    }
+
+    fn write_pending(&mut self, last: bool) -> Result<()> {
+        let Some(cells) = self.pending.take() else {
+            return Ok(());
+        };
+        let mut out = String::new();
+        self.scope(Some(&cells), last, None).render(&self.template.row, &mut out)?;
+        self.writer.write_all(out.as_bytes())?;
+        self.rows_written += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> RowSink for TemplateSink<W> {
+    fn begin(&mut self, columns: &[String]) -> Result<()> {
+        self.columns = columns.to_vec();
+        let mut out = String::new();
+        self.scope(None, false, None).render(&self.template.header, &mut out)?;
+        self.writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    fn row(&mut self, cells: &[CellValue]) -> Result<()> {
+        self.write_pending(false)?;
+        self.pending = Some(cells.to_vec());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_pending(true)?;
+        let mut out = String::new();
+        self.scope(None, false, Some(self.rows_written)).render(&self.template.footer, &mut out)?;
+        self.writer.write_all(out.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Re-run `sql` and write every row through `template` to `path`, compressed when the
+/// path ends in .gz/.zst
+pub fn export_query(db: &DatabaseManager, sql: &str, template: &TemplateSource, path: &Path) -> Result<usize> {
+    let parsed = Template::parse(&template.text).map_err(|e| anyhow!("Template {}: {}", template.name, e))?;
+    let mut writer = CompressedWriter::create(path)?;
+    let count = db.stream_query(sql, &mut TemplateSink::new(&mut writer, parsed, sql.trim()))?;
+    writer.finish()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, columns: &[&str], rows: &[Vec<Value>]) -> Result<String> {
+        let mut out = Vec::new();
+        let mut sink = TemplateSink::new(&mut out, Template::parse(template)?, "SELECT 1");
+        sink.begin(&columns.iter().map(|c| c.to_string()).collect::<Vec<_>>())?;
+        for row in rows {
+            sink.row(&row.iter().cloned().map(CellValue::new).collect::<Vec<_>>())?;
+        }
+        sink.finish()?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn renders_builtin_lua_template() {
+        let rows = [
+            vec![Value::from(1), Value::from("a\"b")],
+            vec![Value::from(2), Value::Null],
+        ];
+        assert_eq!(
+            render(LUA_TEMPLATE, &["id", "name"], &rows).unwrap(),
+            "-- Generated from SELECT 1\nreturn {\n  { id = 1, name = \"a\\\"b\" },\n  { id = 2, name = nil }\n}\n",
+        );
+    }
+
+    #[test]
+    fn renders_conditions_and_row_count() {
+        let template = "{{#each rows}}{{#if name}}{{name}}{{else}}-{{/if}}{{#unless @last}};{{/unless}}{{/each}} ({{row_count}})";
+        let rows = [vec![Value::from("x")], vec![Value::from("")], vec![Value::Null]];
+        assert_eq!(render(template, &["name"], &rows).unwrap(), "x;-;- (3)");
+        assert_eq!(render(template, &["name"], &[]).unwrap(), " (0)");
+    }
+
+    #[test]
+    fn renders_literals() {
+        let cell = |value: Value| CellValue::new(value);
+        assert_eq!(Literal::Lua.render(&cell(Value::from("a\n\t\\"))), "\"a\\n\\t\\\\\"");
+        assert_eq!(Literal::CSharp.render(&cell(Value::Null)), "null");
+        assert_eq!(Literal::GdScript.render(&cell(Value::Bool(true))), "true");
+        assert_eq!(Literal::Json.render(&cell(Value::from("x"))), "\"x\"");
+        assert_eq!(Literal::Str.render(&cell(Value::from(5))), "\"5\"");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        for template in [
+            "no loop",
+            "{{#each rows}}{{/each}}{{#each rows}}{{/each}}",
+            "{{#each rows}}{{#each rows}}{{/each}}{{/each}}",
+            "{{#each rows}}{{id|yaml}}{{/each}}",
+            "{{#each rows}}{{#if id}}{{/each}}",
+            "{{else}}{{#each rows}}{{/each}}",
+            "{{#each rows}}{{id",
+            "{{#each rows}}",
+        ] {
+            assert!(Template::parse(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn unknown_values_fail_at_render() {
+        assert!(render("{{#each rows}}{{missing}}{{/each}}", &["id"], &[vec![Value::from(1)]]).is_err());
+        assert!(render("{{#each rows}}{{/each}}{{missing}}", &["id"], &[]).is_err());
+    }
+}
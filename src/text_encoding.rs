@@ -0,0 +1,336 @@
+//! Text encodings for the CSV pipeline. Everything inside the plugin is UTF-8;
+//! these adapters transcode at the file boundary and handle byte order marks.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl TextEncoding {
+    pub const ALL: [TextEncoding; 4] = [
+        TextEncoding::Utf8,
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+        TextEncoding::Latin1,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16 LE",
+            TextEncoding::Utf16Be => "UTF-16 BE",
+            TextEncoding::Latin1 => "Latin-1",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|e| e == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn bom(&self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf8 => UTF8_BOM,
+            TextEncoding::Utf16Le => UTF16_LE_BOM,
+            TextEncoding::Utf16Be => UTF16_BE_BOM,
+            TextEncoding::Latin1 => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl Newline {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Reads text in `encoding` and yields UTF-8. For the UTF encodings a leading BOM is
+/// stripped and overrides the selection since it is unambiguous; Latin-1 has no BOM,
+/// so bytes like `ÿþ` at the start of a Latin-1 file are kept as text.
+pub struct DecodingReader<R: Read> {
+    inner: R,
+    encoding: TextEncoding,
+    pending: Vec<u8>,
+    output: Vec<u8>,
+    output_pos: usize,
+    bom_checked: bool,
+    eof: bool,
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R, encoding: TextEncoding) -> Self {
+        Self {
+            inner,
+            encoding,
+            pending: Vec::new(),
+            output: Vec::new(),
+            output_pos: 0,
+            bom_checked: false,
+            eof: false,
+        }
+    }
+
+    fn strip_bom(&mut self) {
+        self.bom_checked = true;
+        if self.encoding == TextEncoding::Latin1 {
+            return;
+        }
+        for (bom, encoding) in [
+            (UTF8_BOM, TextEncoding::Utf8),
+            (UTF16_LE_BOM, TextEncoding::Utf16Le),
+            (UTF16_BE_BOM, TextEncoding::Utf16Be),
+        ] {
+            if self.pending.starts_with(bom) {
+                self.pending.drain(..bom.len());
+                self.encoding = encoding;
+                break;
+            }
+        }
+    }
+
+    fn decode_pending(&mut self) {
+        self.output.clear();
+        self.output_pos = 0;
+
+        match self.encoding {
+            TextEncoding::Utf8 => {
+                self.output.append(&mut self.pending);
+            }
+            TextEncoding::Latin1 => {
+                let text: String = self.pending.drain(..).map(|b| b as char).collect();
+                self.output.extend_from_slice(text.as_bytes());
+            }
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+                let little_endian = self.encoding == TextEncoding::Utf16Le;
+                let mut units: Vec<u16> = self
+                    .pending
+                    .chunks_exact(2)
+                    .map(|b| {
+                        if little_endian {
+                            u16::from_le_bytes([b[0], b[1]])
+                        } else {
+                            u16::from_be_bytes([b[0], b[1]])
+                        }
+                    })
+                    .collect();
+                let mut consumed = units.len() * 2;
+
+                // A high surrogate at the end of the chunk needs its partner from the next read
+                if !self.eof && matches!(units.last(), Some(0xD800..=0xDBFF)) {
+                    units.pop();
+                    consumed -= 2;
+                }
+                self.pending.drain(..consumed);
+
+                let text: String = char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect();
+                self.output.extend_from_slice(text.as_bytes());
+
+                if self.eof && !self.pending.is_empty() {
+                    self.pending.clear();
+                    self.output.extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_pos >= self.output.len() {
+            if self.eof && self.pending.is_empty() {
+                return Ok(0);
+            }
+
+            let mut chunk = [0u8; 8192];
+            let n = self.inner.read(&mut chunk)?;
+            self.pending.extend_from_slice(&chunk[..n]);
+            self.eof = n == 0;
+
+            // Wait for enough bytes to recognise any BOM
+            if !self.bom_checked && (self.pending.len() >= UTF8_BOM.len() || self.eof) {
+                self.strip_bom();
+            }
+            if self.bom_checked {
+                self.decode_pending();
+            }
+        }
+
+        let n = buf.len().min(self.output.len() - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+/// Accepts UTF-8 and writes `encoding`, optionally preceded by a BOM
+pub struct EncodingWriter<W: Write> {
+    inner: W,
+    encoding: TextEncoding,
+    write_bom: bool,
+    /// Trailing bytes of a UTF-8 sequence split across writes
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    pub fn new(inner: W, encoding: TextEncoding, write_bom: bool) -> Self {
+        Self {
+            inner,
+            encoding,
+            write_bom,
+            pending: Vec::new(),
+        }
+    }
+
+    fn encode(&mut self, text: &str) -> io::Result<()> {
+        if self.write_bom {
+            self.write_bom = false;
+            self.inner.write_all(self.encoding.bom())?;
+        }
+
+        match self.encoding {
+            TextEncoding::Utf8 => self.inner.write_all(text.as_bytes()),
+            TextEncoding::Latin1 => {
+                let bytes: Vec<u8> = text
+                    .chars()
+                    .map(|c| if (c as u32) <= 0xFF { c as u8 } else { b'?' })
+                    .collect();
+                self.inner.write_all(&bytes)
+            }
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+                let little_endian = self.encoding == TextEncoding::Utf16Le;
+                let bytes: Vec<u8> = text
+                    .encode_utf16()
+                    .flat_map(|unit| {
+                        if little_endian {
+                            unit.to_le_bytes()
+                        } else {
+                            unit.to_be_bytes()
+                        }
+                    })
+                    .collect();
+                self.inner.write_all(&bytes)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+
+        let bytes: Vec<u8> = self.pending.drain(..valid).collect();
+        // Validated above, so this can't fail
+        let text = String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.encode(&text)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.write_bom {
+            // Empty outputs still get their BOM
+            self.encode("")?;
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(reader: impl Read, encoding: TextEncoding) -> String {
+        let mut text = String::new();
+        DecodingReader::new(reader, encoding).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    fn encode(text: &str, encoding: TextEncoding, write_bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut writer = EncodingWriter::new(&mut bytes, encoding, write_bom);
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        bytes
+    }
+
+    /// Hands out one byte per read, to split characters across chunks
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn latin1_keeps_bom_like_bytes() {
+        assert_eq!(decode(&[0xFF, 0xFE, b'a', 0xE9][..], TextEncoding::Latin1), "ÿþaé");
+    }
+
+    #[test]
+    fn utf_bom_overrides_selection() {
+        assert_eq!(decode(&[0xFF, 0xFE, b'h', 0, b'i', 0][..], TextEncoding::Utf8), "hi");
+        assert_eq!(decode(&[0xEF, 0xBB, 0xBF, b'o', b'k'][..], TextEncoding::Utf16Be), "ok");
+        assert_eq!(decode(&b"plain"[..], TextEncoding::Utf8), "plain");
+    }
+
+    #[test]
+    fn surrogate_pairs_survive_split_reads() {
+        let bytes = encode("a😀b", TextEncoding::Utf16Le, false);
+        assert_eq!(decode(Trickle(&bytes), TextEncoding::Utf16Le), "a😀b");
+    }
+
+    #[test]
+    fn round_trips_every_encoding() {
+        for encoding in TextEncoding::ALL {
+            let bytes = encode("naïve, 1\n", encoding, true);
+            assert!(bytes.starts_with(encoding.bom()));
+            assert_eq!(decode(&bytes[..], encoding), "naïve, 1\n", "{}", encoding.label());
+        }
+    }
+
+    #[test]
+    fn writer_handles_split_characters_and_unmappable_ones() {
+        let mut bytes = Vec::new();
+        let mut writer = EncodingWriter::new(&mut bytes, TextEncoding::Latin1, false);
+        let e_acute = "é".as_bytes();
+        writer.write_all(&e_acute[..1]).unwrap();
+        writer.write_all(&e_acute[1..]).unwrap();
+        writer.write_all("€".as_bytes()).unwrap();
+        assert_eq!(bytes, [0xE9, b'?']);
+        assert_eq!(encode("", TextEncoding::Utf8, true), UTF8_BOM);
+    }
+}
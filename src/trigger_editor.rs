@@ -0,0 +1,219 @@
+//! Creating and editing triggers as their full `CREATE TRIGGER` statement. Each is
+//! compiled with `EXPLAIN` before it replaces the old one, so a typo in the body is
+//! reported instead of dropping a working trigger.
+
+use anyhow::{Result, anyhow};
+use gpui::{prelude::*, *};
+use ui::{
+    h_flex, v_flex, button::{Button, ButtonVariants},
+    input::{TextInput, InputState, TabSize},
+    ActiveTheme, Sizable, Disableable,
+};
+use crate::database::DatabaseManager;
+use crate::sql_text::{quote_identifier, script_statements, words};
+
+/// The name and table of a `CREATE TRIGGER` statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerHeader {
+    pub name: String,
+    pub table: String,
+}
+
+impl TriggerHeader {
+    /// Read the header of `sql`, which must be a single `CREATE TRIGGER`
+    pub fn parse(sql: &str) -> Result<Self> {
+        let statements: Vec<_> = script_statements(sql)
+            .into_iter()
+            .filter(|range| !sql[range.clone()].trim().trim_end_matches(';').trim().is_empty())
+            .collect();
+        if statements.len() != 1 {
+            return Err(anyhow!("Write one CREATE TRIGGER statement, not {}", statements.len()));
+        }
+
+        let words = words(sql);
+        let keyword_at = |ix: usize, keyword: &str| words.get(ix).is_some_and(|w| w.is_keyword(keyword));
+        let mut ix = 1;
+        if keyword_at(ix, "TEMP") || keyword_at(ix, "TEMPORARY") {
+            ix += 1;
+        }
+        if !keyword_at(0, "CREATE") || !keyword_at(ix, "TRIGGER") {
+            return Err(anyhow!("Not a CREATE TRIGGER statement"));
+        }
+        ix += 1;
+        if keyword_at(ix, "IF") {
+            ix += 3;
+        }
+        let name = words.get(ix).ok_or_else(|| anyhow!("The trigger has no name"))?.text.clone();
+        let table = words[ix + 1..]
+            .iter()
+            .position(|w| w.is_keyword("ON"))
+            .and_then(|on| words.get(ix + 2 + on))
+            .ok_or_else(|| anyhow!("The trigger names no table (ON ...)"))?
+            .text
+            .clone();
+        Ok(Self { name, table })
+    }
+}
+
+/// A starting point for a new trigger on `table`
+pub fn template(table: &str) -> String {
+    format!(
+        "CREATE TRIGGER {}\nAFTER UPDATE ON {}\nFOR EACH ROW\nBEGIN\n  SELECT 1;\nEND;",
+        quote_identifier(&format!("{}_after_update", table)),
+        quote_identifier(table)
+    )
+}
+
+pub struct TriggerEditor {
+    db: DatabaseManager,
+    table: String,
+    sql_input: Entity<InputState>,
+    /// The trigger being edited; `None` for a new one
+    original: Option<String>,
+    /// Whether a trigger was saved, so the editor refreshes what depends on it
+    pub saved: bool,
+    message: Option<String>,
+}
+
+impl TriggerEditor {
+    /// Edit the trigger `original` on `table` from its stored SQL, or a new one when `None`
+    pub fn new(
+        db: DatabaseManager,
+        table: String,
+        original: Option<(String, String)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let (original, sql) = match original {
+            Some((name, sql)) => (Some(name), sql),
+            None => (None, template(&table)),
+        };
+        let sql_input = cx.new(|cx| {
+            let mut state = InputState::new(window, cx)
+                .code_editor("sql")
+                .line_number(true)
+                .minimap(false)
+                .tab_size(TabSize {
+                    tab_size: 2,
+                    hard_tabs: false,
+                })
+                .soft_wrap(true);
+            state.set_value(&sql, window, cx);
+            state
+        });
+
+        Self {
+            db,
+            table,
+            sql_input,
+            original,
+            saved: false,
+            message: None,
+        }
+    }
+
+    fn header(&self, cx: &App) -> Result<(String, TriggerHeader)> {
+        let sql = self.sql_input.read(cx).value().trim().to_string();
+        let header = TriggerHeader::parse(&sql)?;
+        Ok((sql, header))
+    }
+
+    /// Compile the statement without saving it
+    pub fn check(&mut self, cx: &mut Context<Self>) -> Result<()> {
+        let (sql, header) = self.header(cx)?;
+        self.db.check_trigger(self.original.as_deref(), &sql)?;
+        self.message = Some(format!("✓ {} on {} compiles", header.name, header.table));
+        Ok(())
+    }
+
+    pub fn save(&mut self, cx: &mut Context<Self>) -> Result<()> {
+        let (sql, header) = self.header(cx)?;
+        self.db.save_trigger(self.original.as_deref(), &sql)?;
+        tracing::info!("✓ Saved trigger {} on {}", header.name, header.table);
+        self.message = Some(format!("✓ Saved {}", header.name));
+        self.original = Some(header.name);
+        self.saved = true;
+        Ok(())
+    }
+}
+
+impl Render for TriggerEditor {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let title = match &self.original {
+            Some(name) => format!("Edit trigger {} on {}", name, self.table),
+            None => format!("New trigger on {}", self.table),
+        };
+        let can_write = !self.db.is_read_only();
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(
+                div()
+                    .w_full()
+                    .p_2()
+                    .text_sm()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(title)
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .p_2()
+                    .child(
+                        TextInput::new(&self.sql_input)
+                            .size_full()
+                            .font_family("monospace")
+                            .text_xs()
+                    )
+            )
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .items_center()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        Button::new("check-trigger")
+                            .label("Check")
+                            .tooltip("Compile the trigger with EXPLAIN without saving it")
+                            .xsmall()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.check(cx) {
+                                    editor.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("save-trigger")
+                            .label("Save Trigger")
+                            .tooltip("Compile the trigger, then replace the old definition with it")
+                            .xsmall()
+                            .primary()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.save(cx) {
+                                    tracing::error!("Failed to save trigger: {}", e);
+                                    editor.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(self.message.clone(), |row, message| {
+                        row.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            )
+    }
+}
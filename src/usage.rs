@@ -0,0 +1,168 @@
+//! Table and column usage accumulated from executed queries, for finding dead tables
+//! before a schema cleanup. Counts live in the `queries` sidecar beside the history,
+//! since the recent-query list itself is capped and deduplicated.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::sql_text::words;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageCounts {
+    pub queries: u64,
+    pub tables: BTreeMap<String, u64>,
+    /// Keyed `table.column`
+    pub columns: BTreeMap<String, u64>,
+    /// Unix seconds each table was last queried
+    pub last_used: BTreeMap<String, u64>,
+}
+
+impl UsageCounts {
+    /// Count the tables and columns `sql` refers to. `schema` pairs each table with
+    /// its columns; a column counts toward every referenced table that has it.
+    pub fn record(&mut self, sql: &str, schema: &[(String, Vec<String>)]) {
+        let (tables, columns) = references(sql, schema);
+        if tables.is_empty() {
+            return;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        self.queries += 1;
+        for table in tables {
+            *self.tables.entry(table.clone()).or_default() += 1;
+            self.last_used.insert(table, now);
+        }
+        for column in columns {
+            *self.columns.entry(column).or_default() += 1;
+        }
+    }
+}
+
+/// Tables `sql` names, and `table.column` for their columns it names
+pub fn references(sql: &str, schema: &[(String, Vec<String>)]) -> (Vec<String>, Vec<String>) {
+    let words = words(sql);
+    let named = |name: &str| {
+        words
+            .iter()
+            .any(|w| if w.quoted { w.text == name } else { w.text.eq_ignore_ascii_case(name) })
+    };
+
+    let mut tables = Vec::new();
+    let mut columns = Vec::new();
+    for (table, table_columns) in schema {
+        if !named(table) {
+            continue;
+        }
+        tables.push(table.clone());
+        for column in table_columns.iter().filter(|c| named(c)) {
+            columns.push(format!("{}.{}", table, column));
+        }
+    }
+    (tables, columns)
+}
+
+#[derive(Debug, Clone)]
+pub struct TableUsage {
+    pub table: String,
+    pub queries: u64,
+    pub last_used: Option<u64>,
+    /// Most queried first
+    pub columns: Vec<(String, u64)>,
+    pub unused_columns: Vec<String>,
+    /// Saved queries that still mention the table, even if none has run
+    pub saved_queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UsageReport {
+    pub queries: u64,
+    /// Most queried first
+    pub tables: Vec<TableUsage>,
+}
+
+impl UsageReport {
+    pub fn analyze(counts: &UsageCounts, schema: &[(String, Vec<String>)], saved: &[(String, String)]) -> Self {
+        let mut tables: Vec<TableUsage> = schema
+            .iter()
+            .map(|(table, table_columns)| {
+                let mut columns: Vec<(String, u64)> = table_columns
+                    .iter()
+                    .filter_map(|c| {
+                        let count = counts.columns.get(&format!("{}.{}", table, c)).copied()?;
+                        Some((c.clone(), count))
+                    })
+                    .collect();
+                columns.sort_by(|a, b| b.1.cmp(&a.1));
+                TableUsage {
+                    table: table.clone(),
+                    queries: counts.tables.get(table).copied().unwrap_or_default(),
+                    last_used: counts.last_used.get(table).copied(),
+                    unused_columns: table_columns
+                        .iter()
+                        .filter(|c| !columns.iter().any(|(used, _)| used == *c))
+                        .cloned()
+                        .collect(),
+                    columns,
+                    saved_queries: saved
+                        .iter()
+                        .filter(|(_, sql)| crate::sql_text::mentions(sql, table))
+                        .map(|(name, _)| name.clone())
+                        .collect(),
+                }
+            })
+            .collect();
+        tables.sort_by(|a, b| b.queries.cmp(&a.queries).then_with(|| a.table.cmp(&b.table)));
+
+        Self {
+            queries: counts.queries,
+            tables,
+        }
+    }
+
+    /// Tables no recorded query has touched
+    pub fn unused_tables(&self) -> impl Iterator<Item = &TableUsage> {
+        self.tables.iter().filter(|t| t.queries == 0)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Table usage\n\nFrom {} recorded queries.\n\n", self.queries);
+        md.push_str("| Table | Queries | Most queried columns | Never queried columns |\n");
+        md.push_str("|---|---|---|---|\n");
+        for table in self.tables.iter().filter(|t| t.queries > 0) {
+            let top = table
+                .columns
+                .iter()
+                .take(5)
+                .map(|(c, n)| format!("{} ({})", c, n))
+                .collect::<Vec<_>>()
+                .join(", ");
+            md.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                table.table,
+                table.queries,
+                top,
+                table.unused_columns.join(", ")
+            ));
+        }
+
+        let unused: Vec<_> = self.unused_tables().collect();
+        if !unused.is_empty() {
+            md.push_str("\n## Never queried\n\n");
+            for table in unused {
+                if table.saved_queries.is_empty() {
+                    md.push_str(&format!("- `{}`\n", table.table));
+                } else {
+                    md.push_str(&format!(
+                        "- `{}` (mentioned by saved queries: {})\n",
+                        table.table,
+                        table.saved_queries.join(", ")
+                    ));
+                }
+            }
+        }
+        md
+    }
+}
@@ -0,0 +1,88 @@
+//! UUID keys: spotting columns that hold them, as hyphenated text or 16-byte BLOBs,
+//! showing them shortened, and generating new ones so keys aren't typed by hand.
+
+use uuid::{Uuid, Variant};
+use crate::database::{CellValue, RowData};
+use crate::reflection::{SqlType, TypeSchema};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UuidVersion {
+    /// Random
+    V4,
+    /// Time-ordered, so new keys sort last and index well
+    #[default]
+    V7,
+}
+
+impl UuidVersion {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UuidVersion::V4 => "UUIDv4",
+            UuidVersion::V7 => "UUIDv7",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            UuidVersion::V4 => UuidVersion::V7,
+            UuidVersion::V7 => UuidVersion::V4,
+        }
+    }
+
+    /// A new UUID as lowercase hyphenated text
+    pub fn generate(&self) -> String {
+        let uuid = match self {
+            UuidVersion::V4 => Uuid::new_v4(),
+            UuidVersion::V7 => Uuid::now_v7(),
+        };
+        uuid.hyphenated().to_string()
+    }
+}
+
+/// `text` as a UUID if it is one in hyphenated or braced form. Bare 32-digit hex is
+/// left alone; it is as likely to be a hash.
+pub fn parse_text(text: &str) -> Option<Uuid> {
+    let text = text.trim();
+    if text.len() != 36 && text.len() != 38 {
+        return None;
+    }
+    Uuid::try_parse(text).ok()
+}
+
+/// The UUID in a cell: hyphenated text, or a 16-byte BLOB with the RFC 4122 variant
+/// bits, which random bytes only have a quarter of the time
+pub fn cell_uuid(cell: &CellValue) -> Option<Uuid> {
+    match &cell.blob {
+        Some(bytes) => Uuid::from_slice(bytes).ok().filter(|u| u.get_variant() == Variant::RFC4122),
+        None => cell.value.as_str().and_then(parse_text),
+    }
+}
+
+/// `1b4e28ba…4ec8`, enough to tell keys apart at a glance
+pub fn compact(uuid: &Uuid) -> String {
+    let text = uuid.hyphenated().to_string();
+    format!("{}…{}", &text[..8], &text[32..])
+}
+
+/// Whether each schema field holds UUIDs: every non-NULL value in `rows` is one, or,
+/// with no values to go on, a text or BLOB column is named like `*_uuid` or `*guid`
+pub fn detect_columns(schema: &TypeSchema, rows: &[RowData]) -> Vec<bool> {
+    schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(ix, field)| {
+            let mut cells = rows
+                .iter()
+                .filter_map(|row| row.cells.get(ix))
+                .filter(|cell| !cell.value.is_null())
+                .peekable();
+            if cells.peek().is_some() {
+                return cells.all(|cell| cell_uuid(cell).is_some());
+            }
+            let name = field.name.to_lowercase();
+            matches!(field.sql_type, SqlType::Text | SqlType::Blob)
+                && (name.ends_with("uuid") || name.ends_with("guid"))
+        })
+        .collect()
+}
@@ -0,0 +1,211 @@
+//! Creating and editing views: a name and the SELECT, which for an existing view is
+//! read back from its `CREATE VIEW` in `sqlite_master` and replaced in one transaction.
+
+use anyhow::{Result, anyhow};
+use gpui::{prelude::*, *};
+use ui::{
+    h_flex, v_flex, button::{Button, ButtonVariants},
+    input::{TextInput, InputState, TabSize},
+    ActiveTheme, Sizable, Disableable,
+};
+use crate::database::DatabaseManager;
+use crate::sql_text::words;
+
+/// What follows a view's name in its `CREATE VIEW`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewDefinition {
+    /// The column list, in parentheses, when the view names its columns
+    pub columns: Option<String>,
+    pub select: String,
+}
+
+impl ViewDefinition {
+    /// The stored definition of `view`
+    pub fn load(db: &DatabaseManager, view: &str) -> Result<Self> {
+        let sql = db.view_sql(view)?;
+        Self::parse(&sql).ok_or_else(|| anyhow!("Can't read the definition of view {}", view))
+    }
+
+    /// The column list and SELECT of a `CREATE VIEW` statement
+    pub fn parse(create_sql: &str) -> Option<Self> {
+        let words = words(create_sql);
+        let mut ix = words.iter().position(|w| w.is_keyword("VIEW"))? + 1;
+        if words.get(ix).is_some_and(|w| w.is_keyword("IF")) {
+            ix += 3;
+        }
+        let mut name_end = words.get(ix)?.range.end;
+        // A schema-qualified name is two words
+        if create_sql[name_end..].starts_with('.') {
+            name_end = words.get(ix + 1)?.range.end;
+        }
+        let as_word = words.iter().find(|w| w.range.start >= name_end && w.is_keyword("AS"))?;
+        let columns = create_sql[name_end..as_word.range.start].trim();
+        let select = create_sql[as_word.range.end..].trim().trim_end_matches(';').trim_end();
+        Some(Self {
+            columns: (!columns.is_empty()).then(|| columns.to_string()),
+            select: select.to_string(),
+        })
+    }
+
+    /// The text after the view's name
+    pub fn to_sql(&self) -> String {
+        match &self.columns {
+            Some(columns) => format!("{} AS {}", columns, self.select),
+            None => format!("AS {}", self.select),
+        }
+    }
+}
+
+pub struct ViewEditor {
+    db: DatabaseManager,
+    name_input: Entity<InputState>,
+    sql_input: Entity<InputState>,
+    /// The view being edited; `None` for a new one
+    original: Option<String>,
+    /// The edited view's column list, kept as it was
+    columns: Option<String>,
+    /// The view saved last, for the editor to open
+    pub saved: Option<String>,
+    message: Option<String>,
+}
+
+impl ViewEditor {
+    /// Edit `original`, whose stored definition is `definition`, or a new view when `None`
+    pub fn new(
+        db: DatabaseManager,
+        original: Option<String>,
+        definition: Option<ViewDefinition>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let ViewDefinition { columns, select } = definition.unwrap_or(ViewDefinition {
+            columns: None,
+            select: "SELECT * FROM ".to_string(),
+        });
+
+        let name = original.clone().unwrap_or_default();
+        let name_input = cx.new(|cx| {
+            let mut state = InputState::new(window, cx);
+            state.set_value(&name, window, cx);
+            state
+        });
+        let sql_input = cx.new(|cx| {
+            let mut state = InputState::new(window, cx)
+                .code_editor("sql")
+                .line_number(true)
+                .minimap(false)
+                .tab_size(TabSize {
+                    tab_size: 2,
+                    hard_tabs: false,
+                })
+                .soft_wrap(true);
+            state.set_value(&select, window, cx);
+            state
+        });
+
+        Self {
+            db,
+            name_input,
+            sql_input,
+            original,
+            columns,
+            saved: None,
+            message: None,
+        }
+    }
+
+    pub fn save(&mut self, cx: &mut Context<Self>) -> Result<()> {
+        let name = self.name_input.read(cx).value().trim().to_string();
+        if name.is_empty() {
+            return Err(anyhow!("Name the view"));
+        }
+        let select = self.sql_input.read(cx).value().trim().trim_end_matches(';').trim_end().to_string();
+        if select.is_empty() {
+            return Err(anyhow!("Write the view's SELECT"));
+        }
+        let definition = ViewDefinition { columns: self.columns.clone(), select };
+
+        self.db.save_view(self.original.as_deref(), &name, &definition.to_sql())?;
+        tracing::info!("✓ Saved view {}", name);
+        self.message = Some(format!("✓ Saved {}", name));
+        self.original = Some(name.clone());
+        self.saved = Some(name);
+        Ok(())
+    }
+}
+
+impl Render for ViewEditor {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let title = match &self.original {
+            Some(name) => format!("Edit view {}", name),
+            None => "New view".to_string(),
+        };
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(div().text_sm().child(title))
+                    .child(TextInput::new(&self.name_input).w_64().text_xs())
+                    .when_some(self.columns.clone(), |row, columns| {
+                        row.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!("columns {}", columns))
+                        )
+                    })
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .p_2()
+                    .child(
+                        TextInput::new(&self.sql_input)
+                            .size_full()
+                            .font_family("monospace")
+                            .text_xs()
+                    )
+            )
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .items_center()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        Button::new("save-view")
+                            .label("Save View")
+                            .tooltip("Create the view from the SELECT above, replacing the old definition")
+                            .xsmall()
+                            .primary()
+                            .disabled(self.db.is_read_only())
+                            .on_click(cx.listener(|editor, _, _, cx| {
+                                if let Err(e) = editor.save(cx) {
+                                    tracing::error!("Failed to save view: {}", e);
+                                    editor.message = Some(e.to_string());
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .when_some(self.message.clone(), |row, message| {
+                        row.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message)
+                        )
+                    })
+            )
+    }
+}
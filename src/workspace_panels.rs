@@ -1,177 +1,1796 @@
-use gpui::*;
-use ui::{ActiveTheme, StyledExt, dock::{Panel, PanelEvent}, v_flex, table::Table};
-use std::path::PathBuf;
-use crate::{
-    table_view::DataTableView,
-    query_editor::QueryEditorView,
-    database::DatabaseManager,
-};
-
-/// Table Panel - wraps a single table view
-pub struct TablePanelWrapper {
-    table_name: String,
-    table_view: Entity<Table<DataTableView>>,
-    focus_handle: FocusHandle,
-}
-
-impl TablePanelWrapper {
-    pub fn new(
-        table_name: String,
-        table_view: Entity<Table<DataTableView>>,
-        cx: &mut Context<Self>,
-    ) -> Self {
-        Self {
-            table_name,
-            table_view,
-            focus_handle: cx.focus_handle(),
-        }
-    }
-}
-
-impl EventEmitter<PanelEvent> for TablePanelWrapper {}
-
-impl Render for TablePanelWrapper {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        div()
-            .size_full()
-            .child(self.table_view.clone())
-    }
-}
-
-impl Focusable for TablePanelWrapper {
-    fn focus_handle(&self, _cx: &App) -> FocusHandle {
-        self.focus_handle.clone()
-    }
-}
-
-impl Panel for TablePanelWrapper {
-    fn panel_name(&self) -> &'static str {
-        "table"
-    }
-
-    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
-        self.table_name.clone().into_any_element()
-    }
-
-    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
-        ui::dock::PanelState {
-            panel_name: self.panel_name().to_string(),
-            ..Default::default()
-        }
-    }
-}
-
-/// Query Panel - wraps a query editor view
-pub struct QueryPanelWrapper {
-    query_name: String,
-    query_view: Entity<QueryEditorView>,
-    focus_handle: FocusHandle,
-}
-
-impl QueryPanelWrapper {
-    pub fn new(
-        query_name: String,
-        query_view: Entity<QueryEditorView>,
-        cx: &mut Context<Self>,
-    ) -> Self {
-        Self {
-            query_name,
-            query_view,
-            focus_handle: cx.focus_handle(),
-        }
-    }
-}
-
-impl EventEmitter<PanelEvent> for QueryPanelWrapper {}
-
-impl Render for QueryPanelWrapper {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        div()
-            .size_full()
-            .child(self.query_view.clone())
-    }
-}
-
-impl Focusable for QueryPanelWrapper {
-    fn focus_handle(&self, _cx: &App) -> FocusHandle {
-        self.focus_handle.clone()
-    }
-}
-
-impl Panel for QueryPanelWrapper {
-    fn panel_name(&self) -> &'static str {
-        "query"
-    }
-
-    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
-        self.query_name.clone().into_any_element()
-    }
-
-    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
-        ui::dock::PanelState {
-            panel_name: self.panel_name().to_string(),
-            ..Default::default()
-        }
-    }
-}
-
-/// Welcome Panel - shown when no tables/queries are open
-pub struct WelcomePanelWrapper {
-    focus_handle: FocusHandle,
-}
-
-impl WelcomePanelWrapper {
-    pub fn new(cx: &mut Context<Self>) -> Self {
-        Self {
-            focus_handle: cx.focus_handle(),
-        }
-    }
-}
-
-impl EventEmitter<PanelEvent> for WelcomePanelWrapper {}
-
-impl Render for WelcomePanelWrapper {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        v_flex()
-            .size_full()
-            .items_center()
-            .justify_center()
-            .gap_4()
-            .child(
-                div()
-                    .text_xl()
-                    .font_weight(gpui::FontWeight::BOLD)
-                    .text_color(cx.theme().foreground)
-                    .child("Welcome to Database Editor")
-            )
-            .child(
-                div()
-                    .text_sm()
-                    .text_color(cx.theme().muted_foreground)
-                    .child("Select a table from the sidebar or create a new query")
-            )
-    }
-}
-
-impl Focusable for WelcomePanelWrapper {
-    fn focus_handle(&self, _cx: &App) -> FocusHandle {
-        self.focus_handle.clone()
-    }
-}
-
-impl Panel for WelcomePanelWrapper {
-    fn panel_name(&self) -> &'static str {
-        "welcome"
-    }
-
-    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
-        "Welcome".into_any_element()
-    }
-
-    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
-        ui::dock::PanelState {
-            panel_name: self.panel_name().to_string(),
-            ..Default::default()
-        }
-    }
-}
+use gpui::{prelude::*, *};
+use ui::{
+    ActiveTheme, Sizable, StyledExt, dock::{Panel, PanelEvent}, h_flex, v_flex, table::Table,
+    button::{Button, ButtonVariants}, input::{InputEvent, InputState, TextInput},
+};
+use std::path::PathBuf;
+use crate::{
+    audio::Playback,
+    filter_presets::{FilterPreset, FilterPresets},
+    interop::RecordPayload,
+    row_filter::{Connective, FilterCondition, FilterOp, RowFilter},
+    table_view::DataTableView,
+    query_editor::QueryEditorView,
+    database::{DatabaseManager, IndexInfo},
+};
+
+/// Tags offered next to a `has tag` filter row
+const KNOWN_TAGS_SHOWN: usize = 6;
+
+/// Table Panel - wraps a single table view
+pub struct TablePanelWrapper {
+    table_name: String,
+    table_view: Entity<Table<DataTableView>>,
+    focus_handle: FocusHandle,
+    /// Filter bar rows being edited; applied to the view only on Apply
+    filter_draft: Vec<DraftCondition>,
+    filter_message: Option<String>,
+    /// Single-line WHERE box, made on first render since inputs need a window
+    where_input: Option<Entity<InputState>>,
+    where_error: Option<String>,
+    /// The project's filter presets, shared in a sidecar beside the database
+    presets: FilterPresets,
+    preset_name_input: Option<Entity<InputState>>,
+    /// Preset whose × was clicked once; the second click removes it for everyone
+    preset_delete_pending: Option<String>,
+    preset_message: Option<String>,
+    _subscriptions: Vec<Subscription>,
+}
+
+/// One filter bar row, with its inputs
+struct DraftCondition {
+    connective: Connective,
+    column_ix: usize,
+    op: FilterOp,
+    value: Entity<InputState>,
+    upper: Entity<InputState>,
+    /// The column's tags, offered for `has tag`
+    known_tags: Vec<String>,
+}
+
+impl TablePanelWrapper {
+    pub fn new(
+        table_name: String,
+        table_view: Entity<Table<DataTableView>>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let presets = table_view
+            .read(cx)
+            .delegate()
+            .db_path()
+            .map(|path| {
+                FilterPresets::load(path).unwrap_or_else(|e| {
+                    tracing::error!("Failed to load filter presets: {}", e);
+                    FilterPresets::default()
+                })
+            })
+            .unwrap_or_default();
+        Self {
+            table_name,
+            table_view,
+            focus_handle: cx.focus_handle(),
+            filter_draft: Vec::new(),
+            filter_message: None,
+            where_input: None,
+            where_error: None,
+            presets,
+            preset_name_input: None,
+            preset_delete_pending: None,
+            preset_message: None,
+            _subscriptions: Vec::new(),
+        }
+    }
+
+    fn where_input(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Entity<InputState> {
+        if let Some(input) = &self.where_input {
+            return input.clone();
+        }
+        let input = cx.new(|cx| InputState::new(window, cx));
+        self._subscriptions.push(cx.subscribe_in(
+            &input,
+            window,
+            |panel: &mut Self, _, event: &InputEvent, _, cx| {
+                if matches!(event, InputEvent::PressEnter { .. }) {
+                    panel.apply_raw_where(cx);
+                    cx.notify();
+                }
+            },
+        ));
+        self.where_input = Some(input.clone());
+        input
+    }
+
+    /// Run the WHERE box against the table, keeping SQLite's complaint beside it
+    fn apply_raw_where(&mut self, cx: &mut Context<Self>) {
+        let Some(input) = &self.where_input else {
+            return;
+        };
+        let text = input.read(cx).value().to_string();
+        let result = self.table_view.update(cx, |table, cx| {
+            let result = table.delegate_mut().set_raw_where(&text);
+            cx.notify();
+            result
+        });
+        self.where_error = result.err().map(|e| e.to_string());
+    }
+
+    fn render_where_bar(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let input = self.where_input(window, cx);
+        let active = self.table_view.read(cx).delegate().state.raw_where.is_some();
+
+        h_flex()
+            .w_full()
+            .gap_2()
+            .px_3()
+            .py_1()
+            .items_center()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(div().text_xs().font_family("monospace").text_color(cx.theme().muted_foreground).child("WHERE"))
+            .child(div().flex_1().child(TextInput::new(&input).xsmall()))
+            .child(
+                Button::new("where-apply")
+                    .label("Filter")
+                    .tooltip("Append this condition to the table query (Enter)")
+                    .xsmall()
+                    .when(active, |b| b.primary())
+                    .when(!active, |b| b.ghost())
+                    .on_click(cx.listener(|panel, _, _, cx| {
+                        panel.apply_raw_where(cx);
+                        cx.notify();
+                    }))
+            )
+            .when(active, |this| {
+                this.child(
+                    Button::new("where-clear")
+                        .label("Clear")
+                        .xsmall()
+                        .ghost()
+                        .on_click(cx.listener(|panel, _, window, cx| {
+                            if let Some(input) = &panel.where_input {
+                                input.update(cx, |input, cx| input.set_value("", window, cx));
+                            }
+                            panel.apply_raw_where(cx);
+                            cx.notify();
+                        }))
+                )
+            })
+            .when_some(self.where_error.clone(), |this, error| {
+                this.child(div().text_xs().text_color(cx.theme().red).child(error))
+            })
+    }
+
+    fn add_filter_condition(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.filter_draft.push(DraftCondition {
+            connective: Connective::And,
+            column_ix: 0,
+            op: FilterOp::Equals,
+            value: cx.new(|cx| InputState::new(window, cx)),
+            upper: cx.new(|cx| InputState::new(window, cx)),
+            known_tags: Vec::new(),
+        });
+    }
+
+    /// Collect the tags a `has tag` row can pick from, after its column or operator changed
+    fn refresh_known_tags(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let Some(draft) = self.filter_draft.get(ix) else {
+            return;
+        };
+        let known_tags = if draft.op == FilterOp::HasTag {
+            self.table_view.read(cx).delegate().known_tags(draft.column_ix)
+        } else {
+            Vec::new()
+        };
+        self.filter_draft[ix].known_tags = known_tags;
+    }
+
+    /// Build the draft into a filter and run it against the table
+    fn apply_filter(&mut self, cx: &mut Context<Self>) {
+        let field_names = self.table_view.read(cx).delegate().field_names();
+        let conditions = self
+            .filter_draft
+            .iter()
+            .filter_map(|draft| {
+                Some(FilterCondition {
+                    connective: draft.connective,
+                    column: field_names.get(draft.column_ix)?.clone(),
+                    op: draft.op,
+                    value: draft.value.read(cx).value().to_string(),
+                    upper: draft.upper.read(cx).value().to_string(),
+                })
+            })
+            .collect();
+        let result = self.table_view.update(cx, |table, cx| {
+            let result = table.delegate_mut().set_row_filter(RowFilter { conditions });
+            cx.notify();
+            result
+        });
+        self.filter_message = match result {
+            Ok(()) => None,
+            Err(e) => {
+                tracing::error!("Failed to apply filter: {}", e);
+                Some(e.to_string())
+            }
+        };
+    }
+
+    fn clear_filter(&mut self, cx: &mut Context<Self>) {
+        self.filter_draft.clear();
+        self.filter_message = None;
+        self.table_view.update(cx, |table, cx| {
+            if let Err(e) = table.delegate_mut().set_row_filter(RowFilter::default()) {
+                tracing::error!("Failed to clear filter: {}", e);
+            }
+            cx.notify();
+        });
+    }
+
+    fn preset_name_input(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Entity<InputState> {
+        if let Some(input) = &self.preset_name_input {
+            return input.clone();
+        }
+        let input = cx.new(|cx| InputState::new(window, cx));
+        self.preset_name_input = Some(input.clone());
+        input
+    }
+
+    /// Change the project's presets and write them back, re-reading the sidecar first so
+    /// presets teammates added since the table opened aren't lost
+    fn update_presets(&mut self, cx: &mut Context<Self>, f: impl FnOnce(&mut FilterPresets)) -> anyhow::Result<()> {
+        let path = self
+            .table_view
+            .read(cx)
+            .delegate()
+            .db_path()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| anyhow::anyhow!("An in-memory database has no project to share presets in"))?;
+        let mut presets = FilterPresets::load(&path)?;
+        f(&mut presets);
+        presets.save(&path)?;
+        self.presets = presets;
+        Ok(())
+    }
+
+    /// Save the table's applied filter, WHERE and sort under the typed name
+    fn save_preset(&mut self, cx: &mut Context<Self>) {
+        let Some(input) = &self.preset_name_input else {
+            return;
+        };
+        let name = input.read(cx).value().trim().to_string();
+        if name.is_empty() {
+            self.preset_message = Some("Name the preset first".to_string());
+            return;
+        }
+        let delegate = self.table_view.read(cx).delegate();
+        let preset = FilterPreset {
+            name: name.clone(),
+            table: self.table_name.clone(),
+            filter: delegate.state.row_filter.clone(),
+            raw_where: delegate.state.raw_where.clone(),
+            sort: delegate.state.sort.clone(),
+        };
+        match self.update_presets(cx, |presets| presets.remember(preset)) {
+            Ok(()) => {
+                tracing::info!("✓ Saved filter preset {} for {}", name, self.table_name);
+                self.preset_message = None;
+            }
+            Err(e) => {
+                tracing::error!("Failed to save filter preset: {}", e);
+                self.preset_message = Some(format!("Error: {}", e));
+            }
+        }
+    }
+
+    fn delete_preset(&mut self, name: &str, cx: &mut Context<Self>) {
+        let table = self.table_name.clone();
+        self.preset_delete_pending = None;
+        match self.update_presets(cx, |presets| presets.remove(&table, name)) {
+            Ok(()) => {
+                tracing::info!("✓ Removed filter preset {} from {}", name, table);
+                self.preset_message = None;
+            }
+            Err(e) => {
+                tracing::error!("Failed to remove filter preset: {}", e);
+                self.preset_message = Some(format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Apply a preset to the table and load it into the filter bar and WHERE box for editing
+    fn apply_preset(&mut self, name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(preset) = self.presets.for_table(&self.table_name).find(|p| p.name == name).cloned() else {
+            return;
+        };
+        let result = self.table_view.update(cx, |table, cx| {
+            let result = table
+                .delegate_mut()
+                .set_view(preset.filter.clone(), preset.raw_where.as_deref(), preset.sort.clone());
+            cx.notify();
+            result
+        });
+        if let Err(e) = result {
+            tracing::error!("Failed to apply filter preset: {}", e);
+            self.preset_message = Some(format!("Error: {}", e));
+            return;
+        }
+
+        let field_names = self.table_view.read(cx).delegate().field_names();
+        self.filter_draft = preset
+            .filter
+            .conditions
+            .iter()
+            .map(|condition| DraftCondition {
+                connective: condition.connective,
+                column_ix: field_names.iter().position(|f| *f == condition.column).unwrap_or(0),
+                op: condition.op,
+                value: filled_input(&condition.value, window, cx),
+                upper: filled_input(&condition.upper, window, cx),
+                known_tags: Vec::new(),
+            })
+            .collect();
+        for ix in 0..self.filter_draft.len() {
+            self.refresh_known_tags(ix, cx);
+        }
+        let raw_where = preset.raw_where.clone().unwrap_or_default();
+        let input = self.where_input(window, cx);
+        input.update(cx, |input, cx| input.set_value(&raw_where, window, cx));
+        let input = self.preset_name_input(window, cx);
+        input.update(cx, |input, cx| input.set_value(&preset.name, window, cx));
+        self.filter_message = None;
+        self.where_error = None;
+        self.preset_message = None;
+    }
+
+    /// Chips for the table's presets, and a name box to save the current view as one
+    fn render_preset_bar(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let input = self.preset_name_input(window, cx);
+        let presets: Vec<FilterPreset> = self.presets.for_table(&self.table_name).cloned().collect();
+        let pending = self.preset_delete_pending.clone();
+
+        h_flex()
+            .w_full()
+            .gap_2()
+            .px_3()
+            .py_1()
+            .items_center()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(div().text_xs().text_color(cx.theme().muted_foreground).child("presets"))
+            .when(presets.is_empty(), |this| {
+                this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("none yet — shared with everyone on the project"))
+            })
+            .children(presets.into_iter().enumerate().map(|(ix, preset)| {
+                let confirming = pending.as_deref() == Some(preset.name.as_str());
+                let apply_name = preset.name.clone();
+                let delete_name = preset.name.clone();
+                h_flex()
+                    .gap_0p5()
+                    .child(
+                        Button::new(("filter-preset", ix))
+                            .label(preset.name.clone())
+                            .tooltip(preset.describe())
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(move |panel, _, window, cx| {
+                                panel.apply_preset(&apply_name, window, cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new(("filter-preset-remove", ix))
+                            .label(if confirming { "Confirm Remove" } else { "×" })
+                            .tooltip("Remove the preset from the project")
+                            .xsmall()
+                            .when(confirming, |b| b.primary())
+                            .when(!confirming, |b| b.ghost())
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if panel.preset_delete_pending.as_deref() == Some(delete_name.as_str()) {
+                                    panel.delete_preset(&delete_name, cx);
+                                } else {
+                                    panel.preset_delete_pending = Some(delete_name.clone());
+                                }
+                                cx.notify();
+                            }))
+                    )
+            }))
+            .child(div().flex_1())
+            .child(div().w_40().child(TextInput::new(&input).xsmall()))
+            .child(
+                Button::new("filter-preset-save")
+                    .label("Save Preset")
+                    .tooltip("Save the applied filter, WHERE and sort for the whole team")
+                    .xsmall()
+                    .ghost()
+                    .on_click(cx.listener(|panel, _, _, cx| {
+                        panel.save_preset(cx);
+                        cx.notify();
+                    }))
+            )
+            .when_some(self.preset_message.clone(), |this, message| {
+                this.child(div().text_xs().text_color(cx.theme().red).child(message))
+            })
+    }
+
+    fn render_filter_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let field_names = self.table_view.read(cx).delegate().field_names();
+        let filtered = !self.table_view.read(cx).delegate().state.row_filter.is_empty();
+
+        v_flex()
+            .w_full()
+            .gap_1()
+            .px_3()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .children(self.filter_draft.iter().enumerate().map(|(ix, draft)| {
+                let column = field_names.get(draft.column_ix).cloned().unwrap_or_default();
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(if ix == 0 {
+                        div().w_12().text_xs().text_color(cx.theme().muted_foreground).child("where").into_any_element()
+                    } else {
+                        Button::new(("filter-connective", ix))
+                            .label(draft.connective.keyword())
+                            .tooltip("AND binds tighter than OR")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if let Some(draft) = panel.filter_draft.get_mut(ix) {
+                                    draft.connective = draft.connective.toggled();
+                                }
+                                cx.notify();
+                            }))
+                            .into_any_element()
+                    })
+                    .child(
+                        Button::new(("filter-column", ix))
+                            .label(column)
+                            .tooltip("Click to choose the column")
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                let columns = panel.table_view.read(cx).delegate().field_names().len().max(1);
+                                if let Some(draft) = panel.filter_draft.get_mut(ix) {
+                                    draft.column_ix = (draft.column_ix + 1) % columns;
+                                }
+                                panel.refresh_known_tags(ix, cx);
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new(("filter-op", ix))
+                            .label(draft.op.label())
+                            .xsmall()
+                            .outline()
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if let Some(draft) = panel.filter_draft.get_mut(ix) {
+                                    draft.op = draft.op.next();
+                                }
+                                panel.refresh_known_tags(ix, cx);
+                                cx.notify();
+                            }))
+                    )
+                    .when(draft.op.operands() >= 1, |this| {
+                        this.child(div().w_48().child(TextInput::new(&draft.value).xsmall()))
+                    })
+                    .when(draft.op.operands() >= 2, |this| {
+                        this.child(div().text_xs().child("and"))
+                            .child(div().w_48().child(TextInput::new(&draft.upper).xsmall()))
+                    })
+                    .when(draft.op == FilterOp::InList, |this| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("comma-separated"))
+                    })
+                    .when(draft.op == FilterOp::Equals, |this| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("x'…' to match bytes"))
+                    })
+                    .when(draft.op == FilterOp::StartsWithBytes, |this| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("hex, e.g. x'89504E47'"))
+                    })
+                    .when(draft.op == FilterOp::ByteLength, |this| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child("e.g. 16, >1024 or <=0"))
+                    })
+                    .when(draft.op == FilterOp::HasTag, |this| {
+                        this.children(draft.known_tags.iter().take(KNOWN_TAGS_SHOWN).enumerate().map(|(tag_ix, tag)| {
+                            let value = draft.value.clone();
+                            let tag = tag.clone();
+                            Button::new(("filter-tag", ix * KNOWN_TAGS_SHOWN + tag_ix))
+                                .label(tag.clone())
+                                .xsmall()
+                                .ghost()
+                                .on_click(cx.listener(move |_, _, window, cx| {
+                                    value.update(cx, |input, cx| input.set_value(&tag, window, cx));
+                                    cx.notify();
+                                }))
+                        }))
+                    })
+                    .child(
+                        Button::new(("filter-remove", ix))
+                            .label("×")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if ix < panel.filter_draft.len() {
+                                    panel.filter_draft.remove(ix);
+                                }
+                                cx.notify();
+                            }))
+                    )
+            }))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        Button::new("filter-add")
+                            .label("+ Condition")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(|panel, _, window, cx| {
+                                panel.add_filter_condition(window, cx);
+                                cx.notify();
+                            }))
+                    )
+                    .when(!self.filter_draft.is_empty(), |this| {
+                        this.child(
+                            Button::new("filter-apply")
+                                .label("Apply")
+                                .xsmall()
+                                .primary()
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.apply_filter(cx);
+                                    cx.notify();
+                                }))
+                        )
+                    })
+                    .when(!self.filter_draft.is_empty() || filtered, |this| {
+                        this.child(
+                            Button::new("filter-clear")
+                                .label("Clear")
+                                .xsmall()
+                                .ghost()
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.clear_filter(cx);
+                                    cx.notify();
+                                }))
+                        )
+                    })
+                    .when_some(self.filter_message.clone(), |this, message| {
+                        this.child(div().text_xs().text_color(cx.theme().red).child(message))
+                    })
+            )
+    }
+}
+
+fn filled_input(value: &str, window: &mut Window, cx: &mut Context<TablePanelWrapper>) -> Entity<InputState> {
+    cx.new(|cx| {
+        let mut state = InputState::new(window, cx);
+        state.set_value(value, window, cx);
+        state
+    })
+}
+
+impl EventEmitter<PanelEvent> for TablePanelWrapper {}
+
+impl Render for TablePanelWrapper {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Keep the same rows in view after a refresh reordered or reloaded them
+        self.table_view.update(cx, |table, cx| {
+            if let Some(row_ix) = table.delegate_mut().state.pending_scroll.take() {
+                table.scroll_to_row(row_ix, cx);
+            }
+        });
+
+        let delegate = self.table_view.read(cx).delegate();
+        let sample_banner = delegate.is_sampled().then(|| {
+            format!(
+                "Showing a random sample of {} rows from ~{} — the table is too large to load up front",
+                delegate.rows_loaded(),
+                delegate.state.estimated_rows
+            )
+        });
+
+        // BLOBs and oversized text open here instead of inline in the grid
+        let large_cell = delegate.state.large_cell.as_ref().map(|large| {
+            let kind = match large.audio {
+                Some(format) => format!("{} audio BLOB", format.label()),
+                None if large.is_blob => "BLOB".to_string(),
+                None => "text".to_string(),
+            };
+            let title = format!(
+                "{} of row {} — {} {}",
+                large.column,
+                large.row_id,
+                crate::database::format_bytes(large.size),
+                kind
+            );
+            // Markdown cells say which tab is showing
+            let markdown = large.markdown.then_some(large.show_preview);
+            let audio = large.audio.map(|_| delegate.playback());
+            (title, large.is_blob, large.preview.clone(), large.input.clone(), delegate.is_read_only(), markdown, audio)
+        });
+
+        let index_hint = delegate.state.index_hint.as_ref().map(|hint| hint.message());
+
+        let changes_banner = (!delegate.state.changes.is_empty())
+            .then(|| (delegate.state.changes.summary(), delegate.state.show_only_modified));
+
+        // Each pasted column not matched by name gets a button cycling through table columns
+        let paste_banner = delegate.state.pending_paste.as_ref().map(|pasted| {
+            let field_names = delegate.field_names();
+            let unmatched: Vec<(usize, String, String)> = pasted
+                .unmatched
+                .iter()
+                .map(|&col| {
+                    let target = pasted.targets[col]
+                        .and_then(|t| field_names.get(t).cloned())
+                        .unwrap_or_else(|| "skip".to_string());
+                    (col, pasted.column_label(col), target)
+                })
+                .collect();
+            let mapping = if pasted.headers.is_some() { "by header name" } else { "by position" };
+            let mut message = format!("{} pasted rows mapped {}", pasted.rows.len(), mapping);
+            let confirming = delegate.state.paste_confirming;
+            if let Some(reason) = delegate.paste_over_threshold().filter(|_| confirming) {
+                message.push_str(&format!(
+                    " — ⚠ {} is over the confirmation threshold. Check this is the right table ({}), then insert again.",
+                    reason,
+                    self.table_name
+                ));
+            }
+            (message, unmatched, confirming)
+        });
+
+        v_flex()
+            .size_full()
+            // Plain typing over the grid jumps to matching rows in the focused column
+            .on_key_down(cx.listener(|panel, event: &KeyDownEvent, _, cx| {
+                let modifiers = event.keystroke.modifiers;
+                if modifiers.control || modifiers.alt || modifiers.platform {
+                    return;
+                }
+                let Some(text) = event.keystroke.key_char.clone() else {
+                    return;
+                };
+                panel.table_view.update(cx, |table, cx| {
+                    if let Err(e) = table.delegate_mut().type_ahead(&text) {
+                        tracing::error!("Failed to search column: {}", e);
+                    }
+                    cx.notify();
+                });
+                cx.notify();
+            }))
+            // Records dragged from other Pulsar editors, or payload files from disk
+            .drag_over::<RecordPayload>(|style, _, _, cx| style.bg(cx.theme().accent.opacity(0.1)))
+            .on_drop(cx.listener(|panel, payload: &RecordPayload, _, cx| {
+                panel.table_view.update(cx, |table, cx| {
+                    table.delegate_mut().stage_payload(payload);
+                    cx.notify();
+                });
+                cx.notify();
+            }))
+            .on_drop(cx.listener(|panel, paths: &ExternalPaths, _, cx| {
+                // One staged paste at a time, so only the first payload file is taken
+                let Some(path) = paths.paths().iter().find(|p| p.extension().is_some_and(|e| e == "json")) else {
+                    return;
+                };
+                match RecordPayload::from_file(path) {
+                    Ok(payload) => panel.table_view.update(cx, |table, cx| {
+                        table.delegate_mut().stage_payload(&payload);
+                        cx.notify();
+                    }),
+                    Err(e) => tracing::error!("Failed to read dropped records: {}", e),
+                }
+                cx.notify();
+            }))
+            .when_some(paste_banner, |this, (message, unmatched, confirming)| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_2()
+                        .items_center()
+                        .bg(cx.theme().accent.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_sm().child(message))
+                        .children(unmatched.into_iter().map(|(col, label, target)| {
+                            Button::new(("paste-target", col))
+                                .label(format!("{} → {}", label, target))
+                                .tooltip("Unmatched column: click to choose the table column it fills")
+                                .small()
+                                .ghost()
+                                .on_click(cx.listener(move |panel, _, _, cx| {
+                                    panel.table_view.update(cx, |table, cx| {
+                                        table.delegate_mut().cycle_paste_target(col);
+                                        cx.notify();
+                                    });
+                                    cx.notify();
+                                }))
+                        }))
+                        .child(div().flex_1())
+                        .child(
+                            Button::new("commit-paste")
+                                .label(if confirming { "Confirm Insert" } else { "Insert rows" })
+                                .small()
+                                .primary()
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.table_view.update(cx, |table, cx| {
+                                        match table.delegate_mut().commit_paste() {
+                                            Ok(Some(count)) => tracing::info!("✓ Inserted {} pasted rows", count),
+                                            Ok(None) => {}
+                                            Err(e) => tracing::error!("Failed to insert pasted rows: {}", e),
+                                        }
+                                        cx.notify();
+                                    });
+                                    cx.notify();
+                                }))
+                        )
+                        .child(
+                            Button::new("discard-paste")
+                                .label("Discard")
+                                .small()
+                                .outline()
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.table_view.update(cx, |table, cx| {
+                                        table.delegate_mut().discard_paste();
+                                        cx.notify();
+                                    });
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
+            .when_some(large_cell, |this, (title, is_blob, preview, input, read_only, markdown, audio)| {
+                let show_preview = markdown == Some(true);
+                this.child(
+                    v_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_2()
+                        .bg(cx.theme().accent.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .items_center()
+                                .child(div().text_sm().child(title))
+                                .when(markdown.is_some(), |this| {
+                                    this.children([(false, "Edit"), (true, "Preview")].map(|(preview, label)| {
+                                        Button::new(("markdown-tab", preview as usize))
+                                            .label(label)
+                                            .xsmall()
+                                            .when(preview == show_preview, |b| b.primary())
+                                            .when(preview != show_preview, |b| b.ghost())
+                                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                                panel.table_view.update(cx, |table, cx| {
+                                                    let delegate = table.delegate_mut();
+                                                    if delegate.state.large_cell.as_ref().is_some_and(|l| l.show_preview != preview) {
+                                                        delegate.toggle_markdown_preview();
+                                                    }
+                                                    cx.notify();
+                                                });
+                                                cx.notify();
+                                            }))
+                                    }))
+                                })
+                                .when_some(audio, |this, playback| {
+                                    this.child(
+                                        Button::new("audio-play")
+                                            .label(if playback == Playback::Stopped { "▶ Play" } else { "⟲ Restart" })
+                                            .tooltip("Play the sound from the start")
+                                            .xsmall()
+                                            .primary()
+                                            .on_click(cx.listener(|panel, _, _, cx| {
+                                                panel.table_view.update(cx, |table, cx| {
+                                                    if let Err(e) = table.delegate_mut().play_large_cell() {
+                                                        tracing::error!("Failed to play sound: {}", e);
+                                                    }
+                                                    cx.notify();
+                                                });
+                                                cx.notify();
+                                            }))
+                                    )
+                                    .when(playback != Playback::Stopped, |this| {
+                                        this.child(
+                                            Button::new("audio-pause")
+                                                .label(if playback == Playback::Paused { "Resume" } else { "Pause" })
+                                                .xsmall()
+                                                .outline()
+                                                .on_click(cx.listener(|panel, _, _, cx| {
+                                                    panel.table_view.update(cx, |table, cx| {
+                                                        table.delegate_mut().toggle_audio_pause();
+                                                        cx.notify();
+                                                    });
+                                                    cx.notify();
+                                                }))
+                                        )
+                                    })
+                                })
+                        )
+                        .when(is_blob, |this| {
+                            this.child(
+                                div()
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(preview)
+                            )
+                            .child(
+                                h_flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(div().text_xs().child("Replace from file"))
+                                    .child(div().w_64().child(TextInput::new(&input).xsmall()))
+                            )
+                        })
+                        .when(!is_blob && !show_preview, |this| {
+                            this.child(div().h_64().w_full().child(TextInput::new(&input).h_full()))
+                        })
+                        .when(show_preview, |this| {
+                            let text = input.read(cx).value().to_string();
+                            this.child(
+                                div()
+                                    .id("markdown-preview")
+                                    .h_64()
+                                    .w_full()
+                                    .p_2()
+                                    .overflow_y_scroll()
+                                    .bg(cx.theme().background)
+                                    .border_1()
+                                    .border_color(cx.theme().border)
+                                    .rounded_sm()
+                                    .child(crate::markdown::render_preview(&text, cx))
+                            )
+                        })
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(
+                                    Button::new("large-cell-save")
+                                        .label(if is_blob { "Load file" } else { "Save" })
+                                        .small()
+                                        .primary()
+                                        .disabled(read_only)
+                                        .on_click(cx.listener(move |panel, _, _, cx| {
+                                            panel.table_view.update(cx, |table, cx| {
+                                                let delegate = table.delegate_mut();
+                                                let result = if is_blob {
+                                                    delegate.replace_blob_from_file(cx)
+                                                } else {
+                                                    delegate.save_large_cell(cx)
+                                                };
+                                                if let Err(e) = result {
+                                                    tracing::error!("Failed to save value: {}", e);
+                                                }
+                                                cx.notify();
+                                            });
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("large-cell-export")
+                                        .label("Save to file")
+                                        .tooltip("Write the whole value to a file in the working directory")
+                                        .small()
+                                        .outline()
+                                        .on_click(cx.listener(|panel, _, _, cx| {
+                                            if let Err(e) = panel.table_view.read(cx).delegate().export_large_cell() {
+                                                tracing::error!("Failed to save value to file: {}", e);
+                                            }
+                                        }))
+                                )
+                                .when(!is_blob, |this| {
+                                    this.child(
+                                        Button::new("large-cell-full")
+                                            .label("Show in grid")
+                                            .tooltip("Lift the size limit for this cell; copying and inline editing take the full value")
+                                            .small()
+                                            .ghost()
+                                            .on_click(cx.listener(|panel, _, _, cx| {
+                                                panel.table_view.update(cx, |table, cx| {
+                                                    table.delegate_mut().show_large_cell_in_full();
+                                                    cx.notify();
+                                                });
+                                                cx.notify();
+                                            }))
+                                    )
+                                })
+                                .child(
+                                    Button::new("large-cell-close")
+                                        .label("Close")
+                                        .small()
+                                        .ghost()
+                                        .on_click(cx.listener(|panel, _, _, cx| {
+                                            panel.table_view.update(cx, |table, cx| {
+                                                table.delegate_mut().close_large_cell();
+                                                cx.notify();
+                                            });
+                                            cx.notify();
+                                        }))
+                                )
+                        )
+                )
+            })
+            .when_some(changes_banner, |this, (message, only_modified)| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_1()
+                        .items_center()
+                        .justify_between()
+                        .bg(cx.theme().warning.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_xs().child(message))
+                        .child(
+                            Button::new("only-modified")
+                                .label("Only changed rows")
+                                .xsmall()
+                                .when(only_modified, |b| b.primary())
+                                .when(!only_modified, |b| b.ghost())
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.table_view.update(cx, |table, cx| {
+                                        if let Err(e) = table.delegate_mut().toggle_only_modified() {
+                                            tracing::error!("Failed to filter changed rows: {}", e);
+                                        }
+                                        cx.notify();
+                                    });
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
+            .when_some(sample_banner, |this, message| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_2()
+                        .items_center()
+                        .justify_between()
+                        .bg(cx.theme().accent.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_sm().child(message))
+                        .child(
+                            Button::new("load-full-table")
+                                .label("Load full table")
+                                .small()
+                                .outline()
+                                .on_click(cx.listener(|panel, _, _, cx| {
+                                    panel.table_view.update(cx, |table, cx| {
+                                        if let Err(e) = table.delegate_mut().load_full_table() {
+                                            tracing::error!("Failed to load full table: {}", e);
+                                        }
+                                        cx.notify();
+                                    });
+                                    cx.notify();
+                                }))
+                        )
+                )
+            })
+            .when_some(index_hint, |this, message| {
+                this.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_3()
+                        .py_1()
+                        .items_center()
+                        .justify_between()
+                        .bg(cx.theme().accent.opacity(0.1))
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().text_xs().child(message))
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(
+                                    Button::new("create-hinted-index")
+                                        .label("Create Index")
+                                        .xsmall()
+                                        .outline()
+                                        .on_click(cx.listener(|panel, _, _, cx| {
+                                            panel.table_view.update(cx, |table, cx| {
+                                                if let Err(e) = table.delegate_mut().create_hinted_index() {
+                                                    tracing::error!("Failed to create index: {}", e);
+                                                }
+                                                cx.notify();
+                                            });
+                                            cx.notify();
+                                        }))
+                                )
+                                .child(
+                                    Button::new("dismiss-index-hint")
+                                        .label("Dismiss")
+                                        .xsmall()
+                                        .ghost()
+                                        .on_click(cx.listener(|panel, _, _, cx| {
+                                            panel.table_view.update(cx, |table, cx| {
+                                                table.delegate_mut().dismiss_index_hint();
+                                                cx.notify();
+                                            });
+                                            cx.notify();
+                                        }))
+                                )
+                        )
+                )
+            })
+            .child(self.render_preset_bar(window, cx))
+            .child(self.render_where_bar(window, cx))
+            .child(self.render_filter_bar(cx))
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .child(self.table_view.clone())
+            )
+    }
+}
+
+impl Focusable for TablePanelWrapper {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for TablePanelWrapper {
+    fn panel_name(&self) -> &'static str {
+        "table"
+    }
+
+    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
+        self.table_name.clone().into_any_element()
+    }
+
+    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
+        ui::dock::PanelState {
+            panel_name: self.panel_name().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Query Panel - wraps a query editor view
+pub struct QueryPanelWrapper {
+    query_name: String,
+    query_view: Entity<QueryEditorView>,
+    focus_handle: FocusHandle,
+}
+
+impl QueryPanelWrapper {
+    pub fn new(
+        query_name: String,
+        query_view: Entity<QueryEditorView>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self {
+            query_name,
+            query_view,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+}
+
+impl EventEmitter<PanelEvent> for QueryPanelWrapper {}
+
+impl Render for QueryPanelWrapper {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .child(self.query_view.clone())
+    }
+}
+
+impl Focusable for QueryPanelWrapper {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for QueryPanelWrapper {
+    fn panel_name(&self) -> &'static str {
+        "query"
+    }
+
+    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
+        self.query_name.clone().into_any_element()
+    }
+
+    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
+        ui::dock::PanelState {
+            panel_name: self.panel_name().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Index Manager Panel - every table's indexes, with create, drop and reindex
+pub struct IndexManagerPanel {
+    db: DatabaseManager,
+    indexes: Vec<IndexInfo>,
+    table_input: Entity<InputState>,
+    /// Comma-separated key columns
+    columns_input: Entity<InputState>,
+    /// Optional WHERE condition, for a partial index
+    where_input: Entity<InputState>,
+    unique: bool,
+    message: Option<String>,
+    focus_handle: FocusHandle,
+}
+
+impl IndexManagerPanel {
+    pub fn new(db: DatabaseManager, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut panel = Self {
+            db,
+            indexes: Vec::new(),
+            table_input: cx.new(|cx| InputState::new(window, cx)),
+            columns_input: cx.new(|cx| InputState::new(window, cx)),
+            where_input: cx.new(|cx| InputState::new(window, cx)),
+            unique: false,
+            message: None,
+            focus_handle: cx.focus_handle(),
+        };
+        panel.refresh();
+        panel
+    }
+
+    /// Start the create form on `table`
+    pub fn set_table(&mut self, table: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.table_input.update(cx, |input, cx| input.set_value(table, window, cx));
+    }
+
+    pub fn refresh(&mut self) {
+        match self.db.list_indexes() {
+            Ok(indexes) => self.indexes = indexes,
+            Err(e) => {
+                tracing::error!("Failed to list indexes: {}", e);
+                self.message = Some(format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Create the index typed in the form, named after its table and columns
+    pub fn create_index(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let table = self.table_input.read(cx).value().trim().to_string();
+        if !self.db.list_tables()?.contains(&table) {
+            return Err(anyhow::anyhow!("No table named '{}'", table));
+        }
+        let columns: Vec<String> = self
+            .columns_input
+            .read(cx)
+            .value()
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+        let condition = self.where_input.read(cx).value().to_string();
+        let name = format!("{}_{}_{}", if self.unique { "ux" } else { "ix" }, table, columns.join("_"));
+
+        self.db.create_index(&name, &table, &columns, self.unique, Some(&condition))?;
+        self.db.refresh_schemas()?;
+        self.refresh();
+        self.message = Some(format!("✓ Created {}", name));
+        Ok(())
+    }
+
+    pub fn drop_index(&mut self, name: &str) -> anyhow::Result<()> {
+        self.db.drop_index(name)?;
+        self.db.refresh_schemas()?;
+        self.refresh();
+        self.message = Some(format!("✓ Dropped {}", name));
+        tracing::info!("✓ Dropped index {}", name);
+        Ok(())
+    }
+
+    /// Rebuild one index, or all of them, e.g. after a collation changed
+    pub fn reindex(&mut self, name: Option<&str>) -> anyhow::Result<()> {
+        self.db.reindex(name)?;
+        self.message = Some(match name {
+            Some(name) => format!("✓ Rebuilt {}", name),
+            None => format!("✓ Rebuilt all {} indexes", self.indexes.len()),
+        });
+        tracing::info!("✓ Reindexed {}", name.unwrap_or("the database"));
+        Ok(())
+    }
+}
+
+impl EventEmitter<PanelEvent> for IndexManagerPanel {}
+
+impl Render for IndexManagerPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_write = !self.db.is_read_only();
+
+        let mut list = v_flex().w_full().gap_1();
+        let mut current_table: Option<&str> = None;
+        for (ix, index) in self.indexes.iter().enumerate() {
+            if current_table != Some(index.table_name.as_str()) {
+                current_table = Some(index.table_name.as_str());
+                list = list.child(
+                    div()
+                        .pt_2()
+                        .text_sm()
+                        .font_semibold()
+                        .text_color(cx.theme().foreground)
+                        .child(index.table_name.clone())
+                );
+            }
+            let mut traits = Vec::new();
+            if index.unique {
+                traits.push("unique");
+            }
+            if index.partial {
+                traits.push("partial");
+            }
+            match index.origin.as_str() {
+                "u" => traits.push("from UNIQUE constraint"),
+                "pk" => traits.push("from PRIMARY KEY"),
+                _ => {}
+            }
+            let name = index.name.clone();
+            let reindex_name = index.name.clone();
+            list = list.child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .pl_2()
+                    .items_center()
+                    .text_xs()
+                    .child(div().font_family("monospace").child(index.name.clone()))
+                    .child(div().text_color(cx.theme().muted_foreground).child(format!("({})", index.columns.join(", "))))
+                    .child(div().flex_1().text_color(cx.theme().muted_foreground).child(traits.join(", ")))
+                    .child(
+                        Button::new(("reindex", ix))
+                            .label("Reindex")
+                            .xsmall()
+                            .ghost()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if let Err(e) = panel.reindex(Some(&reindex_name)) {
+                                    tracing::error!("Failed to reindex {}: {}", reindex_name, e);
+                                    panel.message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new(("drop-index", ix))
+                            .label("Drop")
+                            .tooltip(if index.is_automatic() {
+                                "Made for a constraint; it goes when the constraint does"
+                            } else {
+                                "Drop this index"
+                            })
+                            .xsmall()
+                            .ghost()
+                            .disabled(!can_write || index.is_automatic())
+                            .on_click(cx.listener(move |panel, _, _, cx| {
+                                if let Err(e) = panel.drop_index(&name) {
+                                    tracing::error!("Failed to drop index {}: {}", name, e);
+                                    panel.message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+            );
+        }
+        if self.indexes.is_empty() {
+            list = list.child(field_label("No indexes yet", cx));
+        }
+
+        v_flex()
+            .id("index-manager")
+            .size_full()
+            .p_3()
+            .gap_2()
+            .overflow_y_scroll()
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_semibold()
+                            .text_color(cx.theme().foreground)
+                            .child(format!("{} indexes", self.indexes.len()))
+                    )
+                    .child(div().flex_1())
+                    .child(
+                        Button::new("refresh-indexes")
+                            .label("Refresh")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(|panel, _, _, cx| {
+                                panel.refresh();
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("reindex-all")
+                            .label("Reindex All")
+                            .tooltip("Rebuild every index in the database")
+                            .xsmall()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|panel, _, _, cx| {
+                                if let Err(e) = panel.reindex(None) {
+                                    tracing::error!("Failed to reindex: {}", e);
+                                    panel.message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .items_end()
+                    .child(v_flex().gap_1().child(field_label("table", cx)).child(div().w_32().child(TextInput::new(&self.table_input).xsmall())))
+                    .child(v_flex().gap_1().child(field_label("columns, comma-separated", cx)).child(div().w_48().child(TextInput::new(&self.columns_input).xsmall())))
+                    .child(v_flex().gap_1().child(field_label("where (partial index)", cx)).child(div().w_48().child(TextInput::new(&self.where_input).xsmall())))
+                    .child(
+                        Button::new("index-unique")
+                            .label("Unique")
+                            .xsmall()
+                            .when(self.unique, |b| b.primary())
+                            .when(!self.unique, |b| b.ghost())
+                            .on_click(cx.listener(|panel, _, _, cx| {
+                                panel.unique = !panel.unique;
+                                cx.notify();
+                            }))
+                    )
+                    .child(
+                        Button::new("create-index")
+                            .label("Create Index")
+                            .xsmall()
+                            .outline()
+                            .disabled(!can_write)
+                            .on_click(cx.listener(|panel, _, _, cx| {
+                                if let Err(e) = panel.create_index(cx) {
+                                    tracing::error!("Failed to create index: {}", e);
+                                    panel.message = Some(format!("Error: {}", e));
+                                }
+                                cx.notify();
+                            }))
+                    )
+            )
+            .when_some(self.message.clone(), |this, message| {
+                this.child(div().text_xs().text_color(cx.theme().muted_foreground).child(message))
+            })
+            .child(list)
+    }
+}
+
+fn field_label(text: &'static str, cx: &App) -> Div {
+    div().text_xs().text_color(cx.theme().muted_foreground).child(text)
+}
+
+impl Focusable for IndexManagerPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for IndexManagerPanel {
+    fn panel_name(&self) -> &'static str {
+        "indexes"
+    }
+
+    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
+        "Indexes".into_any_element()
+    }
+
+    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
+        ui::dock::PanelState {
+            panel_name: self.panel_name().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Width of a table box in the ER diagram
+const ER_NODE_WIDTH: f32 = 200.0;
+const ER_HEADER_HEIGHT: f32 = 24.0;
+const ER_COLUMN_HEIGHT: f32 = 16.0;
+/// Pointer travel past which a press on a node is a drag, not a click
+const ER_DRAG_THRESHOLD: f32 = 3.0;
+
+/// A table box in the ER diagram
+struct ErNode {
+    table: String,
+    /// Column names, each with `PK`, `FK` or nothing
+    columns: Vec<(String, &'static str)>,
+    origin: Point<Pixels>,
+}
+
+impl ErNode {
+    fn height(&self) -> Pixels {
+        px(ER_HEADER_HEIGHT + ER_COLUMN_HEIGHT * self.columns.len() as f32 + 8.0)
+    }
+}
+
+/// A foreign key drawn from the referencing table's box to the referenced one's
+struct ErEdge {
+    from: usize,
+    to: usize,
+}
+
+struct NodeDrag {
+    node: usize,
+    start: Point<Pixels>,
+    origin: Point<Pixels>,
+    moved: bool,
+}
+
+pub enum ErDiagramEvent {
+    OpenTable(String),
+}
+
+/// ER Diagram Panel - tables as draggable boxes joined by their foreign keys
+pub struct ErDiagramPanel {
+    db: DatabaseManager,
+    nodes: Vec<ErNode>,
+    edges: Vec<ErEdge>,
+    drag: Option<NodeDrag>,
+    message: Option<String>,
+    focus_handle: FocusHandle,
+}
+
+impl ErDiagramPanel {
+    pub fn new(db: DatabaseManager, cx: &mut Context<Self>) -> Self {
+        let mut panel = Self {
+            db,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            drag: None,
+            message: None,
+            focus_handle: cx.focus_handle(),
+        };
+        panel.refresh();
+        panel
+    }
+
+    /// Re-read tables and foreign keys from the schema, keeping boxes the user moved where they are
+    pub fn refresh(&mut self) {
+        if let Err(e) = self.load() {
+            tracing::error!("Failed to read the schema for the ER diagram: {}", e);
+            self.message = Some(format!("Error: {}", e));
+        }
+    }
+
+    fn load(&mut self) -> anyhow::Result<()> {
+        let tables = self.db.list_tables()?;
+        let mut nodes = Vec::with_capacity(tables.len());
+        let mut keys = Vec::new();
+        for table in &tables {
+            let primary_key: Vec<String> = self.db.primary_key_columns(table)?.into_iter().map(|(name, _)| name).collect();
+            let foreign_keys = self.db.foreign_keys(table)?;
+            let columns = self
+                .db
+                .column_names(table)?
+                .into_iter()
+                .map(|name| {
+                    let role = if primary_key.contains(&name) {
+                        "PK"
+                    } else if foreign_keys.iter().any(|k| k.column == name) {
+                        "FK"
+                    } else {
+                        ""
+                    };
+                    (name, role)
+                })
+                .collect();
+            let origin = self
+                .nodes
+                .iter()
+                .find(|n| &n.table == table)
+                .map_or(Point::default(), |n| n.origin);
+            nodes.push(ErNode { table: table.clone(), columns, origin });
+            keys.extend(foreign_keys.into_iter().map(|k| (table.clone(), k.referenced_table)));
+        }
+
+        // New tables go on a grid below the ones already placed
+        let placed: Vec<bool> = nodes
+            .iter()
+            .map(|n| self.nodes.iter().any(|old| old.table == n.table))
+            .collect();
+        let mut y = nodes
+            .iter()
+            .zip(&placed)
+            .filter(|(_, placed)| **placed)
+            .map(|(n, _)| n.origin.y + n.height() + px(24.0))
+            .fold(px(16.0), |a, b| a.max(b));
+        let unplaced: Vec<usize> = (0..nodes.len()).filter(|&i| !placed[i]).collect();
+        for row in unplaced.chunks(4) {
+            let mut row_height = px(0.0);
+            for (column, &i) in row.iter().enumerate() {
+                nodes[i].origin = point(px(16.0 + column as f32 * (ER_NODE_WIDTH + 48.0)), y);
+                row_height = row_height.max(nodes[i].height());
+            }
+            y += row_height + px(24.0);
+        }
+
+        let position = |name: &str| nodes.iter().position(|n| n.table.eq_ignore_ascii_case(name));
+        self.edges = keys
+            .iter()
+            .filter_map(|(from, to)| Some(ErEdge { from: position(from)?, to: position(to)? }))
+            .filter(|edge| edge.from != edge.to)
+            .collect();
+        self.nodes = nodes;
+        self.drag = None;
+        Ok(())
+    }
+
+    /// End points of each edge, relative to the diagram: the referencing box's nearer
+    /// side to the middle of the referenced one's
+    fn edge_lines(&self) -> Vec<(Point<Pixels>, Point<Pixels>)> {
+        self.edges
+            .iter()
+            .map(|edge| {
+                let (from, to) = (&self.nodes[edge.from], &self.nodes[edge.to]);
+                let middle = |n: &ErNode| n.origin.y + n.height() / 2.0;
+                let width = px(ER_NODE_WIDTH);
+                if from.origin.x + width < to.origin.x {
+                    (point(from.origin.x + width, middle(from)), point(to.origin.x, middle(to)))
+                } else if to.origin.x + width < from.origin.x {
+                    (point(from.origin.x, middle(from)), point(to.origin.x + width, middle(to)))
+                } else if from.origin.y < to.origin.y {
+                    (point(from.origin.x + width / 2.0, from.origin.y + from.height()), point(to.origin.x + width / 2.0, to.origin.y))
+                } else {
+                    (point(from.origin.x + width / 2.0, from.origin.y), point(to.origin.x + width / 2.0, to.origin.y + to.height()))
+                }
+            })
+            .collect()
+    }
+
+    fn start_drag(&mut self, node: usize, position: Point<Pixels>) {
+        let origin = self.nodes[node].origin;
+        self.drag = Some(NodeDrag { node, start: position, origin, moved: false });
+    }
+
+    fn drag_to(&mut self, position: Point<Pixels>) -> bool {
+        let Some(drag) = &mut self.drag else {
+            return false;
+        };
+        let delta = position - drag.start;
+        if delta.x.abs() > px(ER_DRAG_THRESHOLD) || delta.y.abs() > px(ER_DRAG_THRESHOLD) {
+            drag.moved = true;
+        }
+        if drag.moved {
+            let origin = drag.origin + delta;
+            self.nodes[drag.node].origin = point(origin.x.max(px(0.0)), origin.y.max(px(0.0)));
+        }
+        drag.moved
+    }
+
+    /// Finish a drag; a press that never moved opens the table
+    fn end_drag(&mut self, cx: &mut Context<Self>) {
+        if let Some(drag) = self.drag.take() {
+            if !drag.moved {
+                cx.emit(ErDiagramEvent::OpenTable(self.nodes[drag.node].table.clone()));
+            }
+        }
+    }
+}
+
+impl EventEmitter<PanelEvent> for ErDiagramPanel {}
+impl EventEmitter<ErDiagramEvent> for ErDiagramPanel {}
+
+impl Render for ErDiagramPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let lines = self.edge_lines();
+        let edge_color = cx.theme().muted_foreground;
+
+        let mut diagram = div()
+            .id("er-diagram")
+            .relative()
+            .flex_1()
+            .w_full()
+            .overflow_hidden()
+            .on_mouse_move(cx.listener(|panel, event: &MouseMoveEvent, _, cx| {
+                if panel.drag_to(event.position) {
+                    cx.notify();
+                }
+            }))
+            .on_mouse_up(MouseButton::Left, cx.listener(|panel, _, _, cx| {
+                panel.end_drag(cx);
+                cx.notify();
+            }))
+            .on_mouse_up_out(MouseButton::Left, cx.listener(|panel, _, _, cx| {
+                panel.drag = None;
+                cx.notify();
+            }))
+            .child(
+                canvas(
+                    |_, _, _| (),
+                    move |bounds, _, window, _| {
+                        for (from, to) in &lines {
+                            let mut builder = PathBuilder::stroke(px(1.5));
+                            builder.move_to(bounds.origin + *from);
+                            builder.line_to(bounds.origin + *to);
+                            if let Ok(path) = builder.build() {
+                                window.paint_path(path, edge_color);
+                            }
+                        }
+                    },
+                )
+                .absolute()
+                .size_full()
+            );
+
+        for (ix, node) in self.nodes.iter().enumerate() {
+            let dragging = self.drag.as_ref().is_some_and(|d| d.node == ix && d.moved);
+            diagram = diagram.child(
+                v_flex()
+                    .id(("er-node", ix))
+                    .absolute()
+                    .left(node.origin.x)
+                    .top(node.origin.y)
+                    .w(px(ER_NODE_WIDTH))
+                    .h(node.height())
+                    .pb_1()
+                    .bg(cx.theme().background)
+                    .border_1()
+                    .border_color(if dragging { cx.theme().accent } else { cx.theme().border })
+                    .rounded_md()
+                    .cursor_pointer()
+                    .on_mouse_down(MouseButton::Left, cx.listener(move |panel, event: &MouseDownEvent, _, cx| {
+                        panel.start_drag(ix, event.position);
+                        cx.stop_propagation();
+                    }))
+                    .child(
+                        div()
+                            .h(px(ER_HEADER_HEIGHT))
+                            .px_2()
+                            .flex()
+                            .items_center()
+                            .bg(cx.theme().muted.opacity(0.5))
+                            .border_b_1()
+                            .border_color(cx.theme().border)
+                            .text_xs()
+                            .font_semibold()
+                            .text_color(cx.theme().foreground)
+                            .child(node.table.clone())
+                    )
+                    .children(node.columns.iter().map(|(name, role)| {
+                        h_flex()
+                            .h(px(ER_COLUMN_HEIGHT))
+                            .px_2()
+                            .gap_2()
+                            .text_xs()
+                            .child(div().flex_1().text_color(cx.theme().foreground).child(name.clone()))
+                            .child(div().text_color(cx.theme().muted_foreground).child(*role))
+                    }))
+            );
+        }
+
+        v_flex()
+            .size_full()
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_semibold()
+                            .text_color(cx.theme().foreground)
+                            .child(format!("{} tables, {} foreign keys", self.nodes.len(), self.edges.len()))
+                    )
+                    .child(field_label("drag boxes to arrange them; click one to open its table", cx))
+                    .child(div().flex_1())
+                    .when_some(self.message.clone(), |this, message| {
+                        this.child(div().text_xs().text_color(cx.theme().muted_foreground).child(message))
+                    })
+                    .child(
+                        Button::new("refresh-er-diagram")
+                            .label("Refresh")
+                            .xsmall()
+                            .ghost()
+                            .on_click(cx.listener(|panel, _, _, cx| {
+                                panel.refresh();
+                                cx.notify();
+                            }))
+                    )
+            )
+            .child(diagram)
+    }
+}
+
+impl Focusable for ErDiagramPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for ErDiagramPanel {
+    fn panel_name(&self) -> &'static str {
+        "er-diagram"
+    }
+
+    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
+        "ER Diagram".into_any_element()
+    }
+
+    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
+        ui::dock::PanelState {
+            panel_name: self.panel_name().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Welcome Panel - shown when no tables/queries are open
+pub struct WelcomePanelWrapper {
+    focus_handle: FocusHandle,
+}
+
+impl WelcomePanelWrapper {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+        }
+    }
+}
+
+impl EventEmitter<PanelEvent> for WelcomePanelWrapper {}
+
+impl Render for WelcomePanelWrapper {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .size_full()
+            .items_center()
+            .justify_center()
+            .gap_4()
+            .child(
+                div()
+                    .text_xl()
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .text_color(cx.theme().foreground)
+                    .child("Welcome to Database Editor")
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("Select a table from the sidebar or create a new query")
+            )
+    }
+}
+
+impl Focusable for WelcomePanelWrapper {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for WelcomePanelWrapper {
+    fn panel_name(&self) -> &'static str {
+        "welcome"
+    }
+
+    fn title(&self, _window: &Window, _cx: &App) -> AnyElement {
+        "Welcome".into_any_element()
+    }
+
+    fn dump(&self, _cx: &App) -> ui::dock::PanelState {
+        ui::dock::PanelState {
+            panel_name: self.panel_name().to_string(),
+            ..Default::default()
+        }
+    }
+}